@@ -163,13 +163,22 @@ fn bench_prd_save(c: &mut Criterion) {
                 title: format!("Task number {}", i),
                 description: format!("Description for task {}", i),
                 acceptance_criteria: vec![
-                    format!("Criterion 1 for task {}", i),
-                    format!("Criterion 2 for task {}", i),
+                    format!("Criterion 1 for task {}", i).into(),
+                    format!("Criterion 2 for task {}", i).into(),
                 ],
                 priority: (i % 3) + 1,
                 passes: i < size / 2,
                 source: "benchmark".to_string(),
                 notes: String::new(),
+                effort: None,
+                estimate: None,
+                discussion: Vec::new(),
+                touched_at: None,
+                depends_on: Vec::new(),
+                model: None,
+                cli: None,
+                epic: None,
+                generated: Vec::new(),
             })
             .collect();
 
@@ -3,9 +3,13 @@
 //! This module detects project type, available tools, and generates config.
 //! Also handles the first-run experience for AI CLI selection.
 
+pub mod presets;
+
 use crate::config::{
-    AfkConfig, AiCliConfig, FeedbackLoopsConfig, SourceConfig, AFK_DIR, CONFIG_FILE,
+    find_config_file, AfkConfig, AiBackend, AiCliConfig, FeedbackLoopsConfig, OllamaConfig,
+    SourceConfig, AFK_DIR,
 };
+use crate::runner::program_command;
 use std::collections::HashMap;
 use std::io::{self, Write};
 use std::path::Path;
@@ -26,8 +30,12 @@ pub struct AiCliInfo {
     pub install_url: &'static str,
 }
 
+/// Command name of the Ollama entry in [`AI_CLIS`], which is offered when
+/// the Ollama server is running rather than when a CLI is installed.
+pub const OLLAMA: &str = "ollama";
+
 /// Known AI CLI tools with their configurations.
-/// Priority order for auto-detection: claude > agent > codex > kiro > aider > amp
+/// Priority order for auto-detection: claude > agent > codex > aider > amp > kiro > ollama
 pub const AI_CLIS: &[AiCliInfo] = &[
     AiCliInfo {
         command: "claude",
@@ -71,6 +79,13 @@ pub const AI_CLIS: &[AiCliInfo] = &[
         description: "Amazon's AI-powered development CLI for terminal-based coding",
         install_url: "https://kiro.dev",
     },
+    AiCliInfo {
+        command: OLLAMA,
+        name: "ollama (local)",
+        args: &[],
+        description: "Local models served by Ollama, with nothing leaving the machine",
+        install_url: "https://ollama.com/download",
+    },
 ];
 
 /// Detected project type.
@@ -143,6 +158,8 @@ pub fn analyse_project(root: Option<&Path>) -> ProjectAnalysis {
             test: Some("cargo test".to_string()),
             build: Some("cargo build --release".to_string()),
             custom: HashMap::new(),
+            wasm: HashMap::new(),
+            ..Default::default()
         };
         return analysis;
     }
@@ -203,6 +220,8 @@ pub fn analyse_project(root: Option<&Path>) -> ProjectAnalysis {
             },
             build: None,
             custom: HashMap::new(),
+            wasm: HashMap::new(),
+            ..Default::default()
         };
         return analysis;
     }
@@ -256,6 +275,8 @@ pub fn analyse_project(root: Option<&Path>) -> ProjectAnalysis {
             },
             build: Some(format!("{run_prefix} build")),
             custom: HashMap::new(),
+            wasm: HashMap::new(),
+            ..Default::default()
         };
         return analysis;
     }
@@ -279,6 +300,8 @@ pub fn analyse_project(root: Option<&Path>) -> ProjectAnalysis {
             test: Some("go test ./...".to_string()),
             build: Some("go build ./...".to_string()),
             custom: HashMap::new(),
+            wasm: HashMap::new(),
+            ..Default::default()
         };
         return analysis;
     }
@@ -339,17 +362,16 @@ pub fn infer_sources(root: Option<&Path>) -> Vec<SourceConfig> {
 
 /// Infer config from the current directory.
 ///
-/// If .afk/config.json exists, loads and returns it.
+/// If a config file exists in .afk (JSON, YAML or TOML), loads and returns it.
 /// Otherwise, analyses the project and generates a config.
 pub fn infer_config(root: Option<&Path>) -> AfkConfig {
     // Try to load existing config
-    let config_path = root
-        .map(|p| p.join(".afk/config.json"))
-        .unwrap_or_else(|| Path::new(".afk/config.json").to_path_buf());
+    let afk_dir = root
+        .map(|p| p.join(AFK_DIR))
+        .unwrap_or_else(|| Path::new(AFK_DIR).to_path_buf());
 
-    if let Ok(config) = AfkConfig::load(Some(config_path.as_path())) {
-        // Only return if the config file actually exists
-        if config_path.exists() {
+    if let Some(config_path) = find_config_file(&afk_dir) {
+        if let Ok(config) = AfkConfig::load(Some(config_path.as_path())) {
             return config;
         }
     }
@@ -484,23 +506,11 @@ fn detect_frontend(root: &Path) -> bool {
 }
 
 fn command_exists(cmd: &str) -> bool {
-    #[cfg(windows)]
-    {
-        // On Windows, use cmd.exe /c to properly resolve .cmd/.bat extensions via PATH
-        Command::new("cmd")
-            .args(["/c", cmd, "--version"])
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    }
-    #[cfg(not(windows))]
-    {
-        Command::new(cmd)
-            .arg("--version")
-            .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
-    }
+    program_command(cmd)
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
 // ============================================================================
@@ -509,12 +519,62 @@ fn command_exists(cmd: &str) -> bool {
 
 /// Detect which AI CLI tools are installed on the system.
 ///
-/// Returns a list of `AiCliInfo` for each installed AI CLI tool.
+/// Returns a list of `AiCliInfo` for each installed AI CLI tool, and for
+/// Ollama if its server is running.
 pub fn detect_available_ai_clis() -> Vec<&'static AiCliInfo> {
-    AI_CLIS
-        .iter()
-        .filter(|cli| command_exists(cli.command))
-        .collect()
+    AI_CLIS.iter().filter(|cli| is_available(cli)).collect()
+}
+
+fn is_available(cli: &AiCliInfo) -> bool {
+    if cli.command == OLLAMA {
+        ollama_models(&OllamaConfig::default()).is_some()
+    } else {
+        command_exists(cli.command)
+    }
+}
+
+/// Models installed on the Ollama server, or `None` if it isn't running.
+pub fn ollama_models(ollama: &OllamaConfig) -> Option<Vec<String>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_millis(500))
+        .build()
+        .ok()?;
+    let response = client.get(ollama.tags_url()).send().ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let tags: serde_json::Value = response.json().ok()?;
+    Some(
+        tags["models"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["name"].as_str().map(String::from))
+            .collect(),
+    )
+}
+
+/// The config for running `cli`. For Ollama, that is the Ollama backend
+/// with the server's first installed model.
+fn ai_cli_config(cli: &AiCliInfo) -> AiCliConfig {
+    if cli.command == OLLAMA {
+        return AiCliConfig {
+            command: OLLAMA.to_string(),
+            args: Vec::new(),
+            backend: AiBackend::Ollama,
+            models: ollama_models(&OllamaConfig::default())
+                .unwrap_or_default()
+                .into_iter()
+                .take(1)
+                .collect(),
+            ..Default::default()
+        };
+    }
+    AiCliConfig {
+        command: cli.command.to_string(),
+        args: cli.args.iter().map(|s| s.to_string()).collect(),
+        ..Default::default()
+    }
 }
 
 /// Result of the AI CLI selection prompt.
@@ -591,14 +651,7 @@ pub fn prompt_ai_cli_selection(available: &[&AiCliInfo]) -> AiCliSelectionResult
         }
     };
 
-    let selected = &available[choice - 1];
-    let config = AiCliConfig {
-        command: selected.command.to_string(),
-        args: selected.args.iter().map(|s| s.to_string()).collect(),
-        ..Default::default()
-    };
-
-    AiCliSelectionResult::Selected(config)
+    AiCliSelectionResult::Selected(ai_cli_config(available[choice - 1]))
 }
 
 /// Ensure an AI CLI is configured, prompting the user if needed.
@@ -609,7 +662,10 @@ pub fn prompt_ai_cli_selection(available: &[&AiCliInfo]) -> AiCliSelectionResult
 ///
 /// # Arguments
 ///
-/// * `config` - Optional existing config (loads from file if None)
+/// * `config` - Optional existing config (loads from file if None). It is
+///   saved with the chosen CLI, so pass the config as written (from
+///   [`AfkConfig::load_base`]), not one with a profile or `${VAR}` values
+///   applied
 /// * `force_prompt` - If true, always prompt even if config exists
 ///
 /// # Returns
@@ -619,14 +675,14 @@ pub fn ensure_ai_cli_configured(
     config: Option<&mut AfkConfig>,
     force_prompt: bool,
 ) -> Option<AiCliConfig> {
-    let config_path = std::path::Path::new(CONFIG_FILE);
+    let config_path = AfkConfig::config_file();
 
     // Check if config file exists - if so, use what's there (unless forcing)
     if config_path.exists() && !force_prompt {
         if let Some(cfg) = config {
             return Some(cfg.ai_cli.clone());
         }
-        if let Ok(cfg) = AfkConfig::load(Some(config_path)) {
+        if let Ok(cfg) = AfkConfig::load(Some(&config_path)) {
             return Some(cfg.ai_cli);
         }
     }
@@ -640,7 +696,7 @@ pub fn ensure_ai_cli_configured(
             // Save the selection to config
             let mut new_config = config
                 .cloned()
-                .or_else(|| AfkConfig::load(None).ok())
+                .or_else(|| AfkConfig::load_base(None).ok())
                 .unwrap_or_default();
 
             new_config.ai_cli = ai_cli.clone();
@@ -652,7 +708,7 @@ pub fn ensure_ai_cli_configured(
             }
 
             // Save config
-            if let Err(e) = new_config.save(Some(config_path)) {
+            if let Err(e) = new_config.save(Some(&config_path)) {
                 eprintln!("\x1b[33mWarning:\x1b[0m Could not save config: {e}");
             } else {
                 println!();
@@ -660,7 +716,7 @@ pub fn ensure_ai_cli_configured(
                     "\x1b[32m✓\x1b[0m Saved AI CLI choice: \x1b[36m{}\x1b[0m",
                     ai_cli.command
                 );
-                println!("  Config: \x1b[2m{}\x1b[0m", CONFIG_FILE);
+                println!("  Config: \x1b[2m{}\x1b[0m", config_path.display());
                 println!();
             }
 
@@ -679,7 +735,7 @@ pub fn ensure_ai_cli_configured(
 ///
 /// The configured `AiCliConfig`, or None if not found/cancelled.
 pub fn switch_ai_cli(cli_name: Option<&str>) -> Option<AiCliConfig> {
-    let config_path = std::path::Path::new(CONFIG_FILE);
+    let config_path = AfkConfig::config_file();
 
     let ai_cli = match cli_name {
         Some(name) => {
@@ -692,17 +748,13 @@ pub fn switch_ai_cli(cli_name: Option<&str>) -> Option<AiCliConfig> {
             match cli_info {
                 Some(info) => {
                     // Check if it's installed
-                    if !command_exists(info.command) {
+                    if !is_available(info) {
                         eprintln!("\x1b[31mError:\x1b[0m {} is not installed.", info.name);
                         eprintln!("  Install: {}", info.install_url);
                         return None;
                     }
 
-                    AiCliConfig {
-                        command: info.command.to_string(),
-                        args: info.args.iter().map(|s| s.to_string()).collect(),
-                        ..Default::default()
-                    }
+                    ai_cli_config(info)
                 }
                 None => {
                     eprintln!("\x1b[31mError:\x1b[0m Unknown AI CLI: {name}");
@@ -726,7 +778,7 @@ pub fn switch_ai_cli(cli_name: Option<&str>) -> Option<AiCliConfig> {
     };
 
     // Load existing config and update
-    let mut config = AfkConfig::load(None).unwrap_or_default();
+    let mut config = AfkConfig::load_base(None).unwrap_or_default();
     config.ai_cli = ai_cli.clone();
 
     // Ensure .afk directory exists
@@ -736,7 +788,7 @@ pub fn switch_ai_cli(cli_name: Option<&str>) -> Option<AiCliConfig> {
     }
 
     // Save config
-    if let Err(e) = config.save(Some(config_path)) {
+    if let Err(e) = config.save(Some(&config_path)) {
         eprintln!("\x1b[31mError:\x1b[0m Could not save config: {e}");
         return None;
     }
@@ -763,7 +815,7 @@ pub fn list_ai_clis() {
     println!();
 
     for cli in AI_CLIS {
-        let installed = command_exists(cli.command);
+        let installed = is_available(cli);
         let status = if installed {
             "\x1b[32m✓\x1b[0m"
         } else {
@@ -781,14 +833,10 @@ pub fn list_ai_clis() {
 /// Detect the best available AI CLI tool without prompting.
 ///
 /// This is used for auto-detection when prompting is not desired.
-/// Priority order: claude > agent > codex > kiro > aider > amp
+/// Priority order: claude > agent > codex > aider > amp > kiro > ollama
 pub fn detect_ai_cli() -> Option<AiCliConfig> {
     let available = detect_available_ai_clis();
-    available.first().map(|cli| AiCliConfig {
-        command: cli.command.to_string(),
-        args: cli.args.iter().map(|s| s.to_string()).collect(),
-        ..Default::default()
-    })
+    available.first().map(|cli| ai_cli_config(cli))
 }
 
 #[cfg(test)]
@@ -971,6 +1019,8 @@ line-length = 100
                 test: Some("cargo test".to_string()),
                 build: None,
                 custom: HashMap::new(),
+                wasm: HashMap::new(),
+                ..Default::default()
             },
         };
 
@@ -1025,6 +1075,7 @@ line-length = 100
         assert!(commands.contains(&"aider"));
         assert!(commands.contains(&"amp"));
         assert!(commands.contains(&"kiro"));
+        assert!(commands.contains(&"ollama"));
     }
 
     #[test]
@@ -1087,6 +1138,62 @@ line-length = 100
         // If None, that's fine too - means no AI CLIs installed
     }
 
+    #[test]
+    fn test_ollama_models_lists_tags() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            let body = r#"{"models":[{"name":"qwen2.5-coder:14b"},{"name":"llama3.1:8b"}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        let ollama = OllamaConfig {
+            host,
+            ..Default::default()
+        };
+        assert_eq!(
+            ollama_models(&ollama),
+            Some(vec![
+                "qwen2.5-coder:14b".to_string(),
+                "llama3.1:8b".to_string()
+            ])
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_ollama_models_without_server() {
+        // Bind then drop a listener to find a port nothing is serving
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let ollama = OllamaConfig {
+            host: format!("http://127.0.0.1:{port}"),
+            ..Default::default()
+        };
+        assert_eq!(ollama_models(&ollama), None);
+    }
+
+    #[test]
+    fn test_ollama_offered_last_with_ollama_backend() {
+        let ollama = AI_CLIS.last().unwrap();
+        assert_eq!(ollama.command, OLLAMA);
+        assert_eq!(ollama.name, "ollama (local)");
+        let config = ai_cli_config(ollama);
+        assert_eq!(config.backend, AiBackend::Ollama);
+        assert!(config.args.is_empty());
+    }
+
     #[test]
     fn test_ai_cli_info_all_have_install_urls() {
         for cli in AI_CLIS {
@@ -0,0 +1,227 @@
+//! Curated init presets.
+//!
+//! A preset is a golden-path config for a common kind of project: quality
+//! gates, context files, prompt instructions, and limits. `afk init --preset`
+//! layers one on top of auto-detection so teams can standardise on it.
+
+use crate::config::AfkConfig;
+
+/// A named set of curated config defaults.
+#[derive(Debug, Clone)]
+pub struct Preset {
+    /// Preset name used on the command line.
+    pub name: &'static str,
+    /// One-line description.
+    pub description: &'static str,
+    /// Type checking gate.
+    pub types: Option<&'static str>,
+    /// Lint gate.
+    pub lint: Option<&'static str>,
+    /// Test gate.
+    pub test: Option<&'static str>,
+    /// Build gate.
+    pub build: Option<&'static str>,
+    /// Extra named gates.
+    pub custom: &'static [(&'static str, &'static str)],
+    /// Files included as prompt context.
+    pub context_files: &'static [&'static str],
+    /// Extra prompt instructions.
+    pub instructions: &'static [&'static str],
+    /// Whether stories need browser verification.
+    pub has_frontend: bool,
+    /// Iteration limit.
+    pub max_iterations: u32,
+    /// Failures allowed per task before skipping it.
+    pub max_task_failures: u32,
+    /// Session timeout in minutes.
+    pub timeout_minutes: u32,
+}
+
+/// All built-in presets.
+pub static PRESETS: &[Preset] = &[
+    Preset {
+        name: "rust-cli",
+        description: "Rust command-line application built with cargo",
+        types: Some("cargo check --all-targets"),
+        lint: Some("cargo clippy --all-targets -- -D warnings"),
+        test: Some("cargo test"),
+        build: Some("cargo build"),
+        custom: &[("fmt", "cargo fmt --check")],
+        context_files: &["README.md", "Cargo.toml"],
+        instructions: &[
+            "Keep `cargo fmt` and `cargo clippy` clean; do not add `#[allow]` to silence warnings",
+            "Avoid `unwrap()` outside tests; return errors with context instead",
+            "Update `--help` text and the README when CLI flags change",
+        ],
+        has_frontend: false,
+        max_iterations: 100,
+        max_task_failures: 5,
+        timeout_minutes: 120,
+    },
+    Preset {
+        name: "python-lib",
+        description: "Python library with ruff, mypy, and pytest",
+        types: Some("mypy ."),
+        lint: Some("ruff check ."),
+        test: Some("pytest"),
+        build: Some("python -m build"),
+        custom: &[("format", "ruff format --check .")],
+        context_files: &["README.md", "pyproject.toml"],
+        instructions: &[
+            "Add type hints to every public function and class",
+            "Document public APIs with docstrings and keep the changelog up to date",
+        ],
+        has_frontend: false,
+        max_iterations: 100,
+        max_task_failures: 5,
+        timeout_minutes: 120,
+    },
+    Preset {
+        name: "node-webapp",
+        description: "Node web application with a browser frontend",
+        types: Some("npm run typecheck"),
+        lint: Some("npm run lint"),
+        test: Some("npm test"),
+        build: Some("npm run build"),
+        custom: &[],
+        context_files: &["README.md", "package.json"],
+        instructions: &[
+            "Verify UI changes in the browser before marking a story complete",
+            "Keep components small and co-locate their tests",
+        ],
+        has_frontend: true,
+        max_iterations: 100,
+        max_task_failures: 5,
+        timeout_minutes: 180,
+    },
+    Preset {
+        name: "docs-site",
+        description: "Documentation site written in Markdown",
+        types: None,
+        lint: Some("npx markdownlint-cli2 \"**/*.md\""),
+        test: None,
+        build: Some("npm run build"),
+        custom: &[],
+        context_files: &["README.md"],
+        instructions: &[
+            "Keep headings, terminology, and tone consistent across pages",
+            "Check that internal links still resolve after moving or renaming pages",
+        ],
+        has_frontend: false,
+        max_iterations: 50,
+        max_task_failures: 3,
+        timeout_minutes: 60,
+    },
+];
+
+/// Look up a preset by name.
+pub fn get_preset(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|p| p.name == name)
+}
+
+/// Names of all built-in presets.
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|p| p.name).collect()
+}
+
+impl Preset {
+    /// Apply the preset on top of a config.
+    ///
+    /// Gates and limits from the preset replace the config's; context files
+    /// and instructions are added if not already present.
+    pub fn apply(&self, config: &mut AfkConfig) {
+        let gates = &mut config.feedback_loops;
+        gates.types = self.types.map(ToOwned::to_owned);
+        gates.lint = self.lint.map(ToOwned::to_owned);
+        gates.test = self.test.map(ToOwned::to_owned);
+        gates.build = self.build.map(ToOwned::to_owned);
+        for (name, cmd) in self.custom {
+            gates.custom.insert(name.to_string(), cmd.to_string());
+        }
+
+        let prompt = &mut config.prompt;
+        for file in self.context_files {
+            if !prompt.context_files.iter().any(|f| f == file) {
+                prompt.context_files.push(file.to_string());
+            }
+        }
+        for instruction in self.instructions {
+            if !prompt.instructions.iter().any(|i| i == instruction) {
+                prompt.instructions.push(instruction.to_string());
+            }
+        }
+        prompt.has_frontend |= self.has_frontend;
+
+        config.limits.max_iterations = self.max_iterations;
+        config.limits.max_task_failures = self.max_task_failures;
+        config.limits.timeout_minutes = self.timeout_minutes;
+    }
+
+    /// Config produced by applying the preset to the defaults.
+    pub fn to_config(&self) -> AfkConfig {
+        let mut config = AfkConfig::default();
+        self.apply(&mut config);
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_preset() {
+        assert_eq!(get_preset("rust-cli").unwrap().name, "rust-cli");
+        assert!(get_preset("cobol-mainframe").is_none());
+        assert_eq!(
+            preset_names(),
+            vec!["rust-cli", "python-lib", "node-webapp", "docs-site"]
+        );
+    }
+
+    #[test]
+    fn test_apply_overrides_gates_and_limits() {
+        let mut config = AfkConfig::default();
+        config.feedback_loops.test = Some("make test".to_string());
+        config.feedback_loops.types = Some("tsc".to_string());
+
+        get_preset("docs-site").unwrap().apply(&mut config);
+
+        assert_eq!(config.feedback_loops.test, None);
+        assert_eq!(config.feedback_loops.types, None);
+        assert_eq!(
+            config.feedback_loops.build.as_deref(),
+            Some("npm run build")
+        );
+        assert_eq!(config.limits.max_iterations, 50);
+        assert_eq!(config.limits.timeout_minutes, 60);
+    }
+
+    #[test]
+    fn test_apply_merges_prompt_settings() {
+        let mut config = AfkConfig::default();
+        config.prompt.context_files = vec!["README.md".to_string(), "ARCH.md".to_string()];
+
+        let preset = get_preset("node-webapp").unwrap();
+        preset.apply(&mut config);
+        preset.apply(&mut config);
+
+        assert_eq!(
+            config.prompt.context_files,
+            vec!["README.md", "ARCH.md", "package.json"]
+        );
+        assert_eq!(config.prompt.instructions.len(), preset.instructions.len());
+        assert!(config.prompt.has_frontend);
+    }
+
+    #[test]
+    fn test_presets_are_valid_configs() {
+        for preset in PRESETS {
+            let config = preset.to_config();
+            let json = serde_json::to_string(&config).unwrap();
+            let parsed: AfkConfig = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.limits.max_iterations, preset.max_iterations);
+            assert!(!preset.description.is_empty());
+        }
+    }
+}
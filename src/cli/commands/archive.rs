@@ -1,11 +1,15 @@
 //! Archive command implementations.
 //!
-//! This module implements the `afk archive` and `afk archive list` commands.
+//! This module implements the `afk archive`, `afk archive list` and
+//! `afk archive diff` commands.
 
 use std::io::{self, Write};
 use std::path::Path;
 
-use crate::progress::{archive_session, list_archives};
+use crate::cli::output::json::{self, ArchiveListJson};
+use crate::progress::{
+    archive_session, diff_sessions, list_archives, SessionDiff, SessionSnapshot,
+};
 
 /// Result type for archive command operations.
 pub type ArchiveCommandResult = Result<(), ArchiveCommandError>;
@@ -19,6 +23,14 @@ pub enum ArchiveCommandError {
     /// Failed to list archived sessions.
     #[error("Failed to list archives: {0}")]
     ListError(String),
+    /// Failed to load a session for diffing.
+    #[error("Failed to load session '{name}': {message}")]
+    DiffError {
+        /// The archive name that failed to load.
+        name: String,
+        /// The underlying error message.
+        message: String,
+    },
 }
 
 /// Execute the archive command (archive and clear session).
@@ -64,11 +76,26 @@ pub fn archive_now(reason: &str, yes: bool) -> ArchiveCommandResult {
 }
 
 /// Execute the archive list command.
-pub fn archive_list() -> ArchiveCommandResult {
-    let archives = list_archives().map_err(|e| ArchiveCommandError::ListError(e.to_string()))?;
+///
+/// With a tag, only sessions carrying that tag are listed. With `as_json`,
+/// every matching session is printed as [`ArchiveListJson`].
+pub fn archive_list(tag: Option<&str>, as_json: bool) -> ArchiveCommandResult {
+    let mut archives =
+        list_archives().map_err(|e| ArchiveCommandError::ListError(e.to_string()))?;
+    if let Some(tag) = tag {
+        archives.retain(|(_, metadata)| metadata.has_tag(tag));
+    }
+
+    if as_json {
+        json::print(&ArchiveListJson::new(&archives));
+        return Ok(());
+    }
 
     if archives.is_empty() {
-        println!("No archived sessions found.");
+        match tag {
+            Some(tag) => println!("No archived sessions tagged '{tag}'."),
+            None => println!("No archived sessions found."),
+        }
         return Ok(());
     }
 
@@ -83,8 +110,13 @@ pub fn archive_list() -> ArchiveCommandResult {
     for (_name, metadata) in archives.iter().take(20) {
         let branch = metadata.branch.as_deref().unwrap_or("-");
         let date = &metadata.archived_at[..19]; // Trim microseconds
+        let tags = if metadata.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" \x1b[2m[{}]\x1b[0m", metadata.tags.join(", "))
+        };
         println!(
-            "{:<24} {:<20} {:<8} {:<10} {}",
+            "{:<24} {:<20} {:<8} {:<10} {}{}",
             date.replace('T', " "),
             if branch.len() > 18 {
                 &branch[..18]
@@ -97,7 +129,8 @@ pub fn archive_list() -> ArchiveCommandResult {
                 metadata.tasks_completed,
                 metadata.tasks_completed + metadata.tasks_pending
             ),
-            metadata.reason
+            metadata.reason,
+            tags
         );
     }
 
@@ -109,6 +142,116 @@ pub fn archive_list() -> ArchiveCommandResult {
     Ok(())
 }
 
+/// Execute the archive diff command.
+pub fn archive_diff(a: &str, b: &str) -> ArchiveCommandResult {
+    let load = |name: &str| {
+        SessionSnapshot::load(name).map_err(|e| ArchiveCommandError::DiffError {
+            name: name.to_string(),
+            message: e.to_string(),
+        })
+    };
+    let snapshot_a = load(a)?;
+    let snapshot_b = load(b)?;
+
+    let diff = diff_sessions(&snapshot_a, &snapshot_b);
+    print!("{}", format_diff_report(a, b, &diff));
+
+    Ok(())
+}
+
+/// Render a session diff as a readable report.
+fn format_diff_report(a: &str, b: &str, diff: &SessionDiff) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "\x1b[1mSession diff\x1b[0m {a} \x1b[2m→\x1b[0m {b}\n\n"
+    ));
+
+    out.push_str(&format!(
+        "  Iterations: {} → {} ({})\n",
+        diff.iterations.0,
+        diff.iterations.1,
+        format_delta(i64::from(diff.iterations.1) - i64::from(diff.iterations.0))
+    ));
+    out.push_str(&format!(
+        "  Completed:  {} → {} ({})\n",
+        diff.completed.0,
+        diff.completed.1,
+        format_delta(diff.completed.1 as i64 - diff.completed.0 as i64)
+    ));
+    out.push_str(&format!(
+        "  Failures:   {} → {} ({})\n",
+        diff.failures.0,
+        diff.failures.1,
+        format_delta(i64::from(diff.failures.1) - i64::from(diff.failures.0))
+    ));
+    if diff.cost != (None, None) {
+        let delta = match diff.cost {
+            (Some(a), Some(b)) => format!(" ({})", format_cost_delta(b - a)),
+            _ => String::new(),
+        };
+        out.push_str(&format!(
+            "  Cost:       {} → {}{delta}\n",
+            format_cost(diff.cost.0),
+            format_cost(diff.cost.1)
+        ));
+    }
+
+    if diff.is_empty() {
+        out.push_str("\n\x1b[2mNo differences.\x1b[0m\n");
+        return out;
+    }
+
+    let sections: [(&str, &str, &Vec<String>); 4] = [
+        ("Newly completed", "\x1b[32m✓\x1b[0m", &diff.newly_completed),
+        ("Regressions", "\x1b[31m✗\x1b[0m", &diff.regressions),
+        ("Added tasks", "\x1b[36m+\x1b[0m", &diff.added_tasks),
+        ("Removed tasks", "\x1b[2m-\x1b[0m", &diff.removed_tasks),
+    ];
+    for (heading, marker, ids) in sections {
+        if !ids.is_empty() {
+            out.push_str(&format!("\n\x1b[1m{heading}\x1b[0m\n"));
+            for id in ids {
+                out.push_str(&format!("  {marker} {id}\n"));
+            }
+        }
+    }
+
+    if !diff.learnings_added.is_empty() || !diff.learnings_removed.is_empty() {
+        out.push_str("\n\x1b[1mLearnings\x1b[0m\n");
+        for (task_id, learning) in &diff.learnings_added {
+            out.push_str(&format!("  \x1b[32m+\x1b[0m [{task_id}] {learning}\n"));
+        }
+        for (task_id, learning) in &diff.learnings_removed {
+            out.push_str(&format!("  \x1b[31m-\x1b[0m [{task_id}] {learning}\n"));
+        }
+    }
+
+    out
+}
+
+/// Format a signed delta with an explicit sign.
+fn format_delta(delta: i64) -> String {
+    if delta > 0 {
+        format!("+{delta}")
+    } else {
+        delta.to_string()
+    }
+}
+
+/// Format a session cost, or "unknown" if none was reported.
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map_or_else(|| "unknown".to_string(), |c| format!("${c:.2}"))
+}
+
+/// Format a cost delta with an explicit sign.
+fn format_cost_delta(delta: f64) -> String {
+    if delta < 0.0 {
+        format!("-${:.2}", -delta)
+    } else {
+        format!("+${delta:.2}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -120,5 +263,52 @@ mod tests {
 
         let err = ArchiveCommandError::ListError("test error".to_string());
         assert!(err.to_string().contains("Failed to list archives"));
+
+        let err = ArchiveCommandError::DiffError {
+            name: "20240101_000000".to_string(),
+            message: "archive not found".to_string(),
+        };
+        assert!(err.to_string().contains("20240101_000000"));
+    }
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta(3), "+3");
+        assert_eq!(format_delta(0), "0");
+        assert_eq!(format_delta(-2), "-2");
+    }
+
+    #[test]
+    fn test_format_diff_report() {
+        let diff = SessionDiff {
+            newly_completed: vec!["task-2".to_string()],
+            regressions: vec!["task-1".to_string()],
+            iterations: (4, 7),
+            completed: (1, 1),
+            cost: (Some(1.5), Some(0.9)),
+            learnings_added: vec![("task-2".to_string(), "Cache the client".to_string())],
+            ..Default::default()
+        };
+
+        let report = format_diff_report("a", "current", &diff);
+        assert!(report.contains("Iterations: 4 → 7 (+3)"));
+        assert!(report.contains("Cost:       $1.50 → $0.90 (-$0.60)"));
+        assert!(report.contains("Newly completed"));
+        assert!(report.contains("task-2"));
+        assert!(report.contains("Regressions"));
+        assert!(report.contains("[task-2] Cache the client"));
+    }
+
+    #[test]
+    fn test_format_diff_report_no_differences() {
+        let report = format_diff_report("a", "b", &SessionDiff::default());
+        assert!(report.contains("No differences"));
+        assert!(!report.contains("Cost:"));
+
+        let diff = SessionDiff {
+            cost: (None, Some(0.25)),
+            ..Default::default()
+        };
+        assert!(format_diff_report("a", "b", &diff).contains("Cost:       unknown → $0.25\n"));
     }
 }
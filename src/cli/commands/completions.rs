@@ -1,12 +1,20 @@
 //! Completions command implementation.
 //!
 //! This module implements the `afk completions` command for generating shell completions.
+//! Static scripts complete commands and flags. Dynamic scripts call back into
+//! afk as you type, which lets them complete task IDs from the tasks file.
 
 use clap::CommandFactory;
-use clap_complete::{generate, Shell};
+use clap_complete::env::Shells;
+use clap_complete::{generate, CompletionCandidate, Shell};
 use std::io;
+use std::path::Path;
 
 use crate::cli::Cli;
+use crate::prd::PrdDocument;
+
+/// Environment variable dynamic completion scripts set when calling afk.
+pub const COMPLETE_ENV: &str = "COMPLETE";
 
 /// Result type for completions command operations.
 pub type CompletionsCommandResult = Result<(), CompletionsCommandError>;
@@ -17,14 +25,29 @@ pub enum CompletionsCommandError {
     /// The specified shell is not supported for completions.
     #[error("Unsupported shell: {0}")]
     UnsupportedShell(String),
+
+    /// Writing the completion script failed.
+    #[error("Failed to write completions: {0}")]
+    Io(#[from] io::Error),
 }
 
 /// Execute the completions command.
-pub fn completions(shell: &str) -> CompletionsCommandResult {
+pub fn completions(shell: &str, dynamic: bool) -> CompletionsCommandResult {
+    if dynamic {
+        let shells = Shells::builtins();
+        let completer = shells
+            .completer(shell)
+            .ok_or_else(|| CompletionsCommandError::UnsupportedShell(shell.to_string()))?;
+        completer.write_registration(COMPLETE_ENV, "afk", "afk", "afk", &mut io::stdout())?;
+        return Ok(());
+    }
+
     let shell_enum = match shell {
         "bash" => Shell::Bash,
         "zsh" => Shell::Zsh,
         "fish" => Shell::Fish,
+        "powershell" => Shell::PowerShell,
+        "elvish" => Shell::Elvish,
         _ => return Err(CompletionsCommandError::UnsupportedShell(shell.to_string())),
     };
 
@@ -34,14 +57,67 @@ pub fn completions(shell: &str) -> CompletionsCommandResult {
     Ok(())
 }
 
+/// Task IDs from the tasks file, for dynamic completion.
+pub fn task_ids() -> Vec<CompletionCandidate> {
+    task_ids_from(None)
+}
+
+/// Task IDs from a tasks file, each with its title as help text.
+///
+/// A missing or unreadable file gives no candidates.
+pub fn task_ids_from(path: Option<&Path>) -> Vec<CompletionCandidate> {
+    PrdDocument::load(path)
+        .map(|prd| {
+            prd.user_stories
+                .into_iter()
+                .map(|story| CompletionCandidate::new(story.id).help(Some(story.title.into())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::prd::UserStory;
+    use tempfile::TempDir;
 
     #[test]
     fn test_completions_command_error_display() {
-        let err = CompletionsCommandError::UnsupportedShell("powershell".to_string());
+        let err = CompletionsCommandError::UnsupportedShell("tcsh".to_string());
         assert!(err.to_string().contains("Unsupported shell"));
-        assert!(err.to_string().contains("powershell"));
+        assert!(err.to_string().contains("tcsh"));
+    }
+
+    #[test]
+    fn test_dynamic_unsupported_shell() {
+        assert!(matches!(
+            completions("tcsh", true),
+            Err(CompletionsCommandError::UnsupportedShell(_))
+        ));
+    }
+
+    #[test]
+    fn test_task_ids_from() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("tasks.json");
+        assert!(task_ids_from(Some(&path)).is_empty());
+
+        let mut prd = PrdDocument::default();
+        prd.user_stories.push(UserStory::new("auth-1", "Add login"));
+        prd.user_stories
+            .push(UserStory::new("auth-2", "Add logout"));
+        prd.save(Some(&path)).unwrap();
+
+        let candidates = task_ids_from(Some(&path));
+        let ids: Vec<String> = candidates
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(ids, ["auth-1", "auth-2"]);
+        assert_eq!(
+            candidates[0].get_help().map(|h| h.to_string()).as_deref(),
+            Some("Add login")
+        );
     }
 }
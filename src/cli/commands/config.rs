@@ -3,9 +3,13 @@
 //! This module implements the `afk config` subcommands for managing
 //! configuration without editing JSON directly.
 
+use std::fs;
+use std::path::{Path, PathBuf};
+
 use crate::config::{
+    find_config_file,
     metadata::{self, KeyMetadata},
-    AfkConfig, FieldError,
+    profile, AfkConfig, ConfigError, ConfigFormat, FieldError, AFK_DIR, PROFILES_DIR,
 };
 
 /// Result type for config command operations.
@@ -33,6 +37,34 @@ pub enum ConfigCommandError {
     /// Unknown configuration section name.
     #[error("Unknown section: {0}")]
     UnknownSection(String),
+
+    /// The interactive editor needs a terminal.
+    #[error("config tui requires an interactive terminal")]
+    NotATerminal,
+
+    /// The interactive editor failed.
+    #[error("Config editor failed: {0}")]
+    TuiError(#[from] std::io::Error),
+
+    /// Unknown config file format name.
+    #[error("Unknown config format: {0} (expected json, yaml or toml)")]
+    UnknownFormat(String),
+
+    /// There is no config file to convert.
+    #[error("No config file in .afk to convert; run afk init first")]
+    NoConfigFile,
+
+    /// Profile names become file names, so are kept simple.
+    #[error("Invalid profile name '{0}' (use letters, digits, '-' and '_')")]
+    InvalidProfileName(String),
+
+    /// A profile with that name already exists.
+    #[error("Profile '{0}' already exists in {PROFILES_DIR}")]
+    ProfileExists(String),
+
+    /// A `--set` override was not `KEY=VALUE`.
+    #[error("Expected KEY=VALUE, got '{0}'")]
+    InvalidOverride(String),
 }
 
 /// Show all config values in a human-readable format.
@@ -40,6 +72,9 @@ pub fn config_show(section_filter: Option<&str>) -> ConfigCommandResult {
     let config = AfkConfig::load(None)?;
 
     println!("\x1b[1m=== afk config ===\x1b[0m");
+    if let Some(name) = profile::active_profile() {
+        println!("\x1b[2mWith profile\x1b[0m \x1b[36m{name}\x1b[0m");
+    }
     println!();
 
     // Determine which sections to show
@@ -95,7 +130,7 @@ pub fn config_get(key: &str) -> ConfigCommandResult {
 
 /// Set a config value.
 pub fn config_set(key: &str, value: &str) -> ConfigCommandResult {
-    let mut config = AfkConfig::load(None)?;
+    let mut config = AfkConfig::load_base(None)?;
     let old_value = config.get_by_path(key).ok();
 
     config.set_by_path(key, value)?;
@@ -118,7 +153,7 @@ pub fn config_set(key: &str, value: &str) -> ConfigCommandResult {
 
 /// Reset config to defaults.
 pub fn config_reset(key: Option<&str>) -> ConfigCommandResult {
-    let mut config = AfkConfig::load(None)?;
+    let mut config = AfkConfig::load_base(None)?;
 
     match key {
         Some(k) => {
@@ -181,6 +216,29 @@ pub fn config_edit() -> ConfigCommandResult {
     Ok(())
 }
 
+/// Browse and edit config in an interactive terminal UI.
+pub fn config_tui() -> ConfigCommandResult {
+    use std::io::IsTerminal;
+
+    if !std::io::stdout().is_terminal() {
+        return Err(ConfigCommandError::NotATerminal);
+    }
+
+    let config = AfkConfig::load_base(None)?;
+    match crate::tui::run_config_editor(config)? {
+        Some(edited) => {
+            edited.save(None)?;
+            println!(
+                "\x1b[32m✓\x1b[0m Config saved to {}",
+                AfkConfig::config_file().display()
+            );
+        }
+        None => println!("\x1b[2mNo changes saved\x1b[0m"),
+    }
+
+    Ok(())
+}
+
 /// Show documentation for config keys.
 pub fn config_explain(key: Option<&str>) -> ConfigCommandResult {
     match key {
@@ -330,9 +388,220 @@ pub fn config_keys() -> ConfigCommandResult {
     Ok(())
 }
 
+/// Convert the config file to another format (json, yaml or toml).
+pub fn config_convert(to: &str) -> ConfigCommandResult {
+    let format =
+        ConfigFormat::parse(to).ok_or_else(|| ConfigCommandError::UnknownFormat(to.into()))?;
+    match convert_config(Path::new(AFK_DIR), format)? {
+        Some((from, to)) => println!(
+            "\x1b[32m✓\x1b[0m Converted {} to {}",
+            from.display(),
+            to.display()
+        ),
+        None => println!("\x1b[2mConfig is already {format}\x1b[0m"),
+    }
+    Ok(())
+}
+
+/// Create profile `name` overriding each `KEY=VALUE` in `settings`.
+pub fn config_profile_create(name: &str, settings: &[String]) -> ConfigCommandResult {
+    let base = AfkConfig::load_base(None)?;
+    let path = create_profile(Path::new(PROFILES_DIR), &base, name, settings)?;
+    println!(
+        "\x1b[32m✓\x1b[0m Created profile \x1b[36m{name}\x1b[0m at {}",
+        path.display()
+    );
+    if settings.is_empty() {
+        println!("\x1b[2m  Add overrides by editing it, e.g. {{\"limits\": {{\"max_iterations\": 50}}}}\x1b[0m");
+    }
+    println!("\x1b[2m  Run with it: afk go --profile {name}, or make it the default: afk config profile use {name}\x1b[0m");
+    Ok(())
+}
+
+/// List profiles with the keys each overrides, marking the active one.
+pub fn config_profile_list() -> ConfigCommandResult {
+    let dir = Path::new(PROFILES_DIR);
+    let names = profile::list_profiles(dir);
+    if names.is_empty() {
+        println!("\x1b[2mNo profiles. Create one with: afk config profile create <name>\x1b[0m");
+        return Ok(());
+    }
+    let active = profile::active_profile();
+    for name in names {
+        let marker = if active.as_deref() == Some(name.as_str()) {
+            "\x1b[32m*\x1b[0m"
+        } else {
+            " "
+        };
+        println!("{marker} \x1b[1m{name}\x1b[0m");
+        match profile::load_profile(dir, &name) {
+            Ok(overrides) => {
+                for (key, value) in profile::flatten(&overrides) {
+                    println!("    {key} = {value}");
+                }
+            }
+            Err(e) => println!("    \x1b[31m{e}\x1b[0m"),
+        }
+    }
+    Ok(())
+}
+
+/// Make profile `name` the default for this project, or go back to the
+/// base config with None.
+pub fn config_profile_use(name: Option<&str>) -> ConfigCommandResult {
+    let afk_dir = Path::new(AFK_DIR);
+    let Some(name) = name else {
+        profile::save_active_profile(afk_dir, None)?;
+        println!("\x1b[32m✓\x1b[0m Using the base config");
+        return Ok(());
+    };
+    // Check the profile applies cleanly before making it the default
+    let overrides = profile::load_profile(Path::new(PROFILES_DIR), name)?;
+    profile::apply(&AfkConfig::load_base(None)?, overrides)?;
+    profile::save_active_profile(afk_dir, Some(name))?;
+    println!("\x1b[32m✓\x1b[0m Using profile \x1b[36m{name}\x1b[0m");
+    if std::env::var(profile::PROFILE_ENV).is_ok_and(|env| env.trim() != name) {
+        println!(
+            "\x1b[33m⚠\x1b[0m {} is set and takes precedence",
+            profile::PROFILE_ENV
+        );
+    }
+    Ok(())
+}
+
+/// Write profile `name` to `profiles_dir`, holding each `KEY=VALUE` in
+/// `settings` as validated against `base`.
+pub fn create_profile(
+    profiles_dir: &Path,
+    base: &AfkConfig,
+    name: &str,
+    settings: &[String],
+) -> Result<PathBuf, ConfigCommandError> {
+    if !profile::is_valid_name(name) {
+        return Err(ConfigCommandError::InvalidProfileName(name.into()));
+    }
+    if profile::find_profile(profiles_dir, name).is_some() {
+        return Err(ConfigCommandError::ProfileExists(name.into()));
+    }
+    if crate::config::is_read_only() {
+        return Err(ConfigError::ReadOnly.into());
+    }
+
+    let mut config = base.clone();
+    let mut overrides = serde_json::json!({});
+    for setting in settings {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| ConfigCommandError::InvalidOverride(setting.clone()))?;
+        config.set_by_path(key.trim(), value.trim())?;
+        profile::set_override(&mut overrides, &config, key.trim())?;
+    }
+
+    let path = profiles_dir.join(format!("{name}.json"));
+    let contents = serde_json::to_string_pretty(&overrides).map_err(ConfigError::from)?;
+    fs::create_dir_all(profiles_dir)
+        .and_then(|()| fs::write(&path, contents + "\n"))
+        .map_err(ConfigError::from)?;
+    Ok(path)
+}
+
+/// Rewrite the config file in `afk_dir` in `format` and remove the old one.
+///
+/// Returns the old and new paths, or None if the config is already in that
+/// format.
+pub fn convert_config(
+    afk_dir: &Path,
+    format: ConfigFormat,
+) -> Result<Option<(PathBuf, PathBuf)>, ConfigCommandError> {
+    let from = find_config_file(afk_dir).ok_or(ConfigCommandError::NoConfigFile)?;
+    if ConfigFormat::from_path(&from) == format {
+        return Ok(None);
+    }
+    let config = AfkConfig::load_base(Some(&from))?;
+    let to = format.file_in(afk_dir);
+    config.save(Some(&to))?;
+    fs::remove_file(&from)?;
+    Ok(Some((from, to)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_convert_config() {
+        let temp = TempDir::new().unwrap();
+        assert!(matches!(
+            convert_config(temp.path(), ConfigFormat::Yaml),
+            Err(ConfigCommandError::NoConfigFile)
+        ));
+
+        let json = temp.path().join("config.json");
+        let mut config = AfkConfig::default();
+        config.set_by_path("limits.max_iterations", "12").unwrap();
+        config.save(Some(&json)).unwrap();
+
+        let (from, to) = convert_config(temp.path(), ConfigFormat::Yaml)
+            .unwrap()
+            .unwrap();
+        assert_eq!(from, json);
+        assert_eq!(to, temp.path().join("config.yaml"));
+        assert!(!json.exists());
+        assert!(fs::read_to_string(&to)
+            .unwrap()
+            .contains("max_iterations: 12"));
+        assert!(convert_config(temp.path(), ConfigFormat::Yaml)
+            .unwrap()
+            .is_none());
+
+        let (_, to) = convert_config(temp.path(), ConfigFormat::Toml)
+            .unwrap()
+            .unwrap();
+        let mut config = AfkConfig::load(Some(&to)).unwrap();
+        assert_eq!(config.get_by_path("limits.max_iterations").unwrap(), "12");
+        config.set_by_path("limits.max_iterations", "20").unwrap();
+        config.save(Some(&to)).unwrap();
+        assert!(fs::read_to_string(&to)
+            .unwrap()
+            .contains("max_iterations = 20"));
+    }
+
+    #[test]
+    fn test_create_profile() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("profiles");
+        let base = AfkConfig::default();
+        let settings = [
+            "limits.max_iterations=50".to_string(),
+            "git.auto_commit = false".to_string(),
+        ];
+        let path = create_profile(&dir, &base, "overnight", &settings).unwrap();
+        assert_eq!(path, dir.join("overnight.json"));
+        let overrides = profile::load_profile(&dir, "overnight").unwrap();
+        assert_eq!(
+            overrides,
+            serde_json::json!({"limits": {"max_iterations": 50}, "git": {"auto_commit": false}})
+        );
+
+        assert!(matches!(
+            create_profile(&dir, &base, "overnight", &[]),
+            Err(ConfigCommandError::ProfileExists(_))
+        ));
+        assert!(matches!(
+            create_profile(&dir, &base, "../escape", &[]),
+            Err(ConfigCommandError::InvalidProfileName(_))
+        ));
+        assert!(matches!(
+            create_profile(&dir, &base, "quick", &["limits.max_iterations".to_string()]),
+            Err(ConfigCommandError::InvalidOverride(_))
+        ));
+        assert!(matches!(
+            create_profile(&dir, &base, "quick", &["limits.nope=1".to_string()]),
+            Err(ConfigCommandError::FieldError(_))
+        ));
+        assert!(profile::find_profile(&dir, "quick").is_none());
+    }
 
     #[test]
     fn test_config_get_valid_key() {
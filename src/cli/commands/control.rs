@@ -0,0 +1,40 @@
+//! Control command implementations.
+//!
+//! This module implements `afk pause` and `afk resume`, which steer a loop
+//! running in another terminal through its control files.
+
+use crate::config::PAUSE_FILE;
+use crate::runner::{control_state, request_pause, resume as clear_pause, ControlState};
+
+/// Result type for control command operations.
+pub type ControlCommandResult = Result<(), ControlCommandError>;
+
+/// Error type for control command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ControlCommandError {
+    /// Failed to write or remove the control file.
+    #[error("Failed to update {PAUSE_FILE}: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Pause the loop after its current iteration.
+pub fn pause() -> ControlCommandResult {
+    if control_state() == ControlState::Paused {
+        println!("\x1b[2mAlready paused.\x1b[0m");
+        return Ok(());
+    }
+    request_pause()?;
+    println!("\x1b[32m✓\x1b[0m Paused: the loop waits once its current iteration ends");
+    println!("\x1b[2m  Carry on with `afk resume`.\x1b[0m");
+    Ok(())
+}
+
+/// Let a paused loop carry on.
+pub fn resume() -> ControlCommandResult {
+    if clear_pause()? {
+        println!("\x1b[32m✓\x1b[0m Resumed");
+    } else {
+        println!("\x1b[2mNot paused.\x1b[0m");
+    }
+    Ok(())
+}
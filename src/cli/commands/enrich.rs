@@ -0,0 +1,201 @@
+//! Enrich command implementation.
+//!
+//! This module implements `afk tasks enrich`, which has the AI write
+//! descriptions and acceptance criteria for pending tasks that have only a
+//! title, marking what it wrote as generated for review.
+
+use std::collections::HashSet;
+
+use crate::bootstrap::ensure_ai_cli_configured;
+use crate::config::AfkConfig;
+use crate::feedback::Spinner;
+use crate::prd::enrich::{
+    apply_enrichment, generate_enrich_prompt, parse_enrichments, thin_fields, Enrichment,
+    GENERATED_DESCRIPTION,
+};
+use crate::prd::{PrdDocument, PrdError, UserStory};
+use crate::runner::ask_ai;
+
+/// Most tasks sent to the AI in one prompt.
+const ENRICH_BATCH: usize = 10;
+
+/// Result type for enrich command operations.
+pub type EnrichCommandResult = Result<(), EnrichCommandError>;
+
+/// Error type for enrich command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum EnrichCommandError {
+    /// Reading or writing the tasks file failed.
+    #[error("Failed to update tasks: {0}")]
+    Prd(#[from] PrdError),
+
+    /// A task named on the command line doesn't exist.
+    #[error("Task not found: {0}")]
+    TaskNotFound(String),
+
+    /// The prompt template failed to render.
+    #[error("Failed to build the enrich prompt: {0}")]
+    Prompt(#[from] tera::Error),
+
+    /// No AI CLI is configured.
+    #[error("No AI CLI configured (run afk init)")]
+    NoAiCli,
+
+    /// Every batch sent to the AI failed.
+    #[error("Enrichment failed: {0}")]
+    Failed(String),
+}
+
+/// Write descriptions and acceptance criteria for thin pending tasks, or
+/// only those in `task_ids` when any are given. With `dry_run`, list the
+/// tasks that would be sent instead.
+pub fn enrich(task_ids: &[String], dry_run: bool) -> EnrichCommandResult {
+    let prd = PrdDocument::load(None)?;
+    if let Some(missing) = task_ids.iter().find(|id| prd.get_story(id).is_none()) {
+        return Err(EnrichCommandError::TaskNotFound(missing.clone()));
+    }
+    let thin = thin_stories(&prd, task_ids);
+    if thin.is_empty() {
+        println!("No thin tasks to enrich: every pending task has a description and criteria.");
+        return Ok(());
+    }
+
+    if dry_run {
+        println!("\x1b[1mTasks to enrich:\x1b[0m");
+        for story in &thin {
+            println!(
+                "  \x1b[36m{}\x1b[0m {} \x1b[2m({})\x1b[0m",
+                story.id,
+                story.title,
+                describe_fields(&thin_fields(story))
+            );
+        }
+        return Ok(());
+    }
+
+    let mut config = AfkConfig::load(None).unwrap_or_default();
+    if let Some(ai_cli) = ensure_ai_cli_configured(Some(&mut config), false) {
+        config.ai_cli = ai_cli;
+    } else {
+        return Err(EnrichCommandError::NoAiCli);
+    }
+
+    let mut enrichments: Vec<Enrichment> = Vec::new();
+    let mut errors = Vec::new();
+    for batch in thin.chunks(ENRICH_BATCH) {
+        let prompt = generate_enrich_prompt(&prd.project, batch)?;
+        let spinner = Spinner::start(&format!(
+            "Enriching {} task(s) with {}...",
+            batch.len(),
+            config.ai_cli.command
+        ));
+        match ask_ai(&config, &prompt).and_then(|reply| parse_enrichments(&reply)) {
+            Ok(batch_enrichments) => {
+                spinner.stop();
+                enrichments.extend(batch_enrichments);
+            }
+            Err(e) => {
+                spinner.stop_with_error(&e);
+                errors.push(e);
+            }
+        }
+    }
+    if enrichments.is_empty() {
+        if let Some(error) = errors.pop() {
+            return Err(EnrichCommandError::Failed(error));
+        }
+    }
+
+    // The AI may have had the tasks file open, so apply to a fresh copy
+    let wanted: HashSet<&str> = thin.iter().map(|s| s.id.as_str()).collect();
+    let mut prd = PrdDocument::load(None)?;
+    let mut enriched = Vec::new();
+    for enrichment in &enrichments {
+        if !wanted.contains(enrichment.id.as_str()) {
+            continue;
+        }
+        let Some(story) = prd.get_story_mut(&enrichment.id) else {
+            continue;
+        };
+        let written = apply_enrichment(story, enrichment);
+        if !written.is_empty() {
+            enriched.push((story.id.clone(), written));
+        }
+    }
+    if enriched.is_empty() {
+        println!("\x1b[33m⚠\x1b[0m The AI wrote nothing usable; no tasks changed.");
+        return Ok(());
+    }
+    prd.save(None)?;
+
+    for (id, written) in &enriched {
+        println!(
+            "\x1b[32m✓\x1b[0m Enriched \x1b[1m{id}\x1b[0m \x1b[2m({})\x1b[0m",
+            describe_fields(written)
+        );
+    }
+    let skipped = thin.len() - enriched.len();
+    if skipped > 0 {
+        println!("  \x1b[2m{skipped} task(s) got nothing usable from the AI\x1b[0m");
+    }
+    println!();
+    println!(
+        "Generated fields are marked for review. Check them with \x1b[36mafk task <id>\x1b[0m, \
+         then \x1b[36mafk task edit <id> --reviewed\x1b[0m."
+    );
+    Ok(())
+}
+
+/// Pending stories with thin fields, limited to `task_ids` if any.
+fn thin_stories<'a>(prd: &'a PrdDocument, task_ids: &[String]) -> Vec<&'a UserStory> {
+    prd.user_stories
+        .iter()
+        .filter(|s| task_ids.is_empty() || task_ids.contains(&s.id))
+        .filter(|s| !thin_fields(s).is_empty())
+        .collect()
+}
+
+/// Fields as a reader would name them.
+fn describe_fields(fields: &[&str]) -> String {
+    fields
+        .iter()
+        .map(|&field| {
+            if field == GENERATED_DESCRIPTION {
+                "description"
+            } else {
+                "acceptance criteria"
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::enrich::GENERATED_CRITERIA;
+
+    #[test]
+    fn test_thin_stories_filters_by_id() {
+        let mut prd = PrdDocument::default();
+        prd.user_stories.push(UserStory::new("a", "Add logout"));
+        let mut specified = UserStory::new("b", "Export PDF");
+        specified.description = "Reports need printing".to_string();
+        specified.acceptance_criteria = vec!["PDF downloads".into()];
+        prd.user_stories.push(specified);
+        prd.user_stories.push(UserStory::new("c", "Dark mode"));
+
+        let ids = |stories: Vec<&UserStory>| -> Vec<String> {
+            stories.iter().map(|s| s.id.clone()).collect()
+        };
+        assert_eq!(ids(thin_stories(&prd, &[])), ["a", "c"]);
+        assert_eq!(
+            ids(thin_stories(&prd, &["c".to_string(), "b".to_string()])),
+            ["c"]
+        );
+        assert_eq!(
+            describe_fields(&[GENERATED_DESCRIPTION, GENERATED_CRITERIA]),
+            "description, acceptance criteria"
+        );
+    }
+}
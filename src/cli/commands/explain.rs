@@ -0,0 +1,151 @@
+//! Explain command implementation.
+//!
+//! This module implements `afk explain --task <id>`, which says why the
+//! loop would or wouldn't pick a task now, and why it was or wasn't picked
+//! at the last selection the loop recorded.
+
+use crate::config::AfkConfig;
+use crate::prd::{PrdDocument, TaskOrder, UserStory};
+use crate::progress::{Selection, SessionProgress};
+
+/// Result type for explain command operations.
+pub type ExplainCommandResult = Result<(), ExplainCommandError>;
+
+/// Error type for explain command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ExplainCommandError {
+    /// The specified task was not found.
+    #[error("Task not found: {0}")]
+    TaskNotFound(String),
+}
+
+/// Tasks ahead of an eligible one that are named before summarising.
+const AHEAD_SHOWN: usize = 3;
+
+/// Execute the explain command.
+pub fn explain(task_id: &str) -> ExplainCommandResult {
+    let config = AfkConfig::load(None).unwrap_or_default();
+    let prd = PrdDocument::load(None).unwrap_or_default();
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let story = prd
+        .get_story(task_id)
+        .ok_or_else(|| ExplainCommandError::TaskNotFound(task_id.to_string()))?;
+
+    let order = TaskOrder::new(&config, &progress);
+    let now = Selection::evaluate(&prd, &progress, &order, config.limits.max_task_failures);
+    print!("{}", describe(story, &now, Selection::load(None).as_ref()));
+    Ok(())
+}
+
+/// The explanation for a task, given selections now and at the last run.
+fn describe(story: &UserStory, now: &Selection, last: Option<&Selection>) -> String {
+    let mut out = format!("\x1b[1m=== {} ===\x1b[0m\n", story.id);
+    out.push_str(&format!("\x1b[1mTitle:\x1b[0m {}\n\n", story.title));
+
+    out.push_str("\x1b[1mNow\x1b[0m\n");
+    if story.passes {
+        out.push_str("  \x1b[32m✓\x1b[0m complete, nothing left to pick\n");
+    } else {
+        out.push_str(&verdict(&story.id, now));
+    }
+
+    out.push('\n');
+    match last {
+        Some(last) => {
+            out.push_str(&format!(
+                "\x1b[1mLast selection\x1b[0m ({})\n",
+                last.evaluated_at.replace('T', " ")
+            ));
+            if last.eligible.contains(&story.id) || last.excluded.contains_key(&story.id) {
+                out.push_str(&verdict(&story.id, last));
+            } else {
+                out.push_str("  not pending at the time\n");
+            }
+        }
+        None => out.push_str("\x1b[2mThe loop hasn't recorded a selection yet.\x1b[0m\n"),
+    }
+    out
+}
+
+/// Lines saying whether a selection picked, queued or excluded a task.
+fn verdict(task_id: &str, selection: &Selection) -> String {
+    if let Some(reasons) = selection.excluded.get(task_id) {
+        return reasons
+            .iter()
+            .map(|reason| format!("  \x1b[31m✗\x1b[0m {reason}\n"))
+            .collect();
+    }
+    match selection.eligible.iter().position(|id| id == task_id) {
+        Some(0) => "  \x1b[32m✓\x1b[0m next to be picked\n".to_string(),
+        Some(n) => {
+            let ahead = &selection.eligible[..n];
+            let mut names = ahead[..ahead.len().min(AHEAD_SHOWN)].join(", ");
+            if ahead.len() > AHEAD_SHOWN {
+                names.push_str(&format!(" and {} more", ahead.len() - AHEAD_SHOWN));
+            }
+            format!(
+                "  \x1b[32m✓\x1b[0m eligible, #{} in line after {names}\n",
+                n + 1
+            )
+        }
+        None => "  not pending\n".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::Exclusion;
+
+    fn selection(eligible: &[&str], excluded: &[(&str, Exclusion)]) -> Selection {
+        Selection {
+            evaluated_at: "2026-01-12T14:05:31".to_string(),
+            eligible: eligible.iter().map(|s| s.to_string()).collect(),
+            excluded: excluded
+                .iter()
+                .map(|(id, reason)| (id.to_string(), vec![reason.clone()]))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_verdict() {
+        let now = selection(
+            &["a", "b", "c", "d", "e"],
+            &[(
+                "x",
+                Exclusion::DependencyUnmet {
+                    waiting_on: vec!["a".to_string()],
+                },
+            )],
+        );
+        assert!(verdict("a", &now).contains("next to be picked"));
+        assert!(verdict("c", &now).contains("#3 in line after a, b"));
+        assert!(verdict("e", &now).contains("#5 in line after a, b, c and 1 more"));
+        assert!(verdict("x", &now).contains("✗\x1b[0m waiting on a"));
+        assert_eq!(verdict("gone", &now), "  not pending\n");
+    }
+
+    #[test]
+    fn test_describe_with_and_without_history() {
+        let story = UserStory::new("b", "Build it");
+        let now = selection(&["a", "b"], &[]);
+        let last = selection(
+            &["a"],
+            &[(
+                "b",
+                Exclusion::Claimed {
+                    worker: "worker-2".to_string(),
+                },
+            )],
+        );
+
+        let text = describe(&story, &now, Some(&last));
+        assert!(text.contains("#2 in line after a"));
+        assert!(text.contains("Last selection\x1b[0m (2026-01-12 14:05:31)"));
+        assert!(text.contains("claimed by worker-2"));
+
+        let text = describe(&story, &now, None);
+        assert!(text.contains("hasn't recorded a selection yet"));
+    }
+}
@@ -0,0 +1,396 @@
+//! Export command implementation.
+//!
+//! This module implements `afk export patches`, which writes the commits
+//! recorded for each completed task as a `git format-patch` mbox file, one
+//! per task and numbered in the order they were made, with an `index.json`
+//! manifest, for reviewing and applying the work without the branch; and
+//! `afk tasks export`, which writes the task list as a spreadsheet for
+//! people who don't use afk.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::cli::output::json::SCHEMA_VERSION;
+use crate::git::{commit_info, format_patch, get_current_branch};
+use crate::prd::{PrdDocument, PrdError};
+use crate::progress::{SessionProgress, TaskStatus};
+use crate::sources::csv::format_record;
+
+/// Name of the manifest written alongside the patches.
+pub const INDEX_FILE: &str = "index.json";
+
+/// Result type for export command operations.
+pub type ExportCommandResult = Result<(), ExportCommandError>;
+
+/// Error type for export command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportCommandError {
+    /// Error loading the progress file.
+    #[error("Failed to load progress: {0}")]
+    LoadError(#[from] crate::progress::ProgressError),
+    /// Error writing the patches.
+    #[error("Failed to write patches: {0}")]
+    IoError(#[from] std::io::Error),
+    /// A commit couldn't be turned into a patch.
+    #[error("git format-patch failed for {0}")]
+    FormatPatch(String),
+    /// Error loading the tasks file.
+    #[error("Failed to load tasks: {0}")]
+    TasksError(#[from] PrdError),
+}
+
+/// Spreadsheet format for `afk tasks export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TaskExportFormat {
+    /// Comma-separated values.
+    Csv,
+    /// Tab-separated values.
+    Tsv,
+}
+
+impl TaskExportFormat {
+    fn delimiter(self) -> char {
+        match self {
+            Self::Csv => ',',
+            Self::Tsv => '\t',
+        }
+    }
+}
+
+/// Columns written by `afk tasks export`. ID, Title, Description, Priority,
+/// Acceptance Criteria and Done match a csv source's default columns, so an
+/// export can be edited and synced back in.
+const TASK_COLUMNS: [&str; 9] = [
+    "ID",
+    "Title",
+    "Description",
+    "Priority",
+    "Status",
+    "Done",
+    "Acceptance Criteria",
+    "Epic",
+    "Depends On",
+];
+
+/// The manifest of an export, written as `index.json`.
+#[derive(Debug, Serialize)]
+struct PatchIndex {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    schema_version: u32,
+    /// ISO timestamp of the export.
+    generated_at: String,
+    /// Branch the commits were exported from.
+    branch: Option<String>,
+    /// Patch files, in the order to apply them.
+    patches: Vec<PatchEntry>,
+    /// Completed tasks with no patch, and why.
+    skipped: Vec<SkippedTask>,
+}
+
+/// One task's patch file.
+#[derive(Debug, Serialize)]
+struct PatchEntry {
+    /// Position in the order to apply (1-based).
+    order: usize,
+    /// Task ID.
+    task_id: String,
+    /// Task title.
+    title: String,
+    /// Patch file name, relative to the export directory.
+    file: String,
+    /// ISO timestamp the task was completed.
+    completed_at: Option<String>,
+    /// The commits in the patch, oldest first.
+    commits: Vec<PatchCommit>,
+}
+
+/// A commit in a patch file.
+#[derive(Debug, Serialize)]
+struct PatchCommit {
+    /// Full commit hash.
+    sha: String,
+    /// Commit subject.
+    subject: String,
+}
+
+/// A completed task left out of the export.
+#[derive(Debug, Serialize)]
+struct SkippedTask {
+    /// Task ID.
+    task_id: String,
+    /// Why it has no patch.
+    reason: String,
+}
+
+/// Write one patch file per completed task, and `index.json`, to `out`.
+pub fn patches(out: &Path) -> ExportCommandResult {
+    let progress = SessionProgress::load(None)?;
+    let prd = PrdDocument::load(None).unwrap_or_default();
+
+    // Each completed task's commits that still exist, oldest first, with
+    // tasks ordered by their first commit
+    let mut tasks = Vec::new();
+    let mut skipped = Vec::new();
+    for task in progress
+        .tasks
+        .values()
+        .filter(|t| t.status == TaskStatus::Completed)
+    {
+        let mut commits: Vec<(String, i64, String)> =
+            task.commits.iter().filter_map(|c| commit_info(c)).collect();
+        if commits.is_empty() {
+            skipped.push(SkippedTask {
+                task_id: task.id.clone(),
+                reason: if task.commits.is_empty() {
+                    "no commits recorded".to_string()
+                } else {
+                    "its commits are no longer in the repository".to_string()
+                },
+            });
+            continue;
+        }
+        commits.sort_by_key(|(_, time, _)| *time);
+        tasks.push((task, commits));
+    }
+    tasks.sort_by(|(a, ac), (b, bc)| ac[0].1.cmp(&bc[0].1).then_with(|| a.id.cmp(&b.id)));
+
+    let mut exported: HashSet<String> = HashSet::new();
+    let mut patches = Vec::new();
+    for (task, commits) in tasks {
+        let commits: Vec<(String, i64, String)> = commits
+            .into_iter()
+            .filter(|(sha, _, _)| exported.insert(sha.clone()))
+            .collect();
+        if commits.is_empty() {
+            skipped.push(SkippedTask {
+                task_id: task.id.clone(),
+                reason: "its commits are in an earlier patch".to_string(),
+            });
+            continue;
+        }
+
+        let mut mbox = String::new();
+        for (sha, _, _) in &commits {
+            mbox.push_str(
+                &format_patch(sha).ok_or_else(|| ExportCommandError::FormatPatch(sha.clone()))?,
+            );
+        }
+        let order = patches.len() + 1;
+        let file = patch_file_name(order, &task.id);
+        if order == 1 {
+            fs::create_dir_all(out)?;
+        }
+        fs::write(out.join(&file), mbox)?;
+        patches.push(PatchEntry {
+            order,
+            task_id: task.id.clone(),
+            title: prd
+                .get_story(&task.id)
+                .map(|s| s.title.clone())
+                .unwrap_or_else(|| task.id.clone()),
+            file,
+            completed_at: task.completed_at.clone(),
+            commits: commits
+                .into_iter()
+                .map(|(sha, _, subject)| PatchCommit { sha, subject })
+                .collect(),
+        });
+    }
+
+    if patches.is_empty() {
+        println!("\x1b[33mNo completed tasks with commits to export.\x1b[0m");
+        return Ok(());
+    }
+
+    skipped.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+    let index = PatchIndex {
+        schema_version: SCHEMA_VERSION,
+        generated_at: Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+        branch: get_current_branch(),
+        patches,
+        skipped,
+    };
+    fs::write(
+        out.join(INDEX_FILE),
+        serde_json::to_string_pretty(&index).unwrap_or_default() + "\n",
+    )?;
+
+    for patch in &index.patches {
+        println!(
+            "  \x1b[36m{}\x1b[0m  {} ({} commit{})",
+            patch.file,
+            patch.title,
+            patch.commits.len(),
+            if patch.commits.len() == 1 { "" } else { "s" }
+        );
+    }
+    for task in &index.skipped {
+        println!("  \x1b[2mskipped {}: {}\x1b[0m", task.task_id, task.reason);
+    }
+    println!(
+        "\x1b[32m✓\x1b[0m Wrote {} patch(es) and {INDEX_FILE} to {}",
+        index.patches.len(),
+        out.display()
+    );
+    println!(
+        "\x1b[2m  Apply in order with `git am {}/*.patch`, or one at a time.\x1b[0m",
+        out.display()
+    );
+    Ok(())
+}
+
+/// File name for a task's patch: its position, then the task ID with
+/// anything unsafe in a file name replaced by `-`.
+fn patch_file_name(order: usize, task_id: &str) -> String {
+    let id: String = task_id
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let id = id.trim_matches(|c| c == '.' || c == '-');
+    if id.is_empty() {
+        format!("{order:02}.patch")
+    } else {
+        format!("{order:02}-{id}.patch")
+    }
+}
+
+/// Write the task list as CSV or TSV to `out`, or stdout when None.
+pub fn tasks(
+    format: TaskExportFormat,
+    out: Option<&Path>,
+    pending_only: bool,
+) -> ExportCommandResult {
+    let prd = PrdDocument::load(None)?;
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let table = tasks_table(&prd, &progress, format.delimiter(), pending_only);
+    match out {
+        Some(out) => {
+            fs::write(out, table)?;
+            println!(
+                "\x1b[32m✓\x1b[0m Exported {} task(s) to {}",
+                prd.user_stories
+                    .iter()
+                    .filter(|s| !pending_only || !s.passes)
+                    .count(),
+                out.display()
+            );
+        }
+        None => print!("{table}"),
+    }
+    Ok(())
+}
+
+/// The task list as delimited text, with a header row.
+fn tasks_table(
+    prd: &PrdDocument,
+    progress: &SessionProgress,
+    delimiter: char,
+    pending_only: bool,
+) -> String {
+    let mut table = format_record(&TASK_COLUMNS, delimiter);
+    table.push('\n');
+    for story in prd
+        .user_stories
+        .iter()
+        .filter(|s| !pending_only || !s.passes)
+    {
+        let status = match progress.get_task(&story.id).map(|t| t.status) {
+            _ if story.passes => "done",
+            Some(TaskStatus::InProgress) => "in progress",
+            Some(TaskStatus::Completed) => "done",
+            Some(TaskStatus::Failed) => "failed",
+            Some(TaskStatus::Skipped) => "skipped",
+            Some(TaskStatus::AwaitingReview) => "awaiting review",
+            Some(TaskStatus::Pending) | None => "pending",
+        };
+        let criteria: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        let row = [
+            story.id.clone(),
+            story.title.clone(),
+            story.description.clone(),
+            story.priority.to_string(),
+            status.to_string(),
+            if story.passes { "yes" } else { "" }.to_string(),
+            criteria.join("\n"),
+            story.epic.clone().unwrap_or_default(),
+            story.depends_on.join(", "),
+        ];
+        table.push_str(&format_record(&row, delimiter));
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ColumnMapping, SourceConfig};
+    use crate::prd::UserStory;
+    use crate::sources::csv::parse_records;
+    use crate::sources::load_csv_tasks;
+
+    #[test]
+    fn test_tasks_table_round_trips_through_csv_source() {
+        let mut login = UserStory::new("auth", "Login, then logout");
+        login.acceptance_criteria = vec!["Form validates".into(), "Says \"hi\"".into()];
+        login.priority = 1;
+        login.epic = Some("Accounts".to_string());
+        let mut docs = UserStory::new("docs", "Docs");
+        docs.passes = true;
+        let prd = PrdDocument {
+            user_stories: vec![login, docs],
+            ..Default::default()
+        };
+        let mut progress = SessionProgress::default();
+        progress.set_task_status("auth", TaskStatus::InProgress, "json", None);
+
+        let table = tasks_table(&prd, &progress, ',', false);
+        let records = parse_records(&table, ',');
+        assert_eq!(records[0], TASK_COLUMNS);
+        assert_eq!(records[1][0], "auth");
+        assert_eq!(records[1][1], "Login, then logout");
+        assert_eq!(records[1][4], "in progress");
+        assert_eq!(records[1][6], "Form validates\nSays \"hi\"");
+        assert_eq!(records[2][4], "done");
+        assert_eq!(records[2][5], "yes");
+        let pending = tasks_table(&prd, &progress, '\t', true);
+        assert_eq!(parse_records(&pending, '\t').len(), 2);
+
+        // Read back as a csv source, the pending task comes back as it was
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("tasks.csv");
+        fs::write(&path, &table).unwrap();
+        let source = SourceConfig {
+            columns: Some(ColumnMapping::default()),
+            ..SourceConfig::csv(path.to_str().unwrap())
+        };
+        let tasks = load_csv_tasks(&source);
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, "auth");
+        assert_eq!(tasks[0].priority, 1);
+        assert_eq!(tasks[0].acceptance_criteria.len(), 2);
+    }
+
+    #[test]
+    fn test_patch_file_name() {
+        assert_eq!(patch_file_name(1, "auth-login"), "01-auth-login.patch");
+        assert_eq!(patch_file_name(12, "JIRA/PROJ 42"), "12-JIRA-PROJ-42.patch");
+        assert_eq!(patch_file_name(3, "../etc"), "03-etc.patch");
+        assert_eq!(patch_file_name(4, "//"), "04.patch");
+    }
+}
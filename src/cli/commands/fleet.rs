@@ -0,0 +1,245 @@
+//! Fleet command implementation.
+//!
+//! This module implements `afk fleet`, which works across the afk projects
+//! listed in an `afk-fleet.json` in the current directory: `init` writes the
+//! file from the projects found below, and `status`, `sync` and `go` run in
+//! each project and report on them together.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::cli::output::json;
+use crate::fleet::{
+    discover, go as fleet_go, project_summary, statuses, sync as fleet_sync, FleetConfig,
+    FleetError, ProjectRun, FLEET_FILE, FLEET_LOG,
+};
+
+/// Result type for fleet command operations.
+pub type FleetCommandResult = Result<(), FleetCommandError>;
+
+/// Error type for fleet command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum FleetCommandError {
+    /// Loading or saving the fleet config failed.
+    #[error("{0}")]
+    FleetError(#[from] FleetError),
+    /// The fleet config file already exists.
+    #[error("{FLEET_FILE} already exists. Use --force to rewrite it.")]
+    AlreadyExists,
+    /// No afk projects were found.
+    #[error("No afk projects (directories with .afk/) found below {0}")]
+    NoProjects(String),
+    /// Failed to get the current directory.
+    #[error("Failed to get current directory: {0}")]
+    IoError(#[from] std::io::Error),
+    /// The command failed in some projects.
+    #[error("Failed in {0} project(s)")]
+    ProjectsFailed(usize),
+}
+
+/// Write `afk-fleet.json` listing the projects found up to `depth` levels
+/// below the current directory.
+pub fn init(depth: usize, force: bool) -> FleetCommandResult {
+    let root = std::env::current_dir()?;
+    if root.join(FLEET_FILE).exists() && !force {
+        return Err(FleetCommandError::AlreadyExists);
+    }
+    let projects = discover(&root, depth);
+    if projects.is_empty() {
+        return Err(FleetCommandError::NoProjects(root.display().to_string()));
+    }
+    let config = FleetConfig {
+        projects,
+        ..Default::default()
+    };
+    config.save(&root)?;
+    println!(
+        "\x1b[32m✓\x1b[0m Wrote {FLEET_FILE} with {} project(s):",
+        config.projects.len()
+    );
+    for project in &config.projects {
+        println!("  {project}");
+    }
+    println!("\x1b[2m  Edit it to drop projects or set max_parallel and go_args.\x1b[0m");
+    Ok(())
+}
+
+/// Show each project's tasks and session, with totals.
+pub fn status(as_json: bool) -> FleetCommandResult {
+    let (root, config) = load()?;
+    let statuses = statuses(&root, &config);
+
+    let mut complete = 0;
+    let mut total = 0;
+    let mut failed = 0;
+    for status in statuses.iter().filter_map(|(_, s)| s.as_ref().ok()) {
+        complete += count(status, "/tasks/complete");
+        total += count(status, "/tasks/total");
+        failed += count(status, "/session/counts/failed");
+    }
+
+    if as_json {
+        let projects: Vec<Value> = statuses
+            .iter()
+            .map(|(project, status)| match status {
+                Ok(status) => json!({ "project": project, "status": status }),
+                Err(error) => json!({ "project": project, "error": error }),
+            })
+            .collect();
+        json::print(&json!({
+            "projects": projects,
+            "total": { "complete": complete, "total": total, "pending": total - complete, "failed": failed },
+        }));
+        return Ok(());
+    }
+
+    println!("\x1b[1m=== afk fleet ===\x1b[0m");
+    println!();
+    let width = name_width(&config);
+    for (project, status) in &statuses {
+        match status {
+            Ok(status) if status["initialised"] == json!(true) => {
+                let done = count(status, "/tasks/complete");
+                let all = count(status, "/tasks/total");
+                let failed = count(status, "/session/counts/failed");
+                let control = status
+                    .pointer("/session/control")
+                    .and_then(Value::as_str)
+                    .unwrap_or("running");
+                let next = status
+                    .pointer("/tasks/next")
+                    .and_then(Value::as_str)
+                    .map(|id| format!("  next: {id}"))
+                    .unwrap_or_default();
+                let colour = if all > 0 && done == all { "32" } else { "0" };
+                print!("  {project:<width$}  \x1b[{colour}m{done:>3}/{all:<3}\x1b[0m done");
+                if failed > 0 {
+                    print!("  \x1b[31m{failed} failed\x1b[0m");
+                }
+                if control != "running" {
+                    print!("  \x1b[33m{}\x1b[0m", control.replace('_', " "));
+                }
+                println!("\x1b[2m{next}\x1b[0m");
+            }
+            Ok(_) => println!("  {project:<width$}  \x1b[33mnot initialised\x1b[0m"),
+            Err(error) => println!("  {project:<width$}  \x1b[31m{error}\x1b[0m"),
+        }
+    }
+    println!();
+    println!(
+        "  {:<width$}  \x1b[1m{complete:>3}/{total:<3}\x1b[0m done, {} pending, {failed} failed",
+        "Total",
+        total - complete
+    );
+    Ok(())
+}
+
+/// Sync tasks from their sources in every project.
+pub fn sync() -> FleetCommandResult {
+    let (root, config) = load()?;
+    let width = name_width(&config);
+    let runs = fleet_sync(&root, &config, |run| print_run(run, width));
+    report_failures(&runs)
+}
+
+/// Run `afk go` across the fleet, `max_parallel` projects at a time
+/// (falling back to the config's), then report on each.
+pub fn go(iterations: Option<u32>, max_parallel: Option<usize>) -> FleetCommandResult {
+    let (root, config) = load()?;
+    let parallel = max_parallel.unwrap_or(config.max_parallel).max(1);
+    println!(
+        "\x1b[1mafk fleet go\x1b[0m \x1b[2m({} project(s), {parallel} at a time; output in each project's {FLEET_LOG})\x1b[0m",
+        config.projects.len()
+    );
+    let width = name_width(&config);
+    let runs = fleet_go(&root, &config, iterations, parallel, |run| {
+        print_run(run, width)
+    });
+    print_report(&root, &runs, width);
+    report_failures(&runs)
+}
+
+/// Load the fleet config from the current directory.
+fn load() -> Result<(PathBuf, FleetConfig), FleetCommandError> {
+    let root = std::env::current_dir()?;
+    let config = FleetConfig::load(&root)?;
+    Ok((root, config))
+}
+
+/// A number from a status JSON, or 0.
+fn count(status: &Value, pointer: &str) -> u64 {
+    status.pointer(pointer).and_then(Value::as_u64).unwrap_or(0)
+}
+
+fn name_width(config: &FleetConfig) -> usize {
+    config
+        .projects
+        .iter()
+        .map(|p| p.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Total".len())
+}
+
+/// One line for a project as its command finishes.
+fn print_run(run: &ProjectRun, width: usize) {
+    let secs = run.duration.as_secs_f64();
+    match &run.error {
+        None => println!(
+            "  \x1b[32m✓\x1b[0m {:<width$}  \x1b[2m{secs:.1}s\x1b[0m",
+            run.project
+        ),
+        Some(error) => println!(
+            "  \x1b[31m✗\x1b[0m {:<width$}  {error} \x1b[2m({secs:.1}s)\x1b[0m",
+            run.project
+        ),
+    }
+}
+
+/// What each project's run did, from its event log, with totals.
+fn print_report(root: &Path, runs: &[ProjectRun], width: usize) {
+    println!();
+    println!("\x1b[1mFleet report\x1b[0m");
+    let mut iterations = 0;
+    let mut commits = 0;
+    let mut tokens = 0;
+    let mut cost = None;
+    for run in runs {
+        let summary = project_summary(root, &run.project);
+        iterations += summary.iterations;
+        commits += summary.commits;
+        tokens += summary.input_tokens + summary.output_tokens;
+        if let Some(c) = summary.cost_usd {
+            cost = Some(cost.unwrap_or(0.0) + c);
+        }
+        let ended = summary
+            .ended
+            .map(|reason| format!("  \x1b[2m{reason}\x1b[0m"))
+            .unwrap_or_default();
+        println!(
+            "  {:<width$}  {} iteration(s), {} commit(s), {} tokens{}{ended}",
+            run.project,
+            summary.iterations,
+            summary.commits,
+            summary.input_tokens + summary.output_tokens,
+            format_cost(summary.cost_usd),
+        );
+    }
+    println!(
+        "  \x1b[1m{:<width$}  {iterations} iteration(s), {commits} commit(s), {tokens} tokens{}\x1b[0m",
+        "Total",
+        format_cost(cost),
+    );
+}
+
+fn format_cost(cost: Option<f64>) -> String {
+    cost.map(|c| format!(", ${c:.2}")).unwrap_or_default()
+}
+
+fn report_failures(runs: &[ProjectRun]) -> FleetCommandResult {
+    match runs.iter().filter(|r| !r.success).count() {
+        0 => Ok(()),
+        n => Err(FleetCommandError::ProjectsFailed(n)),
+    }
+}
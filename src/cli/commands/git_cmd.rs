@@ -0,0 +1,263 @@
+//! Git command implementations.
+//!
+//! This module implements `afk git squash`, which folds the commits the loop
+//! recorded for a task, or for the whole session, into one commit with a
+//! message built from the task titles and learnings.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::config::AfkConfig;
+use crate::git::commit_log;
+use crate::git::squash::{self, SquashError};
+use crate::prd::PrdDocument;
+use crate::progress::SessionProgress;
+
+/// Result type for git command operations.
+pub type GitCommandResult = Result<(), GitCommandError>;
+
+/// Error type for git command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum GitCommandError {
+    /// Error loading the progress file.
+    #[error("Failed to load progress: {0}")]
+    LoadError(#[from] crate::progress::ProgressError),
+    /// Error saving the progress file.
+    #[error("Failed to save progress: {0}")]
+    SaveError(crate::progress::ProgressError),
+    /// The task has no entry in the progress file.
+    #[error("Task {0} not found in progress")]
+    TaskNotFound(String),
+    /// Nothing in scope has recorded commits.
+    #[error("No commits recorded for {0}")]
+    NoCommits(String),
+    /// Some of the commits have been pushed.
+    #[error("These commits are already on a remote branch; use --force to rewrite them anyway")]
+    Pushed,
+    /// The squash itself failed.
+    #[error("Squash failed: {0}")]
+    Squash(#[from] SquashError),
+}
+
+/// Which recorded commits to squash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SquashScope {
+    /// One task's commits.
+    Task(String),
+    /// Every task's commits this session.
+    Session,
+}
+
+/// A task whose commits are being squashed, for the commit message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SquashedTask {
+    id: String,
+    title: String,
+    learnings: Vec<String>,
+}
+
+/// Squash the commits recorded for `scope` into one.
+///
+/// Shows the commits and the new message, then asks before rewriting unless
+/// `yes` is set. Commits already on a remote branch are only rewritten with
+/// `force`. Recorded commit hashes are updated afterwards.
+pub fn squash(scope: &SquashScope, yes: bool, force: bool) -> GitCommandResult {
+    let mut progress = SessionProgress::load(None)?;
+    let (commits, label) = match scope {
+        SquashScope::Task(id) => {
+            let task = progress
+                .tasks
+                .get(id)
+                .ok_or_else(|| GitCommandError::TaskNotFound(id.clone()))?;
+            (task.commits.clone(), format!("task {id}"))
+        }
+        SquashScope::Session => (
+            progress
+                .tasks
+                .values()
+                .flat_map(|t| t.commits.iter().cloned())
+                .collect(),
+            "this session".to_string(),
+        ),
+    };
+    if commits.is_empty() {
+        return Err(GitCommandError::NoCommits(label));
+    }
+
+    let plan = squash::plan(Path::new("."), &commits)?;
+    if plan.pushed && !force {
+        return Err(GitCommandError::Pushed);
+    }
+
+    // Tasks in the order their first commits were made
+    let prd = PrdDocument::load(None).unwrap_or_default();
+    let mut tasks: Vec<SquashedTask> = Vec::new();
+    for commit in &plan.commits {
+        let Some(task) = progress.tasks.values().find(|t| {
+            t.commits.iter().any(|c| commit.starts_with(c.as_str()))
+                && !tasks.iter().any(|s| s.id == t.id)
+        }) else {
+            continue;
+        };
+        tasks.push(SquashedTask {
+            id: task.id.clone(),
+            title: prd
+                .get_story(&task.id)
+                .map(|s| s.title.clone())
+                .unwrap_or_else(|| task.id.clone()),
+            learnings: task.learnings.clone(),
+        });
+    }
+    let template = AfkConfig::load(None)
+        .unwrap_or_default()
+        .git
+        .commit_message_template;
+    let message = squash_message(&template, &tasks);
+
+    println!(
+        "\x1b[1mSquashing {} commit(s) for {label}:\x1b[0m",
+        plan.commits.len()
+    );
+    for line in commit_log(&format!("{}..HEAD", plan.base)) {
+        let short = line.split(' ').next().unwrap_or_default();
+        if plan.commits.iter().any(|c| c.starts_with(short)) {
+            println!("  \x1b[33m●\x1b[0m {line}");
+        } else {
+            println!("  \x1b[2m  {line} (kept, rewritten)\x1b[0m");
+        }
+    }
+    println!();
+    println!("\x1b[1mNew message:\x1b[0m");
+    for line in message.lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("  {line}");
+        }
+    }
+    println!();
+
+    if !yes {
+        print!("Rewrite the branch? [y/N]: ");
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        let confirmed = io::stdin().read_line(&mut input).is_ok()
+            && matches!(input.trim().to_lowercase().as_str(), "y" | "yes");
+        if !confirmed {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
+    let squashed = squash::squash(Path::new("."), &plan, &message)?;
+
+    // Point the recorded hashes at the rewritten commits
+    for task in progress.tasks.values_mut() {
+        let mut commits: Vec<String> = Vec::new();
+        for commit in &task.commits {
+            let new = if plan.commits.iter().any(|c| c.starts_with(commit.as_str())) {
+                squashed.commit.clone()
+            } else {
+                plan.range
+                    .iter()
+                    .find(|c| c.starts_with(commit.as_str()))
+                    .and_then(|c| squashed.rewritten.get(c))
+                    .cloned()
+                    .unwrap_or_else(|| commit.clone())
+            };
+            if !commits.contains(&new) {
+                commits.push(new);
+            }
+        }
+        task.commits = commits;
+    }
+    progress.save(None).map_err(GitCommandError::SaveError)?;
+
+    println!(
+        "\x1b[32m✓\x1b[0m Squashed {} commit(s) into {}",
+        plan.commits.len(),
+        squashed.commit.get(..7).unwrap_or(&squashed.commit)
+    );
+    Ok(())
+}
+
+/// Commit message for squashed tasks: the commit template's subject, then
+/// each task's learnings (under its title when there are several tasks).
+fn squash_message(template: &str, tasks: &[SquashedTask]) -> String {
+    let subject = |task_id: &str, message: &str| {
+        template
+            .replace("{task_id}", task_id)
+            .replace("{message}", message)
+    };
+    let bullets = |task: &SquashedTask| {
+        task.learnings
+            .iter()
+            .map(|l| format!("- {l}\n"))
+            .collect::<String>()
+    };
+
+    match tasks {
+        [] => subject("session", "squashed commits") + "\n",
+        [task] => {
+            let body = bullets(task);
+            let mut message = subject(&task.id, &task.title) + "\n";
+            if !body.is_empty() {
+                message.push('\n');
+                message.push_str(&body);
+            }
+            message
+        }
+        _ => {
+            let mut message = subject("session", &format!("{} tasks", tasks.len())) + "\n";
+            for task in tasks {
+                message.push_str(&format!("\n{}: {}\n", task.id, task.title));
+                message.push_str(&bullets(task));
+            }
+            message
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, title: &str, learnings: &[&str]) -> SquashedTask {
+        SquashedTask {
+            id: id.to_string(),
+            title: title.to_string(),
+            learnings: learnings.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_squash_message_single_task() {
+        let template = "afk: {task_id} - {message}";
+        assert_eq!(
+            squash_message(
+                template,
+                &[task("auth", "Add login", &["Tokens expire hourly"])]
+            ),
+            "afk: auth - Add login\n\n- Tokens expire hourly\n"
+        );
+        assert_eq!(
+            squash_message(template, &[task("auth", "Add login", &[])]),
+            "afk: auth - Add login\n"
+        );
+    }
+
+    #[test]
+    fn test_squash_message_session() {
+        let message = squash_message(
+            "afk: {task_id} - {message}",
+            &[
+                task("auth", "Add login", &["Use bcrypt"]),
+                task("db", "Add users table", &[]),
+            ],
+        );
+        assert_eq!(
+            message,
+            "afk: session - 2 tasks\n\nauth: Add login\n- Use bcrypt\n\ndb: Add users table\n"
+        );
+    }
+}
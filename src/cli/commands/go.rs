@@ -2,18 +2,29 @@
 //!
 //! This module implements the `afk go` command for running the autonomous loop.
 
+use std::env;
 use std::fs;
-use std::io::{self, Write};
-use std::path::Path;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Local;
 
 use crate::bootstrap::{
     analyse_project, ensure_ai_cli_configured, generate_config,
     infer_sources as bootstrap_infer_sources,
 };
-use crate::config::{AfkConfig, SourceConfig};
-use crate::prd::PrdDocument;
-use crate::progress::{archive_session, check_branch_change, update_stored_branch};
-use crate::runner::{run_loop_with_options, run_loop_with_tui, RunOptions, StopReason};
+use crate::config::{AfkConfig, AiBackend, SourceConfig};
+use crate::prd::{PrdDocument, TaskOrder};
+use crate::progress::{
+    archive_session, check_branch_change, history_samples, tag_session, update_stored_branch,
+    Calibration,
+};
+use crate::runner::{
+    commit_sandbox, format_time_budget, prepare_sandbox, refresh_derived, run_loop_with_options,
+    run_loop_with_tui, sandbox_exists, sandbox_path, wait_until, worker_branch,
+    IterationCheckpoint, RunOptions, StartTime, StopReason, SANDBOX_BRANCH,
+};
 
 /// Result type for go command operations.
 pub type GoCommandResult = Result<GoOutcome, GoCommandError>;
@@ -42,12 +53,21 @@ pub enum GoCommandError {
     /// Failed to save configuration to file.
     #[error("Failed to save config: {0}")]
     SaveConfigError(#[from] crate::config::ConfigError),
+    /// Failed to load the configuration.
+    #[error("Failed to load config: {0}")]
+    LoadConfigError(crate::config::ConfigError),
     /// No AI CLI tool is configured or available.
     #[error("No AI CLI configured")]
     NoAiCli,
     /// No task sources found or configured.
     #[error("No task sources found")]
     NoSources,
+    /// The sandbox worktree could not be set up.
+    #[error("Failed to prepare sandbox: {0}")]
+    SandboxError(String),
+    /// The `--at` date and time has already passed.
+    #[error("Start time has already passed: {0}")]
+    StartTimePassed(String),
 }
 
 /// Options for the go command.
@@ -64,12 +84,24 @@ pub struct GoOptions {
     pub until_complete: bool,
     /// Override timeout in minutes.
     pub timeout: Option<u32>,
+    /// Wall-clock budget for a focus run.
+    pub time_budget: Option<Duration>,
+    /// Wait until this time before starting.
+    pub at: Option<StartTime>,
     /// Feedback display mode.
     pub feedback: Option<String>,
     /// Disable ASCII mascot.
     pub no_mascot: bool,
+    /// Skip regenerating stale derived context.
+    pub no_refresh: bool,
     /// Show what would run without running.
     pub dry_run: bool,
+    /// Tags to add to the session.
+    pub tags: Vec<String>,
+    /// Parallel workers (1 runs the usual sequential loop).
+    pub workers: u32,
+    /// Run in a sandbox worktree instead of the working tree.
+    pub sandbox: bool,
 }
 
 /// Execute the go command.
@@ -77,7 +109,16 @@ pub struct GoOptions {
 /// This is the main entry point for running the autonomous loop.
 pub fn go(options: GoOptions) -> GoCommandResult {
     let afk_dir = Path::new(".afk");
-    let config_path = afk_dir.join("config.json");
+    let config_path = AfkConfig::config_file();
+
+    // Work out the start time up front so a past one fails straight away
+    let start_at = match options.at {
+        Some(at) => Some(
+            at.next_after(Local::now())
+                .ok_or_else(|| GoCommandError::StartTimePassed(at.to_string()))?,
+        ),
+        None => None,
+    };
 
     // Handle --init flag: delete config and re-run setup
     if options.init && config_path.exists() {
@@ -131,9 +172,10 @@ pub fn go(options: GoOptions) -> GoCommandResult {
         }
     }
 
-    // Load or create config
+    // Load or create the config as written, which is what gets saved: the
+    // active profile and ${VAR} expansion are only applied for the run
     let mut config = if config_path.exists() {
-        AfkConfig::load(None).unwrap_or_default()
+        AfkConfig::load_base(None).map_err(GoCommandError::LoadConfigError)?
     } else {
         // First run: analyse project and create config
         println!("\x1b[1mAnalysing project...\x1b[0m");
@@ -205,17 +247,55 @@ pub fn go(options: GoOptions) -> GoCommandResult {
             config_path.display()
         );
     }
+    let config = config.resolved().map_err(GoCommandError::LoadConfigError)?;
 
     // Dry run mode
     if options.dry_run {
         let effective_iterations = options.iterations.unwrap_or(config.limits.max_iterations);
         println!("\x1b[1mDry run mode - would execute:\x1b[0m");
-        println!(
-            "  AI CLI: {} {}",
-            config.ai_cli.command,
-            config.ai_cli.args.join(" ")
-        );
-        println!("  Iterations: {}", effective_iterations);
+        if config.ai_cli.backend == AiBackend::Http {
+            println!("  AI backend: {}", config.ai_cli.chat_completions_url());
+        } else if config.ai_cli.backend == AiBackend::Ollama {
+            println!("  AI backend: Ollama at {}", config.ollama.chat_url());
+        } else {
+            println!(
+                "  AI CLI: {} {}",
+                config.ai_cli.command,
+                config.ai_cli.args.join(" ")
+            );
+        }
+        match options.time_budget {
+            Some(budget) if options.iterations.is_none() => {
+                println!("  Time budget: {}", format_time_budget(budget));
+            }
+            Some(budget) => {
+                println!("  Iterations: {}", effective_iterations);
+                println!("  Time budget: {}", format_time_budget(budget));
+            }
+            None => println!("  Iterations: {}", effective_iterations),
+        }
+        if options.workers > 1 {
+            println!(
+                "  Workers: {} (branches {}..{})",
+                options.workers,
+                worker_branch(1),
+                worker_branch(options.workers)
+            );
+        }
+        if options.sandbox {
+            println!(
+                "  Sandbox: {} (branch {SANDBOX_BRANCH})",
+                sandbox_path().display()
+            );
+        }
+        if let Some(start) = start_at {
+            println!("  Start: {}", start.format("%Y-%m-%d %H:%M"));
+        }
+        let prd = PrdDocument::load(None).unwrap_or_default();
+        if let Some(story) = prd.get_next_story(&TaskOrder::load(&config)) {
+            println!("  Next task: {} - {}", story.id, story.title);
+        }
+        print_estimate();
         println!(
             "  Sources: {:?}",
             config
@@ -229,18 +309,57 @@ pub fn go(options: GoOptions) -> GoCommandResult {
         });
     }
 
+    if let Some(start) = start_at {
+        println!(
+            "\x1b[2mWaiting until {} to start (Ctrl+C to cancel)...\x1b[0m",
+            start.format("%Y-%m-%d %H:%M")
+        );
+        wait_until(start);
+    }
+
     // Build run options with feedback settings
+    // A focus run without an explicit count is bounded only by its time budget
     let effective_iterations = options.iterations.or(Some(config.limits.max_iterations));
+    let budget_only = options.time_budget.is_some() && options.iterations.is_none();
     let run_opts = RunOptions::new()
         .with_iterations(effective_iterations)
-        .with_until_complete(options.until_complete)
+        .with_until_complete(options.until_complete || budget_only)
         .with_timeout(options.timeout)
+        .with_time_budget(options.time_budget)
         .with_resume(false)
         .with_feedback_mode(RunOptions::parse_feedback_mode(options.feedback.as_deref()))
-        .with_mascot(!options.no_mascot);
+        .with_mascot(!options.no_mascot)
+        .with_workers(options.workers);
+
+    // Everything from here on happens inside the sandbox
+    let home = if options.sandbox {
+        Some(enter_sandbox()?)
+    } else {
+        None
+    };
+
+    if let Some(checkpoint) = IterationCheckpoint::load(None) {
+        recover_interrupted_iteration(&checkpoint);
+    }
+
+    if config.runner.refresh && !options.no_refresh {
+        match refresh_derived(&config) {
+            Ok(refreshed) => {
+                for name in refreshed {
+                    println!("\x1b[2mInputs changed; regenerating {name}\x1b[0m");
+                }
+            }
+            Err(e) => eprintln!("\x1b[33mWarning:\x1b[0m Failed to refresh derived context: {e}"),
+        }
+    }
 
     // Store current branch in progress for next run's comparison
     let _ = update_stored_branch();
+    if !options.tags.is_empty() {
+        if let Err(e) = tag_session(&options.tags) {
+            eprintln!("\x1b[33mWarning:\x1b[0m Failed to tag session: {e}");
+        }
+    }
 
     // Run the loop - use TUI if requested
     let result = if RunOptions::is_tui_mode(options.feedback.as_deref()) {
@@ -249,11 +368,125 @@ pub fn go(options: GoOptions) -> GoCommandResult {
         run_loop_with_options(&config, run_opts)
     };
 
+    if let Some(home) = home {
+        leave_sandbox(&home);
+    }
+
     Ok(GoOutcome {
         stop_reason: result.stop_reason,
     })
 }
 
+/// Create or reuse the sandbox worktree and move into it.
+///
+/// Returns the directory to come back to.
+fn enter_sandbox() -> Result<PathBuf, GoCommandError> {
+    let home = env::current_dir().map_err(|e| GoCommandError::SandboxError(e.to_string()))?;
+    let resumed = sandbox_exists();
+    let path = prepare_sandbox().map_err(GoCommandError::SandboxError)?;
+    env::set_current_dir(&path).map_err(|e| GoCommandError::SandboxError(e.to_string()))?;
+    let verb = if resumed { "Resuming" } else { "Running" };
+    println!(
+        "\x1b[2m{verb} in sandbox {} on {SANDBOX_BRANCH}; the working tree is left alone.\x1b[0m",
+        path.display()
+    );
+    Ok(home)
+}
+
+/// Commit the run's leftovers in the sandbox and move back out of it.
+fn leave_sandbox(home: &Path) {
+    commit_sandbox(Path::new("."));
+    if let Err(e) = env::set_current_dir(home) {
+        eprintln!("\x1b[33mWarning:\x1b[0m Failed to leave the sandbox: {e}");
+        return;
+    }
+    println!();
+    println!(
+        "Sandbox changes are on \x1b[1m{SANDBOX_BRANCH}\x1b[0m. Review them with \x1b[1mafk sandbox diff\x1b[0m,"
+    );
+    println!(
+        "then \x1b[1mafk sandbox apply\x1b[0m to merge them or \x1b[1mafk sandbox discard\x1b[0m to drop them."
+    );
+}
+
+/// Offer to roll back or resume an iteration that never finished.
+///
+/// Without a terminal to ask on, the iteration is resumed: its changes are
+/// left in place and the next prompt continues the same task.
+fn recover_interrupted_iteration(checkpoint: &IterationCheckpoint) {
+    let task = checkpoint
+        .task_id
+        .as_deref()
+        .map(|id| format!(" ({id})"))
+        .unwrap_or_default();
+    println!();
+    println!(
+        "\x1b[33m⚠\x1b[0m  Iteration {}{task} was interrupted while {} (at {}).",
+        checkpoint.iteration, checkpoint.phase, checkpoint.updated_at
+    );
+
+    let roll_back = io::stdin().is_terminal() && {
+        print!("   Roll back its changes, or resume? [r]esume/[b]ack out: ");
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        io::stdin().read_line(&mut input).is_ok()
+            && matches!(
+                input.trim().to_lowercase().as_str(),
+                "b" | "back" | "rollback"
+            )
+    };
+
+    if roll_back {
+        let rollback = checkpoint.rollback();
+        if rollback.stashed {
+            println!("\x1b[32m✓\x1b[0m Uncommitted changes stashed (see `git stash list`)");
+        }
+        if rollback.commits_undone > 0 {
+            println!(
+                "\x1b[32m✓\x1b[0m Moved back {} commit(s) to {}",
+                rollback.commits_undone,
+                checkpoint.baseline.as_deref().unwrap_or_default()
+            );
+        }
+        if !rollback.stashed && rollback.commits_undone == 0 {
+            println!("\x1b[2m   Nothing to roll back.\x1b[0m");
+        }
+    } else {
+        println!("\x1b[2m   Resuming with the iteration's changes in place.\x1b[0m");
+    }
+    IterationCheckpoint::clear(None);
+    println!();
+}
+
+/// Print the estimated work left, calibrated from past sessions.
+fn print_estimate() {
+    let prd = PrdDocument::load(None).unwrap_or_default();
+    if prd.pending_count() == 0 {
+        return;
+    }
+
+    let calibration = Calibration::from_samples(&history_samples());
+    let iterations = calibration.estimate_iterations(&prd).ceil();
+    let time = calibration
+        .seconds_per_iteration
+        .map(|secs| {
+            format!(
+                ", ~{}",
+                format_time_budget(Duration::from_secs_f64(secs * iterations))
+            )
+        })
+        .unwrap_or_default();
+    let basis = if calibration.samples == 0 {
+        "no history yet".to_string()
+    } else {
+        format!(
+            "{:.1}x estimates, from {} completed tasks",
+            calibration.ratio, calibration.samples
+        )
+    };
+    println!("  Estimate: ~{iterations} iterations{time} \x1b[2m({basis})\x1b[0m");
+}
+
 /// Print helpful message when no sources are found.
 pub fn print_no_sources_help() {
     eprintln!("\x1b[33mNo task sources found.\x1b[0m");
@@ -5,15 +5,33 @@
 //! - `afk tasks` - Display current task list
 //! - `afk tasks sync` - Sync tasks from configured sources
 
-use std::io::{BufRead, BufReader};
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use chrono::Local;
 
 use crate::bootstrap::ensure_ai_cli_configured;
+use crate::cli::output::json::{self, TasksJson};
 use crate::cli::output::{get_effective_mode, output_prompt};
-use crate::config::AfkConfig;
+use crate::config::{
+    AfkConfig, DedupConfig, SourceConfig, SyncConflictPolicy, TaskOrdering, PROGRESS_FILE,
+};
 use crate::feedback::Spinner;
-use crate::prd::{generate_prd_prompt, load_prd_file, sync_prd_with_root, PrdDocument, PrdError};
+use crate::prd::dedup::{dedup, MatchReason, SourceMap, TaskMerge};
+use crate::prd::scoring::{completion_score, order_by_probability};
+use crate::prd::{
+    detect_conflicts, generate_prd_prompt, group_by_epic, load_prd_file, plan_sync,
+    repair_tasks_json, resolve_conflicts, sync_prd_resolving, PrdDocument, PrdError, RepairedTasks,
+    SyncConflict, SyncPlan, TaskOrder, UserStory,
+};
+use crate::progress::SessionProgress;
+use crate::runner::program_command;
+use crate::sources::trello::{ListStatus, TrelloBoardExport};
+
+use super::source::source_type_to_str;
 
 /// Result type for import command operations.
 pub type ImportCommandResult = Result<(), ImportCommandError>;
@@ -39,8 +57,30 @@ pub enum ImportCommandError {
     /// Input file was not found.
     #[error("File not found: {0}")]
     FileNotFound(String),
+    /// No configured source matches a `--source` selector.
+    #[error("No source matches '{0}'. Run `afk source list` to see sources.")]
+    UnknownSource(String),
+    /// The AI's tasks still broke the schema after every retry.
+    #[error("Imported tasks are invalid: {0}")]
+    InvalidOutput(String),
+    /// The input isn't a Trello board export.
+    #[error("Not a Trello board export: {0}")]
+    InvalidTrelloExport(serde_json::Error),
+}
+
+/// What kind of document `afk import` reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// A requirements document, converted by the AI CLI.
+    #[default]
+    Prd,
+    /// A Trello board exported as JSON, converted directly.
+    Trello,
 }
 
+/// Times `afk import` runs the AI CLI before giving up on invalid output.
+const IMPORT_ATTEMPTS: u32 = 3;
+
 /// Import a requirements file into structured JSON.
 ///
 /// Takes a product requirements document (markdown, text, etc.) and generates
@@ -53,6 +93,8 @@ pub enum ImportCommandError {
 /// * `copy` - Copy to clipboard
 /// * `file` - Write prompt to file
 /// * `stdout` - Print to stdout
+/// * `chunk_size` - Import documents longer than this many characters in
+///   parts (0 to never split)
 ///
 /// # Returns
 ///
@@ -63,8 +105,9 @@ pub fn import(
     copy: bool,
     file: bool,
     stdout: bool,
+    chunk_size: usize,
 ) -> ImportCommandResult {
-    import_impl(input_file, output, copy, file, stdout, None)
+    import_impl(input_file, output, copy, file, stdout, chunk_size, None)
 }
 
 /// Internal implementation of import with optional config path for testing.
@@ -74,6 +117,7 @@ pub fn import_impl(
     copy: bool,
     file: bool,
     stdout: bool,
+    chunk_size: usize,
     config_path: Option<&Path>,
 ) -> ImportCommandResult {
     let mut config = AfkConfig::load(config_path)?;
@@ -86,11 +130,9 @@ pub fn import_impl(
 
     let prd_content = load_prd_file(input_path)?;
 
-    // Generate the prompt
-    let prompt = generate_prd_prompt(&prd_content, output)?;
-
     // If any output flag is specified, output the prompt for manual use
     if copy || file || stdout {
+        let prompt = generate_prd_prompt(&prd_content, output)?;
         let mode = get_effective_mode(copy, file, stdout, &config);
         output_prompt(&prompt, mode, &config)?;
 
@@ -103,19 +145,376 @@ pub fn import_impl(
     }
 
     // No output flags - run the AI CLI directly
-    // Ensure AI CLI is configured (first-run experience if needed)
-    if let Some(ai_cli) = ensure_ai_cli_configured(Some(&mut config), false) {
-        config.ai_cli = ai_cli;
-    } else {
-        return Err(ImportCommandError::NoTasks); // No AI CLI available
+    // Ensure AI CLI is configured (first-run experience if needed). An
+    // explicitly given config file is used as it is.
+    if config_path.is_none() {
+        if let Some(ai_cli) = ensure_ai_cli_configured(Some(&mut config), false) {
+            config.ai_cli = ai_cli;
+        } else {
+            return Err(ImportCommandError::NoTasks); // No AI CLI available
+        }
+    }
+
+    // Documents too big for one context window are imported a part at a
+    // time, split at their headings
+    let parts = split_document(&prd_content, chunk_size);
+    if parts.len() > 1 {
+        return import_parts(&config, &parts, output);
+    }
+
+    // The AI writes a draft, which only becomes the output once it passes
+    // the schema
+    let draft = draft_path(output, None);
+    let prompt = generate_prd_prompt(&prd_content, &draft.display().to_string())?;
+    let repaired = import_draft(&config, &prompt, &draft, output)?;
+    write_imported_tasks(&repaired, output)
+}
+
+/// Import a Trello board export into tasks, without the AI CLI.
+///
+/// Cards become tasks and checklists their acceptance criteria; each list's
+/// cards are pending, done or skipped per `statuses` (see
+/// [`TrelloBoardExport::into_stories`]).
+pub fn import_trello(
+    input_file: &str,
+    output: &str,
+    statuses: &[(String, ListStatus)],
+) -> ImportCommandResult {
+    let input_path = Path::new(input_file);
+    if !input_path.exists() {
+        return Err(ImportCommandError::FileNotFound(input_file.to_string()));
+    }
+    let json = load_prd_file(input_path)?;
+    let export =
+        TrelloBoardExport::parse(&json).map_err(ImportCommandError::InvalidTrelloExport)?;
+
+    let board = export.name.clone();
+    let prd = PrdDocument {
+        project: board.clone(),
+        user_stories: export.into_stories(statuses),
+        ..Default::default()
+    };
+    let done = prd.user_stories.iter().filter(|s| s.passes).count();
+    prd.save(Some(Path::new(output)))?;
+
+    println!(
+        "\x1b[32m✓\x1b[0m Imported {} card(s) from {} ({done} already done)",
+        prd.user_stories.len(),
+        if board.is_empty() {
+            "the board"
+        } else {
+            &board
+        }
+    );
+    println!("  Output: \x1b[36m{output}\x1b[0m");
+    println!();
+    println!("\x1b[2mStart working on tasks with:\x1b[0m");
+    println!("  \x1b[36mafk go\x1b[0m");
+    Ok(())
+}
+
+/// Where the AI CLI writes its tasks before they are checked:
+/// `tasks.draft.json` beside `tasks.json`, or `tasks.part2.draft.json` for
+/// the second part of a split document.
+fn draft_path(output: &str, part: Option<usize>) -> PathBuf {
+    let extension = match part {
+        Some(part) => format!("part{part}.draft.json"),
+        None => "draft.json".to_string(),
+    };
+    Path::new(output).with_extension(extension)
+}
+
+/// Run the AI CLI with the import prompt until its draft passes the tasks
+/// schema, repairing what can be repaired and sending the rest back.
+///
+/// Returns the repaired tasks and removes the draft; a draft still invalid
+/// after the last attempt is kept for inspection.
+fn import_draft(
+    config: &AfkConfig,
+    prompt: &str,
+    draft: &Path,
+    output: &str,
+) -> Result<RepairedTasks, ImportCommandError> {
+    let mut attempt_prompt = prompt.to_string();
+    let mut problems = Vec::new();
+    for attempt in 1..=IMPORT_ATTEMPTS {
+        if attempt > 1 {
+            println!();
+            println!(
+                "\x1b[2mAsking {} to fix them (attempt {attempt} of {IMPORT_ATTEMPTS})...\x1b[0m",
+                config.ai_cli.command
+            );
+        }
+        let _ = fs::remove_file(draft);
+        run_import_cli(config, &attempt_prompt)?;
+
+        let repaired = match fs::read_to_string(draft) {
+            Ok(text) => repair_tasks_json(&text),
+            Err(_) => Err(vec![format!("no file was written to {}", draft.display())]),
+        };
+        match repaired {
+            Ok(repaired) => {
+                let _ = fs::remove_file(draft);
+                return Ok(repaired);
+            }
+            Err(found) => problems = found,
+        }
+
+        println!();
+        println!(
+            "\x1b[33mWarning:\x1b[0m The imported tasks have {} problem(s):",
+            problems.len()
+        );
+        for problem in &problems {
+            println!("  {problem}");
+        }
+        attempt_prompt = retry_prompt(prompt, draft, &problems);
+    }
+
+    eprintln!(
+        "\x1b[2mThe last draft was kept at {}; {output} was not changed.\x1b[0m",
+        draft.display()
+    );
+    Err(ImportCommandError::InvalidOutput(problems.join("; ")))
+}
+
+/// The import prompt with the problems in the last draft appended.
+fn retry_prompt(prompt: &str, draft: &Path, problems: &[String]) -> String {
+    let draft = draft.display();
+    let mut retry = format!(
+        "{prompt}\n\n---\n\n## Fix Your Previous Output\n\n\
+         The JSON written to `{draft}` was rejected:\n\n"
+    );
+    for problem in problems {
+        retry.push_str(&format!("- {problem}\n"));
+    }
+    retry.push_str(&format!(
+        "\nWrite the corrected JSON to `{draft}`, following the output format above.\n"
+    ));
+    retry
+}
+
+/// Import a split document a part at a time, then merge the parts' tasks.
+///
+/// Nothing is written unless every part imports.
+fn import_parts(config: &AfkConfig, parts: &[String], output: &str) -> ImportCommandResult {
+    let total = parts.len();
+    println!(
+        "\x1b[2mThe document is large; importing it in {total} parts split at its headings.\x1b[0m"
+    );
+
+    let mut imported = Vec::with_capacity(total);
+    for (i, content) in parts.iter().enumerate() {
+        let part = i + 1;
+        println!();
+        println!("\x1b[1mPart {part} of {total}\x1b[0m");
+        let draft = draft_path(output, Some(part));
+        let prompt = generate_prd_prompt(content, &draft.display().to_string())?;
+        let prompt = format!(
+            "{prompt}\n\n---\n\n## Part {part} of {total}\n\n\
+             The PRD above is one part of a larger document, split at its headings. \
+             Write stories for this part only; the other parts are imported separately.\n"
+        );
+        imported.push(import_draft(config, &prompt, &draft, output)?);
+    }
+
+    let (merged, merges) = merge_parts(imported, &config.dedup);
+    println!();
+    for merge in &merges {
+        println!(
+            "\x1b[2mMerged {} into {} (overlapping story)\x1b[0m",
+            merge.duplicate_id, merge.id
+        );
+    }
+    write_imported_tasks(&merged, output)
+}
+
+/// Split a requirements document at its headings into parts of at most
+/// `max_chars` characters (0 for no limit).
+///
+/// A part starting partway through a `##` section repeats that section's
+/// heading, so its stories keep their epic. A section too big for one part is split at
+/// blank lines.
+fn split_document(content: &str, max_chars: usize) -> Vec<String> {
+    if max_chars == 0 || content.chars().count() <= max_chars {
+        return vec![content.to_string()];
+    }
+
+    // Sections start at each heading outside code fences, remembering the
+    // `##` section they belong to
+    let mut sections: Vec<(String, Option<String>)> = Vec::new();
+    let mut current = String::new();
+    let mut epic: Option<String> = None;
+    let mut current_epic: Option<String> = None;
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let level = trimmed.chars().take_while(|&c| c == '#').count();
+        let heading = !in_fence && level > 0 && trimmed[level..].starts_with(' ');
+        if heading {
+            if !current.trim().is_empty() {
+                sections.push((std::mem::take(&mut current), current_epic.clone()));
+            }
+            current.clear();
+            match level {
+                1 => epic = None,
+                2 => epic = Some(line.to_string()),
+                _ => {}
+            }
+            current_epic = epic.clone();
+        }
+        current.push_str(line);
+        current.push('\n');
+    }
+    if !current.trim().is_empty() {
+        sections.push((current, current_epic));
+    }
+
+    // Split oversized sections at blank lines
+    let mut pieces: Vec<(String, Option<String>)> = Vec::new();
+    for (section, epic) in sections {
+        if section.chars().count() <= max_chars {
+            pieces.push((section, epic));
+            continue;
+        }
+        let mut piece = String::new();
+        for paragraph in section.split_inclusive("\n\n") {
+            if !piece.is_empty() && piece.chars().count() + paragraph.chars().count() > max_chars {
+                pieces.push((std::mem::take(&mut piece), epic.clone()));
+            }
+            piece.push_str(paragraph);
+        }
+        if !piece.trim().is_empty() {
+            pieces.push((piece, epic.clone()));
+        }
+    }
+
+    // Pack pieces into parts
+    let mut parts: Vec<String> = Vec::new();
+    let mut part = String::new();
+    for (piece, epic) in pieces {
+        if !part.is_empty() && part.chars().count() + piece.chars().count() > max_chars {
+            parts.push(std::mem::take(&mut part));
+        }
+        if part.is_empty() {
+            if let Some(epic) = epic.filter(|epic| !piece.starts_with(epic.as_str())) {
+                part.push_str(&epic);
+                part.push_str("\n\n");
+            }
+        }
+        part.push_str(&piece);
+    }
+    if !part.trim().is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// Merge the tasks imported from each part of a document.
+///
+/// Story IDs get a `p<part>-` prefix so parts can't collide and re-imports
+/// give the same IDs, with each part's dependencies renamed to match.
+/// Stories from different parts whose titles are alike (per `dedup`) are
+/// folded together, as sync does for sources.
+fn merge_parts(
+    parts: Vec<RepairedTasks>,
+    dedup_config: &DedupConfig,
+) -> (RepairedTasks, Vec<TaskMerge>) {
+    let mut merged = PrdDocument::default();
+    let mut fixes = Vec::new();
+    let mut stories = Vec::new();
+    let mut sources = HashMap::new();
+    for (i, part) in parts.into_iter().enumerate() {
+        let number = i + 1;
+        let doc = PrdDocument::from_json_value(&part.document);
+        if merged.project.is_empty() {
+            merged.project = doc.project;
+        }
+        if merged.branch_name.is_empty() {
+            merged.branch_name = doc.branch_name;
+        }
+        if merged.description.is_empty() {
+            merged.description = doc.description;
+        }
+        fixes.extend(
+            part.fixes
+                .into_iter()
+                .map(|fix| format!("part {number}: {fix}")),
+        );
+
+        let ids: HashMap<String, String> = doc
+            .user_stories
+            .iter()
+            .map(|s| (s.id.clone(), format!("p{number}-{}", s.id)))
+            .collect();
+        for mut story in doc.user_stories {
+            story.id = ids[&story.id].clone();
+            for dep in &mut story.depends_on {
+                if let Some(id) = ids.get(dep) {
+                    *dep = id.clone();
+                }
+            }
+            // A source per part, so only stories from different parts merge
+            sources.insert(story.id.clone(), std::mem::take(&mut story.source));
+            story.source = format!("import-part{number}");
+            stories.push(story);
+        }
     }
 
-    // Run the AI CLI with the prompt
-    run_ai_cli_for_import(&config, &prompt, output)
+    let deduped = dedup(stories, &[], dedup_config, &mut SourceMap::default());
+    let renamed: HashMap<&str, &str> = deduped
+        .merges
+        .iter()
+        .map(|m| (m.duplicate_id.as_str(), m.id.as_str()))
+        .collect();
+    merged.user_stories = deduped.stories;
+    for story in &mut merged.user_stories {
+        story.source = sources.remove(&story.id).unwrap_or_default();
+        let mut deps = Vec::new();
+        for dep in story.depends_on.drain(..) {
+            let dep = renamed
+                .get(dep.as_str())
+                .map_or(dep.clone(), |id| id.to_string());
+            if dep != story.id && !deps.contains(&dep) {
+                deps.push(dep);
+            }
+        }
+        story.depends_on = deps;
+    }
+
+    let document = serde_json::to_value(&merged).expect("tasks serialize to JSON");
+    (RepairedTasks { document, fixes }, deduped.merges)
+}
+
+/// Write validated tasks to `output`.
+fn write_imported_tasks(repaired: &RepairedTasks, output: &str) -> ImportCommandResult {
+    let output_path = Path::new(output);
+    let write = || -> std::io::Result<()> {
+        if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&repaired.document).map_err(io::Error::other)?;
+        fs::write(output_path, json + "\n")
+    };
+    write().map_err(|e| ImportCommandError::ImportError(PrdError::ReadError(e)))?;
+
+    println!();
+    for fix in &repaired.fixes {
+        println!("\x1b[2mRepaired: {fix}\x1b[0m");
+    }
+    println!("\x1b[32m✓\x1b[0m Requirements imported successfully");
+    println!("  Output: \x1b[36m{output}\x1b[0m");
+    println!();
+    println!("\x1b[2mStart working on tasks with:\x1b[0m");
+    println!("  \x1b[36mafk go\x1b[0m");
+    Ok(())
 }
 
-/// Run the AI CLI with the import prompt.
-fn run_ai_cli_for_import(config: &AfkConfig, prompt: &str, output: &str) -> ImportCommandResult {
+/// Run the AI CLI once with `prompt`, streaming its output.
+fn run_import_cli(config: &AfkConfig, prompt: &str) -> ImportCommandResult {
     let command = &config.ai_cli.command;
     let args: Vec<&str> = config.ai_cli.args.iter().map(|s| s.as_str()).collect();
 
@@ -126,7 +525,7 @@ fn run_ai_cli_for_import(config: &AfkConfig, prompt: &str, output: &str) -> Impo
     )));
 
     // Build the command
-    let mut cmd = Command::new(command);
+    let mut cmd = program_command(command);
     cmd.args(&args)
         .arg(prompt)
         .stdin(Stdio::null())
@@ -194,21 +593,6 @@ fn run_ai_cli_for_import(config: &AfkConfig, prompt: &str, output: &str) -> Impo
         }
     }
 
-    // Check if output file was created
-    let output_path = Path::new(output);
-    if output_path.exists() {
-        println!();
-        println!("\x1b[32m✓\x1b[0m Requirements imported successfully");
-        println!("  Output: \x1b[36m{output}\x1b[0m");
-        println!();
-        println!("\x1b[2mStart working on tasks with:\x1b[0m");
-        println!("  \x1b[36mafk go\x1b[0m");
-    } else {
-        println!();
-        println!("\x1b[33mNote:\x1b[0m Output file not found at {output}");
-        println!("\x1b[2mThe AI may have written to a different location.\x1b[0m");
-    }
-
     Ok(())
 }
 
@@ -220,15 +604,55 @@ fn run_ai_cli_for_import(config: &AfkConfig, prompt: &str, output: &str) -> Impo
 /// # Returns
 ///
 /// Ok(()) on success, or an error if sync fails.
-pub fn tasks_sync() -> ImportCommandResult {
-    tasks_sync_impl(None, None)
+pub fn tasks_sync(source: Option<&str>, dry_run: bool) -> ImportCommandResult {
+    tasks_sync_filtered_impl(None, None, source, dry_run)
 }
 
 /// Internal implementation of tasks_sync with optional paths for testing.
 pub fn tasks_sync_impl(config_path: Option<&Path>, root: Option<&Path>) -> ImportCommandResult {
+    tasks_sync_filtered_impl(config_path, root, None, false)
+}
+
+/// Sync all sources or just one, optionally as a dry run.
+fn tasks_sync_filtered_impl(
+    config_path: Option<&Path>,
+    root: Option<&Path>,
+    source: Option<&str>,
+    dry_run: bool,
+) -> ImportCommandResult {
     let config = AfkConfig::load(config_path)?;
+    let resolve = conflict_resolver(config.runner.sync_conflicts);
 
-    let prd = sync_prd_with_root(&config, None, root)?;
+    // Without sources, tasks.json is the source of truth
+    let prd = if config.sources.is_empty() && !dry_run {
+        sync_prd_resolving(&config, None, root, resolve)?
+    } else {
+        let sources = match source {
+            Some(selector) => select_sources(&config.sources, selector)?,
+            None => config.sources.clone(),
+        };
+        let mut plan = plan_sync(&sources, &config.dedup, None, root)?;
+        if dry_run {
+            let progress_path = root.map(|r| r.join(PROGRESS_FILE));
+            let progress = SessionProgress::load(progress_path.as_deref()).unwrap_or_default();
+            let conflicts = detect_conflicts(&plan, &progress, config.github.sync_back);
+            print_sync_plan(&plan, &sources, &conflicts);
+            return Ok(());
+        }
+        resolve_conflicts(&mut plan, root, config.github.sync_back, resolve)?;
+        plan.save(root)?;
+        if source.is_some() {
+            println!(
+                "\x1b[2mSynced {} of {} sources: {} added, {} updated\x1b[0m",
+                sources.len(),
+                config.sources.len(),
+                plan.added.len(),
+                plan.updated.len()
+            );
+        }
+        print_duplicates(&plan);
+        plan.prd
+    };
 
     // Calculate counts
     let (completed, total) = prd.get_story_counts();
@@ -249,6 +673,172 @@ pub fn tasks_sync_impl(config_path: Option<&Path>, root: Option<&Path>) -> Impor
     Ok(())
 }
 
+/// Settle sync conflicts by asking at a terminal, or by `policy` otherwise.
+fn conflict_resolver(
+    policy: SyncConflictPolicy,
+) -> impl FnMut(&SyncConflict) -> SyncConflictPolicy {
+    let interactive = io::stdin().is_terminal();
+    move |conflict| {
+        println!(
+            "\x1b[33m!\x1b[0m Sync conflict: \x1b[1m{}\x1b[0m {} \x1b[2m({})\x1b[0m",
+            conflict.id,
+            conflict.title,
+            conflict.describe()
+        );
+        let resolution = if interactive {
+            ask_resolution(policy)
+        } else {
+            policy
+        };
+        println!("  \x1b[2m→ {}\x1b[0m", resolution_label(resolution));
+        resolution
+    }
+}
+
+/// Prompt until a valid resolution is entered; Enter or end of input takes
+/// the configured policy.
+fn ask_resolution(policy: SyncConflictPolicy) -> SyncConflictPolicy {
+    loop {
+        print!(
+            "  Keep [l]ocal, take [r]emote, or mark for re[v]iew? [{}]: ",
+            resolution_label(policy)
+        );
+        let _ = io::stdout().flush();
+        let mut input = String::new();
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => return policy,
+            Ok(_) if input.trim().is_empty() => return policy,
+            Ok(_) => {
+                if let Some(resolution) = parse_resolution(&input) {
+                    return resolution;
+                }
+            }
+        }
+    }
+}
+
+/// Parse a conflict resolution typed at the prompt.
+fn parse_resolution(input: &str) -> Option<SyncConflictPolicy> {
+    match input.trim().to_lowercase().as_str() {
+        "l" | "local" | "keep-local" => Some(SyncConflictPolicy::KeepLocal),
+        "r" | "remote" | "take-remote" => Some(SyncConflictPolicy::TakeRemote),
+        "v" | "review" => Some(SyncConflictPolicy::Review),
+        _ => None,
+    }
+}
+
+/// Name of a resolution, as used in config.
+fn resolution_label(resolution: SyncConflictPolicy) -> &'static str {
+    match resolution {
+        SyncConflictPolicy::KeepLocal => "keep-local",
+        SyncConflictPolicy::TakeRemote => "take-remote",
+        SyncConflictPolicy::Review => "review",
+    }
+}
+
+/// Pick sources by 1-based index (as in `afk source list`) or by name.
+///
+/// A name matches a source's type (e.g. `github`, selecting every GitHub
+/// source) or its path, repository, database, board, or project.
+fn select_sources(
+    sources: &[SourceConfig],
+    selector: &str,
+) -> Result<Vec<SourceConfig>, ImportCommandError> {
+    let unknown = || ImportCommandError::UnknownSource(selector.to_string());
+    if let Ok(index) = selector.parse::<usize>() {
+        return index
+            .checked_sub(1)
+            .and_then(|i| sources.get(i))
+            .map(|s| vec![s.clone()])
+            .ok_or_else(unknown);
+    }
+
+    let matches: Vec<SourceConfig> = sources
+        .iter()
+        .filter(|s| {
+            source_type_to_str(&s.source_type).eq_ignore_ascii_case(selector)
+                || [&s.path, &s.repo, &s.database, &s.board, &s.project]
+                    .iter()
+                    .any(|v| v.as_deref() == Some(selector))
+        })
+        .cloned()
+        .collect();
+    if matches.is_empty() {
+        Err(unknown())
+    } else {
+        Ok(matches)
+    }
+}
+
+/// Print what a sync would add, update, and remove.
+fn print_sync_plan(plan: &SyncPlan, sources: &[SourceConfig], conflicts: &[SyncConflict]) {
+    let names: Vec<&str> = sources
+        .iter()
+        .map(|s| source_type_to_str(&s.source_type))
+        .collect();
+    println!(
+        "\x1b[1mDry run\x1b[0m - syncing {} would change .afk/tasks.json:",
+        names.join(", ")
+    );
+    if plan.is_empty() && conflicts.is_empty() {
+        println!("  \x1b[2mNo changes\x1b[0m");
+        return;
+    }
+
+    let title = |id: &str| {
+        plan.prd
+            .get_story(id)
+            .map(|s| s.title.clone())
+            .unwrap_or_default()
+    };
+    for id in &plan.added {
+        println!("  \x1b[32m+ {id}\x1b[0m  {}", title(id));
+    }
+    for id in &plan.updated {
+        println!("  \x1b[33m~ {id}\x1b[0m  {}", title(id));
+    }
+    for id in &plan.removed {
+        println!(
+            "  \x1b[31m- {id}\x1b[0m  {} \x1b[2m(gone from source; pending work is kept)\x1b[0m",
+            title(id)
+        );
+    }
+    for conflict in conflicts {
+        println!(
+            "  \x1b[35m! {}\x1b[0m  {} \x1b[2m({}; will ask or apply runner.sync_conflicts)\x1b[0m",
+            conflict.id,
+            conflict.title,
+            conflict.describe()
+        );
+    }
+    print_duplicates(plan);
+    println!();
+    println!(
+        "  {} added, {} updated, {} removed",
+        plan.added.len(),
+        plan.updated.len(),
+        plan.removed.len()
+    );
+}
+
+/// Report tasks merged across sources, and where sources disagree.
+fn print_duplicates(plan: &SyncPlan) {
+    for merge in &plan.merges {
+        let why = match merge.reason {
+            MatchReason::Remembered => "merged before",
+            MatchReason::Id => "same ID",
+            MatchReason::Title => "same title",
+        };
+        println!(
+            "  \x1b[36m= {}\x1b[0m  also {} in {} \x1b[2m({why})\x1b[0m",
+            merge.id, merge.duplicate_id, merge.duplicate_source
+        );
+    }
+    for conflict in &plan.duplicates {
+        println!("  \x1b[33m! {}\x1b[0m  {}", conflict.id, conflict.detail);
+    }
+}
+
 /// Show the current task list.
 ///
 /// Displays tasks from .afk/tasks.json with their completion status.
@@ -264,26 +854,33 @@ pub fn tasks_show(pending_only: bool, complete_only: bool, limit: usize) -> Impo
     tasks_show_impl(pending_only, complete_only, limit, None)
 }
 
-/// Internal implementation of tasks_show with optional path for testing.
-pub fn tasks_show_impl(
+/// Execute the tasks command with --json.
+pub fn tasks_json(pending_only: bool, complete_only: bool, limit: usize) -> ImportCommandResult {
+    tasks_json_impl(pending_only, complete_only, limit, None)
+}
+
+/// Internal implementation of tasks_json with optional path for testing.
+pub fn tasks_json_impl(
     pending_only: bool,
     complete_only: bool,
     limit: usize,
     tasks_path: Option<&Path>,
 ) -> ImportCommandResult {
     let prd = PrdDocument::load(tasks_path)?;
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let tasks = filter_tasks(&prd, pending_only, complete_only, limit);
+    json::print(&TasksJson::new(&prd, &tasks, &progress));
+    Ok(())
+}
 
-    if prd.user_stories.is_empty() {
-        println!("\x1b[2mNo tasks found.\x1b[0m");
-        println!();
-        println!("Run \x1b[36mafk tasks sync\x1b[0m to aggregate from sources,");
-        println!("or \x1b[36mafk import <file>\x1b[0m to import a requirements doc.");
-        return Ok(());
-    }
-
-    // Filter tasks based on flags
-    let tasks: Vec<_> = prd
-        .user_stories
+/// Stories to list for the --pending/--complete flags, up to `limit`.
+fn filter_tasks(
+    prd: &PrdDocument,
+    pending_only: bool,
+    complete_only: bool,
+    limit: usize,
+) -> Vec<&UserStory> {
+    prd.user_stories
         .iter()
         .filter(|s| {
             if pending_only && complete_only {
@@ -297,7 +894,27 @@ pub fn tasks_show_impl(
             }
         })
         .take(limit)
-        .collect();
+        .collect()
+}
+
+/// Internal implementation of tasks_show with optional path for testing.
+pub fn tasks_show_impl(
+    pending_only: bool,
+    complete_only: bool,
+    limit: usize,
+    tasks_path: Option<&Path>,
+) -> ImportCommandResult {
+    let prd = PrdDocument::load(tasks_path)?;
+
+    if prd.user_stories.is_empty() {
+        println!("\x1b[2mNo tasks found.\x1b[0m");
+        println!();
+        println!("Run \x1b[36mafk tasks sync\x1b[0m to aggregate from sources,");
+        println!("or \x1b[36mafk import <file>\x1b[0m to import a requirements doc.");
+        return Ok(());
+    }
+
+    let tasks = filter_tasks(&prd, pending_only, complete_only, limit);
 
     if tasks.is_empty() && pending_only {
         println!("\x1b[32m✓ All tasks complete!\x1b[0m");
@@ -311,34 +928,28 @@ pub fn tasks_show_impl(
     );
     println!("{}", "─".repeat(80));
 
-    // Print each task
-    for task in &tasks {
-        let status = if task.passes {
-            "\x1b[32m✓ pass\x1b[0m"
-        } else {
-            "\x1b[33m○ pending\x1b[0m"
-        };
-
-        // Truncate title if too long
-        let title = if task.title.len() > 38 {
-            format!("{}…", &task.title[..37])
-        } else {
-            task.title.clone()
-        };
-
-        // Truncate ID if too long
-        let id = if task.id.len() > 18 {
-            format!("{}…", &task.id[..17])
-        } else {
-            task.id.clone()
-        };
-
-        let ac_count = task.acceptance_criteria.len();
-
-        println!(
-            "{:<20} {:>3} {:<40} {:>3} {}",
-            id, task.priority, title, ac_count, status
-        );
+    // Group by epic when the tasks have any
+    if tasks.iter().any(|t| t.epic.is_some()) {
+        for (epic, group) in group_by_epic(tasks.iter().copied()) {
+            let in_epic: Vec<&UserStory> = prd
+                .user_stories
+                .iter()
+                .filter(|s| s.epic.as_deref() == epic)
+                .collect();
+            let done = in_epic.iter().filter(|s| s.passes).count();
+            println!(
+                "\x1b[1;36m▸ {}\x1b[0m \x1b[2m({done}/{} complete)\x1b[0m",
+                epic.unwrap_or("No epic"),
+                in_epic.len()
+            );
+            for task in group {
+                print_task_row(task);
+            }
+        }
+    } else {
+        for task in &tasks {
+            print_task_row(task);
+        }
     }
 
     // Print footer with summary
@@ -369,6 +980,108 @@ pub fn tasks_show_impl(
     Ok(())
 }
 
+/// One row of the `afk tasks` table.
+fn print_task_row(task: &UserStory) {
+    let status = if task.passes {
+        "\x1b[32m✓ pass\x1b[0m"
+    } else {
+        "\x1b[33m○ pending\x1b[0m"
+    };
+
+    // Truncate title if too long
+    let title = if task.title.len() > 38 {
+        format!("{}…", &task.title[..37])
+    } else {
+        task.title.clone()
+    };
+
+    // Truncate ID if too long
+    let id = if task.id.len() > 18 {
+        format!("{}…", &task.id[..17])
+    } else {
+        task.id.clone()
+    };
+
+    let ac_count = task.acceptance_criteria.len();
+
+    println!(
+        "{:<20} {:>3} {:<40} {:>3} {}",
+        id, task.priority, title, ac_count, status
+    );
+}
+
+/// Rank pending tasks by estimated chance of autonomous completion.
+///
+/// Shows the factors behind each score: size, past failures, and pending
+/// dependencies.
+pub fn tasks_scores(limit: usize) -> ImportCommandResult {
+    let config = AfkConfig::load(None).unwrap_or_default();
+    tasks_scores_impl(limit, None, &TaskOrder::load(&config))
+}
+
+/// Internal implementation of tasks_scores with optional path for testing.
+pub fn tasks_scores_impl(
+    limit: usize,
+    tasks_path: Option<&Path>,
+    order: &TaskOrder,
+) -> ImportCommandResult {
+    let prd = PrdDocument::load(tasks_path)?;
+    let pending = prd.get_pending_stories_aged(order.aging.as_ref(), Local::now().naive_local());
+    if pending.is_empty() {
+        println!("\x1b[32m✓ No pending tasks.\x1b[0m");
+        return Ok(());
+    }
+
+    println!(
+        "\x1b[1m{:<20} {:>5} {:>3} {:>5} {:>7}  Blocked by\x1b[0m",
+        "ID", "Odds", "Pri", "Size", "History"
+    );
+    println!("{}", "─".repeat(80));
+
+    let history = &order.history;
+    for story in order_by_probability(pending, &prd, history)
+        .into_iter()
+        .take(limit)
+    {
+        let past = history.get(&story.id).copied().unwrap_or_default();
+        let score = completion_score(story, &prd, past);
+        let id = if story.id.len() > 18 {
+            format!("{}…", &story.id[..17])
+        } else {
+            story.id.clone()
+        };
+        let odds = format!("{:.0}%", score.probability * 100.0);
+        let colour = if score.probability >= 0.7 {
+            "32"
+        } else if score.probability >= 0.4 {
+            "33"
+        } else {
+            "31"
+        };
+        println!(
+            "{:<20} \x1b[{colour}m{odds:>5}\x1b[0m {:>3} {:>5.2} {:>7.2}  {}",
+            id,
+            story.priority,
+            score.size,
+            score.history,
+            score.blocked_by.join(", ")
+        );
+    }
+
+    println!("{}", "─".repeat(80));
+    let hint = match order.ordering {
+        TaskOrdering::Probability => {
+            "afk go picks tasks in this order (runner.ordering = probability)"
+        }
+        TaskOrdering::Priority => {
+            "afk go picks by priority; set runner.ordering = probability to use this order"
+        }
+    };
+    println!("\x1b[2m{hint}\x1b[0m");
+
+    Ok(())
+}
+
 /// Format an ISO timestamp for display.
 fn format_timestamp(ts: &str) -> String {
     // Try to parse and reformat, or return as-is if it fails
@@ -385,6 +1098,7 @@ fn format_timestamp(ts: &str) -> String {
 mod tests {
     use super::*;
     use crate::config::{OutputMode, SourceConfig};
+    use crate::prd::scoring::TaskHistory;
     use crate::prd::UserStory;
     use std::fs;
     use tempfile::TempDir;
@@ -397,6 +1111,22 @@ mod tests {
         (temp, afk_dir)
     }
 
+    #[test]
+    fn test_parse_resolution() {
+        assert_eq!(parse_resolution("l"), Some(SyncConflictPolicy::KeepLocal));
+        assert_eq!(
+            parse_resolution(" Remote\n"),
+            Some(SyncConflictPolicy::TakeRemote)
+        );
+        assert_eq!(parse_resolution("v"), Some(SyncConflictPolicy::Review));
+        assert_eq!(parse_resolution("review"), Some(SyncConflictPolicy::Review));
+        assert_eq!(parse_resolution("x"), None);
+        assert_eq!(
+            resolution_label(SyncConflictPolicy::TakeRemote),
+            "take-remote"
+        );
+    }
+
     #[test]
     fn test_tasks_sync_no_sources_empty_tasks() {
         let (temp, afk_dir) = setup_temp_dir();
@@ -495,7 +1225,7 @@ mod tests {
                     title: "First Story".to_string(),
                     priority: 1,
                     passes: true,
-                    acceptance_criteria: vec!["AC1".to_string(), "AC2".to_string()],
+                    acceptance_criteria: vec!["AC1".into(), "AC2".into()],
                     ..Default::default()
                 },
                 UserStory {
@@ -503,7 +1233,7 @@ mod tests {
                     title: "Second Story".to_string(),
                     priority: 2,
                     passes: false,
-                    acceptance_criteria: vec!["AC3".to_string()],
+                    acceptance_criteria: vec!["AC3".into()],
                     ..Default::default()
                 },
             ],
@@ -516,6 +1246,36 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_tasks_scores() {
+        let (_temp, afk_dir) = setup_temp_dir();
+        let tasks_path = afk_dir.join("tasks.json");
+
+        let mut blocked = UserStory::new("blocked", "Needs story-1");
+        blocked.depends_on = vec!["story-1".to_string()];
+        let prd = PrdDocument {
+            user_stories: vec![UserStory::new("story-1", "First"), blocked],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let order = TaskOrder {
+            history: HashMap::from([(
+                "story-1".to_string(),
+                TaskHistory {
+                    failures: 1,
+                    attempts: 2,
+                },
+            )]),
+            ..Default::default()
+        };
+        assert!(tasks_scores_impl(50, Some(&tasks_path), &order).is_ok());
+
+        let empty = PrdDocument::default();
+        empty.save(Some(&tasks_path)).unwrap();
+        assert!(tasks_scores_impl(50, Some(&tasks_path), &TaskOrder::default()).is_ok());
+    }
+
     #[test]
     fn test_tasks_show_pending_only() {
         let (_temp, afk_dir) = setup_temp_dir();
@@ -713,6 +1473,7 @@ mod tests {
             false,
             false,
             true,
+            0,
             Some(&config_path),
         );
 
@@ -744,6 +1505,7 @@ mod tests {
             false,
             false,
             true,
+            0,
             Some(&config_path),
         );
 
@@ -770,6 +1532,7 @@ mod tests {
             false,
             false,
             true,
+            0,
             Some(&config_path),
         );
 
@@ -785,6 +1548,148 @@ mod tests {
 
         let no_tasks = ImportCommandError::NoTasks;
         assert!(no_tasks.to_string().contains("No tasks found"));
+
+        let unknown = ImportCommandError::UnknownSource("jira".to_string());
+        assert_eq!(
+            unknown.to_string(),
+            "No source matches 'jira'. Run `afk source list` to see sources."
+        );
+
+        let invalid = ImportCommandError::InvalidOutput("$.userStories: no stories found".into());
+        assert_eq!(
+            invalid.to_string(),
+            "Imported tasks are invalid: $.userStories: no stories found"
+        );
+    }
+
+    #[test]
+    fn test_import_draft_and_retry_prompt() {
+        let draft = draft_path(".afk/tasks.json", None);
+        assert_eq!(draft, Path::new(".afk/tasks.draft.json"));
+        assert_eq!(draft_path("out", None), Path::new("out.draft.json"));
+        assert_eq!(
+            draft_path(".afk/tasks.json", Some(2)),
+            Path::new(".afk/tasks.part2.draft.json")
+        );
+
+        let retry = retry_prompt(
+            "Convert the PRD.",
+            &draft,
+            &["$.userStories[0]: missing required property 'id'".to_string()],
+        );
+        assert!(retry.starts_with("Convert the PRD.\n\n---\n\n## Fix Your Previous Output"));
+        assert!(retry.contains("- $.userStories[0]: missing required property 'id'\n"));
+        assert!(retry.ends_with("Write the corrected JSON to `.afk/tasks.draft.json`, following the output format above.\n"));
+    }
+
+    #[test]
+    fn test_split_document_at_headings() {
+        let doc = "# Shop\n\nAn online shop.\n\n## Cart\n\nAdd items.\n\n### Checkout\n\n\
+                   Pay for items.\n\n```md\n# not a heading\n```\n\n## Accounts\n\nSign up.\n";
+        assert_eq!(split_document(doc, 0), [doc]);
+        assert_eq!(split_document(doc, doc.len()), [doc]);
+
+        let parts = split_document(doc, 40);
+        assert_eq!(
+            parts,
+            [
+                "# Shop\n\nAn online shop.\n\n",
+                "## Cart\n\nAdd items.\n\n",
+                "## Cart\n\n### Checkout\n\nPay for items.\n\n",
+                "## Cart\n\n```md\n# not a heading\n```\n\n",
+                "## Accounts\n\nSign up.\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_parts_prefixes_ids_and_folds_duplicates() {
+        let part = |json: serde_json::Value| RepairedTasks {
+            document: json,
+            fixes: vec!["removed trailing commas".to_string()],
+        };
+        let (merged, merges) = merge_parts(
+            vec![
+                part(serde_json::json!({"project": "shop", "userStories": [
+                    {"id": "US-001", "title": "Add cart model", "acceptanceCriteria": ["Cart saves"]},
+                    {"id": "US-002", "title": "Show cart", "dependsOn": ["US-001"]},
+                ]})),
+                part(serde_json::json!({"project": "other", "userStories": [
+                    {"id": "US-001", "title": "Add cart models", "acceptanceCriteria": ["Cart empties"]},
+                    {"id": "US-002", "title": "Checkout", "dependsOn": ["US-001"]},
+                ]})),
+            ],
+            &DedupConfig::default(),
+        );
+
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].duplicate_id, "p2-US-001");
+        assert_eq!(merges[0].id, "p1-US-001");
+        assert_eq!(
+            merged.fixes,
+            [
+                "part 1: removed trailing commas",
+                "part 2: removed trailing commas"
+            ]
+        );
+
+        let doc = PrdDocument::from_json_value(&merged.document);
+        assert_eq!(doc.project, "shop");
+        let ids: Vec<&str> = doc.user_stories.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["p1-US-001", "p1-US-002", "p2-US-002"]);
+        assert_eq!(doc.user_stories[0].acceptance_criteria.len(), 2);
+        assert_eq!(doc.user_stories[1].depends_on, ["p1-US-001"]);
+        assert_eq!(doc.user_stories[2].depends_on, ["p1-US-001"]);
+    }
+
+    #[test]
+    fn test_select_sources() {
+        let sources = vec![
+            SourceConfig::markdown("TODO.md"),
+            SourceConfig::github("acme/api", Vec::new()),
+            SourceConfig::github("acme/web", Vec::new()),
+        ];
+
+        assert_eq!(
+            select_sources(&sources, "1").unwrap(),
+            vec![sources[0].clone()]
+        );
+        assert_eq!(select_sources(&sources, "TODO.md").unwrap().len(), 1);
+        assert_eq!(
+            select_sources(&sources, "acme/web").unwrap(),
+            vec![sources[2].clone()]
+        );
+        assert_eq!(select_sources(&sources, "GitHub").unwrap().len(), 2);
+        assert!(select_sources(&sources, "0").is_err());
+        assert!(select_sources(&sources, "4").is_err());
+        assert!(select_sources(&sources, "trello").is_err());
+    }
+
+    #[test]
+    fn test_tasks_sync_single_source_keeps_others() {
+        let (temp, afk_dir) = setup_temp_dir();
+        let config_path = afk_dir.join("config.json");
+        let first = temp.path().join("first.json");
+        let second = temp.path().join("second.json");
+        fs::write(&first, r#"[{"id": "one", "title": "One"}]"#).unwrap();
+        fs::write(&second, r#"[{"id": "two", "title": "Two"}]"#).unwrap();
+        let config = AfkConfig {
+            sources: vec![
+                SourceConfig::json(first.to_str().unwrap()),
+                SourceConfig::json(second.to_str().unwrap()),
+            ],
+            ..Default::default()
+        };
+        config.save(Some(&config_path)).unwrap();
+
+        // Dry run writes nothing
+        tasks_sync_filtered_impl(Some(&config_path), Some(temp.path()), Some("2"), true).unwrap();
+        assert!(!afk_dir.join("tasks.json").exists());
+
+        tasks_sync_filtered_impl(Some(&config_path), Some(temp.path()), Some("2"), false).unwrap();
+        let prd = PrdDocument::load(Some(&afk_dir.join("tasks.json"))).unwrap();
+        assert_eq!(prd.user_stories.len(), 1);
+        assert_eq!(prd.user_stories[0].id, "two");
     }
 
     #[test]
@@ -836,6 +1741,7 @@ mod tests {
             false, // copy
             false, // file
             false, // stdout - no output flags!
+            0,
             Some(&config_path),
         );
 
@@ -863,6 +1769,7 @@ mod tests {
             true, // copy - output flag set
             false,
             false,
+            0,
             Some(&config_path),
         );
 
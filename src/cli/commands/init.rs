@@ -5,9 +5,11 @@
 use std::fs;
 use std::path::Path;
 
+use super::preset::{find_preset, PresetCommandError};
 use crate::bootstrap::{
     analyse_project, detect_ai_cli, ensure_ai_cli_configured, generate_config, infer_sources,
 };
+use crate::config::find_config_file;
 
 /// Result type for init command operations.
 pub type InitCommandResult = Result<(), InitCommandError>;
@@ -33,6 +35,9 @@ pub enum InitCommandError {
     /// No AI CLI tool is configured or available.
     #[error("No AI CLI configured")]
     NoAiCli,
+    /// The requested preset does not exist.
+    #[error("{0}")]
+    PresetError(#[from] PresetCommandError),
 }
 
 /// Options for the init command.
@@ -43,6 +48,8 @@ pub struct InitOptions {
     pub force: bool,
     /// Accept all defaults without prompting.
     pub yes: bool,
+    /// Curated preset to apply on top of auto-detection.
+    pub preset: Option<String>,
 }
 
 /// Check if the current directory is inside a .afk folder.
@@ -61,12 +68,15 @@ pub fn init(options: InitOptions) -> InitCommandResult {
     }
 
     let afk_dir = Path::new(".afk");
-    let config_path = afk_dir.join("config.json");
+    let existing_config = find_config_file(afk_dir);
+    let preset = options.preset.as_deref().map(find_preset).transpose()?;
 
     // Check if already initialised
-    if config_path.exists() && !options.force {
+    if existing_config.is_some() && !options.force {
         return Err(InitCommandError::AlreadyInitialised);
     }
+    // Re-initialising keeps the config in the format it was in
+    let config_path = existing_config.unwrap_or_else(|| afk_dir.join("config.json"));
 
     // Analyse project
     println!("\x1b[1mAnalysing project...\x1b[0m");
@@ -86,6 +96,13 @@ pub fn init(options: InitOptions) -> InitCommandResult {
     // Generate config
     let mut config = generate_config(&analysis);
     config.sources = infer_sources(None);
+    if let Some(preset) = preset {
+        println!(
+            "  Preset: \x1b[36m{}\x1b[0m ({})",
+            preset.name, preset.description
+        );
+        preset.apply(&mut config);
+    }
 
     // Handle AI CLI selection
     if options.dry_run {
@@ -119,6 +136,9 @@ pub fn init(options: InitOptions) -> InitCommandResult {
     if let Some(ref cmd) = config.feedback_loops.lint {
         println!("  Lint: {cmd}");
     }
+    if !config.prompt.context_files.is_empty() {
+        println!("  Context: {}", config.prompt.context_files.join(", "));
+    }
 
     // Dry run mode
     if options.dry_run {
@@ -175,6 +195,9 @@ mod tests {
 
         let err = InitCommandError::InsideAfkFolder;
         assert!(err.to_string().contains("Cannot init inside a .afk folder"));
+
+        let err = InitCommandError::from(PresetCommandError::unknown("cobol"));
+        assert!(err.to_string().starts_with("Unknown preset: cobol"));
     }
 
     #[test]
@@ -217,6 +240,7 @@ mod tests {
             dry_run: true,
             force: false,
             yes: false,
+            preset: None,
         });
 
         // Restore original directory before assertions
@@ -0,0 +1,129 @@
+//! Log command implementation.
+//!
+//! This module implements `afk log`, which prints the structured event log
+//! and can follow it as the loop appends records.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::EVENTS_FILE;
+use crate::events::EventRecord;
+
+/// How often `--follow` checks the log for new records.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Result type for log command operations.
+pub type LogCommandResult = Result<(), LogCommandError>;
+
+/// Error type for log command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum LogCommandError {
+    /// Failed to read the event log.
+    #[error("Failed to read event log: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Print the last `lines` events, then optionally follow the log.
+pub fn log(lines: usize, follow: bool, json: bool) -> LogCommandResult {
+    log_file(Path::new(EVENTS_FILE), lines, follow, json)
+}
+
+fn log_file(path: &Path, lines: usize, follow: bool, json: bool) -> LogCommandResult {
+    let mut offset = 0;
+    if path.exists() {
+        let contents = std::fs::read_to_string(path)?;
+        offset = contents.len() as u64;
+        for line in tail_lines(&contents, lines) {
+            println!("{}", format_line(line, json));
+        }
+    } else if !follow {
+        if !json {
+            println!("\x1b[2mNo events logged yet. Run `afk go` to start the loop.\x1b[0m");
+        }
+        return Ok(());
+    }
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        let Ok(file) = File::open(path) else {
+            continue;
+        };
+        let len = file.metadata()?.len();
+        if len < offset {
+            // Log was truncated or replaced; start over
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        // Only consume complete lines so a half-written record is read next time
+        while reader.read_line(&mut line)? > 0 && line.ends_with('\n') {
+            offset += line.len() as u64;
+            println!("{}", format_line(line.trim_end(), json));
+            line.clear();
+        }
+    }
+}
+
+/// The last `count` non-empty lines of the log.
+fn tail_lines(contents: &str, count: usize) -> Vec<&str> {
+    let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(count)..].to_vec()
+}
+
+/// Render one log line: raw for `--json`, otherwise time and summary.
+///
+/// Lines that aren't event records are printed as they are.
+fn format_line(line: &str, json: bool) -> String {
+    if json {
+        return line.to_string();
+    }
+    match serde_json::from_str::<EventRecord>(line) {
+        Ok(record) => {
+            let time = record.timestamp.get(11..19).unwrap_or(&record.timestamp);
+            format!("\x1b[2m{time}\x1b[0m {}", record.summary())
+        }
+        Err(_) => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail_lines() {
+        let contents = "a\nb\n\nc\n";
+        assert_eq!(tail_lines(contents, 2), vec!["b", "c"]);
+        assert_eq!(tail_lines(contents, 10), vec!["a", "b", "c"]);
+        assert!(tail_lines(contents, 0).is_empty());
+    }
+
+    #[test]
+    fn test_format_line() {
+        let line =
+            r#"{"timestamp":"2024-05-01T12:34:56.000000","event":"loop_start","max_iterations":5}"#;
+        assert_eq!(format_line(line, true), line);
+        assert_eq!(
+            format_line(line, false),
+            "\x1b[2m12:34:56\x1b[0m loop started (max 5 iterations)"
+        );
+        assert_eq!(format_line("not json", false), "not json");
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = LogCommandError::IoError(std::io::Error::other("denied"));
+        assert_eq!(err.to_string(), "Failed to read event log: denied");
+    }
+}
@@ -0,0 +1,167 @@
+//! Logs command implementation.
+//!
+//! This module implements `afk logs`, which lists the transcripts kept for
+//! past iterations and prints one, optionally following it while the
+//! iteration is still running.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::AfkConfig;
+use crate::progress::transcript::{
+    line_tag, list_logs, log_path, read_transcript, rotated_path, transcript_dir, TAG_EVENT,
+};
+
+/// How often `--follow` checks the transcript for new lines.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Result type for logs command operations.
+pub type LogsCommandResult = Result<(), LogsCommandError>;
+
+/// Error type for logs command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum LogsCommandError {
+    /// No transcript was kept for the iteration.
+    #[error("No transcript for iteration {0} (see 'afk logs' for those kept)")]
+    NotFound(u32),
+    /// Failed to read a transcript.
+    #[error("Failed to read transcript: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Options for `afk logs`.
+#[derive(Debug, Clone, Default)]
+pub struct LogsOptions {
+    /// Iteration to print; None lists the transcripts kept.
+    pub iteration: Option<u32>,
+    /// Keep printing lines as they are appended.
+    pub follow: bool,
+    /// Print only the parsed event summaries.
+    pub events: bool,
+}
+
+/// List iteration transcripts, or print one.
+pub fn logs(options: LogsOptions) -> LogsCommandResult {
+    let dir = transcript_dir(&AfkConfig::load(None).unwrap_or_default().feedback);
+    match options.iteration {
+        None => list(&dir),
+        Some(iteration) => show(&dir, iteration, &options),
+    }
+}
+
+fn list(dir: &Path) -> LogsCommandResult {
+    let iterations = list_logs(dir)?;
+    if iterations.is_empty() {
+        println!(
+            "\x1b[2mNo iteration transcripts in {}. Run `afk go` to start the loop.\x1b[0m",
+            dir.display()
+        );
+        return Ok(());
+    }
+    println!(
+        "\x1b[1mIteration transcripts\x1b[0m \x1b[2m({})\x1b[0m",
+        dir.display()
+    );
+    for iteration in iterations {
+        let path = log_path(dir, iteration);
+        let size: u64 = [path.clone(), rotated_path(dir, iteration)]
+            .iter()
+            .filter_map(|p| fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum();
+        let started = started_at(&path).unwrap_or_default();
+        println!(
+            "  {iteration:>4}  {started:<19}  \x1b[2m{}\x1b[0m",
+            format_size(size)
+        );
+    }
+    println!("\x1b[2mShow one with: afk logs <iteration>\x1b[0m");
+    Ok(())
+}
+
+fn show(dir: &Path, iteration: u32, options: &LogsOptions) -> LogsCommandResult {
+    let contents = read_transcript(dir, iteration)?.ok_or(LogsCommandError::NotFound(iteration))?;
+    print_lines(&contents, options.events);
+    if !options.follow {
+        return Ok(());
+    }
+
+    let path = log_path(dir, iteration);
+    let mut offset = fs::metadata(&path)?.len();
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        let Ok(file) = File::open(&path) else {
+            continue;
+        };
+        let len = file.metadata()?.len();
+        if len < offset {
+            // Transcript was rotated or the iteration rerun; start over
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        // Only consume complete lines so a half-written one is read next time
+        while reader.read_line(&mut line)? > 0 && line.ends_with('\n') {
+            offset += line.len() as u64;
+            print_lines(&line, options.events);
+            line.clear();
+        }
+    }
+}
+
+fn print_lines(contents: &str, events: bool) {
+    for line in contents.lines().filter(|l| !events || is_event(l)) {
+        println!("{line}");
+    }
+}
+
+/// Whether a transcript line is a parsed event summary.
+fn is_event(line: &str) -> bool {
+    line_tag(line) == Some(TAG_EVENT)
+}
+
+/// When the transcript says its iteration started.
+fn started_at(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    BufReader::new(file)
+        .lines()
+        .take(3)
+        .map_while(Result::ok)
+        .find_map(|l| l.strip_prefix("# started: ").map(String::from))
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{bytes} B")
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_event() {
+        assert!(is_event("[12:00:00] event | tool Read started"));
+        assert!(!is_event("[12:00:00] out   | {\"type\":\"event\"}"));
+        assert!(!is_event("# afk iteration 1"));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -6,13 +6,32 @@
 pub mod archive;
 pub mod completions;
 pub mod config;
+pub mod control;
+pub mod enrich;
+pub mod explain;
+pub mod export;
+pub mod fleet;
+pub mod git_cmd;
 pub mod go;
 pub mod import;
 pub mod init;
+pub mod log;
+pub mod logs;
+pub mod preset;
 pub mod progress_cmd;
 pub mod prompt;
+pub mod replay;
+pub mod report;
+pub mod repro;
+pub mod review;
+pub mod sandbox;
+pub mod scheduler;
+pub mod schema;
+pub mod serve;
 pub mod source;
+pub mod stats;
 pub mod status;
 pub mod task;
 pub mod use_cli;
 pub mod verify;
+pub mod watch;
@@ -0,0 +1,126 @@
+//! Preset command implementations.
+//!
+//! This module implements `afk preset list` and `afk preset show` for
+//! browsing the curated configs available to `afk init --preset`.
+
+use crate::bootstrap::presets::{get_preset, preset_names, Preset, PRESETS};
+
+/// Result type for preset command operations.
+pub type PresetCommandResult = Result<(), PresetCommandError>;
+
+/// Error type for preset command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum PresetCommandError {
+    /// No preset with this name exists.
+    #[error("Unknown preset: {name} (available: {available})")]
+    UnknownPreset {
+        /// Requested preset name.
+        name: String,
+        /// Comma-separated list of preset names.
+        available: String,
+    },
+}
+
+impl PresetCommandError {
+    /// Build an unknown-preset error listing the valid names.
+    pub fn unknown(name: &str) -> Self {
+        Self::UnknownPreset {
+            name: name.to_string(),
+            available: preset_names().join(", "),
+        }
+    }
+}
+
+/// Look up a preset, failing with the list of valid names.
+pub fn find_preset(name: &str) -> Result<&'static Preset, PresetCommandError> {
+    get_preset(name).ok_or_else(|| PresetCommandError::unknown(name))
+}
+
+/// List all built-in presets.
+pub fn preset_list() -> PresetCommandResult {
+    println!("\x1b[1mAvailable presets:\x1b[0m");
+    println!();
+    for preset in PRESETS {
+        println!(
+            "  \x1b[36m{:<14}\x1b[0m {}",
+            preset.name, preset.description
+        );
+    }
+    println!();
+    println!("\x1b[2mUse 'afk preset show <name>' for details, 'afk init --preset <name>' to apply.\x1b[0m");
+    Ok(())
+}
+
+/// Show what a preset configures.
+pub fn preset_show(name: &str) -> PresetCommandResult {
+    let preset = find_preset(name)?;
+
+    println!("\x1b[1m{}\x1b[0m", preset.name);
+    println!("  {}", preset.description);
+    println!();
+
+    println!("\x1b[36mQuality gates\x1b[0m");
+    let gates = [
+        ("types", preset.types),
+        ("lint", preset.lint),
+        ("test", preset.test),
+        ("build", preset.build),
+    ];
+    for (gate, cmd) in gates {
+        if let Some(cmd) = cmd {
+            println!("  {gate:<10} {cmd}");
+        }
+    }
+    for (gate, cmd) in preset.custom {
+        println!("  {gate:<10} {cmd}");
+    }
+    println!();
+
+    if !preset.context_files.is_empty() {
+        println!("\x1b[36mContext files\x1b[0m");
+        for file in preset.context_files {
+            println!("  {file}");
+        }
+        println!();
+    }
+
+    if !preset.instructions.is_empty() {
+        println!("\x1b[36mInstructions\x1b[0m");
+        for instruction in preset.instructions {
+            println!("  - {instruction}");
+        }
+        println!();
+    }
+
+    println!("\x1b[36mLimits\x1b[0m");
+    println!("  max_iterations    {}", preset.max_iterations);
+    println!("  max_task_failures {}", preset.max_task_failures);
+    println!("  timeout_minutes   {}", preset.timeout_minutes);
+    if preset.has_frontend {
+        println!();
+        println!("\x1b[2mEnables browser verification (prompt.has_frontend).\x1b[0m");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_command_error_display() {
+        let err = PresetCommandError::unknown("cobol");
+        assert_eq!(
+            err.to_string(),
+            "Unknown preset: cobol (available: rust-cli, python-lib, node-webapp, docs-site)"
+        );
+    }
+
+    #[test]
+    fn test_find_preset() {
+        assert_eq!(find_preset("python-lib").unwrap().name, "python-lib");
+        assert!(find_preset("nope").is_err());
+        assert!(preset_show("nope").is_err());
+    }
+}
@@ -1,10 +1,11 @@
 //! Progress/task status command implementations.
 //!
-//! This module implements the `afk done`, `afk fail`, and `afk reset` commands
-//! for managing task status.
+//! This module implements the `afk done`, `afk fail`, `afk reset`, and
+//! `afk note` commands for managing task status and session notes.
 
 use crate::prd::PrdDocument;
 use crate::progress::{SessionProgress, TaskStatus};
+use crate::runner::{ci_failure_details, verify_story};
 
 /// Result type for progress command operations.
 pub type ProgressCommandResult = Result<(), ProgressCommandError>;
@@ -18,10 +19,44 @@ pub enum ProgressCommandError {
     /// Error saving the progress file.
     #[error("Failed to save progress: {0}")]
     SaveError(std::io::Error),
+    /// A `ci:` acceptance criterion is not green.
+    #[error("CI checks not passing for {task_id}: {details}")]
+    CiNotPassing {
+        /// Task ID that was being completed.
+        task_id: String,
+        /// Failing workflows and their status.
+        details: String,
+    },
+    /// An acceptance criterion's verify command failed.
+    #[error("Acceptance criteria not met for {task_id}: {details}")]
+    CriteriaNotMet {
+        /// Task ID that was being completed.
+        task_id: String,
+        /// Failing checks and the tail of their output.
+        details: String,
+    },
+    /// The task has no acceptance criterion at the given position.
+    #[error("Task {task_id} has no acceptance criterion #{number}")]
+    CriterionNotFound {
+        /// Task ID.
+        task_id: String,
+        /// 1-based criterion number.
+        number: usize,
+    },
+    /// Error saving the tasks file.
+    #[error("Failed to save tasks: {0}")]
+    PrdError(#[from] crate::prd::PrdError),
 }
 
 /// Mark a task as complete.
+///
+/// If the task has `ci:<workflow>` acceptance criteria, the latest CI run of
+/// each workflow on the current branch must have passed, and every
+/// criterion with a verify command must pass it.
 pub fn done(task_id: &str, message: Option<&str>) -> ProgressCommandResult {
+    check_task_ci(task_id)?;
+    check_task_criteria(task_id)?;
+
     // Load progress
     let mut progress = SessionProgress::load(None)?;
 
@@ -54,6 +89,83 @@ pub fn done(task_id: &str, message: Option<&str>) -> ProgressCommandResult {
     Ok(())
 }
 
+/// Check off a single acceptance criterion of a task.
+///
+/// The message, if given, is recorded as the criterion's evidence note.
+pub fn check_criterion(
+    task_id: &str,
+    number: usize,
+    evidence: Option<&str>,
+) -> ProgressCommandResult {
+    let mut prd = PrdDocument::load(None)?;
+    let not_found = || ProgressCommandError::CriterionNotFound {
+        task_id: task_id.to_string(),
+        number,
+    };
+
+    let story = prd
+        .user_stories
+        .iter_mut()
+        .find(|s| s.id == task_id)
+        .ok_or_else(not_found)?;
+    if !story.check_criterion(number, evidence.map(ToOwned::to_owned)) {
+        return Err(not_found());
+    }
+    let (met, total) = story.criteria_counts();
+    let text = story.acceptance_criteria[number - 1].text.clone();
+    prd.save(None)?;
+
+    println!(
+        "\x1b[32m✓\x1b[0m Criterion {number} of \x1b[1m{}\x1b[0m met ({met}/{total})",
+        task_id
+    );
+    println!("  \x1b[2m{text}\x1b[0m");
+
+    Ok(())
+}
+
+/// Refuse completion while any of the task's CI criteria is not green.
+fn check_task_ci(task_id: &str) -> ProgressCommandResult {
+    let Ok(prd) = PrdDocument::load(None) else {
+        return Ok(());
+    };
+    let Some(story) = prd.user_stories.iter().find(|s| s.id == task_id) else {
+        return Ok(());
+    };
+
+    match ci_failure_details(&story.acceptance_criteria) {
+        None => Ok(()),
+        Some(details) => Err(ProgressCommandError::CiNotPassing {
+            task_id: task_id.to_string(),
+            details,
+        }),
+    }
+}
+
+/// Run the task's criterion checks, checking off those that pass, and
+/// refuse completion if any fails.
+fn check_task_criteria(task_id: &str) -> ProgressCommandResult {
+    let Ok(mut prd) = PrdDocument::load(None) else {
+        return Ok(());
+    };
+    let Some(story) = prd.user_stories.iter_mut().find(|s| s.id == task_id) else {
+        return Ok(());
+    };
+    if story.acceptance_criteria.iter().all(|c| c.verify.is_none()) {
+        return Ok(());
+    }
+
+    let failed = verify_story(story, None);
+    prd.save(None)?;
+    match failed {
+        None => Ok(()),
+        Some(details) => Err(ProgressCommandError::CriteriaNotMet {
+            task_id: task_id.to_string(),
+            details,
+        }),
+    }
+}
+
 /// Mark a task as failed.
 pub fn fail(task_id: &str, message: Option<&str>) -> ProgressCommandResult {
     // Load progress
@@ -120,6 +232,37 @@ pub fn reset(task_id: &str) -> ProgressCommandResult {
     Ok(())
 }
 
+/// Add a note to the current session.
+pub fn note(text: &str) -> ProgressCommandResult {
+    let mut progress = SessionProgress::load(None)?;
+    progress.add_note(text.trim());
+    progress
+        .save(None)
+        .map_err(|e| ProgressCommandError::SaveError(std::io::Error::other(e.to_string())))?;
+
+    println!("\x1b[32m✓\x1b[0m Note added to session");
+    Ok(())
+}
+
+/// Show the current session's tags and notes.
+pub fn show_notes() -> ProgressCommandResult {
+    let progress = SessionProgress::load(None)?;
+    if progress.tags.is_empty() && progress.notes.is_empty() {
+        println!("No tags or notes for this session.");
+        println!("\x1b[2mAdd one with: afk note \"<text>\"\x1b[0m");
+        return Ok(());
+    }
+
+    if !progress.tags.is_empty() {
+        println!("\x1b[1mTags:\x1b[0m {}", progress.tags.join(", "));
+    }
+    for note in &progress.notes {
+        let date = note.at.get(..16).unwrap_or(&note.at).replace('T', " ");
+        println!("\x1b[2m{date}\x1b[0m  {}", note.text);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -128,5 +271,23 @@ mod tests {
     fn test_progress_command_error_display() {
         let err = ProgressCommandError::SaveError(std::io::Error::other("test error"));
         assert!(err.to_string().contains("Failed to save progress"));
+
+        let err = ProgressCommandError::CiNotPassing {
+            task_id: "task-1".to_string(),
+            details: "test failed (failure)".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "CI checks not passing for task-1: test failed (failure)"
+        );
+
+        let err = ProgressCommandError::CriterionNotFound {
+            task_id: "task-1".to_string(),
+            number: 4,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Task task-1 has no acceptance criterion #4"
+        );
     }
 }
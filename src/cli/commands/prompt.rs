@@ -4,7 +4,7 @@
 
 use crate::cli::output::output_prompt;
 use crate::config::{AfkConfig, OutputMode};
-use crate::prompt::generate_prompt;
+use crate::prompt::{generate_prompt, template_exists, PromptResult};
 
 /// Result type for prompt command operations.
 pub type PromptCommandResult = Result<(), PromptCommandError>;
@@ -18,6 +18,9 @@ pub enum PromptCommandError {
     /// Error outputting the prompt.
     #[error("Output error: {0}")]
     OutputError(#[from] crate::cli::output::OutputError),
+    /// The requested template does not exist.
+    #[error("Unknown template '{0}': expected 'default' or .afk/templates/{0}.md")]
+    UnknownTemplate(String),
 }
 
 /// Options for the prompt command.
@@ -32,12 +35,25 @@ pub struct PromptOptions {
     pub bootstrap: bool,
     /// Override max iterations.
     pub limit: Option<u32>,
+    /// Named template to render instead of the configured one.
+    pub template: Option<String>,
+    /// Show the estimated size and what was trimmed to fit the budget.
+    pub explain: bool,
 }
 
 /// Execute the prompt command.
 pub fn prompt(options: PromptOptions) -> PromptCommandResult {
     // Load config
-    let config = AfkConfig::load(None).unwrap_or_default();
+    let mut config = AfkConfig::load(None).unwrap_or_default();
+
+    // A named template replaces both the configured name and custom path
+    if let Some(name) = options.template {
+        if !template_exists(&name, None) {
+            return Err(PromptCommandError::UnknownTemplate(name));
+        }
+        config.prompt.template = name;
+        config.prompt.custom_path = None;
+    }
 
     // Generate the prompt
     let result = generate_prompt(&config, options.bootstrap, options.limit)?;
@@ -57,6 +73,17 @@ pub fn prompt(options: PromptOptions) -> PromptCommandResult {
     let is_stdout = mode == OutputMode::Stdout;
     let _ = output_prompt(&result.prompt, mode, &config);
 
+    // Keep the explanation off stdout when the prompt is going there
+    if options.explain {
+        for line in explain_lines(&result, config.prompt_budget()) {
+            if is_stdout {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+
     // Show info unless going to stdout
     if !options.stdout && !is_stdout {
         println!("\x1b[2mIteration {}\x1b[0m", result.iteration);
@@ -68,9 +95,73 @@ pub fn prompt(options: PromptOptions) -> PromptCommandResult {
     Ok(())
 }
 
+/// Lines describing a prompt's estimated size and what was trimmed to fit
+/// `max_tokens`.
+fn explain_lines(result: &PromptResult, max_tokens: u32) -> Vec<String> {
+    let budget = if max_tokens == 0 {
+        "no prompt.max_tokens budget".to_string()
+    } else {
+        format!("budget {max_tokens}")
+    };
+    let mut lines = vec![format!(
+        "\x1b[1mPrompt:\x1b[0m ~{} tokens ({budget})",
+        result.estimated_tokens
+    )];
+    if !result.cuts.is_empty() {
+        lines.push(format!("Trimmed {} item(s) to fit:", result.cuts.len()));
+        for cut in &result.cuts {
+            lines.push(format!("  \x1b[2m-\x1b[0m {}: {}", cut.section, cut.item));
+        }
+    } else if max_tokens > 0 {
+        lines.push("\x1b[2mNothing trimmed\x1b[0m".to_string());
+    }
+    if max_tokens > 0 && result.estimated_tokens > max_tokens as usize {
+        lines.push(
+            "\x1b[33mWarning:\x1b[0m Still over budget with all trimmable context removed"
+                .to_string(),
+        );
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::prompt::Cut;
+
+    fn result(estimated_tokens: usize, cuts: Vec<Cut>) -> PromptResult {
+        PromptResult {
+            prompt: String::new(),
+            iteration: 1,
+            all_complete: false,
+            estimated_tokens,
+            cuts,
+            task_id: None,
+        }
+    }
+
+    #[test]
+    fn test_explain_lines() {
+        let lines = explain_lines(&result(1200, Vec::new()), 0);
+        assert_eq!(
+            lines,
+            ["\x1b[1mPrompt:\x1b[0m ~1200 tokens (no prompt.max_tokens budget)"]
+        );
+
+        let cuts = vec![Cut {
+            section: "learning",
+            item: "Use the crypto module".to_string(),
+        }];
+        let lines = explain_lines(&result(900, cuts), 1000);
+        assert_eq!(lines[0], "\x1b[1mPrompt:\x1b[0m ~900 tokens (budget 1000)");
+        assert_eq!(lines[1], "Trimmed 1 item(s) to fit:");
+        assert!(lines[2].ends_with("learning: Use the crypto module"));
+        assert_eq!(lines.len(), 3);
+
+        let lines = explain_lines(&result(1500, Vec::new()), 1000);
+        assert!(lines[1].contains("Nothing trimmed"));
+        assert!(lines[2].contains("Still over budget"));
+    }
 
     #[test]
     fn test_prompt_command_error_display() {
@@ -78,5 +169,11 @@ mod tests {
             "test".to_string(),
         ));
         assert!(err.to_string().contains("Output error"));
+
+        let err = PromptCommandError::UnknownTemplate("review".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Unknown template 'review': expected 'default' or .afk/templates/review.md"
+        );
     }
 }
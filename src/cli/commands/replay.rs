@@ -0,0 +1,382 @@
+//! Replay command implementation.
+//!
+//! This module implements `afk replay <iteration>`, which puts back together
+//! what happened in a past iteration from what afk kept of it: the prompt
+//! sent (from `.afk/repro/`), what the AI CLI said and the tool calls it
+//! made (from its transcript in `.afk/logs/`), the gates that ran (from `.afk/artifacts/`), and the
+//! commits made between its start and the next iteration's.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::config::{AfkConfig, ARTIFACTS_DIR};
+use crate::events::{self, ToolCall};
+use crate::git;
+use crate::parser::CliFormat;
+use crate::progress::repro::{load_record, prompt_path, IterationRecord};
+use crate::progress::transcript::{line_tag, read_transcript, transcript_dir, TAG_EVENT, TAG_OUT};
+use crate::progress::ProgressError;
+
+/// Result type for replay command operations.
+pub type ReplayCommandResult = Result<(), ReplayCommandError>;
+
+/// Error type for replay command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ReplayCommandError {
+    /// No repro record was saved for the iteration.
+    #[error("No record of iteration {0} (records are kept for the current session)")]
+    NotFound(u32),
+    /// Failed to read the record.
+    #[error("Failed to read iteration record: {0}")]
+    ProgressError(#[from] ProgressError),
+    /// Failed to read the transcript or gate logs.
+    #[error("Failed to read iteration logs: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Failed to serialise the replay.
+    #[error("Failed to serialise replay: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Options for `afk replay`.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayOptions {
+    /// Iteration to replay.
+    pub iteration: u32,
+    /// Print the whole transcript rather than its parsed events.
+    pub raw: bool,
+    /// Print the replay as JSON.
+    pub json: bool,
+}
+
+/// Everything afk kept about one iteration.
+#[derive(Debug, Serialize)]
+pub struct Replay {
+    /// The iteration's repro record.
+    pub record: IterationRecord,
+    /// The prompt it was sent, if saved.
+    pub prompt: Option<String>,
+    /// Its transcript, if kept.
+    pub transcript: Option<String>,
+    /// Tool calls found in the transcript, in order.
+    pub tool_calls: Vec<ToolCall>,
+    /// Gates run for it, in name order.
+    pub gates: Vec<GateRun>,
+    /// What it changed in git, if its start commit is known.
+    pub changes: Option<Changes>,
+}
+
+/// A gate run recorded in the iteration's artefacts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GateRun {
+    /// Gate name.
+    pub name: String,
+    /// Whether it passed.
+    pub passed: bool,
+    /// Duration as recorded, e.g. "1.5s".
+    pub duration: String,
+    /// Path of the full output.
+    pub log: String,
+}
+
+/// Commits made during an iteration.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Changes {
+    /// Revision range covered, e.g. "abc1234..def5678".
+    pub range: String,
+    /// Commits in the range, oldest first.
+    pub commits: Vec<String>,
+    /// `git diff --stat` over the range.
+    pub stat: Vec<String>,
+}
+
+/// Replay an iteration.
+pub fn replay(options: ReplayOptions) -> ReplayCommandResult {
+    let replay = load(options.iteration)?;
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&replay)?);
+    } else {
+        print(&replay, options.raw);
+    }
+    Ok(())
+}
+
+/// Gather what was kept about an iteration.
+pub fn load(iteration: u32) -> Result<Replay, ReplayCommandError> {
+    let record = load_record(iteration)?.ok_or(ReplayCommandError::NotFound(iteration))?;
+    let config = AfkConfig::load(None).unwrap_or_default();
+    let prompt = fs::read_to_string(prompt_path(iteration)).ok();
+    let transcript = read_transcript(&transcript_dir(&config.feedback), iteration)?;
+    let tool_calls = transcript
+        .as_deref()
+        .map(|t| transcript_tool_calls(t, config.ai_cli.detect_cli_format()))
+        .unwrap_or_default();
+    let gates = gate_runs(&Path::new(ARTIFACTS_DIR).join(iteration.to_string()));
+
+    // The iteration's work ends where the next one started
+    let changes = record.base_commit.as_deref().map(|base| {
+        let end = load_record(iteration + 1)
+            .ok()
+            .flatten()
+            .and_then(|next| next.base_commit)
+            .unwrap_or_else(|| "HEAD".to_string());
+        Changes {
+            range: format!("{}..{}", short(base), short(&end)),
+            commits: git::commit_log(&format!("{base}..{end}")),
+            stat: git::diff_stat(base, &end),
+        }
+    });
+
+    Ok(Replay {
+        record,
+        prompt,
+        transcript,
+        tool_calls,
+        gates,
+        changes,
+    })
+}
+
+/// Gate runs recorded in an iteration's artefact directory.
+fn gate_runs(dir: &Path) -> Vec<GateRun> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut gates: Vec<GateRun> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let path = e.path();
+            let header = fs::read_to_string(&path).ok()?;
+            let field = |key: &str| {
+                header
+                    .lines()
+                    .take(3)
+                    .find_map(|l| l.strip_prefix(&format!("# {key}: ")).map(String::from))
+            };
+            // Only gate logs have a header; the AI CLI's stderr log doesn't
+            Some(GateRun {
+                name: field("gate")?,
+                passed: field("status")? == "passed",
+                duration: field("duration").unwrap_or_default(),
+                log: path.display().to_string(),
+            })
+        })
+        .collect();
+    gates.sort_by(|a, b| a.name.cmp(&b.name));
+    gates
+}
+
+fn short(rev: &str) -> &str {
+    rev.get(..7).unwrap_or(rev)
+}
+
+fn print(replay: &Replay, raw: bool) {
+    let record = &replay.record;
+    let heading = |title: &str| println!("\n\x1b[1m── {title} ──\x1b[0m");
+    let dim = |s: &str| println!("\x1b[2m{s}\x1b[0m");
+
+    println!("\x1b[1mIteration {}\x1b[0m", record.iteration);
+    println!("  Started:  {}", record.timestamp);
+    if let Some(ref task_id) = record.task_id {
+        println!("  Task:     {task_id}");
+    }
+    println!(
+        "  Model:    {}",
+        record.model.as_deref().unwrap_or("(CLI default)")
+    );
+    println!("  Command:  {}", record.command.join(" "));
+
+    heading("Prompt");
+    match &replay.prompt {
+        Some(prompt) => println!("{}", prompt.trim_end()),
+        None => dim("(prompt not saved)"),
+    }
+
+    heading("Response");
+    match &replay.transcript {
+        Some(transcript) => {
+            for line in response_lines(transcript, raw) {
+                println!("{line}");
+            }
+        }
+        None => dim("(no transcript kept; see feedback.log_dir)"),
+    }
+
+    heading("Files touched");
+    let files = files_touched(&replay.tool_calls);
+    if files.is_empty() {
+        dim("(no file changes recorded)");
+    }
+    for file in files {
+        println!("  {file}");
+    }
+
+    heading("Commands run");
+    let commands: Vec<&ToolCall> = replay
+        .tool_calls
+        .iter()
+        .filter(|call| call.command.is_some())
+        .collect();
+    if commands.is_empty() {
+        dim("(no commands recorded)");
+    }
+    for call in commands {
+        let mark = if call.success {
+            "\x1b[32m✓\x1b[0m"
+        } else {
+            "\x1b[31m✗\x1b[0m"
+        };
+        let exit = call
+            .exit_code
+            .map(|code| format!("  \x1b[2mexit {code}\x1b[0m"))
+            .unwrap_or_default();
+        println!(
+            "  {mark} {}{exit}",
+            call.command.as_deref().unwrap_or_default()
+        );
+    }
+
+    heading("Gates");
+    if replay.gates.is_empty() {
+        dim("(no gates recorded)");
+    }
+    for gate in &replay.gates {
+        let mark = if gate.passed {
+            "\x1b[32m✓\x1b[0m"
+        } else {
+            "\x1b[31m✗\x1b[0m"
+        };
+        println!(
+            "  {mark} {} {}  \x1b[2m{}\x1b[0m",
+            gate.name, gate.duration, gate.log
+        );
+    }
+
+    heading("Changes");
+    match &replay.changes {
+        Some(changes) if changes.commits.is_empty() => {
+            dim(&format!("(no commits in {})", changes.range))
+        }
+        Some(changes) => {
+            dim(&changes.range);
+            for commit in &changes.commits {
+                println!("  {commit}");
+            }
+            for line in &changes.stat {
+                println!("  \x1b[2m{line}\x1b[0m");
+            }
+        }
+        None => dim("(start commit not recorded)"),
+    }
+}
+
+/// The transcript lines to show as the response.
+///
+/// Parsed events tell the story of a stream-json run far better than the
+/// NDJSON they came from, so raw stdout is left out when there are events,
+/// unless `raw` asks for everything.
+fn response_lines(transcript: &str, raw: bool) -> Vec<&str> {
+    let lines = transcript.lines().filter(|l| line_tag(l).is_some());
+    let has_events = transcript.lines().any(|l| line_tag(l) == Some(TAG_EVENT));
+    if raw || !has_events {
+        return lines.collect();
+    }
+    lines.filter(|l| line_tag(l) != Some(TAG_OUT)).collect()
+}
+
+/// Tool calls in the AI CLI output kept in a transcript.
+fn transcript_tool_calls(transcript: &str, format: CliFormat) -> Vec<ToolCall> {
+    let output: Vec<&str> = transcript
+        .lines()
+        .filter(|l| line_tag(l) == Some(TAG_OUT))
+        .filter_map(|l| l.split_once(" | ").map(|(_, text)| text))
+        .collect();
+    events::tool_calls(&output.join("\n"), format)
+}
+
+/// Files written, edited or deleted, each once, in the order first touched.
+fn files_touched(calls: &[ToolCall]) -> Vec<&str> {
+    let mut files: Vec<&str> = Vec::new();
+    for path in calls
+        .iter()
+        .filter(|call| call.touches_file())
+        .filter_map(|call| call.path.as_deref())
+    {
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_gate_runs() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("test.log"),
+            "# gate: test\n# status: failed\n# duration: 2.0s\n\nboom\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("lint.log"),
+            "# gate: lint\n# status: passed\n# duration: 0.5s\n\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join("ai-cli-stderr.log"), "warning\n").unwrap();
+
+        let gates = gate_runs(temp.path());
+        assert_eq!(gates.len(), 2);
+        assert_eq!(gates[0].name, "lint");
+        assert!(gates[0].passed);
+        assert_eq!(gates[1].name, "test");
+        assert!(!gates[1].passed);
+        assert_eq!(gates[1].duration, "2.0s");
+        assert!(gate_runs(&temp.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_response_lines() {
+        let stream = "# afk iteration 1\n\n\
+                      [12:00:00] out   | {\"type\":\"assistant\"}\n\
+                      [12:00:00] event | assistant: Looking at auth\n\
+                      [12:00:01] err   | warning\n";
+        assert_eq!(
+            response_lines(stream, false),
+            [
+                "[12:00:00] event | assistant: Looking at auth",
+                "[12:00:01] err   | warning"
+            ]
+        );
+        assert_eq!(response_lines(stream, true).len(), 3);
+
+        let text = "[12:00:00] out   | Working\n[12:00:01] afk   | retrying\n";
+        assert_eq!(response_lines(text, false).len(), 2);
+    }
+
+    #[test]
+    fn test_transcript_tool_calls() {
+        let transcript = concat!(
+            "# afk iteration 1\n\n",
+            r#"[12:00:00] out   | {"type":"tool_call","subtype":"completed","tool_call":{"editToolCall":{"args":{"path":"src/a.rs"},"result":{"success":{}}}}}"#,
+            "\n[12:00:01] event | tool Edit completed: src/a.rs\n",
+            r#"[12:00:02] out   | {"type":"tool_call","subtype":"completed","tool_call":{"readToolCall":{"args":{"path":"src/b.rs"},"result":{"success":{}}}}}"#,
+            "\n",
+            r#"[12:00:03] out   | {"type":"tool_call","subtype":"completed","tool_call":{"shellToolCall":{"args":{"command":"cargo test"},"result":{"failure":{"exitCode":101}}}}}"#,
+            "\n",
+            r#"[12:00:04] out   | {"type":"tool_call","subtype":"completed","tool_call":{"editToolCall":{"args":{"path":"src/a.rs"},"result":{"success":{}}}}}"#,
+            "\n",
+        );
+        let calls = transcript_tool_calls(transcript, CliFormat::Cursor);
+        assert_eq!(calls.len(), 4);
+        assert_eq!(files_touched(&calls), ["src/a.rs"]);
+        assert_eq!(calls[2].command.as_deref(), Some("cargo test"));
+        assert_eq!(calls[2].exit_code, Some(101));
+        assert!(!calls[2].success);
+    }
+}
@@ -0,0 +1,234 @@
+//! Report command implementation.
+//!
+//! This module implements `afk report burndown`, which charts the backlog's
+//! burn-down history in tasks, or in summed task estimates when tasks have
+//! them, with the pace of the latest session, when the backlog should be
+//! done at that pace, and how long estimated tasks actually took.
+
+use chrono::{Local, NaiveDateTime};
+
+use crate::cli::output::json::{self, BurndownReportJson, EstimateActualsJson, SCHEMA_VERSION};
+use crate::config::{AfkConfig, ConfigError, EstimateUnit};
+use crate::prd::PrdDocument;
+use crate::progress::burndown::{self, Measure, Pace};
+use crate::progress::{EstimateActuals, SessionProgress, Trend};
+
+/// Result type for report command operations.
+pub type ReportCommandResult = Result<(), ReportCommandError>;
+
+/// Error type for report command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ReportCommandError {
+    /// Failed to load configuration.
+    #[error("Failed to load config: {0}")]
+    ConfigError(#[from] ConfigError),
+}
+
+/// Everything the burn-down report shows.
+struct BurndownReport {
+    unit: &'static str,
+    completed: usize,
+    remaining: usize,
+    history: Vec<usize>,
+    first: Option<String>,
+    last: Option<String>,
+    pace: Option<Pace>,
+    actuals: EstimateActuals,
+    estimate_unit: Option<EstimateUnit>,
+}
+
+impl BurndownReport {
+    fn load() -> Result<(Self, Vec<burndown::BurndownPoint>), ReportCommandError> {
+        let config = AfkConfig::load(None)?;
+        let prd = PrdDocument::load(None).unwrap_or_default();
+        let progress = SessionProgress::load(None).unwrap_or_default();
+        let points = burndown::load(None);
+
+        // Count in estimates once the backlog has them
+        let estimates = burndown::estimate_totals(&prd);
+        let (measure, completed, remaining) = match estimates {
+            Some((done, left)) => (Measure::Estimates, done as usize, left as usize),
+            None => {
+                let (done, total) = prd.get_story_counts();
+                (Measure::Tasks, done, total - done)
+            }
+        };
+        let estimate_unit = estimates.map(|_| config.reporting.estimate_unit);
+        let history = measure.history(&points);
+        let report = Self {
+            unit: estimate_unit.map_or("tasks", EstimateUnit::as_str),
+            completed,
+            remaining,
+            history: history
+                .iter()
+                .filter_map(|p| measure.remaining(p))
+                .collect(),
+            first: history.first().map(|p| p.timestamp.clone()),
+            last: history.last().map(|p| p.timestamp.clone()),
+            pace: Pace::of(history, measure),
+            actuals: EstimateActuals::of(&prd, &progress),
+            estimate_unit,
+        };
+        Ok((report, points))
+    }
+}
+
+/// Show the burn-down report: a `height`-row chart of the last `width`
+/// points, or [`BurndownReportJson`] with `as_json`.
+pub fn burndown(width: usize, height: usize, as_json: bool) -> ReportCommandResult {
+    let (report, points) = BurndownReport::load()?;
+    if as_json {
+        json::print(&BurndownReportJson {
+            schema_version: SCHEMA_VERSION,
+            unit: report.unit,
+            completed: report.completed,
+            remaining: report.remaining,
+            trend: Trend::of(&report.history).map(Trend::label),
+            history: report.history,
+            per_hour: report.pace.as_ref().map(|p| p.per_hour),
+            projected_finish: report
+                .pace
+                .as_ref()
+                .and_then(|p| p.finish)
+                .map(|at| at.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            actuals: EstimateActualsJson {
+                tasks: report.actuals.tasks,
+                estimate: report.actuals.estimate,
+                minutes: report.actuals.seconds as f64 / 60.0,
+            },
+            points,
+        });
+        return Ok(());
+    }
+
+    let unit = report.unit;
+    println!("\x1b[1mBurn-down\x1b[0m \x1b[2m({unit})\x1b[0m");
+    println!();
+    if report.history.is_empty() {
+        println!(
+            "\x1b[2mNo burn-down history yet; a point is recorded after each iteration.\x1b[0m"
+        );
+    } else {
+        for line in burndown::chart(&report.history, width, height) {
+            println!("  {line}");
+        }
+        if let (Some(first), Some(last)) = (&report.first, &report.last) {
+            let shown = report.history.len().min(width);
+            println!(
+                "\x1b[2m  {} → {} ({shown} of {} iterations)\x1b[0m",
+                short_time(first),
+                short_time(last),
+                report.history.len()
+            );
+        }
+    }
+    println!();
+
+    println!(
+        "Remaining: \x1b[1m{}\x1b[0m of {} {unit} \x1b[2m({} done)\x1b[0m",
+        report.remaining,
+        report.completed + report.remaining,
+        report.completed
+    );
+    if let Some(trend) = Trend::of(&report.history) {
+        let color = match trend {
+            Trend::Converging => "32",
+            Trend::Stalled => "33",
+            Trend::Growing => "31",
+        };
+        println!("Trend:     \x1b[{color}m{}\x1b[0m", trend.label());
+    }
+    match &report.pace {
+        Some(pace) => println!("Pace:      {}", describe_pace(pace, unit, report.remaining)),
+        None => println!("Pace:      \x1b[2mnot enough history this session\x1b[0m"),
+    }
+    if let Some(line) = describe_actuals(&report.actuals, report.estimate_unit) {
+        println!("Estimates: {line}");
+    }
+    Ok(())
+}
+
+/// How fast the backlog is burning down, and when it should be done.
+fn describe_pace(pace: &Pace, unit: &str, remaining: usize) -> String {
+    let rate = format!(
+        "{:.1} {unit}/hour over {:.1}h",
+        pace.per_hour.max(0.0),
+        pace.hours
+    );
+    match pace.finish {
+        _ if remaining == 0 => format!("{rate} · \x1b[32mdone\x1b[0m"),
+        Some(finish) => format!("{rate} · done around \x1b[1m{}\x1b[0m", finish_time(finish)),
+        None => format!("{rate} · \x1b[33mnot shrinking\x1b[0m"),
+    }
+}
+
+/// Estimated against actual time for completed tasks, if any were
+/// estimated and timed.
+fn describe_actuals(actuals: &EstimateActuals, unit: Option<EstimateUnit>) -> Option<String> {
+    let unit = unit?;
+    let per_unit = actuals.minutes_per_unit()?;
+    let minutes = actuals.seconds as f64 / 60.0;
+    let tasks = match actuals.tasks {
+        1 => "1 task".to_string(),
+        n => format!("{n} tasks"),
+    };
+    Some(match unit {
+        EstimateUnit::Minutes => {
+            let verdict = if per_unit <= 1.0 {
+                "\x1b[32mon pace\x1b[0m"
+            } else {
+                "\x1b[33mbehind\x1b[0m"
+            };
+            format!(
+                "{tasks} estimated at {} minutes took {minutes:.0} ({per_unit:.1}× estimate, {verdict})",
+                actuals.estimate
+            )
+        }
+        EstimateUnit::Points => format!(
+            "{tasks}, {} points in {minutes:.0} minutes ({per_unit:.1} minutes per point)",
+            actuals.estimate
+        ),
+    })
+}
+
+/// A burn-down timestamp as "Mon 14:05".
+fn short_time(timestamp: &str) -> String {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S")
+        .map(|at| at.format("%a %H:%M").to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// A projected finish: the time today, otherwise the day and time.
+fn finish_time(at: NaiveDateTime) -> String {
+    if at.date() == Local::now().date_naive() {
+        at.format("%H:%M").to_string()
+    } else {
+        at.format("%a %Y-%m-%d %H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_actuals() {
+        let actuals = EstimateActuals {
+            tasks: 2,
+            estimate: 50,
+            seconds: 3600,
+        };
+        assert_eq!(describe_actuals(&actuals, None), None);
+        assert_eq!(
+            describe_actuals(&actuals, Some(EstimateUnit::Points)).as_deref(),
+            Some("2 tasks, 50 points in 60 minutes (1.2 minutes per point)")
+        );
+        let line = describe_actuals(&actuals, Some(EstimateUnit::Minutes)).unwrap();
+        assert!(line.starts_with("2 tasks estimated at 50 minutes took 60 (1.2× estimate"));
+        assert!(line.contains("behind"));
+        assert_eq!(
+            describe_actuals(&EstimateActuals::default(), Some(EstimateUnit::Points)),
+            None
+        );
+    }
+}
@@ -0,0 +1,106 @@
+//! Repro command implementation.
+//!
+//! This module implements `afk repro <iteration>`, which shows the metadata
+//! recorded for an iteration and the command that re-creates it.
+
+use crate::config::{AfkConfig, ARTIFACTS_DIR};
+use crate::progress::repro::{config_hash, load_record, prompt_path, stable_hash};
+use crate::progress::ProgressError;
+
+/// Result type for repro command operations.
+pub type ReproCommandResult = Result<(), ReproCommandError>;
+
+/// Error type for repro command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ReproCommandError {
+    /// No record was saved for the iteration.
+    #[error("No repro record for iteration {0}")]
+    NotFound(u32),
+    /// Failed to read the record.
+    #[error("Failed to read repro record: {0}")]
+    ProgressError(#[from] ProgressError),
+    /// Failed to serialise the record.
+    #[error("Failed to serialise repro record: {0}")]
+    JsonError(#[from] serde_json::Error),
+}
+
+/// Show how to reproduce an iteration.
+pub fn repro(iteration: u32, json: bool) -> ReproCommandResult {
+    let record = load_record(iteration)?.ok_or(ReproCommandError::NotFound(iteration))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&record)?);
+        return Ok(());
+    }
+
+    let dim = |s: &str| format!("\x1b[2m{s}\x1b[0m");
+    println!("\x1b[1mIteration {}\x1b[0m", record.iteration);
+    println!("  Recorded:     {}", record.timestamp);
+    if let Some(ref task_id) = record.task_id {
+        println!("  Task:         {task_id}");
+    }
+    println!(
+        "  Model:        {}",
+        record.model.as_deref().unwrap_or("(CLI default)")
+    );
+    println!("  Seed:         {}", record.seed);
+    println!("  afk:          {}", record.afk_version);
+    println!(
+        "  AI CLI:       {}",
+        record.cli_version.as_deref().unwrap_or("(unknown version)")
+    );
+
+    // Flag drift between the recorded state and what's on disk now
+    let prompt_status = match std::fs::read_to_string(prompt_path(iteration)) {
+        Ok(prompt) if stable_hash(prompt.as_bytes()) == record.prompt_hash => {
+            dim("(matches saved prompt)")
+        }
+        Ok(_) => "\x1b[33m(saved prompt was modified)\x1b[0m".to_string(),
+        Err(_) => "\x1b[33m(saved prompt missing)\x1b[0m".to_string(),
+    };
+    println!("  Prompt hash:  {} {prompt_status}", record.prompt_hash);
+
+    let config_status = match AfkConfig::load(None) {
+        Ok(config) if config_hash(&config) == record.config_hash => dim("(matches current config)"),
+        Ok(_) => "\x1b[33m(config has changed since)\x1b[0m".to_string(),
+        Err(_) => dim("(current config unreadable)"),
+    };
+    println!("  Config hash:  {} {config_status}", record.config_hash);
+
+    if record.afk_version != env!("CARGO_PKG_VERSION") {
+        println!(
+            "  \x1b[33m⚠\x1b[0m Recorded with afk {}, running {}",
+            record.afk_version,
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    let artifacts = std::path::Path::new(ARTIFACTS_DIR).join(iteration.to_string());
+    if artifacts.is_dir() {
+        println!("  Gate logs:    {}", artifacts.display());
+    }
+
+    println!();
+    println!("\x1b[1mCommand:\x1b[0m");
+    println!("  {}", record.shell_command());
+    println!();
+    println!(
+        "{}",
+        dim("Use 'afk repro <iteration> --json' to attach this record to a bug report.")
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repro_command_error_display() {
+        assert_eq!(
+            ReproCommandError::NotFound(7).to_string(),
+            "No repro record for iteration 7"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Review command implementation.
+//!
+//! This module implements `afk review`, which walks the tasks the loop held
+//! for review (with `review.required` set, or after an oversized iteration),
+//! showing each one's acceptance criteria, last gate run and commits, and
+//! asks whether to approve it (completing it), reject it (returning it to
+//! pending with feedback for the next prompt) or edit it.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::cli::commands::task::{edit_story, TaskCommandError, TaskEdit};
+use crate::config::EVENTS_FILE;
+use crate::events;
+use crate::git::{commit_info, diff_stat, show_commit};
+use crate::prd::{mark_story_complete, PrdDocument, PrdError, UserStory};
+use crate::progress::{SessionProgress, TaskStatus};
+
+/// Result type for review command operations.
+pub type ReviewCommandResult = Result<(), ReviewCommandError>;
+
+/// Error type for review command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ReviewCommandError {
+    /// Error loading the progress file.
+    #[error("Failed to load progress: {0}")]
+    LoadError(#[from] crate::progress::ProgressError),
+    /// Error saving the progress file.
+    #[error("Failed to save progress: {0}")]
+    SaveError(crate::progress::ProgressError),
+    /// Error loading or saving the task list.
+    #[error("Failed to update tasks: {0}")]
+    PrdError(#[from] PrdError),
+    /// The task isn't awaiting review.
+    #[error("Task {0} is not awaiting review")]
+    NotAwaitingReview(String),
+    /// Editing the task failed.
+    #[error(transparent)]
+    Edit(#[from] TaskCommandError),
+}
+
+/// What the reviewer chose for a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Choice {
+    Approve,
+    Reject,
+    Edit,
+    Diff,
+    Skip,
+    Quit,
+}
+
+impl Choice {
+    /// Parse an answer to the review prompt.
+    fn parse(answer: &str) -> Option<Self> {
+        match answer.trim().to_lowercase().as_str() {
+            "a" | "approve" => Some(Self::Approve),
+            "r" | "reject" => Some(Self::Reject),
+            "e" | "edit" => Some(Self::Edit),
+            "d" | "diff" => Some(Self::Diff),
+            "s" | "skip" | "" => Some(Self::Skip),
+            "q" | "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Review the tasks awaiting review, or just `task_id`.
+pub fn review(task_id: Option<&str>) -> ReviewCommandResult {
+    let progress = SessionProgress::load(None)?;
+    let prd = PrdDocument::load(None)?;
+
+    let queue: Vec<&UserStory> = match task_id {
+        Some(id) => {
+            if !awaiting_review(&progress, id) {
+                return Err(ReviewCommandError::NotAwaitingReview(id.to_string()));
+            }
+            prd.get_story(id).into_iter().collect()
+        }
+        None => prd
+            .user_stories
+            .iter()
+            .filter(|s| awaiting_review(&progress, &s.id))
+            .collect(),
+    };
+    if queue.is_empty() {
+        println!("\x1b[2mNo tasks awaiting review.\x1b[0m");
+        return Ok(());
+    }
+
+    let total = queue.len();
+    let (mut approved, mut rejected, mut edited) = (0, 0, 0);
+    for (index, story) in queue.into_iter().enumerate() {
+        let commits = task_commits(&progress, &story.id);
+        print_candidate(story, &commits, index + 1, total);
+
+        let choice = loop {
+            let Some(answer) = read_answer("[a]pprove [r]eject [e]dit [d]iff [s]kip [q]uit") else {
+                break Choice::Quit;
+            };
+            match Choice::parse(&answer) {
+                Some(Choice::Diff) => print_diff(&commits),
+                Some(choice) => break choice,
+                None => println!("\x1b[33mAnswer a, r, e, d, s or q.\x1b[0m"),
+            }
+        };
+
+        match choice {
+            Choice::Approve => {
+                approve(&story.id, &story.source)?;
+                approved += 1;
+                println!("\x1b[32m✓\x1b[0m {} approved", story.id);
+            }
+            Choice::Reject => {
+                let feedback = ask("Feedback for the next attempt");
+                reject(&story.id, &story.source, feedback)?;
+                rejected += 1;
+                println!("\x1b[33m↩\x1b[0m {} returned to pending", story.id);
+            }
+            Choice::Edit => {
+                edit(story)?;
+                edited += 1;
+                println!(
+                    "\x1b[33m↩\x1b[0m {} edited and returned to pending",
+                    story.id
+                );
+            }
+            Choice::Skip | Choice::Diff => {}
+            Choice::Quit => break,
+        }
+        println!();
+    }
+
+    println!("Reviewed: {approved} approved, {rejected} rejected, {edited} edited");
+    Ok(())
+}
+
+/// Whether a task is held for review.
+fn awaiting_review(progress: &SessionProgress, task_id: &str) -> bool {
+    progress
+        .get_task(task_id)
+        .is_some_and(|t| t.status == TaskStatus::AwaitingReview)
+}
+
+/// The task's commits that still exist, oldest first, as (hash, subject).
+///
+/// Uses the commits recorded in progress, or those in the event log when
+/// none were.
+fn task_commits(progress: &SessionProgress, task_id: &str) -> Vec<(String, String)> {
+    let mut recorded = progress
+        .get_task(task_id)
+        .map(|t| t.commits.clone())
+        .unwrap_or_default();
+    if recorded.is_empty() {
+        recorded = events::task_commits(Path::new(EVENTS_FILE), task_id);
+    }
+    let mut commits: Vec<(String, i64, String)> =
+        recorded.iter().filter_map(|c| commit_info(c)).collect();
+    commits.sort_by_key(|(_, time, _)| *time);
+    commits.dedup_by(|a, b| a.0 == b.0);
+    commits
+        .into_iter()
+        .map(|(sha, _, subject)| (sha, subject))
+        .collect()
+}
+
+/// Show a task with its criteria checklist, last gate run and commits.
+fn print_candidate(story: &UserStory, commits: &[(String, String)], number: usize, total: usize) {
+    println!(
+        "\x1b[1m[{number}/{total}] {}\x1b[0m  {}",
+        story.id, story.title
+    );
+    if !story.description.is_empty() {
+        println!("\x1b[2m{}\x1b[0m", story.description);
+    }
+
+    if !story.acceptance_criteria.is_empty() {
+        println!();
+        println!("  Acceptance criteria:");
+        for criterion in &story.acceptance_criteria {
+            let mark = if criterion.passed {
+                "\x1b[32m✓\x1b[0m"
+            } else {
+                "☐"
+            };
+            println!("    {mark} {}", criterion.text);
+        }
+    }
+
+    println!();
+    let gates = events::task_gate_run(Path::new(EVENTS_FILE), &story.id);
+    if gates.is_empty() {
+        println!("  Gates: \x1b[2mnone recorded\x1b[0m");
+    } else {
+        let results: Vec<String> = gates
+            .iter()
+            .map(|(name, passed)| {
+                if *passed {
+                    format!("\x1b[32m✓\x1b[0m {name}")
+                } else {
+                    format!("\x1b[31m✗\x1b[0m {name}")
+                }
+            })
+            .collect();
+        println!("  Gates: {}", results.join("  "));
+    }
+
+    println!();
+    if commits.is_empty() {
+        println!("  Commits: \x1b[2mnone recorded\x1b[0m");
+    } else {
+        println!("  Commits:");
+        for (sha, subject) in commits {
+            println!("    \x1b[36m{}\x1b[0m {subject}", &sha[..sha.len().min(7)]);
+        }
+        if let (Some((first, _)), Some((last, _))) = (commits.first(), commits.last()) {
+            for line in diff_stat(&format!("{first}^"), last) {
+                println!("    \x1b[2m{line}\x1b[0m");
+            }
+        }
+    }
+    println!();
+}
+
+/// Print each commit's full patch.
+fn print_diff(commits: &[(String, String)]) {
+    if commits.is_empty() {
+        println!("\x1b[2mNo commits to show.\x1b[0m");
+    }
+    for (sha, _) in commits {
+        if let Some(patch) = show_commit(sha) {
+            println!("{patch}");
+        }
+    }
+}
+
+/// Complete an approved task, syncing it to its source.
+fn approve(task_id: &str, source: &str) -> ReviewCommandResult {
+    let mut progress = SessionProgress::load(None)?;
+    progress.set_task_status(task_id, TaskStatus::Completed, source, None);
+    if let Some(task) = progress.tasks.get_mut(task_id) {
+        task.review_feedback.clear();
+    }
+    progress.save(None).map_err(ReviewCommandError::SaveError)?;
+    mark_story_complete(task_id)?;
+    Ok(())
+}
+
+/// Return a rejected task to pending, keeping the feedback for its next
+/// prompt.
+fn reject(task_id: &str, source: &str, feedback: Option<String>) -> ReviewCommandResult {
+    let mut progress = SessionProgress::load(None)?;
+    return_to_pending(&mut progress, task_id, source, feedback);
+    progress.save(None).map_err(ReviewCommandError::SaveError)
+}
+
+/// Ask for a new description and criteria, then return the task to pending.
+fn edit(story: &UserStory) -> ReviewCommandResult {
+    let mut update = TaskEdit {
+        description: ask("New description (empty to keep)"),
+        ..TaskEdit::default()
+    };
+    println!("New acceptance criteria, one per line (empty line to keep the current ones):");
+    let mut criteria = Vec::new();
+    while let Some(criterion) = ask("  -") {
+        criteria.push(criterion);
+    }
+    if !criteria.is_empty() {
+        update.acceptance_criteria = Some(criteria);
+    }
+
+    if update != TaskEdit::default() {
+        let mut prd = PrdDocument::load(None)?;
+        edit_story(&mut prd, &story.id, update)?;
+        prd.save(None)?;
+    }
+    let mut progress = SessionProgress::load(None)?;
+    return_to_pending(&mut progress, &story.id, &story.source, None);
+    progress.save(None).map_err(ReviewCommandError::SaveError)
+}
+
+/// Put a reviewed task back to pending, adding any feedback to what its
+/// next prompt shows.
+fn return_to_pending(
+    progress: &mut SessionProgress,
+    task_id: &str,
+    source: &str,
+    feedback: Option<String>,
+) {
+    progress.set_task_status(
+        task_id,
+        TaskStatus::Pending,
+        source,
+        Some(match &feedback {
+            Some(feedback) => format!("Rejected in review: {feedback}"),
+            None => "Returned to pending in review".to_string(),
+        }),
+    );
+    if let (Some(task), Some(feedback)) = (progress.tasks.get_mut(task_id), feedback) {
+        task.review_feedback.push(feedback);
+    }
+}
+
+/// Print a prompt and read a line. None at end of input.
+fn read_answer(label: &str) -> Option<String> {
+    print!("{label}: ");
+    let _ = io::stdout().flush();
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input.trim().to_string()),
+    }
+}
+
+/// Print a prompt and read a line. None on an empty line or end of input.
+fn ask(label: &str) -> Option<String> {
+    read_answer(label).filter(|i| !i.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choice_parse() {
+        assert_eq!(Choice::parse("a"), Some(Choice::Approve));
+        assert_eq!(Choice::parse(" Reject\n"), Some(Choice::Reject));
+        assert_eq!(Choice::parse("E"), Some(Choice::Edit));
+        assert_eq!(Choice::parse("d"), Some(Choice::Diff));
+        assert_eq!(Choice::parse(""), Some(Choice::Skip));
+        assert_eq!(Choice::parse("q"), Some(Choice::Quit));
+        assert_eq!(Choice::parse("maybe"), None);
+    }
+
+    #[test]
+    fn test_return_to_pending_keeps_feedback() {
+        let mut progress = SessionProgress::new();
+        progress.set_task_status("auth", TaskStatus::AwaitingReview, "json", None);
+
+        return_to_pending(
+            &mut progress,
+            "auth",
+            "json",
+            Some("Handle an empty username".to_string()),
+        );
+        return_to_pending(&mut progress, "auth", "json", None);
+
+        let task = progress.get_task("auth").unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.review_feedback, ["Handle an empty username"]);
+        assert_eq!(
+            task.message.as_deref(),
+            Some("Returned to pending in review")
+        );
+        assert!(!awaiting_review(&progress, "auth"));
+    }
+}
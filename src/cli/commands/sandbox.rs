@@ -0,0 +1,84 @@
+//! Sandbox command implementation.
+//!
+//! This module implements `afk sandbox`, which reviews and lands the work
+//! of `afk go --sandbox` runs: `diff` shows it, `apply` merges it into the
+//! current branch (or writes it out as a patch), and `discard` drops it.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::runner::{
+    apply_sandbox, discard_sandbox, sandbox_diff, sandbox_exists, sandbox_patch, SANDBOX_BRANCH,
+};
+
+/// Result type for sandbox command operations.
+pub type SandboxCommandResult = Result<(), SandboxCommandError>;
+
+/// Error type for sandbox command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum SandboxCommandError {
+    /// There is no sandbox to act on.
+    #[error("No sandbox. Start one with `afk go --sandbox`.")]
+    NoSandbox,
+    /// A git operation on the sandbox failed.
+    #[error("Sandbox {0}")]
+    GitError(String),
+    /// Failed to write the patch file.
+    #[error("Failed to write patch: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Print the sandbox's changes against the branch it started from.
+pub fn diff(stat: bool) -> SandboxCommandResult {
+    require_sandbox()?;
+    let diff = sandbox_diff(stat).map_err(SandboxCommandError::GitError)?;
+    if diff.is_empty() {
+        println!("\x1b[2mNo changes in the sandbox yet.\x1b[0m");
+    } else {
+        print!("{diff}");
+    }
+    Ok(())
+}
+
+/// Merge the sandbox into the current branch, or with `patch` write its
+/// commits to a file and keep it.
+pub fn apply(patch: Option<PathBuf>) -> SandboxCommandResult {
+    require_sandbox()?;
+    if let Some(path) = patch {
+        let (patch, commits) = sandbox_patch().map_err(SandboxCommandError::GitError)?;
+        fs::write(&path, patch)?;
+        println!(
+            "\x1b[32m✓\x1b[0m Wrote {commits} commit(s) to {}",
+            path.display()
+        );
+        println!(
+            "\x1b[2m  Apply with `git am {}`, then drop the sandbox with `afk sandbox discard`.\x1b[0m",
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let commits = apply_sandbox().map_err(SandboxCommandError::GitError)?;
+    if commits == 0 {
+        println!("\x1b[32m✓\x1b[0m Sandbox applied (no commits to merge; task state updated)");
+    } else {
+        println!("\x1b[32m✓\x1b[0m Merged {commits} commit(s) from {SANDBOX_BRANCH} and removed the sandbox");
+    }
+    Ok(())
+}
+
+/// Remove the sandbox and everything in it.
+pub fn discard() -> SandboxCommandResult {
+    require_sandbox()?;
+    discard_sandbox();
+    println!("\x1b[32m✓\x1b[0m Sandbox discarded");
+    Ok(())
+}
+
+fn require_sandbox() -> SandboxCommandResult {
+    if sandbox_exists() {
+        Ok(())
+    } else {
+        Err(SandboxCommandError::NoSandbox)
+    }
+}
@@ -0,0 +1,176 @@
+//! Scheduler command implementation.
+//!
+//! This module implements `afk scheduler`: `start` is a foreground daemon
+//! that starts `afk go` each time `schedule.cron` falls due and records how
+//! each run went in the event log, and `next` lists the upcoming run times.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::cli::commands::watch::{PidLock, WatchCommandError};
+use crate::config::{AfkConfig, ConfigError, SCHEDULER_PID_FILE};
+use crate::events::{record, Event};
+use crate::runner::CronSchedule;
+
+/// How often the daemon checks whether a run is due or it was interrupted.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Result type for scheduler command operations.
+pub type SchedulerCommandResult = Result<(), SchedulerCommandError>;
+
+/// Error type for scheduler command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum SchedulerCommandError {
+    /// Failed to load configuration.
+    #[error("Failed to load config: {0}")]
+    ConfigError(#[from] ConfigError),
+    /// No schedule is configured.
+    #[error("No schedule configured. Set one with `afk config set schedule.cron \"0 2 * * *\"`.")]
+    NoSchedule,
+    /// The configured cron expression is invalid.
+    #[error("Invalid schedule.cron: {0}")]
+    InvalidCron(String),
+    /// The schedule never matches a real date.
+    #[error("schedule.cron '{0}' never falls due")]
+    NeverDue(String),
+    /// Another `afk scheduler start` holds the PID file.
+    #[error("afk scheduler is already running (PID {0})")]
+    AlreadyRunning(u32),
+    /// Failed to write or read the PID file.
+    #[error("Failed to write PID file: {0}")]
+    PidFileError(String),
+}
+
+/// The configured schedule, with its cron expression.
+fn load_schedule() -> Result<(AfkConfig, CronSchedule, String), SchedulerCommandError> {
+    let config = AfkConfig::load(None)?;
+    let expr = config
+        .schedule
+        .cron
+        .clone()
+        .ok_or(SchedulerCommandError::NoSchedule)?;
+    let cron = CronSchedule::parse(&expr).map_err(SchedulerCommandError::InvalidCron)?;
+    Ok((config, cron, expr))
+}
+
+/// List the next `count` times the schedule falls due.
+pub fn next(count: usize) -> SchedulerCommandResult {
+    let (config, cron, expr) = load_schedule()?;
+    println!("\x1b[1mSchedule:\x1b[0m {expr}");
+    let mut after = Local::now();
+    for _ in 0..count {
+        let Some(at) = cron.next_after(after) else {
+            break;
+        };
+        println!("  {}", at.format("%a %Y-%m-%d %H:%M"));
+        after = at;
+    }
+    if let Some(iterations) = config.schedule.iterations {
+        println!("\x1b[2m  {iterations} iteration(s) per run\x1b[0m");
+    }
+    Ok(())
+}
+
+/// Run in the foreground, starting `afk go` whenever the schedule falls due.
+///
+/// The config is read again after each run, so schedule changes apply from
+/// the next one.
+pub fn start() -> SchedulerCommandResult {
+    let (mut config, mut cron, mut expr) = load_schedule()?;
+    let _lock = PidLock::acquire(Path::new(SCHEDULER_PID_FILE)).map_err(|e| match e {
+        WatchCommandError::AlreadyRunning(pid) => SchedulerCommandError::AlreadyRunning(pid),
+        other => SchedulerCommandError::PidFileError(other.to_string()),
+    })?;
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    if let Err(e) = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst)) {
+        eprintln!("\x1b[2mWarning: Could not set up Ctrl+C handler: {e}\x1b[0m");
+    }
+
+    println!("\x1b[1mafk scheduler\x1b[0m \x1b[2m(Ctrl+C to stop)\x1b[0m");
+    let mut after = Local::now();
+    while !interrupted.load(Ordering::SeqCst) {
+        let due = cron
+            .next_after(after)
+            .ok_or_else(|| SchedulerCommandError::NeverDue(expr.clone()))?;
+        println!(
+            "{} Next run at {} \x1b[2m({expr})\x1b[0m",
+            timestamp(),
+            due.format("%a %Y-%m-%d %H:%M")
+        );
+        if !wait_for(due, &interrupted) {
+            break;
+        }
+
+        run_scheduled(due, config.schedule.iterations);
+        after = Local::now().max(due);
+        match load_schedule() {
+            Ok(loaded) => (config, cron, expr) = loaded,
+            Err(e) => eprintln!("\x1b[33mWarning:\x1b[0m Keeping the previous schedule: {e}"),
+        }
+    }
+
+    println!("\n\x1b[2mScheduler stopped.\x1b[0m");
+    Ok(())
+}
+
+/// Sleep until `when`, returning false if interrupted first.
+fn wait_for(when: DateTime<Local>, interrupted: &AtomicBool) -> bool {
+    while Local::now() < when {
+        if interrupted.load(Ordering::SeqCst) {
+            return false;
+        }
+        std::thread::sleep(TICK);
+    }
+    !interrupted.load(Ordering::SeqCst)
+}
+
+/// Run `afk go` for a due run, wait for it, and record how it went.
+fn run_scheduled(due: DateTime<Local>, iterations: Option<u32>) {
+    let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("afk"));
+    let mut args = vec!["go".to_string()];
+    if let Some(iterations) = iterations {
+        args.push(iterations.to_string());
+    }
+    // Nobody is watching a TUI at 2am
+    args.extend(["--feedback".to_string(), "minimal".to_string()]);
+
+    println!(
+        "{} \x1b[1mStarting afk {}\x1b[0m",
+        timestamp(),
+        args.join(" ")
+    );
+    let start = Instant::now();
+    let status = Command::new(exe).args(&args).status();
+    let (success, exit_code) = match &status {
+        Ok(status) => (status.success(), status.code()),
+        Err(e) => {
+            eprintln!("\x1b[31mFailed to start afk go: {e}\x1b[0m");
+            (false, None)
+        }
+    };
+    let event = Event::ScheduledRun {
+        scheduled_for: due.format("%Y-%m-%d %H:%M").to_string(),
+        success,
+        exit_code,
+        duration_seconds: start.elapsed().as_secs_f64(),
+    };
+    if success {
+        println!("{} \x1b[32m✓\x1b[0m Scheduled run finished", timestamp());
+    } else if let Some(code) = exit_code {
+        eprintln!(
+            "{} \x1b[33mafk go exited with code {code}\x1b[0m",
+            timestamp()
+        );
+    }
+    record(event);
+}
+
+fn timestamp() -> String {
+    format!("\x1b[2m[{}]\x1b[0m", Local::now().format("%H:%M:%S"))
+}
@@ -0,0 +1,126 @@
+//! Schema command implementation.
+//!
+//! This module implements `afk schema <tasks|progress>`, which prints the
+//! JSON Schema for a state file, and `afk tasks validate`, which checks
+//! files against those schemas.
+
+use std::fs;
+use std::path::Path;
+
+use crate::config::{PROGRESS_FILE, TASKS_FILE};
+use crate::schema::{SchemaKind, SchemaViolation};
+
+/// Result type for schema command operations.
+pub type SchemaCommandResult<T> = Result<T, SchemaCommandError>;
+
+/// Error type for schema command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum SchemaCommandError {
+    /// The file to validate does not exist.
+    #[error("File not found: {0}")]
+    NotFound(String),
+    /// Failed to read the file.
+    #[error("Failed to read {0}: {1}")]
+    ReadError(String, std::io::Error),
+    /// The file is not JSON at all.
+    #[error("{0} is not valid JSON: {1}")]
+    ParseError(String, serde_json::Error),
+}
+
+/// Print the schema for a state file.
+pub fn schema(kind: SchemaKind) -> SchemaCommandResult<()> {
+    println!("{}", kind.text().trim_end());
+    Ok(())
+}
+
+/// Check a file against a schema, returning its violations.
+pub fn check_file(path: &Path, kind: SchemaKind) -> SchemaCommandResult<Vec<SchemaViolation>> {
+    let name = path.display().to_string();
+    if !path.exists() {
+        return Err(SchemaCommandError::NotFound(name));
+    }
+    let contents =
+        fs::read_to_string(path).map_err(|e| SchemaCommandError::ReadError(name.clone(), e))?;
+    let document =
+        serde_json::from_str(&contents).map_err(|e| SchemaCommandError::ParseError(name, e))?;
+    Ok(kind.validate(&document))
+}
+
+/// Validate a tasks file, or `.afk/tasks.json` and `.afk/progress.json`
+/// (where they exist) when no path is given.
+///
+/// Returns whether every file matched its schema.
+pub fn validate_tasks(path: Option<&Path>) -> SchemaCommandResult<bool> {
+    let files: Vec<(&Path, SchemaKind)> = match path {
+        Some(path) => vec![(path, SchemaKind::Tasks)],
+        None => [
+            (Path::new(TASKS_FILE), SchemaKind::Tasks),
+            (Path::new(PROGRESS_FILE), SchemaKind::Progress),
+        ]
+        .into_iter()
+        .filter(|(path, _)| path.exists())
+        .collect(),
+    };
+    if files.is_empty() {
+        return Err(SchemaCommandError::NotFound(TASKS_FILE.to_string()));
+    }
+
+    let mut all_valid = true;
+    for (path, kind) in files {
+        let violations = check_file(path, kind)?;
+        if violations.is_empty() {
+            println!("\x1b[32m✓\x1b[0m {} is valid", path.display());
+            continue;
+        }
+        all_valid = false;
+        println!(
+            "\x1b[31m✗\x1b[0m {} has {} problem(s):",
+            path.display(),
+            violations.len()
+        );
+        for violation in violations {
+            println!("  {violation}");
+        }
+    }
+    Ok(all_valid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_check_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("tasks.json");
+
+        fs::write(&path, r#"{"userStories": [{"id": "a", "passes": "no"}]}"#).unwrap();
+        let violations = check_file(&path, SchemaKind::Tasks).unwrap();
+        assert_eq!(
+            violations[0].to_string(),
+            "$.userStories[0].passes: expected boolean, found string"
+        );
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(
+            check_file(&path, SchemaKind::Tasks),
+            Err(SchemaCommandError::ParseError(..))
+        ));
+        assert!(matches!(
+            check_file(&temp.path().join("missing.json"), SchemaKind::Tasks),
+            Err(SchemaCommandError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_tasks_reports_validity() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("tasks.json");
+        fs::write(&path, r#"{"userStories": [{"id": "a"}]}"#).unwrap();
+        assert!(validate_tasks(Some(&path)).unwrap());
+
+        fs::write(&path, r#"{"userStories": [{"title": "no id"}]}"#).unwrap();
+        assert!(!validate_tasks(Some(&path)).unwrap());
+    }
+}
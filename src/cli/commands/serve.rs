@@ -0,0 +1,45 @@
+//! Serve command implementation.
+//!
+//! This module implements `afk serve`, which runs the dashboard and HTTP
+//! API in [`crate::server`] until interrupted.
+
+use crate::config::{AfkConfig, ConfigError};
+use crate::server::{Server, ServerError};
+
+/// Result type for serve command operations.
+pub type ServeCommandResult = Result<(), ServeCommandError>;
+
+/// Error type for serve command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ServeCommandError {
+    /// Failed to load the config.
+    #[error("Failed to load config: {0}")]
+    ConfigError(#[from] ConfigError),
+    /// The server could not start or stopped.
+    #[error("{0}")]
+    ServerError(#[from] ServerError),
+}
+
+/// Serve the API on the configured address, with `host` and `port`
+/// overriding `server.host` and `server.port`.
+pub fn serve(host: Option<String>, port: Option<u16>) -> ServeCommandResult {
+    let mut config = AfkConfig::load(None)?.server;
+    if let Some(host) = host {
+        config.host = host;
+    }
+    if let Some(port) = port {
+        config.port = port;
+    }
+
+    let server = Server::bind(&config)?;
+    let addr = server.local_addr().map_err(ServerError::from)?;
+    println!("\x1b[32m✓\x1b[0m Serving the afk dashboard and API on http://{addr}");
+    if server.requires_token() {
+        println!("\x1b[2m  Send the token as `Authorization: Bearer <token>`.\x1b[0m");
+    } else {
+        println!("\x1b[2m  No token set; only this machine can connect.\x1b[0m");
+    }
+    println!("\x1b[2m  Endpoints: /status /tasks /iteration /events; POST /pause /resume /stop /skip. Ctrl+C to stop.\x1b[0m");
+    server.run()?;
+    Ok(())
+}
@@ -28,6 +28,13 @@ pub enum SourceCommandError {
         /// Maximum valid index.
         max: usize,
     },
+    /// Source needs an ID (Notion database, Trello board, Jira project) but
+    /// none was given.
+    #[error("{0} source requires an ID: afk source add {0} <id>")]
+    MissingId(String),
+    /// Command source added without an executable.
+    #[error("command source requires an executable: afk source add command <path>")]
+    MissingCommand,
     /// No sources are configured.
     #[error("No sources configured")]
     NoSources,
@@ -41,7 +48,10 @@ pub enum SourceCommandError {
 /// # Arguments
 ///
 /// * `source_type` - The type of source to add (beads, json, markdown, github).
-/// * `path` - Optional path for file-based sources (json, markdown).
+/// * `path` - Optional path for file-based sources (json, markdown), or the
+///   directory for stories-dir.
+/// * `writeback` - Write completion back to the source (beads, markdown
+///   and stories-dir only).
 ///
 /// # Returns
 ///
@@ -50,26 +60,30 @@ pub enum SourceCommandError {
 /// # Example
 ///
 /// ```ignore
-/// source_add("json", Some("tasks.json"))?;
-/// source_add("beads", None)?;
+/// source_add("json", Some("tasks.json"), false)?;
+/// source_add("beads", None, false)?;
 /// ```
-pub fn source_add(source_type: &str, path: Option<&str>) -> SourceCommandResult {
-    source_add_impl(source_type, path, None)
+pub fn source_add(source_type: &str, path: Option<&str>, writeback: bool) -> SourceCommandResult {
+    source_add_impl(source_type, path, writeback, None)
 }
 
 /// Internal implementation of source_add with optional config path for testing.
 fn source_add_impl(
     source_type: &str,
     path: Option<&str>,
+    writeback: bool,
     config_path: Option<&Path>,
 ) -> SourceCommandResult {
-    let mut config = AfkConfig::load(config_path)?;
+    let mut config = AfkConfig::load_base(config_path)?;
 
     // Parse and validate source type
     let source_type_enum = parse_source_type(source_type)?;
 
     // Validate path exists for file-based sources
-    if matches!(source_type_enum, SourceType::Json | SourceType::Markdown) {
+    if matches!(
+        source_type_enum,
+        SourceType::Json | SourceType::Markdown | SourceType::StoriesDir | SourceType::Csv
+    ) {
         if let Some(p) = path {
             if !Path::new(p).exists() {
                 return Err(SourceCommandError::FileNotFound(p.to_string()));
@@ -78,7 +92,7 @@ fn source_add_impl(
     }
 
     // Create the source configuration
-    let new_source = match source_type_enum {
+    let mut new_source = match source_type_enum {
         SourceType::Beads => SourceConfig::beads(),
         SourceType::Json => SourceConfig::json(path.unwrap_or(".afk/tasks.json")),
         SourceType::Markdown => SourceConfig::markdown(path.unwrap_or("TODO.md")),
@@ -100,8 +114,30 @@ fn source_add_impl(
             SourceConfig::github(&repo, vec![])
         }
         SourceType::Openspec => SourceConfig::openspec(),
+        SourceType::StoriesDir => SourceConfig::stories_dir(path.unwrap_or("stories")),
+        SourceType::Csv => SourceConfig::csv(path.unwrap_or(crate::sources::csv::DEFAULT_PATH)),
+        SourceType::Notion | SourceType::Trello | SourceType::Jira => {
+            let Some(id) = path.filter(|p| !p.is_empty()) else {
+                return Err(SourceCommandError::MissingId(source_type.to_lowercase()));
+            };
+            match source_type_enum {
+                SourceType::Notion => SourceConfig::notion(id),
+                SourceType::Trello => SourceConfig::trello(id),
+                _ => SourceConfig::jira(id),
+            }
+        }
+        SourceType::Command => match path.filter(|p| !p.is_empty()) {
+            Some(executable) => SourceConfig::command(executable),
+            None => return Err(SourceCommandError::MissingCommand),
+        },
     };
 
+    new_source.writeback = writeback
+        && matches!(
+            source_type_enum,
+            SourceType::Beads | SourceType::Markdown | SourceType::StoriesDir
+        );
+
     // GitHub source: only allow one - replace any existing
     let replaced = if source_type_enum == SourceType::Github {
         let had_github = config
@@ -153,6 +189,21 @@ fn source_list_impl(config_path: Option<&Path>) -> SourceCommandResult {
                 .as_ref()
                 .map(|r| format!(" ({r})"))
                 .unwrap_or_default(),
+            SourceType::Notion => src
+                .database
+                .as_ref()
+                .map(|d| format!(" ({d})"))
+                .unwrap_or_default(),
+            SourceType::Trello => src
+                .board
+                .as_ref()
+                .map(|b| format!(" ({b})"))
+                .unwrap_or_default(),
+            SourceType::Jira => src
+                .project
+                .as_ref()
+                .map(|p| format!(" ({p})"))
+                .unwrap_or_default(),
             _ => src
                 .path
                 .as_ref()
@@ -181,7 +232,7 @@ pub fn source_remove(index: usize) -> SourceCommandResult {
 
 /// Internal implementation of source_remove with optional config path for testing.
 fn source_remove_impl(index: usize, config_path: Option<&Path>) -> SourceCommandResult {
-    let mut config = AfkConfig::load(config_path)?;
+    let mut config = AfkConfig::load_base(config_path)?;
 
     if config.sources.is_empty() {
         return Err(SourceCommandError::NoSources);
@@ -211,18 +262,30 @@ fn parse_source_type(s: &str) -> Result<SourceType, SourceCommandError> {
         "markdown" => Ok(SourceType::Markdown),
         "github" => Ok(SourceType::Github),
         "openspec" => Ok(SourceType::Openspec),
+        "stories-dir" | "stories_dir" | "stories" => Ok(SourceType::StoriesDir),
+        "notion" => Ok(SourceType::Notion),
+        "trello" => Ok(SourceType::Trello),
+        "jira" => Ok(SourceType::Jira),
+        "command" => Ok(SourceType::Command),
+        "csv" | "tsv" => Ok(SourceType::Csv),
         _ => Err(SourceCommandError::InvalidSourceType(s.to_string())),
     }
 }
 
 /// Convert a SourceType enum to its string representation.
-fn source_type_to_str(st: &SourceType) -> &'static str {
+pub(crate) fn source_type_to_str(st: &SourceType) -> &'static str {
     match st {
         SourceType::Beads => "beads",
         SourceType::Json => "json",
         SourceType::Markdown => "markdown",
         SourceType::Github => "github",
         SourceType::Openspec => "openspec",
+        SourceType::StoriesDir => "stories-dir",
+        SourceType::Notion => "notion",
+        SourceType::Trello => "trello",
+        SourceType::Jira => "jira",
+        SourceType::Command => "command",
+        SourceType::Csv => "csv",
     }
 }
 
@@ -247,6 +310,10 @@ mod tests {
         assert_eq!(parse_source_type("json").unwrap(), SourceType::Json);
         assert_eq!(parse_source_type("markdown").unwrap(), SourceType::Markdown);
         assert_eq!(parse_source_type("github").unwrap(), SourceType::Github);
+        assert_eq!(
+            parse_source_type("stories-dir").unwrap(),
+            SourceType::StoriesDir
+        );
     }
 
     #[test]
@@ -273,13 +340,14 @@ mod tests {
         assert_eq!(source_type_to_str(&SourceType::Json), "json");
         assert_eq!(source_type_to_str(&SourceType::Markdown), "markdown");
         assert_eq!(source_type_to_str(&SourceType::Github), "github");
+        assert_eq!(source_type_to_str(&SourceType::StoriesDir), "stories-dir");
     }
 
     #[test]
     fn test_source_add_beads() {
         let (_temp, config_path) = setup_temp_config();
 
-        let result = source_add_impl("beads", None, Some(&config_path));
+        let result = source_add_impl("beads", None, false, Some(&config_path));
         assert!(result.is_ok());
 
         // Verify config was updated
@@ -299,6 +367,7 @@ mod tests {
         let result = source_add_impl(
             "json",
             Some(json_path.to_str().unwrap()),
+            false,
             Some(&config_path),
         );
         assert!(result.is_ok());
@@ -313,7 +382,12 @@ mod tests {
     fn test_source_add_json_file_not_found() {
         let (_temp, config_path) = setup_temp_config();
 
-        let result = source_add_impl("json", Some("/nonexistent/path.json"), Some(&config_path));
+        let result = source_add_impl(
+            "json",
+            Some("/nonexistent/path.json"),
+            false,
+            Some(&config_path),
+        );
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -328,6 +402,7 @@ mod tests {
         let result = source_add_impl(
             "markdown",
             Some("/nonexistent/tasks.md"),
+            false,
             Some(&config_path),
         );
         assert!(result.is_err());
@@ -347,16 +422,18 @@ mod tests {
         let md_path = temp.path().join("TODO.md");
         fs::write(&md_path, "").unwrap();
 
-        source_add_impl("beads", None, Some(&config_path)).unwrap();
+        source_add_impl("beads", None, false, Some(&config_path)).unwrap();
         source_add_impl(
             "json",
             Some(json_path.to_str().unwrap()),
+            false,
             Some(&config_path),
         )
         .unwrap();
         source_add_impl(
             "markdown",
             Some(md_path.to_str().unwrap()),
+            false,
             Some(&config_path),
         )
         .unwrap();
@@ -478,7 +555,7 @@ mod tests {
     fn test_source_add_github() {
         let (_temp, config_path) = setup_temp_config();
 
-        let result = source_add_impl("github", Some("owner/repo"), Some(&config_path));
+        let result = source_add_impl("github", Some("owner/repo"), false, Some(&config_path));
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -499,6 +576,12 @@ mod tests {
 
         let err = SourceCommandError::NoSources;
         assert_eq!(err.to_string(), "No sources configured");
+
+        let err = SourceCommandError::MissingId("notion".to_string());
+        assert_eq!(
+            err.to_string(),
+            "notion source requires an ID: afk source add notion <id>"
+        );
     }
 
     #[test]
@@ -512,7 +595,7 @@ mod tests {
         // Note: Without changing cwd, the default path check won't find the file.
         // In this test, we just verify that when no path is given,
         // the source is created with the default path value.
-        let result = source_add_impl("json", None, Some(&config_path));
+        let result = source_add_impl("json", None, false, Some(&config_path));
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -520,6 +603,102 @@ mod tests {
         assert_eq!(config.sources[0].path, Some(".afk/tasks.json".to_string()));
     }
 
+    #[test]
+    fn test_source_add_csv() {
+        let (temp, config_path) = setup_temp_config();
+        let sheet = temp.path().join("backlog.tsv");
+        assert!(matches!(
+            source_add_impl("tsv", sheet.to_str(), false, Some(&config_path)),
+            Err(SourceCommandError::FileNotFound(_))
+        ));
+        fs::write(&sheet, "Title\nLogin\n").unwrap();
+        source_add_impl("tsv", sheet.to_str(), false, Some(&config_path)).unwrap();
+
+        let config = AfkConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.sources[0].source_type, SourceType::Csv);
+        assert_eq!(config.sources[0].path.as_deref(), sheet.to_str());
+        assert_eq!(source_type_to_str(&SourceType::Csv), "csv");
+    }
+
+    #[test]
+    fn test_source_add_stories_dir_with_writeback() {
+        let (temp, config_path) = setup_temp_config();
+        let stories = temp.path().join("stories");
+        fs::create_dir_all(&stories).unwrap();
+
+        source_add_impl("stories-dir", stories.to_str(), true, Some(&config_path)).unwrap();
+        // Writeback is ignored for sources that don't support it
+        source_add_impl("openspec", None, true, Some(&config_path)).unwrap();
+        let todo = temp.path().join("TODO.md");
+        fs::write(&todo, "- [ ] Task\n").unwrap();
+        source_add_impl("markdown", todo.to_str(), true, Some(&config_path)).unwrap();
+
+        let config = AfkConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.sources[0].source_type, SourceType::StoriesDir);
+        assert!(config.sources[0].writeback);
+        assert!(!config.sources[1].writeback);
+        assert!(config.sources[2].writeback);
+    }
+
+    #[test]
+    fn test_source_add_notion_and_trello() {
+        let (_temp, config_path) = setup_temp_config();
+
+        source_add_impl("notion", Some("abc123"), false, Some(&config_path)).unwrap();
+        source_add_impl("trello", Some("board42"), false, Some(&config_path)).unwrap();
+        assert!(matches!(
+            source_add_impl("trello", None, false, Some(&config_path)),
+            Err(SourceCommandError::MissingId(_))
+        ));
+
+        let config = AfkConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.sources.len(), 2);
+        assert_eq!(config.sources[0].database.as_deref(), Some("abc123"));
+        assert_eq!(config.sources[1].board.as_deref(), Some("board42"));
+    }
+
+    #[test]
+    fn test_source_add_jira() {
+        let (_temp, config_path) = setup_temp_config();
+
+        source_add_impl("jira", Some("SHOP"), false, Some(&config_path)).unwrap();
+        assert!(matches!(
+            source_add_impl("jira", None, false, Some(&config_path)),
+            Err(SourceCommandError::MissingId(_))
+        ));
+
+        let config = AfkConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.sources[0].source_type, SourceType::Jira);
+        assert_eq!(config.sources[0].project.as_deref(), Some("SHOP"));
+        assert_eq!(parse_source_type("Jira").unwrap(), SourceType::Jira);
+        assert_eq!(source_type_to_str(&SourceType::Jira), "jira");
+    }
+
+    #[test]
+    fn test_source_add_command() {
+        let (_temp, config_path) = setup_temp_config();
+
+        source_add_impl(
+            "command",
+            Some("./scripts/tracker.sh"),
+            false,
+            Some(&config_path),
+        )
+        .unwrap();
+        assert!(matches!(
+            source_add_impl("command", None, false, Some(&config_path)),
+            Err(SourceCommandError::MissingCommand)
+        ));
+
+        let config = AfkConfig::load(Some(&config_path)).unwrap();
+        assert_eq!(config.sources[0].source_type, SourceType::Command);
+        assert_eq!(
+            config.sources[0].path.as_deref(),
+            Some("./scripts/tracker.sh")
+        );
+        assert_eq!(source_type_to_str(&SourceType::Command), "command");
+    }
+
     #[test]
     fn test_source_list_github_shows_repo() {
         let (_temp, config_path) = setup_temp_config();
@@ -540,7 +719,12 @@ mod tests {
         let (_temp, config_path) = setup_temp_config();
 
         // Add first GitHub source
-        let result = source_add_impl("github", Some("owner/first-repo"), Some(&config_path));
+        let result = source_add_impl(
+            "github",
+            Some("owner/first-repo"),
+            false,
+            Some(&config_path),
+        );
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -548,7 +732,12 @@ mod tests {
         assert_eq!(config.sources[0].repo, Some("owner/first-repo".to_string()));
 
         // Add second GitHub source - should replace, not add
-        let result = source_add_impl("github", Some("owner/second-repo"), Some(&config_path));
+        let result = source_add_impl(
+            "github",
+            Some("owner/second-repo"),
+            false,
+            Some(&config_path),
+        );
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -564,16 +753,16 @@ mod tests {
         let (_temp, config_path) = setup_temp_config();
 
         // Add beads source first
-        source_add_impl("beads", None, Some(&config_path)).unwrap();
+        source_add_impl("beads", None, false, Some(&config_path)).unwrap();
 
         // Add GitHub source
-        source_add_impl("github", Some("owner/repo1"), Some(&config_path)).unwrap();
+        source_add_impl("github", Some("owner/repo1"), false, Some(&config_path)).unwrap();
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
         assert_eq!(config.sources.len(), 2);
 
         // Replace GitHub source - beads should remain
-        source_add_impl("github", Some("owner/repo2"), Some(&config_path)).unwrap();
+        source_add_impl("github", Some("owner/repo2"), false, Some(&config_path)).unwrap();
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
         assert_eq!(config.sources.len(), 2); // beads + github
@@ -587,7 +776,7 @@ mod tests {
         let (_temp, config_path) = setup_temp_config();
 
         // Add GitHub source with explicit repo - should use provided value
-        source_add_impl("github", Some("explicit/repo"), Some(&config_path)).unwrap();
+        source_add_impl("github", Some("explicit/repo"), false, Some(&config_path)).unwrap();
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
         assert_eq!(config.sources.len(), 1);
@@ -601,7 +790,7 @@ mod tests {
 
         // Add GitHub source with empty string - should attempt inference
         // (will get empty string if not in a git repo with GitHub remote)
-        let result = source_add_impl("github", Some(""), Some(&config_path));
+        let result = source_add_impl("github", Some(""), false, Some(&config_path));
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -615,7 +804,7 @@ mod tests {
         let (_temp, config_path) = setup_temp_config();
 
         // Add GitHub source with None - should attempt inference
-        let result = source_add_impl("github", None, Some(&config_path));
+        let result = source_add_impl("github", None, false, Some(&config_path));
         assert!(result.is_ok());
 
         let config = AfkConfig::load(Some(&config_path)).unwrap();
@@ -0,0 +1,275 @@
+//! Stats command implementation.
+//!
+//! This module implements `afk stats`, which reports metrics across the
+//! archived sessions in `.afk/archive/`, the current session and the event
+//! log, as text, CSV or JSON.
+
+use std::path::Path;
+
+use chrono::{Local, NaiveDate};
+
+use crate::cli::output::json::{self, StatsJson, SCHEMA_VERSION};
+use crate::config::EVENTS_FILE;
+use crate::events;
+use crate::progress::burndown::sparkline;
+use crate::progress::stats::{parse_since, Stats};
+use crate::progress::{list_archives, SessionSnapshot, CURRENT_SESSION};
+use crate::sources::csv::format_record;
+
+/// Failure reasons listed in the text report.
+const TOP_FAILURES: usize = 5;
+
+/// Days of usage listed in the text report.
+const RECENT_DAYS: usize = 14;
+
+/// Result type for stats command operations.
+pub type StatsCommandResult = Result<(), StatsCommandError>;
+
+/// Error type for stats command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum StatsCommandError {
+    /// `--since` could not be parsed.
+    #[error("{0}")]
+    InvalidSince(String),
+    /// An unknown output format was requested.
+    #[error("Unknown format '{0}' (use text, csv or json)")]
+    UnknownFormat(String),
+}
+
+/// Show metrics across sessions started on or after `since`, in `format`
+/// ("text", "csv" or "json").
+pub fn stats(since: Option<&str>, format: &str) -> StatsCommandResult {
+    let since = since
+        .map(|s| parse_since(s, Local::now().date_naive()))
+        .transpose()
+        .map_err(StatsCommandError::InvalidSince)?;
+    let stats = Stats::collect(
+        &load_sessions(),
+        &events::load(Path::new(EVENTS_FILE)),
+        since,
+    );
+    match format {
+        "text" => print_text(&stats, since),
+        "csv" => print!("{}", to_csv(&stats)),
+        "json" => json::print(&StatsJson {
+            schema_version: SCHEMA_VERSION,
+            since: since.map(|d| d.to_string()),
+            stats,
+        }),
+        other => return Err(StatsCommandError::UnknownFormat(other.to_string())),
+    }
+    Ok(())
+}
+
+/// Every archived session, oldest first, then the current one if any.
+fn load_sessions() -> Vec<SessionSnapshot> {
+    let mut names: Vec<String> = list_archives()
+        .unwrap_or_default()
+        .into_iter()
+        .rev()
+        .map(|(name, _)| name)
+        .collect();
+    if Path::new(crate::config::PROGRESS_FILE).exists() {
+        names.push(CURRENT_SESSION.to_string());
+    }
+    names
+        .iter()
+        .filter_map(|name| SessionSnapshot::load(name).ok())
+        .collect()
+}
+
+fn print_text(stats: &Stats, since: Option<NaiveDate>) {
+    let window = since.map_or(String::new(), |d| format!(" since {d}"));
+    println!("\x1b[1m=== afk stats{window} ===\x1b[0m");
+    println!();
+    if stats.sessions == 0 && stats.usage.is_empty() {
+        println!("\x1b[2mNo sessions to report on yet.\x1b[0m");
+        return;
+    }
+
+    let (attempted, completed) = stats.totals();
+    println!(
+        "Sessions:   {} \x1b[2m({} iterations)\x1b[0m",
+        stats.sessions, stats.iterations
+    );
+    println!(
+        "Tasks:      {completed} of {attempted} attempted completed \x1b[2m({})\x1b[0m",
+        percent(completed, attempted)
+    );
+    if let Some(per_task) = stats.iterations_per_task {
+        println!("Iterations: {per_task:.1} per completed task");
+    }
+
+    if !stats.sources.is_empty() {
+        println!();
+        println!("\x1b[1mBy source\x1b[0m");
+        let width = stats
+            .sources
+            .iter()
+            .map(|s| s.source.len())
+            .max()
+            .unwrap_or(0);
+        for source in &stats.sources {
+            println!(
+                "  {:<width$}  {:>4} {:>3} attempted, {} completed, {} failed, {} skipped",
+                source.source,
+                percent(source.completed, source.attempted),
+                source.attempted,
+                source.completed,
+                source.failed,
+                source.skipped
+            );
+        }
+    }
+
+    if !stats.failures.is_empty() {
+        println!();
+        println!("\x1b[1mFailure reasons\x1b[0m");
+        for failure in stats.failures.iter().take(TOP_FAILURES) {
+            println!("  \x1b[31m{:>3}×\x1b[0m {}", failure.count, failure.reason);
+        }
+        let more = stats.failures.len().saturating_sub(TOP_FAILURES);
+        if more > 0 {
+            println!("  \x1b[2m… and {more} more (--format json for all)\x1b[0m");
+        }
+    }
+
+    if !stats.usage.is_empty() {
+        println!();
+        println!("\x1b[1mUsage by day\x1b[0m");
+        let recent = &stats.usage[stats.usage.len().saturating_sub(RECENT_DAYS)..];
+        for day in recent {
+            let cost = day.cost_usd.map_or("-".to_string(), |c| format!("${c:.2}"));
+            println!(
+                "  {}  {:>3} iterations  {:>9} tokens  {cost:>8}",
+                day.date,
+                day.iterations,
+                day.input_tokens + day.output_tokens
+            );
+        }
+        // Cents, so the sparkline has whole values to scale
+        let cents: Vec<usize> = stats
+            .usage
+            .iter()
+            .map(|d| (d.cost_usd.unwrap_or(0.0) * 100.0).round() as usize)
+            .collect();
+        if let Some(total) = stats.total_cost() {
+            println!(
+                "  Cost: \x1b[1m${total:.2}\x1b[0m over {} days {}",
+                stats.usage.len(),
+                sparkline(&cents, RECENT_DAYS * 2)
+            );
+        }
+    }
+}
+
+/// A share as a whole percentage, "-" when there is nothing to share.
+fn percent(part: usize, whole: usize) -> String {
+    if whole == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.0}%", part as f64 * 100.0 / whole as f64)
+    }
+}
+
+/// The stats as CSV, one `metric,group,value` row per figure.
+fn to_csv(stats: &Stats) -> String {
+    let mut rows: Vec<(String, String, String)> = vec![
+        ("sessions".into(), String::new(), stats.sessions.to_string()),
+        (
+            "iterations".into(),
+            String::new(),
+            stats.iterations.to_string(),
+        ),
+    ];
+    if let Some(per_task) = stats.iterations_per_task {
+        rows.push((
+            "iterations_per_task".into(),
+            String::new(),
+            format!("{per_task:.2}"),
+        ));
+    }
+    for source in &stats.sources {
+        for (metric, value) in [
+            ("attempted", source.attempted.to_string()),
+            ("completed", source.completed.to_string()),
+            ("failed", source.failed.to_string()),
+            ("skipped", source.skipped.to_string()),
+            ("success_rate", format!("{:.4}", source.success_rate())),
+        ] {
+            rows.push((metric.into(), source.source.clone(), value));
+        }
+    }
+    for failure in &stats.failures {
+        rows.push((
+            "failure_reason".into(),
+            failure.reason.clone(),
+            failure.count.to_string(),
+        ));
+    }
+    for day in &stats.usage {
+        rows.push((
+            "iterations".into(),
+            day.date.clone(),
+            day.iterations.to_string(),
+        ));
+        rows.push((
+            "input_tokens".into(),
+            day.date.clone(),
+            day.input_tokens.to_string(),
+        ));
+        rows.push((
+            "output_tokens".into(),
+            day.date.clone(),
+            day.output_tokens.to_string(),
+        ));
+        if let Some(cost) = day.cost_usd {
+            rows.push(("cost_usd".into(), day.date.clone(), format!("{cost:.4}")));
+        }
+    }
+
+    let mut csv = String::from("metric,group,value\n");
+    for (metric, group, value) in rows {
+        csv.push_str(&format_record(&[metric, group, value], ','));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::stats::{FailureReason, SourceStats};
+
+    #[test]
+    fn test_to_csv() {
+        let stats = Stats {
+            sessions: 2,
+            iterations: 7,
+            sources: vec![SourceStats {
+                source: "github".to_string(),
+                attempted: 4,
+                completed: 3,
+                failed: 1,
+                skipped: 0,
+            }],
+            iterations_per_task: Some(1.5),
+            failures: vec![FailureReason {
+                reason: "Gates failed: lint, test".to_string(),
+                count: 1,
+            }],
+            usage: Vec::new(),
+        };
+        let csv = to_csv(&stats);
+        assert!(csv.starts_with("metric,group,value\nsessions,,2\niterations,,7\n"));
+        assert!(csv.contains("iterations_per_task,,1.50\n"));
+        assert!(csv.contains("success_rate,github,0.7500\n"));
+        assert!(csv.contains("failure_reason,\"Gates failed: lint, test\",1\n"));
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(2, 3), "67%");
+        assert_eq!(percent(0, 0), "-");
+    }
+}
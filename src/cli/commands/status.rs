@@ -4,9 +4,19 @@
 
 use std::path::Path;
 
-use crate::config::AfkConfig;
-use crate::prd::PrdDocument;
-use crate::progress::{SessionProgress, TaskStatus};
+use crate::cli::output::json::{
+    self, AiCliJson, BurndownJson, EpicJson, EscalationJson, OverrunJson, SessionJson,
+    StatusCountsJson, StatusDetails, StatusJson, TaskSummaryJson,
+};
+use crate::config::{AfkConfig, AiBackend, SourceConfig, SourceType, STOP_FILE};
+use crate::prd::{PrdDocument, TaskOrder};
+use crate::progress::{
+    burndown, session_samples, SessionProgress, TaskProgress, TaskStatus, Trend,
+};
+use crate::runner::{control_state, ControlState};
+
+/// Burn-down points drawn in the status sparkline.
+const BURNDOWN_WIDTH: usize = 24;
 
 /// Result type for status command operations.
 pub type StatusCommandResult = Result<(), StatusCommandError>;
@@ -20,7 +30,14 @@ pub enum StatusCommandError {
 }
 
 /// Execute the status command.
-pub fn status(verbose: bool) -> StatusCommandResult {
+///
+/// With `as_json`, prints [`StatusJson`] instead; `verbose` is ignored.
+pub fn status(verbose: bool, as_json: bool) -> StatusCommandResult {
+    if as_json {
+        json::print(&status_json());
+        return Ok(());
+    }
+
     // Check if initialised
     if !Path::new(".afk").exists() {
         println!("\x1b[33mafk not initialised.\x1b[0m");
@@ -45,6 +62,23 @@ pub fn status(verbose: bool) -> StatusCommandResult {
     } else {
         println!("  Total: {total} ({completed} complete, {pending} pending)");
 
+        let (criteria_met, criteria_total) = prd.get_criteria_counts();
+        if let Some(percent) = (criteria_met * 100).checked_div(criteria_total) {
+            println!("  Criteria: {criteria_met}/{criteria_total} met ({percent}%)");
+        }
+
+        let epics: Vec<String> = prd
+            .get_epic_counts()
+            .into_iter()
+            .map(|(epic, completed, total)| {
+                let colour = if completed == total { "32" } else { "0" };
+                format!("{epic} \x1b[{colour}m{completed}/{total}\x1b[0m")
+            })
+            .collect();
+        if !epics.is_empty() {
+            println!("  Epics: {}", epics.join(", "));
+        }
+
         // Show current in-progress task(s)
         let in_progress_tasks = progress.get_in_progress_tasks();
         for task in &in_progress_tasks {
@@ -65,7 +99,7 @@ pub fn status(verbose: bool) -> StatusCommandResult {
         }
 
         // Show next pending task
-        if let Some(next) = prd.get_next_story() {
+        if let Some(next) = prd.get_next_story(&TaskOrder::new(&config, &progress)) {
             let title = if next.title.len() > 50 {
                 format!("{}...", &next.title[..47])
             } else {
@@ -73,6 +107,33 @@ pub fn status(verbose: bool) -> StatusCommandResult {
             };
             println!("  Next: \x1b[36m{}\x1b[0m - {}", next.id, title);
         }
+
+        for (task, model) in escalated_tasks(&config, &progress) {
+            println!(
+                "  Escalated: \x1b[33m{}\x1b[0m on {model} after {} failures ({})",
+                task.id,
+                task.failure_count,
+                model_history(&task.models)
+            );
+        }
+
+        let mut awaiting: Vec<&str> = progress
+            .tasks
+            .values()
+            .filter(|t| t.status == TaskStatus::AwaitingReview)
+            .map(|t| t.id.as_str())
+            .collect();
+        if !awaiting.is_empty() {
+            awaiting.sort_unstable();
+            println!(
+                "  Awaiting review: \x1b[35m{}\x1b[0m (`afk review`)",
+                awaiting.join(", ")
+            );
+        }
+
+        if let Some(line) = burndown::describe(&burndown::load(None), BURNDOWN_WIDTH) {
+            println!("  Burn-down: {line}");
+        }
     }
     println!();
 
@@ -83,6 +144,15 @@ pub fn status(verbose: bool) -> StatusCommandResult {
         &progress.started_at[..19].replace('T', " ")
     );
     println!("  Iterations: {}", progress.iterations);
+    match control_state() {
+        ControlState::Running => {}
+        ControlState::Paused => {
+            println!("  \x1b[33mPaused\x1b[0m (`afk resume` to carry on)");
+        }
+        ControlState::StopRequested => {
+            println!("  \x1b[33mStop requested\x1b[0m ({STOP_FILE})");
+        }
+    }
 
     // Calculate task counts from PRD with session status overlays
     // This ensures counts are consistent with the Tasks section
@@ -93,6 +163,19 @@ pub fn status(verbose: bool) -> StatusCommandResult {
             pend, in_prog, comp, fail, skip
         );
     }
+
+    // Flag tasks that have taken far longer than their estimate
+    for sample in session_samples(&prd, &progress)
+        .iter()
+        .filter(|s| s.is_overrun())
+    {
+        println!(
+            "  \x1b[33m⚠ Over estimate:\x1b[0m {} ({} iterations vs {} estimated)",
+            sample.task_id,
+            sample.actual,
+            sample.effort.unwrap_or_default()
+        );
+    }
     println!();
 
     // Sources
@@ -101,22 +184,7 @@ pub fn status(verbose: bool) -> StatusCommandResult {
         println!("  (none configured)");
     } else {
         for (i, source) in config.sources.iter().enumerate() {
-            let desc = match &source.source_type {
-                crate::config::SourceType::Beads => "beads".to_string(),
-                crate::config::SourceType::Json => {
-                    format!("json: {}", source.path.as_deref().unwrap_or("?"))
-                }
-                crate::config::SourceType::Markdown => {
-                    format!("markdown: {}", source.path.as_deref().unwrap_or("?"))
-                }
-                crate::config::SourceType::Github => {
-                    format!(
-                        "github: {}",
-                        source.repo.as_deref().unwrap_or("current repo")
-                    )
-                }
-                crate::config::SourceType::Openspec => "openspec".to_string(),
-            };
+            let desc = describe_source(source);
             println!("  {}. {}", i + 1, desc);
         }
     }
@@ -124,11 +192,42 @@ pub fn status(verbose: bool) -> StatusCommandResult {
 
     // AI CLI
     println!("\x1b[1mAI CLI\x1b[0m");
-    println!(
-        "  Command: {} {}",
-        config.ai_cli.command,
-        config.ai_cli.args.join(" ")
-    );
+    if config.ai_cli.backend == AiBackend::Http {
+        println!(
+            "  HTTP backend: {} ({})",
+            config.ai_cli.chat_completions_url(),
+            config
+                .ai_cli
+                .models
+                .first()
+                .map_or("no model", String::as_str)
+        );
+    } else if config.ai_cli.backend == AiBackend::Ollama {
+        println!(
+            "  Ollama: {} ({}, {} token context)",
+            config.ollama.host,
+            config
+                .ai_cli
+                .models
+                .first()
+                .or(config.ollama.model.as_ref())
+                .map_or("no model", String::as_str),
+            config.ollama.context_size
+        );
+    } else {
+        println!(
+            "  Command: {} {}",
+            config.ai_cli.command,
+            config.ai_cli.args.join(" ")
+        );
+    }
+    if config.ai_cli.escalate_after > 0 && !config.ai_cli.escalation_models.is_empty() {
+        println!(
+            "  Escalation: {} (every {} failures)",
+            config.ai_cli.escalation_models.join(" → "),
+            config.ai_cli.escalate_after
+        );
+    }
 
     // Verbose mode: show additional details
     if verbose {
@@ -138,6 +237,171 @@ pub fn status(verbose: bool) -> StatusCommandResult {
     Ok(())
 }
 
+/// Gather the status as JSON, as printed by `afk status --json`.
+pub fn status_json() -> StatusJson {
+    if !Path::new(".afk").exists() {
+        return StatusJson::not_initialised();
+    }
+
+    let config = AfkConfig::load(None).unwrap_or_default();
+    let prd = PrdDocument::load(None).unwrap_or_default();
+    let progress = SessionProgress::load(None).unwrap_or_default();
+
+    let (complete, total) = prd.get_story_counts();
+    let (criteria_met, criteria_total) = prd.get_criteria_counts();
+    let (pending, in_progress, completed, failed, skipped) =
+        calculate_merged_task_counts(&prd, &progress);
+
+    StatusJson::new(StatusDetails {
+        tasks: TaskSummaryJson {
+            total,
+            complete,
+            pending: total - complete,
+            criteria_met,
+            criteria_total,
+            current: progress
+                .get_in_progress_tasks()
+                .iter()
+                .map(|t| t.id.clone())
+                .collect(),
+            next: prd
+                .get_next_story(&TaskOrder::new(&config, &progress))
+                .map(|s| s.id.clone()),
+            epics: prd
+                .get_epic_counts()
+                .into_iter()
+                .map(|(name, complete, total)| EpicJson {
+                    name: name.to_string(),
+                    complete,
+                    total,
+                })
+                .collect(),
+        },
+        session: SessionJson {
+            started_at: progress.started_at.clone(),
+            iterations: progress.iterations,
+            control: json::control_name(control_state()),
+            counts: StatusCountsJson {
+                pending,
+                in_progress,
+                completed,
+                failed,
+                skipped,
+            },
+            overruns: session_samples(&prd, &progress)
+                .into_iter()
+                .filter(|s| s.is_overrun())
+                .map(|s| OverrunJson {
+                    task_id: s.task_id,
+                    actual: s.actual,
+                    estimated: s.effort.unwrap_or_default(),
+                })
+                .collect(),
+            escalated: escalated_tasks(&config, &progress)
+                .into_iter()
+                .map(|(task, model)| EscalationJson {
+                    task_id: task.id.clone(),
+                    model: model.to_string(),
+                    failures: task.failure_count,
+                    models: task.models.clone(),
+                })
+                .collect(),
+        },
+        burndown: burndown_json(),
+        sources: config.sources.iter().map(describe_source).collect(),
+        ai_cli: AiCliJson {
+            command: config.ai_cli.command.clone(),
+            args: config.ai_cli.args.clone(),
+        },
+    })
+}
+
+/// Unfinished tasks that have failed enough to run on an escalation model,
+/// with that model, by task ID.
+fn escalated_tasks<'a>(
+    config: &'a AfkConfig,
+    progress: &'a SessionProgress,
+) -> Vec<(&'a TaskProgress, &'a str)> {
+    let mut escalated: Vec<_> = progress
+        .tasks
+        .values()
+        .filter(|t| t.status != TaskStatus::Completed)
+        .filter_map(|t| Some((t, config.ai_cli.escalation_model(t.failure_count)?)))
+        .collect();
+    escalated.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+    escalated
+}
+
+/// The models a task ran with, runs of the same model collapsed:
+/// "haiku ×2 → sonnet".
+fn model_history(models: &[String]) -> String {
+    let mut runs: Vec<(&str, usize)> = Vec::new();
+    for model in models {
+        match runs.last_mut() {
+            Some((last, count)) if *last == model.as_str() => *count += 1,
+            _ => runs.push((model, 1)),
+        }
+    }
+    if runs.is_empty() {
+        return "no iterations recorded".to_string();
+    }
+    runs.iter()
+        .map(|&(model, count)| match count {
+            1 => model.to_string(),
+            n => format!("{model} ×{n}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" → ")
+}
+
+/// The burn-down history for `afk status --json`.
+fn burndown_json() -> BurndownJson {
+    let points = burndown::load(None);
+    let remaining = burndown::remaining(&points);
+    BurndownJson {
+        trend: Trend::of(&remaining).map(Trend::label),
+        sessions: burndown::session_count(&points),
+        remaining,
+    }
+}
+
+/// One-line description of a configured source.
+fn describe_source(source: &SourceConfig) -> String {
+    match &source.source_type {
+        SourceType::Beads => format!(
+            "beads{}",
+            if source.writeback { " (writeback)" } else { "" }
+        ),
+        SourceType::Json => format!("json: {}", source.path.as_deref().unwrap_or("?")),
+        SourceType::Markdown => format!(
+            "markdown: {}{}",
+            source.path.as_deref().unwrap_or("?"),
+            if source.writeback { " (writeback)" } else { "" }
+        ),
+        SourceType::Github => format!(
+            "github: {}",
+            source.repo.as_deref().unwrap_or("current repo")
+        ),
+        SourceType::Openspec => "openspec".to_string(),
+        SourceType::Notion => format!("notion: {}", source.database.as_deref().unwrap_or("?")),
+        SourceType::Trello => format!("trello: {}", source.board.as_deref().unwrap_or("?")),
+        SourceType::Jira => format!("jira: {}", source.project.as_deref().unwrap_or("?")),
+        SourceType::Command => format!("command: {}", source.path.as_deref().unwrap_or("?")),
+        SourceType::Csv => format!(
+            "csv: {}",
+            source
+                .path
+                .as_deref()
+                .unwrap_or(crate::sources::csv::DEFAULT_PATH)
+        ),
+        SourceType::StoriesDir => format!(
+            "stories-dir: {}{}",
+            source.path.as_deref().unwrap_or("stories"),
+            if source.writeback { " (writeback)" } else { "" }
+        ),
+    }
+}
+
 /// Calculate task counts by merging PRD data with session progress.
 ///
 /// This ensures the Session section's counts are consistent with the Tasks section.
@@ -161,7 +425,7 @@ fn calculate_merged_task_counts(
             // Use session status if task is tracked
             match task_progress.status {
                 TaskStatus::Pending => pending += 1,
-                TaskStatus::InProgress => in_progress += 1,
+                TaskStatus::InProgress | TaskStatus::AwaitingReview => in_progress += 1,
                 TaskStatus::Completed => completed += 1,
                 TaskStatus::Failed => failed += 1,
                 TaskStatus::Skipped => skipped += 1,
@@ -214,12 +478,20 @@ fn print_verbose_details(config: &AfkConfig, prd: &PrdDocument, progress: &Sessi
 
     // Pending Stories
     println!("\x1b[1mPending Stories\x1b[0m");
-    let pending_stories = prd.get_pending_stories();
+    let pending_stories = prd.get_pending_stories(&TaskOrder::new(config, progress));
     if pending_stories.is_empty() {
         println!("  (none)");
     } else {
         for story in pending_stories.iter().take(5) {
-            println!("  - {} (P{}) {}", story.id, story.priority, story.title);
+            let epic = story
+                .epic
+                .as_deref()
+                .map(|e| format!(" \x1b[2m[{e}]\x1b[0m"))
+                .unwrap_or_default();
+            println!(
+                "  - {} (P{}) {}{epic}",
+                story.id, story.priority, story.title
+            );
         }
         if pending_stories.len() > 5 {
             println!("  ... and {} more", pending_stories.len() - 5);
@@ -227,6 +499,30 @@ fn print_verbose_details(config: &AfkConfig, prd: &PrdDocument, progress: &Sessi
     }
     println!();
 
+    // Estimate vs actual
+    let estimated: Vec<_> = session_samples(prd, progress)
+        .into_iter()
+        .filter(|s| s.effort.is_some())
+        .collect();
+    if !estimated.is_empty() {
+        println!("\x1b[1mEstimates\x1b[0m");
+        for sample in &estimated {
+            let flag = if sample.is_overrun() {
+                " \x1b[33m(overrun)\x1b[0m"
+            } else {
+                ""
+            };
+            println!(
+                "  {}: {} of {} estimated iterations{}",
+                sample.task_id,
+                sample.actual,
+                sample.effort.unwrap_or_default(),
+                flag
+            );
+        }
+        println!();
+    }
+
     // Recent Learnings
     println!("\x1b[1mRecent Learnings\x1b[0m");
     let learnings = progress.get_recent_learnings(5);
@@ -256,6 +552,40 @@ mod tests {
         assert_eq!(err.to_string(), "afk not initialised");
     }
 
+    #[test]
+    fn test_escalated_tasks() {
+        let mut config = AfkConfig::default();
+        config.ai_cli.escalation_models = vec!["sonnet".to_string(), "opus".to_string()];
+        let mut progress = SessionProgress::new();
+        for (id, failures) in [("b", 4), ("a", 2), ("c", 1)] {
+            progress.record_attempt(id, "json");
+            for _ in 0..failures {
+                progress.set_task_status(id, TaskStatus::Failed, "json", None);
+            }
+        }
+        progress.record_attempt("done", "json");
+        for _ in 0..3 {
+            progress.set_task_status("done", TaskStatus::Failed, "json", None);
+        }
+        progress.set_task_status("done", TaskStatus::Completed, "json", None);
+
+        let escalated: Vec<(&str, &str)> = escalated_tasks(&config, &progress)
+            .into_iter()
+            .map(|(t, model)| (t.id.as_str(), model))
+            .collect();
+        assert_eq!(escalated, [("a", "sonnet"), ("b", "opus")]);
+    }
+
+    #[test]
+    fn test_model_history() {
+        let models: Vec<String> = ["default", "default", "sonnet", "opus"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(model_history(&models), "default ×2 → sonnet → opus");
+        assert_eq!(model_history(&[]), "no iterations recorded");
+    }
+
     #[test]
     fn test_calculate_merged_task_counts_empty() {
         let prd = PrdDocument::default();
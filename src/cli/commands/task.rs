@@ -1,9 +1,30 @@
 //! Task command implementation.
 //!
-//! This module implements the `afk task` command for showing task details.
+//! This module implements the `afk task` command for showing task details,
+//! `afk task add/edit/rm` for managing tasks in `.afk/tasks.json` by hand, and
+//! `afk task new` for adding tasks from templates.
 
-use crate::prd::PrdDocument;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+
+use chrono::Local;
+
+use crate::config::AfkConfig;
+use crate::prd::enrich::{GENERATED_CRITERIA, GENERATED_DESCRIPTION};
+use crate::prd::{
+    list_templates, AcceptanceCriterion, PrdDocument, PrdError, TaskTemplate, TemplateError,
+    UserStory,
+};
 use crate::progress::SessionProgress;
+use crate::sources::source_owns;
+
+/// Source recorded on tasks added with `afk task add`. No configured source
+/// owns it, so syncing never removes or overwrites these tasks.
+pub const MANUAL_SOURCE: &str = "manual";
+
+/// Longest ID made from a task title.
+const MAX_GENERATED_ID_LEN: usize = 40;
 
 /// Result type for task command operations.
 pub type TaskCommandResult = Result<(), TaskCommandError>;
@@ -14,6 +35,105 @@ pub enum TaskCommandError {
     /// The specified task was not found.
     #[error("Task not found: {0}")]
     TaskNotFound(String),
+
+    /// Neither a task ID nor a subcommand was given.
+    #[error("Specify a task ID, or one of add, new, edit or rm")]
+    NoTaskId,
+
+    /// A new task needs a title.
+    #[error("A task title is required (use --title)")]
+    MissingTitle,
+
+    /// A task ID is empty or contains whitespace.
+    #[error("Invalid task ID: {0:?}")]
+    InvalidId(String),
+
+    /// Another task already has this ID.
+    #[error("A task with ID {0} already exists")]
+    DuplicateId(String),
+
+    /// Priority outside 1 to 5.
+    #[error("Invalid priority {0}: expected 1 (highest) to 5 (lowest)")]
+    InvalidPriority(i32),
+
+    /// A dependency names no other task.
+    #[error("Unknown dependency: {0}")]
+    UnknownDependency(String),
+
+    /// `afk task edit` was given no fields to change.
+    #[error("Nothing to change: give at least one field to edit")]
+    NothingToEdit,
+
+    /// A `--set` value was not `NAME=VALUE`.
+    #[error("Expected NAME=VALUE, got '{0}'")]
+    InvalidSetting(String),
+
+    /// A task template could not be loaded or filled in.
+    #[error(transparent)]
+    Template(#[from] TemplateError),
+
+    /// Reading or writing the tasks file failed.
+    #[error("Failed to update tasks: {0}")]
+    Prd(#[from] PrdError),
+}
+
+/// A task to add by hand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewTask {
+    /// Task ID, or None to make one from the title.
+    pub id: Option<String>,
+    /// Title; required.
+    pub title: Option<String>,
+    /// Description; the title if None.
+    pub description: Option<String>,
+    /// Priority from 1 to 5; 3 if None.
+    pub priority: Option<i32>,
+    /// Acceptance criteria.
+    pub acceptance_criteria: Vec<String>,
+    /// IDs of tasks that must be complete first.
+    pub depends_on: Vec<String>,
+    /// Model to run the task with, overriding `ai_cli.models`.
+    pub model: Option<String>,
+    /// AI CLI to run the task with, overriding `ai_cli.command`.
+    pub cli: Option<String>,
+}
+
+/// Changes to a task. Fields left as None (or empty) are unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskEdit {
+    /// New title.
+    pub title: Option<String>,
+    /// New description.
+    pub description: Option<String>,
+    /// New priority.
+    pub priority: Option<i32>,
+    /// Replacement acceptance criteria. Criteria whose text is unchanged
+    /// stay met.
+    pub acceptance_criteria: Option<Vec<String>>,
+    /// Criteria to add after the others.
+    pub add_criteria: Vec<String>,
+    /// Replacement dependencies.
+    pub depends_on: Option<Vec<String>>,
+    /// New model override; empty clears it.
+    pub model: Option<String>,
+    /// New AI CLI override; empty clears it.
+    pub cli: Option<String>,
+    /// Clear the story's generated marks.
+    pub reviewed: bool,
+}
+
+impl TaskEdit {
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.priority.is_none()
+            && self.acceptance_criteria.is_none()
+            && self.add_criteria.is_empty()
+            && self.depends_on.is_none()
+            && self.model.is_none()
+            && self.cli.is_none()
+            && !self.reviewed
+    }
 }
 
 /// Execute the task command.
@@ -40,10 +160,19 @@ pub fn task(task_id: &str) -> TaskCommandResult {
         if story.passes { "complete" } else { "pending" }
     );
     println!("\x1b[1mPriority:\x1b[0m {}", story.priority);
+    if let Some(effort) = story.effort {
+        let actual = task_progress.map_or(0, |t| t.attempts);
+        println!("\x1b[1mEffort:\x1b[0m {actual} of {effort} estimated iterations");
+    }
     println!();
 
     if !story.description.is_empty() {
-        println!("\x1b[1mDescription:\x1b[0m");
+        let generated = if story.generated.iter().any(|g| g == GENERATED_DESCRIPTION) {
+            " \x1b[33m(generated, needs review)\x1b[0m"
+        } else {
+            ""
+        };
+        println!("\x1b[1mDescription:\x1b[0m{generated}");
         for line in story.description.lines() {
             println!("  {line}");
         }
@@ -51,10 +180,23 @@ pub fn task(task_id: &str) -> TaskCommandResult {
     }
 
     if !story.acceptance_criteria.is_empty() {
-        println!("\x1b[1mAcceptance Criteria:\x1b[0m");
-        for criterion in &story.acceptance_criteria {
-            let check = if story.passes { "✓" } else { "○" };
-            println!("  {check} {criterion}");
+        let (met, total) = story.criteria_counts();
+        let generated = if story.generated.iter().any(|g| g == GENERATED_CRITERIA) {
+            " \x1b[33m(generated, needs review)\x1b[0m"
+        } else {
+            ""
+        };
+        println!("\x1b[1mAcceptance Criteria:\x1b[0m ({met}/{total} met){generated}");
+        for (i, criterion) in story.acceptance_criteria.iter().enumerate() {
+            let check = if story.passes || criterion.passed {
+                "✓"
+            } else {
+                "○"
+            };
+            println!("  {check} {}. {criterion}", i + 1);
+            if let Some(evidence) = &criterion.evidence {
+                println!("       \x1b[2m{evidence}\x1b[0m");
+            }
         }
         println!();
     }
@@ -87,10 +229,609 @@ pub fn task(task_id: &str) -> TaskCommandResult {
     Ok(())
 }
 
+/// Add a task to `.afk/tasks.json`, prompting for its details in a
+/// terminal when no title was given.
+pub fn task_add(new: NewTask) -> TaskCommandResult {
+    let new = if new.title.is_none() && io::stdin().is_terminal() {
+        prompt_new_task(new)
+    } else {
+        new
+    };
+    let mut prd = PrdDocument::load(None)?;
+    let id = add_story(&mut prd, new)?;
+    prd.save(None)?;
+    println!("\x1b[32m✓\x1b[0m Added task \x1b[1m{id}\x1b[0m");
+    Ok(())
+}
+
+/// Add a task made from a template to `.afk/tasks.json`, prompting in a
+/// terminal for placeholders not given as `NAME=VALUE` settings.
+pub fn task_new(template: &str, settings: &[String], id: Option<String>) -> TaskCommandResult {
+    let template = TaskTemplate::load(template, None)?;
+    let mut values = parse_settings(settings)?;
+    if io::stdin().is_terminal() {
+        for name in template.placeholders() {
+            if let Entry::Vacant(entry) = values.entry(name) {
+                if let Some(value) = ask(template.prompt_for(entry.key())) {
+                    entry.insert(value);
+                }
+            }
+        }
+    }
+    let new = new_task_from_template(&template, &values, id)?;
+    let mut prd = PrdDocument::load(None)?;
+    let id = add_story(&mut prd, new)?;
+    prd.save(None)?;
+    println!(
+        "\x1b[32m✓\x1b[0m Added task \x1b[1m{id}\x1b[0m from template {}",
+        template.name
+    );
+    Ok(())
+}
+
+/// List the task templates `afk task new` can use.
+pub fn task_templates() -> TaskCommandResult {
+    println!("\x1b[1mTask templates:\x1b[0m");
+    for template in list_templates(None) {
+        let placeholders = template.placeholders();
+        print!("  \x1b[36m{}\x1b[0m", template.name);
+        if !template.description.is_empty() {
+            print!(" - {}", template.description);
+        }
+        if !placeholders.is_empty() {
+            print!(" \x1b[2m({})\x1b[0m", placeholders.join(", "));
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Placeholder values from `NAME=VALUE` settings.
+fn parse_settings(settings: &[String]) -> Result<HashMap<String, String>, TaskCommandError> {
+    settings
+        .iter()
+        .map(|setting| {
+            setting
+                .split_once('=')
+                .filter(|(name, _)| !name.trim().is_empty())
+                .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+                .ok_or_else(|| TaskCommandError::InvalidSetting(setting.clone()))
+        })
+        .collect()
+}
+
+/// The task a template makes with the given placeholder values.
+pub fn new_task_from_template(
+    template: &TaskTemplate,
+    values: &HashMap<String, String>,
+    id: Option<String>,
+) -> Result<NewTask, TaskCommandError> {
+    let task = template.render(values)?;
+    Ok(NewTask {
+        id,
+        title: Some(task.title),
+        description: Some(task.description),
+        priority: task.priority,
+        acceptance_criteria: task.acceptance_criteria,
+        depends_on: task.depends_on,
+        model: task.model,
+        cli: task.cli,
+    })
+}
+
+/// Edit fields of a task in `.afk/tasks.json`.
+pub fn task_edit(task_id: &str, edit: TaskEdit) -> TaskCommandResult {
+    let mut prd = PrdDocument::load(None)?;
+    edit_story(&mut prd, task_id, edit)?;
+    prd.save(None)?;
+    println!("\x1b[32m✓\x1b[0m Updated task \x1b[1m{task_id}\x1b[0m");
+    if let Some(source) = owning_source(&prd, task_id) {
+        println!(
+            "  \x1b[2mThis task comes from {source}; the next sync may overwrite these changes\x1b[0m"
+        );
+    }
+    Ok(())
+}
+
+/// Remove a task from `.afk/tasks.json`.
+pub fn task_rm(task_id: &str) -> TaskCommandResult {
+    let mut prd = PrdDocument::load(None)?;
+    let source = owning_source(&prd, task_id);
+    let (_, dependents) = remove_story(&mut prd, task_id)?;
+    prd.save(None)?;
+    println!("\x1b[32m✓\x1b[0m Removed task \x1b[1m{task_id}\x1b[0m");
+    if !dependents.is_empty() {
+        println!("  No longer a dependency of: {}", dependents.join(", "));
+    }
+    if let Some(source) = source {
+        println!(
+            "  \x1b[2mThis task comes from {source}; remove it there too or the next sync brings it back\x1b[0m"
+        );
+    }
+    Ok(())
+}
+
+/// The source a task came from, if a configured source owns it.
+fn owning_source(prd: &PrdDocument, task_id: &str) -> Option<String> {
+    let story = prd.get_story(task_id)?;
+    let config = AfkConfig::load(None).unwrap_or_default();
+    config
+        .sources
+        .iter()
+        .any(|source| source_owns(source, &story.source))
+        .then(|| story.source.clone())
+}
+
+/// Add a task to a backlog. Returns its ID.
+pub fn add_story(prd: &mut PrdDocument, new: NewTask) -> Result<String, TaskCommandError> {
+    let title = new
+        .title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .ok_or(TaskCommandError::MissingTitle)?;
+    let id = match new.id {
+        Some(id) => {
+            let id = id.trim().to_string();
+            if id.is_empty() || id.contains(char::is_whitespace) {
+                return Err(TaskCommandError::InvalidId(id));
+            }
+            if prd.get_story(&id).is_some() {
+                return Err(TaskCommandError::DuplicateId(id));
+            }
+            id
+        }
+        None => unique_id(prd, &title),
+    };
+    check_dependencies(prd, &id, &new.depends_on)?;
+
+    let mut story = UserStory::new(id.clone(), title);
+    if let Some(description) = new.description.filter(|d| !d.trim().is_empty()) {
+        story.description = description;
+    }
+    if let Some(priority) = new.priority {
+        story.priority = check_priority(priority)?;
+    }
+    story.acceptance_criteria = criteria(new.acceptance_criteria);
+    story.depends_on = new.depends_on;
+    story.model = non_empty(new.model);
+    story.cli = non_empty(new.cli);
+    story.source = MANUAL_SOURCE.to_string();
+    story.touched_at = Some(now());
+    prd.user_stories.push(story);
+    Ok(id)
+}
+
+/// Change fields of a task in a backlog.
+pub fn edit_story(
+    prd: &mut PrdDocument,
+    task_id: &str,
+    edit: TaskEdit,
+) -> Result<(), TaskCommandError> {
+    if edit.is_empty() {
+        return Err(TaskCommandError::NothingToEdit);
+    }
+    if prd.get_story(task_id).is_none() {
+        return Err(TaskCommandError::TaskNotFound(task_id.to_string()));
+    }
+    if let Some(depends_on) = &edit.depends_on {
+        check_dependencies(prd, task_id, depends_on)?;
+    }
+    let priority = edit.priority.map(check_priority).transpose()?;
+    let story = prd
+        .get_story_mut(task_id)
+        .ok_or_else(|| TaskCommandError::TaskNotFound(task_id.to_string()))?;
+
+    if let Some(title) = edit.title.filter(|t| !t.trim().is_empty()) {
+        story.title = title.trim().to_string();
+    }
+    if let Some(description) = edit.description {
+        story.description = description;
+        story.generated.retain(|g| g != GENERATED_DESCRIPTION);
+    }
+    if let Some(priority) = priority {
+        story.priority = priority;
+    }
+    if let Some(texts) = edit.acceptance_criteria {
+        story.generated.retain(|g| g != GENERATED_CRITERIA);
+        let previous = std::mem::take(&mut story.acceptance_criteria);
+        story.acceptance_criteria = criteria(texts)
+            .into_iter()
+            .map(|criterion| {
+                previous
+                    .iter()
+                    .find(|p| p.text == criterion.text)
+                    .cloned()
+                    .unwrap_or(criterion)
+            })
+            .collect();
+    }
+    story
+        .acceptance_criteria
+        .extend(criteria(edit.add_criteria));
+    if let Some(depends_on) = edit.depends_on {
+        story.depends_on = depends_on;
+    }
+    if let Some(model) = edit.model {
+        story.model = non_empty(Some(model));
+    }
+    if let Some(cli) = edit.cli {
+        story.cli = non_empty(Some(cli));
+    }
+    if edit.reviewed {
+        story.generated.clear();
+    }
+    story.touched_at = Some(now());
+    Ok(())
+}
+
+/// A trimmed value, or None if it is blank.
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Remove a task from a backlog, and from other tasks' dependencies.
+///
+/// Returns the removed task and the IDs of tasks that depended on it.
+pub fn remove_story(
+    prd: &mut PrdDocument,
+    task_id: &str,
+) -> Result<(UserStory, Vec<String>), TaskCommandError> {
+    let index = prd
+        .user_stories
+        .iter()
+        .position(|s| s.id == task_id)
+        .ok_or_else(|| TaskCommandError::TaskNotFound(task_id.to_string()))?;
+    let removed = prd.user_stories.remove(index);
+    let mut dependents = Vec::new();
+    for story in &mut prd.user_stories {
+        let before = story.depends_on.len();
+        story.depends_on.retain(|dep| dep != task_id);
+        if story.depends_on.len() < before {
+            dependents.push(story.id.clone());
+        }
+    }
+    Ok((removed, dependents))
+}
+
+/// An unused ID made from a title: lowercase words joined by hyphens, with
+/// a numeric suffix if another task has it.
+fn unique_id(prd: &PrdDocument, title: &str) -> String {
+    let mut base = String::new();
+    for c in title.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            base.push(c);
+        } else if !base.is_empty() && !base.ends_with('-') {
+            base.push('-');
+        }
+        if base.chars().count() >= MAX_GENERATED_ID_LEN {
+            break;
+        }
+    }
+    let base = match base.trim_end_matches('-') {
+        "" => "task".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    let mut id = base.clone();
+    let mut suffix = 2;
+    while prd.get_story(&id).is_some() {
+        id = format!("{base}-{suffix}");
+        suffix += 1;
+    }
+    id
+}
+
+/// Check a priority is between 1 and 5.
+fn check_priority(priority: i32) -> Result<i32, TaskCommandError> {
+    if (1..=5).contains(&priority) {
+        Ok(priority)
+    } else {
+        Err(TaskCommandError::InvalidPriority(priority))
+    }
+}
+
+/// Check every dependency names another task in the backlog.
+fn check_dependencies(
+    prd: &PrdDocument,
+    task_id: &str,
+    depends_on: &[String],
+) -> Result<(), TaskCommandError> {
+    match depends_on
+        .iter()
+        .find(|dep| *dep == task_id || prd.get_story(dep).is_none())
+    {
+        Some(dep) => Err(TaskCommandError::UnknownDependency(dep.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Acceptance criteria from their texts, skipping blank ones.
+fn criteria(texts: Vec<String>) -> Vec<AcceptanceCriterion> {
+    texts
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .map(AcceptanceCriterion::new)
+        .collect()
+}
+
+fn now() -> String {
+    Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string()
+}
+
+/// Ask at the terminal for the details not given on the command line.
+fn prompt_new_task(mut new: NewTask) -> NewTask {
+    new.title = ask("Title");
+    if new.title.is_none() {
+        return new;
+    }
+    if new.description.is_none() {
+        new.description = ask("Description (Enter to use the title)");
+    }
+    while new.priority.is_none() {
+        match ask("Priority 1-5 [3]") {
+            None => break,
+            Some(input) => new.priority = input.parse().ok().filter(|p| (1..=5).contains(p)),
+        }
+    }
+    if new.acceptance_criteria.is_empty() {
+        println!("Acceptance criteria, one per line (empty line to finish):");
+        while let Some(criterion) = ask("  -") {
+            new.acceptance_criteria.push(criterion);
+        }
+    }
+    new
+}
+
+/// Print a prompt and read a line. None on an empty line or end of input.
+fn ask(label: &str) -> Option<String> {
+    print!("{label}: ");
+    let _ = io::stdout().flush();
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input.trim().to_string()).filter(|i| !i.is_empty()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn backlog() -> PrdDocument {
+        let mut prd = PrdDocument::default();
+        prd.user_stories.push(UserStory::new("auth-1", "Add login"));
+        let mut logout = UserStory::new("auth-2", "Add logout");
+        logout.depends_on = vec!["auth-1".to_string()];
+        let mut met = AcceptanceCriterion::new("Session cleared");
+        met.passed = true;
+        logout.acceptance_criteria = vec![met];
+        prd.user_stories.push(logout);
+        prd
+    }
+
+    fn titled(title: &str) -> NewTask {
+        NewTask {
+            title: Some(title.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_story() {
+        let mut prd = backlog();
+        let id = add_story(
+            &mut prd,
+            NewTask {
+                id: Some("auth-3".to_string()),
+                priority: Some(1),
+                acceptance_criteria: vec!["Works".to_string(), " ".to_string()],
+                depends_on: vec!["auth-1".to_string()],
+                cli: Some(" ".to_string()),
+                ..titled("Reset password")
+            },
+        )
+        .unwrap();
+        assert_eq!(id, "auth-3");
+        let story = prd.get_story("auth-3").unwrap();
+        assert_eq!(story.description, "Reset password");
+        assert_eq!(story.priority, 1);
+        assert_eq!(
+            story.acceptance_criteria,
+            [AcceptanceCriterion::new("Works")]
+        );
+        assert_eq!(story.source, MANUAL_SOURCE);
+        assert!(story.touched_at.is_some());
+    }
+
+    #[test]
+    fn test_add_story_generates_unique_ids() {
+        let mut prd = backlog();
+        assert_eq!(
+            add_story(&mut prd, titled("Fix: the *login* page!")).unwrap(),
+            "fix-the-login-page"
+        );
+        assert_eq!(
+            add_story(&mut prd, titled("Fix the login page")).unwrap(),
+            "fix-the-login-page-2"
+        );
+        assert_eq!(add_story(&mut prd, titled("!!!")).unwrap(), "task");
+        let long = add_story(&mut prd, titled(&"word ".repeat(20))).unwrap();
+        assert!(long.len() <= MAX_GENERATED_ID_LEN);
+        assert!(!long.ends_with('-'));
+    }
+
+    #[test]
+    fn test_add_story_validation() {
+        let mut prd = backlog();
+        assert!(matches!(
+            add_story(&mut prd, NewTask::default()),
+            Err(TaskCommandError::MissingTitle)
+        ));
+        let duplicate = NewTask {
+            id: Some("auth-1".to_string()),
+            ..titled("Again")
+        };
+        assert!(matches!(
+            add_story(&mut prd, duplicate),
+            Err(TaskCommandError::DuplicateId(id)) if id == "auth-1"
+        ));
+        let spaced = NewTask {
+            id: Some("has space".to_string()),
+            ..titled("Spaced")
+        };
+        assert!(matches!(
+            add_story(&mut prd, spaced),
+            Err(TaskCommandError::InvalidId(_))
+        ));
+        let urgent = NewTask {
+            priority: Some(0),
+            ..titled("Urgent")
+        };
+        assert!(matches!(
+            add_story(&mut prd, urgent),
+            Err(TaskCommandError::InvalidPriority(0))
+        ));
+        let orphan = NewTask {
+            depends_on: vec!["nope".to_string()],
+            ..titled("Orphan")
+        };
+        assert!(matches!(
+            add_story(&mut prd, orphan),
+            Err(TaskCommandError::UnknownDependency(dep)) if dep == "nope"
+        ));
+        assert_eq!(prd.user_stories.len(), 2);
+    }
+
+    #[test]
+    fn test_new_task_from_template() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let template = TaskTemplate::load("bugfix", Some(temp.path())).unwrap();
+        let values = parse_settings(&[
+            "component=checkout".to_string(),
+            "error=Card declined".to_string(),
+        ])
+        .unwrap();
+        let new = new_task_from_template(&template, &values, None).unwrap();
+
+        let mut prd = backlog();
+        let id = add_story(&mut prd, new).unwrap();
+        assert_eq!(id, "fix-checkout-card-declined");
+        let story = prd.get_story(&id).unwrap();
+        assert_eq!(story.title, "Fix checkout: Card declined");
+        assert_eq!(story.priority, 2);
+        assert_eq!(story.acceptance_criteria.len(), 3);
+        assert_eq!(
+            story.acceptance_criteria[1].text,
+            "Regression test added that fails without the fix"
+        );
+
+        assert!(matches!(
+            parse_settings(&["component".to_string()]),
+            Err(TaskCommandError::InvalidSetting(_))
+        ));
+        assert!(matches!(
+            new_task_from_template(&template, &HashMap::new(), None),
+            Err(TaskCommandError::Template(
+                TemplateError::MissingValues { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_edit_story() {
+        let mut prd = backlog();
+        assert!(matches!(
+            edit_story(&mut prd, "auth-2", TaskEdit::default()),
+            Err(TaskCommandError::NothingToEdit)
+        ));
+        let edit = TaskEdit {
+            title: Some("Add sign-out".to_string()),
+            priority: Some(2),
+            acceptance_criteria: Some(vec!["Session cleared".to_string(), "Redirects".to_string()]),
+            add_criteria: vec!["Logged".to_string()],
+            depends_on: Some(Vec::new()),
+            model: Some("opus".to_string()),
+            ..Default::default()
+        };
+        edit_story(&mut prd, "auth-2", edit).unwrap();
+        let story = prd.get_story("auth-2").unwrap();
+        assert_eq!(story.title, "Add sign-out");
+        assert_eq!(story.description, "Add logout");
+        assert_eq!(story.priority, 2);
+        let criteria: Vec<(&str, bool)> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| (c.text.as_str(), c.passed))
+            .collect();
+        assert_eq!(
+            criteria,
+            [
+                ("Session cleared", true),
+                ("Redirects", false),
+                ("Logged", false)
+            ]
+        );
+        assert!(story.depends_on.is_empty());
+        assert_eq!(story.model.as_deref(), Some("opus"));
+
+        let clear = TaskEdit {
+            model: Some(String::new()),
+            ..Default::default()
+        };
+        edit_story(&mut prd, "auth-2", clear).unwrap();
+        assert_eq!(prd.get_story("auth-2").unwrap().model, None);
+
+        prd.get_story_mut("auth-2").unwrap().generated =
+            vec![GENERATED_DESCRIPTION.into(), GENERATED_CRITERIA.into()];
+        let describe = TaskEdit {
+            description: Some("Sign the user out".to_string()),
+            ..Default::default()
+        };
+        edit_story(&mut prd, "auth-2", describe).unwrap();
+        assert_eq!(
+            prd.get_story("auth-2").unwrap().generated,
+            [GENERATED_CRITERIA]
+        );
+        let reviewed = TaskEdit {
+            reviewed: true,
+            ..Default::default()
+        };
+        edit_story(&mut prd, "auth-2", reviewed).unwrap();
+        assert!(prd.get_story("auth-2").unwrap().generated.is_empty());
+
+        let cyclic = TaskEdit {
+            depends_on: Some(vec!["auth-2".to_string()]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            edit_story(&mut prd, "auth-2", cyclic),
+            Err(TaskCommandError::UnknownDependency(_))
+        ));
+        let missing = TaskEdit {
+            priority: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            edit_story(&mut prd, "nope", missing),
+            Err(TaskCommandError::TaskNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_remove_story() {
+        let mut prd = backlog();
+        let (removed, dependents) = remove_story(&mut prd, "auth-1").unwrap();
+        assert_eq!(removed.id, "auth-1");
+        assert_eq!(dependents, ["auth-2"]);
+        assert!(prd.get_story("auth-2").unwrap().depends_on.is_empty());
+        assert!(matches!(
+            remove_story(&mut prd, "auth-1"),
+            Err(TaskCommandError::TaskNotFound(_))
+        ));
+    }
+
     #[test]
     fn test_task_command_error_display() {
         let err = TaskCommandError::TaskNotFound("task-123".to_string());
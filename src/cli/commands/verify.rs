@@ -1,9 +1,50 @@
 //! Verify command implementation.
 //!
 //! This module implements the `afk verify` command for running quality gates.
+//! Results can also be emitted as JSON or JUnit XML for CI test reports.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
 
 use crate::config::AfkConfig;
-use crate::runner::{has_configured_gates, run_quality_gates};
+use crate::events::{self, Event};
+use crate::git::{update_submodules, RepoLayout};
+use crate::plugins::PluginHost;
+use crate::prd::PrdDocument;
+use crate::progress::SessionProgress;
+use crate::runner::{
+    changed_files, check_ci_criteria, check_criterion_commands, has_configured_gates, in_iteration,
+    record_phase, run_hook, run_quality_gates_cached, run_quality_gates_quiet, save_gate_artifacts,
+    CiCheck, CriterionCheck, GateCache, GateEnvironment, HookContext, HookEvent, IterationPhase,
+    QualityGateResult, TestPlan,
+};
+
+/// Lines of output kept in a failed gate's excerpt.
+const EXCERPT_LINES: usize = 20;
+
+/// Output format for verify results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerifyFormat {
+    /// Coloured human-readable output.
+    #[default]
+    Text,
+    /// A single JSON report on stdout.
+    Json,
+    /// A JUnit XML report on stdout.
+    Junit,
+}
+
+impl VerifyFormat {
+    /// Parse a `--format` value, falling back to text.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "json" => Self::Json,
+            "junit" => Self::Junit,
+            _ => Self::Text,
+        }
+    }
+}
 
 /// Result type for verify command operations.
 pub type VerifyCommandResult = Result<VerifyOutcome, VerifyCommandError>;
@@ -12,6 +53,8 @@ pub type VerifyCommandResult = Result<VerifyOutcome, VerifyCommandError>;
 pub struct VerifyOutcome {
     /// Whether all gates passed.
     pub all_passed: bool,
+    /// Names of the gates that failed.
+    pub failed_gates: Vec<String>,
 }
 
 /// Error type for verify command operations.
@@ -20,12 +63,85 @@ pub enum VerifyCommandError {
     /// Error loading the configuration file.
     #[error("Failed to load config: {0}")]
     ConfigError(#[from] crate::config::ConfigError),
+    /// The requested task does not exist.
+    #[error("Task not found: {0}")]
+    TaskNotFound(String),
+    /// A configured plugin could not be loaded.
+    #[error("{0}")]
+    PluginError(#[from] crate::plugins::PluginError),
 }
 
 /// Execute the verify command.
-pub fn verify(verbose: bool) -> VerifyCommandResult {
-    // Load config
-    let config = AfkConfig::load(None)?;
+///
+/// With a task ID, the task's `ci:<workflow>` acceptance criteria are also
+/// checked against the latest CI runs for the current branch, and the verify
+/// commands of its acceptance criteria are run. JSON and
+/// JUnit formats print only the report, so it can be redirected to a file.
+/// Unless `use_cache` is false, gates that already passed on the current
+/// working tree are not run again. With `changed_only` (or
+/// `feedback_loops.changed_only` during an iteration), the test gate runs
+/// only the tests mapped from the changed files.
+pub fn verify(
+    verbose: bool,
+    task_id: Option<&str>,
+    format: VerifyFormat,
+    use_cache: bool,
+    changed_only: bool,
+) -> VerifyCommandResult {
+    // Load config, adding any gates contributed by plugins
+    let mut config = AfkConfig::load(None)?;
+    let plugins = PluginHost::load(&config.plugins)?;
+    plugins.add_gates(&mut config.feedback_loops);
+    config.feedback_loops.secret_scan = config.guardrails.secret_scan_config();
+
+    let narrow = changed_only || (config.feedback_loops.changed_only && in_iteration());
+    if narrow && config.feedback_loops.test.is_some() {
+        let plan = TestPlan::build(&changed_files(), &config.feedback_loops);
+        if format == VerifyFormat::Text {
+            println!("\x1b[2mChanged only: {}\x1b[0m", plan.summary());
+        }
+        config.feedback_loops = plan.apply(&config.feedback_loops);
+    }
+
+    let mut cache = if use_cache { GateCache::open() } else { None };
+    let environment = GateEnvironment::check(&config.feedback_loops);
+    record_phase(IterationPhase::GatesRunning);
+    if config.git.update_submodules && RepoLayout::load().has_submodules() {
+        if let Err(e) = update_submodules() {
+            eprintln!("\x1b[33mWarning:\x1b[0m Submodule update failed: {e}");
+        }
+    }
+
+    let outcome = if format == VerifyFormat::Text {
+        verify_text(&config, verbose, task_id, cache.as_mut())?
+    } else {
+        verify_report(&config, task_id, format, cache.as_mut())?
+    };
+    if outcome.all_passed {
+        environment.record_green();
+        record_phase(IterationPhase::Committing);
+    } else {
+        run_gate_fail_hook(&config, task_id, &outcome.failed_gates);
+    }
+    Ok(outcome)
+}
+
+/// Run gates with coloured progress output.
+fn verify_text(
+    config: &AfkConfig,
+    verbose: bool,
+    task_id: Option<&str>,
+    cache: Option<&mut GateCache>,
+) -> VerifyCommandResult {
+    let ci_passed = match task_id {
+        Some(id) => {
+            let (ci, criteria) = task_checks(id)?;
+            // Run both so every failure is reported
+            let ci_passed = report_task_ci(id, &ci);
+            report_task_criteria(id, &criteria) && ci_passed
+        }
+        None => true,
+    };
 
     // Check if any gates are configured
     if !has_configured_gates(&config.feedback_loops) {
@@ -38,20 +154,453 @@ pub fn verify(verbose: bool) -> VerifyCommandResult {
         println!("      \"test\": \"cargo test\"");
         println!("    }}");
         println!("  }}");
-        return Ok(VerifyOutcome { all_passed: true });
+        return Ok(VerifyOutcome {
+            all_passed: ci_passed,
+            failed_gates: Vec::new(),
+        });
     }
 
     // Run quality gates
-    let result = run_quality_gates(&config.feedback_loops, verbose);
+    let result = run_quality_gates_cached(&config.feedback_loops, verbose, cache);
+    let iteration = current_iteration();
+    let paths = keep_gate_artifacts(config, iteration, &result);
+    print_failed_artifacts(&result, &paths);
 
     Ok(VerifyOutcome {
-        all_passed: result.all_passed,
+        all_passed: result.all_passed && ci_passed,
+        failed_gates: failed_gate_names(&result),
+    })
+}
+
+/// Run gates quietly and print a JSON or JUnit report.
+fn verify_report(
+    config: &AfkConfig,
+    task_id: Option<&str>,
+    format: VerifyFormat,
+    cache: Option<&mut GateCache>,
+) -> VerifyCommandResult {
+    let (checks, criteria) = match task_id {
+        Some(id) => task_checks(id)?,
+        None => (Vec::new(), Vec::new()),
+    };
+    let result = run_quality_gates_quiet(&config.feedback_loops, cache);
+    let iteration = current_iteration();
+    let paths = keep_gate_artifacts(config, iteration, &result);
+
+    let report = VerifyReport::new(iteration, &result, &paths, &checks, &criteria);
+    match format {
+        VerifyFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        ),
+        _ => print!("{}", report.to_junit()),
+    }
+
+    Ok(VerifyOutcome {
+        all_passed: report.passed,
+        failed_gates: failed_gate_names(&result),
+    })
+}
+
+/// Names of the gates that failed.
+fn failed_gate_names(result: &QualityGateResult) -> Vec<String> {
+    result
+        .gates
+        .iter()
+        .filter(|g| !g.passed)
+        .map(|g| g.name.clone())
+        .collect()
+}
+
+/// Run the `on_gate_fail` hook, warning (on stderr) if it fails.
+fn run_gate_fail_hook(config: &AfkConfig, task_id: Option<&str>, failed_gates: &[String]) {
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let context = HookContext {
+        iteration: Some(progress.iterations),
+        task_id: task_id.map(str::to_string).or(progress.last_task),
+        failed_gates: failed_gates.to_vec(),
+        ..Default::default()
+    };
+    if let Some(warning) = run_hook(&config.hooks, HookEvent::GateFail, &context) {
+        eprintln!("\x1b[33mWarning:\x1b[0m {warning}");
+    }
+}
+
+/// Iteration gate output belongs to, from the session progress.
+fn current_iteration() -> u32 {
+    SessionProgress::load(None)
+        .map(|p| p.iterations)
+        .unwrap_or(0)
+}
+
+/// Save gate output under the current iteration and log each gate's result.
+///
+/// Returns the log paths in gate order, or none if saving failed.
+fn keep_gate_artifacts(
+    config: &AfkConfig,
+    iteration: u32,
+    result: &QualityGateResult,
+) -> Vec<PathBuf> {
+    for gate in &result.gates {
+        events::record(Event::Gate {
+            iteration,
+            name: gate.name.clone(),
+            passed: gate.passed,
+            duration_seconds: gate.duration_seconds,
+        });
+    }
+    save_gate_artifacts(&config.artifacts, iteration, &result.gates).unwrap_or_else(|e| {
+        eprintln!("\x1b[33mWarning:\x1b[0m Failed to save gate output: {e}");
+        Vec::new()
     })
 }
 
+/// Point at the saved logs of failed gates.
+fn print_failed_artifacts(result: &QualityGateResult, paths: &[PathBuf]) {
+    let failed: Vec<_> = result
+        .gates
+        .iter()
+        .zip(paths)
+        .filter(|(gate, _)| !gate.passed)
+        .collect();
+    if !failed.is_empty() {
+        println!("\x1b[2mFull output:\x1b[0m");
+        for (gate, path) in failed {
+            println!("  {}: {}", gate.name, path.display());
+        }
+    }
+}
+
+/// Machine-readable verify results.
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    /// Whether every gate and CI check passed.
+    pub passed: bool,
+    /// Session iteration the gates ran for.
+    pub iteration: u32,
+    /// Total gate run time in seconds.
+    pub duration_seconds: f64,
+    /// Per-gate results, in run order.
+    pub gates: Vec<GateReport>,
+    /// CI workflow checks for `--task`, if any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ci: Vec<CiReport>,
+    /// Acceptance criterion checks for `--task`, if any.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<CriterionReport>,
+}
+
+/// One gate's result in a verify report.
+#[derive(Debug, Serialize)]
+pub struct GateReport {
+    /// Gate name.
+    pub name: String,
+    /// "passed" or "failed".
+    pub status: &'static str,
+    /// Run time in seconds (of the original run, if cached).
+    pub duration_seconds: f64,
+    /// Whether the result was reused from the gate cache.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cached: bool,
+    /// Tail of the output, for failed gates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// Path of the full output log, if saved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<String>,
+}
+
+/// One CI workflow check in a verify report.
+#[derive(Debug, Serialize)]
+pub struct CiReport {
+    /// Workflow name.
+    pub workflow: String,
+    /// "passed" or "failed".
+    pub status: &'static str,
+    /// Human-readable run status.
+    pub detail: String,
+}
+
+/// One acceptance criterion's verify command in a verify report.
+#[derive(Debug, Serialize)]
+pub struct CriterionReport {
+    /// 1-based position of the criterion.
+    pub number: usize,
+    /// The criterion text.
+    pub criterion: String,
+    /// The command that was run.
+    pub command: String,
+    /// "passed" or "failed".
+    pub status: &'static str,
+    /// Run time in seconds.
+    pub duration_seconds: f64,
+    /// Tail of the output, for failed checks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+}
+
+impl VerifyReport {
+    /// Build a report from gate results, their log paths, CI checks and
+    /// acceptance criterion checks.
+    pub fn new(
+        iteration: u32,
+        result: &QualityGateResult,
+        paths: &[PathBuf],
+        checks: &[CiCheck],
+        criteria: &[CriterionCheck],
+    ) -> Self {
+        let gates = result
+            .gates
+            .iter()
+            .enumerate()
+            .map(|(i, gate)| GateReport {
+                name: gate.name.clone(),
+                status: status_name(gate.passed),
+                duration_seconds: round_seconds(gate.duration_seconds),
+                cached: result.cached_gates.contains(&gate.name),
+                excerpt: (!gate.passed).then(|| excerpt(&gate.output)),
+                artifact: paths.get(i).map(|p| p.display().to_string()),
+            })
+            .collect();
+        let ci = checks
+            .iter()
+            .map(|check| CiReport {
+                workflow: check.workflow.clone(),
+                status: status_name(check.status.is_passed()),
+                detail: check.status.describe(),
+            })
+            .collect();
+        let criteria_reports = criteria
+            .iter()
+            .map(|check| CriterionReport {
+                number: check.number,
+                criterion: check.text.clone(),
+                command: check.command.clone(),
+                status: status_name(check.passed),
+                duration_seconds: round_seconds(check.duration_seconds),
+                excerpt: (!check.passed).then(|| excerpt(&check.output)),
+            })
+            .collect();
+
+        Self {
+            passed: result.all_passed
+                && checks.iter().all(|c| c.status.is_passed())
+                && criteria.iter().all(|c| c.passed),
+            iteration,
+            duration_seconds: round_seconds(result.gates.iter().map(|g| g.duration_seconds).sum()),
+            gates,
+            ci,
+            criteria: criteria_reports,
+        }
+    }
+
+    /// Render the report as JUnit XML.
+    ///
+    /// Gates form one test suite, CI checks another and acceptance criterion
+    /// checks a third, so CI systems show each gate as a test case.
+    pub fn to_junit(&self) -> String {
+        let ci_failures = self.ci.iter().filter(|c| c.status == "failed").count();
+        let criteria_failures = self
+            .criteria
+            .iter()
+            .filter(|c| c.status == "failed")
+            .count();
+        let failures = self.gates.iter().filter(|g| g.status == "failed").count()
+            + ci_failures
+            + criteria_failures;
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"afk verify\" tests=\"{}\" failures=\"{failures}\" time=\"{:.3}\">\n",
+            self.gates.len() + self.ci.len() + self.criteria.len(),
+            self.duration_seconds
+        ));
+
+        let gate_failures = self.gates.iter().filter(|g| g.status == "failed").count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"quality-gates\" tests=\"{}\" failures=\"{gate_failures}\" time=\"{:.3}\">\n",
+            self.gates.len(),
+            self.duration_seconds
+        ));
+        for gate in &self.gates {
+            xml.push_str(&format!(
+                "    <testcase classname=\"afk.gates\" name=\"{}\" time=\"{:.3}\"",
+                xml_escape(&gate.name),
+                gate.duration_seconds
+            ));
+            match &gate.excerpt {
+                Some(excerpt) => xml.push_str(&format!(
+                    ">\n      <failure message=\"{} failed\">{}</failure>\n    </testcase>\n",
+                    xml_escape(&gate.name),
+                    xml_escape(excerpt)
+                )),
+                None => xml.push_str("/>\n"),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+
+        if !self.ci.is_empty() {
+            xml.push_str(&format!(
+                "  <testsuite name=\"ci\" tests=\"{}\" failures=\"{ci_failures}\">\n",
+                self.ci.len()
+            ));
+            for check in &self.ci {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"afk.ci\" name=\"{}\"",
+                    xml_escape(&check.workflow)
+                ));
+                if check.status == "failed" {
+                    xml.push_str(&format!(
+                        ">\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                        xml_escape(&check.detail)
+                    ));
+                } else {
+                    xml.push_str("/>\n");
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        if !self.criteria.is_empty() {
+            xml.push_str(&format!(
+                "  <testsuite name=\"acceptance-criteria\" tests=\"{}\" failures=\"{criteria_failures}\">\n",
+                self.criteria.len()
+            ));
+            for check in &self.criteria {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"afk.criteria\" name=\"{}\" time=\"{:.3}\"",
+                    xml_escape(&format!("#{} {}", check.number, check.criterion)),
+                    check.duration_seconds
+                ));
+                match &check.excerpt {
+                    Some(excerpt) => xml.push_str(&format!(
+                        ">\n      <failure message=\"{} failed\">{}</failure>\n    </testcase>\n",
+                        xml_escape(&check.command),
+                        xml_escape(excerpt)
+                    )),
+                    None => xml.push_str("/>\n"),
+                }
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+
+        xml.push_str("</testsuites>\n");
+        xml
+    }
+}
+
+/// Report status for a pass/fail flag.
+fn status_name(passed: bool) -> &'static str {
+    if passed {
+        "passed"
+    } else {
+        "failed"
+    }
+}
+
+/// Seconds rounded to milliseconds.
+fn round_seconds(seconds: f64) -> f64 {
+    (seconds * 1000.0).round() / 1000.0
+}
+
+/// Last few lines of a gate's output.
+fn excerpt(output: &str) -> String {
+    let lines: Vec<&str> = output.trim_end().lines().collect();
+    let start = lines.len().saturating_sub(EXCERPT_LINES);
+    lines[start..].join("\n")
+}
+
+/// Escape text for XML attributes and content.
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newline are not valid XML
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Check a task's `ci:<workflow>` criteria against the latest CI runs and
+/// run its criteria's verify commands.
+fn task_checks(task_id: &str) -> Result<(Vec<CiCheck>, Vec<CriterionCheck>), VerifyCommandError> {
+    let prd = PrdDocument::load(None).unwrap_or_default();
+    let story = prd
+        .user_stories
+        .iter()
+        .find(|s| s.id == task_id)
+        .ok_or_else(|| VerifyCommandError::TaskNotFound(task_id.to_string()))?;
+    Ok((
+        check_ci_criteria(&story.acceptance_criteria),
+        check_criterion_commands(&story.acceptance_criteria, None),
+    ))
+}
+
+/// Print a task's criterion checks. Returns whether all passed.
+fn report_task_criteria(task_id: &str, checks: &[CriterionCheck]) -> bool {
+    if checks.is_empty() {
+        return true;
+    }
+
+    println!();
+    println!("\x1b[1mChecking acceptance criteria for {task_id}...\x1b[0m");
+    println!();
+    for check in checks {
+        let status = if check.passed {
+            "\x1b[32m✓\x1b[0m"
+        } else {
+            "\x1b[31m✗\x1b[0m"
+        };
+        println!(
+            "  {status} {}. {} \x1b[2m({}, {:.1}s)\x1b[0m",
+            check.number, check.text, check.command, check.duration_seconds
+        );
+        if !check.passed {
+            for line in excerpt(&check.output).lines() {
+                println!("      \x1b[2m{line}\x1b[0m");
+            }
+        }
+    }
+
+    checks.iter().all(|c| c.passed)
+}
+
+/// Print a task's CI checks. Returns whether all passed.
+fn report_task_ci(task_id: &str, checks: &[CiCheck]) -> bool {
+    if checks.is_empty() {
+        return true;
+    }
+
+    println!();
+    println!("\x1b[1mChecking CI for {task_id}...\x1b[0m");
+    println!();
+    for check in checks {
+        let status = if check.status.is_passed() {
+            "\x1b[32m✓\x1b[0m"
+        } else {
+            "\x1b[31m✗\x1b[0m"
+        };
+        println!(
+            "  {} ci:{} \x1b[2m({})\x1b[0m",
+            status,
+            check.workflow,
+            check.status.describe()
+        );
+    }
+
+    checks.iter().all(|c| c.status.is_passed())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::runner::{CiStatus, GateResult};
 
     #[test]
     fn test_verify_command_error_display() {
@@ -59,5 +608,131 @@ mod tests {
             std::io::Error::new(std::io::ErrorKind::NotFound, "test"),
         ));
         assert!(err.to_string().contains("Failed to load config"));
+
+        let err = VerifyCommandError::TaskNotFound("task-1".to_string());
+        assert_eq!(err.to_string(), "Task not found: task-1");
+
+        let err = VerifyCommandError::PluginError(crate::plugins::PluginError::MissingSymbol {
+            path: "libpolicy.so".into(),
+            symbol: "afk_plugin_free".to_string(),
+        });
+        assert_eq!(
+            err.to_string(),
+            "Plugin libpolicy.so does not export afk_plugin_free"
+        );
+    }
+
+    fn sample_result() -> QualityGateResult {
+        let mut result = QualityGateResult::new();
+        result.add_gate(GateResult {
+            name: "lint".to_string(),
+            passed: true,
+            output: "ok\n".to_string(),
+            duration_seconds: 1.23456,
+        });
+        let output: String = (1..=30).map(|i| format!("line {i}\n")).collect();
+        result.add_gate(GateResult {
+            name: "test".to_string(),
+            passed: false,
+            output: output + "error: <a> & \"b\"\n",
+            duration_seconds: 2.0,
+        });
+        result
+    }
+
+    #[test]
+    fn test_verify_format_from_name() {
+        assert_eq!(VerifyFormat::from_name("json"), VerifyFormat::Json);
+        assert_eq!(VerifyFormat::from_name("junit"), VerifyFormat::Junit);
+        assert_eq!(VerifyFormat::from_name("text"), VerifyFormat::Text);
+    }
+
+    #[test]
+    fn test_verify_report_json() {
+        let paths = [PathBuf::from(".afk/artifacts/4/lint.log")];
+        let report = VerifyReport::new(4, &sample_result(), &paths, &[], &[]);
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["passed"], false);
+        assert_eq!(json["iteration"], 4);
+        assert_eq!(json["duration_seconds"], 3.235);
+        assert_eq!(json["gates"][0]["status"], "passed");
+        assert_eq!(json["gates"][0]["duration_seconds"], 1.235);
+        assert_eq!(json["gates"][0]["artifact"], ".afk/artifacts/4/lint.log");
+        assert!(json["gates"][0].get("excerpt").is_none());
+        assert_eq!(json["gates"][1]["status"], "failed");
+        let excerpt = json["gates"][1]["excerpt"].as_str().unwrap();
+        assert_eq!(excerpt.lines().count(), EXCERPT_LINES);
+        assert!(excerpt.ends_with("error: <a> & \"b\""));
+        assert!(json.get("ci").is_none());
+    }
+
+    #[test]
+    fn test_verify_report_ci_checks() {
+        let checks = [CiCheck {
+            workflow: "release".to_string(),
+            status: CiStatus::Failed("failure".to_string()),
+        }];
+        let mut result = QualityGateResult::new();
+        result.add_gate(GateResult {
+            name: "lint".to_string(),
+            passed: true,
+            output: String::new(),
+            duration_seconds: 0.5,
+        });
+
+        let report = VerifyReport::new(1, &result, &[], &checks, &[]);
+        assert!(!report.passed);
+        assert_eq!(report.ci[0].detail, "failed (failure)");
+
+        let xml = report.to_junit();
+        assert!(xml.contains("tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testsuite name=\"ci\" tests=\"1\" failures=\"1\">"));
+        assert!(xml.contains("<failure message=\"failed (failure)\"/>"));
+    }
+
+    #[test]
+    fn test_verify_report_criterion_checks() {
+        let criteria = [CriterionCheck {
+            number: 2,
+            text: "Auth tests pass".to_string(),
+            command: "cargo test auth::".to_string(),
+            passed: false,
+            output: "test auth::login ... FAILED\n".to_string(),
+            duration_seconds: 1.0,
+        }];
+        let report = VerifyReport::new(1, &QualityGateResult::new(), &[], &[], &criteria);
+        assert!(!report.passed);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["criteria"][0]["number"], 2);
+        assert_eq!(json["criteria"][0]["status"], "failed");
+        assert_eq!(
+            json["criteria"][0]["excerpt"],
+            "test auth::login ... FAILED"
+        );
+
+        let xml = report.to_junit();
+        assert!(xml.contains("tests=\"1\" failures=\"1\""));
+        assert!(xml.contains("<testcase classname=\"afk.criteria\" name=\"#2 Auth tests pass\""));
+        assert!(xml.contains("<failure message=\"cargo test auth:: failed\">"));
+    }
+
+    #[test]
+    fn test_verify_report_junit() {
+        let report = VerifyReport::new(1, &sample_result(), &[], &[], &[]);
+        let xml = report.to_junit();
+
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(xml.contains("<testsuites name=\"afk verify\" tests=\"2\" failures=\"1\""));
+        assert!(xml.contains("<testcase classname=\"afk.gates\" name=\"lint\" time=\"1.235\"/>"));
+        assert!(xml.contains("<failure message=\"test failed\">"));
+        assert!(xml.contains("error: &lt;a&gt; &amp; &quot;b&quot;</failure>"));
+        assert!(!xml.contains("<testsuite name=\"ci\""));
+        assert!(xml.ends_with("</testsuites>\n"));
+    }
+
+    #[test]
+    fn test_xml_escape_drops_control_characters() {
+        assert_eq!(xml_escape("a\x1b[31mb\tc\n"), "a[31mb\tc\n");
     }
 }
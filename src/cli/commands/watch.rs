@@ -0,0 +1,480 @@
+//! Watch command implementation.
+//!
+//! This module implements `afk watch`, a foreground daemon that keeps
+//! tasks.json in sync with its sources. File-based sources (json, markdown)
+//! sync as soon as their files change, other sources are polled on their
+//! `poll_minutes` interval, and with `--go` an `afk go` run is started
+//! whenever new pending tasks appear.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+
+use crate::config::{AfkConfig, ConfigError, SourceConfig, SourceType, TASKS_FILE, WATCH_PID_FILE};
+use crate::path_matcher::DEFAULT_IGNORE_PATTERNS;
+use crate::prd::{plan_sync, resolve_conflicts, PrdDocument, PrdError, TaskOrder};
+use crate::sources::markdown::DEFAULT_PATHS as MARKDOWN_DEFAULT_PATHS;
+use crate::sources::schedule::is_event_driven;
+use crate::sources::{is_poll_due, should_sync_on_change};
+use crate::watcher::FileWatcher;
+
+use super::source::source_type_to_str;
+
+/// How often the loop checks for changes, polls, and Ctrl+C.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Result type for watch command operations.
+pub type WatchCommandResult = Result<(), WatchCommandError>;
+
+/// Error type for watch command operations.
+#[derive(Debug, thiserror::Error)]
+pub enum WatchCommandError {
+    /// Failed to load configuration.
+    #[error("Failed to load config: {0}")]
+    ConfigError(#[from] ConfigError),
+    /// No sources are configured.
+    #[error("No task sources configured. Add one with `afk source add`.")]
+    NoSources,
+    /// Another `afk watch` holds the PID file.
+    #[error("afk watch is already running (PID {0})")]
+    AlreadyRunning(u32),
+    /// Failed to write or read the PID file.
+    #[error("Failed to write PID file: {0}")]
+    PidFileError(std::io::Error),
+    /// Failed to resolve the project directory.
+    #[error("Failed to read current directory: {0}")]
+    CurrentDirError(std::io::Error),
+    /// Failed to start the file watcher.
+    #[error("Failed to watch files: {0}")]
+    WatchError(#[from] notify::Error),
+    /// Failed to sync tasks.
+    #[error("Failed to sync tasks: {0}")]
+    SyncError(#[from] PrdError),
+}
+
+/// Options for `afk watch`.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Quiet period after the last change before syncing.
+    pub debounce: Duration,
+    /// Iterations to run when new pending tasks appear (None: only sync).
+    pub go_iterations: Option<u32>,
+}
+
+/// PID file that marks a running `afk watch`, removed on drop.
+#[derive(Debug)]
+pub struct PidLock {
+    path: PathBuf,
+}
+
+impl PidLock {
+    /// Write our PID to `path`, refusing if a live process already holds it.
+    ///
+    /// A PID file left by a process that is no longer running is replaced.
+    pub fn acquire(path: &Path) -> Result<Self, WatchCommandError> {
+        if let Some(pid) = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| s.trim().parse::<u32>().ok())
+        {
+            if pid != std::process::id() && process_alive(pid) {
+                return Err(WatchCommandError::AlreadyRunning(pid));
+            }
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(WatchCommandError::PidFileError)?;
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .map_err(WatchCommandError::PidFileError)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether a process with this PID is running.
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// Whether a process with this PID is running.
+#[cfg(not(unix))]
+fn process_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+}
+
+/// Files whose changes trigger a sync of an event-driven source.
+///
+/// tasks.json itself is never included, since every sync rewrites it.
+pub fn watched_paths(source: &SourceConfig) -> Vec<PathBuf> {
+    if !is_event_driven(source) {
+        return Vec::new();
+    }
+    let defaults: &[&str] = match source.source_type {
+        SourceType::Markdown => MARKDOWN_DEFAULT_PATHS,
+        SourceType::Json => &["tasks.json"],
+        SourceType::Csv => &[crate::sources::csv::DEFAULT_PATH],
+        _ => &[],
+    };
+    match source.path.as_deref() {
+        Some(path) => vec![PathBuf::from(path)],
+        None => defaults.iter().map(PathBuf::from).collect(),
+    }
+    .into_iter()
+    .filter(|p| p != Path::new(TASKS_FILE))
+    .collect()
+}
+
+/// Indices of event-driven sources affected by changes to these paths.
+fn changed_sources(
+    sources: &[SourceConfig],
+    changed: &HashSet<PathBuf>,
+    root: &Path,
+    now: DateTime<Local>,
+) -> Vec<usize> {
+    sources
+        .iter()
+        .enumerate()
+        .filter(|(_, source)| should_sync_on_change(source, now))
+        .filter(|(_, source)| {
+            watched_paths(source).iter().any(|p| {
+                let watched = root.join(p);
+                changed.iter().any(|c| c.starts_with(&watched))
+            })
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Pending task IDs not in `known`.
+fn new_pending(prd: &PrdDocument, known: &HashSet<String>) -> BTreeSet<String> {
+    prd.get_pending_stories(&TaskOrder::default())
+        .iter()
+        .filter(|s| !known.contains(&s.id))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// Watch sources and keep tasks.json in sync until Ctrl+C.
+pub fn watch(options: WatchOptions) -> WatchCommandResult {
+    let config = AfkConfig::load(None)?;
+    if config.sources.is_empty() {
+        return Err(WatchCommandError::NoSources);
+    }
+
+    let _lock = PidLock::acquire(Path::new(WATCH_PID_FILE))?;
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    if let Err(e) = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst)) {
+        eprintln!("\x1b[2mWarning: Could not set up Ctrl+C handler: {e}\x1b[0m");
+    }
+
+    // Watch everything except the usual build/VCS noise; .afk stays visible
+    // so edits to .afk/tasks.md and tasks.json are seen
+    let root = std::env::current_dir()
+        .and_then(fs::canonicalize)
+        .map_err(WatchCommandError::CurrentDirError)?;
+    let ignore = DEFAULT_IGNORE_PATTERNS
+        .iter()
+        .filter(|p| **p != ".afk")
+        .map(|p| (*p).to_string())
+        .collect();
+    let mut watcher = FileWatcher::with_ignore_patterns(&root, ignore);
+    watcher.start()?;
+
+    print_header(&config.sources, &options);
+
+    // Initial sync of every active source
+    let mut last_sync: Vec<Option<DateTime<Local>>> = vec![None; config.sources.len()];
+    let now = Local::now();
+    let initial: Vec<usize> = (0..config.sources.len())
+        .filter(|&i| {
+            let source = &config.sources[i];
+            should_sync_on_change(source, now) || is_poll_due(source, None, now)
+        })
+        .collect();
+    sync_sources(&config, &initial, &mut last_sync)?;
+    let mut known: HashSet<String> = PrdDocument::load(None)?
+        .get_pending_stories(&TaskOrder::default())
+        .iter()
+        .map(|s| s.id.clone())
+        .collect();
+
+    let tasks_file = root.join(TASKS_FILE);
+    let mut changed: HashSet<PathBuf> = HashSet::new();
+    let mut last_change = Instant::now();
+
+    while !interrupted.load(Ordering::SeqCst) {
+        std::thread::sleep(TICK);
+
+        let changes = watcher.get_changes();
+        if !changes.is_empty() {
+            changed.extend(changes.into_iter().map(|c| c.path));
+            last_change = Instant::now();
+        }
+
+        let now = Local::now();
+        let mut due: Vec<usize> = (0..config.sources.len())
+            .filter(|&i| is_poll_due(&config.sources[i], last_sync[i], now))
+            .collect();
+        let settled = !changed.is_empty() && last_change.elapsed() >= options.debounce;
+        let tasks_edited = settled && changed.contains(&tasks_file);
+        if settled {
+            due.extend(changed_sources(&config.sources, &changed, &root, now));
+            changed.clear();
+        }
+        due.sort_unstable();
+        due.dedup();
+        if due.is_empty() && !tasks_edited {
+            continue;
+        }
+
+        sync_sources(&config, &due, &mut last_sync)?;
+        let prd = PrdDocument::load(None)?;
+        let fresh = new_pending(&prd, &known);
+        known.extend(fresh.iter().cloned());
+        if fresh.is_empty() {
+            continue;
+        }
+
+        println!(
+            "{} \x1b[36m{} new pending task{}:\x1b[0m {}",
+            timestamp(),
+            fresh.len(),
+            if fresh.len() == 1 { "" } else { "s" },
+            fresh.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+        if let Some(iterations) = options.go_iterations {
+            run_go(iterations);
+            // Ignore the run's own edits and start afresh
+            watcher.clear();
+            changed.clear();
+            known.extend(
+                PrdDocument::load(None)?
+                    .get_pending_stories(&TaskOrder::default())
+                    .iter()
+                    .map(|s| s.id.clone()),
+            );
+        }
+    }
+
+    watcher.stop();
+    println!("\n\x1b[2mStopped watching.\x1b[0m");
+    Ok(())
+}
+
+/// Sync the sources at these indices, recording when each was synced.
+///
+/// Sync conflicts are settled by `runner.sync_conflicts`.
+fn sync_sources(
+    config: &AfkConfig,
+    indices: &[usize],
+    last_sync: &mut [Option<DateTime<Local>>],
+) -> Result<(), PrdError> {
+    if indices.is_empty() {
+        return Ok(());
+    }
+    let selected: Vec<SourceConfig> = indices.iter().map(|&i| config.sources[i].clone()).collect();
+    let mut plan = plan_sync(&selected, &config.dedup, None, None)?;
+    let policy = config.runner.sync_conflicts;
+    resolve_conflicts(&mut plan, None, config.github.sync_back, |_| policy)?;
+    let now = Local::now();
+    for &i in indices {
+        last_sync[i] = Some(now);
+    }
+    if plan.is_empty() {
+        return Ok(());
+    }
+
+    plan.save(None)?;
+    let names: Vec<&str> = selected
+        .iter()
+        .map(|s| source_type_to_str(&s.source_type))
+        .collect();
+    println!(
+        "{} Synced {}: {} added, {} updated",
+        timestamp(),
+        names.join(", "),
+        plan.added.len(),
+        plan.updated.len()
+    );
+    Ok(())
+}
+
+/// Run `afk go` for a number of iterations and wait for it.
+fn run_go(iterations: u32) {
+    let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("afk"));
+    println!("{} \x1b[1mStarting afk go {iterations}\x1b[0m", timestamp());
+    match Command::new(exe)
+        .args(["go", &iterations.to_string()])
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("\x1b[33mafk go exited with {status}\x1b[0m"),
+        Err(e) => eprintln!("\x1b[31mFailed to start afk go: {e}\x1b[0m"),
+    }
+}
+
+/// Print what is being watched and polled.
+fn print_header(sources: &[SourceConfig], options: &WatchOptions) {
+    println!("\x1b[1mafk watch\x1b[0m \x1b[2m(Ctrl+C to stop)\x1b[0m");
+    for (i, source) in sources.iter().enumerate() {
+        let name = source_type_to_str(&source.source_type);
+        let how = if is_event_driven(source) {
+            let files: Vec<String> = watched_paths(source)
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect();
+            format!("on change to {}", files.join(", "))
+        } else {
+            format!(
+                "every {} min",
+                source
+                    .poll_minutes
+                    .unwrap_or(crate::sources::schedule::DEFAULT_POLL_MINUTES)
+            )
+        };
+        let hours = source
+            .active_hours
+            .as_deref()
+            .map(|h| format!(" \x1b[2m(active {h})\x1b[0m"))
+            .unwrap_or_default();
+        println!("  {}. {name}: sync {how}{hours}", i + 1);
+    }
+    match options.go_iterations {
+        Some(n) => println!("  New pending tasks start \x1b[36mafk go {n}\x1b[0m"),
+        None => println!("  \x1b[2mSync only; pass --go to run iterations on new tasks\x1b[0m"),
+    }
+    println!();
+}
+
+/// Dimmed local time prefix for log lines.
+fn timestamp() -> String {
+    format!("\x1b[2m[{}]\x1b[0m", Local::now().format("%H:%M:%S"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::UserStory;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_watch_command_error_display() {
+        assert_eq!(
+            WatchCommandError::AlreadyRunning(42).to_string(),
+            "afk watch is already running (PID 42)"
+        );
+        assert!(WatchCommandError::NoSources
+            .to_string()
+            .contains("afk source add"));
+    }
+
+    #[test]
+    fn test_pid_lock() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".afk/watch.pid");
+
+        {
+            let _lock = PidLock::acquire(&path).unwrap();
+            let pid = fs::read_to_string(&path).unwrap();
+            assert_eq!(pid.trim(), std::process::id().to_string());
+        }
+        assert!(!path.exists());
+
+        // A stale PID file is replaced
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "4294967290\n").unwrap();
+        let lock = PidLock::acquire(&path).unwrap();
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pid_lock_held_by_live_process() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("watch.pid");
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        fs::write(&path, child.id().to_string()).unwrap();
+
+        let result = PidLock::acquire(&path);
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(matches!(result, Err(WatchCommandError::AlreadyRunning(pid)) if pid == child.id()));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_watched_paths() {
+        assert_eq!(
+            watched_paths(&SourceConfig::markdown("TODO.md")),
+            vec![PathBuf::from("TODO.md")]
+        );
+        assert_eq!(
+            watched_paths(&SourceConfig::new(SourceType::Markdown)).len(),
+            MARKDOWN_DEFAULT_PATHS.len()
+        );
+        assert!(watched_paths(&SourceConfig::json(TASKS_FILE)).is_empty());
+        assert!(watched_paths(&SourceConfig::github("acme/api", Vec::new())).is_empty());
+
+        let mut polled = SourceConfig::markdown("TODO.md");
+        polled.poll_minutes = Some(5);
+        assert!(watched_paths(&polled).is_empty());
+    }
+
+    #[test]
+    fn test_changed_sources() {
+        let root = Path::new("/repo");
+        let sources = vec![
+            SourceConfig::markdown("TODO.md"),
+            SourceConfig::json("backlog/tasks.json"),
+            SourceConfig::beads(),
+        ];
+        let now = Local::now();
+
+        let changed: HashSet<PathBuf> = [PathBuf::from("/repo/backlog/tasks.json")].into();
+        assert_eq!(changed_sources(&sources, &changed, root, now), vec![1]);
+
+        let changed: HashSet<PathBuf> = [PathBuf::from("/repo/src/main.rs")].into();
+        assert!(changed_sources(&sources, &changed, root, now).is_empty());
+    }
+
+    #[test]
+    fn test_new_pending() {
+        let mut done = UserStory::new("done", "Done");
+        done.passes = true;
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory::new("old", "Old"),
+                UserStory::new("new", "New"),
+                done,
+            ],
+            ..Default::default()
+        };
+        let known: HashSet<String> = ["old".to_string()].into();
+
+        let fresh = new_pending(&prd, &known);
+        assert_eq!(fresh.into_iter().collect::<Vec<_>>(), vec!["new"]);
+    }
+}
@@ -8,6 +8,7 @@ pub mod output;
 pub mod update;
 
 use clap::{Args, Parser, Subcommand};
+use clap_complete::ArgValueCandidates;
 use std::fmt;
 
 // ============================================================================
@@ -62,6 +63,12 @@ impl fmt::Display for CliError {
 
 impl std::error::Error for CliError {}
 
+impl From<crate::AfkError> for CliError {
+    fn from(error: crate::AfkError) -> Self {
+        CliError::Command(error.to_string())
+    }
+}
+
 /// Type alias for CLI command results.
 pub type CliResult = Result<ExitCode, CliError>;
 
@@ -87,6 +94,15 @@ pub fn handle_result(result: CliResult) -> std::process::ExitCode {
     }
 }
 
+/// Install the process-wide settings taken from `config`: the commit
+/// identity.
+///
+/// Called once at startup and again by `afk go --profile`, whose profile
+/// is only known after the startup config was loaded.
+pub fn apply_config_settings(config: &crate::config::AfkConfig) {
+    crate::git::set_commit_identity(config.git.commit_identity());
+}
+
 /// Autonomous AI coding loops - Ralph Wiggum style.
 ///
 /// Run AI coding tasks in a loop with fresh context each iteration.
@@ -99,6 +115,61 @@ pub struct Cli {
     /// The subcommand to run.
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Refuse to write any afk state (also enabled by AFK_READ_ONLY=1).
+    #[arg(long, global = true)]
+    pub read_only: bool,
+}
+
+impl Commands {
+    /// Whether this command writes afk state (config, tasks, progress, archives).
+    ///
+    /// Used to refuse the command up front in read-only mode.
+    pub fn writes_state(&self) -> bool {
+        match self {
+            Commands::Status(_)
+            | Commands::Prompt(_)
+            | Commands::Verify(_)
+            | Commands::Completions(_)
+            | Commands::Preset(_)
+            | Commands::Repro(_)
+            | Commands::Replay(_)
+            | Commands::Schema(_)
+            | Commands::Explain(_)
+            | Commands::Log(_)
+            | Commands::Logs(_)
+            | Commands::Serve(_)
+            | Commands::Sandbox(SandboxCommands::Diff(_))
+            | Commands::Fleet(FleetCommands::Status(_))
+            | Commands::Scheduler(SchedulerCommands::Next(_))
+            | Commands::Report(_)
+            | Commands::Export(_)
+            | Commands::Stats(_) => false,
+            Commands::Sandbox(_) => true,
+            Commands::Git(_) => true,
+            Commands::Task(task) => task.command.is_some(),
+            Commands::Source(subcmd) => !matches!(subcmd, SourceCommands::List(_)),
+            Commands::Tasks { command, .. } => match command {
+                Some(TasksCommands::Sync(sync)) => !sync.dry_run,
+                Some(TasksCommands::Enrich(enrich)) => !enrich.dry_run,
+                Some(TasksCommands::Validate(_) | TasksCommands::Export(_)) | None => false,
+            },
+            Commands::Sync(sync) => !sync.dry_run,
+            Commands::Archive { command, .. } => command.is_none(),
+            Commands::Config(subcmd) => matches!(
+                subcmd,
+                ConfigCommands::Set(_)
+                    | ConfigCommands::Reset(_)
+                    | ConfigCommands::Edit(_)
+                    | ConfigCommands::Tui(_)
+                    | ConfigCommands::Convert(_)
+                    | ConfigCommands::Profile(
+                        ConfigProfileCommands::Create(_) | ConfigProfileCommands::Use(_)
+                    )
+            ),
+            _ => true,
+        }
+    }
 }
 
 /// Top-level commands for afk.
@@ -129,9 +200,38 @@ pub enum Commands {
     /// Use -v for verbose output including learnings.
     Status(StatusCommand),
 
-    /// Show details of a specific task.
+    /// Report on progress over time.
+    ///
+    /// Examples:
+    ///   afk report burndown
+    ///   afk report burndown --json
+    #[command(subcommand)]
+    Report(ReportCommands),
+
+    /// Show metrics across archived sessions.
+    ///
+    /// Reads .afk/archive/, the current session and the event log:
+    /// success rate per task source, iterations per completed task,
+    /// failure reasons, and iterations, tokens and cost per day.
+    ///
+    /// Examples:
+    ///   afk stats
+    ///   afk stats --since 30d
+    ///   afk stats --format csv > stats.csv
+    Stats(StatsCommand),
+
+    /// Show, add, edit or remove a task.
     ///
-    /// Displays full task information including acceptance criteria and learnings.
+    /// `afk task <id>` displays full task information including acceptance
+    /// criteria and learnings. The add, edit and rm subcommands manage tasks
+    /// in .afk/tasks.json by hand, and new adds one from a template.
+    ///
+    /// Examples:
+    ///   afk task auth-001
+    ///   afk task add --title "Add logout" --ac "Session is cleared" --ac "Redirects home"
+    ///   afk task new --template bugfix
+    ///   afk task edit auth-001 --priority 1
+    ///   afk task rm auth-001
     Task(TaskCommand),
 
     /// Preview the prompt for the next iteration.
@@ -156,6 +256,28 @@ pub enum Commands {
     /// Clears failure count and sets status back to pending.
     Reset(ResetCommand),
 
+    /// Pause a running loop once its current iteration ends.
+    ///
+    /// The loop waits between iterations until `afk resume`.
+    Pause(PauseCommand),
+
+    /// Let a paused loop carry on.
+    Resume(ResumeCommand),
+
+    /// Serve a web dashboard and HTTP API for watching and steering a run.
+    ///
+    /// The dashboard is at /. The API answers JSON on /status, /tasks,
+    /// /iteration, /events and /metrics, streams updates on /stream, and
+    /// takes POSTs to /pause, /resume, /stop and /skip. Set server.token or
+    /// AFK_SERVER_TOKEN to require `Authorization: Bearer <token>`.
+    Serve(ServeCommand),
+
+    /// Add a note to the current session.
+    ///
+    /// Notes are timestamped, kept with the session, and carried into its
+    /// archive metadata. Run without text to show the session's tags and notes.
+    Note(NoteCommand),
+
     /// Manage task sources.
     #[command(subcommand)]
     Source(SourceCommands),
@@ -166,7 +288,8 @@ pub enum Commands {
     /// configured AI CLI to convert it into structured JSON format in .afk/tasks.json.
     ///
     /// By default, runs the AI CLI directly. Use --stdout, --copy, or --file
-    /// to output the prompt for manual use instead.
+    /// to output the prompt for manual use instead. With --format trello, a
+    /// Trello board export is converted directly, without the AI CLI.
     Import(ImportCommand),
 
     /// List and manage tasks.
@@ -174,7 +297,7 @@ pub enum Commands {
     /// Shows tasks from .afk/tasks.json. Use `afk tasks sync` to aggregate from sources.
     #[command(subcommand_required = false, args_conflicts_with_subcommands = true)]
     Tasks {
-        /// Tasks subcommand (sync) or list tasks if omitted.
+        /// Tasks subcommand (sync, validate, export, enrich) or list tasks if omitted.
         #[command(subcommand)]
         command: Option<TasksCommands>,
 
@@ -189,6 +312,14 @@ pub enum Commands {
         /// Maximum number of tasks to show.
         #[arg(short = 'l', long, default_value = "50")]
         limit: usize,
+
+        /// Rank pending tasks by estimated chance of autonomous completion.
+        #[arg(long, conflicts_with = "complete")]
+        scores: bool,
+
+        /// Print the tasks as JSON instead of a table.
+        #[arg(long, conflicts_with = "scores")]
+        json: bool,
     },
 
     /// Sync tasks from configured sources.
@@ -197,6 +328,26 @@ pub enum Commands {
     /// into .afk/tasks.json.
     Sync(TasksSyncCommand),
 
+    /// Watch sources and keep tasks in sync.
+    ///
+    /// Syncs file-based sources (TODO.md, tasks.json) when they change and
+    /// polls the rest on their poll_minutes interval. With --go, runs
+    /// iterations whenever new pending tasks appear. Stop with Ctrl+C.
+    ///
+    /// Examples:
+    ///   afk watch              # Sync on change
+    ///   afk watch --go 3       # Also run 3 iterations when tasks appear
+    Watch(WatchCommand),
+
+    /// Run the loop on the schedule in schedule.cron.
+    ///
+    /// Examples:
+    ///   afk config set schedule.cron "0 2 * * *"
+    ///   afk scheduler next
+    ///   afk scheduler start    # Run afk go at 02:00 every day
+    #[command(subcommand)]
+    Scheduler(SchedulerCommands),
+
     /// Archive and clear current session.
     ///
     /// Moves tasks.json and progress.json to a timestamped archive directory,
@@ -224,6 +375,101 @@ pub enum Commands {
     #[command(subcommand)]
     Config(ConfigCommands),
 
+    /// Show how to reproduce a past iteration.
+    ///
+    /// Prints the model, seed, versions, and prompt/config hashes recorded
+    /// for the iteration, plus the exact AI CLI command it ran.
+    Repro(ReproCommand),
+
+    /// Replay what happened in a past iteration.
+    ///
+    /// Puts together the prompt it was sent, what the AI CLI said, the gates
+    /// that ran and the commits it made, for working out why it went wrong.
+    ///
+    /// Examples:
+    ///   afk replay 4
+    ///   afk replay 4 --raw
+    Replay(ReplayCommand),
+
+    /// Show the structured event log.
+    ///
+    /// Prints recent loop events from .afk/events.jsonl. Use --follow to
+    /// keep printing as they arrive and --json for raw NDJSON records.
+    Log(LogCommand),
+
+    /// Show the AI CLI transcript of a past iteration.
+    ///
+    /// Without an iteration, lists the transcripts kept in .afk/logs. With
+    /// one, prints its raw output, stderr and parsed events; use --follow to
+    /// watch an iteration that is still running.
+    ///
+    /// Examples:
+    ///   afk logs
+    ///   afk logs 12 --events
+    Logs(LogsCommand),
+
+    /// Review and land the work of `afk go --sandbox` runs.
+    ///
+    /// Examples:
+    ///   afk sandbox diff --stat
+    ///   afk sandbox apply
+    ///   afk sandbox apply --patch sandbox.patch
+    #[command(subcommand)]
+    Sandbox(SandboxCommands),
+
+    /// Tidy up the git history the loop leaves behind.
+    ///
+    /// Examples:
+    ///   afk git squash --task auth-login
+    ///   afk git squash --session --yes
+    #[command(subcommand)]
+    Git(GitCommands),
+
+    /// Export the session's work for review elsewhere.
+    ///
+    /// Examples:
+    ///   afk export patches --out review/
+    #[command(subcommand)]
+    Export(ExportCommands),
+
+    /// Approve, reject or edit the tasks held for review.
+    ///
+    /// With review.required set, tasks the loop marks passed wait here
+    /// instead of completing. Each one is shown with its acceptance
+    /// criteria, last gate run and commits.
+    ///
+    /// Examples:
+    ///   afk review
+    ///   afk review auth-login
+    Review(ReviewCommand),
+
+    /// Work across several afk projects listed in afk-fleet.json.
+    ///
+    /// Examples:
+    ///   afk fleet init                  # List the projects found below here
+    ///   afk fleet status
+    ///   afk fleet go 5 --max-parallel 2
+    #[command(subcommand)]
+    Fleet(FleetCommands),
+
+    /// Explain why the loop would or wouldn't pick a task.
+    ///
+    /// Shows the reasons a task is excluded now (unfinished dependencies,
+    /// failure budget used up, skipped this session, claimed by a worker)
+    /// and at the last selection the loop recorded.
+    ///
+    /// Examples:
+    ///   afk explain --task auth-login
+    Explain(ExplainCommand),
+
+    /// Browse curated init presets.
+    ///
+    /// Presets bundle quality gates, context files, prompt instructions,
+    /// and limits for common project types. Apply one with
+    /// 'afk init --preset <name>'.
+    #[command(subcommand)]
+    Preset(PresetCommands),
+
     /// Update afk to the latest version.
     ///
     /// Downloads and installs the latest release from GitHub.
@@ -231,9 +477,24 @@ pub enum Commands {
 
     /// Generate shell completions.
     ///
-    /// Outputs completion script to stdout for bash, zsh, or fish.
+    /// Outputs a completion script to stdout for bash, zsh, fish, powershell
+    /// or elvish. With --dynamic, the script asks afk for completions as you
+    /// type, which also completes task IDs.
+    ///
+    /// Examples:
+    ///   afk completions bash > ~/.local/share/bash-completion/completions/afk
+    ///   afk completions zsh --dynamic > ~/.zfunc/_afk
     Completions(CompletionsCommand),
 
+    /// Print the JSON Schema for tasks.json or progress.json.
+    ///
+    /// For tools that generate afk backlogs to check their output against.
+    ///
+    /// Examples:
+    ///   afk schema tasks > tasks.schema.json
+    ///   afk schema progress
+    Schema(SchemaCommand),
+
     /// Switch the AI CLI used by afk.
     ///
     /// Quickly switch between AI CLI tools (claude, cursor, codex, etc.)
@@ -271,7 +532,7 @@ pub struct GoCommand {
 
     /// Re-run setup (re-prompts for AI CLI selection).
     ///
-    /// Deletes existing .afk/config.json and prompts for full reconfiguration
+    /// Deletes the existing .afk config file and prompts for full reconfiguration
     /// including AI CLI selection.
     #[arg(long)]
     pub init: bool,
@@ -286,6 +547,19 @@ pub struct GoCommand {
     #[arg(short = 't', long)]
     pub timeout: Option<u32>,
 
+    /// Run for a wall-clock budget (e.g. 90m, 1h30m) instead of an iteration count.
+    ///
+    /// The in-flight iteration finishes before stopping. Replaces the session
+    /// timeout; an explicit iteration count still applies.
+    #[arg(long = "for", value_name = "DURATION", value_parser = crate::runner::parse_time_budget)]
+    pub time_budget: Option<std::time::Duration>,
+
+    /// Wait until a local time (e.g. 02:00 or "2026-01-31 02:00") before starting.
+    ///
+    /// A bare time means its next occurrence, today or tomorrow.
+    #[arg(long, value_name = "TIME", value_parser = crate::runner::parse_start_time)]
+    pub at: Option<crate::runner::StartTime>,
+
     /// Feedback display mode.
     ///
     /// Options: tui (rich dashboard), full, minimal, off
@@ -295,6 +569,34 @@ pub struct GoCommand {
     /// Disable ASCII mascot in feedback display.
     #[arg(long)]
     pub no_mascot: bool,
+
+    /// Don't regenerate derived context (warm-up notes) whose inputs changed.
+    #[arg(long)]
+    pub no_refresh: bool,
+
+    /// Tag the session (repeatable), e.g. --tag payment-refactor-night2.
+    ///
+    /// Tags are kept in the archive and searchable with `afk archive list --tag`.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Run this many AI CLI instances in parallel, each on its own task.
+    ///
+    /// Workers use separate git worktrees on afk/worker-N branches, merged
+    /// back one at a time as their tasks pass.
+    #[arg(short = 'w', long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=16))]
+    pub workers: u32,
+
+    /// Run in a sandbox git worktree, leaving the working tree untouched.
+    ///
+    /// Changes stay on the afk/sandbox branch until `afk sandbox apply`
+    /// merges them; review them first with `afk sandbox diff`.
+    #[arg(long)]
+    pub sandbox: bool,
+
+    /// Lay a config profile from .afk/profiles/ over the config for this run.
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
 }
 
 /// Arguments for the 'init' command.
@@ -311,6 +613,10 @@ pub struct InitCommand {
     /// Accept all defaults without prompting.
     #[arg(short = 'y', long)]
     pub yes: bool,
+
+    /// Apply a curated preset (see 'afk preset list').
+    #[arg(short = 'p', long)]
+    pub preset: Option<String>,
 }
 
 /// Arguments for the 'status' command.
@@ -319,169 +625,768 @@ pub struct StatusCommand {
     /// Show verbose output including learnings.
     #[arg(short = 'v', long)]
     pub verbose: bool,
-}
 
-/// Arguments for the 'task' command.
-#[derive(Args, Debug)]
-pub struct TaskCommand {
-    /// Task ID to show details for.
-    pub task_id: String,
+    /// Print the status as JSON instead of text.
+    #[arg(long, conflicts_with = "verbose")]
+    pub json: bool,
 }
 
-/// Subcommands for source management.
+/// Subcommands for progress reports.
 #[derive(Subcommand, Debug)]
-pub enum SourceCommands {
-    /// Add a task source.
-    Add(SourceAddCommand),
+pub enum ReportCommands {
+    /// Chart the backlog burn-down, with the pace and a projected finish.
+    ///
+    /// Counts summed task estimates (reporting.estimate_unit) when tasks
+    /// have them, otherwise tasks.
+    Burndown(ReportBurndownCommand),
+}
 
-    /// List configured task sources.
-    List(SourceListCommand),
+/// Arguments for 'report burndown' command.
+#[derive(Args, Debug)]
+pub struct ReportBurndownCommand {
+    /// Most recent points to chart.
+    #[arg(short = 'w', long, default_value_t = 60)]
+    pub width: usize,
 
-    /// Remove a task source by index (1-based).
-    Remove(SourceRemoveCommand),
+    /// Rows in the chart.
+    #[arg(long, default_value_t = 10)]
+    pub height: usize,
+
+    /// Print the report and full history as JSON instead.
+    #[arg(long)]
+    pub json: bool,
 }
 
-/// Arguments for 'source add' command.
+/// Arguments for the 'stats' command.
 #[derive(Args, Debug)]
-pub struct SourceAddCommand {
-    /// Type of source to add.
-    #[arg(value_parser = ["beads", "json", "markdown", "github"])]
-    pub source_type: String,
+pub struct StatsCommand {
+    /// Only sessions started and events recorded on or after a date
+    /// (2026-01-31) or within a span (30d, 4w).
+    #[arg(long)]
+    pub since: Option<String>,
 
-    /// Path to the source file (for json/markdown types).
-    pub path: Option<String>,
+    /// Output format: text, or csv / json for other tools.
+    #[arg(long, value_parser = ["text", "csv", "json"], default_value = "text")]
+    pub format: String,
+
+    /// Print JSON (same as --format json).
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
 }
 
-/// Arguments for 'source list' command.
+/// Arguments for the 'task' command.
 #[derive(Args, Debug)]
-pub struct SourceListCommand {}
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct TaskCommand {
+    /// Task subcommand (add, new, edit, rm), or show a task if omitted.
+    #[command(subcommand)]
+    pub command: Option<TaskCommands>,
 
-/// Arguments for 'source remove' command.
-#[derive(Args, Debug)]
-pub struct SourceRemoveCommand {
-    /// Index of source to remove (1-based).
-    pub index: usize,
+    /// Task ID to show details for.
+    #[arg(required = true, add = ArgValueCandidates::new(commands::completions::task_ids))]
+    pub task_id: Option<String>,
 }
 
-/// Subcommands for task list management.
+/// Subcommands for managing tasks by hand.
 #[derive(Subcommand, Debug)]
-pub enum TasksCommands {
-    /// Sync tasks from all configured sources.
+pub enum TaskCommands {
+    /// Add a task to .afk/tasks.json.
     ///
-    /// Aggregates tasks from beads, JSON, markdown, and GitHub into a unified
-    /// .afk/tasks.json file.
-    Sync(TasksSyncCommand),
-}
+    /// In a terminal, prompts for the title, description, priority and
+    /// acceptance criteria when --title is not given. Without --id, the ID
+    /// is made from the title.
+    Add(TaskAddCommand),
 
-/// Subcommands for config management.
-#[derive(Subcommand, Debug)]
-pub enum ConfigCommands {
-    /// Show all configuration values.
+    /// Add a task from a template in .afk/templates/tasks/.
     ///
-    /// Displays all config sections and their current values.
-    Show(ConfigShowCommand),
-
-    /// Get a specific config value.
+    /// Fills the template's placeholders from --set, prompting in a
+    /// terminal for the rest. Built-in templates: bugfix, feature and
+    /// refactor.
     ///
-    /// Use dot notation for nested keys (e.g., limits.max_iterations).
-    Get(ConfigGetCommand),
+    /// Examples:
+    ///   afk task new --template bugfix
+    ///   afk task new -t bugfix --set component=checkout --set error="Card declined"
+    ///   afk task new --list
+    New(TaskNewCommand),
 
-    /// Set a config value.
+    /// Edit fields of a task in .afk/tasks.json.
     ///
-    /// Use dot notation for keys. Values are validated before saving.
-    Set(ConfigSetCommand),
+    /// Only the fields given are changed. Tasks from a source may be
+    /// overwritten by the next sync.
+    Edit(TaskEditCommand),
 
-    /// Reset config to defaults.
+    /// Remove a task from .afk/tasks.json.
     ///
-    /// Can reset a specific key, a section, or all config.
-    Reset(ConfigResetCommand),
+    /// Other tasks that depend on it no longer do.
+    #[command(alias = "remove")]
+    Rm(TaskRmCommand),
+}
 
-    /// Open config file in your editor.
-    ///
-    /// Uses $EDITOR environment variable.
-    Edit(ConfigEditCommand),
+/// Arguments for 'task add' command.
+#[derive(Args, Debug)]
+pub struct TaskAddCommand {
+    /// Task ID (made from the title if omitted).
+    #[arg(long)]
+    pub id: Option<String>,
 
-    /// Show documentation for config keys.
-    ///
-    /// Displays description, type, default value, and examples.
-    Explain(ConfigExplainCommand),
+    /// Task title.
+    #[arg(short, long)]
+    pub title: Option<String>,
 
-    /// List all valid config keys.
-    Keys(ConfigKeysCommand),
+    /// Task description (defaults to the title).
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// Priority from 1 (highest) to 5 (lowest).
+    #[arg(short, long)]
+    pub priority: Option<i32>,
+
+    /// Acceptance criterion (repeatable).
+    #[arg(long = "ac", value_name = "CRITERION")]
+    pub acceptance_criteria: Vec<String>,
+
+    /// ID of a task that must be complete first (repeatable).
+    #[arg(long, value_name = "TASK_ID")]
+    pub depends_on: Vec<String>,
+
+    /// Model to run the task with, overriding ai_cli.models.
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// AI CLI to run the task with, overriding ai_cli.command.
+    #[arg(long)]
+    pub cli: Option<String>,
 }
 
-/// Arguments for 'config show' command.
+/// Arguments for 'task new' command.
 #[derive(Args, Debug)]
-pub struct ConfigShowCommand {
-    /// Filter to a specific section (e.g., 'limits', 'ai_cli').
-    #[arg(short = 's', long)]
-    pub section: Option<String>,
+pub struct TaskNewCommand {
+    /// Template name (a .afk/templates/tasks/<name>.json file or a built-in).
+    #[arg(short, long, required_unless_present = "list")]
+    pub template: Option<String>,
+
+    /// Value for a template placeholder (repeatable).
+    #[arg(long = "set", value_name = "NAME=VALUE")]
+    pub settings: Vec<String>,
+
+    /// Task ID (made from the title if omitted).
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// List the available templates.
+    #[arg(long, conflicts_with = "template")]
+    pub list: bool,
 }
 
-/// Arguments for 'config get' command.
+/// Arguments for 'task edit' command.
 #[derive(Args, Debug)]
-pub struct ConfigGetCommand {
-    /// Config key in dot notation (e.g., limits.max_iterations).
-    pub key: String,
+pub struct TaskEditCommand {
+    /// Task ID to edit.
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
+    pub task_id: String,
+
+    /// New title.
+    #[arg(short, long)]
+    pub title: Option<String>,
+
+    /// New description.
+    #[arg(short, long)]
+    pub description: Option<String>,
+
+    /// New priority from 1 (highest) to 5 (lowest).
+    #[arg(short, long)]
+    pub priority: Option<i32>,
+
+    /// Replace the acceptance criteria (repeatable).
+    #[arg(long = "ac", value_name = "CRITERION")]
+    pub acceptance_criteria: Vec<String>,
+
+    /// Add an acceptance criterion (repeatable).
+    #[arg(long = "add-ac", value_name = "CRITERION")]
+    pub add_criteria: Vec<String>,
+
+    /// Replace the task's dependencies (repeatable).
+    #[arg(long, value_name = "TASK_ID")]
+    pub depends_on: Vec<String>,
+
+    /// Model to run the task with ("" clears it).
+    #[arg(long)]
+    pub model: Option<String>,
+
+    /// AI CLI to run the task with ("" clears it).
+    #[arg(long)]
+    pub cli: Option<String>,
+
+    /// Accept the fields `afk tasks enrich` generated as reviewed.
+    #[arg(long)]
+    pub reviewed: bool,
 }
 
-/// Arguments for 'config set' command.
+/// Arguments for 'task rm' command.
 #[derive(Args, Debug)]
-pub struct ConfigSetCommand {
-    /// Config key in dot notation (e.g., limits.max_iterations).
-    pub key: String,
-
-    /// Value to set.
-    pub value: String,
+pub struct TaskRmCommand {
+    /// Task ID to remove.
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
+    pub task_id: String,
 }
 
-/// Arguments for 'config reset' command.
+/// Arguments for the 'repro' command.
 #[derive(Args, Debug)]
-pub struct ConfigResetCommand {
-    /// Key or section to reset. If omitted, resets all config.
-    pub key: Option<String>,
+pub struct ReproCommand {
+    /// Session iteration number.
+    pub iteration: u32,
 
-    /// Skip confirmation prompt.
-    #[arg(short = 'y', long)]
-    pub yes: bool,
+    /// Print the record as JSON (for bug reports).
+    #[arg(long)]
+    pub json: bool,
 }
 
-/// Arguments for 'config edit' command.
+/// Arguments for the 'replay' command.
 #[derive(Args, Debug)]
-pub struct ConfigEditCommand {}
+pub struct ReplayCommand {
+    /// Session iteration number.
+    pub iteration: u32,
 
-/// Arguments for 'config explain' command.
-#[derive(Args, Debug)]
-pub struct ConfigExplainCommand {
-    /// Config key to explain. If omitted, lists all keys with brief descriptions.
-    pub key: Option<String>,
+    /// Show the whole transcript, not just the parsed events.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Print the replay as JSON.
+    #[arg(long)]
+    pub json: bool,
 }
 
-/// Arguments for 'config keys' command.
+/// Arguments for the 'log' command.
 #[derive(Args, Debug)]
-pub struct ConfigKeysCommand {}
+pub struct LogCommand {
+    /// Keep printing events as they are appended.
+    #[arg(short, long)]
+    pub follow: bool,
 
-/// Arguments for 'import' command.
-#[derive(Args, Debug)]
-pub struct ImportCommand {
-    /// Input file to import.
-    pub input_file: String,
+    /// Print raw NDJSON records.
+    #[arg(long)]
+    pub json: bool,
 
-    /// Output JSON path.
-    #[arg(short = 'o', long, default_value = ".afk/tasks.json")]
-    pub output: String,
+    /// Number of recent events to show.
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub lines: usize,
+}
 
-    /// Copy to clipboard.
-    #[arg(short = 'c', long)]
-    pub copy: bool,
+/// Arguments for the 'logs' command.
+#[derive(Args, Debug)]
+pub struct LogsCommand {
+    /// Iteration to show (omit to list the transcripts kept).
+    pub iteration: Option<u32>,
 
-    /// Write prompt to file.
-    #[arg(short = 'f', long)]
-    pub file: bool,
+    /// Keep printing lines as they are appended.
+    #[arg(short, long, requires = "iteration")]
+    pub follow: bool,
+
+    /// Show only the parsed event summaries.
+    #[arg(long, requires = "iteration")]
+    pub events: bool,
+}
+
+/// Subcommands for the sandbox.
+#[derive(Subcommand, Debug)]
+pub enum SandboxCommands {
+    /// Show the sandbox's changes, including uncommitted work.
+    Diff(SandboxDiffCommand),
+
+    /// Merge the sandbox into the current branch and remove it.
+    ///
+    /// Its task state replaces .afk/tasks.json and .afk/progress.json.
+    Apply(SandboxApplyCommand),
+
+    /// Remove the sandbox, throwing its changes away.
+    Discard(SandboxDiscardCommand),
+}
+
+/// Subcommands for git history.
+#[derive(Subcommand, Debug)]
+pub enum GitCommands {
+    /// Squash the commits recorded for a task, or the session, into one.
+    ///
+    /// The message is built from commit_message_template, the task titles
+    /// and their learnings. Other commits in between are kept, with new
+    /// hashes; the hashes in progress.json are updated to match.
+    Squash(GitSquashCommand),
+}
+
+/// Subcommands for exporting work.
+#[derive(Subcommand, Debug)]
+pub enum ExportCommands {
+    /// Write each completed task's commits as a patch file, with an index.
+    ///
+    /// Files are numbered in the order to apply them with `git am`.
+    Patches(ExportPatchesCommand),
+}
+
+/// Subcommands for multi-project fleets.
+#[derive(Subcommand, Debug)]
+pub enum FleetCommands {
+    /// Write afk-fleet.json listing the afk projects below this directory.
+    Init(FleetInitCommand),
+
+    /// Show tasks and session state for every project, with totals.
+    Status(FleetStatusCommand),
+
+    /// Sync tasks from their sources in every project.
+    Sync(FleetSyncCommand),
+
+    /// Run the loop in every project and report across them.
+    ///
+    /// Each project's output goes to its .afk/fleet-go.log.
+    Go(FleetGoCommand),
+}
+
+/// Arguments for 'fleet init' command.
+#[derive(Args, Debug)]
+pub struct FleetInitCommand {
+    /// How many directory levels down to look for projects.
+    #[arg(long, default_value_t = 3)]
+    pub depth: usize,
+
+    /// Rewrite an existing afk-fleet.json.
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+/// Arguments for 'fleet status' command.
+#[derive(Args, Debug)]
+pub struct FleetStatusCommand {
+    /// Print the status as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for 'fleet sync' command.
+#[derive(Args, Debug)]
+pub struct FleetSyncCommand {}
+
+/// Arguments for 'fleet go' command.
+#[derive(Args, Debug)]
+pub struct FleetGoCommand {
+    /// Iterations to run in each project (default: limits.max_iterations).
+    pub iterations: Option<u32>,
+
+    /// Projects to run at once (overrides max_parallel in afk-fleet.json).
+    #[arg(short = 'p', long, value_parser = clap::value_parser!(u32).range(1..=32))]
+    pub max_parallel: Option<u32>,
+}
+
+/// Arguments for 'git squash' command.
+#[derive(Args, Debug)]
+pub struct GitSquashCommand {
+    /// Task whose commits to squash.
+    #[arg(long, value_name = "ID", required_unless_present = "session")]
+    pub task: Option<String>,
+
+    /// Squash every commit recorded this session.
+    #[arg(long, conflicts_with = "task")]
+    pub session: bool,
+
+    /// Skip the confirmation prompt.
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+
+    /// Rewrite commits that are already on a remote branch.
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Arguments for 'export patches' command.
+#[derive(Args, Debug)]
+pub struct ExportPatchesCommand {
+    /// Directory to write the patches and index.json to.
+    #[arg(short, long, value_name = "DIR", default_value = "afk-patches")]
+    pub out: std::path::PathBuf,
+}
+
+/// Arguments for 'review' command.
+#[derive(Args, Debug)]
+pub struct ReviewCommand {
+    /// Review only this task.
+    pub task_id: Option<String>,
+}
+
+/// Arguments for 'sandbox diff' command.
+#[derive(Args, Debug)]
+pub struct SandboxDiffCommand {
+    /// Show a diffstat instead of the full diff.
+    #[arg(long)]
+    pub stat: bool,
+}
+
+/// Arguments for 'sandbox apply' command.
+#[derive(Args, Debug)]
+pub struct SandboxApplyCommand {
+    /// Write the sandbox's commits to this file for `git am` instead of
+    /// merging, keeping the sandbox.
+    #[arg(long, value_name = "FILE")]
+    pub patch: Option<std::path::PathBuf>,
+}
+
+/// Arguments for 'sandbox discard' command.
+#[derive(Args, Debug)]
+pub struct SandboxDiscardCommand {}
+
+/// Arguments for the watch command.
+#[derive(Args, Debug)]
+pub struct WatchCommand {
+    /// Run `afk go` for this many iterations when new pending tasks appear.
+    #[arg(long, value_name = "ITERATIONS", num_args = 0..=1, default_missing_value = "1")]
+    pub go: Option<u32>,
+
+    /// Milliseconds to wait after the last file change before syncing.
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub debounce: u64,
+}
+
+/// Subcommands for scheduled runs.
+#[derive(Subcommand, Debug)]
+pub enum SchedulerCommands {
+    /// Run in the foreground, starting `afk go` whenever the schedule is due.
+    ///
+    /// Each run is recorded in the event log. Stop with Ctrl+C.
+    Start(SchedulerStartCommand),
+
+    /// List the next times the schedule is due.
+    Next(SchedulerNextCommand),
+}
+
+/// Arguments for 'scheduler start' command.
+#[derive(Args, Debug)]
+pub struct SchedulerStartCommand {}
+
+/// Arguments for 'scheduler next' command.
+#[derive(Args, Debug)]
+pub struct SchedulerNextCommand {
+    /// How many run times to list.
+    #[arg(short = 'n', long, default_value_t = 5)]
+    pub count: usize,
+}
+
+/// Subcommands for preset browsing.
+#[derive(Subcommand, Debug)]
+pub enum PresetCommands {
+    /// List available presets.
+    List(PresetListCommand),
+
+    /// Show what a preset configures.
+    Show(PresetShowCommand),
+}
+
+/// Arguments for 'preset list' command.
+#[derive(Args, Debug)]
+pub struct PresetListCommand {}
+
+/// Arguments for 'preset show' command.
+#[derive(Args, Debug)]
+pub struct PresetShowCommand {
+    /// Preset name (e.g., rust-cli).
+    pub name: String,
+}
+
+/// Subcommands for source management.
+#[derive(Subcommand, Debug)]
+pub enum SourceCommands {
+    /// Add a task source.
+    Add(SourceAddCommand),
+
+    /// List configured task sources.
+    List(SourceListCommand),
+
+    /// Remove a task source by index (1-based).
+    Remove(SourceRemoveCommand),
+}
+
+/// Arguments for 'source add' command.
+#[derive(Args, Debug)]
+pub struct SourceAddCommand {
+    /// Type of source to add.
+    #[arg(value_parser = ["beads", "json", "markdown", "github", "stories-dir", "notion", "trello", "jira", "command", "csv", "tsv"])]
+    pub source_type: String,
+
+    /// Path to the source file (for json/markdown/csv types), directory (for
+    /// stories-dir), database ID (for notion), board ID (for trello),
+    /// project key (for jira) or executable (for command).
+    pub path: Option<String>,
+
+    /// Write completion back: comment on and update issues (beads), check
+    /// off completed items (markdown) or move completed stories into a
+    /// done/ subfolder (stories-dir).
+    #[arg(long)]
+    pub writeback: bool,
+}
+
+/// Arguments for 'source list' command.
+#[derive(Args, Debug)]
+pub struct SourceListCommand {}
+
+/// Arguments for 'source remove' command.
+#[derive(Args, Debug)]
+pub struct SourceRemoveCommand {
+    /// Index of source to remove (1-based).
+    pub index: usize,
+}
+
+/// Subcommands for task list management.
+#[derive(Subcommand, Debug)]
+pub enum TasksCommands {
+    /// Sync tasks from all configured sources.
+    ///
+    /// Aggregates tasks from beads, JSON, markdown, and GitHub into a unified
+    /// .afk/tasks.json file.
+    Sync(TasksSyncCommand),
+
+    /// Check tasks.json (and progress.json) against afk's JSON Schema.
+    ///
+    /// With a path, validates that file as a tasks file. Exits non-zero if
+    /// anything doesn't match.
+    ///
+    /// Examples:
+    ///   afk tasks validate                 # .afk/tasks.json and progress.json
+    ///   afk tasks validate backlog.json    # A generated backlog
+    Validate(TasksValidateCommand),
+
+    /// Export the task list as a CSV or TSV spreadsheet.
+    ///
+    /// Writes one row per task with its status, for sharing with people who
+    /// don't use afk. The columns match a csv source's defaults, so an
+    /// edited export can be added back with `afk source add csv`.
+    ///
+    /// Examples:
+    ///   afk tasks export > tasks.csv
+    ///   afk tasks export --format tsv -o backlog.tsv --pending
+    Export(TasksExportCommand),
+
+    /// Have the AI write descriptions and criteria for thin tasks.
+    ///
+    /// Sends pending tasks that have only a title (such as imported
+    /// checklist items) to the AI CLI, and writes back the descriptions and
+    /// acceptance criteria it suggests. Written fields are marked generated
+    /// until reviewed with `afk task edit <id> --reviewed`.
+    ///
+    /// Examples:
+    ///   afk tasks enrich --dry-run     # List the thin tasks
+    ///   afk tasks enrich               # Enrich them all
+    ///   afk tasks enrich auth-001      # Enrich one task
+    Enrich(TasksEnrichCommand),
+}
+
+/// Subcommands for config management.
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Show all configuration values.
+    ///
+    /// Displays all config sections and their current values.
+    Show(ConfigShowCommand),
+
+    /// Get a specific config value.
+    ///
+    /// Use dot notation for nested keys (e.g., limits.max_iterations).
+    Get(ConfigGetCommand),
+
+    /// Set a config value.
+    ///
+    /// Use dot notation for keys. Values are validated before saving.
+    Set(ConfigSetCommand),
+
+    /// Reset config to defaults.
+    ///
+    /// Can reset a specific key, a section, or all config.
+    Reset(ConfigResetCommand),
+
+    /// Open config file in your editor.
+    ///
+    /// Uses $EDITOR environment variable.
+    Edit(ConfigEditCommand),
+
+    /// Browse and edit config interactively.
+    ///
+    /// Navigate sections and fields with the keyboard, with inline docs,
+    /// defaults, and validation. Press 's' to save, 'q' to quit.
+    Tui(ConfigTuiCommand),
+
+    /// Show documentation for config keys.
+    ///
+    /// Displays description, type, default value, and examples.
+    Explain(ConfigExplainCommand),
+
+    /// List all valid config keys.
+    Keys(ConfigKeysCommand),
+
+    /// Convert the config file to another format.
+    ///
+    /// Rewrites .afk/config.json, .afk/config.yaml or .afk/config.toml in
+    /// the given format and removes the old file. YAML and TOML configs can
+    /// carry comments; converting drops any existing comments.
+    ///
+    /// Examples:
+    ///   afk config convert --to yaml
+    ///   afk config convert --to toml
+    Convert(ConfigConvertCommand),
+
+    /// Manage named profiles laid over the config.
+    ///
+    /// A profile in .afk/profiles/ overrides some keys of the config, e.g.
+    /// a cautious daytime run against an aggressive overnight one.
+    ///
+    /// Examples:
+    ///   afk config profile create overnight --set limits.max_iterations=200
+    ///   afk config profile list
+    ///   afk config profile use overnight
+    ///   afk go --profile quick
+    #[command(subcommand)]
+    Profile(ConfigProfileCommands),
+}
+
+/// Subcommands for config profiles.
+#[derive(Subcommand, Debug)]
+pub enum ConfigProfileCommands {
+    /// Create a profile in .afk/profiles/.
+    Create(ConfigProfileCreateCommand),
+
+    /// List profiles and the keys they override.
+    List(ConfigProfileListCommand),
+
+    /// Make a profile the default for this project.
+    ///
+    /// Saved in .afk/profile. AFK_PROFILE and `afk go --profile` take
+    /// precedence.
+    Use(ConfigProfileUseCommand),
+}
+
+/// Arguments for 'config profile create' command.
+#[derive(Args, Debug)]
+pub struct ConfigProfileCreateCommand {
+    /// Profile name (letters, digits, '-' and '_').
+    pub name: String,
+
+    /// Override a config key in the profile (repeatable), e.g.
+    /// --set limits.max_iterations=200.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub settings: Vec<String>,
+}
+
+/// Arguments for 'config profile list' command.
+#[derive(Args, Debug)]
+pub struct ConfigProfileListCommand {}
+
+/// Arguments for 'config profile use' command.
+#[derive(Args, Debug)]
+pub struct ConfigProfileUseCommand {
+    /// Profile to use.
+    #[arg(required_unless_present = "clear")]
+    pub name: Option<String>,
+
+    /// Go back to the base config.
+    #[arg(long, conflicts_with = "name")]
+    pub clear: bool,
+}
+
+/// Arguments for 'config show' command.
+#[derive(Args, Debug)]
+pub struct ConfigShowCommand {
+    /// Filter to a specific section (e.g., 'limits', 'ai_cli').
+    #[arg(short = 's', long)]
+    pub section: Option<String>,
+}
+
+/// Arguments for 'config get' command.
+#[derive(Args, Debug)]
+pub struct ConfigGetCommand {
+    /// Config key in dot notation (e.g., limits.max_iterations).
+    pub key: String,
+}
+
+/// Arguments for 'config set' command.
+#[derive(Args, Debug)]
+pub struct ConfigSetCommand {
+    /// Config key in dot notation (e.g., limits.max_iterations).
+    pub key: String,
+
+    /// Value to set.
+    pub value: String,
+}
+
+/// Arguments for 'config reset' command.
+#[derive(Args, Debug)]
+pub struct ConfigResetCommand {
+    /// Key or section to reset. If omitted, resets all config.
+    pub key: Option<String>,
+
+    /// Skip confirmation prompt.
+    #[arg(short = 'y', long)]
+    pub yes: bool,
+}
+
+/// Arguments for 'config edit' command.
+#[derive(Args, Debug)]
+pub struct ConfigEditCommand {}
+
+/// Arguments for 'config tui' command.
+#[derive(Args, Debug)]
+pub struct ConfigTuiCommand {}
+
+/// Arguments for 'config explain' command.
+#[derive(Args, Debug)]
+pub struct ConfigExplainCommand {
+    /// Config key to explain. If omitted, lists all keys with brief descriptions.
+    pub key: Option<String>,
+}
+
+/// Arguments for 'config keys' command.
+#[derive(Args, Debug)]
+pub struct ConfigKeysCommand {}
+
+/// Arguments for 'config convert' command.
+#[derive(Args, Debug)]
+pub struct ConfigConvertCommand {
+    /// Format to convert to.
+    #[arg(long, value_parser = ["json", "yaml", "toml"])]
+    pub to: String,
+}
+
+/// Arguments for 'import' command.
+#[derive(Args, Debug)]
+pub struct ImportCommand {
+    /// Input file to import.
+    pub input_file: String,
+
+    /// Input format: a requirements document for the AI CLI to convert
+    /// (prd), or a Trello board exported as JSON (trello), converted
+    /// directly.
+    #[arg(long, value_enum, default_value = "prd")]
+    pub format: commands::import::ImportFormat,
+
+    /// Trello: what to do with a list's cards, as LIST=STATUS where the
+    /// status is pending, done or skip (repeatable). Lists named "Done"
+    /// and the like are done by default, and others pending.
+    #[arg(long, value_name = "LIST=STATUS", value_parser = crate::sources::trello::parse_list_status)]
+    pub list_status: Vec<(String, crate::sources::trello::ListStatus)>,
+
+    /// Output JSON path.
+    #[arg(short = 'o', long, default_value = ".afk/tasks.json")]
+    pub output: String,
+
+    /// Copy to clipboard.
+    #[arg(short = 'c', long)]
+    pub copy: bool,
+
+    /// Write prompt to file.
+    #[arg(short = 'f', long)]
+    pub file: bool,
 
     /// Print prompt to stdout.
     #[arg(short = 's', long)]
     pub stdout: bool,
+
+    /// Import documents longer than this many characters in parts, split
+    /// at headings (0 to never split).
+    #[arg(long, default_value = "40000", value_name = "CHARS")]
+    pub chunk_size: usize,
 }
 
 /// Arguments for 'tasks sync' command.
@@ -490,6 +1395,16 @@ pub struct TasksSyncCommand {
     /// Clear completed tasks and progress before syncing (pending tasks preserved).
     #[arg(short = 'r', long)]
     pub reset: bool,
+
+    /// Sync only this source, by index (see `afk source list`) or name.
+    ///
+    /// A name is a source type (e.g. github) or a source's path or repo.
+    #[arg(short = 's', long, value_name = "SOURCE")]
+    pub source: Option<String>,
+
+    /// Show what would be added, updated, or removed without writing.
+    #[arg(short = 'n', long, conflicts_with = "reset")]
+    pub dry_run: bool,
 }
 
 /// Arguments for the 'prompt' command.
@@ -514,6 +1429,15 @@ pub struct PromptCommand {
     /// Override max iterations.
     #[arg(short = 'l', long)]
     pub limit: Option<u32>,
+
+    /// Render a named template from .afk/templates/ ("default" for built-in).
+    #[arg(short = 't', long)]
+    pub template: Option<String>,
+
+    /// Show the prompt's estimated tokens and what was trimmed to fit
+    /// prompt.max_tokens.
+    #[arg(short = 'e', long)]
+    pub explain: bool,
 }
 
 /// Arguments for the 'verify' command.
@@ -522,23 +1446,50 @@ pub struct VerifyCommand {
     /// Show full output from failed gates.
     #[arg(short = 'v', long)]
     pub verbose: bool,
+
+    /// Also check the task's `ci:<workflow>` acceptance criteria.
+    #[arg(long)]
+    pub task: Option<String>,
+
+    /// Output format: text, or a json / junit report for CI.
+    #[arg(long, value_parser = ["text", "json", "junit"], default_value = "text")]
+    pub format: String,
+
+    /// Print the JSON report (same as --format json).
+    #[arg(long, conflicts_with = "format")]
+    pub json: bool,
+
+    /// Run only the tests covering files changed in this iteration (or
+    /// uncommitted changes outside the loop).
+    #[arg(long)]
+    pub changed_only: bool,
+
+    /// Run every gate, ignoring cached passes for the current working tree.
+    #[arg(long)]
+    pub no_cache: bool,
 }
 
 /// Arguments for the 'done' command.
 #[derive(Args, Debug)]
 pub struct DoneCommand {
     /// Task ID to mark as complete.
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
     pub task_id: String,
 
-    /// Completion message.
+    /// Completion message (evidence note when used with --criterion).
     #[arg(short = 'm', long)]
     pub message: Option<String>,
+
+    /// Check off a single acceptance criterion (1-based) instead of the task.
+    #[arg(long)]
+    pub criterion: Option<usize>,
 }
 
 /// Arguments for the 'fail' command.
 #[derive(Args, Debug)]
 pub struct FailCommand {
     /// Task ID to mark as failed.
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
     pub task_id: String,
 
     /// Failure reason.
@@ -550,14 +1501,62 @@ pub struct FailCommand {
 #[derive(Args, Debug)]
 pub struct ResetCommand {
     /// Task ID to reset.
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
     pub task_id: String,
 }
 
+/// Arguments for the 'pause' command.
+#[derive(Args, Debug)]
+pub struct PauseCommand {}
+
+/// Arguments for the 'resume' command.
+#[derive(Args, Debug)]
+pub struct ResumeCommand {}
+
+/// Arguments for the 'serve' command.
+#[derive(Args, Debug)]
+pub struct ServeCommand {
+    /// Port to listen on (overrides server.port).
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// Address to listen on (overrides server.host).
+    #[arg(long)]
+    pub host: Option<String>,
+}
+
+/// Arguments for the 'note' command.
+#[derive(Args, Debug)]
+pub struct NoteCommand {
+    /// Note text. Omit to list the session's tags and notes.
+    pub text: Option<String>,
+}
+
 /// Subcommands for archive management.
 #[derive(Subcommand, Debug)]
 pub enum ArchiveCommands {
     /// List archived sessions.
-    List,
+    List {
+        /// Only show sessions with this tag.
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Print the sessions as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Compare two sessions.
+    ///
+    /// Shows tasks newly completed, regressions, iteration and failure
+    /// deltas, and learning changes. Use "current" for the live session.
+    Diff {
+        /// Baseline archive name (from `afk archive list`) or "current".
+        a: String,
+
+        /// Archive name to compare against (defaults to "current").
+        #[arg(default_value = "current")]
+        b: String,
+    },
 }
 
 /// Arguments for the 'update' command.
@@ -576,8 +1575,64 @@ pub struct UpdateCommand {
 #[derive(Args, Debug)]
 pub struct CompletionsCommand {
     /// Shell to generate completions for.
-    #[arg(value_parser = ["bash", "zsh", "fish"])]
+    #[arg(value_parser = ["bash", "zsh", "fish", "powershell", "elvish"])]
     pub shell: String,
+
+    /// Print a script that asks afk for completions at runtime, including
+    /// task IDs from .afk/tasks.json.
+    #[arg(long)]
+    pub dynamic: bool,
+}
+
+/// Arguments for the 'explain' command.
+#[derive(Args, Debug)]
+pub struct ExplainCommand {
+    /// Task ID to explain.
+    #[arg(long)]
+    pub task: String,
+}
+
+/// Arguments for the 'schema' command.
+#[derive(Args, Debug)]
+pub struct SchemaCommand {
+    /// File to print the schema for.
+    #[arg(value_enum)]
+    pub kind: crate::schema::SchemaKind,
+}
+
+/// Arguments for the 'tasks validate' command.
+#[derive(Args, Debug)]
+pub struct TasksValidateCommand {
+    /// Tasks file to validate (default: .afk/tasks.json and .afk/progress.json).
+    pub path: Option<std::path::PathBuf>,
+}
+
+/// Arguments for the 'tasks enrich' command.
+#[derive(Args, Debug)]
+pub struct TasksEnrichCommand {
+    /// Tasks to enrich (default: every thin pending task).
+    #[arg(add = ArgValueCandidates::new(commands::completions::task_ids))]
+    pub task_ids: Vec<String>,
+
+    /// List the tasks that would be enriched without calling the AI.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Arguments for the 'tasks export' command.
+#[derive(Args, Debug)]
+pub struct TasksExportCommand {
+    /// Spreadsheet format.
+    #[arg(long, value_enum, default_value = "csv")]
+    pub format: commands::export::TaskExportFormat,
+
+    /// File to write (default: stdout).
+    #[arg(short = 'o', long)]
+    pub output: Option<std::path::PathBuf>,
+
+    /// Export only tasks that haven't passed.
+    #[arg(short = 'p', long)]
+    pub pending: bool,
 }
 
 /// Arguments for the 'use' command.
@@ -604,6 +1659,13 @@ impl GoCommand {
         use commands::go::GoOptions;
 
         let (iterations, source_path) = self.parse_args();
+        if let Some(profile) = &self.profile {
+            crate::config::set_profile(Some(profile));
+            // Fail now rather than fall back to defaults later, and replace
+            // the settings main installed from the config without the profile
+            let config = crate::config::AfkConfig::load(None).map_err(crate::AfkError::from)?;
+            apply_config_settings(&config);
+        }
 
         let options = GoOptions {
             iterations,
@@ -612,15 +1674,23 @@ impl GoCommand {
             fresh: self.fresh,
             until_complete: self.until_complete,
             timeout: self.timeout,
+            time_budget: self.time_budget,
+            at: self.at,
             feedback: self.feedback.clone(),
             no_mascot: self.no_mascot,
+            no_refresh: self.no_refresh,
             dry_run: self.dry_run,
+            tags: self.tags.clone(),
+            workers: self.workers,
+            sandbox: self.sandbox,
         };
 
         match commands::go::go(options) {
             Ok(outcome) => match outcome.stop_reason {
                 StopReason::Complete => Ok(ExitCode::SUCCESS),
                 StopReason::MaxIterations => Ok(ExitCode::SUCCESS),
+                StopReason::TimeBudget => Ok(ExitCode::SUCCESS),
+                StopReason::ExternalStop => Ok(ExitCode::SUCCESS),
                 StopReason::UserInterrupt => Ok(ExitCode::INTERRUPT),
                 _ => Ok(ExitCode::FAILURE),
             },
@@ -653,6 +1723,7 @@ impl InitCommand {
             dry_run: self.dry_run,
             force: self.force,
             yes: self.yes,
+            preset: self.preset.clone(),
         };
 
         match commands::init::init(options) {
@@ -668,7 +1739,26 @@ impl InitCommand {
 impl StatusCommand {
     /// Execute the status command.
     pub fn execute(&self) -> CliResult {
-        commands::status::status(self.verbose)
+        commands::status::status(self.verbose, self.json)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ReportBurndownCommand {
+    /// Execute the report burndown command.
+    pub fn execute(&self) -> CliResult {
+        commands::report::burndown(self.width, self.height.max(1), self.json)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl StatsCommand {
+    /// Execute the stats command.
+    pub fn execute(&self) -> CliResult {
+        let format = if self.json { "json" } else { &self.format };
+        commands::stats::stats(self.since.as_deref(), format)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
@@ -677,16 +1767,65 @@ impl StatusCommand {
 impl TaskCommand {
     /// Execute the task command.
     pub fn execute(&self) -> CliResult {
-        commands::task::task(&self.task_id)
+        let result = match (&self.command, &self.task_id) {
+            (Some(TaskCommands::Add(c)), _) => commands::task::task_add(c.to_new_task()),
+            (Some(TaskCommands::New(c)), _) => match &c.template {
+                Some(template) if !c.list => {
+                    commands::task::task_new(template, &c.settings, c.id.clone())
+                }
+                _ => commands::task::task_templates(),
+            },
+            (Some(TaskCommands::Edit(c)), _) => {
+                commands::task::task_edit(&c.task_id, c.to_task_edit())
+            }
+            (Some(TaskCommands::Rm(c)), _) => commands::task::task_rm(&c.task_id),
+            (None, Some(task_id)) => commands::task::task(task_id),
+            (None, None) => Err(commands::task::TaskCommandError::NoTaskId),
+        };
+        result
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
+impl TaskAddCommand {
+    /// The task to add, as given on the command line.
+    pub fn to_new_task(&self) -> commands::task::NewTask {
+        commands::task::NewTask {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            description: self.description.clone(),
+            priority: self.priority,
+            acceptance_criteria: self.acceptance_criteria.clone(),
+            depends_on: self.depends_on.clone(),
+            model: self.model.clone(),
+            cli: self.cli.clone(),
+        }
+    }
+}
+
+impl TaskEditCommand {
+    /// The changes to make, as given on the command line.
+    pub fn to_task_edit(&self) -> commands::task::TaskEdit {
+        commands::task::TaskEdit {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            priority: self.priority,
+            acceptance_criteria: (!self.acceptance_criteria.is_empty())
+                .then(|| self.acceptance_criteria.clone()),
+            add_criteria: self.add_criteria.clone(),
+            depends_on: (!self.depends_on.is_empty()).then(|| self.depends_on.clone()),
+            model: self.model.clone(),
+            cli: self.cli.clone(),
+            reviewed: self.reviewed,
+        }
+    }
+}
+
 impl SourceAddCommand {
     /// Execute the source add command.
     pub fn execute(&self) -> CliResult {
-        commands::source::source_add(&self.source_type, self.path.as_deref())
+        commands::source::source_add(&self.source_type, self.path.as_deref(), self.writeback)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
@@ -713,12 +1852,22 @@ impl SourceRemoveCommand {
 impl ImportCommand {
     /// Execute the import command.
     pub fn execute(&self) -> CliResult {
+        if self.format == commands::import::ImportFormat::Trello {
+            return commands::import::import_trello(
+                &self.input_file,
+                &self.output,
+                &self.list_status,
+            )
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()));
+        }
         commands::import::import(
             &self.input_file,
             &self.output,
             self.copy,
             self.file,
             self.stdout,
+            self.chunk_size,
         )
         .map(|()| ExitCode::SUCCESS)
         .map_err(|e| CliError::Command(e.to_string()))
@@ -757,15 +1906,27 @@ impl TasksSyncCommand {
             }
         }
 
-        commands::import::tasks_sync()
+        commands::import::tasks_sync(self.source.as_deref(), self.dry_run)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-/// Execute the tasks command (list tasks).
-pub fn execute_tasks(pending: bool, complete: bool, limit: usize) -> CliResult {
-    commands::import::tasks_show(pending, complete, limit)
+/// Execute the tasks command (list tasks), as JSON with `json`.
+pub fn execute_tasks(pending: bool, complete: bool, limit: usize, json: bool) -> CliResult {
+    let result = if json {
+        commands::import::tasks_json(pending, complete, limit)
+    } else {
+        commands::import::tasks_show(pending, complete, limit)
+    };
+    result
+        .map(|()| ExitCode::SUCCESS)
+        .map_err(|e| CliError::Command(e.to_string()))
+}
+
+/// Execute the tasks command with --scores (rank by completion chance).
+pub fn execute_task_scores(limit: usize) -> CliResult {
+    commands::import::tasks_scores(limit)
         .map(|()| ExitCode::SUCCESS)
         .map_err(|e| CliError::Command(e.to_string()))
 }
@@ -781,128 +1942,390 @@ impl PromptCommand {
             stdout: self.stdout,
             bootstrap: self.bootstrap,
             limit: self.limit,
+            template: self.template.clone(),
+            explain: self.explain,
+        };
+
+        commands::prompt::prompt(options)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl VerifyCommand {
+    /// Execute the verify command.
+    pub fn execute(&self) -> CliResult {
+        match commands::verify::verify(
+            self.verbose,
+            self.task.as_deref(),
+            if self.json {
+                commands::verify::VerifyFormat::Json
+            } else {
+                commands::verify::VerifyFormat::from_name(&self.format)
+            },
+            !self.no_cache,
+            self.changed_only,
+        ) {
+            Ok(outcome) => {
+                if outcome.all_passed {
+                    Ok(ExitCode::SUCCESS)
+                } else {
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+            Err(e) => Err(CliError::Command(e.to_string())),
+        }
+    }
+}
+
+impl DoneCommand {
+    /// Execute the done command.
+    pub fn execute(&self) -> CliResult {
+        let result = match self.criterion {
+            Some(number) => commands::progress_cmd::check_criterion(
+                &self.task_id,
+                number,
+                self.message.as_deref(),
+            ),
+            None => commands::progress_cmd::done(&self.task_id, self.message.as_deref()),
+        };
+        result
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl FailCommand {
+    /// Execute the fail command.
+    pub fn execute(&self) -> CliResult {
+        commands::progress_cmd::fail(&self.task_id, self.message.as_deref())
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl PauseCommand {
+    /// Execute the pause command.
+    pub fn execute(&self) -> CliResult {
+        commands::control::pause()
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ResumeCommand {
+    /// Execute the resume command.
+    pub fn execute(&self) -> CliResult {
+        commands::control::resume()
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ServeCommand {
+    /// Execute the serve command.
+    pub fn execute(&self) -> CliResult {
+        commands::serve::serve(self.host.clone(), self.port)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl NoteCommand {
+    /// Execute the note command.
+    pub fn execute(&self) -> CliResult {
+        let result = match self.text.as_deref() {
+            Some(text) => commands::progress_cmd::note(text),
+            None => commands::progress_cmd::show_notes(),
+        };
+        result
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ResetCommand {
+    /// Execute the reset command.
+    pub fn execute(&self) -> CliResult {
+        commands::progress_cmd::reset(&self.task_id)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+/// Execute the archive command (archive and clear session).
+pub fn execute_archive_now(reason: &str, yes: bool) -> CliResult {
+    commands::archive::archive_now(reason, yes)
+        .map(|()| ExitCode::SUCCESS)
+        .map_err(|e| CliError::Command(e.to_string()))
+}
+
+/// Execute the archive list command.
+pub fn execute_archive_list(tag: Option<&str>, json: bool) -> CliResult {
+    commands::archive::archive_list(tag, json)
+        .map(|()| ExitCode::SUCCESS)
+        .map_err(|e| CliError::Command(e.to_string()))
+}
+
+/// Execute the archive diff command.
+pub fn execute_archive_diff(a: &str, b: &str) -> CliResult {
+    commands::archive::archive_diff(a, b)
+        .map(|()| ExitCode::SUCCESS)
+        .map_err(|e| CliError::Command(e.to_string()))
+}
+
+impl ConfigShowCommand {
+    /// Execute the config show command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_show(self.section.as_deref())
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigGetCommand {
+    /// Execute the config get command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_get(&self.key)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigSetCommand {
+    /// Execute the config set command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_set(&self.key, &self.value)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigResetCommand {
+    /// Execute the config reset command.
+    pub fn execute(&self) -> CliResult {
+        use std::io::{self, Write};
+
+        // Confirm unless --yes (for resetting all)
+        if self.key.is_none() && !self.yes {
+            print!("Reset all config to defaults? [Y/n]: ");
+            let _ = io::stdout().flush();
+
+            let mut input = String::new();
+            if io::stdin().read_line(&mut input).is_ok() {
+                let input = input.trim().to_lowercase();
+                if input == "n" || input == "no" {
+                    println!("Cancelled.");
+                    return Ok(ExitCode::SUCCESS);
+                }
+            }
+        }
+
+        commands::config::config_reset(self.key.as_deref())
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigEditCommand {
+    /// Execute the config edit command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_edit()
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ReproCommand {
+    /// Execute the repro command.
+    pub fn execute(&self) -> CliResult {
+        commands::repro::repro(self.iteration, self.json)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ReplayCommand {
+    /// Execute the replay command.
+    pub fn execute(&self) -> CliResult {
+        commands::replay::replay(commands::replay::ReplayOptions {
+            iteration: self.iteration,
+            raw: self.raw,
+            json: self.json,
+        })
+        .map(|()| ExitCode::SUCCESS)
+        .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl FleetInitCommand {
+    /// Execute the fleet init command.
+    pub fn execute(&self) -> CliResult {
+        commands::fleet::init(self.depth, self.force)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl FleetStatusCommand {
+    /// Execute the fleet status command.
+    pub fn execute(&self) -> CliResult {
+        commands::fleet::status(self.json)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl FleetSyncCommand {
+    /// Execute the fleet sync command.
+    pub fn execute(&self) -> CliResult {
+        commands::fleet::sync()
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl FleetGoCommand {
+    /// Execute the fleet go command.
+    pub fn execute(&self) -> CliResult {
+        commands::fleet::go(self.iterations, self.max_parallel.map(|p| p as usize))
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl GitSquashCommand {
+    /// Execute the git squash command.
+    pub fn execute(&self) -> CliResult {
+        let scope = match &self.task {
+            Some(id) => commands::git_cmd::SquashScope::Task(id.clone()),
+            None => commands::git_cmd::SquashScope::Session,
         };
+        commands::git_cmd::squash(&scope, self.yes, self.force)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ExportPatchesCommand {
+    /// Execute the export patches command.
+    pub fn execute(&self) -> CliResult {
+        commands::export::patches(&self.out)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
 
-        commands::prompt::prompt(options)
+impl ReviewCommand {
+    /// Execute the review command.
+    pub fn execute(&self) -> CliResult {
+        commands::review::review(self.task_id.as_deref())
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl VerifyCommand {
-    /// Execute the verify command.
+impl SandboxDiffCommand {
+    /// Execute the sandbox diff command.
     pub fn execute(&self) -> CliResult {
-        match commands::verify::verify(self.verbose) {
-            Ok(outcome) => {
-                if outcome.all_passed {
-                    Ok(ExitCode::SUCCESS)
-                } else {
-                    Ok(ExitCode::FAILURE)
-                }
-            }
-            Err(e) => Err(CliError::Command(e.to_string())),
-        }
+        commands::sandbox::diff(self.stat)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl DoneCommand {
-    /// Execute the done command.
+impl SandboxApplyCommand {
+    /// Execute the sandbox apply command.
     pub fn execute(&self) -> CliResult {
-        commands::progress_cmd::done(&self.task_id, self.message.as_deref())
+        commands::sandbox::apply(self.patch.clone())
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl FailCommand {
-    /// Execute the fail command.
+impl SandboxDiscardCommand {
+    /// Execute the sandbox discard command.
     pub fn execute(&self) -> CliResult {
-        commands::progress_cmd::fail(&self.task_id, self.message.as_deref())
+        commands::sandbox::discard()
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl ResetCommand {
-    /// Execute the reset command.
+impl LogCommand {
+    /// Execute the log command.
     pub fn execute(&self) -> CliResult {
-        commands::progress_cmd::reset(&self.task_id)
+        commands::log::log(self.lines, self.follow, self.json)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-/// Execute the archive command (archive and clear session).
-pub fn execute_archive_now(reason: &str, yes: bool) -> CliResult {
-    commands::archive::archive_now(reason, yes)
+impl LogsCommand {
+    /// Execute the logs command.
+    pub fn execute(&self) -> CliResult {
+        commands::logs::logs(commands::logs::LogsOptions {
+            iteration: self.iteration,
+            follow: self.follow,
+            events: self.events,
+        })
         .map(|()| ExitCode::SUCCESS)
         .map_err(|e| CliError::Command(e.to_string()))
+    }
 }
 
-/// Execute the archive list command.
-pub fn execute_archive_list() -> CliResult {
-    commands::archive::archive_list()
+impl WatchCommand {
+    /// Execute the watch command.
+    pub fn execute(&self) -> CliResult {
+        commands::watch::watch(commands::watch::WatchOptions {
+            debounce: std::time::Duration::from_millis(self.debounce),
+            go_iterations: self.go,
+        })
         .map(|()| ExitCode::SUCCESS)
         .map_err(|e| CliError::Command(e.to_string()))
+    }
 }
 
-impl ConfigShowCommand {
-    /// Execute the config show command.
+impl SchedulerStartCommand {
+    /// Execute the scheduler start command.
     pub fn execute(&self) -> CliResult {
-        commands::config::config_show(self.section.as_deref())
+        commands::scheduler::start()
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl ConfigGetCommand {
-    /// Execute the config get command.
+impl SchedulerNextCommand {
+    /// Execute the scheduler next command.
     pub fn execute(&self) -> CliResult {
-        commands::config::config_get(&self.key)
+        commands::scheduler::next(self.count)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl ConfigSetCommand {
-    /// Execute the config set command.
+impl PresetListCommand {
+    /// Execute the preset list command.
     pub fn execute(&self) -> CliResult {
-        commands::config::config_set(&self.key, &self.value)
+        commands::preset::preset_list()
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl ConfigResetCommand {
-    /// Execute the config reset command.
+impl PresetShowCommand {
+    /// Execute the preset show command.
     pub fn execute(&self) -> CliResult {
-        use std::io::{self, Write};
-
-        // Confirm unless --yes (for resetting all)
-        if self.key.is_none() && !self.yes {
-            print!("Reset all config to defaults? [Y/n]: ");
-            let _ = io::stdout().flush();
-
-            let mut input = String::new();
-            if io::stdin().read_line(&mut input).is_ok() {
-                let input = input.trim().to_lowercase();
-                if input == "n" || input == "no" {
-                    println!("Cancelled.");
-                    return Ok(ExitCode::SUCCESS);
-                }
-            }
-        }
-
-        commands::config::config_reset(self.key.as_deref())
+        commands::preset::preset_show(&self.name)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
 }
 
-impl ConfigEditCommand {
-    /// Execute the config edit command.
+impl ConfigTuiCommand {
+    /// Execute the config tui command.
     pub fn execute(&self) -> CliResult {
-        commands::config::config_edit()
+        commands::config::config_tui()
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
@@ -926,6 +2349,42 @@ impl ConfigKeysCommand {
     }
 }
 
+impl ConfigConvertCommand {
+    /// Execute the config convert command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_convert(&self.to)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigProfileCreateCommand {
+    /// Execute the config profile create command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_profile_create(&self.name, &self.settings)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigProfileListCommand {
+    /// Execute the config profile list command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_profile_list()
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ConfigProfileUseCommand {
+    /// Execute the config profile use command.
+    pub fn execute(&self) -> CliResult {
+        commands::config::config_profile_use(self.name.as_deref())
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
 impl UpdateCommand {
     /// Execute the update command.
     pub fn execute(&self) -> CliResult {
@@ -938,7 +2397,54 @@ impl UpdateCommand {
 impl CompletionsCommand {
     /// Execute the completions command.
     pub fn execute(&self) -> CliResult {
-        commands::completions::completions(&self.shell)
+        commands::completions::completions(&self.shell, self.dynamic)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl ExplainCommand {
+    /// Execute the explain command.
+    pub fn execute(&self) -> CliResult {
+        commands::explain::explain(&self.task)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl SchemaCommand {
+    /// Execute the schema command.
+    pub fn execute(&self) -> CliResult {
+        commands::schema::schema(self.kind)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl TasksValidateCommand {
+    /// Execute the tasks validate command.
+    pub fn execute(&self) -> CliResult {
+        match commands::schema::validate_tasks(self.path.as_deref()) {
+            Ok(true) => Ok(ExitCode::SUCCESS),
+            Ok(false) => Ok(ExitCode::FAILURE),
+            Err(e) => Err(CliError::Command(e.to_string())),
+        }
+    }
+}
+
+impl TasksExportCommand {
+    /// Execute the tasks export command.
+    pub fn execute(&self) -> CliResult {
+        commands::export::tasks(self.format, self.output.as_deref(), self.pending)
+            .map(|()| ExitCode::SUCCESS)
+            .map_err(|e| CliError::Command(e.to_string()))
+    }
+}
+
+impl TasksEnrichCommand {
+    /// Execute the tasks enrich command.
+    pub fn execute(&self) -> CliResult {
+        commands::enrich::enrich(&self.task_ids, self.dry_run)
             .map(|()| ExitCode::SUCCESS)
             .map_err(|e| CliError::Command(e.to_string()))
     }
@@ -1050,6 +2556,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_go_command_time_budget() {
+        let cli = Cli::try_parse_from(["afk", "go", "--for", "1h30m"]).unwrap();
+        match cli.command {
+            Some(Commands::Go(cmd)) => {
+                assert_eq!(cmd.time_budget, Some(std::time::Duration::from_secs(5400)));
+            }
+            _ => panic!("Expected Go command"),
+        }
+
+        assert!(Cli::try_parse_from(["afk", "go", "--for", "soon"]).is_err());
+    }
+
     #[test]
     fn test_init_command() {
         let cli = Cli::try_parse_from(["afk", "init", "-n", "-f", "-y"]).unwrap();
@@ -1063,6 +2582,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_init_command_preset() {
+        let cli = Cli::try_parse_from(["afk", "init", "--preset", "rust-cli"]).unwrap();
+        match cli.command {
+            Some(Commands::Init(cmd)) => assert_eq!(cmd.preset.as_deref(), Some("rust-cli")),
+            _ => panic!("Expected Init command"),
+        }
+    }
+
     #[test]
     fn test_status_command() {
         let cli = Cli::try_parse_from(["afk", "status"]).unwrap();
@@ -1095,11 +2623,15 @@ mod tests {
                 pending,
                 complete,
                 limit,
+                scores,
+                json,
             }) => {
                 assert!(command.is_none()); // No subcommand = list tasks
                 assert!(!pending);
                 assert!(!complete);
                 assert_eq!(limit, 50); // Default limit
+                assert!(!scores);
+                assert!(!json);
             }
             _ => panic!("Expected Tasks command"),
         }
@@ -1114,6 +2646,7 @@ mod tests {
                 pending,
                 complete,
                 limit,
+                ..
             }) => {
                 assert!(command.is_none());
                 assert!(pending);
@@ -1129,12 +2662,81 @@ mod tests {
         let cli = Cli::try_parse_from(["afk", "task", "auth-001"]).unwrap();
         match cli.command {
             Some(Commands::Task(cmd)) => {
-                assert_eq!(cmd.task_id, "auth-001");
+                assert_eq!(cmd.task_id.as_deref(), Some("auth-001"));
             }
             _ => panic!("Expected Task command"),
         }
     }
 
+    #[test]
+    fn test_task_subcommands() {
+        let cli = Cli::try_parse_from([
+            "afk",
+            "task",
+            "add",
+            "--title",
+            "Add logout",
+            "-p",
+            "2",
+            "--ac",
+            "One",
+            "--ac",
+            "Two",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Task(TaskCommand {
+                command: Some(TaskCommands::Add(add)),
+                task_id: None,
+            })) => {
+                assert_eq!(add.title.as_deref(), Some("Add logout"));
+                assert_eq!(add.priority, Some(2));
+                assert_eq!(add.acceptance_criteria, ["One", "Two"]);
+            }
+            _ => panic!("Expected task add command"),
+        }
+
+        let cli = Cli::try_parse_from(["afk", "task", "edit", "a-1", "--add-ac", "Three"]).unwrap();
+        match cli.command {
+            Some(Commands::Task(TaskCommand {
+                command: Some(TaskCommands::Edit(edit)),
+                ..
+            })) => {
+                let edit = edit.to_task_edit();
+                assert_eq!(edit.acceptance_criteria, None);
+                assert_eq!(edit.add_criteria, ["Three"]);
+            }
+            _ => panic!("Expected task edit command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "afk",
+            "task",
+            "new",
+            "-t",
+            "bugfix",
+            "--set",
+            "component=checkout",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Task(TaskCommand {
+                command: Some(TaskCommands::New(new)),
+                ..
+            })) => {
+                assert_eq!(new.template.as_deref(), Some("bugfix"));
+                assert_eq!(new.settings, ["component=checkout"]);
+                assert!(!new.list);
+            }
+            _ => panic!("Expected task new command"),
+        }
+        assert!(Cli::try_parse_from(["afk", "task", "new", "--list"]).is_ok());
+        assert!(Cli::try_parse_from(["afk", "task", "new"]).is_err());
+
+        assert!(Cli::try_parse_from(["afk", "task", "remove", "a-1"]).is_ok());
+        assert!(Cli::try_parse_from(["afk", "task"]).is_err());
+    }
+
     #[test]
     fn test_prompt_command() {
         let cli = Cli::try_parse_from(["afk", "prompt", "-c", "-b", "-l", "20"]).unwrap();
@@ -1143,9 +2745,16 @@ mod tests {
                 assert!(cmd.copy);
                 assert!(cmd.bootstrap);
                 assert_eq!(cmd.limit, Some(20));
+                assert!(cmd.template.is_none());
             }
             _ => panic!("Expected Prompt command"),
         }
+
+        let cli = Cli::try_parse_from(["afk", "prompt", "--template", "review"]).unwrap();
+        match cli.command {
+            Some(Commands::Prompt(cmd)) => assert_eq!(cmd.template.as_deref(), Some("review")),
+            _ => panic!("Expected Prompt command"),
+        }
     }
 
     #[test]
@@ -1154,6 +2763,15 @@ mod tests {
         match cli.command {
             Some(Commands::Verify(cmd)) => {
                 assert!(cmd.verbose);
+                assert!(cmd.task.is_none());
+            }
+            _ => panic!("Expected Verify command"),
+        }
+
+        let cli = Cli::try_parse_from(["afk", "verify", "--task", "task-1"]).unwrap();
+        match cli.command {
+            Some(Commands::Verify(cmd)) => {
+                assert_eq!(cmd.task.as_deref(), Some("task-1"));
             }
             _ => panic!("Expected Verify command"),
         }
@@ -1166,9 +2784,16 @@ mod tests {
             Some(Commands::Done(cmd)) => {
                 assert_eq!(cmd.task_id, "task-123");
                 assert_eq!(cmd.message, Some("All tests pass".to_string()));
+                assert!(cmd.criterion.is_none());
             }
             _ => panic!("Expected Done command"),
         }
+
+        let cli = Cli::try_parse_from(["afk", "done", "task-123", "--criterion", "2"]).unwrap();
+        match cli.command {
+            Some(Commands::Done(cmd)) => assert_eq!(cmd.criterion, Some(2)),
+            _ => panic!("Expected Done command"),
+        }
     }
 
     #[test]
@@ -1234,9 +2859,37 @@ mod tests {
             Some(Commands::Import(cmd)) => {
                 assert_eq!(cmd.input_file, "requirements.md");
                 assert!(cmd.copy);
+                assert_eq!(cmd.format, commands::import::ImportFormat::Prd);
+            }
+            _ => panic!("Expected Import command"),
+        }
+
+        let cli = Cli::try_parse_from([
+            "afk",
+            "import",
+            "--format",
+            "trello",
+            "board.json",
+            "--list-status",
+            "Icebox=skip",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Import(cmd)) => {
+                assert_eq!(cmd.format, commands::import::ImportFormat::Trello);
+                assert_eq!(
+                    cmd.list_status,
+                    [(
+                        "Icebox".to_string(),
+                        crate::sources::trello::ListStatus::Skip
+                    )]
+                );
             }
             _ => panic!("Expected Import command"),
         }
+        assert!(
+            Cli::try_parse_from(["afk", "import", "b.json", "--list-status", "Icebox"]).is_err()
+        );
     }
 
     #[test]
@@ -1302,12 +2955,166 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Commands::Archive {
-                command: Some(ArchiveCommands::List),
+                command: Some(ArchiveCommands::List {
+                    tag: None,
+                    json: false
+                }),
+                ..
+            })
+        ));
+
+        let cli = Cli::try_parse_from(["afk", "archive", "list", "--tag", "night2"]).unwrap();
+        match cli.command {
+            Some(Commands::Archive {
+                command: Some(ArchiveCommands::List { tag, .. }),
+                ..
+            }) => assert_eq!(tag.as_deref(), Some("night2")),
+            _ => panic!("Expected Archive list command"),
+        }
+    }
+
+    #[test]
+    fn test_json_flags() {
+        let cli = Cli::try_parse_from(["afk", "status", "--json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Status(StatusCommand { json: true, .. }))
+        ));
+        assert!(Cli::try_parse_from(["afk", "status", "--json", "-v"]).is_err());
+
+        let cli = Cli::try_parse_from(["afk", "tasks", "-p", "--json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Tasks { json: true, .. })
+        ));
+        assert!(Cli::try_parse_from(["afk", "tasks", "--scores", "--json"]).is_err());
+
+        let cli = Cli::try_parse_from(["afk", "verify", "--json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Verify(VerifyCommand { json: true, .. }))
+        ));
+        assert!(Cli::try_parse_from(["afk", "verify", "--json", "--format", "junit"]).is_err());
+
+        let cli = Cli::try_parse_from(["afk", "archive", "list", "--json"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Archive {
+                command: Some(ArchiveCommands::List { json: true, .. }),
                 ..
             })
         ));
     }
 
+    #[test]
+    fn test_note_and_go_tag_commands() {
+        let cli = Cli::try_parse_from(["afk", "note", "Webhooks need retries"]).unwrap();
+        match cli.command {
+            Some(Commands::Note(c)) => assert_eq!(c.text.as_deref(), Some("Webhooks need retries")),
+            _ => panic!("Expected Note command"),
+        }
+
+        let cli =
+            Cli::try_parse_from(["afk", "go", "--tag", "payments", "--tag", "night2"]).unwrap();
+        match cli.command {
+            Some(Commands::Go(c)) => assert_eq!(c.tags, vec!["payments", "night2"]),
+            _ => panic!("Expected Go command"),
+        }
+    }
+
+    #[test]
+    fn test_read_only_flag_is_global() {
+        let cli = Cli::try_parse_from(["afk", "status", "--read-only"]).unwrap();
+        assert!(cli.read_only);
+
+        let cli = Cli::try_parse_from(["afk", "--read-only", "tasks"]).unwrap();
+        assert!(cli.read_only);
+    }
+
+    #[test]
+    fn test_commands_writes_state() {
+        let writes = |args: &[&str]| {
+            Cli::try_parse_from(args)
+                .unwrap()
+                .command
+                .unwrap()
+                .writes_state()
+        };
+
+        assert!(!writes(&["afk", "status"]));
+        assert!(!writes(&["afk", "tasks"]));
+        assert!(!writes(&["afk", "task", "task-1"]));
+        assert!(writes(&["afk", "task", "rm", "task-1"]));
+        assert!(!writes(&["afk", "archive", "list"]));
+        assert!(!writes(&["afk", "config", "show"]));
+        assert!(!writes(&["afk", "source", "list"]));
+        assert!(!writes(&["afk", "serve"]));
+        assert!(!writes(&["afk", "fleet", "status"]));
+        assert!(!writes(&["afk", "scheduler", "next"]));
+        assert!(writes(&["afk", "scheduler", "start"]));
+        assert!(writes(&["afk", "fleet", "go", "--max-parallel", "2"]));
+        assert!(writes(&["afk", "git", "squash", "--session"]));
+        assert!(!writes(&["afk", "export", "patches"]));
+        assert!(writes(&["afk", "review"]));
+
+        assert!(writes(&["afk", "go"]));
+        assert!(writes(&["afk", "done", "task-1"]));
+        assert!(writes(&["afk", "tasks", "sync"]));
+        assert!(writes(&["afk", "tasks", "sync", "--source", "2"]));
+        assert!(!writes(&["afk", "tasks", "sync", "--dry-run"]));
+        assert!(!writes(&["afk", "tasks", "export", "--format", "tsv"]));
+        assert!(writes(&["afk", "tasks", "enrich", "auth-001"]));
+        assert!(!writes(&["afk", "tasks", "enrich", "--dry-run"]));
+        assert!(!writes(&["afk", "sync", "-n"]));
+        assert!(writes(&["afk", "archive", "-y"]));
+        assert!(writes(&[
+            "afk",
+            "config",
+            "set",
+            "limits.max_iterations",
+            "5"
+        ]));
+    }
+
+    #[test]
+    fn test_git_squash_command() {
+        let cli = Cli::try_parse_from(["afk", "git", "squash", "--task", "auth", "-y"]).unwrap();
+        match cli.command {
+            Some(Commands::Git(GitCommands::Squash(cmd))) => {
+                assert_eq!(cmd.task.as_deref(), Some("auth"));
+                assert!(!cmd.session);
+                assert!(cmd.yes);
+            }
+            _ => panic!("Expected git squash command"),
+        }
+        assert!(Cli::try_parse_from(["afk", "git", "squash"]).is_err());
+        assert!(Cli::try_parse_from(["afk", "git", "squash", "--task", "a", "--session"]).is_err());
+    }
+
+    #[test]
+    fn test_review_command() {
+        let cli = Cli::try_parse_from(["afk", "review", "auth"]).unwrap();
+        match cli.command {
+            Some(Commands::Review(cmd)) => assert_eq!(cmd.task_id.as_deref(), Some("auth")),
+            _ => panic!("Expected review command"),
+        }
+    }
+
+    #[test]
+    fn test_archive_diff_command() {
+        let cli = Cli::try_parse_from(["afk", "archive", "diff", "20240101_120000"]).unwrap();
+        match cli.command {
+            Some(Commands::Archive {
+                command: Some(ArchiveCommands::Diff { a, b }),
+                ..
+            }) => {
+                assert_eq!(a, "20240101_120000");
+                assert_eq!(b, "current");
+            }
+            _ => panic!("Expected Archive diff command"),
+        }
+    }
+
     #[test]
     fn test_update_command() {
         let cli = Cli::try_parse_from(["afk", "update", "--beta", "--check"]).unwrap();
@@ -1326,6 +3133,16 @@ mod tests {
         match cli.command {
             Some(Commands::Completions(cmd)) => {
                 assert_eq!(cmd.shell, "zsh");
+                assert!(!cmd.dynamic);
+            }
+            _ => panic!("Expected Completions command"),
+        }
+
+        let cli = Cli::try_parse_from(["afk", "completions", "powershell", "--dynamic"]).unwrap();
+        match cli.command {
+            Some(Commands::Completions(cmd)) => {
+                assert_eq!(cmd.shell, "powershell");
+                assert!(cmd.dynamic);
             }
             _ => panic!("Expected Completions command"),
         }
@@ -1351,7 +3168,7 @@ mod tests {
 
     #[test]
     fn test_invalid_shell_rejected() {
-        let result = Cli::try_parse_from(["afk", "completions", "powershell"]);
+        let result = Cli::try_parse_from(["afk", "completions", "tcsh"]);
         assert!(result.is_err());
     }
 
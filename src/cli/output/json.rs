@@ -0,0 +1,466 @@
+//! Machine-readable output for `--json`.
+//!
+//! `afk status`, `afk tasks`, `afk archive list` and `afk report burndown`
+//! print these structures instead of ANSI text when given `--json`, so
+//! scripts and CI can read afk state without scraping. Every document carries `schema_version`; fields
+//! are only ever added within a version, never renamed or removed.
+//! `afk verify --json` prints the verify report, which follows the same rule,
+//! as does `afk stats --format json`.
+
+use serde::Serialize;
+
+use crate::prd::{PrdDocument, UserStory};
+use crate::progress::stats::Stats;
+use crate::progress::{ArchiveMetadata, BurndownPoint, SessionNote, SessionProgress, TaskStatus};
+use crate::runner::ControlState;
+
+/// Version of the `--json` schemas.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Print a document as pretty JSON on stdout.
+pub fn print<T: Serialize>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).unwrap_or_default()
+    );
+}
+
+/// `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct StatusJson {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    pub schema_version: u32,
+    /// Whether `.afk/` exists. The other fields are absent when it doesn't.
+    pub initialised: bool,
+    /// Project details, when initialised.
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub details: Option<StatusDetails>,
+}
+
+impl StatusJson {
+    /// Status of a project without `.afk/`.
+    pub fn not_initialised() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            initialised: false,
+            details: None,
+        }
+    }
+
+    /// Status of an initialised project.
+    pub fn new(details: StatusDetails) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            initialised: true,
+            details: Some(details),
+        }
+    }
+}
+
+/// Status of an initialised project.
+#[derive(Debug, Serialize)]
+pub struct StatusDetails {
+    /// Backlog summary.
+    pub tasks: TaskSummaryJson,
+    /// Current session.
+    pub session: SessionJson,
+    /// Burn-down history of the backlog.
+    pub burndown: BurndownJson,
+    /// Configured sources, described as in the text output.
+    pub sources: Vec<String>,
+    /// The AI CLI the loop runs.
+    pub ai_cli: AiCliJson,
+}
+
+/// Backlog summary in `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct TaskSummaryJson {
+    /// Stories in the backlog.
+    pub total: usize,
+    /// Stories that pass.
+    pub complete: usize,
+    /// Stories that don't pass yet.
+    pub pending: usize,
+    /// Acceptance criteria met.
+    pub criteria_met: usize,
+    /// Acceptance criteria in total.
+    pub criteria_total: usize,
+    /// IDs of tasks in progress.
+    pub current: Vec<String>,
+    /// ID of the next pending task, if any.
+    pub next: Option<String>,
+    /// Progress through each epic, in the order epics first appear.
+    pub epics: Vec<EpicJson>,
+}
+
+/// An epic in `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct EpicJson {
+    /// Epic name.
+    pub name: String,
+    /// Stories in the epic that pass.
+    pub complete: usize,
+    /// Stories in the epic.
+    pub total: usize,
+}
+
+/// Session section of `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct SessionJson {
+    /// ISO timestamp the session started.
+    pub started_at: String,
+    /// Iterations run this session.
+    pub iterations: u32,
+    /// "running", "paused" or "stop_requested" (from `.afk/control/`).
+    pub control: &'static str,
+    /// Tasks by session status.
+    pub counts: StatusCountsJson,
+    /// Tasks that have taken far longer than estimated.
+    pub overruns: Vec<OverrunJson>,
+    /// Unfinished tasks running on an escalation model.
+    pub escalated: Vec<EscalationJson>,
+}
+
+/// Task counts by session status.
+#[derive(Debug, Default, Serialize)]
+pub struct StatusCountsJson {
+    /// Tasks waiting to be started.
+    pub pending: usize,
+    /// Tasks being worked on.
+    pub in_progress: usize,
+    /// Completed tasks.
+    pub completed: usize,
+    /// Failed tasks.
+    pub failed: usize,
+    /// Skipped tasks.
+    pub skipped: usize,
+}
+
+/// A task over its estimate.
+#[derive(Debug, Serialize)]
+pub struct OverrunJson {
+    /// Task ID.
+    pub task_id: String,
+    /// Iterations spent on it.
+    pub actual: u32,
+    /// Iterations estimated.
+    pub estimated: u32,
+}
+
+/// A task moved onto a stronger model (`ai_cli.escalation_models`).
+#[derive(Debug, Serialize)]
+pub struct EscalationJson {
+    /// Task ID.
+    pub task_id: String,
+    /// Model it now runs on.
+    pub model: String,
+    /// Its failures so far.
+    pub failures: u32,
+    /// Model each iteration on it used, oldest first.
+    pub models: Vec<String>,
+}
+
+/// Burn-down section of `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct BurndownJson {
+    /// Remaining tasks after each recorded iteration, oldest first.
+    pub remaining: Vec<usize>,
+    /// Sessions the history covers.
+    pub sessions: usize,
+    /// "converging", "stalled" or "growing"; null with fewer than two points.
+    pub trend: Option<&'static str>,
+}
+
+/// `afk report burndown --json`.
+#[derive(Debug, Serialize)]
+pub struct BurndownReportJson {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    pub schema_version: u32,
+    /// What the amounts count: "tasks", "points" or "minutes".
+    pub unit: &'static str,
+    /// Amount done in the backlog now.
+    pub completed: usize,
+    /// Amount left in the backlog now.
+    pub remaining: usize,
+    /// Remaining amount after each recorded iteration, oldest first.
+    pub history: Vec<usize>,
+    /// "converging", "stalled" or "growing"; null with fewer than two points.
+    pub trend: Option<&'static str>,
+    /// Amount finished per hour in the latest session, if measurable.
+    pub per_hour: Option<f64>,
+    /// When the backlog should be done at that pace (local ISO time).
+    pub projected_finish: Option<String>,
+    /// Estimates of completed tasks against the time they took.
+    pub actuals: EstimateActualsJson,
+    /// The recorded burn-down points, oldest first.
+    pub points: Vec<BurndownPoint>,
+}
+
+/// `afk stats --format json`.
+#[derive(Debug, Serialize)]
+pub struct StatsJson {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    pub schema_version: u32,
+    /// Earliest date included ("YYYY-MM-DD"), if filtered with `--since`.
+    pub since: Option<String>,
+    /// The metrics.
+    #[serde(flatten)]
+    pub stats: Stats,
+}
+
+/// Estimate-vs-actual totals in `afk report burndown --json`.
+#[derive(Debug, Serialize)]
+pub struct EstimateActualsJson {
+    /// Completed tasks with an estimate and recorded time.
+    pub tasks: usize,
+    /// Their summed estimates.
+    pub estimate: u32,
+    /// Minutes the loop spent on them.
+    pub minutes: f64,
+}
+
+/// The AI CLI in `afk status --json`.
+#[derive(Debug, Serialize)]
+pub struct AiCliJson {
+    /// Command name.
+    pub command: String,
+    /// Arguments passed before the prompt.
+    pub args: Vec<String>,
+}
+
+/// Name of a control state in JSON.
+pub fn control_name(state: ControlState) -> &'static str {
+    match state {
+        ControlState::Running => "running",
+        ControlState::Paused => "paused",
+        ControlState::StopRequested => "stop_requested",
+    }
+}
+
+/// `afk tasks --json`.
+#[derive(Debug, Serialize)]
+pub struct TasksJson {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    pub schema_version: u32,
+    /// Stories in the backlog, before filtering.
+    pub total: usize,
+    /// Stories that pass.
+    pub complete: usize,
+    /// Stories that don't pass yet.
+    pub pending: usize,
+    /// The listed tasks, after `--pending`/`--complete`/`--limit`.
+    pub tasks: Vec<TaskJson>,
+}
+
+impl TasksJson {
+    /// List the given stories of a backlog.
+    pub fn new(prd: &PrdDocument, stories: &[&UserStory], progress: &SessionProgress) -> Self {
+        let (complete, total) = prd.get_story_counts();
+        Self {
+            schema_version: SCHEMA_VERSION,
+            total,
+            complete,
+            pending: total - complete,
+            tasks: stories
+                .iter()
+                .map(|story| TaskJson::new(story, progress))
+                .collect(),
+        }
+    }
+}
+
+/// One task in `afk tasks --json`.
+#[derive(Debug, Serialize)]
+pub struct TaskJson {
+    /// Task ID.
+    pub id: String,
+    /// Title.
+    pub title: String,
+    /// Priority (1 is highest).
+    pub priority: i32,
+    /// Whether the story passes.
+    pub passes: bool,
+    /// Session status, falling back to `passes` for untracked tasks.
+    pub status: TaskStatus,
+    /// Source the task came from.
+    pub source: String,
+    /// Epic the task belongs to, if any.
+    pub epic: Option<String>,
+    /// Estimated iterations, if set.
+    pub effort: Option<u32>,
+    /// Estimated size in `reporting.estimate_unit`, if set.
+    pub estimate: Option<u32>,
+    /// Wall-clock seconds the loop has spent on the task.
+    pub seconds_spent: u64,
+    /// IDs of stories this one depends on.
+    pub depends_on: Vec<String>,
+    /// Model the task runs with, if it overrides `ai_cli.models`.
+    pub model: Option<String>,
+    /// AI CLI the task runs with, if it overrides `ai_cli.command`.
+    pub cli: Option<String>,
+    /// Acceptance criteria.
+    pub acceptance_criteria: Vec<CriterionJson>,
+}
+
+impl TaskJson {
+    /// Describe a story, with its status in the session.
+    pub fn new(story: &UserStory, progress: &SessionProgress) -> Self {
+        let status = match progress.get_task(&story.id) {
+            Some(task) => task.status,
+            None if story.passes => TaskStatus::Completed,
+            None => TaskStatus::Pending,
+        };
+        Self {
+            id: story.id.clone(),
+            title: story.title.clone(),
+            priority: story.priority,
+            passes: story.passes,
+            status,
+            source: story.source.clone(),
+            epic: story.epic.clone(),
+            effort: story.effort,
+            estimate: story.estimate,
+            seconds_spent: progress
+                .get_task(&story.id)
+                .map_or(0, |task| task.seconds_spent),
+            depends_on: story.depends_on.clone(),
+            model: story.model.clone(),
+            cli: story.cli.clone(),
+            acceptance_criteria: story
+                .acceptance_criteria
+                .iter()
+                .map(|c| CriterionJson {
+                    text: c.text.clone(),
+                    passed: c.passed,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An acceptance criterion in `afk tasks --json`.
+#[derive(Debug, Serialize)]
+pub struct CriterionJson {
+    /// Criterion text.
+    pub text: String,
+    /// Whether it has been met.
+    pub passed: bool,
+}
+
+/// `afk archive list --json`.
+#[derive(Debug, Serialize)]
+pub struct ArchiveListJson {
+    /// Schema version ([`SCHEMA_VERSION`]).
+    pub schema_version: u32,
+    /// Archived sessions, newest first.
+    pub archives: Vec<ArchiveJson>,
+}
+
+impl ArchiveListJson {
+    /// List archives as returned by `list_archives`.
+    pub fn new(archives: &[(String, ArchiveMetadata)]) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            archives: archives
+                .iter()
+                .map(|(name, metadata)| ArchiveJson {
+                    name: name.clone(),
+                    archived_at: metadata.archived_at.clone(),
+                    branch: metadata.branch.clone(),
+                    reason: metadata.reason.clone(),
+                    iterations: metadata.iterations,
+                    tasks_completed: metadata.tasks_completed,
+                    tasks_pending: metadata.tasks_pending,
+                    tags: metadata.tags.clone(),
+                    notes: metadata.notes.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One archived session in `afk archive list --json`.
+#[derive(Debug, Serialize)]
+pub struct ArchiveJson {
+    /// Archive directory name (accepted by `afk archive diff`).
+    pub name: String,
+    /// ISO timestamp it was archived.
+    pub archived_at: String,
+    /// Branch at the time, if known.
+    pub branch: Option<String>,
+    /// Why it was archived.
+    pub reason: String,
+    /// Iterations the session ran.
+    pub iterations: u32,
+    /// Tasks completed.
+    pub tasks_completed: usize,
+    /// Tasks still pending.
+    pub tasks_pending: usize,
+    /// Session tags.
+    pub tags: Vec<String>,
+    /// Session notes.
+    pub notes: Vec<SessionNote>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::AcceptanceCriterion;
+
+    #[test]
+    fn test_status_json_not_initialised() {
+        let json = serde_json::to_value(StatusJson::not_initialised()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"schema_version": SCHEMA_VERSION, "initialised": false})
+        );
+        assert_eq!(control_name(ControlState::StopRequested), "stop_requested");
+    }
+
+    #[test]
+    fn test_tasks_json() {
+        let mut done = UserStory::new("done", "Done");
+        done.passes = true;
+        let mut todo = UserStory::new("todo", "To do");
+        todo.acceptance_criteria = vec![AcceptanceCriterion::new("Works")];
+        todo.depends_on = vec!["done".to_string()];
+        let prd = PrdDocument {
+            user_stories: vec![done, todo],
+            ..Default::default()
+        };
+        let mut progress = SessionProgress::default();
+        progress.set_task_status("todo", TaskStatus::Failed, "json", None);
+
+        let stories: Vec<&UserStory> = prd.user_stories.iter().collect();
+        let json = serde_json::to_value(TasksJson::new(&prd, &stories, &progress)).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["total"], 2);
+        assert_eq!(json["pending"], 1);
+        assert_eq!(json["tasks"][0]["status"], "completed");
+        assert_eq!(json["tasks"][1]["status"], "failed");
+        assert_eq!(json["tasks"][1]["depends_on"][0], "done");
+        assert_eq!(json["tasks"][1]["acceptance_criteria"][0]["text"], "Works");
+        assert_eq!(json["tasks"][1]["effort"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_archive_list_json() {
+        let metadata = ArchiveMetadata {
+            archived_at: "2026-01-12T14:05:31.123".to_string(),
+            branch: None,
+            reason: "manual".to_string(),
+            iterations: 7,
+            tasks_completed: 3,
+            tasks_pending: 1,
+            tags: Vec::new(),
+            notes: Vec::new(),
+        };
+        let list = ArchiveListJson::new(&[("20260112_140531".to_string(), metadata)]);
+        let json = serde_json::to_value(list).unwrap();
+        assert_eq!(json["archives"][0]["name"], "20260112_140531");
+        assert_eq!(json["archives"][0]["iterations"], 7);
+        // Empty collections are still present, unlike in metadata.json
+        assert_eq!(json["archives"][0]["tags"], serde_json::json!([]));
+        assert_eq!(json["archives"][0]["branch"], serde_json::Value::Null);
+    }
+}
@@ -1,6 +1,9 @@
 //! Output handlers for afk prompts.
 //!
 //! This module provides functionality to output prompts to clipboard, file, or stdout.
+//! The [`json`] submodule holds the machine-readable schemas behind `--json`.
+
+pub mod json;
 
 use std::fs;
 use std::path::Path;
@@ -0,0 +1,207 @@
+//! `${VAR}` expansion in config values.
+//!
+//! String values in the config may refer to environment variables as
+//! `${NAME}`, or `${NAME:-fallback}` for one that may be unset, so a config
+//! can be committed without machine-specific paths or secrets. `$${` is a
+//! literal `${`. A bare `$NAME` is left alone. Sections holding shell
+//! commands ([`SHELL_SECTIONS`]) are left for the shell, which expands
+//! them when they run, with the `AFK_*` variables hooks rely on set.
+//!
+//! Expansion happens in [`AfkConfig::load`](super::AfkConfig::load), never
+//! in [`AfkConfig::load_base`](super::AfkConfig::load_base), so saving the
+//! config never writes expanded values back.
+
+use serde_json::Value;
+
+use super::ConfigError;
+
+/// Top-level sections that are not expanded.
+pub const SHELL_SECTIONS: &[&str] = &["hooks", "feedback_loops"];
+
+/// What went wrong expanding one string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpandError {
+    /// `${NAME}` names a variable that isn't set.
+    Undefined(String),
+    /// A `${` with no closing `}`, or with an empty or invalid name.
+    Malformed(String),
+}
+
+/// Expand `${NAME}` and `${NAME:-fallback}` in `text`, looking variables up
+/// with `lookup`.
+pub fn expand(text: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String, ExpandError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            out.push_str("${");
+            rest = escaped;
+            continue;
+        }
+        let Some(body) = after.strip_prefix('{') else {
+            out.push('$');
+            rest = after;
+            continue;
+        };
+        let end = body
+            .find('}')
+            .ok_or_else(|| ExpandError::Malformed(format!("${{{body}")))?;
+        let (name, fallback) = match body[..end].split_once(":-") {
+            Some((name, fallback)) => (name, Some(fallback)),
+            None => (&body[..end], None),
+        };
+        if !is_valid_name(name) {
+            return Err(ExpandError::Malformed(format!("${{{}}}", &body[..end])));
+        }
+        match lookup(name).filter(|value| !(value.is_empty() && fallback.is_some())) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(fallback.ok_or_else(|| ExpandError::Undefined(name.into()))?),
+        }
+        rest = &body[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Expand every string in a serialized config, except in
+/// [`SHELL_SECTIONS`].
+pub fn expand_config(
+    config: &mut Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigError> {
+    let Value::Object(sections) = config else {
+        return Ok(());
+    };
+    for (section, value) in sections.iter_mut() {
+        if !SHELL_SECTIONS.contains(&section.as_str()) {
+            expand_at(section, value, lookup)?;
+        }
+    }
+    Ok(())
+}
+
+fn expand_at(
+    key: &str,
+    value: &mut Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigError> {
+    match value {
+        Value::String(text) if text.contains('$') => {
+            *text = expand(text, lookup).map_err(|e| match e {
+                ExpandError::Undefined(name) => ConfigError::UndefinedVariable {
+                    name,
+                    key: key.to_string(),
+                },
+                ExpandError::Malformed(text) => ConfigError::InvalidExpansion {
+                    text,
+                    key: key.to_string(),
+                },
+            })?;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand_at(&format!("{key}[{i}]"), item, lookup)?;
+            }
+        }
+        Value::Object(map) => {
+            for (name, item) in map.iter_mut() {
+                expand_at(&format!("{key}.{name}"), item, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether `name` is a valid environment variable name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/dev".to_string()),
+            "AFK_MODEL" => Some("opus".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand("${HOME}/notes.md", &env).unwrap(),
+            "/home/dev/notes.md"
+        );
+        assert_eq!(
+            expand("--model=${AFK_MODEL} ${HOME}", &env).unwrap(),
+            "--model=opus /home/dev"
+        );
+        assert_eq!(expand("${MODEL:-sonnet}", &env).unwrap(), "sonnet");
+        assert_eq!(expand("${EMPTY:-x}", &env).unwrap(), "x");
+        assert_eq!(expand("${EMPTY}", &env).unwrap(), "");
+        assert_eq!(expand("${MISSING:-}", &env).unwrap(), "");
+        assert_eq!(
+            expand("$${HOME} $HOME $5", &env).unwrap(),
+            "${HOME} $HOME $5"
+        );
+        assert_eq!(expand("no vars", &env).unwrap(), "no vars");
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        assert_eq!(
+            expand("key=${API_KEY}", &env),
+            Err(ExpandError::Undefined("API_KEY".to_string()))
+        );
+        assert_eq!(
+            expand("${HOME", &env),
+            Err(ExpandError::Malformed("${HOME".to_string()))
+        );
+        assert_eq!(
+            expand("${}", &env),
+            Err(ExpandError::Malformed("${}".to_string()))
+        );
+        assert!(matches!(
+            expand("${1BAD}", &env),
+            Err(ExpandError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_expand_config() {
+        let mut config = json!({
+            "ai_cli": {"command": "claude", "args": ["--model", "${AFK_MODEL}"]},
+            "sources": [{"type": "markdown", "path": "${HOME}/TODO.md"}],
+            "hooks": {"on_task_complete": "notify ${AFK_TASK_TITLE}"},
+            "limits": {"max_iterations": 5}
+        });
+        expand_config(&mut config, &env).unwrap();
+        assert_eq!(config["ai_cli"]["args"], json!(["--model", "opus"]));
+        assert_eq!(config["sources"][0]["path"], "/home/dev/TODO.md");
+        assert_eq!(
+            config["hooks"]["on_task_complete"],
+            "notify ${AFK_TASK_TITLE}"
+        );
+
+        let mut config = json!({"sources": [{"type": "json", "path": "${TASKS_DIR}/a.json"}]});
+        let err = expand_config(&mut config, &env).unwrap_err();
+        assert!(matches!(
+            &err,
+            ConfigError::UndefinedVariable { name, key }
+                if name == "TASKS_DIR" && key == "sources[0].path"
+        ));
+        assert!(err.to_string().contains("${TASKS_DIR}"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Config file formats.
+//!
+//! The config can live in `.afk/config.json`, `.afk/config.yaml` (or
+//! `.yml`) or `.afk/config.toml`. The format follows the file extension, so
+//! `afk config get/set` and everything else that loads and saves the config
+//! work the same whichever file a project uses. `afk config convert` moves a
+//! project from one format to another.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::{AfkConfig, ConfigError, AFK_DIR};
+
+/// Config file names afk looks for in `.afk`, in order of preference.
+pub const CONFIG_FILE_NAMES: &[&str] = &["config.json", "config.yaml", "config.yml", "config.toml"];
+
+/// A config file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON, the default.
+    Json,
+    /// YAML.
+    Yaml,
+    /// TOML.
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Every format, in order of preference.
+    pub const ALL: [ConfigFormat; 3] = [Self::Json, Self::Yaml, Self::Toml];
+
+    /// The format of a config file, from its extension. Unknown extensions
+    /// are read as JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::Yaml,
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+
+    /// Parse a format name: json, yaml, yml or toml.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    /// The format's name, which is also its file extension.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+        }
+    }
+
+    /// Path of a config file in this format inside `afk_dir`.
+    pub fn file_in(self, afk_dir: &Path) -> PathBuf {
+        afk_dir.join(format!("config.{}", self.name()))
+    }
+
+    /// Parse a config written in this format.
+    pub fn deserialize(self, contents: &str) -> Result<AfkConfig, ConfigError> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(contents)?,
+            Self::Yaml => serde_yaml::from_str(contents)?,
+            Self::Toml => toml::from_str(contents)?,
+        })
+    }
+
+    /// Parse a file in this format as a generic value, e.g. a partial
+    /// config such as a profile.
+    pub fn deserialize_value(self, contents: &str) -> Result<serde_json::Value, ConfigError> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(contents)?,
+            Self::Yaml => serde_yaml::from_str(contents)?,
+            Self::Toml => toml::from_str(contents)?,
+        })
+    }
+
+    /// Write a config in this format.
+    pub fn serialize(self, config: &AfkConfig) -> Result<String, ConfigError> {
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(config)?,
+            Self::Yaml => serde_yaml::to_string(config)?,
+            Self::Toml => toml::to_string_pretty(config)?,
+        })
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The config file in `afk_dir`, if there is one.
+///
+/// When more than one exists, JSON wins, then YAML, then TOML.
+pub fn find_config_file(afk_dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| afk_dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// The project's config file: the one that exists in `.afk`, or
+/// `.afk/config.json` for a new project.
+pub fn config_path() -> PathBuf {
+    find_config_file(Path::new(AFK_DIR)).unwrap_or_else(|| PathBuf::from(super::CONFIG_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SourceConfig, SourceType};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn sample() -> AfkConfig {
+        let mut config = AfkConfig::default();
+        config.sources.push(SourceConfig::beads());
+        config.feedback_loops.lint = Some("cargo clippy".to_string());
+        config.limits.max_iterations = 42;
+        config
+            .feedback_loops
+            .custom
+            .insert("docs".to_string(), "mdbook build".to_string());
+        config
+    }
+
+    #[test]
+    fn test_from_path_and_parse() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new(".afk/config.yml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new(".afk/config.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new(".afk/config")),
+            ConfigFormat::Json
+        );
+        assert_eq!(ConfigFormat::parse("YML"), Some(ConfigFormat::Yaml));
+        assert_eq!(ConfigFormat::parse("ini"), None);
+        assert_eq!(
+            ConfigFormat::Toml.file_in(Path::new(".afk")),
+            PathBuf::from(".afk/config.toml")
+        );
+    }
+
+    #[test]
+    fn test_round_trip_every_format() {
+        let config = sample();
+        for format in ConfigFormat::ALL {
+            let text = format.serialize(&config).unwrap();
+            let parsed = format.deserialize(&text).unwrap();
+            assert_eq!(
+                serde_json::to_value(&parsed).unwrap(),
+                serde_json::to_value(&config).unwrap(),
+                "{format} round trip"
+            );
+            assert_eq!(parsed.sources[0].source_type, SourceType::Beads);
+        }
+    }
+
+    #[test]
+    fn test_partial_yaml_and_toml_use_defaults() {
+        let yaml = "# Comments are allowed\nlimits:\n  max_iterations: 7\n";
+        let config = ConfigFormat::Yaml.deserialize(yaml).unwrap();
+        assert_eq!(config.limits.max_iterations, 7);
+        assert_eq!(config.ai_cli.command, AfkConfig::default().ai_cli.command);
+
+        let toml = "# Comments are allowed\n[limits]\nmax_iterations = 9\n";
+        let config = ConfigFormat::Toml.deserialize(toml).unwrap();
+        assert_eq!(config.limits.max_iterations, 9);
+
+        assert!(ConfigFormat::Toml.deserialize("[limits\n").is_err());
+    }
+
+    #[test]
+    fn test_find_config_file() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(find_config_file(temp.path()), None);
+        fs::write(temp.path().join("config.toml"), "").unwrap();
+        assert_eq!(
+            find_config_file(temp.path()),
+            Some(temp.path().join("config.toml"))
+        );
+        fs::write(temp.path().join("config.json"), "{}").unwrap();
+        assert_eq!(
+            find_config_file(temp.path()),
+            Some(temp.path().join("config.json"))
+        );
+    }
+}
@@ -45,6 +45,32 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "120",
         examples: &["30", "60", "240"],
     },
+    KeyMetadata {
+        key: "limits.max_task_minutes",
+        description: "Minutes of iteration time a task may use before the loop skips it \
+                      and moves on. 0 means no limit.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["0", "30", "90"],
+    },
+    KeyMetadata {
+        key: "limits.max_session_minutes",
+        description: "Minutes of iteration time a session may use, summed across runs, \
+                      before the loop stops. 0 means no limit.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["0", "240", "600"],
+    },
+    KeyMetadata {
+        key: "limits.rate_limit_backoff_secs",
+        description: "Seconds to wait after the AI CLI reports a rate limit (a 429 or \
+                      'rate limit' message) before the next iteration. The wait doubles with \
+                      each rate limit in a row, up to `retry.max_backoff_secs`, and \
+                      rate-limited iterations don't count as failures of the task.",
+        value_type: "non-negative integer",
+        default: "30",
+        examples: &["30", "60", "300"],
+    },
     // output section
     KeyMetadata {
         key: "output.default",
@@ -91,14 +117,56 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "false",
         examples: &["true", "false"],
     },
+    KeyMetadata {
+        key: "ai_cli.escalation_models",
+        description: "Stronger models to move a repeatedly failing task onto, weakest first. \
+                      Each step replaces ai_cli.models for that task's iterations.",
+        value_type: "comma-separated strings",
+        default: "(none)",
+        examples: &["sonnet, opus", "gpt-5"],
+    },
+    KeyMetadata {
+        key: "ai_cli.escalate_after",
+        description: "Failures of a task before it moves one step up \
+                      ai_cli.escalation_models. 0 disables escalation.",
+        value_type: "integer",
+        default: "2",
+        examples: &["2", "3", "0"],
+    },
+    KeyMetadata {
+        key: "ai_cli.backend",
+        description: "How the AI runs. 'cli' spawns ai_cli.command each iteration; 'http' \
+                      streams from an OpenAI-compatible chat completions endpoint at \
+                      ai_cli.base_url, using the model from ai_cli.models; 'ollama' \
+                      streams from the local Ollama server in the ollama section.",
+        value_type: "cli | http | ollama",
+        default: "cli",
+        examples: &["cli", "http", "ollama"],
+    },
+    KeyMetadata {
+        key: "ai_cli.base_url",
+        description: "Base URL of the HTTP backend's OpenAI-compatible API. \
+                      /chat/completions is appended.",
+        value_type: "string (URL)",
+        default: "https://api.openai.com/v1",
+        examples: &["http://localhost:11434/v1", "https://openrouter.ai/api/v1"],
+    },
+    KeyMetadata {
+        key: "ai_cli.api_key_env",
+        description: "Environment variable holding the HTTP backend's API key, sent as a \
+                      bearer token. Nothing is sent if it is unset.",
+        value_type: "string",
+        default: "OPENAI_API_KEY",
+        examples: &["OPENAI_API_KEY", "OPENROUTER_API_KEY"],
+    },
     // prompt section
     KeyMetadata {
         key: "prompt.template",
         description: "Template name for prompt generation. Use 'default' for the built-in \
-                      template or specify a custom template name.",
+                      template, or a name to load .afk/templates/<name>.md.",
         value_type: "string",
         default: "default",
-        examples: &["default", "minimal", "verbose"],
+        examples: &["default", "brief", "review"],
     },
     KeyMetadata {
         key: "prompt.custom_path",
@@ -133,6 +201,25 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "false (auto-detected during init)",
         examples: &["true", "false"],
     },
+    KeyMetadata {
+        key: "prompt.continuation_mode",
+        description: "Prompt style when the same task spans several iterations. 'full' renders \
+                      the whole template each time; 'condensed' sends only the task summary, \
+                      changes since the last attempt, and outstanding criteria.",
+        value_type: "full | condensed",
+        default: "full",
+        examples: &["full", "condensed"],
+    },
+    KeyMetadata {
+        key: "prompt.max_tokens",
+        description: "Estimated tokens (about four characters each) a prompt may use. Longer \
+                      prompts drop recent commits, then task learnings, then context files \
+                      until they fit; 'afk prompt --explain' shows what was cut. 0 disables \
+                      the limit.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["0", "32000", "100000"],
+    },
     // git section
     KeyMetadata {
         key: "git.auto_commit",
@@ -150,6 +237,100 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "afk: {task_id} - {message}",
         examples: &["[{task_id}] {message}", "feat({task_id}): {message}"],
     },
+    KeyMetadata {
+        key: "git.author_name",
+        description: "Author and committer name for commits made during the loop, by the \
+                      AI CLI or afk. Falls back to the repository's user.name when unset.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["afk-bot"],
+    },
+    KeyMetadata {
+        key: "git.author_email",
+        description: "Author and committer email for commits made during the loop. Falls \
+                      back to the repository's user.email when unset.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["afk@company.dev"],
+    },
+    KeyMetadata {
+        key: "git.update_submodules",
+        description: "Run 'git submodule update --init --recursive' before quality gates, \
+                      so gates see the submodule commits the repository points at.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "git.auto_branch",
+        description: "Work on each task on its own branch (branch_prefix + task ID), started \
+                      from the branch the run began on. The loop returns to that branch once \
+                      the task passes. Ignored by parallel runs, which use worker branches.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "git.branch_prefix",
+        description: "Prefix for task branch names when auto_branch is on.",
+        value_type: "string",
+        default: "afk/",
+        examples: &["afk/", "feature/", "bot/afk-"],
+    },
+    KeyMetadata {
+        key: "git.auto_merge",
+        description: "With auto_branch, re-run the quality gates on a task's branch once the \
+                      task passes and merge it back into the base branch if they pass. \
+                      Branches with failing gates or merge conflicts are left for review.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "git.open_pr",
+        description: "Push the branch and open a pull request with the gh CLI when a task \
+                      is completed, recording its URL in progress.json. With auto_branch \
+                      the PR is opened from the task branch instead of merging it.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "git.revert_on_gate_failure",
+        description: "When an iteration leaves the quality gates failing and they passed \
+                      before it, reset to the commit it started at, undo its file changes, \
+                      record the failure with the gate output, and give that output to the \
+                      next prompt.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "git.pr_title_template",
+        description: "Tera template for pull request titles. Has task (id, title, \
+                      description, acceptance_criteria), gates, branch and base.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["{{ task.id }}: {{ task.title }}"],
+    },
+    KeyMetadata {
+        key: "git.pr_body_template",
+        description: "Tera template for pull request bodies, with the same variables as \
+                      pr_title_template.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["{{ task.description }}"],
+    },
+    // github section
+    KeyMetadata {
+        key: "github.sync_back",
+        description: "Close a task's GitHub issue (with a comment) when the task is completed \
+                      by afk done or the loop. Labels and assignees are re-imported on every \
+                      sync regardless.",
+        value_type: "bool",
+        default: "true",
+        examples: &["true", "false"],
+    },
     // archive section
     KeyMetadata {
         key: "archive.enabled",
@@ -166,6 +347,489 @@ pub static METADATA: &[KeyMetadata] = &[
         default: ".afk/archive",
         examples: &[".afk/archive", "archives", ".archive"],
     },
+    // artifacts section
+    KeyMetadata {
+        key: "artifacts.enabled",
+        description: "Whether gate output is kept as artefacts under \
+                      .afk/artifacts/<iteration>/<gate>.log after each verify run.",
+        value_type: "bool",
+        default: "true",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "artifacts.max_kb",
+        description: "Maximum size of a single gate log in KiB. Longer output is truncated \
+                      from the start, keeping the tail where failures usually are.",
+        value_type: "positive integer",
+        default: "256",
+        examples: &["64", "256", "1024"],
+    },
+    KeyMetadata {
+        key: "artifacts.keep_iterations",
+        description: "Number of iterations whose gate artefacts are kept; older ones are \
+                      deleted. 0 keeps all.",
+        value_type: "non-negative integer",
+        default: "20",
+        examples: &["5", "20", "0"],
+    },
+    // runner section
+    KeyMetadata {
+        key: "runner.warmup",
+        description: "Run a read-only warm-up iteration before the first task. The AI reads \
+                      the repo and summarises its conventions and a plan; the summary is \
+                      saved to .afk/warmup.md and prepended to later prompts.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "runner.refresh",
+        description: "Before each afk go, regenerate derived context (such as the warm-up \
+                      notes) whose inputs - context files, AGENTS.md, README, manifests, \
+                      instructions - changed since it was made. Skip once with --no-refresh.",
+        value_type: "bool",
+        default: "true",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "runner.priority_aging",
+        description: "Let pending tasks gain priority the longer they sit untouched, so \
+                      low-priority chores are not starved forever. Ties still go to the \
+                      originally more urgent task.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "runner.aging_days",
+        description: "With priority_aging, days a task must sit untouched (not added or \
+                      changed by sync) to gain one priority level.",
+        value_type: "positive integer",
+        default: "7",
+        examples: &["3", "7", "14"],
+    },
+    KeyMetadata {
+        key: "runner.aging_cap",
+        description: "With priority_aging, the most priority levels a task can gain.",
+        value_type: "non-negative integer",
+        default: "2",
+        examples: &["1", "2", "4"],
+    },
+    KeyMetadata {
+        key: "runner.ordering",
+        description: "How the next task is picked. 'priority' takes the most urgent task; \
+                      'probability' takes the task most likely to be finished autonomously \
+                      (small, no past failures, dependencies done) so long unattended runs \
+                      complete as many tasks as possible.",
+        value_type: "priority | probability",
+        default: "priority",
+        examples: &["priority", "probability"],
+    },
+    KeyMetadata {
+        key: "runner.sync_conflicts",
+        description: "How sync settles a task its source closed while it failed locally, or \
+                      reopened after it was completed, when there is no terminal to ask. \
+                      'keep-local' leaves the local state; 'take-remote' follows the source; \
+                      'review' skips the task and flags it for a human.",
+        value_type: "keep-local | take-remote | review",
+        default: "keep-local",
+        examples: &["keep-local", "take-remote", "review"],
+    },
+    KeyMetadata {
+        key: "runner.stall_factor",
+        description: "Kill the AI CLI once it has printed nothing for this many times \
+                      feedback.thinking_threshold_secs, and retry the iteration. 0 disables \
+                      stall detection.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["0", "30", "60"],
+    },
+    KeyMetadata {
+        key: "runner.stall_retries",
+        description: "Times a stalled iteration is retried before it fails and the loop stops.",
+        value_type: "non-negative integer",
+        default: "1",
+        examples: &["0", "1", "3"],
+    },
+    KeyMetadata {
+        key: "runner.stall_nudge",
+        description: "When retrying a stalled iteration, tell the AI CLI its previous attempt \
+                      appeared stuck and ask it to take a different approach.",
+        value_type: "boolean",
+        default: "true",
+        examples: &["true", "false"],
+    },
+    // guardrails section
+    KeyMetadata {
+        key: "guardrails.max_changed_lines",
+        description: "Maximum lines changed (added + removed) in a single iteration. Larger \
+                      diffs trigger the on_exceed action. 0 disables the check.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["500", "1000", "2000"],
+    },
+    KeyMetadata {
+        key: "guardrails.max_changed_files",
+        description: "Maximum files changed in a single iteration. More files trigger the \
+                      on_exceed action. 0 disables the check.",
+        value_type: "non-negative integer",
+        default: "0",
+        examples: &["10", "25", "50"],
+    },
+    KeyMetadata {
+        key: "guardrails.on_exceed",
+        description: "What to do when an iteration exceeds a guardrail. 'warn' only reports \
+                      it; the others don't accept the tasks it completed. 'split' reopens \
+                      them and asks the AI to split the work in the next prompt, 'review' \
+                      holds them for afk review, 'stop' holds them and ends the loop.",
+        value_type: "warn | split | review | stop",
+        default: "split",
+        examples: &["warn", "split", "review", "stop"],
+    },
+    KeyMetadata {
+        key: "guardrails.protected_paths",
+        description: "Globs of paths an iteration may not change. Changes to them are \
+                      reverted after the iteration, recorded against the task, and reported \
+                      in the next prompt.",
+        value_type: "comma-separated globs",
+        default: "(none)",
+        examples: &[".github/workflows/**, secrets/**", "*.pem"],
+    },
+    KeyMetadata {
+        key: "guardrails.secret_scan",
+        description: "Scan each iteration's changes for API keys, private keys and \
+                      credentials. Findings undo the iteration's commits and fail it, and \
+                      `afk verify` gains a 'secrets' gate.",
+        value_type: "boolean",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "guardrails.secret_patterns",
+        description: "Extra regexes the secret scan flags, on top of the built-in patterns.",
+        value_type: "comma-separated regexes",
+        default: "(none)",
+        examples: &["ACME-[0-9a-f]{32}"],
+    },
+    KeyMetadata {
+        key: "guardrails.secret_allowlist",
+        description: "Regexes for secret scan matches that are fine to commit, such as \
+                      test fixtures. A finding whose line matches one is ignored.",
+        value_type: "comma-separated regexes",
+        default: "(none)",
+        examples: &["EXAMPLE", "fixtures/fake-key"],
+    },
+    // plugins section
+    KeyMetadata {
+        key: "plugins.paths",
+        description: "Shared libraries implementing the afk plugin C ABI. Plugins receive \
+                      runner events, can veto an iteration's commits, and can add quality gates.",
+        value_type: "comma-separated file paths",
+        default: "(none)",
+        examples: &[".afk/plugins/libpolicy.so", "target/release/libafk_policy.dylib"],
+    },
+    // reporting section
+    KeyMetadata {
+        key: "reporting.tracking_issue",
+        description: "GitHub issue or PR that afk keeps a single rolling comment on, summarising \
+                      the run (tasks done, current task, cost). Posted with the gh CLI.",
+        value_type: "issue number, owner/repo#number, or URL (optional)",
+        default: "(not set)",
+        examples: &["42", "acme/shop#42", "https://github.com/acme/shop/pull/7"],
+    },
+    KeyMetadata {
+        key: "reporting.every",
+        description: "Iterations between updates of the tracking comment. It is also updated \
+                      when the loop stops.",
+        value_type: "positive integer",
+        default: "5",
+        examples: &["1", "5", "10"],
+    },
+    KeyMetadata {
+        key: "reporting.estimate_unit",
+        description: "What a task's estimate counts: story points or minutes of work. \
+                      afk report burndown charts remaining estimates in this unit, and with \
+                      minutes compares them with the time tasks actually took.",
+        value_type: "points | minutes",
+        default: "points",
+        examples: &["points", "minutes"],
+    },
+    // hooks section
+    KeyMetadata {
+        key: "hooks.pre_iteration",
+        description: "Shell command run before each iteration, once its task is picked. Gets \
+                      AFK_ITERATION, AFK_TASK_ID and AFK_TASK_TITLE.",
+        value_type: "shell command (optional)",
+        default: "(not set)",
+        examples: &["./scripts/reset-db.sh", "echo \"$AFK_TASK_ID\" >> .afk/started.log"],
+    },
+    KeyMetadata {
+        key: "hooks.post_iteration",
+        description: "Shell command run after each iteration. Also gets AFK_SUCCESS (true or \
+                      false) and AFK_ERROR when the iteration failed.",
+        value_type: "shell command (optional)",
+        default: "(not set)",
+        examples: &["docker compose down", "./scripts/cleanup.sh"],
+    },
+    KeyMetadata {
+        key: "hooks.on_task_complete",
+        description: "Shell command run for each task the loop completes, with AFK_TASK_ID and \
+                      AFK_TASK_TITLE set.",
+        value_type: "shell command (optional)",
+        default: "(not set)",
+        examples: &["./scripts/notify-slack.sh \"Done: $AFK_TASK_TITLE\""],
+    },
+    KeyMetadata {
+        key: "hooks.on_task_fail",
+        description: "Shell command run when an iteration records a failure for its task \
+                      (e.g. via `afk fail`). AFK_ERROR holds the failure message.",
+        value_type: "shell command (optional)",
+        default: "(not set)",
+        examples: &["./scripts/notify-slack.sh \"Failed: $AFK_TASK_ID\""],
+    },
+    KeyMetadata {
+        key: "hooks.on_gate_fail",
+        description: "Shell command run when `afk verify` finds failing quality gates. \
+                      AFK_FAILED_GATES lists them, comma-separated.",
+        value_type: "shell command (optional)",
+        default: "(not set)",
+        examples: &["./scripts/notify-slack.sh \"Gates failed: $AFK_FAILED_GATES\""],
+    },
+    // notifications section
+    KeyMetadata {
+        key: "notifications.webhook_url",
+        description: "URL to POST a message to when a notification fires (Slack or Discord \
+                      incoming webhook, or any endpoint taking JSON). Falls back to the \
+                      AFK_WEBHOOK_URL environment variable.",
+        value_type: "URL (optional)",
+        default: "(not set)",
+        examples: &["https://hooks.slack.com/services/T000/B000/XXXX"],
+    },
+    KeyMetadata {
+        key: "notifications.format",
+        description: "Webhook payload shape. 'auto' picks Slack or Discord from the URL and \
+                      plain JSON otherwise.",
+        value_type: "auto | slack | discord | json",
+        default: "auto",
+        examples: &["auto", "slack", "json"],
+    },
+    KeyMetadata {
+        key: "notifications.desktop",
+        description: "Also show a native desktop notification (notify-send on Linux, \
+                      osascript on macOS).",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "notifications.events",
+        description: "Events that send a notification: run_end (the loop stopped), \
+                      max_failures (a task reached limits.max_task_failures), stalled \
+                      (iterations in a row without a commit).",
+        value_type: "comma-separated event names",
+        default: "run_end, max_failures, stalled",
+        examples: &["run_end", "run_end, stalled"],
+    },
+    KeyMetadata {
+        key: "notifications.stall_iterations",
+        description: "Iterations in a row without a commit before the run counts as \
+                      stalled. 0 turns stall notifications off.",
+        value_type: "non-negative integer",
+        default: "3",
+        examples: &["0", "3", "5"],
+    },
+    // server section
+    KeyMetadata {
+        key: "server.host",
+        description: "Address `afk serve` listens on. Anything other than a loopback \
+                      address needs a token.",
+        value_type: "IP address or host name",
+        default: "127.0.0.1",
+        examples: &["127.0.0.1", "0.0.0.0"],
+    },
+    KeyMetadata {
+        key: "server.port",
+        description: "Port `afk serve` listens on.",
+        value_type: "port number",
+        default: "7878",
+        examples: &["7878", "8080"],
+    },
+    KeyMetadata {
+        key: "server.token",
+        description: "Token API clients must send as `Authorization: Bearer <token>` (or a \
+                      `token` query parameter). Falls back to the AFK_SERVER_TOKEN \
+                      environment variable.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["s3cret-token"],
+    },
+    // selection section
+    KeyMetadata {
+        key: "selection.strategy",
+        description: "How the next task is picked: 'priority' (most urgent), 'fifo' (oldest \
+                      first, by when sync added or last changed it), 'smallest-first' \
+                      (fewest estimated iterations and unmet acceptance criteria), \
+                      'dependency-aware' (tasks that unblock the most others) or \
+                      'round-robin-by-epic' (one task from each epic in turn). \
+                      runner.ordering = probability takes precedence.",
+        value_type: "priority | fifo | smallest-first | dependency-aware | round-robin-by-epic",
+        default: "priority",
+        examples: &["priority", "dependency-aware", "round-robin-by-epic"],
+    },
+    KeyMetadata {
+        key: "selection.stick_to_epic",
+        description: "Finish every pending task in the epic worked on last before moving \
+                      on to another. Within the epic, selection.strategy still decides \
+                      the order.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    // dedup section
+    KeyMetadata {
+        key: "dedup.by_id",
+        description: "Merge tasks with the same ID from different kinds of source into one \
+                      during sync.",
+        value_type: "bool",
+        default: "true",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "dedup.by_title",
+        description: "Merge tasks from different kinds of source whose titles match \
+                      (ignoring case and punctuation) at least dedup.title_similarity.",
+        value_type: "bool",
+        default: "true",
+        examples: &["true", "false"],
+    },
+    KeyMetadata {
+        key: "dedup.title_similarity",
+        description: "How alike two titles must be to count as the same task, from 0 to \
+                      1; 1 only matches titles that are the same once normalised.",
+        value_type: "float (0-1)",
+        default: "0.9",
+        examples: &["0.9", "1.0"],
+    },
+    // ollama section
+    KeyMetadata {
+        key: "ollama.host",
+        description: "Address of the Ollama server used when ai_cli.backend is 'ollama'.",
+        value_type: "string (URL)",
+        default: "http://localhost:11434",
+        examples: &["http://localhost:11434", "http://gpu-box:11434"],
+    },
+    KeyMetadata {
+        key: "ollama.model",
+        description: "Ollama model to run. A model picked from ai_cli.models takes \
+                      precedence.",
+        value_type: "string (optional)",
+        default: "(not set)",
+        examples: &["qwen2.5-coder:14b", "llama3.1:8b"],
+    },
+    KeyMetadata {
+        key: "ollama.context_size",
+        description: "Context window to ask Ollama for, in tokens. Prompts are trimmed to \
+                      three quarters of it, leaving the rest for the reply.",
+        value_type: "integer",
+        default: "8192",
+        examples: &["8192", "32768"],
+    },
+    // review section
+    KeyMetadata {
+        key: "review.required",
+        description: "Hold tasks the AI marks passed as awaiting review instead of \
+                      completing them; `afk review` approves them, or rejects them back to \
+                      pending with feedback for the next prompt.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
+    // retry section
+    KeyMetadata {
+        key: "retry.cli_crash",
+        description: "What to do after the AI CLI exits with an error: 'retry' carries on, 'backoff' waits \
+                      first, 'skip' skips the task after `retry.skip_after` in a row, \
+                      'stop' ends the loop.",
+        value_type: "retry | backoff | skip | stop",
+        default: "stop",
+        examples: &["retry", "backoff", "skip", "stop"],
+    },
+    KeyMetadata {
+        key: "retry.timeout",
+        description: "What to do after the AI CLI stalls and is stopped: 'retry' carries on, 'backoff' waits \
+                      first, 'skip' skips the task after `retry.skip_after` in a row, \
+                      'stop' ends the loop.",
+        value_type: "retry | backoff | skip | stop",
+        default: "stop",
+        examples: &["retry", "backoff", "skip", "stop"],
+    },
+    KeyMetadata {
+        key: "retry.gates_failed",
+        description: "What to do after an iteration's quality gates or criterion checks fail: 'retry' carries on, 'backoff' waits \
+                      first, 'skip' skips the task after `retry.skip_after` in a row, \
+                      'stop' ends the loop.",
+        value_type: "retry | backoff | skip | stop",
+        default: "retry",
+        examples: &["retry", "backoff", "skip", "stop"],
+    },
+    KeyMetadata {
+        key: "retry.no_changes",
+        description: "What to do after an iteration changes no files and completes nothing: 'retry' carries on, 'backoff' waits \
+                      first, 'skip' skips the task after `retry.skip_after` in a row, \
+                      'stop' ends the loop.",
+        value_type: "retry | backoff | skip | stop",
+        default: "retry",
+        examples: &["retry", "backoff", "skip", "stop"],
+    },
+    KeyMetadata {
+        key: "retry.rate_limit",
+        description: "What to do after the AI CLI reports a rate limit: 'retry' carries on, 'backoff' waits \
+                      first, 'skip' skips the task after `retry.skip_after` in a row, \
+                      'stop' ends the loop.",
+        value_type: "retry | backoff | skip | stop",
+        default: "backoff",
+        examples: &["retry", "backoff", "skip", "stop"],
+    },
+    KeyMetadata {
+        key: "retry.backoff_secs",
+        description: "Seconds the 'backoff' policy waits after the first failure; the wait \
+                      doubles with each failure of the same kind in a row. Rate limits use \
+                      `limits.rate_limit_backoff_secs` instead.",
+        value_type: "integer",
+        default: "30",
+        examples: &["30", "120"],
+    },
+    KeyMetadata {
+        key: "retry.max_backoff_secs",
+        description: "Longest wait for the 'backoff' policy, in seconds.",
+        value_type: "integer",
+        default: "900",
+        examples: &["900", "3600"],
+    },
+    KeyMetadata {
+        key: "retry.skip_after",
+        description: "Failures of one kind in a row before the 'skip' policy skips the task.",
+        value_type: "integer",
+        default: "2",
+        examples: &["1", "3"],
+    },
+    // schedule section
+    KeyMetadata {
+        key: "schedule.cron",
+        description: "When `afk scheduler start` runs `afk go`, as a five-field cron \
+                      expression (minute hour day month weekday) in local time, or \
+                      @hourly, @daily, @weekly or @monthly.",
+        value_type: "cron expression (optional)",
+        default: "(not set)",
+        examples: &["0 2 * * *", "30 22 * * mon-fri", "@daily"],
+    },
+    KeyMetadata {
+        key: "schedule.iterations",
+        description: "Iterations for each scheduled run. Unset uses limits.max_iterations.",
+        value_type: "positive integer (optional)",
+        default: "(not set)",
+        examples: &["5", "20"],
+    },
     // feedback section
     KeyMetadata {
         key: "feedback.enabled",
@@ -184,7 +848,7 @@ pub static METADATA: &[KeyMetadata] = &[
     },
     KeyMetadata {
         key: "feedback.show_files",
-        description: "Whether to show modified files in feedback display.",
+        description: "Whether to show modified files in feedback display, and the files each iteration changed (from git) after it.",
         value_type: "bool",
         default: "true",
         examples: &["true", "false"],
@@ -234,6 +898,31 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "10",
         examples: &["5", "10", "30"],
     },
+    KeyMetadata {
+        key: "feedback.log_dir",
+        description: "Directory each iteration's transcript is written to as it runs \
+                      (iteration-<n>.log: raw AI CLI output, stderr and parsed events). \
+                      View with 'afk logs <n>'. Empty disables transcripts.",
+        value_type: "path",
+        default: ".afk/logs",
+        examples: &[".afk/logs", "/tmp/afk-logs", ""],
+    },
+    KeyMetadata {
+        key: "feedback.log_keep",
+        description: "Iteration transcripts to keep; older ones are deleted when a new \
+                      iteration starts. 0 keeps all.",
+        value_type: "integer",
+        default: "50",
+        examples: &["20", "50", "0"],
+    },
+    KeyMetadata {
+        key: "feedback.log_max_kb",
+        description: "Size in KB at which a transcript is rotated to iteration-<n>.log.1 \
+                      and a fresh file started. 0 never rotates.",
+        value_type: "integer",
+        default: "10240",
+        examples: &["1024", "10240", "0"],
+    },
     // feedback_loops section
     KeyMetadata {
         key: "feedback_loops.types",
@@ -263,6 +952,16 @@ pub static METADATA: &[KeyMetadata] = &[
         default: "(not set)",
         examples: &["pip wheel .", "npm run build", "cargo build"],
     },
+    KeyMetadata {
+        key: "feedback_loops.changed_only",
+        description: "While the loop runs, have 'afk verify' replace the test gate with the \
+                      tests covering the iteration's changed files (test_map rules, then \
+                      built-in heuristics). Falls back to the full test command when a \
+                      changed file can't be mapped.",
+        value_type: "bool",
+        default: "false",
+        examples: &["true", "false"],
+    },
 ];
 
 /// Get metadata for a specific key.
@@ -342,11 +1041,11 @@ mod tests {
     #[test]
     fn test_keys_for_section() {
         let limits_keys = keys_for_section("limits");
-        assert_eq!(limits_keys.len(), 3);
+        assert_eq!(limits_keys.len(), 6);
         assert!(limits_keys.iter().all(|m| m.key.starts_with("limits.")));
 
         let git_keys = keys_for_section("git");
-        assert_eq!(git_keys.len(), 2);
+        assert_eq!(git_keys.len(), 12);
     }
 
     #[test]
@@ -1,18 +1,25 @@
 //! Configuration models for .afk/config.json.
 //!
 //! This module contains Serde models for the afk configuration,
-//! mirroring the Python Pydantic models in src/afk/config.py.
+//! mirroring the Python Pydantic models in src/afk/config.py. The same
+//! models load from `.afk/config.yaml` or `.afk/config.toml` (see [`format`]).
 
+pub mod expand;
 pub mod field;
+pub mod format;
 pub mod metadata;
+pub mod profile;
 mod validation;
 
 use serde::{Deserialize, Serialize};
 
 pub use field::{ConfigField, FieldError};
+pub use format::{find_config_file, ConfigFormat};
+pub use profile::{active_profile, set_profile, PROFILES_DIR};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 /// Default config directory path.
 pub const AFK_DIR: &str = ".afk";
@@ -24,6 +31,75 @@ pub const PROGRESS_FILE: &str = ".afk/progress.json";
 pub const TASKS_FILE: &str = ".afk/tasks.json";
 /// Default archive directory path.
 pub const ARCHIVE_DIR: &str = ".afk/archive";
+/// Per-iteration reproducibility records.
+pub const REPRO_DIR: &str = ".afk/repro";
+/// Default directory for per-iteration AI CLI transcripts.
+pub const LOGS_DIR: &str = ".afk/logs";
+/// Gate output artefacts, one subdirectory per iteration.
+pub const ARTIFACTS_DIR: &str = ".afk/artifacts";
+/// Repository summary written by the warm-up iteration.
+pub const WARMUP_FILE: &str = ".afk/warmup.md";
+/// Git worktrees and logs for parallel workers.
+pub const WORKTREES_DIR: &str = ".afk/worktrees";
+/// PID file held by a running `afk watch`.
+pub const WATCH_PID_FILE: &str = ".afk/watch.pid";
+/// PID file held by a running `afk scheduler start`.
+pub const SCHEDULER_PID_FILE: &str = ".afk/scheduler.pid";
+/// Cached passing quality gate results, keyed on the working tree hash.
+pub const GATE_CACHE_FILE: &str = ".afk/gate-cache.json";
+/// Tool versions from the last run where every gate passed.
+pub const GATE_ENV_FILE: &str = ".afk/gate-env.json";
+/// Input fingerprints of derived artefacts, used to refresh stale ones.
+pub const REFRESH_FILE: &str = ".afk/refresh.json";
+/// Structured event log, one JSON record per line.
+pub const EVENTS_FILE: &str = ".afk/events.jsonl";
+/// Phase of the iteration in progress, left behind if afk dies mid-iteration.
+pub const ITERATION_STATE_FILE: &str = ".afk/iteration-state.json";
+/// Completed and remaining task counts after each iteration.
+pub const BURNDOWN_FILE: &str = ".afk/burndown.jsonl";
+/// Why the last task selection passed over each pending task.
+pub const SELECTION_FILE: &str = ".afk/selection.json";
+/// Which source tasks sync merged into which, so merges stick.
+pub const SOURCE_MAP_FILE: &str = ".afk/source-map.json";
+/// Control files external tools create to steer a running loop.
+pub const CONTROL_DIR: &str = ".afk/control";
+/// While this file exists, the loop waits between iterations.
+pub const PAUSE_FILE: &str = ".afk/control/pause";
+/// Creating this file stops the loop before its next iteration.
+pub const STOP_FILE: &str = ".afk/control/stop";
+/// Creating this file skips the task being worked on once its iteration ends.
+pub const SKIP_FILE: &str = ".afk/control/skip";
+/// Directory of named prompt templates (`<name>.md`).
+pub const TEMPLATES_DIR: &str = ".afk/templates";
+/// Directory of task templates for `afk task new` (`<name>.json`).
+pub const TASK_TEMPLATES_DIR: &str = ".afk/templates/tasks";
+/// Environment variable that enables read-only mode when set to 1/true.
+pub const READ_ONLY_ENV: &str = "AFK_READ_ONLY";
+
+/// Read-only mode enabled via the `--read-only` flag.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable read-only mode for this process.
+///
+/// In read-only mode every attempt to write afk state (config, tasks,
+/// progress, archives) fails instead of touching disk.
+pub fn set_read_only(enabled: bool) {
+    READ_ONLY.store(enabled, Ordering::SeqCst);
+}
+
+/// Whether read-only mode is active, via flag or `AFK_READ_ONLY`.
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::SeqCst)
+        || read_only_from_env(std::env::var(READ_ONLY_ENV).ok().as_deref())
+}
+
+/// Interpret an `AFK_READ_ONLY` value.
+fn read_only_from_env(value: Option<&str>) -> bool {
+    matches!(
+        value.map(|v| v.trim().to_lowercase()).as_deref(),
+        Some("1" | "true" | "yes")
+    )
+}
 
 /// Source types supported by afk.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -39,63 +115,161 @@ pub enum SourceType {
     Github,
     /// OpenSpec change proposals with structured specs.
     Openspec,
+    /// Directory of markdown files, one story per file.
+    #[serde(rename = "stories-dir")]
+    StoriesDir,
+    /// Notion database via the Notion API.
+    Notion,
+    /// Trello board via the Trello API.
+    Trello,
+    /// Jira project via the Jira REST API.
+    Jira,
+    /// External command that prints tasks as JSON on stdout.
+    Command,
+    /// CSV or TSV spreadsheet, one task per row.
+    Csv,
 }
 
 /// Configuration for a task source.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SourceConfig {
-    /// Type of source (beads, json, markdown, github).
+    /// Type of source (beads, json, markdown, github, openspec, stories-dir,
+    /// notion, trello, jira, command, csv).
     #[serde(rename = "type")]
     pub source_type: SourceType,
-    /// Path to source file (for json/markdown sources).
+    /// Path to source file (for json/markdown/csv sources), directory (for
+    /// stories-dir sources) or executable (for command sources).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    /// Command-specific: arguments passed to the executable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
     /// GitHub-specific: repository in "owner/repo" format.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repo: Option<String>,
-    /// GitHub-specific: labels to filter issues.
+    /// Notion-specific: database ID to query.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Trello-specific: board ID to read cards from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    /// Jira-specific: site base URL (e.g. "https://acme.atlassian.net").
+    /// Falls back to the `JIRA_URL` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Jira-specific: project key to read issues from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Jira-specific: extra JQL filter, combined with the project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jql: Option<String>,
+    /// Notion/Trello/Jira: environment variable holding the API token
+    /// (defaults to `NOTION_TOKEN`, `TRELLO_TOKEN` or `JIRA_API_TOKEN`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
+    /// Notion/Trello/Jira: which properties map to task fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<PropertyMapping>,
+    /// CSV-specific: which columns map to task fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<ColumnMapping>,
+    /// GitHub/Trello/Jira: labels to filter issues or cards.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub labels: Vec<String>,
+    /// GitHub: fetch the latest N issue comments on sync and include them
+    /// in the task's prompt context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comments: Option<u32>,
+    /// Watch mode: minutes between polls. File-based sources are watched
+    /// for changes instead unless this is set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_minutes: Option<u32>,
+    /// Watch mode: local time window in which syncing is allowed, as
+    /// "HH:MM-HH:MM" (may wrap midnight, e.g. "18:00-08:00").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_hours: Option<String>,
+    /// Write completion back to the source (beads: comment with the outcome
+    /// and reopen or block failed issues; markdown: check the item off;
+    /// stories-dir: move finished story files into a `done/` subfolder).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub writeback: bool,
 }
 
 impl SourceConfig {
-    /// Create a new beads source.
-    pub fn beads() -> Self {
+    /// Create a source of the given type with no options set.
+    pub fn new(source_type: SourceType) -> Self {
         Self {
-            source_type: SourceType::Beads,
+            source_type,
             path: None,
+            args: Vec::new(),
             repo: None,
+            database: None,
+            board: None,
+            url: None,
+            project: None,
+            jql: None,
+            token_env: None,
+            properties: None,
+            columns: None,
             labels: Vec::new(),
+            comments: None,
+            poll_minutes: None,
+            active_hours: None,
+            writeback: false,
         }
     }
 
+    /// Create a new beads source.
+    pub fn beads() -> Self {
+        Self::new(SourceType::Beads)
+    }
+
     /// Create a new JSON source with a path.
     pub fn json(path: impl Into<String>) -> Self {
         Self {
-            source_type: SourceType::Json,
             path: Some(path.into()),
-            repo: None,
-            labels: Vec::new(),
+            ..Self::new(SourceType::Json)
         }
     }
 
     /// Create a new markdown source with a path.
     pub fn markdown(path: impl Into<String>) -> Self {
         Self {
-            source_type: SourceType::Markdown,
             path: Some(path.into()),
-            repo: None,
-            labels: Vec::new(),
+            ..Self::new(SourceType::Markdown)
+        }
+    }
+
+    /// Create a new stories-dir source with a directory path.
+    pub fn stories_dir(path: impl Into<String>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::new(SourceType::StoriesDir)
+        }
+    }
+
+    /// Create a new CSV source with a path (`.tsv` files are tab-separated).
+    pub fn csv(path: impl Into<String>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::new(SourceType::Csv)
+        }
+    }
+
+    /// Create a new command source that runs an executable.
+    pub fn command(path: impl Into<String>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::new(SourceType::Command)
         }
     }
 
     /// Create a new GitHub source.
     pub fn github(repo: impl Into<String>, labels: Vec<String>) -> Self {
         Self {
-            source_type: SourceType::Github,
-            path: None,
             repo: Some(repo.into()),
             labels,
+            ..Self::new(SourceType::Github)
         }
     }
 
@@ -104,11 +278,146 @@ impl SourceConfig {
     /// Reads tasks from `openspec/changes/<change-id>/tasks.md` files
     /// and includes spec context in the task descriptions.
     pub fn openspec() -> Self {
+        Self::new(SourceType::Openspec)
+    }
+
+    /// Create a new Notion database source.
+    pub fn notion(database: impl Into<String>) -> Self {
         Self {
-            source_type: SourceType::Openspec,
-            path: None,
-            repo: None,
-            labels: Vec::new(),
+            database: Some(database.into()),
+            ..Self::new(SourceType::Notion)
+        }
+    }
+
+    /// Create a new Trello board source.
+    pub fn trello(board: impl Into<String>) -> Self {
+        Self {
+            board: Some(board.into()),
+            ..Self::new(SourceType::Trello)
+        }
+    }
+
+    /// Create a new Jira project source.
+    pub fn jira(project: impl Into<String>) -> Self {
+        Self {
+            project: Some(project.into()),
+            ..Self::new(SourceType::Jira)
+        }
+    }
+}
+
+/// Maps properties in an external tool (Notion, Trello, Jira) to task fields.
+///
+/// For Trello, `status` is the list a card is in and criteria come from
+/// the card's checklists. For Jira, properties are field names or IDs
+/// (such as `customfield_10042`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertyMapping {
+    /// Property holding the task title.
+    #[serde(default = "default_title_property")]
+    pub title: String,
+    /// Property holding the task description.
+    #[serde(default = "default_description_property")]
+    pub description: String,
+    /// Property holding the priority (number, or a name like "High" or "P1").
+    #[serde(default = "default_priority_property")]
+    pub priority: String,
+    /// Property holding acceptance criteria (one per line, or multi-select).
+    #[serde(default = "default_criteria_property")]
+    pub criteria: String,
+    /// Property holding the status.
+    #[serde(default = "default_status_property")]
+    pub status: String,
+    /// Status values (or Trello list names) that mean the task is done.
+    #[serde(default = "default_done_values")]
+    pub done: Vec<String>,
+}
+
+fn default_title_property() -> String {
+    "Name".to_string()
+}
+
+fn default_description_property() -> String {
+    "Description".to_string()
+}
+
+fn default_priority_property() -> String {
+    "Priority".to_string()
+}
+
+fn default_criteria_property() -> String {
+    "Acceptance Criteria".to_string()
+}
+
+fn default_status_property() -> String {
+    "Status".to_string()
+}
+
+fn default_done_values() -> Vec<String> {
+    vec!["Done".to_string()]
+}
+
+impl Default for PropertyMapping {
+    fn default() -> Self {
+        Self {
+            title: default_title_property(),
+            description: default_description_property(),
+            priority: default_priority_property(),
+            criteria: default_criteria_property(),
+            status: default_status_property(),
+            done: default_done_values(),
+        }
+    }
+}
+
+/// Maps the header row of a CSV source to task fields.
+///
+/// Headers match case-insensitively. Rows without a title are skipped, and
+/// rows whose `done` cell is "yes", "true", "x", "done" or similar are
+/// already complete.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    /// Column holding the task ID; without one, IDs come from titles.
+    #[serde(default = "default_id_column")]
+    pub id: String,
+    /// Column holding the task title.
+    #[serde(default = "default_title_column")]
+    pub title: String,
+    /// Column holding the task description.
+    #[serde(default = "default_description_property")]
+    pub description: String,
+    /// Column holding the priority (number, or a name like "High" or "P1").
+    #[serde(default = "default_priority_property")]
+    pub priority: String,
+    /// Column holding acceptance criteria, one per line.
+    #[serde(default = "default_criteria_property")]
+    pub criteria: String,
+    /// Column marking the task done.
+    #[serde(default = "default_done_column")]
+    pub done: String,
+}
+
+fn default_id_column() -> String {
+    "ID".to_string()
+}
+
+fn default_title_column() -> String {
+    "Title".to_string()
+}
+
+fn default_done_column() -> String {
+    "Done".to_string()
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            id: default_id_column(),
+            title: default_title_column(),
+            description: default_description_property(),
+            priority: default_priority_property(),
+            criteria: default_criteria_property(),
+            done: default_done_column(),
         }
     }
 }
@@ -131,6 +440,76 @@ pub struct FeedbackLoopsConfig {
     /// Custom commands with name => command mapping.
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub custom: HashMap<String, String>,
+    /// Sandboxed WASI gate modules with name => gate mapping.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub wasm: HashMap<String, WasmGateConfig>,
+    /// Narrow the test gate to the changed files while the loop runs.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub changed_only: bool,
+    /// Rules mapping changed files to test commands, tried before the
+    /// built-in heuristics.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_map: Vec<TestMapping>,
+    /// Built-in secrets gate, set from `guardrails` when the gates run.
+    #[serde(skip)]
+    pub secret_scan: Option<SecretScanConfig>,
+}
+
+/// Patterns for the built-in secrets gate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecretScanConfig {
+    /// Regexes flagged in addition to the built-in ones.
+    pub patterns: Vec<String>,
+    /// Regexes for matches that are not secrets (e.g. test fixtures).
+    pub allowlist: Vec<String>,
+}
+
+/// Maps changed files matching a glob to the test command covering them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestMapping {
+    /// Glob over repository paths (`*`, `**`, `?`); without a `/` it
+    /// matches the file name at any depth.
+    pub pattern: String,
+    /// Command to run, with `{path}`, `{dir}`, `{stem}` and `{module}`
+    /// filled in. Empty means the files need no tests.
+    pub command: String,
+}
+
+/// A quality gate run as a WASI module in an embedded runtime.
+///
+/// The module only sees the directories it is explicitly granted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasmGateConfig {
+    /// Path to the `.wasm` module.
+    pub module: String,
+    /// Arguments passed to the module.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Directories the module may read.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dirs: Vec<String>,
+    /// Directories the module may read and write.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write_dirs: Vec<String>,
+    /// Seconds the module may run before the gate fails (0 = no limit).
+    #[serde(default = "default_wasm_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_wasm_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for WasmGateConfig {
+    fn default() -> Self {
+        Self {
+            module: String::new(),
+            args: Vec::new(),
+            dirs: Vec::new(),
+            write_dirs: Vec::new(),
+            timeout_secs: default_wasm_timeout_secs(),
+        }
+    }
 }
 
 /// Configuration for iteration limits.
@@ -145,6 +524,18 @@ pub struct LimitsConfig {
     /// Maximum time in minutes before timeout.
     #[serde(default = "default_timeout_minutes")]
     pub timeout_minutes: u32,
+    /// Minutes of iteration time a task may use before it is skipped
+    /// (0 = no limit).
+    #[serde(default)]
+    pub max_task_minutes: u32,
+    /// Minutes of iteration time a session may use, across runs, before
+    /// the loop stops (0 = no limit).
+    #[serde(default)]
+    pub max_session_minutes: u32,
+    /// First wait after the AI CLI reports a rate limit, in seconds; it
+    /// doubles with each rate limit in a row, up to `retry.max_backoff_secs`.
+    #[serde(default = "default_backoff_secs")]
+    pub rate_limit_backoff_secs: u64,
     /// Prevent system sleep during autonomous sessions.
     /// Uses `caffeinate` on macOS, `systemd-inhibit` on Linux.
     #[serde(default = "default_true")]
@@ -169,6 +560,9 @@ impl Default for LimitsConfig {
             max_iterations: default_max_iterations(),
             max_task_failures: default_max_task_failures(),
             timeout_minutes: default_timeout_minutes(),
+            max_task_minutes: 0,
+            max_session_minutes: 0,
+            rate_limit_backoff_secs: default_backoff_secs(),
             prevent_sleep: default_true(),
         }
     }
@@ -224,6 +618,31 @@ pub enum AiOutputFormat {
     StreamJson,
 }
 
+/// How afk runs the AI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiBackend {
+    /// Spawn `ai_cli.command` for each iteration.
+    #[default]
+    Cli,
+    /// Stream from an OpenAI-compatible chat completions endpoint.
+    Http,
+    /// Stream from a local Ollama server, as set in the `ollama` section.
+    Ollama,
+}
+
+impl AiBackend {
+    fn is_cli(&self) -> bool {
+        *self == AiBackend::Cli
+    }
+}
+
+/// Base URL of the HTTP backend when `ai_cli.base_url` is unset.
+pub const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+/// Environment variable holding the HTTP backend's API key when
+/// `ai_cli.api_key_env` is unset.
+pub const DEFAULT_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
 /// Configuration for AI CLI integration.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AiCliConfig {
@@ -244,12 +663,32 @@ pub struct AiCliConfig {
     /// This brings different perspectives and avoids local optima.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub models: Vec<String>,
+    /// Stronger models to move a failing task onto, weakest first. Every
+    /// `escalate_after` failures of a task move it one model up the list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalation_models: Vec<String>,
+    /// Failures of a task before each escalation step (0 disables escalation).
+    #[serde(default = "default_escalate_after")]
+    pub escalate_after: u32,
+    /// Whether to spawn `command` or call a chat completions endpoint.
+    #[serde(default, skip_serializing_if = "AiBackend::is_cli")]
+    pub backend: AiBackend,
+    /// Base URL of the HTTP backend, e.g. `http://localhost:11434/v1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// Environment variable holding the HTTP backend's API key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
 }
 
 fn default_ai_command() -> String {
     "claude".to_string()
 }
 
+fn default_escalate_after() -> u32 {
+    2
+}
+
 fn default_ai_args() -> Vec<String> {
     vec![
         "--dangerously-skip-permissions".to_string(),
@@ -265,6 +704,11 @@ impl Default for AiCliConfig {
             output_format: AiOutputFormat::default(),
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: default_escalate_after(),
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         }
     }
 }
@@ -329,13 +773,42 @@ impl AiCliConfig {
             .map(|d| d.as_nanos())
             .unwrap_or(0);
 
-        let index = (nanos as usize) % self.models.len();
+        self.select_model_seeded(nanos as u64)
+    }
+
+    /// Select a model deterministically from a seed.
+    ///
+    /// The same seed always picks the same model, so a recorded iteration
+    /// can be replayed with the model it originally used.
+    #[must_use]
+    pub fn select_model_seeded(&self, seed: u64) -> Option<&str> {
+        if self.models.is_empty() {
+            return None;
+        }
+        let index = (seed % self.models.len() as u64) as usize;
         Some(&self.models[index])
     }
 
+    /// The escalation model for a task that has failed `failures` times.
+    ///
+    /// None until the task has failed `escalate_after` times; after that each
+    /// further `escalate_after` failures step one model up
+    /// `escalation_models`, stopping at the last.
+    #[must_use]
+    pub fn escalation_model(&self, failures: u32) -> Option<&str> {
+        let step = failures.checked_div(self.escalate_after).unwrap_or(0) as usize;
+        let index = step
+            .checked_sub(1)?
+            .min(self.escalation_models.len().checked_sub(1)?);
+        Some(&self.escalation_models[index])
+    }
+
     /// Get the output format arguments for the detected CLI.
     fn get_output_format_args(&self) -> Vec<String> {
-        if self.output_format == AiOutputFormat::Text {
+        // Aider has no structured output; its text is parsed as it is
+        if self.output_format == AiOutputFormat::Text
+            || self.detect_cli_format() == crate::parser::CliFormat::Aider
+        {
             return Vec::new();
         }
 
@@ -354,125 +827,917 @@ impl AiCliConfig {
             args.push("--verbose".to_string());
         }
 
-        // Add partial streaming flag for stream-json mode
-        if self.output_format == AiOutputFormat::StreamJson && self.stream_partial {
-            if cmd_lower.contains("cursor") {
-                args.push("--stream-partial-output".to_string());
-            } else if cmd_lower.contains("claude") {
-                args.push("--include-partial-messages".to_string());
-            }
+        // Add partial streaming flag for stream-json mode
+        if self.output_format == AiOutputFormat::StreamJson && self.stream_partial {
+            if cmd_lower.contains("cursor") {
+                args.push("--stream-partial-output".to_string());
+            } else if cmd_lower.contains("claude") {
+                args.push("--include-partial-messages".to_string());
+            }
+        }
+
+        args
+    }
+
+    /// Check if this CLI is configured for NDJSON streaming.
+    #[must_use]
+    pub fn uses_stream_json(&self) -> bool {
+        self.output_format == AiOutputFormat::StreamJson || !self.backend.is_cli()
+    }
+
+    /// The HTTP backend's chat completions URL.
+    pub fn chat_completions_url(&self) -> String {
+        let base = self.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        format!("{}/chat/completions", base.trim_end_matches('/'))
+    }
+
+    /// The HTTP backend's API key, if its environment variable is set.
+    pub fn api_key(&self) -> Option<String> {
+        let name = self.api_key_env.as_deref().unwrap_or(DEFAULT_API_KEY_ENV);
+        std::env::var(name).ok().filter(|key| !key.is_empty())
+    }
+
+    /// Detect which CLI format parser to use based on the command.
+    pub fn detect_cli_format(&self) -> crate::parser::CliFormat {
+        match self.backend {
+            AiBackend::Cli => {}
+            AiBackend::Http => return crate::parser::CliFormat::OpenAi,
+            AiBackend::Ollama => return crate::parser::CliFormat::Ollama,
+        }
+        let cmd_lower = self.command.to_lowercase();
+        if cmd_lower.contains("cursor") {
+            crate::parser::CliFormat::Cursor
+        } else if cmd_lower.contains("claude") {
+            crate::parser::CliFormat::Claude
+        } else if cmd_lower.contains("gemini") {
+            crate::parser::CliFormat::Gemini
+        } else if cmd_lower.contains("aider") {
+            crate::parser::CliFormat::Aider
+        } else {
+            crate::parser::CliFormat::Auto
+        }
+    }
+}
+
+/// Configuration for prompt generation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptConfig {
+    /// Template name or "default".
+    #[serde(default = "default_template")]
+    pub template: String,
+    /// Path to custom template file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_path: Option<String>,
+    /// Additional context files to include.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_files: Vec<String>,
+    /// Additional instructions to include.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instructions: Vec<String>,
+    /// Whether this project has frontend/UI components requiring browser verification.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub has_frontend: bool,
+    /// Prompt style when the same task spans several iterations.
+    #[serde(default, skip_serializing_if = "ContinuationMode::is_full")]
+    pub continuation_mode: ContinuationMode,
+    /// Estimated tokens a prompt may use before context is trimmed (0 = no
+    /// limit).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_tokens: u32,
+}
+
+/// Prompt style used when the same task spans several iterations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContinuationMode {
+    /// Render the full template every iteration (default).
+    #[default]
+    Full,
+    /// Send a condensed prompt: task summary, changes since the last
+    /// attempt, and outstanding criteria.
+    Condensed,
+}
+
+impl ContinuationMode {
+    /// Whether this is the default full-template mode.
+    pub fn is_full(&self) -> bool {
+        *self == ContinuationMode::Full
+    }
+}
+
+fn default_template() -> String {
+    "default".to_string()
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            template: default_template(),
+            custom_path: None,
+            context_files: Vec::new(),
+            instructions: Vec::new(),
+            has_frontend: false,
+            continuation_mode: ContinuationMode::Full,
+            max_tokens: 0,
+        }
+    }
+}
+
+/// Configuration for git integration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitConfig {
+    /// Whether to auto-commit after task completion.
+    #[serde(default = "default_true")]
+    pub auto_commit: bool,
+    /// Template for commit messages.
+    #[serde(default = "default_commit_template")]
+    pub commit_message_template: String,
+    /// Author name for commits made during the loop (repo identity if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+    /// Author email for commits made during the loop (repo identity if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author_email: Option<String>,
+    /// Run `git submodule update --init --recursive` before quality gates.
+    #[serde(default)]
+    pub update_submodules: bool,
+    /// Work on each task on its own branch.
+    #[serde(default)]
+    pub auto_branch: bool,
+    /// Prefix for task branch names (`afk/` gives `afk/<task-id>`).
+    #[serde(default = "default_branch_prefix")]
+    pub branch_prefix: String,
+    /// Merge a task branch back once the task passes and its gates pass.
+    #[serde(default)]
+    pub auto_merge: bool,
+    /// Open a pull request with `gh` when a task is completed.
+    #[serde(default)]
+    pub open_pr: bool,
+    /// Tera template for pull request titles (built-in title if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_title_template: Option<String>,
+    /// Tera template for pull request bodies (built-in body if unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_body_template: Option<String>,
+    /// Revert an iteration that leaves previously green quality gates
+    /// failing, and re-prompt with the gate output.
+    #[serde(default)]
+    pub revert_on_gate_failure: bool,
+}
+
+impl GitConfig {
+    /// The configured commit identity, if either part is set.
+    pub fn commit_identity(&self) -> Option<crate::git::CommitIdentity> {
+        (self.author_name.is_some() || self.author_email.is_some()).then(|| {
+            crate::git::CommitIdentity {
+                name: self.author_name.clone(),
+                email: self.author_email.clone(),
+            }
+        })
+    }
+
+    /// Environment variables that apply the commit identity to a process.
+    ///
+    /// Empty when no identity is configured, so git falls back to the
+    /// repository's own user.name/user.email.
+    pub fn identity_env(&self) -> Vec<(&'static str, String)> {
+        self.commit_identity()
+            .map(|identity| identity.env())
+            .unwrap_or_default()
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_branch_prefix() -> String {
+    "afk/".to_string()
+}
+
+/// Configuration for syncing with GitHub issue sources.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GithubConfig {
+    /// Close a task's GitHub issue when the task is completed.
+    #[serde(default = "default_true")]
+    pub sync_back: bool,
+}
+
+impl Default for GithubConfig {
+    fn default() -> Self {
+        Self {
+            sync_back: default_true(),
+        }
+    }
+}
+
+fn default_commit_template() -> String {
+    "afk: {task_id} - {message}".to_string()
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            auto_commit: default_true(),
+            commit_message_template: default_commit_template(),
+            author_name: None,
+            author_email: None,
+            update_submodules: false,
+            auto_branch: false,
+            branch_prefix: default_branch_prefix(),
+            auto_merge: false,
+            open_pr: false,
+            pr_title_template: None,
+            pr_body_template: None,
+            revert_on_gate_failure: false,
+        }
+    }
+}
+
+/// Configuration for session archiving.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// Whether archiving is enabled.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Directory to store archives.
+    #[serde(default = "default_archive_directory")]
+    pub directory: String,
+}
+
+fn default_archive_directory() -> String {
+    ".afk/archive".to_string()
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            directory: default_archive_directory(),
+        }
+    }
+}
+
+/// Configuration for the loop runner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunnerConfig {
+    /// Run a read-only warm-up iteration before the first task, and prepend
+    /// its summary to later prompts.
+    #[serde(default)]
+    pub warmup: bool,
+    /// Before each session, regenerate derived artefacts (such as the
+    /// warm-up notes) whose inputs changed.
+    #[serde(default = "default_true")]
+    pub refresh: bool,
+    /// Let pending tasks gain priority the longer they sit untouched.
+    #[serde(default)]
+    pub priority_aging: bool,
+    /// Days untouched per priority level gained.
+    #[serde(default = "default_aging_days")]
+    pub aging_days: u32,
+    /// Most priority levels a task can gain by aging.
+    #[serde(default = "default_aging_cap")]
+    pub aging_cap: u32,
+    /// How pending tasks are ordered.
+    #[serde(default)]
+    pub ordering: TaskOrdering,
+    /// How sync settles a task whose source and local progress disagree,
+    /// when there is no terminal to ask.
+    #[serde(default)]
+    pub sync_conflicts: SyncConflictPolicy,
+    /// Kill the AI CLI once it has produced no output for this many times
+    /// `feedback.thinking_threshold_secs`. 0 disables stall detection.
+    #[serde(default)]
+    pub stall_factor: u32,
+    /// Times a stalled iteration is retried before it fails.
+    #[serde(default = "default_stall_retries")]
+    pub stall_retries: u32,
+    /// Tell the AI CLI its previous attempt appeared stuck when retrying.
+    #[serde(default = "default_true")]
+    pub stall_nudge: bool,
+}
+
+/// Order in which pending tasks are picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskOrdering {
+    /// Highest priority first (default).
+    #[default]
+    Priority,
+    /// Most likely to be completed autonomously first, then by priority.
+    Probability,
+}
+
+/// Strategy for picking the next pending task (`selection.strategy`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SelectionStrategy {
+    /// Highest priority first (default).
+    #[default]
+    Priority,
+    /// Oldest first, by when sync added or last changed the task.
+    Fifo,
+    /// Fewest estimated iterations, then fewest unmet criteria, first.
+    SmallestFirst,
+    /// Dependencies first, then tasks that unblock the most others.
+    DependencyAware,
+    /// One task from each epic in turn.
+    RoundRobinByEpic,
+}
+
+impl SelectionStrategy {
+    /// Every strategy, in the order they are documented.
+    pub const ALL: [Self; 5] = [
+        Self::Priority,
+        Self::Fifo,
+        Self::SmallestFirst,
+        Self::DependencyAware,
+        Self::RoundRobinByEpic,
+    ];
+
+    /// The config name of the strategy.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Priority => "priority",
+            Self::Fifo => "fifo",
+            Self::SmallestFirst => "smallest-first",
+            Self::DependencyAware => "dependency-aware",
+            Self::RoundRobinByEpic => "round-robin-by-epic",
+        }
+    }
+}
+
+/// How the loop picks its next task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectionConfig {
+    /// Strategy for ordering pending tasks.
+    #[serde(default)]
+    pub strategy: SelectionStrategy,
+    /// Finish the pending tasks in the epic worked on last before moving on.
+    #[serde(default)]
+    pub stick_to_epic: bool,
+}
+
+/// Address of the Ollama server when `ollama.host` is unset.
+pub const DEFAULT_OLLAMA_HOST: &str = "http://localhost:11434";
+
+/// Human review of tasks the loop completes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewConfig {
+    /// Hold tasks the AI marks passed as awaiting review until they are
+    /// approved with `afk review`.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// What the loop does after an iteration fails in a given way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryPolicy {
+    /// Carry on with the next iteration.
+    Retry,
+    /// Wait `retry.backoff_secs` (`limits.rate_limit_backoff_secs` for rate
+    /// limits), doubling with each failure in a row, then carry on.
+    Backoff,
+    /// Skip the task once it has failed this way `retry.skip_after` times
+    /// in a row.
+    Skip,
+    /// Stop the loop.
+    Stop,
+}
+
+impl RetryPolicy {
+    /// The policy's name in config files.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Retry => "retry",
+            Self::Backoff => "backoff",
+            Self::Skip => "skip",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+impl std::str::FromStr for RetryPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "retry" => Ok(Self::Retry),
+            "backoff" => Ok(Self::Backoff),
+            "skip" => Ok(Self::Skip),
+            "stop" => Ok(Self::Stop),
+            other => Err(format!("unknown retry policy: {other}")),
+        }
+    }
+}
+
+/// How the loop responds to each kind of failed iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// The AI CLI exited with an error.
+    #[serde(default = "default_retry_stop")]
+    pub cli_crash: RetryPolicy,
+    /// The AI CLI stalled and was stopped.
+    #[serde(default = "default_retry_stop")]
+    pub timeout: RetryPolicy,
+    /// Quality gates or criterion checks failed.
+    #[serde(default = "default_retry_retry")]
+    pub gates_failed: RetryPolicy,
+    /// The iteration changed no files and completed nothing.
+    #[serde(default = "default_retry_retry")]
+    pub no_changes: RetryPolicy,
+    /// The AI CLI reported a rate limit.
+    #[serde(default = "default_retry_backoff")]
+    pub rate_limit: RetryPolicy,
+    /// First wait for `backoff`, in seconds. Rate limits use
+    /// `limits.rate_limit_backoff_secs` instead.
+    #[serde(default = "default_backoff_secs")]
+    pub backoff_secs: u64,
+    /// Longest wait for `backoff`, in seconds.
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Failures of one kind in a row before `skip` skips the task.
+    #[serde(default = "default_skip_after")]
+    pub skip_after: u32,
+}
+
+fn default_retry_stop() -> RetryPolicy {
+    RetryPolicy::Stop
+}
+
+fn default_retry_retry() -> RetryPolicy {
+    RetryPolicy::Retry
+}
+
+fn default_retry_backoff() -> RetryPolicy {
+    RetryPolicy::Backoff
+}
+
+fn default_backoff_secs() -> u64 {
+    30
+}
+
+fn default_max_backoff_secs() -> u64 {
+    900
+}
+
+fn default_skip_after() -> u32 {
+    2
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            cli_crash: default_retry_stop(),
+            timeout: default_retry_stop(),
+            gates_failed: default_retry_retry(),
+            no_changes: default_retry_retry(),
+            rate_limit: default_retry_backoff(),
+            backoff_secs: default_backoff_secs(),
+            max_backoff_secs: default_max_backoff_secs(),
+            skip_after: default_skip_after(),
+        }
+    }
+}
+
+/// Settings for a local Ollama server, used when `ai_cli.backend` is
+/// `ollama`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OllamaConfig {
+    /// Address of the Ollama server.
+    #[serde(default = "default_ollama_host")]
+    pub host: String,
+    /// Model to run, such as `qwen2.5-coder:14b`. A model picked from
+    /// `ai_cli.models` takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Context window to ask the server for, in tokens.
+    #[serde(default = "default_context_size")]
+    pub context_size: u32,
+}
+
+fn default_ollama_host() -> String {
+    DEFAULT_OLLAMA_HOST.to_string()
+}
+
+fn default_context_size() -> u32 {
+    8192
+}
+
+impl Default for OllamaConfig {
+    fn default() -> Self {
+        Self {
+            host: default_ollama_host(),
+            model: None,
+            context_size: default_context_size(),
+        }
+    }
+}
+
+impl OllamaConfig {
+    /// The server's streaming chat endpoint.
+    pub fn chat_url(&self) -> String {
+        format!("{}/api/chat", self.host.trim_end_matches('/'))
+    }
+
+    /// The server's list of installed models.
+    pub fn tags_url(&self) -> String {
+        format!("{}/api/tags", self.host.trim_end_matches('/'))
+    }
+
+    /// Most tokens a prompt may take, leaving a quarter of the context
+    /// window for the reply.
+    pub fn prompt_budget(&self) -> u32 {
+        self.context_size / 4 * 3
+    }
+}
+
+/// How sync spots the same task listed by different sources.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DedupConfig {
+    /// Merge tasks with the same ID.
+    #[serde(default = "default_true")]
+    pub by_id: bool,
+    /// Merge tasks whose normalised titles are at least
+    /// `title_similarity` alike.
+    #[serde(default = "default_true")]
+    pub by_title: bool,
+    /// How alike two titles must be to match, from 0 to 1 (1 = the same
+    /// once case and punctuation are ignored).
+    #[serde(default = "default_title_similarity")]
+    pub title_similarity: f64,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            by_id: true,
+            by_title: true,
+            title_similarity: default_title_similarity(),
+        }
+    }
+}
+
+fn default_title_similarity() -> f64 {
+    0.9
+}
+
+/// How a sync conflict is settled.
+///
+/// A conflict is a task its source closed while local progress shows it
+/// failed, or a completed task its source has reopened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncConflictPolicy {
+    /// Keep the local state (default).
+    #[default]
+    KeepLocal,
+    /// Take the source's state.
+    TakeRemote,
+    /// Skip the task and flag it for a human to review.
+    Review,
+}
+
+fn default_aging_days() -> u32 {
+    7
+}
+
+fn default_aging_cap() -> u32 {
+    2
+}
+
+fn default_stall_retries() -> u32 {
+    1
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            warmup: false,
+            refresh: true,
+            priority_aging: false,
+            aging_days: default_aging_days(),
+            aging_cap: default_aging_cap(),
+            ordering: TaskOrdering::default(),
+            sync_conflicts: SyncConflictPolicy::default(),
+            stall_factor: 0,
+            stall_retries: default_stall_retries(),
+            stall_nudge: true,
+        }
+    }
+}
+
+/// Configuration for gate output artefacts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactsConfig {
+    /// Whether gate output is kept under `.afk/artifacts/`.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Maximum size of a single gate log in KiB; longer output keeps its tail.
+    #[serde(default = "default_artifact_max_kb")]
+    pub max_kb: u32,
+    /// Number of iterations whose artefacts are kept. 0 keeps all.
+    #[serde(default = "default_artifact_keep_iterations")]
+    pub keep_iterations: u32,
+}
+
+fn default_artifact_max_kb() -> u32 {
+    256
+}
+
+fn default_artifact_keep_iterations() -> u32 {
+    20
+}
+
+impl Default for ArtifactsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            max_kb: default_artifact_max_kb(),
+            keep_iterations: default_artifact_keep_iterations(),
+        }
+    }
+}
+
+/// Configuration for progress reports to stakeholders.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportingConfig {
+    /// GitHub issue or PR that gets a rolling run summary comment: a
+    /// number, "owner/repo#123", or an issue or PR URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracking_issue: Option<String>,
+    /// Iterations between summary updates.
+    #[serde(default = "default_report_every")]
+    pub every: u32,
+    /// What task `estimate`s count.
+    #[serde(default)]
+    pub estimate_unit: EstimateUnit,
+}
+
+/// What task `estimate`s count (`reporting.estimate_unit`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EstimateUnit {
+    /// Story points (default).
+    #[default]
+    Points,
+    /// Minutes of work.
+    Minutes,
+}
+
+impl EstimateUnit {
+    /// The config name of the unit.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Points => "points",
+            Self::Minutes => "minutes",
         }
-
-        args
     }
+}
 
-    /// Check if this CLI is configured for NDJSON streaming.
-    #[must_use]
-    pub fn uses_stream_json(&self) -> bool {
-        self.output_format == AiOutputFormat::StreamJson
+fn default_report_every() -> u32 {
+    5
+}
+
+impl Default for ReportingConfig {
+    fn default() -> Self {
+        Self {
+            tracking_issue: None,
+            every: default_report_every(),
+            estimate_unit: EstimateUnit::default(),
+        }
     }
+}
 
-    /// Detect which CLI format parser to use based on the command.
-    pub fn detect_cli_format(&self) -> crate::parser::CliFormat {
-        let cmd_lower = self.command.to_lowercase();
-        if cmd_lower.contains("cursor") {
-            crate::parser::CliFormat::Cursor
-        } else if cmd_lower.contains("claude") {
-            crate::parser::CliFormat::Claude
-        } else {
-            crate::parser::CliFormat::Auto
+/// Something in a run worth telling someone about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyEvent {
+    /// The loop stopped, for any reason.
+    RunEnd,
+    /// A task reached `limits.max_task_failures` failures.
+    MaxFailures,
+    /// Several iterations in a row made no commits.
+    Stalled,
+}
+
+impl NotifyEvent {
+    /// Every event, in the order they're documented.
+    pub const ALL: [NotifyEvent; 3] = [Self::RunEnd, Self::MaxFailures, Self::Stalled];
+
+    /// Name used in config and webhook payloads.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RunEnd => "run_end",
+            Self::MaxFailures => "max_failures",
+            Self::Stalled => "stalled",
         }
     }
+
+    /// Parse an event name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
 }
 
-/// Configuration for prompt generation.
+/// Payload shape for the notification webhook.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Pick from the URL: Slack and Discord webhooks get their format,
+    /// anything else gets JSON (default).
+    #[default]
+    Auto,
+    /// Slack incoming webhook (`{"text": ...}`).
+    Slack,
+    /// Discord webhook (`{"content": ...}`).
+    Discord,
+    /// Generic JSON with the event name, message, and details.
+    Json,
+}
+
+/// Configuration for notifications about the run.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct PromptConfig {
-    /// Template name or "default".
-    #[serde(default = "default_template")]
-    pub template: String,
-    /// Path to custom template file.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub custom_path: Option<String>,
-    /// Additional context files to include.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub context_files: Vec<String>,
-    /// Additional instructions to include.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub instructions: Vec<String>,
-    /// Whether this project has frontend/UI components requiring browser verification.
-    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
-    pub has_frontend: bool,
+pub struct NotificationsConfig {
+    /// URL to POST notifications to. Falls back to the `AFK_WEBHOOK_URL`
+    /// environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Payload shape for the webhook.
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// Also show a native desktop notification.
+    #[serde(default)]
+    pub desktop: bool,
+    /// Events to notify about.
+    #[serde(default = "default_notify_events")]
+    pub events: Vec<NotifyEvent>,
+    /// Iterations in a row without a commit that count as stalled (0 = off).
+    #[serde(default = "default_stall_iterations")]
+    pub stall_iterations: u32,
+    /// Message templates by event name, replacing the built-in messages.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, String>,
 }
 
-fn default_template() -> String {
-    "default".to_string()
+fn default_notify_events() -> Vec<NotifyEvent> {
+    NotifyEvent::ALL.to_vec()
 }
 
-impl Default for PromptConfig {
+fn default_stall_iterations() -> u32 {
+    3
+}
+
+impl Default for NotificationsConfig {
     fn default() -> Self {
         Self {
-            template: default_template(),
-            custom_path: None,
-            context_files: Vec::new(),
-            instructions: Vec::new(),
-            has_frontend: false,
+            webhook_url: None,
+            format: WebhookFormat::default(),
+            desktop: false,
+            events: default_notify_events(),
+            stall_iterations: default_stall_iterations(),
+            templates: HashMap::new(),
         }
     }
 }
 
-/// Configuration for git integration.
+/// Settings for `afk serve`, the HTTP API for watching and steering a run.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct GitConfig {
-    /// Whether to auto-commit after task completion.
-    #[serde(default = "default_true")]
-    pub auto_commit: bool,
-    /// Template for commit messages.
-    #[serde(default = "default_commit_template")]
-    pub commit_message_template: String,
+pub struct ServerConfig {
+    /// Address to listen on.
+    #[serde(default = "default_server_host")]
+    pub host: String,
+    /// Port to listen on.
+    #[serde(default = "default_server_port")]
+    pub port: u16,
+    /// Token clients must send as `Authorization: Bearer <token>`. Falls
+    /// back to the `AFK_SERVER_TOKEN` environment variable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
 }
 
-fn default_true() -> bool {
-    true
+fn default_server_host() -> String {
+    "127.0.0.1".to_string()
 }
 
-fn default_commit_template() -> String {
-    "afk: {task_id} - {message}".to_string()
+fn default_server_port() -> u16 {
+    7878
 }
 
-impl Default for GitConfig {
+impl Default for ServerConfig {
     fn default() -> Self {
         Self {
-            auto_commit: default_true(),
-            commit_message_template: default_commit_template(),
+            host: default_server_host(),
+            port: default_server_port(),
+            token: None,
         }
     }
 }
 
-/// Configuration for session archiving.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct ArchiveConfig {
-    /// Whether archiving is enabled.
-    #[serde(default = "default_true")]
-    pub enabled: bool,
-    /// Directory to store archives.
-    #[serde(default = "default_archive_directory")]
-    pub directory: String,
+/// Recurring runs for `afk scheduler start`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Cron expression for when to run, e.g. "0 2 * * *" for 02:00 daily.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// Iterations for each scheduled run (defaults to `limits.max_iterations`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<u32>,
 }
 
-fn default_archive_directory() -> String {
-    ".afk/archive".to_string()
+/// Shell commands run at points in the loop's lifecycle.
+///
+/// Each runs through the shell with `AFK_HOOK`, `AFK_ITERATION`,
+/// `AFK_TASK_ID` and, where they apply, `AFK_TASK_TITLE`, `AFK_SUCCESS`,
+/// `AFK_ERROR` and `AFK_FAILED_GATES` set. A failing hook is reported as a
+/// warning and never stops the loop.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run before each iteration, once its task is picked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_iteration: Option<String>,
+    /// Run after each iteration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_iteration: Option<String>,
+    /// Run for each task the loop completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_complete: Option<String>,
+    /// Run when an iteration records a task failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_task_fail: Option<String>,
+    /// Run when `afk verify` finds failing quality gates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_gate_fail: Option<String>,
 }
 
-impl Default for ArchiveConfig {
-    fn default() -> Self {
-        Self {
-            enabled: default_true(),
-            directory: default_archive_directory(),
-        }
+/// Action taken when an iteration exceeds a guardrail.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardrailAction {
+    /// Print a warning and continue.
+    Warn,
+    /// Put the iteration's completed stories back to pending and ask the AI
+    /// to split the work in the next prompt (default).
+    #[default]
+    Split,
+    /// Hold the iteration's completed stories for `afk review` and continue.
+    Review,
+    /// Hold the iteration's completed stories for `afk review` and stop the
+    /// loop. The change is left in place, including any commits the
+    /// iteration made.
+    Stop,
+}
+
+/// Configuration for per-iteration guardrails.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardrailsConfig {
+    /// Maximum lines changed (added + removed) per iteration. 0 disables the check.
+    #[serde(default)]
+    pub max_changed_lines: u32,
+    /// Maximum files changed per iteration. 0 disables the check.
+    #[serde(default)]
+    pub max_changed_files: u32,
+    /// What to do when a limit is exceeded.
+    #[serde(default)]
+    pub on_exceed: GuardrailAction,
+    /// Globs of paths iterations may not change (e.g. `.github/workflows/**`).
+    /// Changes to them are reverted after the iteration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_paths: Vec<String>,
+    /// Scan each iteration's changes for secrets, and add a `secrets` gate.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub secret_scan: bool,
+    /// Extra regexes the secret scan flags, on top of the built-in ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_patterns: Vec<String>,
+    /// Regexes for matches the secret scan should let through.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secret_allowlist: Vec<String>,
+}
+
+impl GuardrailsConfig {
+    /// The secrets gate's patterns, if the secret scan is on.
+    pub fn secret_scan_config(&self) -> Option<SecretScanConfig> {
+        self.secret_scan.then(|| SecretScanConfig {
+            patterns: self.secret_patterns.clone(),
+            allowlist: self.secret_allowlist.clone(),
+        })
     }
 }
 
+/// Configuration for dynamically loaded runner plugins.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Paths to plugin shared libraries (cdylibs), loaded in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<String>,
+}
+
 /// Feedback display mode.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -516,6 +1781,16 @@ pub struct FeedbackConfig {
     /// Seconds of inactivity before "stalled" state (no output for extended period).
     #[serde(default = "default_thinking_threshold_secs")]
     pub thinking_threshold_secs: u64,
+    /// Directory for per-iteration transcripts of the AI CLI's output
+    /// (empty disables them).
+    #[serde(default = "default_log_dir")]
+    pub log_dir: String,
+    /// Iteration transcripts to keep (0 keeps all).
+    #[serde(default = "default_log_keep")]
+    pub log_keep: u32,
+    /// Size in KB at which a transcript is rotated (0 never rotates).
+    #[serde(default = "default_log_max_kb")]
+    pub log_max_kb: u32,
 }
 
 fn default_refresh_rate() -> f64 {
@@ -534,6 +1809,18 @@ fn default_thinking_threshold_secs() -> u64 {
     10
 }
 
+fn default_log_dir() -> String {
+    LOGS_DIR.to_string()
+}
+
+fn default_log_keep() -> u32 {
+    50
+}
+
+fn default_log_max_kb() -> u32 {
+    10_240
+}
+
 impl Default for FeedbackConfig {
     fn default() -> Self {
         Self {
@@ -546,6 +1833,9 @@ impl Default for FeedbackConfig {
             max_output_lines: default_max_output_lines(),
             active_threshold_secs: default_active_threshold_secs(),
             thinking_threshold_secs: default_thinking_threshold_secs(),
+            log_dir: default_log_dir(),
+            log_keep: default_log_keep(),
+            log_max_kb: default_log_max_kb(),
         }
     }
 }
@@ -574,12 +1864,57 @@ pub struct AfkConfig {
     /// Git integration settings.
     #[serde(default)]
     pub git: GitConfig,
+    /// GitHub issue sync settings.
+    #[serde(default)]
+    pub github: GithubConfig,
     /// Archive settings.
     #[serde(default)]
     pub archive: ArchiveConfig,
     /// Feedback display settings.
     #[serde(default)]
     pub feedback: FeedbackConfig,
+    /// Gate output artefacts.
+    #[serde(default)]
+    pub artifacts: ArtifactsConfig,
+    /// Loop runner settings.
+    #[serde(default)]
+    pub runner: RunnerConfig,
+    /// Per-iteration guardrails.
+    #[serde(default)]
+    pub guardrails: GuardrailsConfig,
+    /// Runner plugins.
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    /// Progress reporting.
+    #[serde(default)]
+    pub reporting: ReportingConfig,
+    /// Lifecycle hook commands.
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Webhook and desktop notifications.
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+    /// HTTP API server.
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// Recurring scheduled runs.
+    #[serde(default)]
+    pub schedule: ScheduleConfig,
+    /// Task selection.
+    #[serde(default)]
+    pub selection: SelectionConfig,
+    /// Merging duplicate tasks during sync.
+    #[serde(default)]
+    pub dedup: DedupConfig,
+    /// Local Ollama server.
+    #[serde(default)]
+    pub ollama: OllamaConfig,
+    /// Human review of completed tasks.
+    #[serde(default)]
+    pub review: ReviewConfig,
+    /// Responses to each kind of failed iteration.
+    #[serde(default)]
+    pub retry: RetryConfig,
 }
 
 /// Error type for config operations.
@@ -591,6 +1926,47 @@ pub enum ConfigError {
     /// Error parsing the config file JSON.
     #[error("Failed to parse config JSON: {0}")]
     ParseError(#[from] serde_json::Error),
+    /// Error parsing or writing the config file YAML.
+    #[error("Failed to parse config YAML: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+    /// Error parsing the config file TOML.
+    #[error("Failed to parse config TOML: {0}")]
+    TomlError(#[from] toml::de::Error),
+    /// Error writing the config as TOML.
+    #[error("Failed to write config TOML: {0}")]
+    TomlWriteError(#[from] toml::ser::Error),
+    /// Refused to write because read-only mode is active.
+    #[error("Refusing to write config: read-only mode is active")]
+    ReadOnly,
+    /// The profile asked for has no file in `.afk/profiles/`.
+    #[error("Unknown profile '{0}' (see 'afk config profile list')")]
+    UnknownProfile(String),
+    /// A config value refers to an environment variable that isn't set.
+    #[error(
+        "Environment variable ${{{name}}} in {key} is not set (set it, or give a fallback: ${{{name}:-value}})"
+    )]
+    UndefinedVariable {
+        /// The variable.
+        name: String,
+        /// Dot-notation key of the value, e.g. `ai_cli.args[1]`.
+        key: String,
+    },
+    /// A config value has a `${` that isn't a valid variable reference.
+    #[error("Invalid variable reference {text} in {key} (write $${{ for a literal ${{)")]
+    InvalidExpansion {
+        /// The reference.
+        text: String,
+        /// Dot-notation key of the value.
+        key: String,
+    },
+    /// A source's `active_hours` isn't a "HH:MM-HH:MM" window.
+    #[error("Invalid active_hours '{value}' in sources[{index}] (expected HH:MM-HH:MM, e.g. 09:00-17:30)")]
+    InvalidActiveHours {
+        /// The value given.
+        value: String,
+        /// Position of the source in `sources`.
+        index: usize,
+    },
 }
 
 impl AfkConfig {
@@ -598,55 +1974,124 @@ impl AfkConfig {
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to config file. Defaults to `.afk/config.json` if None.
+    /// * `path` - Path to config file. Defaults to the project's config
+    ///   file (see [`AfkConfig::config_file`]) if None. The format follows
+    ///   the file extension.
     ///
     /// # Returns
     ///
     /// The loaded configuration, or defaults if the file doesn't exist.
+    /// Without a `path`, the active profile (see [`profile`]) is laid over
+    /// the project's config. `${VAR}` references are then expanded from
+    /// the environment (see [`expand`]).
     pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
-        let path = path
-            .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+        Self::load_base(path)?.resolve(path.is_none())
+    }
+
+    /// This config as [`AfkConfig::load`] would give it for the project:
+    /// with the active profile laid over it and `${VAR}` references
+    /// expanded.
+    ///
+    /// For commands that save a config from [`AfkConfig::load_base`] and
+    /// then run with it, so neither ends up in the saved file.
+    pub fn resolved(&self) -> Result<Self, ConfigError> {
+        self.resolve(true)
+    }
+
+    /// Lay the active profile over the config, if `profile`, and expand it.
+    fn resolve(&self, profile: bool) -> Result<Self, ConfigError> {
+        let mut value = serde_json::to_value(self)?;
+        if let (true, Some(name)) = (profile, active_profile()) {
+            let overrides = profile::load_profile(Path::new(PROFILES_DIR), &name)?;
+            profile::overlay(&mut value, overrides);
+        }
+        expand::expand_config(&mut value, &|name| std::env::var(name).ok())?;
+        let config: Self = serde_json::from_value(value)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load configuration like [`AfkConfig::load`], without any profile or
+    /// `${VAR}` expansion: the config as written, for commands that change
+    /// and save it.
+    pub fn load_base(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let path = path.map(PathBuf::from).unwrap_or_else(Self::config_file);
 
         if !path.exists() {
             return Ok(Self::default());
         }
 
         let contents = fs::read_to_string(&path)?;
-        let config: AfkConfig = serde_json::from_str(&contents)?;
+        let config: Self = ConfigFormat::from_path(&path).deserialize(&contents)?;
+        config.validate()?;
         Ok(config)
     }
 
+    /// Check values that parse as config but can't be used, so they are
+    /// reported rather than quietly ignored.
+    fn validate(&self) -> Result<(), ConfigError> {
+        for (index, source) in self.sources.iter().enumerate() {
+            if let Some(value) = &source.active_hours {
+                if crate::sources::schedule::ActiveHours::parse(value).is_none() {
+                    return Err(ConfigError::InvalidActiveHours {
+                        value: value.clone(),
+                        index,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Save configuration to a file.
     ///
     /// # Arguments
     ///
-    /// * `path` - Path to save to. Defaults to `.afk/config.json` if None.
+    /// * `path` - Path to save to. Defaults to the project's config file
+    ///   (see [`AfkConfig::config_file`]) if None. The format follows the
+    ///   file extension.
     ///
     /// Creates parent directories if they don't exist.
     pub fn save(&self, path: Option<&Path>) -> Result<(), ConfigError> {
-        let path = path
-            .map(PathBuf::from)
-            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE));
+        if is_read_only() {
+            return Err(ConfigError::ReadOnly);
+        }
+
+        let path = path.map(PathBuf::from).unwrap_or_else(Self::config_file);
 
         // Create parent directory if needed
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        let contents = serde_json::to_string_pretty(self)?;
+        let contents = ConfigFormat::from_path(&path).serialize(self)?;
         fs::write(&path, contents)?;
         Ok(())
     }
 
+    /// Most tokens a generated prompt may take: `prompt.max_tokens`, or 0
+    /// for no limit, kept within the context window when running against
+    /// Ollama.
+    pub fn prompt_budget(&self) -> u32 {
+        if self.ai_cli.backend != AiBackend::Ollama {
+            return self.prompt.max_tokens;
+        }
+        match self.prompt.max_tokens {
+            0 => self.ollama.prompt_budget(),
+            max => max.min(self.ollama.prompt_budget()),
+        }
+    }
+
     /// Get the path for the afk directory.
     pub fn afk_dir() -> PathBuf {
         PathBuf::from(AFK_DIR)
     }
 
-    /// Get the path for the config file.
+    /// Get the path for the config file: whichever of `.afk/config.json`,
+    /// `.afk/config.yaml`, `.afk/config.yml` or `.afk/config.toml` exists,
+    /// or `.afk/config.json` if none does.
     pub fn config_file() -> PathBuf {
-        PathBuf::from(CONFIG_FILE)
+        format::config_path()
     }
 
     /// Get the path for the progress file.
@@ -673,9 +2118,24 @@ impl AfkConfig {
             "ai_cli" => self.ai_cli.get_field(key),
             "prompt" => self.prompt.get_field(key),
             "git" => self.git.get_field(key),
+            "github" => self.github.get_field(key),
             "archive" => self.archive.get_field(key),
             "feedback" => self.feedback.get_field(key),
             "feedback_loops" => self.feedback_loops.get_field(key),
+            "artifacts" => self.artifacts.get_field(key),
+            "runner" => self.runner.get_field(key),
+            "guardrails" => self.guardrails.get_field(key),
+            "plugins" => self.plugins.get_field(key),
+            "reporting" => self.reporting.get_field(key),
+            "hooks" => self.hooks.get_field(key),
+            "notifications" => self.notifications.get_field(key),
+            "server" => self.server.get_field(key),
+            "schedule" => self.schedule.get_field(key),
+            "selection" => self.selection.get_field(key),
+            "dedup" => self.dedup.get_field(key),
+            "ollama" => self.ollama.get_field(key),
+            "review" => self.review.get_field(key),
+            "retry" => self.retry.get_field(key),
             _ => None,
         };
         value.ok_or_else(|| FieldError::UnknownKey(path.into()))
@@ -690,9 +2150,24 @@ impl AfkConfig {
             "ai_cli" => self.ai_cli.set_field(key, value),
             "prompt" => self.prompt.set_field(key, value),
             "git" => self.git.set_field(key, value),
+            "github" => self.github.set_field(key, value),
             "archive" => self.archive.set_field(key, value),
             "feedback" => self.feedback.set_field(key, value),
             "feedback_loops" => self.feedback_loops.set_field(key, value),
+            "artifacts" => self.artifacts.set_field(key, value),
+            "runner" => self.runner.set_field(key, value),
+            "guardrails" => self.guardrails.set_field(key, value),
+            "plugins" => self.plugins.set_field(key, value),
+            "reporting" => self.reporting.set_field(key, value),
+            "hooks" => self.hooks.set_field(key, value),
+            "notifications" => self.notifications.set_field(key, value),
+            "server" => self.server.set_field(key, value),
+            "schedule" => self.schedule.set_field(key, value),
+            "selection" => self.selection.set_field(key, value),
+            "dedup" => self.dedup.set_field(key, value),
+            "ollama" => self.ollama.set_field(key, value),
+            "review" => self.review.set_field(key, value),
+            "retry" => self.retry.set_field(key, value),
             _ => Err(FieldError::UnknownKey(path.into())),
         }
     }
@@ -705,9 +2180,24 @@ impl AfkConfig {
             "ai_cli",
             "prompt",
             "git",
+            "github",
             "archive",
             "feedback",
             "feedback_loops",
+            "artifacts",
+            "runner",
+            "guardrails",
+            "plugins",
+            "reporting",
+            "hooks",
+            "notifications",
+            "server",
+            "schedule",
+            "selection",
+            "dedup",
+            "ollama",
+            "review",
+            "retry",
             "sources",
         ]
     }
@@ -720,9 +2210,24 @@ impl AfkConfig {
             "ai_cli" => Some(AiCliConfig::field_names()),
             "prompt" => Some(PromptConfig::field_names()),
             "git" => Some(GitConfig::field_names()),
+            "github" => Some(GithubConfig::field_names()),
             "archive" => Some(ArchiveConfig::field_names()),
             "feedback" => Some(FeedbackConfig::field_names()),
             "feedback_loops" => Some(FeedbackLoopsConfig::field_names()),
+            "artifacts" => Some(ArtifactsConfig::field_names()),
+            "runner" => Some(RunnerConfig::field_names()),
+            "guardrails" => Some(GuardrailsConfig::field_names()),
+            "plugins" => Some(PluginsConfig::field_names()),
+            "reporting" => Some(ReportingConfig::field_names()),
+            "hooks" => Some(HooksConfig::field_names()),
+            "notifications" => Some(NotificationsConfig::field_names()),
+            "server" => Some(ServerConfig::field_names()),
+            "schedule" => Some(ScheduleConfig::field_names()),
+            "selection" => Some(SelectionConfig::field_names()),
+            "dedup" => Some(DedupConfig::field_names()),
+            "ollama" => Some(OllamaConfig::field_names()),
+            "review" => Some(ReviewConfig::field_names()),
+            "retry" => Some(RetryConfig::field_names()),
             _ => None,
         }
     }
@@ -767,6 +2272,10 @@ impl AfkConfig {
                 self.git = GitConfig::default();
                 Ok(())
             }
+            "github" => {
+                self.github = GithubConfig::default();
+                Ok(())
+            }
             "archive" => {
                 self.archive = ArchiveConfig::default();
                 Ok(())
@@ -779,6 +2288,62 @@ impl AfkConfig {
                 self.feedback_loops = FeedbackLoopsConfig::default();
                 Ok(())
             }
+            "artifacts" => {
+                self.artifacts = ArtifactsConfig::default();
+                Ok(())
+            }
+            "runner" => {
+                self.runner = RunnerConfig::default();
+                Ok(())
+            }
+            "guardrails" => {
+                self.guardrails = GuardrailsConfig::default();
+                Ok(())
+            }
+            "plugins" => {
+                self.plugins = PluginsConfig::default();
+                Ok(())
+            }
+            "reporting" => {
+                self.reporting = ReportingConfig::default();
+                Ok(())
+            }
+            "hooks" => {
+                self.hooks = HooksConfig::default();
+                Ok(())
+            }
+            "notifications" => {
+                self.notifications = NotificationsConfig::default();
+                Ok(())
+            }
+            "server" => {
+                self.server = ServerConfig::default();
+                Ok(())
+            }
+            "schedule" => {
+                self.schedule = ScheduleConfig::default();
+                Ok(())
+            }
+            "selection" => {
+                self.selection = SelectionConfig::default();
+                Ok(())
+            }
+            "dedup" => {
+                self.dedup = DedupConfig::default();
+                Ok(())
+            }
+            "ollama" => {
+                self.ollama = OllamaConfig::default();
+                Ok(())
+            }
+            "review" => {
+                self.review = ReviewConfig::default();
+                Ok(())
+            }
+            "retry" => {
+                self.retry = RetryConfig::default();
+                Ok(())
+            }
             _ => Err(FieldError::UnknownKey(section.into())),
         }
     }
@@ -795,9 +2360,24 @@ impl AfkConfig {
             "ai_cli" => default.ai_cli.get_field(key),
             "prompt" => default.prompt.get_field(key),
             "git" => default.git.get_field(key),
+            "github" => default.github.get_field(key),
             "archive" => default.archive.get_field(key),
             "feedback" => default.feedback.get_field(key),
             "feedback_loops" => default.feedback_loops.get_field(key),
+            "artifacts" => default.artifacts.get_field(key),
+            "runner" => default.runner.get_field(key),
+            "guardrails" => default.guardrails.get_field(key),
+            "plugins" => default.plugins.get_field(key),
+            "reporting" => default.reporting.get_field(key),
+            "hooks" => default.hooks.get_field(key),
+            "notifications" => default.notifications.get_field(key),
+            "server" => default.server.get_field(key),
+            "schedule" => default.schedule.get_field(key),
+            "selection" => default.selection.get_field(key),
+            "dedup" => default.dedup.get_field(key),
+            "ollama" => default.ollama.get_field(key),
+            "review" => default.review.get_field(key),
+            "retry" => default.retry.get_field(key),
             _ => return Err(FieldError::UnknownKey(path.into())),
         };
 
@@ -813,6 +2393,34 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_read_only_from_env() {
+        assert!(read_only_from_env(Some("1")));
+        assert!(read_only_from_env(Some("true")));
+        assert!(read_only_from_env(Some("YES")));
+        assert!(!read_only_from_env(Some("0")));
+        assert!(!read_only_from_env(Some("")));
+        assert!(!read_only_from_env(None));
+    }
+
+    #[test]
+    fn test_resolved_expands_a_copy() {
+        let mut config = AfkConfig::default();
+        config.ai_cli.args = vec![
+            "--model".to_string(),
+            "${AFK_RESOLVED_TEST_MODEL:-sonnet}".to_string(),
+        ];
+        let resolved = config.resolved().unwrap();
+        assert_eq!(resolved.ai_cli.args[1], "sonnet");
+        assert_eq!(config.ai_cli.args[1], "${AFK_RESOLVED_TEST_MODEL:-sonnet}");
+
+        config.ai_cli.args = vec!["${AFK_RESOLVED_TEST_UNSET}".to_string()];
+        assert!(matches!(
+            config.resolved(),
+            Err(ConfigError::UndefinedVariable { .. })
+        ));
+    }
+
     #[test]
     fn test_source_config_beads() {
         let source = SourceConfig::beads();
@@ -890,6 +2498,10 @@ mod tests {
             test: Some("pytest".to_string()),
             build: Some("pip wheel .".to_string()),
             custom,
+            wasm: HashMap::new(),
+            changed_only: false,
+            test_map: Vec::new(),
+            secret_scan: None,
         };
         assert_eq!(config.types, Some("mypy .".to_string()));
         assert_eq!(config.lint, Some("ruff check .".to_string()));
@@ -906,6 +2518,7 @@ mod tests {
         let config = LimitsConfig::default();
         assert_eq!(config.max_iterations, 200);
         assert_eq!(config.max_task_failures, 50);
+        assert_eq!(config.rate_limit_backoff_secs, 30);
         assert_eq!(config.timeout_minutes, 120);
         assert!(config.prevent_sleep);
     }
@@ -916,11 +2529,16 @@ mod tests {
             max_iterations: 5,
             max_task_failures: 1,
             timeout_minutes: 30,
+            max_task_minutes: 20,
+            max_session_minutes: 240,
+            rate_limit_backoff_secs: 60,
             prevent_sleep: false,
         };
         assert_eq!(config.max_iterations, 5);
         assert_eq!(config.max_task_failures, 1);
         assert_eq!(config.timeout_minutes, 30);
+        assert_eq!(config.max_task_minutes, 20);
+        assert_eq!(config.max_session_minutes, 240);
         assert!(!config.prevent_sleep);
     }
 
@@ -957,6 +2575,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         assert_eq!(config.command, "aider");
         assert_eq!(config.args, vec!["--message"]);
@@ -970,6 +2593,11 @@ mod tests {
             output_format: AiOutputFormat::StreamJson,
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         assert!(full_args.contains(&"--print".to_string()));
@@ -985,6 +2613,11 @@ mod tests {
             output_format: AiOutputFormat::StreamJson,
             stream_partial: true,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         assert!(full_args.contains(&"--stream-partial-output".to_string()));
@@ -998,6 +2631,11 @@ mod tests {
             output_format: AiOutputFormat::StreamJson,
             stream_partial: true,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         assert!(full_args.contains(&"--output-format".to_string()));
@@ -1013,6 +2651,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         assert_eq!(full_args, vec!["-p"]);
@@ -1053,6 +2696,42 @@ mod tests {
         assert!(config.models.contains(&selected.unwrap().to_string()));
     }
 
+    #[test]
+    fn test_ai_cli_select_model_seeded_is_deterministic() {
+        let config = AiCliConfig {
+            models: vec!["sonnet".to_string(), "opus".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(config.select_model_seeded(4), Some("sonnet"));
+        assert_eq!(config.select_model_seeded(7), Some("opus"));
+        assert_eq!(config.select_model_seeded(7), config.select_model_seeded(7));
+        assert!(AiCliConfig::default().select_model_seeded(7).is_none());
+    }
+
+    #[test]
+    fn test_ai_cli_escalation_model() {
+        let mut config = AiCliConfig {
+            escalation_models: vec!["sonnet".to_string(), "opus".to_string()],
+            ..Default::default()
+        };
+        let picks: Vec<Option<&str>> = (0..7).map(|f| config.escalation_model(f)).collect();
+        assert_eq!(
+            picks,
+            [
+                None,
+                None,
+                Some("sonnet"),
+                Some("sonnet"),
+                Some("opus"),
+                Some("opus"),
+                Some("opus")
+            ]
+        );
+        config.escalate_after = 0;
+        assert_eq!(config.escalation_model(9), None);
+        assert_eq!(AiCliConfig::default().escalation_model(9), None);
+    }
+
     #[test]
     fn test_ai_cli_full_args_with_model_includes_flag() {
         let config = AiCliConfig {
@@ -1061,6 +2740,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: vec!["opus".to_string()],
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         // Should include --model flag
@@ -1076,6 +2760,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: vec!["sonnet".to_string()],
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         let full_args = config.full_args();
         // -p should come first (from args), then --model
@@ -1092,6 +2781,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         // Explicitly pass a model
         let full_args = config.full_args_with_model(Some("gpt-4"));
@@ -1107,6 +2801,11 @@ mod tests {
             output_format: AiOutputFormat::Text,
             stream_partial: false,
             models: Vec::new(),
+            escalation_models: Vec::new(),
+            escalate_after: 2,
+            backend: AiBackend::Cli,
+            base_url: None,
+            api_key_env: None,
         };
         // No model means no --model flag
         let full_args = config.full_args_with_model(None);
@@ -1159,6 +2858,63 @@ mod tests {
         assert!(!text_config.uses_stream_json());
     }
 
+    #[test]
+    fn test_ai_cli_http_backend() {
+        use crate::parser::CliFormat;
+
+        let mut config = AfkConfig::default();
+        assert!(!serde_json::to_string(&config.ai_cli)
+            .unwrap()
+            .contains("backend"));
+        config.set_by_path("ai_cli.backend", "http").unwrap();
+        config
+            .set_by_path("ai_cli.base_url", "http://localhost:11434/v1/")
+            .unwrap();
+        assert_eq!(config.ai_cli.backend, AiBackend::Http);
+        assert_eq!(
+            config.ai_cli.chat_completions_url(),
+            "http://localhost:11434/v1/chat/completions"
+        );
+        assert_eq!(config.ai_cli.detect_cli_format(), CliFormat::OpenAi);
+        assert!(config.ai_cli.uses_stream_json());
+        assert!(config.set_by_path("ai_cli.backend", "grpc").is_err());
+
+        config.ai_cli.base_url = None;
+        assert_eq!(
+            config.ai_cli.chat_completions_url(),
+            "https://api.openai.com/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_ollama_backend() {
+        use crate::parser::CliFormat;
+
+        let mut config = AfkConfig::default();
+        config.set_by_path("ai_cli.backend", "ollama").unwrap();
+        config
+            .set_by_path("ollama.host", "http://gpu-box:11434/")
+            .unwrap();
+        config
+            .set_by_path("ollama.model", "qwen2.5-coder:14b")
+            .unwrap();
+        assert_eq!(config.ollama.chat_url(), "http://gpu-box:11434/api/chat");
+        assert_eq!(config.ollama.model.as_deref(), Some("qwen2.5-coder:14b"));
+        assert_eq!(config.ai_cli.detect_cli_format(), CliFormat::Ollama);
+        assert!(config.ai_cli.uses_stream_json());
+        assert!(config.set_by_path("ollama.context_size", "0").is_err());
+
+        // The prompt leaves a quarter of the context window for the reply
+        config.set_by_path("ollama.context_size", "4096").unwrap();
+        assert_eq!(config.prompt_budget(), 3072);
+        config.prompt.max_tokens = 2000;
+        assert_eq!(config.prompt_budget(), 2000);
+        config.prompt.max_tokens = 9000;
+        assert_eq!(config.prompt_budget(), 3072);
+        config.ai_cli.backend = AiBackend::Cli;
+        assert_eq!(config.prompt_budget(), 9000);
+    }
+
     #[test]
     fn test_ai_cli_detect_cli_format() {
         use crate::parser::CliFormat;
@@ -1175,10 +2931,24 @@ mod tests {
         };
         assert_eq!(claude_config.detect_cli_format(), CliFormat::Claude);
 
-        let unknown_config = AiCliConfig {
+        let gemini_config = AiCliConfig {
+            command: "gemini".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(gemini_config.detect_cli_format(), CliFormat::Gemini);
+
+        let aider_config = AiCliConfig {
             command: "aider".to_string(),
             ..Default::default()
         };
+        assert_eq!(aider_config.detect_cli_format(), CliFormat::Aider);
+        // Aider gets no --output-format, which it doesn't have
+        assert!(aider_config.get_output_format_args().is_empty());
+
+        let unknown_config = AiCliConfig {
+            command: "amp".to_string(),
+            ..Default::default()
+        };
         assert_eq!(unknown_config.detect_cli_format(), CliFormat::Auto);
     }
 
@@ -1203,8 +2973,12 @@ mod tests {
                 "Use British English".to_string(),
             ],
             has_frontend: true,
+            continuation_mode: ContinuationMode::Condensed,
+            max_tokens: 32000,
         };
         assert_eq!(config.template, "minimal");
+        assert_eq!(config.max_tokens, 32000);
+        assert_eq!(config.continuation_mode, ContinuationMode::Condensed);
         assert_eq!(config.custom_path, Some(".afk/prompt.jinja2".to_string()));
         assert_eq!(config.context_files, vec!["AGENTS.md", "README.md"]);
         assert_eq!(
@@ -1226,11 +3000,57 @@ mod tests {
         let config = GitConfig {
             auto_commit: true,
             commit_message_template: "[{task_id}] {message}".to_string(),
+            author_name: None,
+            author_email: None,
+            update_submodules: false,
+            auto_branch: true,
+            branch_prefix: "task/".to_string(),
+            auto_merge: false,
+            open_pr: false,
+            pr_title_template: None,
+            pr_body_template: None,
+            revert_on_gate_failure: true,
         };
         assert!(config.auto_commit);
+        assert!(config.revert_on_gate_failure);
         assert_eq!(config.commit_message_template, "[{task_id}] {message}");
     }
 
+    #[test]
+    fn test_git_config_identity_env() {
+        let mut config = GitConfig::default();
+        assert!(config.commit_identity().is_none());
+        assert!(config.identity_env().is_empty());
+
+        config.author_email = Some("afk@company.dev".to_string());
+        assert_eq!(
+            config.identity_env(),
+            vec![
+                ("GIT_AUTHOR_EMAIL", "afk@company.dev".to_string()),
+                ("GIT_COMMITTER_EMAIL", "afk@company.dev".to_string()),
+            ]
+        );
+
+        config.author_name = Some("afk-bot".to_string());
+        let env = config.identity_env();
+        assert_eq!(env.len(), 4);
+        assert!(env.contains(&("GIT_AUTHOR_NAME", "afk-bot".to_string())));
+        assert!(env.contains(&("GIT_COMMITTER_NAME", "afk-bot".to_string())));
+    }
+
+    #[test]
+    fn test_runner_sync_conflicts_field() {
+        let mut config = RunnerConfig::default();
+        assert_eq!(config.sync_conflicts, SyncConflictPolicy::KeepLocal);
+        config.set_field("sync_conflicts", "Take-Remote").unwrap();
+        assert_eq!(config.sync_conflicts, SyncConflictPolicy::TakeRemote);
+        assert_eq!(config.get_field("sync_conflicts").unwrap(), "take-remote");
+        assert!(config.set_field("sync_conflicts", "remote").is_err());
+
+        let parsed: RunnerConfig = serde_json::from_str(r#"{"sync_conflicts": "review"}"#).unwrap();
+        assert_eq!(parsed.sync_conflicts, SyncConflictPolicy::Review);
+    }
+
     #[test]
     fn test_archive_config_defaults() {
         let config = ArchiveConfig::default();
@@ -1351,6 +3171,32 @@ mod tests {
         assert_eq!(config.limits.max_iterations, 10);
     }
 
+    #[test]
+    fn test_afk_config_load_rejects_bad_active_hours() {
+        let temp = TempDir::new().unwrap();
+        let config_path = temp.path().join("config.json");
+        fs::write(
+            &config_path,
+            r#"{"sources": [{"type": "beads", "active_hours": "18:00-08:00"},
+                {"type": "github", "active_hours": "9am-5pm"}]}"#,
+        )
+        .unwrap();
+
+        let err = AfkConfig::load(Some(&config_path)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid active_hours '9am-5pm' in sources[1] (expected HH:MM-HH:MM, e.g. 09:00-17:30)"
+        );
+        assert!(AfkConfig::load_base(Some(&config_path)).is_err());
+
+        fs::write(
+            &config_path,
+            r#"{"sources": [{"type": "github", "active_hours": "09:00-17:00"}]}"#,
+        )
+        .unwrap();
+        assert!(AfkConfig::load(Some(&config_path)).is_ok());
+    }
+
     #[test]
     fn test_afk_config_save_creates_directory() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,294 @@
+//! Named config profiles in `.afk/profiles/`.
+//!
+//! A profile is a partial config (`overnight.json`, `quick.yaml`, ...)
+//! laid over the base config: objects merge key by key, anything else
+//! (numbers, strings, lists) replaces the base value. The active profile
+//! comes from `afk go --profile`, then `AFK_PROFILE`, then the name saved
+//! in `.afk/profile` by `afk config profile use`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json::{Map, Value};
+
+use super::{AfkConfig, ConfigError, ConfigFormat, AFK_DIR};
+
+/// Directory of profile files.
+pub const PROFILES_DIR: &str = ".afk/profiles";
+/// Name of the profile `afk config profile use` made the default.
+pub const ACTIVE_PROFILE_FILE: &str = ".afk/profile";
+/// Environment variable naming the profile to use.
+pub const PROFILE_ENV: &str = "AFK_PROFILE";
+
+/// Profile chosen for this process with `--profile`.
+static PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Use a profile for the rest of this process, ahead of `AFK_PROFILE` and
+/// `.afk/profile`.
+pub fn set_profile(name: Option<&str>) {
+    *PROFILE.lock().unwrap_or_else(|e| e.into_inner()) = name.map(String::from);
+}
+
+/// The profile in effect, if any.
+pub fn active_profile() -> Option<String> {
+    let chosen = PROFILE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    chosen
+        .or_else(|| std::env::var(PROFILE_ENV).ok())
+        .or_else(|| saved_profile(Path::new(AFK_DIR)))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// The profile saved in `afk_dir` by `afk config profile use`.
+pub fn saved_profile(afk_dir: &Path) -> Option<String> {
+    let name = fs::read_to_string(afk_dir.join("profile")).ok()?;
+    Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+/// Save `name` as the default profile in `afk_dir`, or clear it.
+pub fn save_active_profile(afk_dir: &Path, name: Option<&str>) -> Result<(), ConfigError> {
+    if super::is_read_only() {
+        return Err(ConfigError::ReadOnly);
+    }
+    let path = afk_dir.join("profile");
+    match name {
+        Some(name) => {
+            fs::create_dir_all(afk_dir)?;
+            fs::write(path, format!("{name}\n"))?;
+        }
+        None if path.exists() => fs::remove_file(path)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// The file for profile `name` in `profiles_dir`, in whichever format it
+/// was written.
+pub fn find_profile(profiles_dir: &Path, name: &str) -> Option<PathBuf> {
+    ["json", "yaml", "yml", "toml"]
+        .iter()
+        .map(|ext| profiles_dir.join(format!("{name}.{ext}")))
+        .find(|path| path.is_file())
+}
+
+/// Names of the profiles in `profiles_dir`, sorted.
+pub fn list_profiles(profiles_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(profiles_dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext, "json" | "yaml" | "yml" | "toml"))
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Whether `name` can be used as a profile file name.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Read the overrides in profile `name` from `profiles_dir`.
+pub fn load_profile(profiles_dir: &Path, name: &str) -> Result<Value, ConfigError> {
+    let path = find_profile(profiles_dir, name)
+        .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))?;
+    let contents = fs::read_to_string(&path)?;
+    ConfigFormat::from_path(&path).deserialize_value(&contents)
+}
+
+/// Lay the `overrides` of a profile over `base`.
+pub fn apply(base: &AfkConfig, overrides: Value) -> Result<AfkConfig, ConfigError> {
+    let mut value = serde_json::to_value(base)?;
+    overlay(&mut value, overrides);
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Merge `overrides` into `base`: objects key by key, anything else
+/// replaced.
+pub fn overlay(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// The overrides in a profile as dot-notation keys and values, sorted.
+pub fn flatten(overrides: &Value) -> Vec<(String, String)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    walk(&path, value, out);
+                }
+            }
+            Value::String(s) => out.push((prefix.to_string(), s.clone())),
+            other => out.push((prefix.to_string(), other.to_string())),
+        }
+    }
+    let mut out = Vec::new();
+    walk("", overrides, &mut out);
+    out.sort();
+    out
+}
+
+/// `value` as an object, replacing it if it is anything else.
+fn object(value: &mut Value) -> &mut Map<String, Value> {
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!("just made an object"),
+    }
+}
+
+/// Set dot-notation `path` in `overrides` to the value it has in `config`,
+/// so a profile stores just the keys it changes, with their types.
+pub fn set_override(
+    overrides: &mut Value,
+    config: &AfkConfig,
+    path: &str,
+) -> Result<(), ConfigError> {
+    let full = serde_json::to_value(config)?;
+    let keys: Vec<&str> = path.split('.').collect();
+    let value = keys
+        .iter()
+        .try_fold(&full, |value, key| value.get(key))
+        .cloned()
+        .unwrap_or(Value::Null);
+    let (last, parents) = keys.split_last().expect("split always yields a key");
+    let mut target = overrides;
+    for key in parents {
+        target = object(target)
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    object(target).insert(last.to_string(), value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_overlay_merges_objects_and_replaces_the_rest() {
+        let mut base = json!({
+            "limits": {"max_iterations": 5, "timeout_minutes": 60},
+            "sources": [{"type": "beads"}]
+        });
+        overlay(
+            &mut base,
+            json!({"limits": {"max_iterations": 50}, "sources": [], "git": {"auto_commit": false}}),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "limits": {"max_iterations": 50, "timeout_minutes": 60},
+                "sources": [],
+                "git": {"auto_commit": false}
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_profile_to_config() {
+        let base = AfkConfig::default();
+        let config = apply(&base, json!({"limits": {"max_iterations": 99}})).unwrap();
+        assert_eq!(config.limits.max_iterations, 99);
+        assert_eq!(config.limits.timeout_minutes, base.limits.timeout_minutes);
+        assert!(apply(&base, json!({"limits": {"max_iterations": "lots"}})).is_err());
+    }
+
+    #[test]
+    fn test_profile_files() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("profiles");
+        assert!(list_profiles(&dir).is_empty());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("overnight.json"),
+            r#"{"limits": {"max_iterations": 40}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("quick.yaml"), "limits:\n  max_iterations: 3\n").unwrap();
+        fs::write(dir.join("notes.txt"), "not a profile").unwrap();
+
+        assert_eq!(list_profiles(&dir), ["overnight", "quick"]);
+        assert_eq!(
+            load_profile(&dir, "quick").unwrap(),
+            json!({"limits": {"max_iterations": 3}})
+        );
+        assert!(matches!(
+            load_profile(&dir, "missing"),
+            Err(ConfigError::UnknownProfile(name)) if name == "missing"
+        ));
+    }
+
+    #[test]
+    fn test_saved_profile() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(saved_profile(temp.path()), None);
+        save_active_profile(temp.path(), Some("overnight")).unwrap();
+        assert_eq!(saved_profile(temp.path()).as_deref(), Some("overnight"));
+        save_active_profile(temp.path(), None).unwrap();
+        assert_eq!(saved_profile(temp.path()), None);
+    }
+
+    #[test]
+    fn test_set_override_and_flatten() {
+        let mut config = AfkConfig::default();
+        config.set_by_path("limits.max_iterations", "40").unwrap();
+        config.set_by_path("git.auto_commit", "false").unwrap();
+        let mut overrides = json!({});
+        set_override(&mut overrides, &config, "limits.max_iterations").unwrap();
+        set_override(&mut overrides, &config, "git.auto_commit").unwrap();
+        assert_eq!(
+            overrides,
+            json!({"limits": {"max_iterations": 40}, "git": {"auto_commit": false}})
+        );
+        assert_eq!(
+            flatten(&overrides),
+            [
+                ("git.auto_commit".to_string(), "false".to_string()),
+                ("limits.max_iterations".to_string(), "40".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("overnight"));
+        assert!(is_valid_name("ci-fast_2"));
+        assert!(!is_valid_name(""));
+        assert!(!is_valid_name("../config"));
+    }
+}
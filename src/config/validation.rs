@@ -5,8 +5,13 @@
 
 use super::field::{format_optional, format_vec, parse_bool, parse_vec, ConfigField, FieldError};
 use super::{
-    AiCliConfig, AiOutputFormat, ArchiveConfig, FeedbackConfig, FeedbackLoopsConfig, FeedbackMode,
-    GitConfig, LimitsConfig, OutputConfig, OutputMode, PromptConfig,
+    AiBackend, AiCliConfig, AiOutputFormat, ArchiveConfig, ArtifactsConfig, ContinuationMode,
+    DedupConfig, EstimateUnit, FeedbackConfig, FeedbackLoopsConfig, FeedbackMode, GitConfig,
+    GithubConfig, GuardrailAction, GuardrailsConfig, HooksConfig, LimitsConfig,
+    NotificationsConfig, NotifyEvent, OllamaConfig, OutputConfig, OutputMode, PluginsConfig,
+    PromptConfig, ReportingConfig, RetryConfig, RetryPolicy, ReviewConfig, RunnerConfig,
+    ScheduleConfig, SelectionConfig, SelectionStrategy, ServerConfig, SyncConflictPolicy,
+    TaskOrdering, WebhookFormat,
 };
 
 impl ConfigField for LimitsConfig {
@@ -15,6 +20,9 @@ impl ConfigField for LimitsConfig {
             "max_iterations" => Some(self.max_iterations.to_string()),
             "max_task_failures" => Some(self.max_task_failures.to_string()),
             "timeout_minutes" => Some(self.timeout_minutes.to_string()),
+            "max_task_minutes" => Some(self.max_task_minutes.to_string()),
+            "max_session_minutes" => Some(self.max_session_minutes.to_string()),
+            "rate_limit_backoff_secs" => Some(self.rate_limit_backoff_secs.to_string()),
             "prevent_sleep" => Some(self.prevent_sleep.to_string()),
             _ => None,
         }
@@ -43,6 +51,28 @@ impl ConfigField for LimitsConfig {
                 })?;
                 Ok(())
             }
+            "max_task_minutes" => {
+                self.max_task_minutes = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "max_session_minutes" => {
+                self.max_session_minutes = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "rate_limit_backoff_secs" => {
+                self.rate_limit_backoff_secs =
+                    value.parse().map_err(|_| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "non-negative integer".into(),
+                    })?;
+                Ok(())
+            }
             "prevent_sleep" => {
                 self.prevent_sleep = parse_bool(value).map_err(|_| FieldError::InvalidValue {
                     key: key.into(),
@@ -59,6 +89,9 @@ impl ConfigField for LimitsConfig {
             "max_iterations",
             "max_task_failures",
             "timeout_minutes",
+            "max_task_minutes",
+            "max_session_minutes",
+            "rate_limit_backoff_secs",
             "prevent_sleep",
         ]
     }
@@ -132,6 +165,18 @@ impl ConfigField for AiCliConfig {
             ),
             "stream_partial" => Some(self.stream_partial.to_string()),
             "models" => Some(format_vec(&self.models)),
+            "escalation_models" => Some(format_vec(&self.escalation_models)),
+            "escalate_after" => Some(self.escalate_after.to_string()),
+            "backend" => Some(
+                match self.backend {
+                    AiBackend::Cli => "cli",
+                    AiBackend::Http => "http",
+                    AiBackend::Ollama => "ollama",
+                }
+                .to_string(),
+            ),
+            "base_url" => Some(format_optional(&self.base_url)),
+            "api_key_env" => Some(format_optional(&self.api_key_env)),
             _ => None,
         }
     }
@@ -171,6 +216,47 @@ impl ConfigField for AiCliConfig {
                 self.models = parse_vec(value);
                 Ok(())
             }
+            "escalation_models" => {
+                self.escalation_models = parse_vec(value);
+                Ok(())
+            }
+            "escalate_after" => {
+                self.escalate_after = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "backend" => {
+                self.backend = match value.to_lowercase().as_str() {
+                    "cli" => AiBackend::Cli,
+                    "http" => AiBackend::Http,
+                    "ollama" => AiBackend::Ollama,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "cli, http or ollama".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "base_url" => {
+                self.base_url = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "api_key_env" => {
+                self.api_key_env = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
             _ => Err(FieldError::UnknownKey(key.into())),
         }
     }
@@ -182,6 +268,11 @@ impl ConfigField for AiCliConfig {
             "output_format",
             "stream_partial",
             "models",
+            "escalation_models",
+            "escalate_after",
+            "backend",
+            "base_url",
+            "api_key_env",
         ]
     }
 
@@ -198,6 +289,14 @@ impl ConfigField for PromptConfig {
             "context_files" => Some(format_vec(&self.context_files)),
             "instructions" => Some(format_vec(&self.instructions)),
             "has_frontend" => Some(self.has_frontend.to_string()),
+            "continuation_mode" => Some(
+                match self.continuation_mode {
+                    ContinuationMode::Full => "full",
+                    ContinuationMode::Condensed => "condensed",
+                }
+                .to_string(),
+            ),
+            "max_tokens" => Some(self.max_tokens.to_string()),
             _ => None,
         }
     }
@@ -231,6 +330,26 @@ impl ConfigField for PromptConfig {
                 })?;
                 Ok(())
             }
+            "continuation_mode" => {
+                self.continuation_mode = match value.to_lowercase().as_str() {
+                    "full" => ContinuationMode::Full,
+                    "condensed" => ContinuationMode::Condensed,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "full or condensed".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "max_tokens" => {
+                self.max_tokens = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
             _ => Err(FieldError::UnknownKey(key.into())),
         }
     }
@@ -242,6 +361,8 @@ impl ConfigField for PromptConfig {
             "context_files",
             "instructions",
             "has_frontend",
+            "continuation_mode",
+            "max_tokens",
         ]
     }
 
@@ -255,6 +376,16 @@ impl ConfigField for GitConfig {
         match key {
             "auto_commit" => Some(self.auto_commit.to_string()),
             "commit_message_template" => Some(self.commit_message_template.clone()),
+            "author_name" => Some(format_optional(&self.author_name)),
+            "author_email" => Some(format_optional(&self.author_email)),
+            "update_submodules" => Some(self.update_submodules.to_string()),
+            "auto_branch" => Some(self.auto_branch.to_string()),
+            "branch_prefix" => Some(self.branch_prefix.clone()),
+            "auto_merge" => Some(self.auto_merge.to_string()),
+            "open_pr" => Some(self.open_pr.to_string()),
+            "revert_on_gate_failure" => Some(self.revert_on_gate_failure.to_string()),
+            "pr_title_template" => Some(format_optional(&self.pr_title_template)),
+            "pr_body_template" => Some(format_optional(&self.pr_body_template)),
             _ => None,
         }
     }
@@ -272,12 +403,98 @@ impl ConfigField for GitConfig {
                 self.commit_message_template = value.to_string();
                 Ok(())
             }
+            "author_name" => {
+                self.author_name = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "author_email" => {
+                self.author_email = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "update_submodules" => {
+                self.update_submodules =
+                    parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "true or false".into(),
+                    })?;
+                Ok(())
+            }
+            "auto_branch" => {
+                self.auto_branch = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "branch_prefix" => {
+                self.branch_prefix = value.to_string();
+                Ok(())
+            }
+            "auto_merge" => {
+                self.auto_merge = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "open_pr" => {
+                self.open_pr = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "revert_on_gate_failure" => {
+                self.revert_on_gate_failure =
+                    parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "true or false".into(),
+                    })?;
+                Ok(())
+            }
+            "pr_title_template" => {
+                self.pr_title_template = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "pr_body_template" => {
+                self.pr_body_template = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
             _ => Err(FieldError::UnknownKey(key.into())),
         }
     }
 
     fn field_names() -> &'static [&'static str] {
-        &["auto_commit", "commit_message_template"]
+        &[
+            "auto_commit",
+            "commit_message_template",
+            "author_name",
+            "author_email",
+            "update_submodules",
+            "auto_branch",
+            "branch_prefix",
+            "auto_merge",
+            "open_pr",
+            "pr_title_template",
+            "pr_body_template",
+            "revert_on_gate_failure",
+        ]
     }
 
     fn section_name() -> &'static str {
@@ -285,6 +502,36 @@ impl ConfigField for GitConfig {
     }
 }
 
+impl ConfigField for GithubConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "sync_back" => Some(self.sync_back.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "sync_back" => {
+                self.sync_back = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["sync_back"]
+    }
+
+    fn section_name() -> &'static str {
+        "github"
+    }
+}
+
 impl ConfigField for ArchiveConfig {
     fn get_field(&self, key: &str) -> Option<String> {
         match key {
@@ -339,6 +586,9 @@ impl ConfigField for FeedbackConfig {
             "max_output_lines" => Some(self.max_output_lines.to_string()),
             "active_threshold_secs" => Some(self.active_threshold_secs.to_string()),
             "thinking_threshold_secs" => Some(self.thinking_threshold_secs.to_string()),
+            "log_dir" => Some(self.log_dir.clone()),
+            "log_keep" => Some(self.log_keep.to_string()),
+            "log_max_kb" => Some(self.log_max_kb.to_string()),
             _ => None,
         }
     }
@@ -417,6 +667,24 @@ impl ConfigField for FeedbackConfig {
                     })?;
                 Ok(())
             }
+            "log_dir" => {
+                self.log_dir = value.to_string();
+                Ok(())
+            }
+            "log_keep" => {
+                self.log_keep = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "log_max_kb" => {
+                self.log_max_kb = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
             _ => Err(FieldError::UnknownKey(key.into())),
         }
     }
@@ -432,6 +700,9 @@ impl ConfigField for FeedbackConfig {
             "max_output_lines",
             "active_threshold_secs",
             "thinking_threshold_secs",
+            "log_dir",
+            "log_keep",
+            "log_max_kb",
         ]
     }
 
@@ -447,11 +718,20 @@ impl ConfigField for FeedbackLoopsConfig {
             "lint" => Some(format_optional(&self.lint)),
             "test" => Some(format_optional(&self.test)),
             "build" => Some(format_optional(&self.build)),
+            "changed_only" => Some(self.changed_only.to_string()),
             _ => None,
         }
     }
 
     fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        if key == "changed_only" {
+            self.changed_only = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                key: key.into(),
+                expected: "true or false".into(),
+            })?;
+            return Ok(());
+        }
+
         let opt_value = if value.is_empty() || value == "(not set)" {
             None
         } else {
@@ -480,10 +760,872 @@ impl ConfigField for FeedbackLoopsConfig {
     }
 
     fn field_names() -> &'static [&'static str] {
-        &["types", "lint", "test", "build"]
+        &["types", "lint", "test", "build", "changed_only"]
     }
 
     fn section_name() -> &'static str {
         "feedback_loops"
     }
 }
+
+impl ConfigField for ArtifactsConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "enabled" => Some(self.enabled.to_string()),
+            "max_kb" => Some(self.max_kb.to_string()),
+            "keep_iterations" => Some(self.keep_iterations.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "enabled" => {
+                self.enabled = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "max_kb" => {
+                let v: u32 = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "positive integer".into(),
+                })?;
+                if v == 0 {
+                    return Err(FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    });
+                }
+                self.max_kb = v;
+                Ok(())
+            }
+            "keep_iterations" => {
+                self.keep_iterations = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["enabled", "max_kb", "keep_iterations"]
+    }
+
+    fn section_name() -> &'static str {
+        "artifacts"
+    }
+}
+
+impl ConfigField for RunnerConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "warmup" => Some(self.warmup.to_string()),
+            "refresh" => Some(self.refresh.to_string()),
+            "priority_aging" => Some(self.priority_aging.to_string()),
+            "aging_days" => Some(self.aging_days.to_string()),
+            "aging_cap" => Some(self.aging_cap.to_string()),
+            "ordering" => Some(
+                match self.ordering {
+                    TaskOrdering::Priority => "priority",
+                    TaskOrdering::Probability => "probability",
+                }
+                .to_string(),
+            ),
+            "sync_conflicts" => Some(
+                match self.sync_conflicts {
+                    SyncConflictPolicy::KeepLocal => "keep-local",
+                    SyncConflictPolicy::TakeRemote => "take-remote",
+                    SyncConflictPolicy::Review => "review",
+                }
+                .to_string(),
+            ),
+            "stall_factor" => Some(self.stall_factor.to_string()),
+            "stall_retries" => Some(self.stall_retries.to_string()),
+            "stall_nudge" => Some(self.stall_nudge.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "warmup" => {
+                self.warmup = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "refresh" => {
+                self.refresh = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "priority_aging" => {
+                self.priority_aging = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "aging_days" => {
+                let v: u32 = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "positive integer".into(),
+                })?;
+                if v == 0 {
+                    return Err(FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    });
+                }
+                self.aging_days = v;
+                Ok(())
+            }
+            "aging_cap" => {
+                self.aging_cap = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "ordering" => {
+                self.ordering = match value.to_lowercase().as_str() {
+                    "priority" => TaskOrdering::Priority,
+                    "probability" => TaskOrdering::Probability,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "priority or probability".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "sync_conflicts" => {
+                self.sync_conflicts = match value.to_lowercase().as_str() {
+                    "keep-local" => SyncConflictPolicy::KeepLocal,
+                    "take-remote" => SyncConflictPolicy::TakeRemote,
+                    "review" => SyncConflictPolicy::Review,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "keep-local, take-remote or review".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "stall_factor" => {
+                self.stall_factor = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "stall_retries" => {
+                self.stall_retries = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            "stall_nudge" => {
+                self.stall_nudge = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "warmup",
+            "refresh",
+            "priority_aging",
+            "aging_days",
+            "aging_cap",
+            "ordering",
+            "sync_conflicts",
+            "stall_factor",
+            "stall_retries",
+            "stall_nudge",
+        ]
+    }
+
+    fn section_name() -> &'static str {
+        "runner"
+    }
+}
+
+impl ConfigField for GuardrailsConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "max_changed_lines" => Some(self.max_changed_lines.to_string()),
+            "max_changed_files" => Some(self.max_changed_files.to_string()),
+            "on_exceed" => Some(
+                match self.on_exceed {
+                    GuardrailAction::Warn => "warn",
+                    GuardrailAction::Split => "split",
+                    GuardrailAction::Review => "review",
+                    GuardrailAction::Stop => "stop",
+                }
+                .to_string(),
+            ),
+            "protected_paths" => Some(format_vec(&self.protected_paths)),
+            "secret_scan" => Some(self.secret_scan.to_string()),
+            "secret_patterns" => Some(format_vec(&self.secret_patterns)),
+            "secret_allowlist" => Some(format_vec(&self.secret_allowlist)),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "max_changed_lines" => {
+                self.max_changed_lines = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer (0 = unlimited)".into(),
+                })?;
+                Ok(())
+            }
+            "max_changed_files" => {
+                self.max_changed_files = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer (0 = unlimited)".into(),
+                })?;
+                Ok(())
+            }
+            "on_exceed" => {
+                self.on_exceed = match value.to_lowercase().as_str() {
+                    "warn" => GuardrailAction::Warn,
+                    "split" => GuardrailAction::Split,
+                    "review" => GuardrailAction::Review,
+                    "stop" => GuardrailAction::Stop,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "warn, split, review, or stop".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "protected_paths" => {
+                self.protected_paths = parse_vec(value);
+                Ok(())
+            }
+            "secret_scan" => {
+                self.secret_scan = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "boolean (true/false)".into(),
+                })?;
+                Ok(())
+            }
+            "secret_patterns" | "secret_allowlist" => {
+                let patterns = parse_vec(value);
+                if let Some(bad) = patterns.iter().find(|p| regex::Regex::new(p).is_err()) {
+                    return Err(FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: format!("comma-separated regexes ('{bad}' is not valid)"),
+                    });
+                }
+                if key == "secret_patterns" {
+                    self.secret_patterns = patterns;
+                } else {
+                    self.secret_allowlist = patterns;
+                }
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "max_changed_lines",
+            "max_changed_files",
+            "on_exceed",
+            "protected_paths",
+            "secret_scan",
+            "secret_patterns",
+            "secret_allowlist",
+        ]
+    }
+
+    fn section_name() -> &'static str {
+        "guardrails"
+    }
+}
+
+impl ConfigField for PluginsConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "paths" => Some(format_vec(&self.paths)),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "paths" => {
+                self.paths = parse_vec(value);
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["paths"]
+    }
+
+    fn section_name() -> &'static str {
+        "plugins"
+    }
+}
+
+impl ConfigField for ReportingConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "tracking_issue" => Some(format_optional(&self.tracking_issue)),
+            "every" => Some(self.every.to_string()),
+            "estimate_unit" => Some(self.estimate_unit.as_str().to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "tracking_issue" => {
+                self.tracking_issue = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "every" => {
+                let v: u32 = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "positive integer".into(),
+                })?;
+                if v == 0 {
+                    return Err(FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    });
+                }
+                self.every = v;
+                Ok(())
+            }
+            "estimate_unit" => {
+                self.estimate_unit = match value.to_lowercase().as_str() {
+                    "points" => EstimateUnit::Points,
+                    "minutes" => EstimateUnit::Minutes,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "points or minutes".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["tracking_issue", "every", "estimate_unit"]
+    }
+
+    fn section_name() -> &'static str {
+        "reporting"
+    }
+}
+
+impl ConfigField for HooksConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "pre_iteration" => Some(format_optional(&self.pre_iteration)),
+            "post_iteration" => Some(format_optional(&self.post_iteration)),
+            "on_task_complete" => Some(format_optional(&self.on_task_complete)),
+            "on_task_fail" => Some(format_optional(&self.on_task_fail)),
+            "on_gate_fail" => Some(format_optional(&self.on_gate_fail)),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        let field = match key {
+            "pre_iteration" => &mut self.pre_iteration,
+            "post_iteration" => &mut self.post_iteration,
+            "on_task_complete" => &mut self.on_task_complete,
+            "on_task_fail" => &mut self.on_task_fail,
+            "on_gate_fail" => &mut self.on_gate_fail,
+            _ => return Err(FieldError::UnknownKey(key.into())),
+        };
+        *field = if value.is_empty() || value == "(not set)" {
+            None
+        } else {
+            Some(value.to_string())
+        };
+        Ok(())
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "pre_iteration",
+            "post_iteration",
+            "on_task_complete",
+            "on_task_fail",
+            "on_gate_fail",
+        ]
+    }
+
+    fn section_name() -> &'static str {
+        "hooks"
+    }
+}
+
+impl ConfigField for NotificationsConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "webhook_url" => Some(format_optional(&self.webhook_url)),
+            "format" => Some(
+                match self.format {
+                    WebhookFormat::Auto => "auto",
+                    WebhookFormat::Slack => "slack",
+                    WebhookFormat::Discord => "discord",
+                    WebhookFormat::Json => "json",
+                }
+                .to_string(),
+            ),
+            "desktop" => Some(self.desktop.to_string()),
+            "events" => {
+                let names: Vec<String> = self.events.iter().map(|e| e.name().to_string()).collect();
+                Some(format_vec(&names))
+            }
+            "stall_iterations" => Some(self.stall_iterations.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "webhook_url" => {
+                self.webhook_url = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "format" => {
+                self.format = match value.to_lowercase().as_str() {
+                    "auto" => WebhookFormat::Auto,
+                    "slack" => WebhookFormat::Slack,
+                    "discord" => WebhookFormat::Discord,
+                    "json" => WebhookFormat::Json,
+                    _ => {
+                        return Err(FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: "auto, slack, discord or json".into(),
+                        })
+                    }
+                };
+                Ok(())
+            }
+            "desktop" => {
+                self.desktop = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "events" => {
+                self.events = parse_vec(value)
+                    .iter()
+                    .map(|name| NotifyEvent::from_name(name))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "comma-separated run_end, max_failures, stalled".into(),
+                    })?;
+                Ok(())
+            }
+            "stall_iterations" => {
+                self.stall_iterations = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "webhook_url",
+            "format",
+            "desktop",
+            "events",
+            "stall_iterations",
+        ]
+    }
+
+    fn section_name() -> &'static str {
+        "notifications"
+    }
+}
+
+impl ConfigField for ServerConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "host" => Some(self.host.clone()),
+            "port" => Some(self.port.to_string()),
+            "token" => Some(format_optional(&self.token)),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "host" => {
+                self.host = value.to_string();
+                Ok(())
+            }
+            "port" => {
+                self.port = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "port number (0-65535)".into(),
+                })?;
+                Ok(())
+            }
+            "token" => {
+                self.token = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["host", "port", "token"]
+    }
+
+    fn section_name() -> &'static str {
+        "server"
+    }
+}
+
+impl ConfigField for ScheduleConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "cron" => Some(format_optional(&self.cron)),
+            "iterations" => Some(
+                self.iterations
+                    .map_or_else(|| "(not set)".to_string(), |n| n.to_string()),
+            ),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        let unset = value.is_empty() || value == "(not set)";
+        match key {
+            "cron" => {
+                if !unset {
+                    crate::runner::CronSchedule::parse(value).map_err(|e| {
+                        FieldError::InvalidValue {
+                            key: key.into(),
+                            expected: e,
+                        }
+                    })?;
+                }
+                self.cron = (!unset).then(|| value.to_string());
+                Ok(())
+            }
+            "iterations" => {
+                self.iterations = if unset {
+                    None
+                } else {
+                    Some(value.parse().map_err(|_| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    })?)
+                };
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["cron", "iterations"]
+    }
+
+    fn section_name() -> &'static str {
+        "schedule"
+    }
+}
+
+impl ConfigField for SelectionConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "strategy" => Some(self.strategy.as_str().to_string()),
+            "stick_to_epic" => Some(self.stick_to_epic.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "strategy" => {
+                let value = value.to_lowercase().replace('_', "-");
+                self.strategy = SelectionStrategy::ALL
+                    .into_iter()
+                    .find(|s| s.as_str() == value)
+                    .ok_or_else(|| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "priority, fifo, smallest-first, dependency-aware or \
+                                   round-robin-by-epic"
+                            .into(),
+                    })?;
+                Ok(())
+            }
+            "stick_to_epic" => {
+                self.stick_to_epic = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["strategy", "stick_to_epic"]
+    }
+
+    fn section_name() -> &'static str {
+        "selection"
+    }
+}
+
+impl ConfigField for DedupConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "by_id" => Some(self.by_id.to_string()),
+            "by_title" => Some(self.by_title.to_string()),
+            "title_similarity" => Some(self.title_similarity.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "by_id" => {
+                self.by_id = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "by_title" => {
+                self.by_title = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            "title_similarity" => {
+                self.title_similarity = value
+                    .parse()
+                    .ok()
+                    .filter(|s: &f64| (0.0..=1.0).contains(s))
+                    .ok_or_else(|| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "number from 0 to 1 (e.g., 0.9)".into(),
+                    })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["by_id", "by_title", "title_similarity"]
+    }
+
+    fn section_name() -> &'static str {
+        "dedup"
+    }
+}
+
+impl ConfigField for OllamaConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "host" => Some(self.host.clone()),
+            "model" => Some(format_optional(&self.model)),
+            "context_size" => Some(self.context_size.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "host" => {
+                self.host = value.to_string();
+                Ok(())
+            }
+            "model" => {
+                self.model = if value.is_empty() || value == "(not set)" {
+                    None
+                } else {
+                    Some(value.to_string())
+                };
+                Ok(())
+            }
+            "context_size" => {
+                self.context_size = value
+                    .parse()
+                    .ok()
+                    .filter(|&size: &u32| size > 0)
+                    .ok_or_else(|| FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["host", "model", "context_size"]
+    }
+
+    fn section_name() -> &'static str {
+        "ollama"
+    }
+}
+
+impl ConfigField for RetryConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "cli_crash" => Some(self.cli_crash.as_str().to_string()),
+            "timeout" => Some(self.timeout.as_str().to_string()),
+            "gates_failed" => Some(self.gates_failed.as_str().to_string()),
+            "no_changes" => Some(self.no_changes.as_str().to_string()),
+            "rate_limit" => Some(self.rate_limit.as_str().to_string()),
+            "backoff_secs" => Some(self.backoff_secs.to_string()),
+            "max_backoff_secs" => Some(self.max_backoff_secs.to_string()),
+            "skip_after" => Some(self.skip_after.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        let policy = |value: &str| {
+            value
+                .parse::<RetryPolicy>()
+                .map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "retry, backoff, skip, or stop".into(),
+                })
+        };
+        match key {
+            "cli_crash" => self.cli_crash = policy(value)?,
+            "timeout" => self.timeout = policy(value)?,
+            "gates_failed" => self.gates_failed = policy(value)?,
+            "no_changes" => self.no_changes = policy(value)?,
+            "rate_limit" => self.rate_limit = policy(value)?,
+            "backoff_secs" => {
+                self.backoff_secs = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+            }
+            "max_backoff_secs" => {
+                self.max_backoff_secs = value.parse().map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "non-negative integer".into(),
+                })?;
+            }
+            "skip_after" => {
+                self.skip_after = value.parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                    FieldError::InvalidValue {
+                        key: key.into(),
+                        expected: "positive integer".into(),
+                    }
+                })?;
+            }
+            _ => return Err(FieldError::UnknownKey(key.into())),
+        }
+        Ok(())
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "cli_crash",
+            "timeout",
+            "gates_failed",
+            "no_changes",
+            "rate_limit",
+            "backoff_secs",
+            "max_backoff_secs",
+            "skip_after",
+        ]
+    }
+
+    fn section_name() -> &'static str {
+        "retry"
+    }
+}
+
+impl ConfigField for ReviewConfig {
+    fn get_field(&self, key: &str) -> Option<String> {
+        match key {
+            "required" => Some(self.required.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, key: &str, value: &str) -> Result<(), FieldError> {
+        match key {
+            "required" => {
+                self.required = parse_bool(value).map_err(|_| FieldError::InvalidValue {
+                    key: key.into(),
+                    expected: "true or false".into(),
+                })?;
+                Ok(())
+            }
+            _ => Err(FieldError::UnknownKey(key.into())),
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["required"]
+    }
+
+    fn section_name() -> &'static str {
+        "review"
+    }
+}
@@ -0,0 +1,74 @@
+//! Crate-wide error type.
+//!
+//! Each module has its own error enum; [`AfkError`] wraps them so code
+//! using afk as a library can propagate any of them with `?` and match on
+//! the kind that went wrong. Turning one into a message and an exit code is
+//! up to the caller, as `main.rs` does for the CLI.
+
+use crate::config::{ConfigError, FieldError};
+use crate::fleet::FleetError;
+use crate::plugins::PluginError;
+use crate::prd::PrdError;
+use crate::progress::ProgressError;
+use crate::prompt::PromptError;
+use crate::sources::SourceError;
+
+/// Result type for afk library operations.
+pub type Result<T, E = AfkError> = std::result::Result<T, E>;
+
+/// Any error from the afk library.
+#[derive(Debug, thiserror::Error)]
+pub enum AfkError {
+    /// Loading, parsing or saving the config.
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    /// Getting or setting a config key.
+    #[error(transparent)]
+    Field(#[from] FieldError),
+    /// Reading, syncing or saving tasks.
+    #[error(transparent)]
+    Prd(#[from] PrdError),
+    /// Reading or saving session progress and archives.
+    #[error(transparent)]
+    Progress(#[from] ProgressError),
+    /// Loading tasks from a source.
+    #[error(transparent)]
+    Source(#[from] SourceError),
+    /// Building the prompt.
+    #[error(transparent)]
+    Prompt(#[from] PromptError),
+    /// Fleet config and runs.
+    #[error(transparent)]
+    Fleet(#[from] FleetError),
+    /// Loading plugins.
+    #[error(transparent)]
+    Plugin(#[from] PluginError),
+    /// Any other I/O error.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AfkConfig, SourceConfig};
+    use crate::sources::load_tasks;
+
+    fn load_all() -> Result<usize> {
+        let config = AfkConfig::load(Some(std::path::Path::new("/nonexistent/config.json")))?;
+        let mut source = SourceConfig::command("");
+        source.path = None;
+        let tasks = load_tasks(&[config.sources, vec![source]].concat())?;
+        Ok(tasks.len())
+    }
+
+    #[test]
+    fn test_errors_propagate_with_their_kind() {
+        let err = load_all().unwrap_err();
+        assert!(matches!(
+            err,
+            AfkError::Source(SourceError::Misconfigured(_))
+        ));
+        assert_eq!(err.to_string(), "Command source has no path");
+    }
+}
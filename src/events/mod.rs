@@ -0,0 +1,904 @@
+//! Structured event log.
+//!
+//! The loop appends one JSON record per line to `.afk/events.jsonl` as it
+//! works: iterations starting and ending, the task chosen, stalls, commits,
+//! gate results, tool calls, the files changed, and token usage. External dashboards can tail the file, or
+//! use `afk log --follow --json`. Logging never interrupts the loop; write
+//! failures are ignored.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config::EVENTS_FILE;
+use crate::git::changes::FileChange;
+use crate::parser::{CliFormat, StreamEvent, StreamJsonParser, TokenUsage};
+
+/// Something that happened during a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The loop is about to start.
+    LoopStart {
+        /// Iteration limit for this run.
+        max_iterations: u32,
+    },
+    /// An iteration is about to run.
+    IterationStart {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration is expected to work on.
+        task_id: Option<String>,
+    },
+    /// The task picked for an iteration.
+    TaskSelected {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task ID.
+        task_id: String,
+        /// Task title.
+        title: String,
+        /// Task priority (1 = highest).
+        priority: i32,
+    },
+    /// An iteration finished.
+    IterationEnd {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration worked on.
+        task_id: Option<String>,
+        /// Whether the AI CLI run succeeded.
+        success: bool,
+        /// Wall-clock duration in seconds.
+        duration_seconds: f64,
+        /// Error message, if the run failed.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// The AI CLI printed nothing for too long and was killed.
+    Stalled {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration worked on.
+        task_id: Option<String>,
+        /// Seconds the AI CLI was silent.
+        idle_seconds: u64,
+        /// Attempt that stalled (1 for the first run of the iteration).
+        attempt: u32,
+    },
+    /// A commit made during an iteration.
+    Commit {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration worked on.
+        task_id: Option<String>,
+        /// Commit SHA.
+        sha: String,
+    },
+    /// A quality gate ran (via `afk verify`).
+    Gate {
+        /// Iteration the gate ran in.
+        iteration: u32,
+        /// Gate name.
+        name: String,
+        /// Whether the gate passed.
+        passed: bool,
+        /// Gate duration in seconds.
+        duration_seconds: f64,
+    },
+    /// An iteration was reverted for leaving the quality gates failing.
+    GateRevert {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration worked on.
+        task_id: Option<String>,
+        /// Commit the iteration was reset to.
+        commit: String,
+        /// Names of the gates that failed.
+        failed_gates: Vec<String>,
+        /// The end of each failed gate's output.
+        output: String,
+    },
+    /// Files an iteration changed, from git rather than the AI CLI's output.
+    FilesChanged {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// The files, sorted by path, with lines added and removed.
+        files: Vec<FileChange>,
+    },
+    /// A tool call the AI CLI made, once it completed.
+    ToolCall {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// The call.
+        #[serde(flatten)]
+        call: ToolCall,
+    },
+    /// Tokens an iteration used, as reported by the AI CLI.
+    TokenUsage {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Input tokens, including cache reads and writes.
+        input_tokens: u64,
+        /// Output tokens.
+        output_tokens: u64,
+        /// Cost in USD, if reported.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cost_usd: Option<f64>,
+    },
+    /// The loop stopped.
+    LoopEnd {
+        /// Why the loop stopped.
+        reason: String,
+        /// Iterations completed.
+        iterations: u32,
+        /// Tasks completed.
+        tasks_completed: u32,
+    },
+    /// `afk scheduler` started a scheduled run and it finished.
+    ScheduledRun {
+        /// When the run was due, as local time.
+        scheduled_for: String,
+        /// Whether `afk go` exited successfully.
+        success: bool,
+        /// Exit code of `afk go`, if it exited normally.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        exit_code: Option<i32>,
+        /// Wall-clock duration in seconds.
+        duration_seconds: f64,
+    },
+}
+
+/// A completed tool call, as reported in an AI CLI's output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Tool name, as the CLI calls it (e.g. "Bash", "replace").
+    pub tool: String,
+    /// Kind of tool: Read, Write, Edit, Delete, Command, Search, or the
+    /// tool name for anything else.
+    pub kind: String,
+    /// File the call worked on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Shell command the call ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Whether the call succeeded.
+    pub success: bool,
+    /// Exit code of the command, if the CLI reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+}
+
+impl ToolCall {
+    /// Whether the call changed a file.
+    pub fn touches_file(&self) -> bool {
+        self.path.is_some() && matches!(self.kind.as_str(), "Write" | "Edit" | "Delete")
+    }
+}
+
+/// An event with the time it was recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Local time, e.g. "2024-05-01T12:00:00.000000".
+    pub timestamp: String,
+    /// The event.
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+impl EventRecord {
+    /// Stamp an event with the current time.
+    pub fn now(event: Event) -> Self {
+        Self {
+            timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            event,
+        }
+    }
+
+    /// One-line human summary, without the timestamp.
+    pub fn summary(&self) -> String {
+        let task = |id: &Option<String>| id.as_deref().map(|t| format!(" {t}")).unwrap_or_default();
+        match &self.event {
+            Event::LoopStart { max_iterations } => {
+                format!("loop started (max {max_iterations} iterations)")
+            }
+            Event::IterationStart { iteration, task_id } => {
+                format!("iteration {iteration} started{}", task(task_id))
+            }
+            Event::TaskSelected {
+                iteration,
+                task_id,
+                title,
+                priority,
+            } => format!("iteration {iteration} picked {task_id} (P{priority}): {title}"),
+            Event::IterationEnd {
+                iteration,
+                task_id,
+                success,
+                duration_seconds,
+                error,
+            } => format!(
+                "iteration {iteration} {}{} in {duration_seconds:.1}s{}",
+                if *success { "finished" } else { "failed" },
+                task(task_id),
+                error
+                    .as_deref()
+                    .map(|e| format!(": {e}"))
+                    .unwrap_or_default()
+            ),
+            Event::Stalled {
+                iteration,
+                task_id,
+                idle_seconds,
+                attempt,
+            } => format!(
+                "iteration {iteration} stalled{} after {idle_seconds}s without output \
+                 (attempt {attempt})",
+                task(task_id)
+            ),
+            Event::Commit { iteration, sha, .. } => format!(
+                "iteration {iteration} committed {}",
+                sha.get(..7).unwrap_or(sha)
+            ),
+            Event::Gate {
+                iteration,
+                name,
+                passed,
+                duration_seconds,
+            } => format!(
+                "iteration {iteration} gate {name} {} ({duration_seconds:.1}s)",
+                if *passed { "passed" } else { "failed" }
+            ),
+            Event::GateRevert {
+                iteration,
+                task_id,
+                failed_gates,
+                ..
+            } => format!(
+                "iteration {iteration} reverted{} after gates failed: {}",
+                task(task_id),
+                failed_gates.join(", ")
+            ),
+            Event::FilesChanged { iteration, files } => {
+                let (added, removed) = files
+                    .iter()
+                    .fold((0, 0), |(a, r), f| (a + f.insertions, r + f.deletions));
+                format!(
+                    "iteration {iteration} changed {} file{} (+{added}/-{removed})",
+                    files.len(),
+                    if files.len() == 1 { "" } else { "s" }
+                )
+            }
+            Event::ToolCall { iteration, call } => {
+                let target = match (&call.path, &call.command) {
+                    (Some(path), _) => format!(" {path}"),
+                    (None, Some(command)) => format!(" $ {command}"),
+                    (None, None) => String::new(),
+                };
+                format!(
+                    "iteration {iteration} {} {}{target}{}",
+                    if call.success { "ran" } else { "failed" },
+                    call.tool,
+                    call.exit_code
+                        .filter(|&code| code != 0)
+                        .map(|code| format!(" (exit code {code})"))
+                        .unwrap_or_default()
+                )
+            }
+            Event::TokenUsage {
+                iteration,
+                input_tokens,
+                output_tokens,
+                cost_usd,
+            } => format!(
+                "iteration {iteration} used {input_tokens} in / {output_tokens} out tokens{}",
+                cost_usd.map(|c| format!(" (${c:.4})")).unwrap_or_default()
+            ),
+            Event::LoopEnd {
+                reason,
+                iterations,
+                tasks_completed,
+            } => format!("loop ended: {reason} ({iterations} iterations, {tasks_completed} tasks)"),
+            Event::ScheduledRun {
+                scheduled_for,
+                success,
+                exit_code,
+                duration_seconds,
+            } => format!(
+                "scheduled run for {scheduled_for} {} in {duration_seconds:.1}s{}",
+                if *success { "finished" } else { "failed" },
+                exit_code
+                    .filter(|_| !*success)
+                    .map(|c| format!(" (exit code {c})"))
+                    .unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Append an event to `.afk/events.jsonl`.
+///
+/// Does nothing in read-only mode; write errors are ignored.
+pub fn record(event: Event) {
+    if crate::config::is_read_only() {
+        return;
+    }
+    let _ = record_to(Path::new(EVENTS_FILE), &EventRecord::now(event));
+}
+
+/// Append a record to an event log file.
+pub fn record_to(path: &Path, record: &EventRecord) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Names of the gates that failed in the most recent gate run in a log.
+///
+/// A run is the set of gate events for one iteration. Returns an empty list
+/// if the log is missing or the last run passed.
+pub fn last_gate_failures(path: &Path) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let gates: Vec<(u32, String, bool)> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<EventRecord>(line).ok())
+        .filter_map(|record| match record.event {
+            Event::Gate {
+                iteration,
+                name,
+                passed,
+                ..
+            } => Some((iteration, name, passed)),
+            _ => None,
+        })
+        .collect();
+    let Some(last) = gates.last().map(|(iteration, _, _)| *iteration) else {
+        return Vec::new();
+    };
+    // Walk back over the last iteration's run only
+    let mut failures: Vec<String> = gates
+        .into_iter()
+        .rev()
+        .take_while(|(iteration, _, _)| *iteration == last)
+        .filter(|(_, _, passed)| !passed)
+        .map(|(_, name, _)| name)
+        .collect();
+    failures.reverse();
+    failures
+}
+
+/// Every record in an event log, oldest first. A missing log or unreadable
+/// lines give fewer records, never an error.
+pub fn load(path: &Path) -> Vec<EventRecord> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str::<EventRecord>(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// SHAs of the commits made while working on a task, oldest first.
+///
+/// Returns an empty list if the log is missing.
+pub fn task_commits(path: &Path, task_id: &str) -> Vec<String> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<EventRecord>(line).ok())
+        .filter_map(|record| match record.event {
+            Event::Commit {
+                task_id: Some(id),
+                sha,
+                ..
+            } if id == task_id => Some(sha),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The gates of the latest gate run in an iteration on a task, as (name,
+/// passed) in the order they ran.
+///
+/// Returns an empty list if the log is missing or no such iteration ran gates.
+pub fn task_gate_run(path: &Path, task_id: &str) -> Vec<(String, bool)> {
+    let mut run = Vec::new();
+    // The iteration in progress, if it is on the task, and whether a gate
+    // in it has been seen yet
+    let mut current: Option<(u32, bool)> = None;
+    for record in load(path) {
+        match record.event {
+            Event::IterationStart {
+                iteration,
+                task_id: id,
+            } => current = (id.as_deref() == Some(task_id)).then_some((iteration, false)),
+            Event::Gate {
+                iteration,
+                name,
+                passed,
+                ..
+            } => {
+                if let Some((_, seen)) = current.as_mut().filter(|(i, _)| *i == iteration) {
+                    if !*seen {
+                        run.clear();
+                        *seen = true;
+                    }
+                    run.push((name, passed));
+                }
+            }
+            _ => {}
+        }
+    }
+    run
+}
+
+/// What an event log records about the latest run of the loop.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    /// Iterations finished.
+    pub iterations: u32,
+    /// Commits made.
+    pub commits: u32,
+    /// Task picked most recently, as (ID, title).
+    pub current_task: Option<(String, String)>,
+    /// Input tokens used.
+    pub input_tokens: u64,
+    /// Output tokens used.
+    pub output_tokens: u64,
+    /// Cost in USD, summed over iterations that reported one.
+    pub cost_usd: Option<f64>,
+    /// Why the run stopped, if it has.
+    pub ended: Option<String>,
+}
+
+/// Summarise the events since the last loop start in a log.
+///
+/// Returns an empty summary if the log is missing.
+pub fn run_summary(path: &Path) -> RunSummary {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return RunSummary::default();
+    };
+    let events: Vec<Event> = contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<EventRecord>(line).ok())
+        .map(|record| record.event)
+        .collect();
+    let start = events
+        .iter()
+        .rposition(|e| matches!(e, Event::LoopStart { .. }))
+        .unwrap_or(0);
+
+    let mut summary = RunSummary::default();
+    for event in &events[start..] {
+        match event {
+            Event::IterationEnd { .. } => summary.iterations += 1,
+            Event::Commit { .. } => summary.commits += 1,
+            Event::TaskSelected { task_id, title, .. } => {
+                summary.current_task = Some((task_id.clone(), title.clone()));
+            }
+            Event::TokenUsage {
+                input_tokens,
+                output_tokens,
+                cost_usd,
+                ..
+            } => {
+                summary.input_tokens += input_tokens;
+                summary.output_tokens += output_tokens;
+                if let Some(cost) = cost_usd {
+                    summary.cost_usd = Some(summary.cost_usd.unwrap_or(0.0) + cost);
+                }
+            }
+            Event::LoopEnd { reason, .. } => summary.ended = Some(reason.clone()),
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Token usage from an AI CLI's output.
+///
+/// Uses the usage in the last result event, as printed by Claude Code and
+/// Gemini CLI, or else the per-message usage Aider prints, summed. Returns
+/// input tokens (with cache reads and writes), output tokens, and cost if
+/// reported.
+pub fn token_usage(output: &str) -> Option<(u64, u64, Option<f64>)> {
+    let mut parser = StreamJsonParser::auto_detect();
+    let mut result = None;
+    let mut messages: Option<TokenUsage> = None;
+    for event in output.lines().flat_map(|line| parser.parse_events(line)) {
+        match event {
+            StreamEvent::Result {
+                usage: Some(usage), ..
+            } => result = Some(usage),
+            StreamEvent::Usage(usage) => {
+                messages = Some(messages.unwrap_or_default() + usage);
+            }
+            _ => {}
+        }
+    }
+    let usage = result.or(messages)?;
+    Some((usage.input_tokens, usage.output_tokens, usage.cost_usd))
+}
+
+/// Completed tool calls in an AI CLI's output, in order.
+///
+/// Takes the CLI's format, as Claude Code's tool calls are nested in its
+/// messages and are missed when its output is taken for Cursor's.
+pub fn tool_calls(output: &str, format: CliFormat) -> Vec<ToolCall> {
+    let mut parser = StreamJsonParser::new(format);
+    output
+        .lines()
+        .flat_map(|line| parser.parse_events(line))
+        .filter_map(|event| match event {
+            StreamEvent::ToolCompleted {
+                tool_name,
+                tool_type,
+                path,
+                command,
+                success,
+                exit_code,
+                ..
+            } => Some(ToolCall {
+                tool: tool_name,
+                kind: tool_type.to_string(),
+                path,
+                command,
+                success,
+                exit_code,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_run_summary_covers_latest_run() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("events.jsonl");
+        let events = [
+            Event::LoopStart { max_iterations: 5 },
+            Event::IterationEnd {
+                iteration: 1,
+                task_id: None,
+                success: true,
+                duration_seconds: 1.0,
+                error: None,
+            },
+            Event::LoopStart { max_iterations: 5 },
+            Event::TaskSelected {
+                iteration: 1,
+                task_id: "a".to_string(),
+                title: "Task A".to_string(),
+                priority: 1,
+            },
+            Event::IterationEnd {
+                iteration: 1,
+                task_id: Some("a".to_string()),
+                success: true,
+                duration_seconds: 1.0,
+                error: None,
+            },
+            Event::Commit {
+                iteration: 1,
+                task_id: Some("a".to_string()),
+                sha: "abc".to_string(),
+            },
+            Event::TokenUsage {
+                iteration: 1,
+                input_tokens: 100,
+                output_tokens: 20,
+                cost_usd: Some(0.25),
+            },
+            Event::TokenUsage {
+                iteration: 2,
+                input_tokens: 50,
+                output_tokens: 10,
+                cost_usd: None,
+            },
+        ];
+        for event in events {
+            record_to(&path, &EventRecord::now(event)).unwrap();
+        }
+
+        let summary = run_summary(&path);
+        assert_eq!(summary.iterations, 1);
+        assert_eq!(summary.commits, 1);
+        assert_eq!(
+            summary.current_task,
+            Some(("a".to_string(), "Task A".to_string()))
+        );
+        assert_eq!(summary.input_tokens, 150);
+        assert_eq!(summary.cost_usd, Some(0.25));
+        assert!(summary.ended.is_none());
+        assert_eq!(
+            run_summary(&temp.path().join("missing")),
+            RunSummary::default()
+        );
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".afk/events.jsonl");
+        let start = EventRecord::now(Event::IterationStart {
+            iteration: 1,
+            task_id: Some("auth".to_string()),
+        });
+        let usage = EventRecord::now(Event::TokenUsage {
+            iteration: 1,
+            input_tokens: 1200,
+            output_tokens: 300,
+            cost_usd: None,
+        });
+        record_to(&path, &start).unwrap();
+        record_to(&path, &usage).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#""event":"iteration_start""#));
+        assert!(!lines[1].contains("cost_usd"));
+
+        let parsed: EventRecord = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(parsed, start);
+    }
+
+    #[test]
+    fn test_summary() {
+        let record = |event| EventRecord::now(event).summary();
+        assert_eq!(
+            record(Event::IterationEnd {
+                iteration: 2,
+                task_id: Some("auth".to_string()),
+                success: false,
+                duration_seconds: 12.34,
+                error: Some("exit 1".to_string()),
+            }),
+            "iteration 2 failed auth in 12.3s: exit 1"
+        );
+        assert_eq!(
+            record(Event::Commit {
+                iteration: 2,
+                task_id: None,
+                sha: "0123456789abcdef".to_string(),
+            }),
+            "iteration 2 committed 0123456"
+        );
+        assert_eq!(
+            record(Event::Stalled {
+                iteration: 4,
+                task_id: Some("auth".to_string()),
+                idle_seconds: 300,
+                attempt: 1,
+            }),
+            "iteration 4 stalled auth after 300s without output (attempt 1)"
+        );
+        assert_eq!(
+            record(Event::TokenUsage {
+                iteration: 3,
+                input_tokens: 10,
+                output_tokens: 5,
+                cost_usd: Some(0.5),
+            }),
+            "iteration 3 used 10 in / 5 out tokens ($0.5000)"
+        );
+        assert_eq!(
+            record(Event::ScheduledRun {
+                scheduled_for: "2026-03-10 02:00".to_string(),
+                success: false,
+                exit_code: Some(1),
+                duration_seconds: 61.0,
+            }),
+            "scheduled run for 2026-03-10 02:00 failed in 61.0s (exit code 1)"
+        );
+        assert_eq!(
+            record(Event::ToolCall {
+                iteration: 2,
+                call: ToolCall {
+                    tool: "Bash".to_string(),
+                    kind: "Command".to_string(),
+                    path: None,
+                    command: Some("cargo test".to_string()),
+                    success: false,
+                    exit_code: Some(101),
+                },
+            }),
+            "iteration 2 failed Bash $ cargo test (exit code 101)"
+        );
+        assert_eq!(
+            record(Event::GateRevert {
+                iteration: 5,
+                task_id: Some("auth".to_string()),
+                commit: "0123456789abcdef".to_string(),
+                failed_gates: vec!["test".to_string(), "lint".to_string()],
+                output: "== test ==\n1 failed".to_string(),
+            }),
+            "iteration 5 reverted auth after gates failed: test, lint"
+        );
+        assert_eq!(
+            record(Event::FilesChanged {
+                iteration: 3,
+                files: vec![FileChange {
+                    path: "src/lib.rs".to_string(),
+                    status: crate::git::changes::FileStatus::Modified,
+                    insertions: 12,
+                    deletions: 4,
+                }],
+            }),
+            "iteration 3 changed 1 file (+12/-4)"
+        );
+    }
+
+    #[test]
+    fn test_tool_call_round_trip() {
+        let record = EventRecord::now(Event::ToolCall {
+            iteration: 1,
+            call: ToolCall {
+                tool: "Write".to_string(),
+                kind: "Write".to_string(),
+                path: Some("src/lib.rs".to_string()),
+                command: None,
+                success: true,
+                exit_code: None,
+            },
+        });
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""event":"tool_call","iteration":1,"tool":"Write""#));
+        assert!(!json.contains("command"));
+        assert_eq!(serde_json::from_str::<EventRecord>(&json).unwrap(), record);
+    }
+
+    #[test]
+    fn test_tool_calls() {
+        let output = concat!(
+            r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"a","name":"Write","input":{"file_path":"src/lib.rs"}},{"type":"tool_use","id":"b","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"a"},{"type":"tool_result","tool_use_id":"b","is_error":true}]}}"#,
+            "\n",
+        );
+        let calls = tool_calls(output, CliFormat::Claude);
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].path.as_deref(), Some("src/lib.rs"));
+        assert!(calls[0].touches_file());
+        assert_eq!(calls[1].command.as_deref(), Some("cargo test"));
+        assert!(!calls[1].success);
+        assert!(!calls[1].touches_file());
+        assert!(tool_calls("plain text\n", CliFormat::Auto).is_empty());
+    }
+
+    #[test]
+    fn test_token_usage() {
+        let output = concat!(
+            "{\"type\":\"system\",\"model\":\"m\"}\n",
+            "plain text\n",
+            "{\"type\":\"result\",\"subtype\":\"success\",\"total_cost_usd\":0.02,",
+            "\"usage\":{\"input_tokens\":100,\"cache_read_input_tokens\":900,\"output_tokens\":50}}\n"
+        );
+        assert_eq!(token_usage(output), Some((1000, 50, Some(0.02))));
+        assert_eq!(token_usage("{\"type\":\"result\"}\nno usage\n"), None);
+        assert_eq!(token_usage(""), None);
+
+        let aider = concat!(
+            "Aider v0.86.1\n",
+            "Tokens: 2.5k sent, 300 received. Cost: $0.01 message, $0.01 session.\n",
+            "Tokens: 1,000 sent, 1k cache hit, 200 received. Cost: $0.02 message, $0.03 session.\n",
+        );
+        let (input, output, cost) = token_usage(aider).unwrap();
+        assert_eq!((input, output), (4500, 500));
+        assert!((cost.unwrap() - 0.03).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_last_gate_failures() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("events.jsonl");
+        assert!(last_gate_failures(&path).is_empty());
+
+        let gate = |iteration, name: &str, passed| {
+            EventRecord::now(Event::Gate {
+                iteration,
+                name: name.to_string(),
+                passed,
+                duration_seconds: 1.0,
+            })
+        };
+        record_to(&path, &gate(1, "lint", false)).unwrap();
+        record_to(&path, &gate(2, "lint", true)).unwrap();
+        record_to(&path, &gate(2, "test", false)).unwrap();
+        record_to(&path, &gate(2, "types", false)).unwrap();
+        record_to(
+            &path,
+            &EventRecord::now(Event::LoopStart { max_iterations: 3 }),
+        )
+        .unwrap();
+        assert_eq!(last_gate_failures(&path), vec!["test", "types"]);
+
+        record_to(&path, &gate(3, "test", true)).unwrap();
+        assert!(last_gate_failures(&path).is_empty());
+    }
+
+    #[test]
+    fn test_task_commits() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("events.jsonl");
+        assert!(task_commits(&path, "auth").is_empty());
+
+        let commit = |iteration, task_id: Option<&str>, sha: &str| {
+            EventRecord::now(Event::Commit {
+                iteration,
+                task_id: task_id.map(String::from),
+                sha: sha.to_string(),
+            })
+        };
+        record_to(&path, &commit(1, Some("auth"), "aaa")).unwrap();
+        record_to(&path, &commit(2, Some("billing"), "bbb")).unwrap();
+        record_to(&path, &commit(3, None, "ccc")).unwrap();
+        record_to(&path, &commit(4, Some("auth"), "ddd")).unwrap();
+        assert_eq!(task_commits(&path, "auth"), ["aaa", "ddd"]);
+    }
+
+    #[test]
+    fn test_task_gate_run() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("events.jsonl");
+        assert!(task_gate_run(&path, "auth").is_empty());
+
+        let start = |iteration, task_id: &str| {
+            EventRecord::now(Event::IterationStart {
+                iteration,
+                task_id: Some(task_id.to_string()),
+            })
+        };
+        let gate = |iteration, name: &str, passed| {
+            EventRecord::now(Event::Gate {
+                iteration,
+                name: name.to_string(),
+                passed,
+                duration_seconds: 1.0,
+            })
+        };
+        for record in [
+            start(1, "auth"),
+            gate(1, "lint", false),
+            start(2, "auth"),
+            gate(2, "lint", true),
+            gate(2, "test", false),
+            start(3, "billing"),
+            gate(3, "lint", false),
+            // An iteration without gates keeps the earlier run
+            start(4, "auth"),
+        ] {
+            record_to(&path, &record).unwrap();
+        }
+        assert_eq!(
+            task_gate_run(&path, "auth"),
+            [("lint".to_string(), true), ("test".to_string(), false)]
+        );
+        assert_eq!(
+            task_gate_run(&path, "billing"),
+            [("lint".to_string(), false)]
+        );
+    }
+}
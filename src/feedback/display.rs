@@ -9,6 +9,8 @@
 use std::io::{self, Write};
 use std::time::Instant;
 
+use crate::git::RepoLayout;
+
 use super::art::{get_mascot, get_spinner_frame};
 use super::celebration::visible_len;
 use super::metrics::{ActivityState, IterationMetrics};
@@ -35,6 +37,8 @@ pub struct FeedbackDisplay {
     mode: DisplayMode,
     /// Whether to show the ASCII mascot.
     show_mascot: bool,
+    /// Whether to show the files panel.
+    show_files: bool,
     /// Whether the display has been started.
     started: bool,
     /// Start time of the current session.
@@ -53,6 +57,8 @@ pub struct FeedbackDisplay {
     progress: f32,
     /// Last rendered line count (for clearing).
     last_line_count: usize,
+    /// Submodules and LFS patterns, for labelling changed files.
+    layout: RepoLayout,
 }
 
 impl FeedbackDisplay {
@@ -61,6 +67,7 @@ impl FeedbackDisplay {
         Self {
             mode: DisplayMode::Minimal,
             show_mascot: true,
+            show_files: true,
             started: false,
             start_time: None,
             spinner_frame: 0,
@@ -70,6 +77,7 @@ impl FeedbackDisplay {
             task_description: None,
             progress: 0.0,
             last_line_count: 0,
+            layout: RepoLayout::load(),
         }
     }
 
@@ -90,6 +98,11 @@ impl FeedbackDisplay {
         }
     }
 
+    /// Set whether to show the files panel.
+    pub fn set_show_files(&mut self, show: bool) {
+        self.show_files = show;
+    }
+
     /// Start the feedback display.
     pub fn start(&mut self) {
         if self.started {
@@ -297,9 +310,11 @@ impl FeedbackDisplay {
         lines.push("\x1b[36m├─────────────────────────────────────────────────────────────────────────────┤\x1b[0m".to_string());
         lines.extend(self.render_activity_section(metrics, activity_state));
 
-        // Files section
-        lines.push("\x1b[36m├─────────────────────────────────────────────────────────────────────────────┤\x1b[0m".to_string());
-        lines.extend(self.render_files_section(metrics));
+        // Files section (if enabled)
+        if self.show_files {
+            lines.push("\x1b[36m├─────────────────────────────────────────────────────────────────────────────┤\x1b[0m".to_string());
+            lines.extend(self.render_files_section(metrics));
+        }
 
         // Mascot section (if enabled)
         if self.show_mascot {
@@ -392,10 +407,16 @@ impl FeedbackDisplay {
                 } else {
                     "\x1b[33m"
                 };
-                let truncated_path = self.truncate_path(path, 60);
+                // Submodule and LFS paths are labelled, leaving less room
+                let label = self.layout.kind(path).label();
+                let truncated_path =
+                    self.truncate_path(path, if label.is_some() { 48 } else { 60 });
+                let label = label
+                    .map(|l| format!(" \x1b[35m[{l}]\x1b[0m"))
+                    .unwrap_or_default();
                 let file_line = format!(
-                    "\x1b[36m│\x1b[0m    {}{}\x1b[0m \x1b[2m{}\x1b[0m",
-                    style, prefix, truncated_path
+                    "\x1b[36m│\x1b[0m    {}{}\x1b[0m \x1b[2m{}\x1b[0m{}",
+                    style, prefix, truncated_path, label
                 );
                 lines.push(self.pad_line(&file_line));
             }
@@ -649,6 +670,36 @@ mod tests {
         assert!(has_files);
     }
 
+    #[test]
+    fn test_render_without_files() {
+        let mut display = FeedbackDisplay::with_mode(DisplayMode::Full);
+        display.set_show_files(false);
+        let mut metrics = IterationMetrics::default();
+        metrics.files_modified.insert("src/main.rs".to_string());
+
+        let lines = display.render_full(&metrics, ActivityState::Active);
+        assert!(!lines.iter().any(|l| l.contains("main.rs")));
+    }
+
+    #[test]
+    fn test_render_labels_submodule_and_lfs_files() {
+        let mut display = FeedbackDisplay::new();
+        display.layout = RepoLayout::parse(
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n",
+            "*.psd filter=lfs diff=lfs merge=lfs -text\n",
+        );
+        let mut metrics = IterationMetrics::default();
+        metrics.files_modified.insert("vendor/lib".to_string());
+        metrics.files_created.insert("art/logo.psd".to_string());
+        metrics.files_created.insert("src/new.rs".to_string());
+
+        let lines = display.render_files_section(&metrics);
+        let line_for = |name: &str| lines.iter().find(|l| l.contains(name)).unwrap();
+        assert!(line_for("vendor/lib").contains("[submodule]"));
+        assert!(line_for("logo.psd").contains("[lfs]"));
+        assert!(!line_for("new.rs").contains("[lfs]"));
+    }
+
     #[test]
     fn test_display_mode_default() {
         let mode = DisplayMode::default();
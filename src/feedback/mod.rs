@@ -10,11 +10,13 @@
 //! - `celebration` - Celebration displays for task/session completion
 //! - `metrics` - Metrics collection for iteration statistics
 //! - `art` - ASCII art assets (mascots, spinners, fireworks)
+//! - `notify` - Webhook and desktop notifications about the run
 
 mod art;
 mod celebration;
 mod display;
 mod metrics;
+mod notify;
 mod spinner;
 
 pub use art::{
@@ -26,4 +28,5 @@ pub use celebration::{
 };
 pub use display::{DisplayMode, FeedbackDisplay};
 pub use metrics::{ActivityState, IterationMetrics, MetricsCollector};
+pub use notify::{Notifier, NotifyDetails, WEBHOOK_ENV};
 pub use spinner::Spinner;
@@ -0,0 +1,420 @@
+//! Notifications about the run.
+//!
+//! With a webhook URL (or `notifications.desktop`) configured, afk tells
+//! someone when the loop stops, when a task hits `limits.max_task_failures`,
+//! and when several iterations in a row make no commits. Messages come from
+//! built-in templates that `notifications.templates` can replace (Tera
+//! syntax, e.g. `{{ task_id }}`). Failures to notify are reported as
+//! warnings and never interrupt the loop.
+
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde_json::{json, Map, Value};
+use tera::{Context, Tera};
+
+use crate::config::{NotificationsConfig, NotifyEvent, WebhookFormat};
+
+/// Environment variable read when `notifications.webhook_url` is unset.
+pub const WEBHOOK_ENV: &str = "AFK_WEBHOOK_URL";
+
+/// How long to wait for the webhook to answer.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Title of desktop notifications.
+const DESKTOP_TITLE: &str = "afk";
+
+/// Built-in message for an event.
+fn default_template(event: NotifyEvent) -> &'static str {
+    match event {
+        NotifyEvent::RunEnd => {
+            "afk run ended: {{ reason }} ({{ tasks_completed }} task(s) completed in \
+             {{ iterations }} iteration(s))"
+        }
+        NotifyEvent::MaxFailures => {
+            "afk: task {{ task_id }} ({{ task_title }}) has failed {{ failures }} times"
+        }
+        NotifyEvent::Stalled => {
+            "afk: no commits in the last {{ stalled_iterations }} iterations \
+             (working on {{ task_id }})"
+        }
+    }
+}
+
+/// Details of an event, available to message templates.
+pub type NotifyDetails = Map<String, Value>;
+
+/// Sends notifications for the events configured.
+#[derive(Debug)]
+pub struct Notifier {
+    config: NotificationsConfig,
+    webhook_url: Option<String>,
+    idle_iterations: u32,
+}
+
+impl Notifier {
+    /// Create a notifier from config. Returns None if there is nowhere to
+    /// send notifications.
+    pub fn new(config: &NotificationsConfig) -> Option<Self> {
+        let webhook_url = config
+            .webhook_url
+            .clone()
+            .or_else(|| std::env::var(WEBHOOK_ENV).ok())
+            .filter(|url| !url.trim().is_empty());
+        if webhook_url.is_none() && !config.desktop {
+            return None;
+        }
+        Some(Self {
+            config: config.clone(),
+            webhook_url,
+            idle_iterations: 0,
+        })
+    }
+
+    /// Notify that the loop stopped.
+    pub fn run_end(&self, reason: &str, iterations: u32, tasks_completed: u32) -> Vec<String> {
+        self.notify(
+            NotifyEvent::RunEnd,
+            details(&[
+                ("reason", json!(reason)),
+                ("iterations", json!(iterations)),
+                ("tasks_completed", json!(tasks_completed)),
+            ]),
+        )
+    }
+
+    /// Record the end of an iteration, notifying if its task just reached
+    /// `max_failures` failures or the run has now gone `stall_iterations`
+    /// iterations without a commit. Returns any warnings.
+    pub fn after_iteration(
+        &mut self,
+        task: Option<(&str, &str)>,
+        failures: (u32, u32),
+        max_failures: u32,
+        committed: bool,
+    ) -> Vec<String> {
+        self.due_after_iteration(task, failures, max_failures, committed)
+            .into_iter()
+            .flat_map(|(event, details)| self.notify(event, details))
+            .collect()
+    }
+
+    /// Events an iteration's outcome calls for. Each threshold is reported
+    /// once, when it is crossed.
+    fn due_after_iteration(
+        &mut self,
+        task: Option<(&str, &str)>,
+        (before, after): (u32, u32),
+        max_failures: u32,
+        committed: bool,
+    ) -> Vec<(NotifyEvent, NotifyDetails)> {
+        let mut due = Vec::new();
+        let (task_id, task_title) = task.unwrap_or_default();
+        if max_failures > 0 && before < max_failures && after >= max_failures {
+            due.push((
+                NotifyEvent::MaxFailures,
+                details(&[
+                    ("task_id", json!(task_id)),
+                    ("task_title", json!(task_title)),
+                    ("failures", json!(after)),
+                ]),
+            ));
+        }
+
+        self.idle_iterations = if committed {
+            0
+        } else {
+            self.idle_iterations + 1
+        };
+        let stall = self.config.stall_iterations;
+        if stall > 0 && self.idle_iterations == stall {
+            due.push((
+                NotifyEvent::Stalled,
+                details(&[
+                    ("task_id", json!(task_id)),
+                    ("task_title", json!(task_title)),
+                    ("stalled_iterations", json!(stall)),
+                ]),
+            ));
+        }
+        due
+    }
+
+    /// Send a notification, if the event is enabled. Returns any warnings.
+    pub fn notify(&self, event: NotifyEvent, details: NotifyDetails) -> Vec<String> {
+        if !self.config.events.contains(&event) {
+            return Vec::new();
+        }
+        let (message, mut warnings) = match render_message(&self.config, event, &details) {
+            Ok(message) => (message, Vec::new()),
+            Err(e) => (
+                render_message(&NotificationsConfig::default(), event, &details)
+                    .unwrap_or_default(),
+                vec![format!(
+                    "Invalid notifications.templates.{}: {e}",
+                    event.name()
+                )],
+            ),
+        };
+
+        if let Some(url) = &self.webhook_url {
+            let payload = webhook_payload(self.config.format, url, event, &message, &details);
+            if let Err(e) = post_webhook(url, &payload) {
+                warnings.push(format!("Notification webhook failed: {e}"));
+            }
+        }
+        if self.config.desktop {
+            if let Err(e) = desktop_notification(&message) {
+                warnings.push(format!("Desktop notification failed: {e}"));
+            }
+        }
+        warnings
+    }
+}
+
+/// Build template details from name/value pairs.
+fn details(pairs: &[(&str, Value)]) -> NotifyDetails {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+}
+
+/// Render the message for an event, from the configured template or the
+/// built-in one.
+pub fn render_message(
+    config: &NotificationsConfig,
+    event: NotifyEvent,
+    details: &NotifyDetails,
+) -> Result<String, tera::Error> {
+    let template = config
+        .templates
+        .get(event.name())
+        .map_or(default_template(event), String::as_str);
+    let mut context = Context::new();
+    context.insert("event", event.name());
+    for (key, value) in details {
+        context.insert(key.as_str(), value);
+    }
+    Tera::one_off(template, &context, false).map(|m| m.trim().to_string())
+}
+
+/// Work out the payload shape, detecting Slack and Discord from the URL.
+pub fn resolve_format(format: WebhookFormat, url: &str) -> WebhookFormat {
+    match format {
+        WebhookFormat::Auto if url.contains("hooks.slack.com") => WebhookFormat::Slack,
+        WebhookFormat::Auto
+            if url.contains("discord.com/api/webhooks")
+                || url.contains("discordapp.com/api/webhooks") =>
+        {
+            WebhookFormat::Discord
+        }
+        WebhookFormat::Auto => WebhookFormat::Json,
+        other => other,
+    }
+}
+
+/// Body to POST to the webhook.
+pub fn webhook_payload(
+    format: WebhookFormat,
+    url: &str,
+    event: NotifyEvent,
+    message: &str,
+    details: &NotifyDetails,
+) -> Value {
+    match resolve_format(format, url) {
+        WebhookFormat::Slack => json!({ "text": message }),
+        WebhookFormat::Discord => json!({ "content": message }),
+        _ => json!({
+            "event": event.name(),
+            "message": message,
+            "details": details,
+        }),
+    }
+}
+
+/// POST a payload to the webhook.
+fn post_webhook(url: &str, payload: &Value) -> Result<(), String> {
+    let client = Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(url)
+        .json(payload)
+        .send()
+        .map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status()))
+    }
+}
+
+/// Show a native desktop notification.
+fn desktop_notification(message: &str) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(message),
+            applescript_string(DESKTOP_TITLE)
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.args([DESKTOP_TITLE, message]);
+        command
+    } else {
+        return Err("not supported on this platform".to_string());
+    };
+    let status = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {status}"))
+    }
+}
+
+/// Quote a string for AppleScript.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn webhook_config() -> NotificationsConfig {
+        NotificationsConfig {
+            webhook_url: Some("http://127.0.0.1:9/hook".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_notifier_needs_a_destination() {
+        if std::env::var(WEBHOOK_ENV).is_err() {
+            assert!(Notifier::new(&NotificationsConfig::default()).is_none());
+        }
+        assert!(Notifier::new(&webhook_config()).is_some());
+        let desktop = NotificationsConfig {
+            desktop: true,
+            ..Default::default()
+        };
+        assert!(Notifier::new(&desktop).is_some());
+    }
+
+    #[test]
+    fn test_render_default_and_custom_messages() {
+        let config = NotificationsConfig::default();
+        let run_end = details(&[
+            ("reason", json!("All tasks completed")),
+            ("iterations", json!(7)),
+            ("tasks_completed", json!(3)),
+        ]);
+        assert_eq!(
+            render_message(&config, NotifyEvent::RunEnd, &run_end).unwrap(),
+            "afk run ended: All tasks completed (3 task(s) completed in 7 iteration(s))"
+        );
+
+        let mut custom = config.clone();
+        custom.templates.insert(
+            "run_end".to_string(),
+            ":tada: {{ event }}: {{ reason }}".to_string(),
+        );
+        assert_eq!(
+            render_message(&custom, NotifyEvent::RunEnd, &run_end).unwrap(),
+            ":tada: run_end: All tasks completed"
+        );
+
+        custom
+            .templates
+            .insert("run_end".to_string(), "{{ missing }}".to_string());
+        assert!(render_message(&custom, NotifyEvent::RunEnd, &run_end).is_err());
+    }
+
+    #[test]
+    fn test_webhook_payload_formats() {
+        let details = details(&[("task_id", json!("auth-1"))]);
+        let slack = "https://hooks.slack.com/services/T/B/X";
+        let discord = "https://discord.com/api/webhooks/1/abc";
+        let other = "https://example.com/afk";
+
+        assert_eq!(
+            webhook_payload(
+                WebhookFormat::Auto,
+                slack,
+                NotifyEvent::Stalled,
+                "hi",
+                &details
+            ),
+            json!({"text": "hi"})
+        );
+        assert_eq!(
+            webhook_payload(
+                WebhookFormat::Auto,
+                discord,
+                NotifyEvent::Stalled,
+                "hi",
+                &details
+            ),
+            json!({"content": "hi"})
+        );
+        assert_eq!(
+            webhook_payload(
+                WebhookFormat::Auto,
+                other,
+                NotifyEvent::Stalled,
+                "hi",
+                &details
+            ),
+            json!({"event": "stalled", "message": "hi", "details": {"task_id": "auth-1"}})
+        );
+        assert_eq!(
+            resolve_format(WebhookFormat::Slack, other),
+            WebhookFormat::Slack
+        );
+    }
+
+    #[test]
+    fn test_thresholds_fire_once() {
+        let mut notifier = Notifier::new(&NotificationsConfig {
+            stall_iterations: 2,
+            ..webhook_config()
+        })
+        .unwrap();
+        let task = Some(("auth-1", "Login"));
+        let events = |due: Vec<(NotifyEvent, NotifyDetails)>| -> Vec<NotifyEvent> {
+            due.into_iter().map(|(event, _)| event).collect()
+        };
+
+        assert!(events(notifier.due_after_iteration(task, (1, 2), 3, false)).is_empty());
+        assert_eq!(
+            events(notifier.due_after_iteration(task, (2, 3), 3, false)),
+            vec![NotifyEvent::MaxFailures, NotifyEvent::Stalled]
+        );
+        assert!(events(notifier.due_after_iteration(task, (3, 4), 3, false)).is_empty());
+        assert!(events(notifier.due_after_iteration(task, (4, 4), 3, true)).is_empty());
+        assert!(events(notifier.due_after_iteration(task, (4, 4), 3, false)).is_empty());
+        assert_eq!(
+            events(notifier.due_after_iteration(task, (4, 4), 3, false)),
+            vec![NotifyEvent::Stalled]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_webhook_is_a_warning() {
+        let notifier = Notifier::new(&webhook_config()).unwrap();
+        let warnings = notifier.run_end("User interrupted", 1, 0);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Notification webhook failed"));
+    }
+}
@@ -0,0 +1,376 @@
+//! Multi-project fleets.
+//!
+//! A fleet is a directory holding several afk projects (repositories with
+//! `.afk/`), listed in an `afk-fleet.json` at its root. `afk fleet init`
+//! writes the file from the projects it finds, and `afk fleet status`,
+//! `afk fleet sync` and `afk fleet go` run the matching afk command in each
+//! project, as a child process started in the project's directory, and
+//! report across them all.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::{AFK_DIR, EVENTS_FILE};
+use crate::events::{run_summary, RunSummary};
+
+/// Name of the fleet config file at the fleet root.
+pub const FLEET_FILE: &str = "afk-fleet.json";
+
+/// Where each project's `afk fleet go` output is written, relative to it.
+pub const FLEET_LOG: &str = ".afk/fleet-go.log";
+
+/// Directories never searched for projects.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+/// Error type for fleet operations.
+#[derive(Debug, thiserror::Error)]
+pub enum FleetError {
+    /// There is no fleet config file.
+    #[error("No {FLEET_FILE} in {0}. Run `afk fleet init` to create one.")]
+    NotFound(String),
+    /// Failed to read or write the fleet config file.
+    #[error("Failed to access {FLEET_FILE}: {0}")]
+    IoError(#[from] io::Error),
+    /// The fleet config file is not valid JSON.
+    #[error("Failed to parse {FLEET_FILE}: {0}")]
+    ParseError(#[from] serde_json::Error),
+}
+
+/// The fleet config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FleetConfig {
+    /// Project directories, relative to the fleet root.
+    pub projects: Vec<String>,
+    /// Projects `afk fleet go` runs at once.
+    #[serde(default = "default_max_parallel")]
+    pub max_parallel: usize,
+    /// Extra arguments for `afk go` in every project.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub go_args: Vec<String>,
+}
+
+fn default_max_parallel() -> usize {
+    1
+}
+
+impl Default for FleetConfig {
+    fn default() -> Self {
+        Self {
+            projects: Vec::new(),
+            max_parallel: default_max_parallel(),
+            go_args: Vec::new(),
+        }
+    }
+}
+
+impl FleetConfig {
+    /// Load the fleet config from `root`.
+    pub fn load(root: &Path) -> Result<Self, FleetError> {
+        let path = root.join(FLEET_FILE);
+        if !path.exists() {
+            return Err(FleetError::NotFound(root.display().to_string()));
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Write the fleet config to `root`.
+    pub fn save(&self, root: &Path) -> Result<(), FleetError> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(root.join(FLEET_FILE), format!("{json}\n"))?;
+        Ok(())
+    }
+}
+
+/// Directories under `root` holding an afk project, relative to it and
+/// sorted, searching at most `max_depth` levels down. Projects are not
+/// searched for nested projects, and hidden and build directories are
+/// skipped.
+pub fn discover(root: &Path, max_depth: usize) -> Vec<String> {
+    let mut found = Vec::new();
+    discover_in(root, root, max_depth, &mut found);
+    found.sort();
+    found
+}
+
+fn discover_in(root: &Path, dir: &Path, depth: usize, found: &mut Vec<String>) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+        let path = entry.path();
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        if path.join(AFK_DIR).is_dir() {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            found.push(relative.to_string_lossy().replace('\\', "/"));
+        } else {
+            discover_in(root, &path, depth - 1, found);
+        }
+    }
+}
+
+/// How an afk command went in one project.
+#[derive(Debug, Clone)]
+pub struct ProjectRun {
+    /// Project directory, as listed in the fleet config.
+    pub project: String,
+    /// Whether the command exited successfully.
+    pub success: bool,
+    /// The command's standard output.
+    pub output: String,
+    /// Why it failed, if it did.
+    pub error: Option<String>,
+    /// How long it ran.
+    pub duration: Duration,
+}
+
+/// Path of the running afk binary, for starting it in each project.
+fn afk_exe() -> PathBuf {
+    std::env::current_exe().unwrap_or_else(|_| PathBuf::from("afk"))
+}
+
+/// Run afk with `args` in a project, keeping its output.
+///
+/// With `log`, output goes to that file in the project instead.
+fn run_afk(root: &Path, project: &str, args: &[String], log: Option<&str>) -> ProjectRun {
+    let dir = root.join(project);
+    let start = Instant::now();
+    let mut command = Command::new(afk_exe());
+    command.args(args).current_dir(&dir).stdin(Stdio::null());
+    if let Some(log) = log {
+        match File::create(dir.join(log)).and_then(|f| Ok((f.try_clone()?, f))) {
+            Ok((out, err)) => {
+                command.stdout(out).stderr(err);
+            }
+            Err(e) => return failed(project, format!("could not write {log}: {e}"), start),
+        }
+    }
+    let output = match command.output() {
+        Ok(output) => output,
+        Err(e) => return failed(project, format!("could not start afk: {e}"), start),
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    ProjectRun {
+        project: project.to_string(),
+        success: output.status.success(),
+        output: String::from_utf8_lossy(&output.stdout).into_owned(),
+        error: (!output.status.success()).then(|| {
+            stderr
+                .lines()
+                .last()
+                .map(String::from)
+                .unwrap_or_else(|| format!("exited with {}", output.status))
+        }),
+        duration: start.elapsed(),
+    }
+}
+
+fn failed(project: &str, error: String, start: Instant) -> ProjectRun {
+    ProjectRun {
+        project: project.to_string(),
+        success: false,
+        output: String::new(),
+        error: Some(error),
+        duration: start.elapsed(),
+    }
+}
+
+/// Run `job` for each project, `max_parallel` at a time, calling
+/// `on_done` as each finishes. Results are in project order.
+fn for_each_project(
+    projects: &[String],
+    max_parallel: usize,
+    job: impl Fn(&str) -> ProjectRun + Sync,
+    on_done: impl Fn(&ProjectRun) + Sync,
+) -> Vec<ProjectRun> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<ProjectRun>>> = Mutex::new(vec![None; projects.len()]);
+    thread::scope(|scope| {
+        for _ in 0..max_parallel.clamp(1, projects.len().max(1)) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let Some(project) = projects.get(i) else {
+                    break;
+                };
+                let run = job(project);
+                on_done(&run);
+                if let Ok(mut results) = results.lock() {
+                    results[i] = Some(run);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// `afk status --json` of each project, or why it couldn't be read.
+pub fn statuses(root: &Path, config: &FleetConfig) -> Vec<(String, Result<Value, String>)> {
+    let args = ["status".to_string(), "--json".to_string()];
+    for_each_project(
+        &config.projects,
+        config.projects.len(),
+        |project| run_afk(root, project, &args, None),
+        |_| {},
+    )
+    .into_iter()
+    .map(|run| {
+        let status = match run.error {
+            Some(error) => Err(error),
+            None => serde_json::from_str(&run.output).map_err(|e| e.to_string()),
+        };
+        (run.project, status)
+    })
+    .collect()
+}
+
+/// Run `afk sync` in each project, one after another.
+pub fn sync(
+    root: &Path,
+    config: &FleetConfig,
+    on_done: impl Fn(&ProjectRun) + Sync,
+) -> Vec<ProjectRun> {
+    let args = ["sync".to_string()];
+    for_each_project(
+        &config.projects,
+        1,
+        |project| run_afk(root, project, &args, None),
+        on_done,
+    )
+}
+
+/// Run `afk go` in each project, `max_parallel` at a time, writing each
+/// project's output to [`FLEET_LOG`] in it.
+pub fn go(
+    root: &Path,
+    config: &FleetConfig,
+    iterations: Option<u32>,
+    max_parallel: usize,
+    on_done: impl Fn(&ProjectRun) + Sync,
+) -> Vec<ProjectRun> {
+    let args = go_args(config, iterations);
+    for_each_project(
+        &config.projects,
+        max_parallel,
+        |project| run_afk(root, project, &args, Some(FLEET_LOG)),
+        on_done,
+    )
+}
+
+/// Arguments for `afk go` in each project. The TUI needs a terminal of
+/// its own, so feedback is off unless `go_args` picks a mode.
+fn go_args(config: &FleetConfig, iterations: Option<u32>) -> Vec<String> {
+    let mut args = vec!["go".to_string()];
+    if let Some(iterations) = iterations {
+        args.push(iterations.to_string());
+    }
+    if !config.go_args.iter().any(|a| a.starts_with("--feedback")) {
+        args.extend(["--feedback".to_string(), "off".to_string()]);
+    }
+    args.extend(config.go_args.iter().cloned());
+    args
+}
+
+/// What the event log of a project records about its latest run.
+pub fn project_summary(root: &Path, project: &str) -> RunSummary {
+    run_summary(&root.join(project).join(EVENTS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_finds_projects() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        for dir in [
+            "api/.afk",
+            "apps/web/.afk",
+            "apps/web/packages/inner/.afk",
+            "node_modules/pkg/.afk",
+            ".hidden/.afk",
+            "docs",
+            "deep/a/b/c/.afk",
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        assert_eq!(discover(root, 3), vec!["api", "apps/web"]);
+        assert_eq!(discover(root, 1), vec!["api"]);
+        assert_eq!(discover(root, 4).len(), 3);
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        let temp = TempDir::new().unwrap();
+        assert!(matches!(
+            FleetConfig::load(temp.path()),
+            Err(FleetError::NotFound(_))
+        ));
+        let config = FleetConfig {
+            projects: vec!["api".to_string(), "web".to_string()],
+            max_parallel: 2,
+            go_args: Vec::new(),
+        };
+        config.save(temp.path()).unwrap();
+        assert_eq!(FleetConfig::load(temp.path()).unwrap(), config);
+
+        fs::write(temp.path().join(FLEET_FILE), r#"{"projects": ["x"]}"#).unwrap();
+        assert_eq!(FleetConfig::load(temp.path()).unwrap().max_parallel, 1);
+    }
+
+    #[test]
+    fn test_go_args() {
+        let mut config = FleetConfig::default();
+        assert_eq!(go_args(&config, Some(5)), ["go", "5", "--feedback", "off"]);
+        config.go_args = vec!["--feedback".to_string(), "minimal".to_string()];
+        assert_eq!(go_args(&config, None), ["go", "--feedback", "minimal"]);
+    }
+
+    #[test]
+    fn test_for_each_project_keeps_order() {
+        let projects: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let done = AtomicUsize::new(0);
+        let runs = for_each_project(
+            &projects,
+            3,
+            |project| ProjectRun {
+                project: project.to_string(),
+                success: project != "2",
+                output: String::new(),
+                error: None,
+                duration: Duration::ZERO,
+            },
+            |_| {
+                done.fetch_add(1, Ordering::SeqCst);
+            },
+        );
+        assert_eq!(done.load(Ordering::SeqCst), 5);
+        let names: Vec<&str> = runs.iter().map(|r| r.project.as_str()).collect();
+        assert_eq!(names, ["0", "1", "2", "3", "4"]);
+        assert!(!runs[2].success);
+    }
+}
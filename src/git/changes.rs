@@ -0,0 +1,363 @@
+//! Files an iteration changed, as git sees them.
+//!
+//! A [`Snapshot`] of `git status --porcelain` is taken before an iteration,
+//! and [`Snapshot::changes`] compares the repository against it afterwards:
+//! every file that differs from the commit the iteration started at, whether
+//! committed since or not, less the files that were already dirty before
+//! and are still exactly as they were. Line counts are from that commit.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// How a file changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    /// The file is new.
+    Added,
+    /// The file was changed.
+    Modified,
+    /// The file was removed.
+    Deleted,
+}
+
+impl FileStatus {
+    /// One-character marker, as in `git status --short`.
+    pub fn marker(self) -> char {
+        match self {
+            FileStatus::Added => 'A',
+            FileStatus::Modified => 'M',
+            FileStatus::Deleted => 'D',
+        }
+    }
+}
+
+/// A file changed during an iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Path relative to the repository root.
+    pub path: String,
+    /// How it changed.
+    pub status: FileStatus,
+    /// Lines added (0 for binary files).
+    pub insertions: u32,
+    /// Lines removed (0 for binary files).
+    pub deletions: u32,
+}
+
+/// The state of a repository's working tree at one moment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Repository root the snapshot was taken in.
+    root: PathBuf,
+    /// HEAD commit, or None before the first commit.
+    head: Option<String>,
+    /// Dirty paths, with the blob hash of their contents (None if deleted).
+    dirty: HashMap<String, Option<String>>,
+}
+
+impl Snapshot {
+    /// Snapshot the repository in the current directory, or None outside a
+    /// git repository.
+    pub fn take() -> Option<Self> {
+        Self::take_in(Path::new("."))
+    }
+
+    /// Snapshot the repository containing `dir`.
+    pub fn take_in(dir: &Path) -> Option<Self> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(root.trim());
+        let head =
+            git(&root, &["rev-parse", "--verify", "-q", "HEAD"]).map(|h| h.trim().to_string());
+        let status = git(
+            &root,
+            &["status", "--porcelain", "-z", "--untracked-files=all"],
+        )?;
+        let paths = parse_status(&status);
+        let dirty = hash_paths(&root, &paths);
+        Some(Self { root, head, dirty })
+    }
+
+    /// Paths that were dirty when the snapshot was taken, sorted.
+    pub fn dirty_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.dirty.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    /// Files changed since the snapshot was taken, sorted by path.
+    pub fn changes(&self) -> Vec<FileChange> {
+        let Some(after) = Self::take_in(&self.root) else {
+            return Vec::new();
+        };
+
+        let mut changes: HashMap<String, FileChange> = HashMap::new();
+        if let Some(ref head) = self.head {
+            let numstat = git(
+                &self.root,
+                &[
+                    "diff",
+                    "--no-renames",
+                    "--no-ext-diff",
+                    "--raw",
+                    "--numstat",
+                    head,
+                ],
+            )
+            .unwrap_or_default();
+            for change in parse_raw_numstat(&numstat) {
+                changes.insert(change.path.clone(), change);
+            }
+        }
+        // Untracked files, and everything before the first commit
+        for path in after.dirty.keys() {
+            if changes.contains_key(path) || !self.root.join(path).is_file() {
+                continue;
+            }
+            if self.head.is_some() && !is_untracked(&self.root, path) {
+                continue;
+            }
+            let contents = std::fs::read(self.root.join(path)).unwrap_or_default();
+            changes.insert(
+                path.clone(),
+                FileChange {
+                    path: path.clone(),
+                    status: FileStatus::Added,
+                    insertions: line_count(&contents),
+                    deletions: 0,
+                },
+            );
+        }
+
+        let mut changes: Vec<FileChange> = changes
+            .into_values()
+            .filter(|change| !change.path.starts_with(".afk/"))
+            // Already dirty and untouched since
+            .filter(
+                |change| match (self.dirty.get(&change.path), after.dirty.get(&change.path)) {
+                    (Some(before), Some(now)) => before != now,
+                    _ => true,
+                },
+            )
+            .collect();
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        changes
+    }
+}
+
+/// Run git in `dir`, returning its stdout if it succeeded.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether `path` is untracked and not ignored.
+fn is_untracked(root: &Path, path: &str) -> bool {
+    git(root, &["ls-files", "--error-unmatch", "--", path]).is_none()
+}
+
+/// Blob hashes of `paths`, None for paths that no longer exist.
+fn hash_paths(root: &Path, paths: &[String]) -> HashMap<String, Option<String>> {
+    let existing: Vec<&str> = paths
+        .iter()
+        .filter(|p| root.join(p).is_file())
+        .map(String::as_str)
+        .collect();
+    let mut args = vec!["hash-object", "--"];
+    args.extend(&existing);
+    let hashes: Vec<String> = if existing.is_empty() {
+        Vec::new()
+    } else {
+        git(root, &args)
+            .map(|out| out.lines().map(String::from).collect())
+            .unwrap_or_default()
+    };
+
+    let mut by_path: HashMap<String, Option<String>> =
+        paths.iter().map(|p| (p.clone(), None)).collect();
+    for (path, hash) in existing.iter().zip(hashes) {
+        by_path.insert(path.to_string(), Some(hash));
+    }
+    by_path
+}
+
+/// Paths in `git status --porcelain -z` output. A rename's entry is
+/// followed by its old path, which is left out.
+fn parse_status(output: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut entries = output.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        let Some(path) = entry.get(3..) else {
+            continue;
+        };
+        paths.push(path.to_string());
+        if entry.starts_with('R') || entry.starts_with('C') {
+            entries.next();
+        }
+    }
+    paths
+}
+
+/// Changes in `git diff --raw --numstat` output: the raw lines give each
+/// file's status, the numstat lines its line counts.
+fn parse_raw_numstat(output: &str) -> Vec<FileChange> {
+    let mut statuses: HashMap<&str, FileStatus> = HashMap::new();
+    let mut changes = Vec::new();
+    for line in output.lines() {
+        if let Some(raw) = line.strip_prefix(':') {
+            // :100644 100644 <sha> <sha> M\tpath
+            let Some((meta, path)) = raw.split_once('\t') else {
+                continue;
+            };
+            let status = match meta.rsplit(' ').next() {
+                Some("A") => FileStatus::Added,
+                Some("D") => FileStatus::Deleted,
+                _ => FileStatus::Modified,
+            };
+            statuses.insert(path, status);
+            continue;
+        }
+        let mut parts = line.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        // Binary files show "-" for both counts
+        changes.push(FileChange {
+            path: path.to_string(),
+            status: FileStatus::Modified,
+            insertions: added.parse().unwrap_or(0),
+            deletions: removed.parse().unwrap_or(0),
+        });
+    }
+    for change in &mut changes {
+        if let Some(status) = statuses.get(change.path.as_str()) {
+            change.status = *status;
+        }
+    }
+    changes
+}
+
+/// Lines in a new file, or 0 if it looks binary.
+fn line_count(contents: &[u8]) -> u32 {
+    if contents.contains(&0) {
+        return 0;
+    }
+    let newlines = contents.iter().filter(|&&b| b == b'\n').count();
+    let unterminated = !contents.is_empty() && !contents.ends_with(b"\n");
+    (newlines + usize::from(unterminated)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn run(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=afk", "-c", "user.email=afk@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let output = " M src/lib.rs\0?? notes.txt\0R  new.rs\0old.rs\0 D gone.rs\0";
+        assert_eq!(
+            parse_status(output),
+            ["src/lib.rs", "notes.txt", "new.rs", "gone.rs"]
+        );
+        assert!(parse_status("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_raw_numstat() {
+        let output = ":100644 100644 abc1234 def5678 M\tsrc/lib.rs\n\
+                      :000000 100644 0000000 abc1234 A\tsrc/new.rs\n\
+                      :100644 000000 abc1234 0000000 D\told.rs\n\
+                      :100644 100644 abc1234 def5678 M\tlogo.png\n\
+                      3\t1\tsrc/lib.rs\n\
+                      10\t0\tsrc/new.rs\n\
+                      0\t7\told.rs\n\
+                      -\t-\tlogo.png\n";
+        let changes = parse_raw_numstat(output);
+        assert_eq!(
+            changes[0],
+            FileChange {
+                path: "src/lib.rs".to_string(),
+                status: FileStatus::Modified,
+                insertions: 3,
+                deletions: 1,
+            }
+        );
+        assert_eq!(changes[1].status, FileStatus::Added);
+        assert_eq!(changes[2].status, FileStatus::Deleted);
+        assert_eq!(changes[2].deletions, 7);
+        assert_eq!((changes[3].insertions, changes[3].deletions), (0, 0));
+    }
+
+    #[test]
+    fn test_line_count() {
+        assert_eq!(line_count(b""), 0);
+        assert_eq!(line_count(b"one\ntwo\n"), 2);
+        assert_eq!(line_count(b"one\ntwo"), 2);
+        assert_eq!(line_count(b"\x89PNG\0\n"), 0);
+    }
+
+    #[test]
+    fn test_changes_since_snapshot() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        run(dir, &["init", "-q"]);
+        fs::write(dir.join("kept.rs"), "a\nb\n").unwrap();
+        fs::write(dir.join("edited.rs"), "a\nb\n").unwrap();
+        fs::write(dir.join("gone.rs"), "a\n").unwrap();
+        run(dir, &["add", "."]);
+        run(dir, &["commit", "-q", "-m", "init"]);
+        // Dirty before the iteration, and left alone
+        fs::write(dir.join("kept.rs"), "a\nb\nc\n").unwrap();
+        fs::write(dir.join("scratch.txt"), "notes\n").unwrap();
+
+        let before = Snapshot::take_in(dir).unwrap();
+        fs::write(dir.join("edited.rs"), "a\nB\nc\n").unwrap();
+        fs::remove_file(dir.join("gone.rs")).unwrap();
+        fs::write(dir.join("new.rs"), "x\ny\nz\n").unwrap();
+        run(dir, &["add", "edited.rs", "gone.rs"]);
+        run(dir, &["commit", "-q", "-m", "work"]);
+
+        let changes = before.changes();
+        let summary: Vec<(&str, FileStatus, u32, u32)> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.status, c.insertions, c.deletions))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("edited.rs", FileStatus::Modified, 2, 1),
+                ("gone.rs", FileStatus::Deleted, 0, 1),
+                ("new.rs", FileStatus::Added, 3, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_outside_repo() {
+        let temp = TempDir::new().unwrap();
+        assert!(Snapshot::take_in(temp.path()).is_none());
+    }
+}
@@ -0,0 +1,210 @@
+//! Submodule and Git LFS awareness.
+//!
+//! Reads `.gitmodules` and the `filter=lfs` patterns in `.gitattributes`
+//! so afk can tell submodule pointers and LFS-tracked files apart from
+//! ordinary paths: commits leave unrelated submodule pointer changes out,
+//! and the files panel labels both kinds.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::path_matcher::glob_matches;
+
+/// What kind of path a changed file is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    /// An ordinary tracked file.
+    Regular,
+    /// A submodule (its pointer) or a path inside one.
+    Submodule,
+    /// A file stored with Git LFS.
+    Lfs,
+}
+
+impl PathKind {
+    /// Short label shown next to the path, if it isn't a regular file.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Self::Regular => None,
+            Self::Submodule => Some("submodule"),
+            Self::Lfs => Some("lfs"),
+        }
+    }
+}
+
+/// Submodules and LFS patterns of a repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoLayout {
+    submodules: Vec<String>,
+    lfs_patterns: Vec<String>,
+}
+
+impl RepoLayout {
+    /// Read the layout of the repository in the current directory.
+    pub fn load() -> Self {
+        Self::load_at(Path::new("."))
+    }
+
+    /// Read the layout of the repository rooted at `root`. Missing files
+    /// mean no submodules or no LFS patterns.
+    pub fn load_at(root: &Path) -> Self {
+        let read = |name: &str| fs::read_to_string(root.join(name)).unwrap_or_default();
+        Self::parse(&read(".gitmodules"), &read(".gitattributes"))
+    }
+
+    /// Build a layout from the contents of `.gitmodules` and `.gitattributes`.
+    pub fn parse(gitmodules: &str, gitattributes: &str) -> Self {
+        let submodules = gitmodules
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "path").then(|| value.trim().trim_end_matches('/').to_string())
+            })
+            .filter(|path| !path.is_empty())
+            .collect();
+        let lfs_patterns = gitattributes
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = parts.next()?;
+                parts
+                    .any(|attr| attr == "filter=lfs")
+                    .then(|| pattern.trim_start_matches('/').to_string())
+            })
+            .collect();
+        Self {
+            submodules,
+            lfs_patterns,
+        }
+    }
+
+    /// Paths of the repository's submodules.
+    pub fn submodules(&self) -> &[String] {
+        &self.submodules
+    }
+
+    /// Whether the repository has any submodules.
+    pub fn has_submodules(&self) -> bool {
+        !self.submodules.is_empty()
+    }
+
+    /// Classify a path relative to the repository root.
+    pub fn kind(&self, path: &str) -> PathKind {
+        let path = path.trim_start_matches("./");
+        let in_submodule = self.submodules.iter().any(|sub| {
+            path == sub
+                || path
+                    .strip_prefix(sub.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        });
+        if in_submodule {
+            return PathKind::Submodule;
+        }
+        if self.lfs_patterns.iter().any(|p| glob_matches(p, path)) {
+            return PathKind::Lfs;
+        }
+        PathKind::Regular
+    }
+
+    /// Staged submodule pointers a commit shouldn't include: those whose
+    /// path isn't named in the task ID or commit message.
+    pub fn unrelated_submodules(
+        &self,
+        staged: &[String],
+        task_id: &str,
+        message: &str,
+    ) -> Vec<String> {
+        staged
+            .iter()
+            .filter(|path| self.submodules.contains(path))
+            .filter(|path| !task_id.contains(path.as_str()) && !message.contains(path.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Run `git submodule update --init --recursive`.
+pub fn update_submodules() -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["submodule", "update", "--init", "--recursive"])
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(stderr
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or("git submodule update failed")
+        .trim()
+        .to_string())
+}
+
+/// Remove paths from the index, keeping their working tree changes.
+pub fn unstage(paths: &[String]) -> bool {
+    if paths.is_empty() {
+        return true;
+    }
+    Command::new("git")
+        .args(["reset", "-q", "--"])
+        .args(paths)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GITMODULES: &str = r#"[submodule "vendor/lib"]
+	path = vendor/lib
+	url = https://example.com/lib.git
+[submodule "docs"]
+	path = docs-site
+	url = ../docs.git
+"#;
+
+    const GITATTRIBUTES: &str = "# Binary assets\n*.psd filter=lfs diff=lfs merge=lfs -text\n\
+                                 /assets/** filter=lfs diff=lfs merge=lfs -text\n\
+                                 data/*.csv filter=lfs\n*.md text\n";
+
+    #[test]
+    fn test_parse_and_classify() {
+        let layout = RepoLayout::parse(GITMODULES, GITATTRIBUTES);
+        assert_eq!(layout.submodules(), ["vendor/lib", "docs-site"]);
+        assert!(layout.has_submodules());
+
+        assert_eq!(layout.kind("vendor/lib"), PathKind::Submodule);
+        assert_eq!(layout.kind("vendor/lib/src/a.c"), PathKind::Submodule);
+        assert_eq!(layout.kind("vendor/library.c"), PathKind::Regular);
+        assert_eq!(layout.kind("art/logo.psd"), PathKind::Lfs);
+        assert_eq!(layout.kind("assets/img/hero.png"), PathKind::Lfs);
+        assert_eq!(layout.kind("data/users.csv"), PathKind::Lfs);
+        assert_eq!(layout.kind("data/raw/users.csv"), PathKind::Regular);
+        assert_eq!(layout.kind("README.md"), PathKind::Regular);
+        assert_eq!(PathKind::Lfs.label(), Some("lfs"));
+        assert_eq!(PathKind::Regular.label(), None);
+
+        assert_eq!(RepoLayout::parse("", ""), RepoLayout::default());
+    }
+
+    #[test]
+    fn test_unrelated_submodules() {
+        let layout = RepoLayout::parse(GITMODULES, "");
+        let staged = vec![
+            "src/main.rs".to_string(),
+            "vendor/lib".to_string(),
+            "docs-site".to_string(),
+        ];
+        assert_eq!(
+            layout.unrelated_submodules(&staged, "deps-1", "Bump vendor/lib to 2.0"),
+            vec!["docs-site".to_string()]
+        );
+    }
+}
@@ -1,8 +1,65 @@
 //! Git operations.
 //!
-//! This module handles branching, committing, and status checks.
+//! This module handles branching, committing, and status checks, knows
+//! which paths are submodules or stored with Git LFS, works out which
+//! files an iteration changed, and opens pull requests for completed tasks.
 
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::RwLock;
+
+pub mod changes;
+mod layout;
+pub mod pr;
+pub mod squash;
+
+pub use layout::{unstage, update_submodules, PathKind, RepoLayout};
+
+/// Identity for commits afk makes, installed from the `git` config.
+static COMMIT_IDENTITY: RwLock<Option<CommitIdentity>> = RwLock::new(None);
+
+/// Name and email that commits made during the loop are attributed to.
+///
+/// A part left unset falls back to the repository's git config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitIdentity {
+    /// Author and committer name.
+    pub name: Option<String>,
+    /// Author and committer email.
+    pub email: Option<String>,
+}
+
+impl CommitIdentity {
+    /// Git environment variables that apply this identity to a process.
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let mut env = Vec::new();
+        if let Some(name) = &self.name {
+            env.push(("GIT_AUTHOR_NAME", name.clone()));
+            env.push(("GIT_COMMITTER_NAME", name.clone()));
+        }
+        if let Some(email) = &self.email {
+            env.push(("GIT_AUTHOR_EMAIL", email.clone()));
+            env.push(("GIT_COMMITTER_EMAIL", email.clone()));
+        }
+        env
+    }
+}
+
+/// Set the identity for commits afk makes in this process.
+pub fn set_commit_identity(identity: Option<CommitIdentity>) {
+    if let Ok(mut current) = COMMIT_IDENTITY.write() {
+        *current = identity;
+    }
+}
+
+/// Environment for git commands that create commits.
+fn identity_env() -> Vec<(&'static str, String)> {
+    COMMIT_IDENTITY
+        .read()
+        .ok()
+        .and_then(|identity| identity.as_ref().map(CommitIdentity::env))
+        .unwrap_or_default()
+}
 
 /// Check if the current directory is a git repository.
 pub fn is_git_repo() -> bool {
@@ -51,6 +108,17 @@ pub fn create_branch(name: &str) -> bool {
     create.map(|o| o.status.success()).unwrap_or(false)
 }
 
+/// Checkout an existing branch, carrying uncommitted changes over.
+///
+/// Returns true if successful.
+pub fn checkout(name: &str) -> bool {
+    Command::new("git")
+        .args(["checkout", name])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
 /// Check if there are uncommitted changes.
 pub fn has_uncommitted_changes() -> bool {
     let output = Command::new("git").args(["status", "--porcelain"]).output();
@@ -92,14 +160,36 @@ pub fn stage_all() -> bool {
 pub fn commit(message: &str) -> bool {
     Command::new("git")
         .args(["commit", "-m", message])
+        .envs(identity_env())
         .output()
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
 
+/// Stage and commit everything in the worktree at `dir`.
+///
+/// Returns true if a commit was made; false if there was nothing to commit
+/// or it failed.
+pub fn commit_all_in(dir: &Path, message: &str) -> bool {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .envs(identity_env())
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+    };
+    git(&["add", "-A"]).is_some()
+        && git(&["diff", "--cached", "--quiet"]).is_none()
+        && git(&["commit", "-m", message]).is_some()
+}
+
 /// Auto-commit with a conventional commit message.
 ///
-/// Format: `feat: [task_id] - message`
+/// Format: `feat: [task_id] - message`. Submodule pointer changes are
+/// committed only when the task ID or message names the submodule.
 pub fn auto_commit(task_id: &str, message: &str) -> bool {
     let commit_msg = if message.is_empty() {
         format!("feat: {task_id}")
@@ -112,6 +202,15 @@ pub fn auto_commit(task_id: &str, message: &str) -> bool {
         return false;
     }
 
+    // Leave submodule pointer changes out unless the commit is about them
+    let layout = RepoLayout::load();
+    if layout.has_submodules() {
+        let unrelated = layout.unrelated_submodules(&get_staged_files(), task_id, message);
+        if !unstage(&unrelated) {
+            return false;
+        }
+    }
+
     // Check if there's anything to commit
     if get_staged_files().is_empty() {
         return true; // Nothing to commit is still success
@@ -134,6 +233,548 @@ pub fn get_current_commit_short() -> Option<String> {
     }
 }
 
+/// Get the full hash of the current commit.
+pub fn get_current_commit() -> Option<String> {
+    get_current_commit_in(Path::new("."))
+}
+
+/// Get the full hash of the commit checked out in the worktree at `dir`.
+pub fn get_current_commit_in(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// List commits made since a revision, oldest first.
+pub fn commits_since(rev: &str) -> Vec<String> {
+    let output = match Command::new("git")
+        .args(["rev-list", "--reverse", &format!("{rev}..HEAD")])
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Files changed since a revision: in commits after it, staged, unstaged,
+/// or untracked (and not ignored). Sorted, without duplicates.
+pub fn changed_files_since(rev: &str) -> Vec<String> {
+    changed_files_since_in(Path::new("."), rev)
+}
+
+/// [`changed_files_since`] in the worktree at `dir`.
+pub fn changed_files_since_in(dir: &Path, rev: &str) -> Vec<String> {
+    let list = |args: &[&str]| -> Vec<String> {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| {
+                String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .map(|l| l.trim().to_string())
+                    .filter(|l| !l.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let mut files = list(&["diff", "--name-only", rev]);
+    files.extend(list(&["ls-files", "--others", "--exclude-standard"]));
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Put paths back as they were at a revision, committing the restore with
+/// `message` if any of them had been committed since.
+///
+/// Paths are relative to the repository root, as git reports them, and
+/// those that didn't exist at `rev` are deleted. Returns true if every path
+/// was restored.
+pub fn restore_paths(rev: &str, paths: &[String], message: &str) -> bool {
+    get_repo_root().is_some_and(|root| restore_paths_in(Path::new(&root), rev, paths, message))
+}
+
+/// [`restore_paths`] in the working tree at `dir`, e.g. a worktree.
+pub fn restore_paths_in(dir: &Path, rev: &str, paths: &[String], message: &str) -> bool {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .envs(identity_env())
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+    };
+    let mut restored = true;
+    for path in paths {
+        if git(&["cat-file", "-e", &format!("{rev}:{path}")]).is_some() {
+            restored &= git(&["checkout", rev, "--", path]).is_some();
+        } else {
+            let _ = git(&["rm", "-q", "-f", "--ignore-unmatch", "--", path]);
+            let file = dir.join(path);
+            if file.exists() {
+                restored &= std::fs::remove_file(file).is_ok();
+            }
+        }
+    }
+
+    // Commit restores of changes that had already been committed
+    let mut args = vec!["diff", "--cached", "--name-only", "HEAD", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let staged: Vec<String> = git(&args)
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    if !staged.is_empty() {
+        let mut args = vec!["commit", "-q", "-m", message, "--"];
+        args.extend(staged.iter().map(String::as_str));
+        restored &= git(&args).is_some();
+    }
+    restored
+}
+
+/// Store the current contents of `paths` in the repository as blobs, for
+/// [`restore_saved`], with None for paths that don't exist. Paths are
+/// relative to the repository root. Returns None if git fails.
+pub fn save_paths(paths: &[String]) -> Option<Vec<(String, Option<String>)>> {
+    let root = PathBuf::from(get_repo_root()?);
+    let existing: Vec<&str> = paths
+        .iter()
+        .filter(|p| root.join(p).is_file())
+        .map(String::as_str)
+        .collect();
+    let mut blobs = Vec::new();
+    if !existing.is_empty() {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&root)
+            .args(["hash-object", "-w", "--"])
+            .args(&existing)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())?;
+        blobs = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect();
+    }
+    if blobs.len() != existing.len() {
+        return None;
+    }
+    let mut by_path: std::collections::HashMap<&str, String> =
+        existing.into_iter().zip(blobs).collect();
+    Some(
+        paths
+            .iter()
+            .map(|p| (p.clone(), by_path.remove(p.as_str())))
+            .collect(),
+    )
+}
+
+/// Write back contents stored by [`save_paths`], deleting paths that
+/// didn't exist. Returns true if every path was restored.
+pub fn restore_saved(saved: &[(String, Option<String>)]) -> bool {
+    let Some(root) = get_repo_root().map(PathBuf::from) else {
+        return saved.is_empty();
+    };
+    let mut restored = true;
+    for (path, blob) in saved {
+        let path = root.join(path);
+        restored &= match blob {
+            Some(blob) => Command::new("git")
+                .args(["cat-file", "blob", blob])
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .is_some_and(|o| std::fs::write(&path, o.stdout).is_ok()),
+            None => !path.exists() || std::fs::remove_file(&path).is_ok(),
+        };
+    }
+    restored
+}
+
+/// The patch from a revision to the working tree, without context lines
+/// and leaving `.afk/` out. Untracked files are not included.
+pub fn diff_since(rev: &str) -> Option<String> {
+    diff_since_in(Path::new("."), rev)
+}
+
+/// [`diff_since`] in the worktree at `dir`.
+pub fn diff_since_in(dir: &Path, rev: &str) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--no-color", "--no-ext-diff", "-U0", rev])
+        .args(["--", ".", ":(exclude).afk"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Untracked files that aren't ignored.
+pub fn untracked_files() -> Vec<String> {
+    untracked_files_in(Path::new("."))
+}
+
+/// [`untracked_files`] in the worktree at `dir`, relative to it.
+pub fn untracked_files_in(dir: &Path) -> Vec<String> {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["ls-files", "--others", "--exclude-standard"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Move the current branch back to a revision, keeping the changes since
+/// it in the working tree, unstaged.
+///
+/// Returns true if successful.
+pub fn reset_mixed(rev: &str) -> bool {
+    reset_mixed_in(Path::new("."), rev)
+}
+
+/// [`reset_mixed`] in the worktree at `dir`.
+pub fn reset_mixed_in(dir: &Path, rev: &str) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["reset", "-q", "--mixed", rev])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Stash uncommitted changes, including untracked files, leaving `.afk/`
+/// alone.
+///
+/// Returns true if anything was stashed.
+pub fn stash_changes(message: &str) -> bool {
+    let before = stash_count();
+    let stashed = Command::new("git")
+        .args(["stash", "push", "--include-untracked", "-m", message])
+        .args(["--", ".", ":(exclude).afk"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    stashed && stash_count() > before
+}
+
+/// Number of entries in the stash.
+fn stash_count() -> usize {
+    Command::new("git")
+        .args(["stash", "list"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().count())
+        .unwrap_or(0)
+}
+
+/// Move the current branch and working tree back to a revision.
+///
+/// Returns true if successful.
+pub fn reset_hard(rev: &str) -> bool {
+    Command::new("git")
+        .args(["reset", "--hard", rev])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// The last `count` commits on HEAD as "<short hash> <subject>", newest first.
+pub fn recent_commits(count: usize) -> Vec<String> {
+    let output = match Command::new("git")
+        .args(["log", "--oneline", "--no-decorate", &format!("-{count}")])
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Commits in a revision range as "<short hash> <subject>", oldest first.
+pub fn commit_log(range: &str) -> Vec<String> {
+    let output = match Command::new("git")
+        .args(["log", "--oneline", "--no-decorate", "--reverse", range])
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// A commit's full hash, commit time (Unix seconds) and subject, or None if
+/// it doesn't exist.
+pub fn commit_info(rev: &str) -> Option<(String, i64, String)> {
+    let output = Command::new("git")
+        .args(["show", "-s", "--format=%H%x00%ct%x00%s", rev, "--"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let mut parts = text.trim_end().splitn(3, '\0');
+    let hash = parts.next()?.to_string();
+    let time = parts.next()?.parse().ok()?;
+    Some((hash, time, parts.next().unwrap_or_default().to_string()))
+}
+
+/// One commit as an mbox patch, as `git format-patch --stdout` writes it.
+pub fn format_patch(rev: &str) -> Option<String> {
+    Command::new("git")
+        .args(["format-patch", "--stdout", "-1", rev])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+}
+
+/// A commit's message and patch, as `git show` prints it, without colour.
+pub fn show_commit(rev: &str) -> Option<String> {
+    Command::new("git")
+        .args([
+            "show",
+            "--no-color",
+            "--no-ext-diff",
+            "--stat",
+            "-p",
+            rev,
+            "--",
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+}
+
+/// `git diff --stat` lines between two revisions, ending with the summary.
+pub fn diff_stat(from: &str, to: &str) -> Vec<String> {
+    let output = match Command::new("git")
+        .args(["diff", "--stat", from, to])
+        .output()
+    {
+        Ok(o) if o.status.success() => o,
+        _ => return Vec::new(),
+    };
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+/// Size of a diff in files and lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    /// Number of files changed.
+    pub files: u32,
+    /// Number of lines changed (added + removed).
+    pub lines: u32,
+}
+
+/// Get the size of all changes since a revision.
+///
+/// Compares the working tree against `rev`, so commits made since `rev`,
+/// uncommitted changes to tracked files and new untracked files (those not
+/// ignored) are all counted.
+pub fn diff_stats_since(rev: &str) -> Option<DiffStats> {
+    let output = Command::new("git")
+        .args(["diff", "--numstat", rev])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut stats = parse_numstat(&String::from_utf8_lossy(&output.stdout));
+
+    // `git diff` leaves out files git doesn't know about yet
+    let untracked = Command::new("git")
+        .args(["ls-files", "--others", "--exclude-standard", "-z"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    for path in untracked
+        .stdout
+        .split(|&b| b == 0)
+        .filter(|p| !p.is_empty())
+    {
+        let path = String::from_utf8_lossy(path);
+        stats.files += 1;
+        stats.lines += std::fs::read(path.as_ref()).map_or(0, |bytes| count_lines(&bytes));
+    }
+    Some(stats)
+}
+
+/// Lines in a new file, as `git diff --numstat` would count them.
+///
+/// Binary files (those with a NUL byte) count as no lines.
+fn count_lines(bytes: &[u8]) -> u32 {
+    if bytes.contains(&0) {
+        return 0;
+    }
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    let unterminated = usize::from(bytes.last().is_some_and(|&b| b != b'\n'));
+    u32::try_from(newlines + unterminated).unwrap_or(u32::MAX)
+}
+
+/// Parse `git diff --numstat` output into totals.
+///
+/// Binary files (shown as `-\t-\tpath`) count as a changed file with no lines.
+fn parse_numstat(output: &str) -> DiffStats {
+    output
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .fold(DiffStats::default(), |mut stats, line| {
+            let mut parts = line.split('\t');
+            let added = parts.next().and_then(|n| n.parse::<u32>().ok());
+            let removed = parts.next().and_then(|n| n.parse::<u32>().ok());
+            stats.files += 1;
+            stats.lines += added.unwrap_or(0) + removed.unwrap_or(0);
+            stats
+        })
+}
+
+/// Check out `branch` at HEAD in a new worktree at `path`.
+///
+/// Any stale worktree at the same path is removed first, and the branch is
+/// reset to HEAD if it already exists.
+pub fn add_worktree(path: &Path, branch: &str) -> bool {
+    remove_worktree(path);
+    Command::new("git")
+        .args(["worktree", "add", "-B", branch])
+        .arg(path)
+        .arg("HEAD")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Remove a worktree, discarding any uncommitted changes in it.
+pub fn remove_worktree(path: &Path) -> bool {
+    let removed = Command::new("git")
+        .args(["worktree", "remove", "--force"])
+        .arg(path)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    let _ = Command::new("git").args(["worktree", "prune"]).output();
+    removed
+}
+
+/// Merge a branch into the current branch with a merge commit.
+///
+/// A merge that fails (e.g. on conflicts) is aborted, leaving the current
+/// branch untouched. Returns true if the merge succeeded.
+pub fn merge_branch(branch: &str, message: &str) -> bool {
+    let merged = Command::new("git")
+        .args(["merge", "--no-ff", "--no-edit", "-m", message, branch])
+        .envs(identity_env())
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !merged {
+        let _ = Command::new("git").args(["merge", "--abort"]).output();
+    }
+    merged
+}
+
+/// Fast-forward a worktree's branch to a revision.
+///
+/// Fails without changes if the branch has commits `rev` doesn't contain.
+pub fn fast_forward_worktree(path: &Path, rev: &str) -> bool {
+    Command::new("git")
+        .arg("-C")
+        .arg(path)
+        .args(["merge", "--ff-only", rev])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Delete a branch whether or not it has been merged. Returns true if deleted.
+pub fn delete_branch(branch: &str) -> bool {
+    Command::new("git")
+        .args(["branch", "-D", branch])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// The best common ancestor of two revisions.
+pub fn merge_base(a: &str, b: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["merge-base", a, b])
+        .output()
+        .ok()?;
+    let base = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !base.is_empty()).then_some(base)
+}
+
+/// Whether git tracks any file at or under `path`.
+pub fn is_tracked(path: &str) -> bool {
+    Command::new("git")
+        .args(["ls-files", "--", path])
+        .output()
+        .map(|o| o.status.success() && !o.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+/// Delete a branch if it has been merged. Returns true if deleted.
+pub fn delete_merged_branch(branch: &str) -> bool {
+    Command::new("git")
+        .args(["branch", "-d", branch])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
 /// Get the repository root path.
 pub fn get_repo_root() -> Option<String> {
     let output = Command::new("git")
@@ -223,6 +864,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_numstat() {
+        let output = "10\t2\tsrc/main.rs\n0\t5\tREADME.md\n-\t-\tlogo.png\n";
+        let stats = parse_numstat(output);
+        assert_eq!(
+            stats,
+            DiffStats {
+                files: 3,
+                lines: 17
+            }
+        );
+
+        assert_eq!(parse_numstat(""), DiffStats::default());
+    }
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines(b""), 0);
+        assert_eq!(count_lines(b"one\ntwo\n"), 2);
+        assert_eq!(count_lines(b"one\ntwo"), 2);
+        assert_eq!(count_lines(b"\x89PNG\0\n\n"), 0);
+    }
+
     #[test]
     fn test_has_uncommitted_changes() {
         // This test just verifies the function runs without error
@@ -248,6 +912,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_commit_info() {
+        let (hash, time, _) = commit_info("HEAD").unwrap();
+        assert_eq!(Some(hash), get_current_commit());
+        assert!(time > 0);
+        assert!(commit_info("0000000000000000000000000000000000000000").is_none());
+    }
+
     #[test]
     fn test_get_repo_root() {
         let root = get_repo_root();
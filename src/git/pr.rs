@@ -0,0 +1,253 @@
+//! Pull requests for completed tasks.
+//!
+//! With `git.open_pr`, afk pushes the branch a task was completed on and
+//! opens a pull request through the gh CLI. The title and body are Tera
+//! templates given the task, its acceptance criteria and the quality gate
+//! results; the built-in ones apply when `git.pr_title_template` or
+//! `git.pr_body_template` is unset.
+
+use std::process::Command;
+
+use serde::Serialize;
+use tera::{Context, Tera};
+
+use crate::config::GitConfig;
+use crate::prd::UserStory;
+
+/// Built-in pull request title.
+const DEFAULT_TITLE: &str = "{{ task.id }}: {{ task.title }}";
+
+/// Built-in pull request body.
+const DEFAULT_BODY: &str = r#"{{ task.description }}
+{% if task.acceptance_criteria %}
+## Acceptance criteria
+
+{% for criterion in task.acceptance_criteria -%}
+- [{% if criterion.passed %}x{% else %} {% endif %}] {{ criterion.text }}
+{% endfor -%}
+{% endif %}
+## Quality gates
+
+{% if gates -%}
+{% for gate in gates -%}
+- {% if gate.passed %}✅{% else %}❌{% endif %} {{ gate.name }}
+{% endfor -%}
+{% else -%}
+No quality gates configured.
+{% endif %}
+Opened by afk for task `{{ task.id }}`."#;
+
+/// A quality gate result shown in a pull request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrGate {
+    /// Gate name.
+    pub name: String,
+    /// Whether it passed.
+    pub passed: bool,
+}
+
+/// Title and body of a pull request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    /// Title.
+    pub title: String,
+    /// Markdown body.
+    pub body: String,
+}
+
+/// The task as the templates see it.
+#[derive(Serialize)]
+struct TaskContext<'a> {
+    id: &'a str,
+    title: &'a str,
+    description: &'a str,
+    acceptance_criteria: Vec<CriterionContext<'a>>,
+}
+
+#[derive(Serialize)]
+struct CriterionContext<'a> {
+    text: &'a str,
+    passed: bool,
+}
+
+impl PullRequest {
+    /// Render the title and body for a task completed on `branch`.
+    ///
+    /// `base` is the branch the PR targets, if afk knows it; templates see
+    /// an empty string otherwise.
+    pub fn render(
+        config: &GitConfig,
+        story: &UserStory,
+        gates: &[PrGate],
+        branch: &str,
+        base: Option<&str>,
+    ) -> Result<Self, tera::Error> {
+        let mut context = Context::new();
+        context.insert(
+            "task",
+            &TaskContext {
+                id: &story.id,
+                title: &story.title,
+                description: &story.description,
+                acceptance_criteria: story
+                    .acceptance_criteria
+                    .iter()
+                    .map(|c| CriterionContext {
+                        text: &c.text,
+                        passed: c.passed,
+                    })
+                    .collect(),
+            },
+        );
+        context.insert("gates", gates);
+        context.insert("branch", branch);
+        context.insert("base", base.unwrap_or_default());
+
+        let title = config.pr_title_template.as_deref().unwrap_or(DEFAULT_TITLE);
+        let body = config.pr_body_template.as_deref().unwrap_or(DEFAULT_BODY);
+        Ok(Self {
+            title: Tera::one_off(title, &context, false)?.trim().to_string(),
+            body: Tera::one_off(body, &context, false)?.trim().to_string(),
+        })
+    }
+}
+
+/// Push `head` to origin and open a pull request from it.
+///
+/// Without a `base`, gh targets the repository's default branch. If a pull
+/// request from `head` is already open, its URL is returned instead.
+/// Returns the pull request URL.
+pub fn open(head: &str, base: Option<&str>, pr: &PullRequest) -> Result<String, String> {
+    push(head)?;
+    let mut cmd = Command::new("gh");
+    cmd.args(["pr", "create", "--head", head])
+        .args(["--title", &pr.title])
+        .args(["--body", &pr.body]);
+    if let Some(base) = base {
+        cmd.args(["--base", base]);
+    }
+    let output = cmd.output().map_err(|e| format!("failed to run gh: {e}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        return find_url(&stdout).ok_or_else(|| "gh did not print a pull request URL".to_string());
+    }
+    if stderr.contains("already exists") {
+        if let Some(url) = find_url(&stderr) {
+            return Ok(url);
+        }
+    }
+    Err(last_line(&stderr, "gh pr create failed"))
+}
+
+/// Push a branch to origin, setting its upstream.
+fn push(branch: &str) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(["push", "-u", "origin", branch])
+        .output()
+        .map_err(|e| format!("failed to run git: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(last_line(
+            &String::from_utf8_lossy(&output.stderr),
+            "git push failed",
+        ))
+    }
+}
+
+/// The last URL in gh's output.
+fn find_url(output: &str) -> Option<String> {
+    output
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| line.starts_with("https://") || line.starts_with("http://"))
+        .map(String::from)
+}
+
+/// The last non-empty line of an error output.
+fn last_line(output: &str, fallback: &str) -> String {
+    output
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or(fallback)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::AcceptanceCriterion;
+
+    fn story() -> UserStory {
+        let mut story = UserStory::new("auth-1", "Add login");
+        story.description = "Users can log in.".to_string();
+        let mut met = AcceptanceCriterion::new("Form validates");
+        met.passed = true;
+        story.acceptance_criteria = vec![met, AcceptanceCriterion::new("Docs updated")];
+        story
+    }
+
+    #[test]
+    fn test_render_default() {
+        let gates = vec![
+            PrGate {
+                name: "lint".to_string(),
+                passed: true,
+            },
+            PrGate {
+                name: "test".to_string(),
+                passed: false,
+            },
+        ];
+        let pr = PullRequest::render(&GitConfig::default(), &story(), &gates, "afk/auth-1", None)
+            .unwrap();
+        assert_eq!(pr.title, "auth-1: Add login");
+        assert!(pr.body.starts_with("Users can log in."));
+        assert!(pr
+            .body
+            .contains("- [x] Form validates\n- [ ] Docs updated\n"));
+        assert!(pr.body.contains("- ✅ lint\n- ❌ test\n"));
+        assert!(pr.body.ends_with("Opened by afk for task `auth-1`."));
+
+        let pr =
+            PullRequest::render(&GitConfig::default(), &story(), &[], "afk/auth-1", None).unwrap();
+        assert!(pr.body.contains("No quality gates configured."));
+    }
+
+    #[test]
+    fn test_render_custom_templates() {
+        let config = GitConfig {
+            pr_title_template: Some("[{{ task.id }}] {{ task.title }} into {{ base }}".into()),
+            pr_body_template: Some("From {{ branch }}: {{ gates | length }} gates".into()),
+            ..Default::default()
+        };
+        let pr = PullRequest::render(&config, &story(), &[], "afk/auth-1", Some("main")).unwrap();
+        assert_eq!(pr.title, "[auth-1] Add login into main");
+        assert_eq!(pr.body, "From afk/auth-1: 0 gates");
+
+        let broken = GitConfig {
+            pr_title_template: Some("{{ task.id".into()),
+            ..Default::default()
+        };
+        assert!(PullRequest::render(&broken, &story(), &[], "b", None).is_err());
+    }
+
+    #[test]
+    fn test_find_url() {
+        assert_eq!(
+            find_url("\nhttps://github.com/o/r/pull/7\n").as_deref(),
+            Some("https://github.com/o/r/pull/7")
+        );
+        let exists = "a pull request for branch \"afk/a\" into branch \"main\" already exists:\n\
+                      https://github.com/o/r/pull/3\n";
+        assert_eq!(
+            find_url(exists).as_deref(),
+            Some("https://github.com/o/r/pull/3")
+        );
+        assert_eq!(find_url("Warning: 2 uncommitted changes"), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! Squashing recorded commits into one.
+//!
+//! The loop commits little and often, so a task can end up spread over many
+//! `afk:` commits, sometimes between commits for other tasks. [`plan`] finds
+//! where the commits sit on the current branch and [`squash`] rebases from
+//! just before the oldest of them: the first is picked, the rest are folded
+//! into it where it stands, and the result is reworded. Other commits in the
+//! range are replayed as they were, with new hashes.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::identity_env;
+
+/// Numbers each squash's scratch directory within this process.
+static SCRATCH: AtomicU32 = AtomicU32::new(0);
+
+/// Error type for squash operations.
+#[derive(Debug, thiserror::Error)]
+pub enum SquashError {
+    /// There were no commits to squash.
+    #[error("No commits to squash")]
+    NoCommits,
+    /// A commit is unknown or not on the current branch.
+    #[error("Commit {0} is not on the current branch")]
+    NotOnBranch(String),
+    /// The range to rewrite contains a merge commit.
+    #[error("Can't squash across merge commits")]
+    Merges,
+    /// The oldest commit has no parent to rebase onto.
+    #[error("Can't squash the repository's first commit")]
+    RootCommit,
+    /// Tracked files have uncommitted changes.
+    #[error("Commit or stash your changes before squashing")]
+    DirtyTree,
+    /// A git command failed.
+    #[error("git {0}")]
+    Git(String),
+}
+
+/// Where the commits to squash sit on the current branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquashPlan {
+    /// Parent of the oldest commit, which the rebase starts from.
+    pub base: String,
+    /// Commits to squash, full hashes, oldest first.
+    pub commits: Vec<String>,
+    /// Every commit from `base` to HEAD, oldest first.
+    pub range: Vec<String>,
+    /// Whether any of the range is on a remote branch already.
+    pub pushed: bool,
+}
+
+impl SquashPlan {
+    /// Commits in the range that aren't being squashed.
+    pub fn others(&self) -> Vec<&str> {
+        self.range
+            .iter()
+            .filter(|c| !self.commits.contains(c))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The rebase todo list: the first commit picked, the others folded into
+    /// it, then `exec` to reword it.
+    fn todo(&self, reword: &str) -> String {
+        let mut lines = Vec::new();
+        for commit in &self.range {
+            if !self.commits.contains(commit) {
+                lines.push(format!("pick {commit}"));
+            } else if *commit == self.commits[0] {
+                lines.push(format!("pick {commit}"));
+                for other in &self.commits[1..] {
+                    lines.push(format!("fixup {other}"));
+                }
+                lines.push(format!("exec {reword}"));
+            }
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+/// The result of a squash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Squashed {
+    /// The commit the squashed commits became.
+    pub commit: String,
+    /// New hashes of the other commits that were replayed, by old hash.
+    pub rewritten: HashMap<String, String>,
+}
+
+/// Work out how to squash `commits` in the repository at `dir`.
+///
+/// Commits may be abbreviated and in any order; duplicates are ignored.
+pub fn plan(dir: &Path, commits: &[String]) -> Result<SquashPlan, SquashError> {
+    let mut resolved: Vec<String> = Vec::new();
+    for commit in commits {
+        let full = git(
+            dir,
+            &[
+                "rev-parse",
+                "--verify",
+                "-q",
+                &format!("{commit}^{{commit}}"),
+            ],
+        )
+        .map(|out| out.trim().to_string())
+        .ok_or_else(|| SquashError::NotOnBranch(commit.clone()))?;
+        if git(dir, &["merge-base", "--is-ancestor", &full, "HEAD"]).is_none() {
+            return Err(SquashError::NotOnBranch(commit.clone()));
+        }
+        if !resolved.contains(&full) {
+            resolved.push(full);
+        }
+    }
+    if resolved.is_empty() {
+        return Err(SquashError::NoCommits);
+    }
+
+    // The oldest commit is the one furthest from HEAD
+    let distance = |commit: &str| {
+        git(dir, &["rev-list", "--count", &format!("{commit}..HEAD")])
+            .and_then(|out| out.trim().parse::<usize>().ok())
+            .unwrap_or(0)
+    };
+    resolved.sort_by_cached_key(|c| std::cmp::Reverse(distance(c)));
+    let base = git(
+        dir,
+        &["rev-parse", "--verify", "-q", &format!("{}^", resolved[0])],
+    )
+    .map(|out| out.trim().to_string())
+    .ok_or(SquashError::RootCommit)?;
+
+    let range_spec = format!("{base}..HEAD");
+    let merges = git(dir, &["rev-list", "--merges", &range_spec]).unwrap_or_default();
+    if !merges.trim().is_empty() {
+        return Err(SquashError::Merges);
+    }
+    let range: Vec<String> = git(dir, &["rev-list", "--reverse", &range_spec])
+        .unwrap_or_default()
+        .lines()
+        .map(String::from)
+        .collect();
+    let pushed = git(dir, &["branch", "-r", "--contains", &resolved[0]])
+        .is_some_and(|out| !out.trim().is_empty());
+
+    Ok(SquashPlan {
+        base,
+        commits: resolved,
+        range,
+        pushed,
+    })
+}
+
+/// Squash the planned commits into one with `message`, rewriting the
+/// current branch. The rebase is aborted if anything goes wrong.
+pub fn squash(dir: &Path, plan: &SquashPlan, message: &str) -> Result<Squashed, SquashError> {
+    let dirty = git(dir, &["status", "--porcelain", "--untracked-files=no"]).unwrap_or_default();
+    if !dirty.trim().is_empty() {
+        return Err(SquashError::DirtyTree);
+    }
+
+    let before = subjects(dir, &plan.base);
+    let scratch = std::env::temp_dir().join(format!(
+        "afk-squash-{}-{}",
+        std::process::id(),
+        SCRATCH.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&scratch).map_err(|e| SquashError::Git(e.to_string()))?;
+    let message_file = scratch.join("message");
+    let todo_file = scratch.join("todo");
+    let head_file = scratch.join("head");
+    let reword = format!(
+        "git commit --amend -q -F {} && git rev-parse HEAD > {}",
+        shell_quote(&message_file),
+        shell_quote(&head_file)
+    );
+    let written =
+        fs::write(&message_file, message).and_then(|()| fs::write(&todo_file, plan.todo(&reword)));
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&scratch);
+        return Err(SquashError::Git(e.to_string()));
+    }
+
+    let output = Command::new("git")
+        .args(["rebase", "-q", "-i", &plan.base])
+        .env(
+            "GIT_SEQUENCE_EDITOR",
+            format!("cp {}", shell_quote(&todo_file)),
+        )
+        .env("GIT_EDITOR", "true")
+        .envs(identity_env())
+        .current_dir(dir)
+        .output();
+    let commit = fs::read_to_string(&head_file)
+        .map(|out| out.trim().to_string())
+        .unwrap_or_default();
+    let _ = fs::remove_dir_all(&scratch);
+
+    match output {
+        Ok(out) if out.status.success() && !commit.is_empty() => {}
+        Ok(out) => {
+            let _ = git(dir, &["rebase", "--abort"]);
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let reason = stderr.lines().next().unwrap_or("rebase failed");
+            return Err(SquashError::Git(format!("rebase failed: {reason}")));
+        }
+        Err(e) => return Err(SquashError::Git(e.to_string())),
+    }
+
+    // Replayed commits keep their author, date and subject
+    let after: HashMap<String, String> = subjects(dir, &plan.base)
+        .into_iter()
+        .map(|(hash, key)| (key, hash))
+        .collect();
+    let others: HashSet<&str> = plan.others().into_iter().collect();
+    let rewritten = before
+        .into_iter()
+        .filter(|(hash, _)| others.contains(hash.as_str()))
+        .filter_map(|(hash, key)| after.get(&key).map(|new| (hash, new.clone())))
+        .collect();
+
+    Ok(Squashed { commit, rewritten })
+}
+
+/// Each commit since `base` with a key of its author, date and subject.
+fn subjects(dir: &Path, base: &str) -> Vec<(String, String)> {
+    git(
+        dir,
+        &["log", "--format=%H%x00%at %ae %s", &format!("{base}..HEAD")],
+    )
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|line| line.split_once('\0'))
+    .map(|(hash, key)| (hash.to_string(), key.to_string()))
+    .collect()
+}
+
+/// Quote a path for the shell git runs editors and `exec` lines in.
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+/// Run git in `dir`, returning its stdout if it succeeded.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=afk", "-c", "user.email=afk@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit(dir: &Path, file: &str, message: &str) -> String {
+        fs::write(dir.join(file), message).unwrap();
+        run(dir, &["add", file]);
+        run(dir, &["commit", "-q", "-m", message]);
+        run(dir, &["rev-parse", "HEAD"])
+    }
+
+    fn repo() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        run(temp.path(), &["init", "-q"]);
+        run(temp.path(), &["config", "user.name", "afk"]);
+        run(temp.path(), &["config", "user.email", "afk@example.com"]);
+        commit(temp.path(), "README", "init");
+        temp
+    }
+
+    #[test]
+    fn test_todo() {
+        let plan = SquashPlan {
+            base: "base".to_string(),
+            commits: vec!["a".to_string(), "c".to_string()],
+            range: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            pushed: false,
+        };
+        assert_eq!(
+            plan.todo("git commit --amend"),
+            "pick a\nfixup c\nexec git commit --amend\npick b\n"
+        );
+        assert_eq!(plan.others(), ["b"]);
+    }
+
+    #[test]
+    fn test_plan_orders_commits() {
+        let temp = repo();
+        let dir = temp.path();
+        let first = commit(dir, "a.rs", "afk: one");
+        let second = commit(dir, "b.rs", "afk: two");
+
+        let plan = plan(dir, &[second.clone(), first[..7].to_string()]).unwrap();
+        assert_eq!(plan.commits, [first.clone(), second.clone()]);
+        assert_eq!(plan.range, [first, second]);
+        assert!(!plan.pushed);
+        assert!(matches!(
+            super::plan(dir, &["0000000".to_string()]),
+            Err(SquashError::NotOnBranch(_))
+        ));
+        assert!(matches!(super::plan(dir, &[]), Err(SquashError::NoCommits)));
+    }
+
+    #[test]
+    fn test_squash_interleaved_commits() {
+        let temp = repo();
+        let dir = temp.path();
+        let one = commit(dir, "a.rs", "afk: auth part one");
+        let other = commit(dir, "b.rs", "afk: other task");
+        let two = commit(dir, "c.rs", "afk: auth part two");
+
+        let plan = plan(dir, &[one, two]).unwrap();
+        let squashed = squash(dir, &plan, "auth: Add login\n\n- Learned a thing\n").unwrap();
+
+        let log = run(dir, &["log", "--format=%s", "-3"]);
+        assert_eq!(log, "afk: other task\nauth: Add login\ninit");
+        assert_eq!(run(dir, &["rev-parse", "HEAD~1"]), squashed.commit);
+        let files = run(dir, &["show", "--name-only", "--format=", &squashed.commit]);
+        assert_eq!(files, "a.rs\nc.rs");
+        assert_eq!(
+            squashed.rewritten.get(&other),
+            Some(&run(dir, &["rev-parse", "HEAD"]))
+        );
+    }
+
+    #[test]
+    fn test_squash_refuses_dirty_tree() {
+        let temp = repo();
+        let dir = temp.path();
+        let one = commit(dir, "a.rs", "afk: one");
+        fs::write(dir.join("README"), "changed").unwrap();
+        let plan = plan(dir, &[one]).unwrap();
+        assert!(matches!(
+            squash(dir, &plan, "msg"),
+            Err(SquashError::DirtyTree)
+        ));
+    }
+}
@@ -4,6 +4,20 @@
 //! implementing the Ralph Wiggum pattern for autonomous AI coding.
 //!
 //! See AGENTS.md for project conventions and architecture overview.
+//!
+//! Loading config, tasks and progress returns typed errors, wrapped
+//! crate-wide by [`AfkError`]. Commands and the loop still print to the
+//! terminal as they work.
+//!
+//! ```no_run
+//! use afk::config::AfkConfig;
+//!
+//! fn pending_titles() -> afk::Result<Vec<String>> {
+//!     let config = AfkConfig::load(None)?;
+//!     let tasks = afk::load_tasks(&config.sources)?;
+//!     Ok(tasks.into_iter().filter(|t| !t.passes).map(|t| t.title).collect())
+//! }
+//! ```
 
 #![deny(missing_docs)]
 
@@ -14,17 +28,24 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub mod bootstrap;
 pub mod cli;
 pub mod config;
+pub mod error;
+pub mod events;
 pub mod feedback;
+pub mod fleet;
 pub mod git;
 pub mod parser;
 pub mod path_matcher;
+pub mod plugins;
 pub mod prd;
 pub mod progress;
 pub mod prompt;
 pub mod runner;
+pub mod schema;
+pub mod server;
 pub mod sources;
 pub mod tui;
 pub mod watcher;
 
 // Re-export key types for convenience
-pub use sources::aggregate_tasks;
+pub use error::{AfkError, Result};
+pub use sources::{aggregate_tasks, load_tasks};
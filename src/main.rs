@@ -3,14 +3,39 @@
 //! This is the main entry point for the afk CLI tool.
 
 use afk::cli::{
-    handle_result, ArchiveCommands, Cli, CliResult, Commands, ConfigCommands, ExitCode,
-    SourceCommands, TasksCommands,
+    handle_result, ArchiveCommands, Cli, CliError, CliResult, Commands, ConfigCommands,
+    ConfigProfileCommands, ExitCode, ExportCommands, FleetCommands, GitCommands, PresetCommands,
+    ReportCommands, SandboxCommands, SchedulerCommands, SourceCommands, TasksCommands,
 };
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::CompleteEnv;
 
 fn main() -> std::process::ExitCode {
+    // Answers dynamic completion requests from shell scripts and exits.
+    CompleteEnv::with_factory(Cli::command)
+        .var(afk::cli::commands::completions::COMPLETE_ENV)
+        .complete();
+
+    // Windows consoles only interpret the ANSI colours afk prints once
+    // virtual terminal processing is switched on
+    #[cfg(windows)]
+    crossterm::ansi_support::supports_ansi();
+
     let cli = Cli::parse();
 
+    if cli.read_only {
+        afk::config::set_read_only(true);
+    }
+    if afk::config::is_read_only() && cli.command.as_ref().is_some_and(Commands::writes_state) {
+        return handle_result(Err(CliError::Command(
+            "Refusing to run this command: read-only mode is active".to_string(),
+        )));
+    }
+
+    if let Ok(config) = afk::config::AfkConfig::load(None) {
+        afk::cli::apply_config_settings(&config);
+    }
+
     let result: CliResult = match cli.command {
         None => {
             // No subcommand provided - show help
@@ -34,6 +59,10 @@ fn main() -> std::process::ExitCode {
             Commands::Done(c) => c.execute(),
             Commands::Fail(c) => c.execute(),
             Commands::Reset(c) => c.execute(),
+            Commands::Pause(c) => c.execute(),
+            Commands::Resume(c) => c.execute(),
+            Commands::Serve(c) => c.execute(),
+            Commands::Note(c) => c.execute(),
             Commands::Source(subcmd) => match subcmd {
                 SourceCommands::Add(c) => c.execute(),
                 SourceCommands::List(c) => c.execute(),
@@ -45,17 +74,35 @@ fn main() -> std::process::ExitCode {
                 pending,
                 complete,
                 limit,
+                scores,
+                json,
             } => match command {
                 Some(TasksCommands::Sync(c)) => c.execute(),
-                None => afk::cli::execute_tasks(pending, complete, limit),
+                Some(TasksCommands::Validate(c)) => c.execute(),
+                Some(TasksCommands::Export(c)) => c.execute(),
+                Some(TasksCommands::Enrich(c)) => c.execute(),
+                None if scores => afk::cli::execute_task_scores(limit),
+                None => afk::cli::execute_tasks(pending, complete, limit, json),
             },
             Commands::Sync(c) => c.execute(),
+            Commands::Watch(c) => c.execute(),
+            Commands::Stats(c) => c.execute(),
+            Commands::Report(subcmd) => match subcmd {
+                ReportCommands::Burndown(c) => c.execute(),
+            },
+            Commands::Scheduler(subcmd) => match subcmd {
+                SchedulerCommands::Start(c) => c.execute(),
+                SchedulerCommands::Next(c) => c.execute(),
+            },
             Commands::Archive {
                 command,
                 reason,
                 yes,
             } => match command {
-                Some(ArchiveCommands::List) => afk::cli::execute_archive_list(),
+                Some(ArchiveCommands::List { tag, json }) => {
+                    afk::cli::execute_archive_list(tag.as_deref(), json)
+                }
+                Some(ArchiveCommands::Diff { a, b }) => afk::cli::execute_archive_diff(&a, &b),
                 None => afk::cli::execute_archive_now(&reason, yes),
             },
             Commands::Config(subcmd) => match subcmd {
@@ -64,11 +111,46 @@ fn main() -> std::process::ExitCode {
                 ConfigCommands::Set(c) => c.execute(),
                 ConfigCommands::Reset(c) => c.execute(),
                 ConfigCommands::Edit(c) => c.execute(),
+                ConfigCommands::Tui(c) => c.execute(),
                 ConfigCommands::Explain(c) => c.execute(),
                 ConfigCommands::Keys(c) => c.execute(),
+                ConfigCommands::Convert(c) => c.execute(),
+                ConfigCommands::Profile(subcmd) => match subcmd {
+                    ConfigProfileCommands::Create(c) => c.execute(),
+                    ConfigProfileCommands::List(c) => c.execute(),
+                    ConfigProfileCommands::Use(c) => c.execute(),
+                },
+            },
+            Commands::Repro(c) => c.execute(),
+            Commands::Replay(c) => c.execute(),
+            Commands::Log(c) => c.execute(),
+            Commands::Logs(c) => c.execute(),
+            Commands::Fleet(subcmd) => match subcmd {
+                FleetCommands::Init(c) => c.execute(),
+                FleetCommands::Status(c) => c.execute(),
+                FleetCommands::Sync(c) => c.execute(),
+                FleetCommands::Go(c) => c.execute(),
+            },
+            Commands::Sandbox(subcmd) => match subcmd {
+                SandboxCommands::Diff(c) => c.execute(),
+                SandboxCommands::Apply(c) => c.execute(),
+                SandboxCommands::Discard(c) => c.execute(),
+            },
+            Commands::Git(subcmd) => match subcmd {
+                GitCommands::Squash(c) => c.execute(),
+            },
+            Commands::Export(subcmd) => match subcmd {
+                ExportCommands::Patches(c) => c.execute(),
+            },
+            Commands::Review(c) => c.execute(),
+            Commands::Explain(c) => c.execute(),
+            Commands::Preset(subcmd) => match subcmd {
+                PresetCommands::List(c) => c.execute(),
+                PresetCommands::Show(c) => c.execute(),
             },
             Commands::Update(c) => c.execute(),
             Commands::Completions(c) => c.execute(),
+            Commands::Schema(c) => c.execute(),
             Commands::Use(c) => c.execute(),
         },
     };
@@ -0,0 +1,182 @@
+//! The `afk-result` block agents end their work with.
+//!
+//! The prompt asks the agent to finish with a fenced `afk-result` JSON block
+//! saying how its task went, so afk can record the outcome even when the
+//! agent forgot to update `tasks.json` or run `afk done`:
+//!
+//! ````text
+//! ```afk-result
+//! {"status": "done", "task_id": "auth-login", "summary": "Added the login form", "learnings": ["Forms use the shared validator"]}
+//! ```
+//! ````
+
+use serde::Deserialize;
+
+use super::{StreamEvent, StreamJsonParser};
+
+/// Opening fence of a result block.
+const RESULT_FENCE: &str = "```afk-result";
+
+/// How the agent says its task went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentStatus {
+    /// The task is complete.
+    #[serde(alias = "complete", alias = "completed")]
+    Done,
+    /// Progress was made but the task isn't finished.
+    Partial,
+    /// The agent can't continue without help.
+    Blocked,
+    /// The attempt failed.
+    Failed,
+}
+
+impl std::fmt::Display for AgentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Done => "done",
+            Self::Partial => "partial",
+            Self::Blocked => "blocked",
+            Self::Failed => "failed",
+        })
+    }
+}
+
+/// An agent's `afk-result` block.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AgentResult {
+    /// How the task went.
+    pub status: AgentStatus,
+    /// The task worked on, if the agent says.
+    #[serde(default)]
+    pub task_id: Option<String>,
+    /// What the agent did.
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Things later iterations should know.
+    #[serde(default)]
+    pub learnings: Vec<String>,
+}
+
+/// The last well-formed `afk-result` block in `text`.
+pub fn extract_agent_result(text: &str) -> Option<AgentResult> {
+    text.match_indices(RESULT_FENCE)
+        .filter_map(|(start, _)| {
+            let body = &text[start + RESULT_FENCE.len()..];
+            let end = body.find("```").unwrap_or(body.len());
+            serde_json::from_str(body[..end].trim()).ok()
+        })
+        .last()
+}
+
+/// Collects what an agent writes, line by line, to find its result block.
+///
+/// For CLIs that stream JSON only the assistant's own text is kept, so the
+/// prompt's example block is never mistaken for the agent's; lines that
+/// aren't stream events are kept as they are, for CLIs that turn out to
+/// print plain text.
+#[derive(Default)]
+pub struct ResultCollector {
+    parser: Option<StreamJsonParser>,
+    text: String,
+}
+
+impl ResultCollector {
+    /// Collect plain text output, or stream-json output parsed by `parser`.
+    pub fn new(parser: Option<StreamJsonParser>) -> Self {
+        Self {
+            parser,
+            text: String::new(),
+        }
+    }
+
+    /// Add one line of raw AI CLI output.
+    pub fn push_line(&mut self, line: &str) {
+        let Some(parser) = self.parser.as_mut() else {
+            self.text.push_str(line);
+            self.text.push('\n');
+            return;
+        };
+        let events = parser.parse_events(line);
+        if events.is_empty() {
+            self.text.push_str(line);
+            self.text.push('\n');
+        }
+        for event in events {
+            match event {
+                StreamEvent::AssistantMessage { text }
+                | StreamEvent::Result {
+                    result_text: Some(text),
+                    ..
+                } => self.text.push_str(&text),
+                _ => {}
+            }
+        }
+    }
+
+    /// The agent's result block, if it wrote one.
+    pub fn result(&self) -> Option<AgentResult> {
+        extract_agent_result(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CliFormat;
+
+    #[test]
+    fn test_extract_agent_result() {
+        let text = "All done.\n\n```afk-result\n{\"status\": \"done\", \"task_id\": \"auth\", \
+                    \"summary\": \"Added login\", \"learnings\": [\"Use the validator\"]}\n```\n";
+        let result = extract_agent_result(text).unwrap();
+        assert_eq!(result.status, AgentStatus::Done);
+        assert_eq!(result.task_id.as_deref(), Some("auth"));
+        assert_eq!(result.summary.as_deref(), Some("Added login"));
+        assert_eq!(result.learnings, ["Use the validator"]);
+    }
+
+    #[test]
+    fn test_extract_agent_result_takes_last_valid_block() {
+        let text = "```afk-result\n{\"status\": \"partial\"}\n```\nmore work\n\
+                    ```afk-result\n{\"status\": \"completed\"}\n```\n\
+                    ```afk-result\n{\"status\": \"done | partial | blocked | failed\"}\n```";
+        let result = extract_agent_result(text).unwrap();
+        assert_eq!(result.status, AgentStatus::Done);
+        assert!(result.task_id.is_none());
+        assert!(result.learnings.is_empty());
+
+        assert!(extract_agent_result("no block here").is_none());
+        assert!(extract_agent_result("```afk-result\nnot json\n```").is_none());
+    }
+
+    #[test]
+    fn test_collector_keeps_only_assistant_text() {
+        let mut collector = ResultCollector::new(Some(StreamJsonParser::new(CliFormat::Claude)));
+        collector.push_line(
+            r#"{"type":"user","message":{"content":[{"type":"text","text":"```afk-result\n{\"status\": \"done\"}\n```"}]}}"#,
+        );
+        assert!(collector.result().is_none());
+
+        collector.push_line(
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Blocked.\n```afk-result\n{\"status\": \"blocked\", \"summary\": \"Needs an API key\"}\n```"}]}}"#,
+        );
+        let result = collector.result().unwrap();
+        assert_eq!(result.status, AgentStatus::Blocked);
+        assert_eq!(result.summary.as_deref(), Some("Needs an API key"));
+
+        // A CLI printing plain text despite the stream-json setting
+        let mut fallback = ResultCollector::new(Some(StreamJsonParser::new(CliFormat::Claude)));
+        for line in ["```afk-result", r#"{"status": "partial"}"#, "```"] {
+            fallback.push_line(line);
+        }
+        assert_eq!(fallback.result().unwrap().status, AgentStatus::Partial);
+
+        let mut plain = ResultCollector::new(None);
+        for line in ["```afk-result", r#"{"status": "failed"}"#, "```"] {
+            plain.push_line(line);
+        }
+        assert_eq!(plain.result().unwrap().status, AgentStatus::Failed);
+    }
+}
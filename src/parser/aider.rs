@@ -0,0 +1,222 @@
+//! Aider's plain text output.
+//!
+//! Aider has no JSON output, so its status lines (`Applied edit to ...`,
+//! `Commit ...`, `Tokens: ... sent, ... received.`) are matched as text and
+//! everything else is the model talking.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::stream_json::{StreamEvent, TokenUsage, ToolType};
+
+static BANNER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Aider v\d").expect("BANNER regex is valid"));
+static MODEL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:Main model|Model): (\S+) with .*edit format").expect("MODEL regex is valid")
+});
+static APPLIED_EDIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Applied edit to (.+)$").expect("APPLIED_EDIT regex is valid"));
+static ADDED_TO_CHAT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Added (.+) to the chat\.?$").expect("ADDED_TO_CHAT regex is valid")
+});
+static COMMIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Commit ([a-f0-9]{7,}) .+$").expect("COMMIT regex is valid"));
+static TOKENS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Tokens: ([\d.,]+[kM]?) sent(?:, ([\d.,]+[kM]?) cache write)?(?:, ([\d.,]+[kM]?) cache hit)?, ([\d.,]+[kM]?) received\.(?: Cost: \$([\d.]+) message)?")
+        .expect("TOKENS regex is valid")
+});
+static ERROR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Error: (.+)$").expect("ERROR regex is valid"));
+
+/// Startup lines with nothing worth reporting.
+const HEADER_PREFIXES: &[&str] = &[
+    "Weak model:",
+    "Editor model:",
+    "Git repo:",
+    "Repo-map:",
+    "Use /help",
+];
+
+/// Whether `line` is one only Aider writes.
+pub(super) fn is_aider_line(line: &str) -> bool {
+    let line = line.trim();
+    BANNER.is_match(line) || MODEL.is_match(line) || TOKENS.is_match(line)
+}
+
+/// Parse one line of Aider output.
+pub(super) fn parse_line(line: &str) -> Option<StreamEvent> {
+    let line = line.trim();
+    if line.is_empty() || HEADER_PREFIXES.iter().any(|p| line.starts_with(p)) {
+        return None;
+    }
+    if BANNER.is_match(line) {
+        return Some(StreamEvent::SystemInit {
+            model: None,
+            session_id: None,
+        });
+    }
+    if let Some(caps) = MODEL.captures(line) {
+        return Some(StreamEvent::SystemInit {
+            model: Some(caps[1].to_string()),
+            session_id: None,
+        });
+    }
+    if let Some(caps) = APPLIED_EDIT.captures(line) {
+        return Some(completed("edit", ToolType::Edit, Some(&caps[1])));
+    }
+    if let Some(caps) = ADDED_TO_CHAT.captures(line) {
+        return Some(completed("add", ToolType::Read, Some(&caps[1])));
+    }
+    if COMMIT.is_match(line) {
+        return Some(completed(
+            "commit",
+            ToolType::Other("Commit".to_string()),
+            None,
+        ));
+    }
+    if let Some(caps) = TOKENS.captures(line) {
+        let count = |i: usize| caps.get(i).and_then(|m| parse_count(m.as_str()));
+        return Some(StreamEvent::Usage(TokenUsage {
+            input_tokens: count(1).unwrap_or(0) + count(2).unwrap_or(0) + count(3).unwrap_or(0),
+            output_tokens: count(4).unwrap_or(0),
+            cost_usd: caps.get(5).and_then(|m| m.as_str().parse().ok()),
+        }));
+    }
+    if let Some(caps) = ERROR.captures(line) {
+        return Some(StreamEvent::Error {
+            message: caps[1].to_string(),
+        });
+    }
+    Some(StreamEvent::AssistantMessage {
+        text: line.to_string(),
+    })
+}
+
+fn completed(name: &str, tool_type: ToolType, path: Option<&str>) -> StreamEvent {
+    StreamEvent::ToolCompleted {
+        tool_name: name.to_string(),
+        tool_type,
+        path: path.map(|p| p.trim().to_string()),
+        command: None,
+        success: true,
+        exit_code: None,
+        lines: None,
+        file_size: None,
+    }
+}
+
+/// A token count as Aider prints it: `850`, `3,200`, `2.5k` or `1.2M`.
+fn parse_count(text: &str) -> Option<u64> {
+    let text = text.replace(',', "");
+    let (number, scale) = match text.strip_suffix('k') {
+        Some(n) => (n, 1_000.0),
+        None => match text.strip_suffix('M') {
+            Some(n) => (n, 1_000_000.0),
+            None => (text.as_str(), 1.0),
+        },
+    };
+    let value: f64 = number.parse().ok()?;
+    Some((value * scale).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CliFormat, StreamJsonParser};
+
+    const FIXTURE: &str = include_str!("fixtures/aider.txt");
+
+    fn events() -> Vec<StreamEvent> {
+        let mut parser = StreamJsonParser::auto_detect();
+        let events = FIXTURE
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .collect();
+        assert_eq!(parser.effective_format(), CliFormat::Aider);
+        events
+    }
+
+    #[test]
+    fn test_parse_aider_session() {
+        let events = events();
+        assert_eq!(events.len(), 11);
+        assert_eq!(
+            events[1],
+            StreamEvent::SystemInit {
+                model: Some("anthropic/claude-sonnet-4-20250514".to_string()),
+                session_id: None,
+            }
+        );
+        assert_eq!(
+            events[3],
+            StreamEvent::AssistantMessage {
+                text: "I'll add validation to the config loader.".to_string()
+            }
+        );
+        assert_eq!(
+            events[8],
+            StreamEvent::Error {
+                message: "cannot run tests, cargo not found".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_aider_tool_calls() {
+        let events = events();
+        assert!(matches!(
+            &events[2],
+            StreamEvent::ToolCompleted { tool_type: ToolType::Read, path: Some(path), .. }
+                if path == "src/config.rs"
+        ));
+        assert!(matches!(
+            &events[5],
+            StreamEvent::ToolCompleted { tool_type: ToolType::Edit, path: Some(path), .. }
+                if path == "src/config.rs"
+        ));
+        assert!(matches!(
+            &events[6],
+            StreamEvent::ToolCompleted { tool_type: ToolType::Other(name), .. } if name == "Commit"
+        ));
+    }
+
+    #[test]
+    fn test_parse_aider_usage() {
+        let events = events();
+        assert_eq!(
+            events[7],
+            StreamEvent::Usage(TokenUsage {
+                input_tokens: 14_500,
+                output_tokens: 1_400,
+                cost_usd: Some(0.05),
+            })
+        );
+        assert_eq!(
+            events[10],
+            StreamEvent::Usage(TokenUsage {
+                input_tokens: 3_200,
+                output_tokens: 850,
+                cost_usd: Some(0.01),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("850"), Some(850));
+        assert_eq!(parse_count("3,200"), Some(3_200));
+        assert_eq!(parse_count("2.5k"), Some(2_500));
+        assert_eq!(parse_count("1.2M"), Some(1_200_000));
+        assert_eq!(parse_count("lots"), None);
+    }
+
+    #[test]
+    fn test_is_aider_line() {
+        assert!(is_aider_line("Aider v0.86.1"));
+        assert!(is_aider_line("Model: gpt-4o with diff edit format"));
+        assert!(is_aider_line("Tokens: 850 sent, 120 received."));
+        assert!(!is_aider_line("Applied the fix"));
+        assert!(!is_aider_line("Running tests..."));
+    }
+}
@@ -0,0 +1,229 @@
+//! Gemini CLI stream-json events.
+//!
+//! `gemini --output-format stream-json` writes `init`, `message` (with a
+//! `role`), `tool_use`, `tool_result`, `error` and `result` events. Tool
+//! results carry only the ID of their call, so calls are remembered until
+//! their result arrives.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::stream_json::{classify_tool_name, PendingTool, StreamEvent, TokenUsage, ToolType};
+
+/// Whether `json` looks like a Gemini CLI event rather than a Claude or
+/// Cursor one.
+pub(super) fn is_gemini_event(json: &Value) -> bool {
+    match json.get("type").and_then(Value::as_str) {
+        Some("init") => true,
+        Some("message") => json.get("role").is_some(),
+        Some("tool_use") => json.get("tool_name").is_some(),
+        Some("tool_result") => json.get("tool_id").is_some(),
+        Some("result") => json.get("stats").is_some() || json.get("status").is_some(),
+        _ => false,
+    }
+}
+
+/// Parse one Gemini CLI event.
+pub(super) fn parse_event(
+    json: &Value,
+    raw: &str,
+    tools: &mut HashMap<String, PendingTool>,
+) -> Option<StreamEvent> {
+    let text = |key: &str| json.get(key).and_then(Value::as_str).map(String::from);
+    let event_type = json.get("type")?.as_str()?;
+    match event_type {
+        "init" => Some(StreamEvent::SystemInit {
+            model: text("model"),
+            session_id: text("session_id"),
+        }),
+        "message" => {
+            let content = text("content").unwrap_or_default();
+            match json.get("role").and_then(Value::as_str) {
+                Some("user") => Some(StreamEvent::UserMessage { text: content }),
+                _ => Some(StreamEvent::AssistantMessage { text: content }),
+            }
+        }
+        "tool_use" => {
+            let name = text("tool_name").unwrap_or_else(|| "unknown".to_string());
+            let parameters = json.get("parameters");
+            let call = PendingTool {
+                tool_type: tool_type(&name),
+                path: parameters.and_then(tool_path),
+                command: parameters
+                    .and_then(|p| p.get("command"))
+                    .and_then(Value::as_str)
+                    .map(String::from),
+                name,
+            };
+            if let Some(id) = text("tool_id") {
+                tools.insert(id, call.clone());
+            }
+            Some(StreamEvent::ToolStarted {
+                tool_name: call.name,
+                tool_type: call.tool_type,
+                path: call.path,
+                command: call.command,
+            })
+        }
+        "tool_result" => {
+            let call = text("tool_id").and_then(|id| tools.remove(&id));
+            let success = json.get("status").and_then(Value::as_str) != Some("error");
+            Some(PendingTool::completed(call, success, None))
+        }
+        "error" => Some(StreamEvent::Error {
+            message: text("message").unwrap_or_else(|| "Unknown error".to_string()),
+        }),
+        "result" => {
+            let stats = json.get("stats");
+            Some(StreamEvent::Result {
+                success: json.get("status").and_then(Value::as_str) != Some("error"),
+                duration_ms: stats
+                    .and_then(|s| s.get("duration_ms"))
+                    .and_then(Value::as_u64),
+                result_text: json
+                    .get("error")
+                    .and_then(|e| e.get("message"))
+                    .and_then(Value::as_str)
+                    .map(String::from),
+                usage: stats.map(|stats| TokenUsage {
+                    input_tokens: stats["input_tokens"].as_u64().unwrap_or(0),
+                    output_tokens: stats["output_tokens"].as_u64().unwrap_or(0),
+                    cost_usd: None,
+                }),
+            })
+        }
+        _ => Some(StreamEvent::Unknown {
+            event_type: event_type.to_string(),
+            raw: raw.to_string(),
+        }),
+    }
+}
+
+/// Tool type for a Gemini CLI tool name.
+fn tool_type(name: &str) -> ToolType {
+    match name {
+        "replace" => ToolType::Edit,
+        "search_file_content" | "list_directory" => ToolType::Search,
+        _ => classify_tool_name(name),
+    }
+}
+
+/// The file or directory a tool call works on.
+fn tool_path(parameters: &Value) -> Option<String> {
+    ["file_path", "absolute_path", "path", "dir_path"]
+        .iter()
+        .find_map(|key| parameters.get(key).and_then(Value::as_str))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CliFormat, StreamJsonParser};
+
+    const FIXTURE: &str = include_str!("fixtures/gemini.jsonl");
+
+    fn events() -> Vec<StreamEvent> {
+        let mut parser = StreamJsonParser::auto_detect();
+        let events = FIXTURE
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .collect();
+        assert_eq!(parser.effective_format(), CliFormat::Gemini);
+        events
+    }
+
+    #[test]
+    fn test_parse_gemini_session() {
+        let events = events();
+        assert_eq!(events.len(), 12);
+        assert_eq!(
+            events[0],
+            StreamEvent::SystemInit {
+                model: Some("gemini-2.5-pro".to_string()),
+                session_id: Some("4f1c2d3e".to_string()),
+            }
+        );
+        assert!(matches!(events[1], StreamEvent::UserMessage { .. }));
+        assert_eq!(
+            events[2],
+            StreamEvent::AssistantMessage {
+                text: "I'll start by reading the config module.".to_string()
+            }
+        );
+        assert!(matches!(
+            &events[10],
+            StreamEvent::AssistantMessage { text } if text.ends_with("<promise>COMPLETE</promise>")
+        ));
+        assert_eq!(
+            events[9],
+            StreamEvent::Error {
+                message: "Loop detected, stopping tool calls".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_gemini_tool_calls() {
+        let events = events();
+        assert_eq!(
+            events[3],
+            StreamEvent::ToolStarted {
+                tool_name: "read_file".to_string(),
+                tool_type: ToolType::Read,
+                path: Some("/work/src/config.rs".to_string()),
+                command: None,
+            }
+        );
+        // Results are matched to their call by ID
+        assert!(matches!(
+            &events[6],
+            StreamEvent::ToolCompleted { tool_name, tool_type: ToolType::Edit, path: Some(path), success: true, .. }
+                if tool_name == "replace" && path == "/work/src/config.rs"
+        ));
+        assert!(matches!(
+            &events[8],
+            StreamEvent::ToolCompleted {
+                tool_type: ToolType::Command,
+                success: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_gemini_result_usage() {
+        assert_eq!(
+            events()[11],
+            StreamEvent::Result {
+                success: true,
+                duration_ms: Some(21500),
+                result_text: None,
+                usage: Some(TokenUsage {
+                    input_tokens: 14100,
+                    output_tokens: 1130,
+                    cost_usd: None,
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_is_gemini_event() {
+        let event = |s: &str| serde_json::from_str::<Value>(s).unwrap();
+        assert!(is_gemini_event(&event(r#"{"type":"init","model":"m"}"#)));
+        assert!(is_gemini_event(&event(
+            r#"{"type":"tool_use","tool_name":"read_file"}"#
+        )));
+        assert!(!is_gemini_event(&event(
+            r#"{"type":"tool_use","name":"Read","input":{}}"#
+        )));
+        assert!(!is_gemini_event(&event(
+            r#"{"type":"system","subtype":"init"}"#
+        )));
+        assert!(!is_gemini_event(&event(
+            r#"{"type":"result","subtype":"success","usage":{}}"#
+        )));
+    }
+}
@@ -6,11 +6,19 @@
 //! ## Parsing Modes
 //!
 //! - **Regex-based**: For plain text output (legacy/fallback)
-//! - **NDJSON stream-json**: For structured streaming output from Cursor/Claude CLIs
-
+//! - **NDJSON stream-json**: For structured streaming output from Cursor, Claude
+//!   and Gemini CLIs, the HTTP and Ollama backends' chunks, and Aider's status
+//!   lines mapped to the same events
+
+mod agent_result;
+mod aider;
+mod gemini;
+mod ollama;
+mod openai;
 mod stream_json;
 
-pub use stream_json::{CliFormat, StreamEvent, StreamJsonParser, ToolType};
+pub use agent_result::{extract_agent_result, AgentResult, AgentStatus, ResultCollector};
+pub use stream_json::{CliFormat, StreamEvent, StreamJsonParser, TokenUsage, ToolType};
 
 use regex::Regex;
 use std::sync::LazyLock;
@@ -131,6 +139,35 @@ impl ParsedEvent {
     }
 }
 
+/// Phrases AI CLIs print when they hit a rate or usage limit.
+static RATE_LIMIT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\brate[ _-]?limit(s|ed|_\w+)?\b|too many requests|(status|error|code) 429\b|\b429 too many|usage limit|quota exceeded",
+    )
+    .expect("RATE_LIMIT regex is valid")
+});
+
+/// Whether a message reports a rate or usage limit (a 429, "rate limit",
+/// "too many requests" and the like).
+pub fn is_rate_limit_message(text: &str) -> bool {
+    RATE_LIMIT.is_match(text)
+}
+
+/// The message of a stream event reporting a rate limit: an error, or a
+/// failed result, whose text reads like one.
+pub fn rate_limit_message(event: &StreamEvent) -> Option<&str> {
+    let text = match event {
+        StreamEvent::Error { message } => message,
+        StreamEvent::Result {
+            success: false,
+            result_text: Some(text),
+            ..
+        } => text,
+        _ => return None,
+    };
+    is_rate_limit_message(text).then_some(text.as_str())
+}
+
 // ============================================================================
 // Regex patterns
 // ============================================================================
@@ -360,6 +397,43 @@ impl Default for OutputParser {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rate_limit_message() {
+        assert!(is_rate_limit_message("API Error: 429 Too Many Requests"));
+        assert!(is_rate_limit_message("Claude usage limit reached"));
+        assert!(is_rate_limit_message("rate_limit_error: slow down"));
+        assert!(!is_rate_limit_message("Added a rate limiter to the API"));
+
+        let error = StreamEvent::Error {
+            message: "Rate limit exceeded, retry later".to_string(),
+        };
+        assert_eq!(
+            rate_limit_message(&error),
+            Some("Rate limit exceeded, retry later")
+        );
+        let failed = StreamEvent::Result {
+            success: false,
+            duration_ms: None,
+            result_text: Some("Error 429: quota exceeded".to_string()),
+            usage: None,
+        };
+        assert_eq!(
+            rate_limit_message(&failed),
+            Some("Error 429: quota exceeded")
+        );
+        let done = StreamEvent::Result {
+            success: true,
+            duration_ms: None,
+            result_text: Some("Handled the rate limit case".to_string()),
+            usage: None,
+        };
+        assert_eq!(rate_limit_message(&done), None);
+        let other = StreamEvent::Error {
+            message: "Permission denied".to_string(),
+        };
+        assert_eq!(rate_limit_message(&other), None);
+    }
+
     #[test]
     fn test_empty_line() {
         let parser = OutputParser::new();
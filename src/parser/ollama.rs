@@ -0,0 +1,129 @@
+//! Ollama `/api/chat` stream chunks.
+//!
+//! The Ollama backend hands on each streamed JSON object as a line. Like
+//! OpenAI chunks, text is held until a line of it is complete. The last
+//! chunk, marked `done`, carries the timings and token counts, so when it
+//! also finishes a held line of text it reports two events: the message,
+//! then the result.
+
+use serde_json::Value;
+
+use super::openai::take_text;
+use super::stream_json::{StreamEvent, TokenUsage};
+
+/// Whether `json` looks like an Ollama chat chunk.
+pub(super) fn is_chunk(json: &Value) -> bool {
+    json.get("done").is_some_and(Value::is_boolean)
+        && (json.get("message").is_some() || json.get("created_at").is_some())
+}
+
+/// Parse one chunk, holding partial lines of text in `pending`. A result
+/// following the message the chunk finishes is added to `held`.
+pub(super) fn parse_chunk(
+    json: &Value,
+    raw: &str,
+    pending: &mut String,
+    held: &mut Vec<StreamEvent>,
+) -> Option<StreamEvent> {
+    if let Some(error) = json.get("error") {
+        let message = error
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| error.to_string());
+        return Some(StreamEvent::Error { message });
+    }
+
+    if let Some(text) = json
+        .get("message")
+        .and_then(|message| message.get("content"))
+        .and_then(Value::as_str)
+    {
+        pending.push_str(text);
+    }
+    let done = json.get("done").and_then(Value::as_bool) == Some(true);
+    let event = take_text(pending, done, raw);
+    if !done {
+        return Some(event);
+    }
+
+    let count = |key: &str| json.get(key).and_then(Value::as_u64);
+    let result = StreamEvent::Result {
+        success: json.get("done_reason").and_then(Value::as_str) != Some("load"),
+        duration_ms: count("total_duration").map(|ns| ns / 1_000_000),
+        result_text: None,
+        usage: Some(TokenUsage {
+            input_tokens: count("prompt_eval_count").unwrap_or(0),
+            output_tokens: count("eval_count").unwrap_or(0),
+            cost_usd: None,
+        }),
+    };
+    match event {
+        StreamEvent::AssistantMessage { .. } => {
+            held.push(result);
+            Some(event)
+        }
+        _ => Some(result),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CliFormat, StreamJsonParser};
+
+    const FIXTURE: &str = include_str!("fixtures/ollama.jsonl");
+
+    #[test]
+    fn test_parse_ollama_chunks() {
+        let mut parser = StreamJsonParser::auto_detect();
+        let events: Vec<StreamEvent> = FIXTURE
+            .lines()
+            .flat_map(|line| parser.parse_events(line))
+            .filter(|event| !matches!(event, StreamEvent::Unknown { .. }))
+            .collect();
+        assert_eq!(parser.effective_format(), CliFormat::Ollama);
+        assert_eq!(
+            events,
+            [
+                StreamEvent::AssistantMessage {
+                    text: "I added the missing bounds check.".to_string()
+                },
+                // The last chunk finishes the line and reports usage
+                StreamEvent::AssistantMessage {
+                    text: "<promise>COMPLETE</promise>".to_string()
+                },
+                StreamEvent::Result {
+                    success: true,
+                    duration_ms: Some(4210),
+                    result_text: None,
+                    usage: Some(TokenUsage {
+                        input_tokens: 1536,
+                        output_tokens: 18,
+                        cost_usd: None,
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ollama_error() {
+        let mut parser = StreamJsonParser::new(CliFormat::Ollama);
+        assert_eq!(
+            parser.parse_line(r#"{"error":"model 'qwen3' not found"}"#),
+            Some(StreamEvent::Error {
+                message: "model 'qwen3' not found".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_ollama_chunk() {
+        let chunk = |s: &str| serde_json::from_str::<Value>(s).unwrap();
+        assert!(is_chunk(&chunk(
+            r#"{"model":"m","created_at":"t","message":{"content":"x"},"done":false}"#
+        )));
+        assert!(!is_chunk(&chunk(r#"{"choices":[],"done":false}"#)));
+        assert!(!is_chunk(&chunk(r#"{"type":"result","done":true}"#)));
+    }
+}
@@ -0,0 +1,151 @@
+//! OpenAI-compatible chat completion chunks.
+//!
+//! The HTTP backend hands on each streamed `chat.completion.chunk` as a
+//! line. A chunk carries a few tokens of the reply, so text is held until
+//! a line of it is complete, or the reply finishes, and then reported as
+//! one assistant message. The usage chunk sent last becomes the result.
+
+use serde_json::Value;
+
+use super::stream_json::{StreamEvent, TokenUsage};
+
+/// Whether `json` looks like a chat completion chunk.
+pub(super) fn is_chunk(json: &Value) -> bool {
+    json.get("object").and_then(Value::as_str) == Some("chat.completion.chunk")
+        || json.get("choices").is_some_and(Value::is_array)
+}
+
+/// Parse one chunk, holding partial lines of text in `pending`.
+pub(super) fn parse_chunk(json: &Value, raw: &str, pending: &mut String) -> Option<StreamEvent> {
+    if let Some(error) = json.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .unwrap_or_else(|| error.to_string());
+        return Some(StreamEvent::Error { message });
+    }
+
+    let choice = json
+        .get("choices")
+        .and_then(Value::as_array)
+        .and_then(|choices| choices.first());
+    let Some(choice) = choice else {
+        // Only the last chunk has no choices; it carries the usage
+        return Some(StreamEvent::Result {
+            success: true,
+            duration_ms: None,
+            result_text: None,
+            usage: json.get("usage").and_then(usage),
+        });
+    };
+
+    if let Some(text) = choice
+        .get("delta")
+        .and_then(|delta| delta.get("content"))
+        .and_then(Value::as_str)
+    {
+        pending.push_str(text);
+    }
+    let finished = choice
+        .get("finish_reason")
+        .is_some_and(|reason| !reason.is_null());
+    Some(take_text(pending, finished, raw))
+}
+
+/// Take the complete lines of text from `pending`, or all of it once the
+/// reply has `finished`, as an assistant message. A chunk that completes
+/// no text is reported as an unknown `chunk` event.
+pub(super) fn take_text(pending: &mut String, finished: bool, raw: &str) -> StreamEvent {
+    let complete = if finished {
+        std::mem::take(pending)
+    } else {
+        match pending.rfind('\n') {
+            Some(end) => {
+                let rest = pending.split_off(end + 1);
+                std::mem::replace(pending, rest)
+            }
+            None => String::new(),
+        }
+    };
+
+    let text = complete.trim_end();
+    if text.trim().is_empty() {
+        StreamEvent::Unknown {
+            event_type: "chunk".to_string(),
+            raw: raw.to_string(),
+        }
+    } else {
+        StreamEvent::AssistantMessage {
+            text: text.to_string(),
+        }
+    }
+}
+
+fn usage(usage: &Value) -> Option<TokenUsage> {
+    Some(TokenUsage {
+        input_tokens: usage.get("prompt_tokens")?.as_u64()?,
+        output_tokens: usage
+            .get("completion_tokens")
+            .and_then(Value::as_u64)
+            .unwrap_or(0),
+        cost_usd: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CliFormat, StreamJsonParser};
+
+    const FIXTURE: &str = include_str!("fixtures/openai.jsonl");
+
+    #[test]
+    fn test_parse_openai_chunks() {
+        let mut parser = StreamJsonParser::auto_detect();
+        let events: Vec<StreamEvent> = FIXTURE
+            .lines()
+            .filter_map(|line| parser.parse_line(line))
+            .filter(|event| !matches!(event, StreamEvent::Unknown { .. }))
+            .collect();
+        assert_eq!(parser.effective_format(), CliFormat::OpenAi);
+        assert_eq!(
+            events,
+            [
+                StreamEvent::AssistantMessage {
+                    text: "I updated the config loader.".to_string()
+                },
+                StreamEvent::AssistantMessage {
+                    text: "All tests pass.".to_string()
+                },
+                // Held until the reply finishes, as it has no newline
+                StreamEvent::AssistantMessage {
+                    text: "<promise>COMPLETE</promise>".to_string()
+                },
+                StreamEvent::Result {
+                    success: true,
+                    duration_ms: None,
+                    result_text: None,
+                    usage: Some(TokenUsage {
+                        input_tokens: 812,
+                        output_tokens: 24,
+                        cost_usd: None,
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_openai_error() {
+        let mut parser = StreamJsonParser::new(CliFormat::OpenAi);
+        assert_eq!(
+            parser.parse_line(
+                r#"{"error":{"message":"model not found","type":"invalid_request_error"}}"#
+            ),
+            Some(StreamEvent::Error {
+                message: "model not found".to_string()
+            })
+        );
+    }
+}
@@ -1,10 +1,16 @@
 //! NDJSON stream parser for AI CLI output.
 //!
-//! Parses newline-delimited JSON (NDJSON) events from Cursor and Claude CLIs
-//! when using `--output-format stream-json`.
+//! Parses newline-delimited JSON (NDJSON) events from Cursor, Claude and
+//! Gemini CLIs when using `--output-format stream-json`, Aider's plain
+//! text output, which has no JSON mode, and the chat completion chunks
+//! streamed to the HTTP backend into the same events.
+
+use std::collections::HashMap;
 
 use serde_json::Value;
 
+use super::{aider, gemini, ollama, openai};
+
 /// Normalised stream event from any supported AI CLI.
 #[derive(Debug, Clone, PartialEq)]
 pub enum StreamEvent {
@@ -33,6 +39,8 @@ pub enum StreamEvent {
         tool_type: ToolType,
         /// File path if applicable.
         path: Option<String>,
+        /// Shell command, for command tools.
+        command: Option<String>,
     },
     /// Tool call completed.
     ToolCompleted {
@@ -42,8 +50,12 @@ pub enum StreamEvent {
         tool_type: ToolType,
         /// File path if applicable.
         path: Option<String>,
+        /// Shell command, for command tools.
+        command: Option<String>,
         /// Whether the tool call succeeded.
         success: bool,
+        /// Exit code of a command, if reported.
+        exit_code: Option<i32>,
         /// Number of lines read/written if applicable.
         lines: Option<u32>,
         /// File size if applicable.
@@ -57,7 +69,11 @@ pub enum StreamEvent {
         duration_ms: Option<u64>,
         /// Result text/summary.
         result_text: Option<String>,
+        /// Tokens used over the session, if reported.
+        usage: Option<TokenUsage>,
     },
+    /// Tokens used by one message, for CLIs that report usage as they go.
+    Usage(TokenUsage),
     /// Error event.
     Error {
         /// The error message.
@@ -72,6 +88,33 @@ pub enum StreamEvent {
     },
 }
 
+/// Tokens used, and what they cost if the CLI says.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    /// Input tokens, including cache reads and writes.
+    pub input_tokens: u64,
+    /// Output tokens.
+    pub output_tokens: u64,
+    /// Cost in US dollars.
+    pub cost_usd: Option<f64>,
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = TokenUsage;
+
+    fn add(self, other: TokenUsage) -> TokenUsage {
+        let cost_usd = match (self.cost_usd, other.cost_usd) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        TokenUsage {
+            input_tokens: self.input_tokens + other.input_tokens,
+            output_tokens: self.output_tokens + other.output_tokens,
+            cost_usd,
+        }
+    }
+}
+
 /// Type of tool being used.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ToolType {
@@ -113,6 +156,14 @@ pub enum CliFormat {
     Cursor,
     /// Claude Code CLI stream-json format.
     Claude,
+    /// Gemini CLI stream-json format.
+    Gemini,
+    /// Aider's plain text output.
+    Aider,
+    /// OpenAI-compatible chat completion chunks, from the HTTP backend.
+    OpenAi,
+    /// Ollama chat chunks, from the Ollama backend.
+    Ollama,
     /// Auto-detect from first event.
     Auto,
 }
@@ -123,6 +174,48 @@ pub struct StreamJsonParser {
     format: CliFormat,
     /// Detected format (after auto-detection).
     detected_format: Option<CliFormat>,
+    /// Claude and Gemini tool calls by ID, as their results only carry the
+    /// ID.
+    pending_tools: HashMap<String, PendingTool>,
+    /// Streamed reply text waiting for the end of its line.
+    pending_text: String,
+    /// Events after the first from the last line parsed.
+    held: Vec<StreamEvent>,
+}
+
+/// A tool call waiting for its result.
+#[derive(Debug, Clone)]
+pub(super) struct PendingTool {
+    pub(super) name: String,
+    pub(super) tool_type: ToolType,
+    pub(super) path: Option<String>,
+    pub(super) command: Option<String>,
+}
+
+impl PendingTool {
+    /// The call's result, or a generic one if the call wasn't seen.
+    pub(super) fn completed(
+        call: Option<PendingTool>,
+        success: bool,
+        exit_code: Option<i32>,
+    ) -> StreamEvent {
+        let call = call.unwrap_or_else(|| PendingTool {
+            name: "tool".to_string(),
+            tool_type: ToolType::Other("tool".to_string()),
+            path: None,
+            command: None,
+        });
+        StreamEvent::ToolCompleted {
+            tool_name: call.name,
+            tool_type: call.tool_type,
+            path: call.path,
+            command: call.command,
+            success,
+            exit_code,
+            lines: None,
+            file_size: None,
+        }
+    }
 }
 
 impl StreamJsonParser {
@@ -131,6 +224,9 @@ impl StreamJsonParser {
         Self {
             format,
             detected_format: None,
+            pending_tools: HashMap::new(),
+            pending_text: String::new(),
+            held: Vec::new(),
         }
     }
 
@@ -145,36 +241,67 @@ impl StreamJsonParser {
     }
 
     /// Parse a single NDJSON line into a StreamEvent.
+    ///
+    /// A line holding several events returns only the first; see
+    /// [`StreamJsonParser::parse_events`].
     pub fn parse_line(&mut self, line: &str) -> Option<StreamEvent> {
+        self.held.clear();
         let line = line.trim();
         if line.is_empty() {
             return None;
         }
 
-        // Try to parse as JSON
-        let json: Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => {
-                // Not valid JSON, might be plain text output
-                return None;
-            }
-        };
+        // Try to parse as JSON; plain text output has no event in it, except
+        // from Aider, which only writes text
+        let json = parse_event_json(line);
 
         // Auto-detect format from first event if needed
         if self.format == CliFormat::Auto && self.detected_format.is_none() {
-            self.detected_format = Some(self.detect_format(&json));
+            self.detected_format = match json {
+                Some(ref json) => Some(self.detect_format(json)),
+                None => aider::is_aider_line(line).then_some(CliFormat::Aider),
+            };
         }
 
         let format = self.effective_format();
+        if format == CliFormat::Aider {
+            return aider::parse_line(line);
+        }
+        let json = json?;
         match format {
             CliFormat::Cursor => self.parse_cursor_event(&json, line),
             CliFormat::Claude => self.parse_claude_event(&json, line),
+            CliFormat::Gemini => gemini::parse_event(&json, line, &mut self.pending_tools),
+            CliFormat::OpenAi => openai::parse_chunk(&json, line, &mut self.pending_text),
+            CliFormat::Ollama => {
+                ollama::parse_chunk(&json, line, &mut self.pending_text, &mut self.held)
+            }
+            CliFormat::Aider => None,
             CliFormat::Auto => self.parse_cursor_event(&json, line), // Fallback
         }
     }
 
+    /// Parse a single NDJSON line into every event in it.
+    ///
+    /// Most lines hold at most one event, but a Claude message can carry
+    /// text and several tool calls or results, and the last chunk of an
+    /// Ollama reply can finish a line of text and report usage at once.
+    pub fn parse_events(&mut self, line: &str) -> Vec<StreamEvent> {
+        let first = self.parse_line(line);
+        first.into_iter().chain(self.held.drain(..)).collect()
+    }
+
     /// Detect format from JSON structure.
     fn detect_format(&self, json: &Value) -> CliFormat {
+        if gemini::is_gemini_event(json) {
+            return CliFormat::Gemini;
+        }
+        if openai::is_chunk(json) {
+            return CliFormat::OpenAi;
+        }
+        if ollama::is_chunk(json) {
+            return CliFormat::Ollama;
+        }
         // Cursor uses "tool_call" with "subtype"
         // Claude uses "tool_use" and "tool_result"
         if json.get("tool_call").is_some() {
@@ -224,20 +351,25 @@ impl StreamJsonParser {
 
                 // Extract tool info from Cursor's nested structure
                 let (tool_name, tool_type, path) = extract_cursor_tool_info(tool_call);
+                let command = extract_cursor_command(tool_call);
 
                 match subtype {
                     "started" => Some(StreamEvent::ToolStarted {
                         tool_name,
                         tool_type,
                         path,
+                        command,
                     }),
                     "completed" => {
-                        let (success, lines, file_size) = extract_cursor_tool_result(tool_call);
+                        let (success, lines, file_size, exit_code) =
+                            extract_cursor_tool_result(tool_call);
                         Some(StreamEvent::ToolCompleted {
                             tool_name,
                             tool_type,
                             path,
+                            command,
                             success,
+                            exit_code,
                             lines,
                             file_size,
                         })
@@ -261,6 +393,7 @@ impl StreamJsonParser {
                     success,
                     duration_ms,
                     result_text,
+                    usage: result_usage(json),
                 })
             }
             _ => Some(StreamEvent::Unknown {
@@ -271,7 +404,11 @@ impl StreamJsonParser {
     }
 
     /// Parse a Claude CLI stream-json event.
-    fn parse_claude_event(&self, json: &Value, raw: &str) -> Option<StreamEvent> {
+    ///
+    /// Tool calls come as `tool_use` blocks in assistant messages, and their
+    /// results as `tool_result` blocks in the user messages that follow.
+    /// Each block is reported as its own event, after the message's text.
+    fn parse_claude_event(&mut self, json: &Value, raw: &str) -> Option<StreamEvent> {
         let event_type = json.get("type")?.as_str()?;
 
         match event_type {
@@ -286,43 +423,23 @@ impl StreamJsonParser {
                     .map(|s| s.to_string());
                 Some(StreamEvent::SystemInit { model, session_id })
             }
-            "user" => {
-                let text = extract_message_text(json.get("message")?)?;
-                Some(StreamEvent::UserMessage { text })
-            }
-            "assistant" => {
-                let text = extract_message_text(json.get("message")?)?;
-                Some(StreamEvent::AssistantMessage { text })
-            }
-            "tool_use" => {
-                let tool_name = json
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-                let tool_type = classify_tool_name(&tool_name);
-                let path = json
-                    .get("input")
-                    .and_then(|i| i.get("path"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                Some(StreamEvent::ToolStarted {
-                    tool_name,
-                    tool_type,
-                    path,
-                })
-            }
-            "tool_result" => {
-                // Claude doesn't include tool name in result, use generic
-                Some(StreamEvent::ToolCompleted {
-                    tool_name: "tool".to_string(),
-                    tool_type: ToolType::Other("tool".to_string()),
-                    path: None,
-                    success: true,
-                    lines: None,
-                    file_size: None,
-                })
+            "user" | "assistant" => {
+                let message = json.get("message")?;
+                let text = extract_message_text(message)?;
+                self.held = message
+                    .get("content")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|block| self.parse_claude_tool_block(block))
+                    .collect();
+                if event_type == "user" {
+                    Some(StreamEvent::UserMessage { text })
+                } else {
+                    Some(StreamEvent::AssistantMessage { text })
+                }
             }
+            "tool_use" | "tool_result" => self.parse_claude_tool_block(json),
             "result" => {
                 let subtype = json.get("subtype").and_then(|v| v.as_str());
                 let success = subtype == Some("success");
@@ -335,6 +452,7 @@ impl StreamJsonParser {
                     success,
                     duration_ms,
                     result_text,
+                    usage: result_usage(json),
                 })
             }
             "error" => {
@@ -353,6 +471,53 @@ impl StreamJsonParser {
     }
 }
 
+impl StreamJsonParser {
+    /// Parse a Claude `tool_use` or `tool_result`, whether a top-level event
+    /// or a block in a message.
+    fn parse_claude_tool_block(&mut self, block: &Value) -> Option<StreamEvent> {
+        match block.get("type")?.as_str()? {
+            "tool_use" => {
+                let name = block
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+                let input = block.get("input");
+                let input_text = |keys: &[&str]| {
+                    keys.iter()
+                        .find_map(|key| input?.get(key)?.as_str())
+                        .map(String::from)
+                };
+                let call = PendingTool {
+                    tool_type: classify_tool_name(&name),
+                    path: input_text(&["file_path", "path", "notebook_path"]),
+                    command: input_text(&["command"]),
+                    name,
+                };
+                if let Some(id) = block.get("id").and_then(|v| v.as_str()) {
+                    self.pending_tools.insert(id.to_string(), call.clone());
+                }
+                Some(StreamEvent::ToolStarted {
+                    tool_name: call.name,
+                    tool_type: call.tool_type,
+                    path: call.path,
+                    command: call.command,
+                })
+            }
+            "tool_result" => {
+                // Results only carry the ID of their call
+                let call = block
+                    .get("tool_use_id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| self.pending_tools.remove(id));
+                let failed = block.get("is_error").and_then(|v| v.as_bool()) == Some(true);
+                Some(PendingTool::completed(call, !failed, None))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl Default for StreamJsonParser {
     fn default() -> Self {
         Self::auto_detect()
@@ -402,6 +567,7 @@ fn extract_cursor_tool_info(tool_call: &Value) -> (String, ToolType, Option<Stri
         ("editToolCall", ToolType::Edit, "Edit"),
         ("deleteToolCall", ToolType::Delete, "Delete"),
         ("bashToolCall", ToolType::Command, "Bash"),
+        ("shellToolCall", ToolType::Command, "Shell"),
         ("searchToolCall", ToolType::Search, "Search"),
         ("grepToolCall", ToolType::Search, "Grep"),
         ("globToolCall", ToolType::Search, "Glob"),
@@ -436,8 +602,30 @@ fn extract_cursor_tool_info(tool_call: &Value) -> (String, ToolType, Option<Stri
     )
 }
 
-/// Extract result info from Cursor's tool_call completion.
-fn extract_cursor_tool_result(tool_call: &Value) -> (bool, Option<u32>, Option<u32>) {
+/// The command of a Cursor shell tool call.
+fn extract_cursor_command(tool_call: &Value) -> Option<String> {
+    ["shellToolCall", "bashToolCall"]
+        .iter()
+        .find_map(|key| tool_call.get(key)?.get("args")?.get("command")?.as_str())
+        .map(String::from)
+}
+
+/// Extract result info from Cursor's tool_call completion: success, lines,
+/// file size and exit code.
+fn extract_cursor_tool_result(tool_call: &Value) -> (bool, Option<u32>, Option<u32>, Option<i32>) {
+    // Shell results carry an exit code, whether they succeeded or failed
+    for key in ["shellToolCall", "bashToolCall"] {
+        if let Some(result) = tool_call.get(key).and_then(|inner| inner.get("result")) {
+            let outcome = result.get("success").or_else(|| result.get("failure"));
+            let exit_code = outcome
+                .and_then(|o| o.get("exitCode"))
+                .and_then(|v| v.as_i64())
+                .map(|code| code as i32);
+            let success = result.get("success").is_some() && exit_code.unwrap_or(0) == 0;
+            return (success, None, None, exit_code);
+        }
+    }
+
     // Look for result.success in any of the tool call types
     for key in [
         "readToolCall",
@@ -458,15 +646,31 @@ fn extract_cursor_tool_result(tool_call: &Value) -> (bool, Option<u32>, Option<u
                     .and_then(|s| s.get("fileSize"))
                     .and_then(|v| v.as_u64())
                     .map(|v| v as u32);
-                return (success, lines, file_size);
+                return (success, lines, file_size, None);
             }
         }
     }
-    (true, None, None)
+    (true, None, None, None)
+}
+
+/// Token usage from the `usage` object of a Claude or Cursor result, with
+/// cache reads and writes counted as input.
+fn result_usage(json: &Value) -> Option<TokenUsage> {
+    let usage = json.get("usage")?;
+    let count = |key: &str| usage[key].as_u64().unwrap_or(0);
+    Some(TokenUsage {
+        input_tokens: count("input_tokens")
+            + count("cache_read_input_tokens")
+            + count("cache_creation_input_tokens"),
+        output_tokens: count("output_tokens"),
+        cost_usd: json["total_cost_usd"]
+            .as_f64()
+            .or_else(|| json["cost_usd"].as_f64()),
+    })
 }
 
 /// Classify a tool name into a ToolType.
-fn classify_tool_name(name: &str) -> ToolType {
+pub(super) fn classify_tool_name(name: &str) -> ToolType {
     let lower = name.to_lowercase();
     if lower.contains("read") {
         ToolType::Read
@@ -485,6 +689,30 @@ fn classify_tool_name(name: &str) -> ToolType {
     }
 }
 
+/// Most `{` positions tried when looking for an event inside a noisy line.
+const MAX_EVENT_OFFSETS: usize = 8;
+
+/// Parse a line as a JSON event, tolerating noise around it.
+///
+/// CLIs that interleave stderr with stdout can leave a warning on the same
+/// line as an event (`warning: ...{"type":"assistant",...}`). If the whole
+/// line isn't JSON, the first object that starts at a `{` and has a string
+/// `type` field is used, and anything after it is ignored.
+fn parse_event_json(line: &str) -> Option<Value> {
+    if let Ok(json) = serde_json::from_str(line) {
+        return Some(json);
+    }
+    line.match_indices('{')
+        .take(MAX_EVENT_OFFSETS)
+        .find_map(|(start, _)| {
+            serde_json::Deserializer::from_str(&line[start..])
+                .into_iter::<Value>()
+                .next()
+                .and_then(Result::ok)
+                .filter(|json| json.get("type").is_some_and(Value::is_string))
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -502,6 +730,24 @@ mod tests {
         assert!(parser.parse_line("not valid json").is_none());
     }
 
+    #[test]
+    fn test_parse_event_with_interleaved_noise() {
+        let mut parser = StreamJsonParser::new(CliFormat::Claude);
+        let event = parser.parse_line(
+            r#"(node:42) Warning: slow{"type":"system","subtype":"init","model":"m"}trailing"#,
+        );
+        assert!(matches!(
+            event,
+            Some(StreamEvent::SystemInit { model: Some(ref m), .. }) if m == "m"
+        ));
+
+        // Braces in plain text without an event stay plain text
+        assert!(parser
+            .parse_line("fn main() { println!(\"hi\"); }")
+            .is_none());
+        assert!(parser.parse_line(r#"config {"debug":true}"#).is_none());
+    }
+
     #[test]
     fn test_parse_cursor_system_init() {
         let mut parser = StreamJsonParser::new(CliFormat::Cursor);
@@ -542,6 +788,7 @@ mod tests {
                 tool_name,
                 tool_type,
                 path,
+                ..
             } => {
                 assert_eq!(tool_name, "Read");
                 assert_eq!(tool_type, ToolType::Read);
@@ -610,15 +857,36 @@ mod tests {
                 success,
                 duration_ms,
                 result_text,
+                usage,
             } => {
                 assert!(success);
                 assert_eq!(duration_ms, Some(5234));
                 assert_eq!(result_text, Some("Done!".to_string()));
+                assert_eq!(usage, None);
             }
             _ => panic!("Expected Result"),
         }
     }
 
+    #[test]
+    fn test_parse_claude_result_usage() {
+        let mut parser = StreamJsonParser::new(CliFormat::Claude);
+        let event = parser
+            .parse_line(r#"{"type":"result","subtype":"success","is_error":false,"total_cost_usd":0.12,"usage":{"input_tokens":100,"cache_read_input_tokens":2000,"cache_creation_input_tokens":500,"output_tokens":300}}"#)
+            .unwrap();
+        assert!(matches!(
+            event,
+            StreamEvent::Result {
+                usage: Some(TokenUsage {
+                    input_tokens: 2600,
+                    output_tokens: 300,
+                    cost_usd: Some(cost),
+                }),
+                ..
+            } if cost == 0.12
+        ));
+    }
+
     #[test]
     fn test_parse_claude_assistant_message() {
         let mut parser = StreamJsonParser::new(CliFormat::Claude);
@@ -644,6 +912,7 @@ mod tests {
                 tool_name,
                 tool_type,
                 path,
+                ..
             } => {
                 assert_eq!(tool_name, "Read");
                 assert_eq!(tool_type, ToolType::Read);
@@ -669,6 +938,45 @@ mod tests {
         assert_eq!(parser.effective_format(), CliFormat::Claude);
     }
 
+    #[test]
+    fn test_auto_detect_gemini() {
+        let mut parser = StreamJsonParser::auto_detect();
+        parser.parse_line(r#"{"type":"init","session_id":"abc","model":"gemini-2.5-pro"}"#);
+        assert_eq!(parser.effective_format(), CliFormat::Gemini);
+    }
+
+    #[test]
+    fn test_auto_detect_aider() {
+        let mut parser = StreamJsonParser::auto_detect();
+        // Text that isn't Aider's leaves the format undecided
+        assert!(parser.parse_line("Loading...").is_none());
+        assert_eq!(parser.effective_format(), CliFormat::Auto);
+        parser.parse_line("Aider v0.86.1");
+        assert_eq!(parser.effective_format(), CliFormat::Aider);
+    }
+
+    #[test]
+    fn test_token_usage_add() {
+        let a = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cost_usd: None,
+        };
+        let b = TokenUsage {
+            input_tokens: 1,
+            output_tokens: 2,
+            cost_usd: Some(0.5),
+        };
+        assert_eq!(
+            a + b,
+            TokenUsage {
+                input_tokens: 11,
+                output_tokens: 7,
+                cost_usd: Some(0.5),
+            }
+        );
+    }
+
     #[test]
     fn test_tool_type_display() {
         assert_eq!(format!("{}", ToolType::Read), "Read");
@@ -736,4 +1044,58 @@ mod tests {
             _ => panic!("Expected AssistantMessage"),
         }
     }
+
+    #[test]
+    fn test_parse_claude_tool_blocks_in_messages() {
+        let mut parser = StreamJsonParser::new(CliFormat::Claude);
+        let events = parser.parse_events(
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Running tests"},{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"cargo test"}}]}}"#,
+        );
+        assert_eq!(
+            events,
+            [
+                StreamEvent::AssistantMessage {
+                    text: "Running tests".to_string()
+                },
+                StreamEvent::ToolStarted {
+                    tool_name: "Bash".to_string(),
+                    tool_type: ToolType::Command,
+                    path: None,
+                    command: Some("cargo test".to_string()),
+                },
+            ]
+        );
+
+        // The result is matched to its call by ID
+        let events = parser.parse_events(
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"toolu_1","is_error":true,"content":"1 failed"}]}}"#,
+        );
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[1],
+            StreamEvent::ToolCompleted { tool_name, command: Some(command), success: false, .. }
+                if tool_name == "Bash" && command == "cargo test"
+        ));
+    }
+
+    #[test]
+    fn test_parse_cursor_shell_exit_code() {
+        let mut parser = StreamJsonParser::new(CliFormat::Cursor);
+        let event = parser
+            .parse_line(r#"{"type":"tool_call","subtype":"completed","tool_call":{"shellToolCall":{"args":{"command":"npm test"},"result":{"failure":{"exitCode":1}}}}}"#)
+            .unwrap();
+        assert_eq!(
+            event,
+            StreamEvent::ToolCompleted {
+                tool_name: "Shell".to_string(),
+                tool_type: ToolType::Command,
+                path: None,
+                command: Some("npm test".to_string()),
+                success: false,
+                exit_code: Some(1),
+                lines: None,
+                file_size: None,
+            }
+        );
+    }
 }
@@ -1,7 +1,8 @@
 //! Shared utility for matching paths against ignore patterns.
 //!
 //! This module provides a reusable `PathMatcher` for checking if paths
-//! should be ignored based on glob-like patterns.
+//! should be ignored based on glob-like patterns, and [`glob_matches`] for
+//! gitattributes-style globs over repository paths.
 
 use std::path::Path;
 
@@ -97,6 +98,45 @@ impl Default for PathMatcher {
     }
 }
 
+/// Whether a glob applies to a repository path, as in `.gitattributes`.
+///
+/// A pattern without a slash matches the file name at any depth; one with
+/// a slash matches the whole path from the root.
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let target = if pattern.contains(&'/') {
+        path
+    } else {
+        path.rsplit('/').next().unwrap_or(path)
+    };
+    let target: Vec<char> = target.chars().collect();
+    glob_match(&pattern, &target)
+}
+
+/// Match `*` (within a directory), `**` (across directories) and `?`.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            // "**/" may also match no directories at all
+            let (rest, whole_dirs) = match pattern.get(2) {
+                Some('/') => (&pattern[3..], true),
+                _ => (&pattern[2..], false),
+            };
+            (0..=text.len())
+                .filter(|&i| !whole_dirs || i == 0 || text[i - 1] == '/')
+                .any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(&pattern[1..], &text[i..])),
+        Some('?') => {
+            text.first().is_some_and(|c| *c != '/') && glob_match(&pattern[1..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,4 +214,21 @@ mod tests {
         let cloned = matcher.clone();
         assert_eq!(cloned.patterns(), matcher.patterns());
     }
+
+    #[test]
+    fn test_glob_match() {
+        let matches = |p: &str, t: &str| {
+            glob_match(
+                &p.chars().collect::<Vec<_>>(),
+                &t.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert!(matches("**/build/*.bin", "build/a.bin"));
+        assert!(matches("**/build/*.bin", "x/y/build/a.bin"));
+        assert!(!matches("**/build/*.bin", "xbuild/a.bin"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("*.bin", "dir/a.bin"));
+        assert!(matches("file?.txt", "file1.txt"));
+    }
 }
@@ -0,0 +1,370 @@
+//! Runner plugins loaded from shared libraries.
+//!
+//! Organisations that need custom policy enforcement can compile it
+//! separately from afk as a cdylib and list it under `plugins.paths`.
+//! A plugin exports three C functions:
+//!
+//! ```c
+//! uint32_t afk_plugin_abi_version(void);
+//! char *afk_plugin_on_event(const char *event_json);
+//! void afk_plugin_free(char *response);
+//! ```
+//!
+//! `afk_plugin_on_event` receives each runner event as JSON (see
+//! [`PluginEvent`]) and returns either NULL or a JSON [`PluginResponse`],
+//! which afk hands back to `afk_plugin_free` once read. A response that
+//! doesn't parse counts as a veto.
+
+use std::collections::BTreeMap;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+
+use libloading::Library;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{FeedbackLoopsConfig, PluginsConfig};
+
+/// ABI version plugins must report from `afk_plugin_abi_version`.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+const ABI_VERSION_SYMBOL: &[u8] = b"afk_plugin_abi_version\0";
+const ON_EVENT_SYMBOL: &[u8] = b"afk_plugin_on_event\0";
+const FREE_SYMBOL: &[u8] = b"afk_plugin_free\0";
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type OnEventFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+/// An event sent to plugins, serialised as JSON with an `event` tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PluginEvent {
+    /// The loop is about to start.
+    LoopStart {
+        /// Iteration limit for this run.
+        max_iterations: u32,
+    },
+    /// An iteration is about to run.
+    IterationStart {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration is expected to work on.
+        task_id: Option<String>,
+    },
+    /// An iteration finished. Plugins may veto its commits.
+    IterationEnd {
+        /// Iteration number (1-based).
+        iteration: u32,
+        /// Task the iteration worked on.
+        task_id: Option<String>,
+        /// Commits made during the iteration, oldest first.
+        commits: Vec<String>,
+    },
+    /// The loop stopped.
+    LoopEnd {
+        /// Why the loop stopped.
+        reason: String,
+    },
+    /// Request for additional quality gates.
+    CollectGates,
+}
+
+/// A plugin's reply to an event.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct PluginResponse {
+    /// Reason to reject the iteration and stop the loop. Its commits are
+    /// undone and the tasks it completed reopened before anything is
+    /// synced, merged or turned into a PR.
+    #[serde(default)]
+    pub veto: Option<String>,
+    /// Extra quality gates (name to shell command), for `collect_gates`.
+    #[serde(default)]
+    pub gates: BTreeMap<String, String>,
+}
+
+/// Error type for plugin loading.
+#[derive(Debug, thiserror::Error)]
+pub enum PluginError {
+    /// The shared library could not be opened.
+    #[error("Failed to load plugin {path}: {message}")]
+    LoadError {
+        /// Path to the plugin.
+        path: PathBuf,
+        /// Loader error message.
+        message: String,
+    },
+    /// A required symbol is not exported.
+    #[error("Plugin {path} does not export {symbol}")]
+    MissingSymbol {
+        /// Path to the plugin.
+        path: PathBuf,
+        /// Name of the missing symbol.
+        symbol: String,
+    },
+    /// The plugin was built against a different ABI version.
+    #[error("Plugin {path} uses ABI version {found}, expected {expected}")]
+    AbiMismatch {
+        /// Path to the plugin.
+        path: PathBuf,
+        /// Version reported by the plugin.
+        found: u32,
+        /// Version afk supports.
+        expected: u32,
+    },
+}
+
+/// A loaded plugin library.
+pub struct Plugin {
+    name: String,
+    library: Library,
+}
+
+impl Plugin {
+    /// Load a plugin and check its ABI version.
+    pub fn load(path: &Path) -> Result<Self, PluginError> {
+        // SAFETY: opening a library runs its initialisers. Plugins are trusted
+        // code the user has explicitly listed in their config.
+        let library = unsafe { Library::new(path) }.map_err(|e| PluginError::LoadError {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+
+        let missing = |symbol: &[u8]| PluginError::MissingSymbol {
+            path: path.to_path_buf(),
+            symbol: String::from_utf8_lossy(&symbol[..symbol.len() - 1]).into_owned(),
+        };
+
+        // SAFETY: the symbol types match the documented plugin ABI.
+        let found = unsafe {
+            let version = library
+                .get::<AbiVersionFn>(ABI_VERSION_SYMBOL)
+                .map_err(|_| missing(ABI_VERSION_SYMBOL))?;
+            library
+                .get::<OnEventFn>(ON_EVENT_SYMBOL)
+                .map_err(|_| missing(ON_EVENT_SYMBOL))?;
+            library
+                .get::<FreeFn>(FREE_SYMBOL)
+                .map_err(|_| missing(FREE_SYMBOL))?;
+            version()
+        };
+
+        if found != PLUGIN_ABI_VERSION {
+            return Err(PluginError::AbiMismatch {
+                path: path.to_path_buf(),
+                found,
+                expected: PLUGIN_ABI_VERSION,
+            });
+        }
+
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().trim_start_matches("lib").to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        Ok(Self { name, library })
+    }
+
+    /// Plugin name, derived from the library file name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Send an event to the plugin and read its response.
+    pub fn handle(&self, event: &PluginEvent) -> PluginResponse {
+        let Ok(json) = serde_json::to_string(event) else {
+            return PluginResponse::default();
+        };
+        let Ok(input) = CString::new(json) else {
+            return PluginResponse::default();
+        };
+
+        // SAFETY: symbols were checked at load time and match the ABI. The
+        // returned string is owned by the plugin and released via its own
+        // free function after we copy it.
+        unsafe {
+            let (Ok(on_event), Ok(free)) = (
+                self.library.get::<OnEventFn>(ON_EVENT_SYMBOL),
+                self.library.get::<FreeFn>(FREE_SYMBOL),
+            ) else {
+                return PluginResponse::default();
+            };
+
+            let output = on_event(input.as_ptr());
+            if output.is_null() {
+                return PluginResponse::default();
+            }
+            let response = CStr::from_ptr(output).to_string_lossy().into_owned();
+            free(output);
+            parse_response(&response)
+        }
+    }
+}
+
+/// Parse a plugin response.
+///
+/// An empty response means the same as NULL. One that isn't a valid
+/// response is a veto, so a broken policy plugin stops the loop rather
+/// than letting everything through.
+fn parse_response(json: &str) -> PluginResponse {
+    if json.trim().is_empty() {
+        return PluginResponse::default();
+    }
+    serde_json::from_str(json).unwrap_or_else(|e| PluginResponse {
+        veto: Some(format!("invalid plugin response: {e}")),
+        ..Default::default()
+    })
+}
+
+/// The set of plugins configured for a project.
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Plugin>,
+    /// Extra quality gates, collected once at load time.
+    gates: Vec<(String, String)>,
+}
+
+impl PluginHost {
+    /// Load every plugin listed in the config.
+    ///
+    /// Fails if any plugin cannot be loaded, so policy is never silently skipped.
+    /// Each plugin is asked for its quality gates once, here: they describe
+    /// configuration rather than any one iteration.
+    pub fn load(config: &PluginsConfig) -> Result<Self, PluginError> {
+        let plugins = config
+            .paths
+            .iter()
+            .map(|p| Plugin::load(Path::new(p)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let gates = plugins
+            .iter()
+            .flat_map(|p| p.handle(&PluginEvent::CollectGates).gates)
+            .collect();
+        Ok(Self { plugins, gates })
+    }
+
+    /// Whether no plugins are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Send an event to every plugin.
+    ///
+    /// Returns the first veto as "plugin: reason", if any plugin vetoed.
+    pub fn emit(&self, event: &PluginEvent) -> Option<String> {
+        let mut veto = None;
+        for plugin in &self.plugins {
+            let response = plugin.handle(event);
+            if veto.is_none() {
+                veto = response
+                    .veto
+                    .map(|reason| format!("{}: {reason}", plugin.name()));
+            }
+        }
+        veto
+    }
+
+    /// Extra quality gates from every plugin (name, shell command).
+    pub fn gates(&self) -> &[(String, String)] {
+        &self.gates
+    }
+
+    /// Add the plugins' quality gates to `feedback_loops` as custom gates,
+    /// so whatever runs the gates runs them too.
+    pub fn add_gates(&self, feedback_loops: &mut FeedbackLoopsConfig) {
+        feedback_loops.custom.extend(self.gates.iter().cloned());
+    }
+
+    /// A host with no libraries whose gates come from a `collect_gates`
+    /// response.
+    #[cfg(test)]
+    pub(crate) fn with_gates_response(response: &str) -> Self {
+        Self {
+            plugins: Vec::new(),
+            gates: parse_response(response).gates.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_event_json() {
+        let event = PluginEvent::IterationEnd {
+            iteration: 2,
+            task_id: Some("task-1".to_string()),
+            commits: vec!["abc123".to_string()],
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"event":"iteration_end","iteration":2,"task_id":"task-1","commits":["abc123"]}"#
+        );
+
+        let json = serde_json::to_string(&PluginEvent::CollectGates).unwrap();
+        assert_eq!(json, r#"{"event":"collect_gates"}"#);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response = parse_response(r#"{"veto": "Touches billing code"}"#);
+        assert_eq!(response.veto.as_deref(), Some("Touches billing code"));
+        assert!(response.gates.is_empty());
+
+        let response = parse_response(r#"{"gates": {"licence": "./check-licences.sh"}}"#);
+        assert_eq!(response.gates["licence"], "./check-licences.sh");
+
+        assert_eq!(parse_response(""), PluginResponse::default());
+        let response = parse_response("not json");
+        assert!(response
+            .veto
+            .unwrap()
+            .starts_with("invalid plugin response: expected ident"));
+        let response = parse_response(r#"{"veto": 3}"#);
+        assert!(response
+            .veto
+            .unwrap()
+            .starts_with("invalid plugin response"));
+    }
+
+    #[test]
+    fn test_empty_host() {
+        let host = PluginHost::load(&PluginsConfig::default()).unwrap();
+        assert!(host.is_empty());
+        assert!(host.emit(&PluginEvent::CollectGates).is_none());
+        assert!(host.gates().is_empty());
+    }
+
+    #[test]
+    fn test_add_gates() {
+        let host = PluginHost::with_gates_response(r#"{"gates": {"licence": "./licences.sh"}}"#);
+        let mut feedback_loops = FeedbackLoopsConfig::default();
+        host.add_gates(&mut feedback_loops);
+        assert_eq!(feedback_loops.custom["licence"], "./licences.sh");
+    }
+
+    #[test]
+    fn test_missing_plugin_fails_to_load() {
+        let config = PluginsConfig {
+            paths: vec!["/nonexistent/libafk_policy.so".to_string()],
+        };
+        let err = PluginHost::load(&config).err().unwrap();
+        assert!(err
+            .to_string()
+            .contains("Failed to load plugin /nonexistent/libafk_policy.so"));
+    }
+
+    #[test]
+    fn test_plugin_error_display() {
+        let err = PluginError::AbiMismatch {
+            path: PathBuf::from("libpolicy.so"),
+            found: 2,
+            expected: 1,
+        };
+        assert_eq!(
+            err.to_string(),
+            "Plugin libpolicy.so uses ABI version 2, expected 1"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Sync conflict detection and resolution.
+//!
+//! Sync normally lets each side own its part of a task: sources own the
+//! details, tasks.json owns completion. The two can disagree about state,
+//! though: a source may close a task the loop has been failing on, or
+//! reopen one afk already completed. These conflicts are surfaced so a
+//! person (or `runner.sync_conflicts`, when nobody is at the terminal)
+//! decides which side wins, instead of sync silently keeping the local one.
+
+use std::path::Path;
+
+use crate::config::{SyncConflictPolicy, PROGRESS_FILE};
+use crate::progress::{SessionProgress, TaskStatus};
+
+use super::{PrdError, SyncPlan};
+
+/// How the source and local state of a task disagree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The source closed (or dropped) a task that failed locally.
+    ClosedRemotely,
+    /// The source lists as open a task completed locally.
+    ReopenedRemotely,
+}
+
+/// A task whose source and local state disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    /// Task ID.
+    pub id: String,
+    /// Task title.
+    pub title: String,
+    /// How the two sides disagree.
+    pub kind: ConflictKind,
+}
+
+impl SyncConflict {
+    /// One-line description of the disagreement.
+    pub fn describe(&self) -> &'static str {
+        match self.kind {
+            ConflictKind::ClosedRemotely => "closed in source, failed locally",
+            ConflictKind::ReopenedRemotely => "open in source, completed locally",
+        }
+    }
+}
+
+/// Whether the loop closes a story's source item when it completes, so
+/// the source still listing it means it was reopened.
+fn closed_on_completion(source: &str, github_sync_back: bool) -> bool {
+    source == "beads" || (github_sync_back && source.starts_with("github:"))
+}
+
+/// Find the tasks in a sync plan whose source and local state disagree.
+///
+/// A pending task the source no longer lists conflicts when progress
+/// records it failed. A completed task conflicts when its source still
+/// lists it and is one the loop closes on completion (beads, or GitHub
+/// with `github.sync_back`); other sources never mark tasks done, so still
+/// listing a completed task is normal for them.
+pub fn detect_conflicts(
+    plan: &SyncPlan,
+    progress: &SessionProgress,
+    github_sync_back: bool,
+) -> Vec<SyncConflict> {
+    let mut conflicts: Vec<SyncConflict> = plan
+        .removed
+        .iter()
+        .filter(|id| {
+            progress
+                .get_task(id)
+                .is_some_and(|t| t.status == TaskStatus::Failed)
+        })
+        .filter_map(|id| plan.prd.get_story(id))
+        .map(|story| SyncConflict {
+            id: story.id.clone(),
+            title: story.title.clone(),
+            kind: ConflictKind::ClosedRemotely,
+        })
+        .collect();
+
+    conflicts.extend(
+        plan.prd
+            .user_stories
+            .iter()
+            .filter(|s| s.passes && plan.listed.contains(&s.id))
+            .filter(|s| closed_on_completion(&s.source, github_sync_back))
+            .map(|story| SyncConflict {
+                id: story.id.clone(),
+                title: story.title.clone(),
+                kind: ConflictKind::ReopenedRemotely,
+            }),
+    );
+    conflicts
+}
+
+/// Apply a resolution to a conflicting task.
+///
+/// Keeping local changes nothing. Taking the remote side completes a task
+/// the source closed, or reopens one it reopened. Review marks the task
+/// skipped in progress, with a message saying why.
+pub fn apply_resolution(
+    plan: &mut SyncPlan,
+    progress: &mut SessionProgress,
+    conflict: &SyncConflict,
+    resolution: SyncConflictPolicy,
+) {
+    let Some(story) = plan
+        .prd
+        .user_stories
+        .iter_mut()
+        .find(|s| s.id == conflict.id)
+    else {
+        return;
+    };
+    match resolution {
+        SyncConflictPolicy::KeepLocal => {}
+        SyncConflictPolicy::TakeRemote => {
+            let (passes, status, message) = match conflict.kind {
+                ConflictKind::ClosedRemotely => (true, TaskStatus::Completed, "Closed in source"),
+                ConflictKind::ReopenedRemotely => {
+                    (false, TaskStatus::Pending, "Reopened in source")
+                }
+            };
+            story.passes = passes;
+            progress.set_task_status(
+                &conflict.id,
+                status,
+                &story.source,
+                Some(message.to_string()),
+            );
+        }
+        SyncConflictPolicy::Review => {
+            progress.set_task_status(
+                &conflict.id,
+                TaskStatus::Skipped,
+                &story.source,
+                Some(format!(
+                    "Sync conflict ({}): needs review",
+                    conflict.describe()
+                )),
+            );
+        }
+    }
+}
+
+/// Detect conflicts in a plan and settle each with `resolve`.
+///
+/// Progress is read from and written to `.afk/progress.json` under `root`
+/// (saved only when a resolution changed it). Returns each conflict with
+/// the resolution applied.
+pub fn resolve_conflicts(
+    plan: &mut SyncPlan,
+    root: Option<&Path>,
+    github_sync_back: bool,
+    mut resolve: impl FnMut(&SyncConflict) -> SyncConflictPolicy,
+) -> Result<Vec<(SyncConflict, SyncConflictPolicy)>, PrdError> {
+    let progress_path = root.map(|r| r.join(PROGRESS_FILE));
+    let mut progress = SessionProgress::load(progress_path.as_deref())?;
+    let conflicts = detect_conflicts(plan, &progress, github_sync_back);
+
+    let mut resolved = Vec::new();
+    for conflict in conflicts {
+        let resolution = resolve(&conflict);
+        apply_resolution(plan, &mut progress, &conflict, resolution);
+        resolved.push((conflict, resolution));
+    }
+
+    if resolved
+        .iter()
+        .any(|(_, r)| *r != SyncConflictPolicy::KeepLocal)
+    {
+        progress.save(progress_path.as_deref())?;
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::{PrdDocument, UserStory};
+    use std::collections::HashSet;
+
+    fn story(id: &str, source: &str, passes: bool) -> UserStory {
+        UserStory {
+            source: source.to_string(),
+            passes,
+            ..UserStory::new(id, id)
+        }
+    }
+
+    fn plan() -> SyncPlan {
+        SyncPlan {
+            prd: PrdDocument {
+                user_stories: vec![
+                    story("failing", "beads", false),
+                    story("dropped", "beads", false),
+                    story("done", "beads", true),
+                    story("done-json", "json:tasks.json", true),
+                ],
+                ..Default::default()
+            },
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed: vec!["failing".to_string(), "dropped".to_string()],
+            listed: HashSet::from(["done".to_string(), "done-json".to_string()]),
+            merges: Vec::new(),
+            folded: Vec::new(),
+            duplicates: Vec::new(),
+            source_map: Default::default(),
+        }
+    }
+
+    fn progress() -> SessionProgress {
+        let mut progress = SessionProgress::new();
+        progress.set_task_status("failing", TaskStatus::Failed, "beads", None);
+        progress.set_task_status("dropped", TaskStatus::Pending, "beads", None);
+        progress
+    }
+
+    #[test]
+    fn test_detect_conflicts() {
+        let conflicts = detect_conflicts(&plan(), &progress(), true);
+        let found: Vec<(&str, ConflictKind)> =
+            conflicts.iter().map(|c| (c.id.as_str(), c.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("failing", ConflictKind::ClosedRemotely),
+                ("done", ConflictKind::ReopenedRemotely),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_github_reopen_needs_sync_back() {
+        let mut plan = plan();
+        plan.prd.user_stories[2].source = "github:owner/repo".to_string();
+        assert_eq!(detect_conflicts(&plan, &progress(), true).len(), 2);
+        assert_eq!(detect_conflicts(&plan, &progress(), false).len(), 1);
+    }
+
+    #[test]
+    fn test_take_remote() {
+        let mut plan = plan();
+        let mut progress = progress();
+        for conflict in detect_conflicts(&plan, &progress, true) {
+            apply_resolution(
+                &mut plan,
+                &mut progress,
+                &conflict,
+                SyncConflictPolicy::TakeRemote,
+            );
+        }
+
+        assert!(plan.prd.get_story("failing").unwrap().passes);
+        assert_eq!(
+            progress.get_task("failing").unwrap().status,
+            TaskStatus::Completed
+        );
+        assert!(!plan.prd.get_story("done").unwrap().passes);
+        assert_eq!(
+            progress.get_task("done").unwrap().status,
+            TaskStatus::Pending
+        );
+    }
+
+    #[test]
+    fn test_review_and_keep_local() {
+        let mut plan = plan();
+        let mut progress = progress();
+        let conflicts = detect_conflicts(&plan, &progress, true);
+
+        apply_resolution(
+            &mut plan,
+            &mut progress,
+            &conflicts[0],
+            SyncConflictPolicy::Review,
+        );
+        let task = progress.get_task("failing").unwrap();
+        assert_eq!(task.status, TaskStatus::Skipped);
+        assert!(task.message.as_deref().unwrap().contains("needs review"));
+
+        apply_resolution(
+            &mut plan,
+            &mut progress,
+            &conflicts[1],
+            SyncConflictPolicy::KeepLocal,
+        );
+        assert!(plan.prd.get_story("done").unwrap().passes);
+        assert!(progress.get_task("done").is_none());
+    }
+
+    #[test]
+    fn test_resolve_conflicts_saves_progress() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let progress_path = temp.path().join(PROGRESS_FILE);
+        progress().save(Some(&progress_path)).unwrap();
+
+        let mut plan = plan();
+        let resolved = resolve_conflicts(&mut plan, Some(temp.path()), true, |c| match c.kind {
+            ConflictKind::ClosedRemotely => SyncConflictPolicy::Review,
+            ConflictKind::ReopenedRemotely => SyncConflictPolicy::KeepLocal,
+        })
+        .unwrap();
+        assert_eq!(resolved.len(), 2);
+
+        let saved = SessionProgress::load(Some(&progress_path)).unwrap();
+        assert_eq!(
+            saved.get_task("failing").unwrap().status,
+            TaskStatus::Skipped
+        );
+    }
+}
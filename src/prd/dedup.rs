@@ -0,0 +1,483 @@
+//! Merging the same task listed by several sources.
+//!
+//! The same piece of work often turns up in two places, such as a TODO.md
+//! item and a GitHub issue. Sync folds such duplicates into one task: ones
+//! with the same ID, or titles that are nearly the same once normalised
+//! (see [`DedupConfig`]). The task listed first keeps its ID and source,
+//! and gains what the duplicate adds. Each merge is remembered in
+//! `.afk/source-map.json`, so later syncs merge the pair the same way even
+//! after their titles drift apart, and a task keeps its ID if its first
+//! source drops it.
+//!
+//! Only tasks from different kinds of source are merged; a source listing
+//! two similar tasks presumably means two tasks.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{DedupConfig, SOURCE_MAP_FILE};
+
+use super::{PrdError, UserStory};
+
+/// Remembered merges, as stored in `.afk/source-map.json`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceMap {
+    /// One entry per merged task.
+    #[serde(default)]
+    pub links: Vec<SourceLink>,
+}
+
+/// A source task merged into another.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceLink {
+    /// Source the duplicate came from.
+    pub source: String,
+    /// ID the duplicate has in its source.
+    pub id: String,
+    /// ID of the task it was merged into.
+    pub merged_into: String,
+}
+
+impl SourceMap {
+    /// Load the source map, or an empty one if there is none.
+    pub fn load(root: Option<&Path>) -> Self {
+        fs::read_to_string(map_path(root))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the source map, unless it is empty and was never written.
+    pub fn save(&self, root: Option<&Path>) -> Result<(), PrdError> {
+        let path = map_path(root);
+        if self.links.is_empty() && !path.exists() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// ID of the task a source's task was merged into, if it was.
+    pub fn merged_into(&self, source: &str, id: &str) -> Option<&str> {
+        self.links
+            .iter()
+            .find(|l| l.source == source && l.id == id)
+            .map(|l| l.merged_into.as_str())
+    }
+
+    /// Remember that a source's task was merged into another.
+    fn link(&mut self, source: &str, id: &str, merged_into: &str) {
+        match self
+            .links
+            .iter_mut()
+            .find(|l| l.source == source && l.id == id)
+        {
+            Some(link) => link.merged_into = merged_into.to_string(),
+            None => self.links.push(SourceLink {
+                source: source.to_string(),
+                id: id.to_string(),
+                merged_into: merged_into.to_string(),
+            }),
+        }
+    }
+}
+
+fn map_path(root: Option<&Path>) -> std::path::PathBuf {
+    match root {
+        Some(root) => root.join(SOURCE_MAP_FILE),
+        None => Path::new(SOURCE_MAP_FILE).to_path_buf(),
+    }
+}
+
+/// Why two tasks were taken to be the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchReason {
+    /// An earlier sync merged them.
+    Remembered,
+    /// They have the same ID.
+    Id,
+    /// Their titles are alike.
+    Title,
+}
+
+/// A task folded into another during sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskMerge {
+    /// ID of the task kept.
+    pub id: String,
+    /// ID the duplicate has in its source.
+    pub duplicate_id: String,
+    /// Source of the duplicate.
+    pub duplicate_source: String,
+    /// Why they matched.
+    pub reason: MatchReason,
+}
+
+/// Sources that disagree about a task, reported rather than settled
+/// silently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateConflict {
+    /// ID of the task kept.
+    pub id: String,
+    /// What disagrees, and what sync did about it.
+    pub detail: String,
+}
+
+/// Stories with duplicates folded together.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deduped {
+    /// Stories to sync, each listed once.
+    pub stories: Vec<UserStory>,
+    /// Duplicates folded into another story.
+    pub merges: Vec<TaskMerge>,
+    /// Disagreements found on the way.
+    pub conflicts: Vec<DuplicateConflict>,
+}
+
+/// Fold duplicates in `incoming`, stories fresh from the synced sources.
+///
+/// `anchors` are stories already in tasks.json from sources not being
+/// synced; an incoming story that duplicates one comes back as the anchor
+/// with the duplicate merged in. New merges are added to `map`.
+pub fn dedup(
+    incoming: Vec<UserStory>,
+    anchors: &[UserStory],
+    config: &DedupConfig,
+    map: &mut SourceMap,
+) -> Deduped {
+    let mut result = Deduped::default();
+    for mut story in incoming {
+        let found = find_match(&story, &result.stories, config, map)
+            .map(|(i, reason)| (Target::Kept(i), reason))
+            .or_else(|| {
+                find_match(&story, anchors, config, map)
+                    .map(|(i, reason)| (Target::Anchor(i), reason))
+            });
+        let Some((target, reason)) = found else {
+            // Keep the ID an earlier merge gave it, even with the task it
+            // was merged into gone
+            if let Some(id) = map.merged_into(&story.source, &story.id) {
+                story.id = id.to_string();
+            }
+            result.stories.push(story);
+            continue;
+        };
+
+        let kept = match target {
+            Target::Kept(i) => &mut result.stories[i],
+            Target::Anchor(i) => {
+                result.stories.push(anchors[i].clone());
+                result.stories.last_mut().expect("just pushed")
+            }
+        };
+        if reason == MatchReason::Id && !titles_alike(&kept.title, &story.title, config) {
+            result.conflicts.push(DuplicateConflict {
+                id: kept.id.clone(),
+                detail: format!(
+                    "{} lists a different task with the same ID (\"{}\"); kept the one from {}",
+                    story.source, story.title, kept.source
+                ),
+            });
+            continue;
+        }
+        if kept.priority != story.priority {
+            result.conflicts.push(DuplicateConflict {
+                id: kept.id.clone(),
+                detail: format!(
+                    "priority P{} in {}, P{} in {}; kept P{}",
+                    kept.priority,
+                    kept.source,
+                    story.priority,
+                    story.source,
+                    kept.priority.min(story.priority)
+                ),
+            });
+        }
+        map.link(&story.source, &story.id, &kept.id);
+        result.merges.push(TaskMerge {
+            id: kept.id.clone(),
+            duplicate_id: story.id.clone(),
+            duplicate_source: story.source.clone(),
+            reason,
+        });
+        merge(kept, story);
+    }
+    result
+}
+
+/// Where a match was found.
+enum Target {
+    /// A story already kept from the incoming list.
+    Kept(usize),
+    /// A story from a source that isn't being synced.
+    Anchor(usize),
+}
+
+/// The first of `candidates` from another kind of source that `story`
+/// duplicates, and why.
+fn find_match(
+    story: &UserStory,
+    candidates: &[UserStory],
+    config: &DedupConfig,
+    map: &SourceMap,
+) -> Option<(usize, MatchReason)> {
+    let kind = source_kind(&story.source);
+    let others = || {
+        candidates
+            .iter()
+            .enumerate()
+            .filter(move |(_, c)| source_kind(&c.source) != kind)
+    };
+    if let Some(id) = map.merged_into(&story.source, &story.id) {
+        if let Some((i, _)) = others().find(|(_, c)| c.id == id) {
+            return Some((i, MatchReason::Remembered));
+        }
+    }
+    if config.by_id {
+        if let Some((i, _)) = others().find(|(_, c)| c.id == story.id) {
+            return Some((i, MatchReason::Id));
+        }
+    }
+    if config.by_title {
+        if let Some((i, _)) = others().find(|(_, c)| titles_alike(&c.title, &story.title, config)) {
+            return Some((i, MatchReason::Title));
+        }
+    }
+    None
+}
+
+/// The kind of source a story came from: "github" for
+/// "github:acme/api#12", "beads" for "beads".
+pub fn source_kind(source: &str) -> &str {
+    source.split(':').next().unwrap_or(source)
+}
+
+/// Fold what `duplicate` adds into `kept`: the most urgent priority, any
+/// acceptance criteria, dependencies and notes it lacks, and fields it
+/// leaves unset.
+fn merge(kept: &mut UserStory, duplicate: UserStory) {
+    kept.priority = kept.priority.min(duplicate.priority);
+    if kept.description.trim().is_empty() || kept.description == kept.title {
+        kept.description = duplicate.description;
+    }
+    for criterion in duplicate.acceptance_criteria {
+        if !kept
+            .acceptance_criteria
+            .iter()
+            .any(|c| c.text == criterion.text)
+        {
+            kept.acceptance_criteria.push(criterion);
+        }
+    }
+    for dep in duplicate.depends_on {
+        if !kept.depends_on.contains(&dep) && dep != kept.id {
+            kept.depends_on.push(dep);
+        }
+    }
+    let notes = duplicate.notes.trim();
+    if !notes.is_empty() && !kept.notes.contains(notes) {
+        if !kept.notes.is_empty() {
+            kept.notes.push('\n');
+        }
+        kept.notes.push_str(notes);
+    }
+    kept.discussion.extend(duplicate.discussion);
+    kept.effort = kept.effort.or(duplicate.effort);
+    kept.estimate = kept.estimate.or(duplicate.estimate);
+    kept.model = kept.model.take().or(duplicate.model);
+    kept.cli = kept.cli.take().or(duplicate.cli);
+    kept.epic = kept.epic.take().or(duplicate.epic);
+    kept.passes = kept.passes || duplicate.passes;
+}
+
+/// Whether two titles are at least `title_similarity` alike once
+/// normalised.
+fn titles_alike(a: &str, b: &str, config: &DedupConfig) -> bool {
+    similarity(&normalise(a), &normalise(b)) >= config.title_similarity
+}
+
+/// Lowercase words, without punctuation.
+fn normalise(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How alike two strings are, from 0 to 1: one less the edit distance
+/// over the longer length.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: &str, title: &str, source: &str) -> UserStory {
+        UserStory {
+            source: source.to_string(),
+            ..UserStory::new(id, title)
+        }
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(normalise("Add  login-form!"), "add login form");
+        assert!((similarity("abc", "abc") - 1.0).abs() < f64::EPSILON);
+        assert!((similarity("add login form", "add a login form") - 0.875).abs() < 1e-9);
+        assert!(similarity("add login form", "fix billing") < 0.5);
+    }
+
+    #[test]
+    fn test_dedup_by_title_and_id() {
+        let mut todo = story("add-login-form", "Add login form", "markdown:TODO.md");
+        todo.priority = 2;
+        let mut issue = story("gh-12", "Add login form.", "github:acme/api#12");
+        issue.priority = 1;
+        issue.acceptance_criteria = vec!["Form validates email".into()];
+        let mut beads = story("add-login-form", "Add login forms", "beads");
+        beads.priority = 1;
+        let other = story("docs", "Write docs", "github:acme/api#13");
+        let mut map = SourceMap::default();
+
+        let deduped = dedup(
+            vec![todo, issue, beads, other],
+            &[],
+            &DedupConfig::default(),
+            &mut map,
+        );
+        let ids: Vec<&str> = deduped.stories.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["add-login-form", "docs"]);
+        let merged = &deduped.stories[0];
+        assert_eq!(merged.source, "markdown:TODO.md");
+        assert_eq!(merged.priority, 1);
+        assert!(merged
+            .acceptance_criteria
+            .iter()
+            .any(|c| c.text == "Form validates email"));
+        let reasons: Vec<MatchReason> = deduped.merges.iter().map(|m| m.reason).collect();
+        assert_eq!(reasons, [MatchReason::Title, MatchReason::Id]);
+        assert_eq!(deduped.conflicts.len(), 1);
+        assert!(deduped.conflicts[0].detail.starts_with("priority P2"));
+        assert_eq!(
+            map.merged_into("github:acme/api#12", "gh-12"),
+            Some("add-login-form")
+        );
+    }
+
+    #[test]
+    fn test_dedup_same_id_different_task_is_a_conflict() {
+        let json = story("T-1", "Add login form", "json:tasks.json");
+        let beads = story("T-1", "Migrate billing tables", "beads");
+        let mut map = SourceMap::default();
+
+        let deduped = dedup(vec![json, beads], &[], &DedupConfig::default(), &mut map);
+        assert_eq!(deduped.stories.len(), 1);
+        assert_eq!(deduped.stories[0].title, "Add login form");
+        assert!(deduped.merges.is_empty());
+        assert!(deduped.conflicts[0].detail.contains("different task"));
+        assert!(map.links.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_remembers_merges_and_anchors() {
+        let mut map = SourceMap::default();
+        map.link("github:acme/api#12", "gh-12", "add-login-form");
+
+        // The title drifted, but the remembered merge still applies
+        let todo = story("add-login-form", "Add login form", "markdown:TODO.md");
+        let issue = story("gh-12", "Login page with SSO", "github:acme/api#12");
+        let deduped = dedup(
+            vec![todo.clone(), issue.clone()],
+            &[],
+            &DedupConfig::default(),
+            &mut map,
+        );
+        assert_eq!(deduped.stories.len(), 1);
+        assert_eq!(deduped.merges[0].reason, MatchReason::Remembered);
+
+        // Syncing GitHub alone merges into the TODO.md task already synced
+        let deduped = dedup(
+            vec![issue.clone()],
+            std::slice::from_ref(&todo),
+            &DedupConfig::default(),
+            &mut map,
+        );
+        assert_eq!(deduped.stories[0].id, "add-login-form");
+        assert_eq!(deduped.stories[0].source, "markdown:TODO.md");
+
+        // With the TODO.md task gone, the issue keeps the merged ID
+        let deduped = dedup(vec![issue], &[], &DedupConfig::default(), &mut map);
+        assert_eq!(deduped.stories[0].id, "add-login-form");
+        assert_eq!(deduped.stories[0].source, "github:acme/api#12");
+    }
+
+    #[test]
+    fn test_dedup_respects_config_and_source_kind() {
+        let a = story("a-1", "Add login form", "markdown:TODO.md");
+        let b = story("b-1", "Add login form", "markdown:BACKLOG.md");
+        let c = story("a-1", "Add login form", "beads");
+        let off = DedupConfig {
+            by_id: false,
+            by_title: false,
+            ..DedupConfig::default()
+        };
+
+        let mut map = SourceMap::default();
+        let deduped = dedup(
+            vec![a.clone(), b.clone()],
+            &[],
+            &DedupConfig::default(),
+            &mut map,
+        );
+        assert_eq!(deduped.stories.len(), 2);
+        let deduped = dedup(vec![a, b, c], &[], &off, &mut map);
+        assert_eq!(deduped.stories.len(), 3);
+    }
+
+    #[test]
+    fn test_source_map_round_trip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = Some(temp.path());
+        SourceMap::default().save(root).unwrap();
+        assert!(!temp.path().join(SOURCE_MAP_FILE).exists());
+
+        let mut map = SourceMap::default();
+        map.link("beads", "bd-1", "T-1");
+        map.link("beads", "bd-1", "T-2");
+        map.save(root).unwrap();
+        let loaded = SourceMap::load(root);
+        assert_eq!(loaded, map);
+        assert_eq!(loaded.links.len(), 1);
+        assert_eq!(loaded.merged_into("beads", "bd-1"), Some("T-2"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Filling in thin tasks with AI-written detail.
+//!
+//! Tasks imported from checklists often have only a title: the description
+//! repeats it and the sole criterion is `Complete: <title>`. `afk tasks
+//! enrich` sends such tasks to the AI and writes back a description and
+//! acceptance criteria, listing the fields it wrote in the story's
+//! `generated` so a person can review them. Generated fields survive
+//! syncs for as long as the source still has nothing better.
+
+use serde::Deserialize;
+use serde_json::Value;
+use tera::{Context, Tera};
+
+use super::repair::{extract_json, strip_trailing_commas};
+use super::{AcceptanceCriterion, UserStory};
+
+/// Prompt asking the AI to enrich a batch of tasks.
+pub const ENRICH_TEMPLATE: &str = include_str!("enrich_template.md");
+
+/// The `generated` entry for a written description.
+pub const GENERATED_DESCRIPTION: &str = "description";
+
+/// The `generated` entry for written acceptance criteria.
+pub const GENERATED_CRITERIA: &str = "acceptanceCriteria";
+
+/// The AI's detail for one task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Enrichment {
+    /// ID of the task.
+    pub id: String,
+    /// Description written for it.
+    #[serde(default)]
+    pub description: String,
+    /// Acceptance criteria written for it.
+    #[serde(default, alias = "acceptance_criteria")]
+    pub acceptance_criteria: Vec<String>,
+}
+
+/// Whether a story's description says no more than its title.
+fn thin_description(story: &UserStory) -> bool {
+    let description = story.description.trim();
+    description.is_empty() || description.eq_ignore_ascii_case(story.title.trim())
+}
+
+/// Whether a story's criteria are missing or only the placeholder sources
+/// write when they have none.
+fn thin_criteria(story: &UserStory) -> bool {
+    let title = story.title.trim();
+    story.acceptance_criteria.iter().all(|criterion| {
+        let text = criterion.text.trim();
+        text.eq_ignore_ascii_case(title)
+            || text
+                .strip_prefix("Complete:")
+                .is_some_and(|rest| rest.trim().eq_ignore_ascii_case(title))
+    })
+}
+
+/// The fields of a pending story that need writing, as `generated`
+/// entries. Empty for stories that are complete or already specified.
+pub fn thin_fields(story: &UserStory) -> Vec<&'static str> {
+    if story.passes {
+        return Vec::new();
+    }
+    let mut fields = Vec::new();
+    if thin_description(story) {
+        fields.push(GENERATED_DESCRIPTION);
+    }
+    if thin_criteria(story) {
+        fields.push(GENERATED_CRITERIA);
+    }
+    fields
+}
+
+/// Render the enrich prompt for `stories`.
+pub fn generate_enrich_prompt(
+    project: &str,
+    stories: &[&UserStory],
+) -> Result<String, tera::Error> {
+    let tasks: Vec<Value> = stories
+        .iter()
+        .map(|story| {
+            let description = (!thin_description(story)).then_some(&story.description);
+            let criteria: Vec<&str> = if thin_criteria(story) {
+                Vec::new()
+            } else {
+                story
+                    .acceptance_criteria
+                    .iter()
+                    .map(|c| c.as_str())
+                    .collect()
+            };
+            serde_json::json!({
+                "id": story.id,
+                "title": story.title,
+                "epic": story.epic,
+                "description": description,
+                "criteria": criteria,
+            })
+        })
+        .collect();
+
+    let mut tera = Tera::default();
+    tera.add_raw_template("enrich", ENRICH_TEMPLATE)?;
+    let mut context = Context::new();
+    context.insert("project", project);
+    context.insert("tasks", &tasks);
+    tera.render("enrich", &context)
+}
+
+/// Parse the AI's reply to the enrich prompt.
+pub fn parse_enrichments(reply: &str) -> Result<Vec<Enrichment>, String> {
+    #[derive(Deserialize)]
+    struct Reply {
+        #[serde(alias = "userStories", alias = "tasks")]
+        stories: Vec<Enrichment>,
+    }
+
+    let json = extract_json(reply);
+    let parsed: Result<Reply, _> = serde_json::from_str(json)
+        .or_else(|first| serde_json::from_str(&strip_trailing_commas(json)).map_err(|_| first));
+    parsed
+        .map(|reply| reply.stories)
+        .map_err(|e| format!("the AI's reply is not the expected JSON: {e}"))
+}
+
+/// Write an enrichment into the thin fields of `story`, marking them
+/// generated. Fields already specified are left alone. Returns the fields
+/// written.
+pub fn apply_enrichment(story: &mut UserStory, enrichment: &Enrichment) -> Vec<&'static str> {
+    let mut written = Vec::new();
+    for field in thin_fields(story) {
+        match field {
+            GENERATED_DESCRIPTION => {
+                let description = enrichment.description.trim();
+                if description.is_empty() {
+                    continue;
+                }
+                story.description = description.to_string();
+            }
+            _ => {
+                let criteria: Vec<AcceptanceCriterion> = enrichment
+                    .acceptance_criteria
+                    .iter()
+                    .map(|c| c.trim())
+                    .filter(|c| !c.is_empty())
+                    .map(AcceptanceCriterion::new)
+                    .collect();
+                if criteria.is_empty() {
+                    continue;
+                }
+                story.acceptance_criteria = criteria;
+            }
+        }
+        if !story.generated.iter().any(|g| g == field) {
+            story.generated.push(field.to_string());
+        }
+        written.push(field);
+    }
+    written
+}
+
+/// Carry generated fields from the `previous` version of a story into the
+/// version a source just gave, where the source's is still thin.
+pub fn keep_generated(story: &mut UserStory, previous: &UserStory) {
+    for field in &previous.generated {
+        let kept = match field.as_str() {
+            GENERATED_DESCRIPTION if thin_description(story) => {
+                story.description.clone_from(&previous.description);
+                true
+            }
+            GENERATED_CRITERIA if thin_criteria(story) => {
+                story
+                    .acceptance_criteria
+                    .clone_from(&previous.acceptance_criteria);
+                true
+            }
+            _ => false,
+        };
+        if kept && !story.generated.contains(field) {
+            story.generated.push(field.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checklist_item(id: &str, title: &str) -> UserStory {
+        let mut story = UserStory::new(id, title);
+        story.acceptance_criteria = vec![format!("Complete: {title}").into()];
+        story
+    }
+
+    #[test]
+    fn test_thin_fields() {
+        let thin = checklist_item("a", "Add logout");
+        assert_eq!(
+            thin_fields(&thin),
+            [GENERATED_DESCRIPTION, GENERATED_CRITERIA]
+        );
+
+        let mut described = checklist_item("b", "Add logout");
+        described.description = "Clear the session and redirect home".to_string();
+        assert_eq!(thin_fields(&described), [GENERATED_CRITERIA]);
+
+        let mut specified = described.clone();
+        specified.acceptance_criteria = vec!["Session cookie is removed".into()];
+        assert!(thin_fields(&specified).is_empty());
+
+        let mut done = checklist_item("c", "Add logout");
+        done.passes = true;
+        assert!(thin_fields(&done).is_empty());
+    }
+
+    #[test]
+    fn test_prompt_lists_tasks() {
+        let mut described = checklist_item("b", "Export PDF");
+        described.description = "Reports need printing".to_string();
+        described.epic = Some("Reports".to_string());
+        let thin = checklist_item("a", "Add logout");
+        let prompt = generate_enrich_prompt("shop", &[&thin, &described]).unwrap();
+        assert!(prompt.contains("Project: shop"));
+        assert!(prompt.contains("### a: Add logout"));
+        assert!(prompt.contains("### b: Export PDF\n\nEpic: Reports\n\nReports need printing"));
+        assert!(!prompt.contains("Complete: Add logout"));
+        assert!(prompt.contains("\"acceptanceCriteria\""));
+    }
+
+    #[test]
+    fn test_parse_and_apply_enrichments() {
+        let reply = "Here you go:\n```json\n{\"stories\": [\n  {\"id\": \"a\", \"description\": \"Clear the session.\", \
+                     \"acceptance_criteria\": [\"Session cookie removed\", \" \", \"Test covers logout\"]},\n]}\n```";
+        let enrichments = parse_enrichments(reply).unwrap();
+        assert_eq!(enrichments.len(), 1);
+
+        let mut story = checklist_item("a", "Add logout");
+        let written = apply_enrichment(&mut story, &enrichments[0]);
+        assert_eq!(written, [GENERATED_DESCRIPTION, GENERATED_CRITERIA]);
+        assert_eq!(story.description, "Clear the session.");
+        assert_eq!(
+            story.acceptance_criteria,
+            ["Session cookie removed", "Test covers logout"]
+        );
+        assert_eq!(story.generated, ["description", "acceptanceCriteria"]);
+
+        // Enriched fields are no longer thin
+        assert!(apply_enrichment(&mut story, &enrichments[0]).is_empty());
+        assert!(parse_enrichments("no JSON here").is_err());
+    }
+
+    #[test]
+    fn test_keep_generated_until_source_has_detail() {
+        let mut enriched = checklist_item("a", "Add logout");
+        enriched.description = "Clear the session.".to_string();
+        enriched.acceptance_criteria = vec!["Session cookie removed".into()];
+        enriched.generated = vec![GENERATED_DESCRIPTION.into(), GENERATED_CRITERIA.into()];
+
+        let mut synced = checklist_item("a", "Add logout");
+        keep_generated(&mut synced, &enriched);
+        assert_eq!(synced, enriched);
+
+        let mut detailed = checklist_item("a", "Add logout");
+        detailed.description = "Log out from the account menu".to_string();
+        keep_generated(&mut detailed, &enriched);
+        assert_eq!(detailed.description, "Log out from the account menu");
+        assert_eq!(detailed.acceptance_criteria, ["Session cookie removed"]);
+        assert_eq!(detailed.generated, [GENERATED_CRITERIA]);
+    }
+}
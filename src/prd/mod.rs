@@ -3,21 +3,176 @@
 //! This module contains Serde models for user stories and task documents.
 //! The "PRD" terminology is retained internally for backwards compatibility.
 
+pub mod conflicts;
+pub mod dedup;
+pub mod enrich;
 pub mod parse;
+pub mod repair;
+pub mod scoring;
+pub mod selection;
 pub mod store;
+pub mod template;
 
+pub use conflicts::{
+    apply_resolution, detect_conflicts, resolve_conflicts, ConflictKind, SyncConflict,
+};
 pub use parse::{generate_prd_prompt, load_prd_file, PrdParseError, PRD_PARSE_TEMPLATE};
+pub use repair::{repair_tasks_json, RepairedTasks};
+pub use selection::{group_by_epic, TaskOrder};
 pub use store::{
     get_current_branch, get_project_name, get_project_name_from_root, mark_story_complete,
     mark_story_complete_with_path, mark_story_in_progress, mark_story_in_progress_with_path,
-    sync_prd, sync_prd_with_root,
+    plan_sync, sync_prd, sync_prd_resolving, sync_prd_with_root, SyncPlan,
 };
+pub use template::{list_templates, TaskTemplate, TemplateError, TemplateTask};
 
 use crate::config::TASKS_FILE;
+use crate::schema::{self, SchemaKind};
+use chrono::{Local, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// How pending stories gain priority while they sit untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityAging {
+    /// Days untouched per priority level gained.
+    pub days_per_level: u32,
+    /// Most priority levels a story can gain.
+    pub cap: u32,
+}
+
+impl PriorityAging {
+    /// Levels gained by a story last touched at `touched_at`.
+    ///
+    /// Stories without a (parseable) timestamp don't age.
+    pub fn boost(&self, touched_at: Option<&str>, now: NaiveDateTime) -> i32 {
+        let Some(touched) =
+            touched_at.and_then(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S%.f").ok())
+        else {
+            return 0;
+        };
+        let days = (now - touched).num_days().max(0);
+        let levels = days / i64::from(self.days_per_level.max(1));
+        levels.min(i64::from(self.cap)) as i32
+    }
+}
+
+/// A single acceptance criterion with its own completion state.
+///
+/// Serialised as a plain string until it is checked off, given evidence or
+/// a verify command, so existing tasks.json files round-trip unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptanceCriterion {
+    /// The criterion text.
+    pub text: String,
+    /// Whether the criterion has been met.
+    pub passed: bool,
+    /// Optional note recording how the criterion was verified.
+    pub evidence: Option<String>,
+    /// Optional shell command that exits successfully when the criterion
+    /// holds, e.g. `cargo test auth::`.
+    pub verify: Option<String>,
+}
+
+impl AcceptanceCriterion {
+    /// Create an unmet criterion.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            passed: false,
+            evidence: None,
+            verify: None,
+        }
+    }
+
+    /// The criterion text.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<String> for AcceptanceCriterion {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
+}
+
+impl From<&str> for AcceptanceCriterion {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+impl PartialEq<str> for AcceptanceCriterion {
+    fn eq(&self, other: &str) -> bool {
+        self.text == other
+    }
+}
+
+impl PartialEq<&str> for AcceptanceCriterion {
+    fn eq(&self, other: &&str) -> bool {
+        self.text == *other
+    }
+}
+
+impl std::fmt::Display for AcceptanceCriterion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// On-disk forms of an acceptance criterion.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum CriterionRepr {
+    Text(String),
+    Tracked {
+        text: String,
+        #[serde(default)]
+        passed: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        evidence: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        verify: Option<String>,
+    },
+}
+
+impl Serialize for AcceptanceCriterion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !self.passed && self.evidence.is_none() && self.verify.is_none() {
+            CriterionRepr::Text(self.text.clone()).serialize(serializer)
+        } else {
+            CriterionRepr::Tracked {
+                text: self.text.clone(),
+                passed: self.passed,
+                evidence: self.evidence.clone(),
+                verify: self.verify.clone(),
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AcceptanceCriterion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match CriterionRepr::deserialize(deserializer)? {
+            CriterionRepr::Text(text) => Self::new(text),
+            CriterionRepr::Tracked {
+                text,
+                passed,
+                evidence,
+                verify,
+            } => Self {
+                text,
+                passed,
+                evidence,
+                verify,
+            },
+        })
+    }
+}
+
 /// A user story in Ralph format with acceptance criteria.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -30,7 +185,7 @@ pub struct UserStory {
     pub description: String,
     /// List of acceptance criteria.
     #[serde(default)]
-    pub acceptance_criteria: Vec<String>,
+    pub acceptance_criteria: Vec<AcceptanceCriterion>,
     /// Priority level (1-5, 1 = highest).
     #[serde(default = "default_priority")]
     pub priority: i32,
@@ -43,6 +198,37 @@ pub struct UserStory {
     /// Additional notes.
     #[serde(default)]
     pub notes: String,
+    /// Estimated effort in iterations, if the source provides one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<u32>,
+    /// Estimated size in `reporting.estimate_unit` (story points or
+    /// minutes), for burn-down reporting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u32>,
+    /// Latest comments from the source's discussion thread, oldest first,
+    /// each summarised as "@author (date): text".
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discussion: Vec<String>,
+    /// When sync last added or changed the story; priority aging counts
+    /// from here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub touched_at: Option<String>,
+    /// IDs of stories that must pass before this one can be done.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Model to run the story's iterations with, overriding `ai_cli.models`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// AI CLI to run the story's iterations with, overriding `ai_cli.command`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli: Option<String>,
+    /// Epic (larger piece of work) the story belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epic: Option<String>,
+    /// Fields `afk tasks enrich` wrote that no one has reviewed yet
+    /// (`description`, `acceptanceCriteria`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub generated: Vec<String>,
 }
 
 fn default_priority() -> i32 {
@@ -64,6 +250,15 @@ impl Default for UserStory {
             passes: false,
             source: default_source(),
             notes: String::new(),
+            effort: None,
+            estimate: None,
+            discussion: Vec::new(),
+            touched_at: None,
+            depends_on: Vec::new(),
+            model: None,
+            cli: None,
+            epic: None,
+            generated: Vec::new(),
         }
     }
 }
@@ -80,6 +275,52 @@ impl UserStory {
         }
     }
 
+    /// Number of met criteria and total criteria.
+    ///
+    /// All criteria of a passing story count as met.
+    pub fn criteria_counts(&self) -> (usize, usize) {
+        let total = self.acceptance_criteria.len();
+        if self.passes {
+            return (total, total);
+        }
+        let met = self.acceptance_criteria.iter().filter(|c| c.passed).count();
+        (met, total)
+    }
+
+    /// Priority after aging (lower is more urgent).
+    pub fn effective_priority(&self, aging: Option<&PriorityAging>, now: NaiveDateTime) -> i32 {
+        let boost = aging.map_or(0, |a| a.boost(self.touched_at.as_deref(), now));
+        self.priority - boost
+    }
+
+    /// Criteria that have not been met yet.
+    pub fn unmet_criteria(&self) -> Vec<&AcceptanceCriterion> {
+        if self.passes {
+            return Vec::new();
+        }
+        self.acceptance_criteria
+            .iter()
+            .filter(|c| !c.passed)
+            .collect()
+    }
+
+    /// Check off a criterion by its 1-based position.
+    ///
+    /// Returns false if there is no criterion at that position.
+    pub fn check_criterion(&mut self, number: usize, evidence: Option<String>) -> bool {
+        let Some(criterion) = number
+            .checked_sub(1)
+            .and_then(|i| self.acceptance_criteria.get_mut(i))
+        else {
+            return false;
+        };
+        criterion.passed = true;
+        if evidence.is_some() {
+            criterion.evidence = evidence;
+        }
+        true
+    }
+
     /// Create from a JSON dict that may use various key names.
     ///
     /// Supports:
@@ -115,8 +356,7 @@ impl UserStory {
             .and_then(|v| v.as_array())
             .map(|arr| {
                 arr.iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| s.to_string())
+                    .filter_map(|v| AcceptanceCriterion::deserialize(v).ok())
                     .collect()
             })
             .unwrap_or_default();
@@ -144,6 +384,68 @@ impl UserStory {
             .unwrap_or("")
             .to_string();
 
+        let effort = data
+            .get("effort")
+            .and_then(|v| v.as_u64())
+            .and_then(|e| u32::try_from(e).ok());
+
+        let estimate = data
+            .get("estimate")
+            .or_else(|| data.get("storyPoints"))
+            .and_then(|v| v.as_u64())
+            .and_then(|e| u32::try_from(e).ok());
+
+        let discussion = data
+            .get("discussion")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(ToOwned::to_owned))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let touched_at = data
+            .get("touchedAt")
+            .and_then(|v| v.as_str())
+            .map(ToOwned::to_owned);
+
+        let depends_on = data
+            .get("dependsOn")
+            .or_else(|| data.get("depends_on"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(ToOwned::to_owned))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let model = data
+            .get("model")
+            .and_then(|v| v.as_str())
+            .map(ToOwned::to_owned);
+
+        let cli = data
+            .get("cli")
+            .and_then(|v| v.as_str())
+            .map(ToOwned::to_owned);
+
+        let epic = data
+            .get("epic")
+            .and_then(|v| v.as_str())
+            .map(ToOwned::to_owned);
+
+        let generated = data
+            .get("generated")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(ToOwned::to_owned))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Self {
             id,
             title,
@@ -153,6 +455,15 @@ impl UserStory {
             passes,
             source,
             notes,
+            effort,
+            estimate,
+            discussion,
+            touched_at,
+            depends_on,
+            model,
+            cli,
+            epic,
+            generated,
         }
     }
 }
@@ -187,6 +498,15 @@ pub enum PrdError {
     /// Failed to parse the PRD/tasks JSON.
     #[error("Failed to parse PRD JSON: {0}")]
     ParseError(#[from] serde_json::Error),
+    /// The tasks file does not match the tasks schema.
+    #[error("Tasks file does not match the schema: {0}")]
+    Invalid(String),
+    /// Refused to write because read-only mode is active.
+    #[error("Refusing to write tasks: read-only mode is active")]
+    ReadOnly,
+    /// Failed to update session progress while syncing.
+    #[error("Failed to update progress: {0}")]
+    Progress(#[from] crate::progress::ProgressError),
 }
 
 impl PrdDocument {
@@ -260,6 +580,11 @@ impl PrdDocument {
         let contents = fs::read_to_string(&path)?;
         let data: serde_json::Value = serde_json::from_str(&contents)?;
 
+        let violations = SchemaKind::Tasks.validate(&data);
+        if !violations.is_empty() {
+            return Err(PrdError::Invalid(schema::describe(&violations)));
+        }
+
         Ok(Self::from_json_value(&data))
     }
 
@@ -271,6 +596,10 @@ impl PrdDocument {
     ///
     /// Creates parent directories if they don't exist.
     pub fn save(&self, path: Option<&Path>) -> Result<(), PrdError> {
+        if crate::config::is_read_only() {
+            return Err(PrdError::ReadOnly);
+        }
+
         let path = path
             .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from(TASKS_FILE));
@@ -285,21 +614,44 @@ impl PrdDocument {
         Ok(())
     }
 
-    /// Get stories that haven't passed yet, sorted by priority.
+    /// Get stories that haven't passed yet, in `order`.
+    ///
+    /// Stories are sorted by priority, aged if `order` has aging. With
+    /// probability ordering, likely completions come first; otherwise the
+    /// selection strategy decides. Either way, with `stick_to_epic` on, the
+    /// rest of the epic worked on last comes first.
+    #[must_use]
+    pub fn get_pending_stories(&self, order: &TaskOrder) -> Vec<&UserStory> {
+        let pending =
+            self.get_pending_stories_aged(order.aging.as_ref(), Local::now().naive_local());
+        order.apply(pending, self)
+    }
+
+    /// Number of stories that haven't passed yet.
+    #[must_use]
+    pub fn pending_count(&self) -> usize {
+        self.user_stories.iter().filter(|s| !s.passes).count()
+    }
+
+    /// Get pending stories ordered by priority after aging.
+    ///
+    /// Ties go to the story with the higher original priority, so urgent
+    /// items still come before aged chores.
     #[must_use]
-    pub fn get_pending_stories(&self) -> Vec<&UserStory> {
+    pub fn get_pending_stories_aged(
+        &self,
+        aging: Option<&PriorityAging>,
+        now: NaiveDateTime,
+    ) -> Vec<&UserStory> {
         let mut pending: Vec<&UserStory> = self.user_stories.iter().filter(|s| !s.passes).collect();
-        pending.sort_unstable_by_key(|s| s.priority);
+        pending.sort_by_key(|s| (s.effective_priority(aging, now), s.priority));
         pending
     }
 
-    /// Get the next story to work on (highest priority, not passed).
+    /// Get the next story to work on (first of [`Self::get_pending_stories`]).
     #[must_use]
-    pub fn get_next_story(&self) -> Option<&UserStory> {
-        self.user_stories
-            .iter()
-            .filter(|s| !s.passes)
-            .min_by_key(|s| s.priority)
+    pub fn get_next_story(&self, order: &TaskOrder) -> Option<&UserStory> {
+        self.get_pending_stories(order).into_iter().next()
     }
 
     /// Check if all stories have passed.
@@ -342,6 +694,29 @@ impl PrdDocument {
         let total = self.user_stories.len();
         (completed, total)
     }
+
+    /// Get counts of completed and total stories in each epic, in the order
+    /// epics first appear. Stories without an epic are left out.
+    #[must_use]
+    pub fn get_epic_counts(&self) -> Vec<(&str, usize, usize)> {
+        group_by_epic(&self.user_stories)
+            .into_iter()
+            .filter_map(|(epic, stories)| {
+                let completed = stories.iter().filter(|s| s.passes).count();
+                Some((epic?, completed, stories.len()))
+            })
+            .collect()
+    }
+
+    /// Get criterion-level counts across all stories.
+    ///
+    /// Returns (met, total) acceptance criteria.
+    pub fn get_criteria_counts(&self) -> (usize, usize) {
+        self.user_stories
+            .iter()
+            .map(UserStory::criteria_counts)
+            .fold((0, 0), |(met, total), (m, t)| (met + m, total + t))
+    }
 }
 
 #[cfg(test)]
@@ -378,11 +753,20 @@ mod tests {
             id: "test-001".to_string(),
             title: "Test Story".to_string(),
             description: "A test story".to_string(),
-            acceptance_criteria: vec!["AC1".to_string(), "AC2".to_string()],
+            acceptance_criteria: vec!["AC1".into(), "AC2".into()],
             priority: 1,
             passes: true,
             source: "json:test.json".to_string(),
             notes: "Some notes".to_string(),
+            effort: None,
+            estimate: None,
+            discussion: Vec::new(),
+            touched_at: None,
+            depends_on: Vec::new(),
+            model: None,
+            cli: None,
+            epic: None,
+            generated: Vec::new(),
         };
 
         let json = serde_json::to_string(&story).unwrap();
@@ -400,7 +784,9 @@ mod tests {
             "priority": 1,
             "passes": true,
             "source": "beads",
-            "notes": "Test notes"
+            "notes": "Test notes",
+            "effort": 2,
+            "discussion": ["@alice (2024-05-03): Make it 10."]
         }"#;
 
         let data: serde_json::Value = serde_json::from_str(json).unwrap();
@@ -414,6 +800,8 @@ mod tests {
         assert!(story.passes);
         assert_eq!(story.source, "beads");
         assert_eq!(story.notes, "Test notes");
+        assert_eq!(story.effort, Some(2));
+        assert_eq!(story.discussion, vec!["@alice (2024-05-03): Make it 10."]);
     }
 
     #[test]
@@ -604,6 +992,19 @@ mod tests {
         assert!(prd.user_stories[0].passes);
     }
 
+    #[test]
+    fn test_prd_document_load_rejects_schema_violations() {
+        let temp = TempDir::new().unwrap();
+        let prd_path = temp.path().join("tasks.json");
+        fs::write(&prd_path, r#"{"userStories": [{"title": "No id"}]}"#).unwrap();
+
+        let err = PrdDocument::load(Some(&prd_path)).unwrap_err();
+        assert!(matches!(err, PrdError::Invalid(_)));
+        assert!(err
+            .to_string()
+            .contains("$.userStories[0]: missing required property 'id'"));
+    }
+
     #[test]
     fn test_prd_document_save_creates_directory() {
         let temp = TempDir::new().unwrap();
@@ -635,21 +1036,39 @@ mod tests {
                     id: "story-1".to_string(),
                     title: "First Story".to_string(),
                     description: "Description 1".to_string(),
-                    acceptance_criteria: vec!["AC1".to_string(), "AC2".to_string()],
+                    acceptance_criteria: vec!["AC1".into(), "AC2".into()],
                     priority: 1,
                     passes: false,
                     source: "beads".to_string(),
                     notes: "Notes 1".to_string(),
+                    effort: None,
+                    estimate: None,
+                    discussion: Vec::new(),
+                    touched_at: None,
+                    depends_on: Vec::new(),
+                    model: None,
+                    cli: None,
+                    epic: None,
+                    generated: Vec::new(),
                 },
                 UserStory {
                     id: "story-2".to_string(),
                     title: "Second Story".to_string(),
                     description: "Description 2".to_string(),
-                    acceptance_criteria: vec!["AC3".to_string()],
+                    acceptance_criteria: vec!["AC3".into()],
                     priority: 2,
                     passes: true,
                     source: "json:test.json".to_string(),
                     notes: String::new(),
+                    effort: None,
+                    estimate: None,
+                    discussion: Vec::new(),
+                    touched_at: None,
+                    depends_on: Vec::new(),
+                    model: None,
+                    cli: None,
+                    epic: None,
+                    generated: Vec::new(),
                 },
             ],
             last_synced: "2024-01-01T12:00:00".to_string(),
@@ -702,7 +1121,7 @@ mod tests {
             ..Default::default()
         };
 
-        let pending = prd.get_pending_stories();
+        let pending = prd.get_pending_stories(&TaskOrder::default());
         assert_eq!(pending.len(), 3);
         assert_eq!(pending[0].id, "high-priority");
         assert_eq!(pending[1].id, "medium-priority");
@@ -735,7 +1154,7 @@ mod tests {
             ..Default::default()
         };
 
-        let next = prd.get_next_story();
+        let next = prd.get_next_story(&TaskOrder::default());
         assert!(next.is_some());
         assert_eq!(next.unwrap().id, "pending-high");
     }
@@ -743,7 +1162,7 @@ mod tests {
     #[test]
     fn test_get_next_story_empty() {
         let prd = PrdDocument::default();
-        assert!(prd.get_next_story().is_none());
+        assert!(prd.get_next_story(&TaskOrder::default()).is_none());
     }
 
     #[test]
@@ -764,7 +1183,7 @@ mod tests {
             ..Default::default()
         };
 
-        assert!(prd.get_next_story().is_none());
+        assert!(prd.get_next_story(&TaskOrder::default()).is_none());
     }
 
     #[test]
@@ -912,6 +1331,23 @@ mod tests {
         assert_eq!(total, 3);
     }
 
+    #[test]
+    fn test_get_epic_counts() {
+        let mut prd = PrdDocument::default();
+        for (id, epic, passes) in [
+            ("a", Some("auth"), true),
+            ("b", None, false),
+            ("c", Some("billing"), false),
+            ("d", Some("auth"), false),
+        ] {
+            let mut story = UserStory::new(id, id);
+            story.epic = epic.map(String::from);
+            story.passes = passes;
+            prd.user_stories.push(story);
+        }
+        assert_eq!(prd.get_epic_counts(), [("auth", 1, 2), ("billing", 0, 1)]);
+    }
+
     #[test]
     fn test_get_story_counts_empty() {
         let prd = PrdDocument::default();
@@ -920,6 +1356,58 @@ mod tests {
         assert_eq!(total, 0);
     }
 
+    #[test]
+    fn test_acceptance_criterion_serde_forms() {
+        let json =
+            r#"["Plain criterion", {"text": "Tracked", "passed": true, "evidence": "See PR"}]"#;
+        let criteria: Vec<AcceptanceCriterion> = serde_json::from_str(json).unwrap();
+        assert_eq!(criteria[0], "Plain criterion");
+        assert!(!criteria[0].passed);
+        assert!(criteria[1].passed);
+        assert_eq!(criteria[1].evidence.as_deref(), Some("See PR"));
+
+        // Unmet criteria stay plain strings on disk
+        let out = serde_json::to_string(&criteria).unwrap();
+        assert!(out.starts_with(r#"["Plain criterion",{"text":"Tracked""#));
+
+        let json = r#"[{"text": "Auth tests pass", "verify": "cargo test auth::"}]"#;
+        let criteria: Vec<AcceptanceCriterion> = serde_json::from_str(json).unwrap();
+        assert_eq!(criteria[0].verify.as_deref(), Some("cargo test auth::"));
+        assert!(!criteria[0].passed);
+        assert_eq!(
+            serde_json::to_string(&criteria).unwrap(),
+            r#"[{"text":"Auth tests pass","passed":false,"verify":"cargo test auth::"}]"#
+        );
+    }
+
+    #[test]
+    fn test_check_criterion_and_counts() {
+        let mut story = UserStory {
+            id: "s1".to_string(),
+            acceptance_criteria: vec!["A".into(), "B".into(), "C".into()],
+            ..Default::default()
+        };
+        assert!(story.check_criterion(2, Some("Tested manually".to_string())));
+        assert!(!story.check_criterion(0, None));
+        assert!(!story.check_criterion(4, None));
+
+        assert_eq!(story.criteria_counts(), (1, 3));
+        let unmet: Vec<&str> = story.unmet_criteria().iter().map(|c| c.as_str()).collect();
+        assert_eq!(unmet, vec!["A", "C"]);
+
+        let done = UserStory {
+            id: "s2".to_string(),
+            acceptance_criteria: vec!["D".into()],
+            passes: true,
+            ..Default::default()
+        };
+        let prd = PrdDocument {
+            user_stories: vec![story, done],
+            ..Default::default()
+        };
+        assert_eq!(prd.get_criteria_counts(), (2, 4));
+    }
+
     #[test]
     fn test_with_real_prd_json_format() {
         // Test with the actual tasks.json format from the Python version
@@ -972,7 +1460,7 @@ mod tests {
             "json:docs/prds/rust-rewrite-tasks.json"
         );
 
-        let pending = prd.get_pending_stories();
+        let pending = prd.get_pending_stories(&TaskOrder::default());
         assert_eq!(pending.len(), 1);
         assert_eq!(pending[0].id, "rust-002");
 
@@ -991,4 +1479,57 @@ mod tests {
         // Default source should be json:.afk/tasks.json for from_json_value
         assert_eq!(story.source, "json:.afk/tasks.json");
     }
+
+    #[test]
+    fn test_priority_aging_boost() {
+        let aging = PriorityAging {
+            days_per_level: 7,
+            cap: 2,
+        };
+        let now =
+            NaiveDateTime::parse_from_str("2024-06-30T12:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        assert_eq!(aging.boost(None, now), 0);
+        assert_eq!(aging.boost(Some("not a date"), now), 0);
+        assert_eq!(aging.boost(Some("2024-06-25T12:00:00.000000"), now), 0);
+        assert_eq!(aging.boost(Some("2024-06-23T12:00:00.000000"), now), 1);
+        assert_eq!(aging.boost(Some("2024-01-01T00:00:00.000000"), now), 2);
+    }
+
+    #[test]
+    fn test_pending_stories_aged_order() {
+        let story = |id: &str, priority: i32, touched: Option<&str>| UserStory {
+            priority,
+            touched_at: touched.map(ToOwned::to_owned),
+            ..UserStory::new(id, id)
+        };
+        let prd = PrdDocument {
+            user_stories: vec![
+                story("chore", 4, Some("2024-01-01T00:00:00.000000")),
+                story("feature", 3, Some("2024-06-29T00:00:00.000000")),
+                story("urgent", 2, None),
+            ],
+            ..Default::default()
+        };
+        let now =
+            NaiveDateTime::parse_from_str("2024-06-30T12:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+        let ids = |stories: Vec<&UserStory>| -> Vec<String> {
+            stories.iter().map(|s| s.id.clone()).collect()
+        };
+
+        assert_eq!(
+            ids(prd.get_pending_stories_aged(None, now)),
+            vec!["urgent", "feature", "chore"]
+        );
+
+        // The stale chore ages from 4 to 2 but the urgent task keeps the tie
+        let aging = PriorityAging {
+            days_per_level: 7,
+            cap: 2,
+        };
+        assert_eq!(
+            ids(prd.get_pending_stories_aged(Some(&aging), now)),
+            vec!["urgent", "chore", "feature"]
+        );
+    }
 }
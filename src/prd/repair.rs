@@ -0,0 +1,263 @@
+//! Checking and repairing AI-written task files.
+//!
+//! `afk import` has the AI CLI write its tasks to a draft file, then checks
+//! the draft against the tasks schema before it becomes `tasks.json`. Slips
+//! models commonly make are fixed on the way: the JSON wrapped in a code
+//! fence or prose, trailing commas, and keys in the wrong case
+//! (`user_stories` or `AcceptanceCriteria`). Whatever can't be fixed is
+//! returned as a list of problems to send back to the AI.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::schema::SchemaKind;
+
+/// A draft that passed the schema, with the fixes it needed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairedTasks {
+    /// The repaired document.
+    pub document: Value,
+    /// One line per fix applied.
+    pub fixes: Vec<String>,
+}
+
+/// Parse, repair and validate AI-written tasks JSON.
+///
+/// Returns the repaired document, or every problem left after repair.
+pub fn repair_tasks_json(text: &str) -> Result<RepairedTasks, Vec<String>> {
+    let mut fixes = Vec::new();
+
+    let json = extract_json(text);
+    if json.len() != text.trim().len() {
+        fixes.push("extracted the JSON from surrounding text".to_string());
+    }
+
+    let mut document: Value = match serde_json::from_str(json) {
+        Ok(document) => document,
+        Err(first) => match serde_json::from_str(&strip_trailing_commas(json)) {
+            Ok(document) => {
+                fixes.push("removed trailing commas".to_string());
+                document
+            }
+            Err(_) => return Err(vec![format!("not valid JSON: {first}")]),
+        },
+    };
+
+    let keys = SchemaKeys::new();
+    let mut renames = Vec::new();
+    if let Value::Object(top) = &mut document {
+        rename_keys(top, &keys.top, &mut renames);
+        for list in ["userStories", "tasks", "items"] {
+            let Some(Value::Array(stories)) = top.get_mut(list) else {
+                continue;
+            };
+            for story in stories.iter_mut().filter_map(Value::as_object_mut) {
+                rename_keys(story, &keys.story, &mut renames);
+                let Some(Value::Array(criteria)) = story.get_mut("acceptanceCriteria") else {
+                    continue;
+                };
+                for criterion in criteria.iter_mut().filter_map(Value::as_object_mut) {
+                    rename_keys(criterion, &keys.criterion, &mut renames);
+                }
+            }
+        }
+    }
+    for (from, to) in renames {
+        let fix = format!("renamed {from} to {to}");
+        if !fixes.contains(&fix) {
+            fixes.push(fix);
+        }
+    }
+
+    let mut problems: Vec<String> = SchemaKind::Tasks
+        .validate(&document)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let has_stories = ["userStories", "tasks", "items"].iter().any(|list| {
+        document
+            .get(list)
+            .and_then(Value::as_array)
+            .is_some_and(|stories| !stories.is_empty())
+    });
+    if problems.is_empty() && !has_stories {
+        problems.push("$.userStories: no stories found".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok(RepairedTasks { document, fixes })
+    } else {
+        Err(problems)
+    }
+}
+
+/// The JSON object in `text`: the body of its first code fence if it has
+/// one, then everything from the first `{` to the last `}`.
+pub(crate) fn extract_json(text: &str) -> &str {
+    let mut text = text.trim();
+    if let Some(start) = text.find("```") {
+        let body = &text[start + 3..];
+        let body = body.find('\n').map_or(body, |newline| &body[newline + 1..]);
+        text = body.find("```").map_or(body, |end| &body[..end]).trim();
+    }
+    match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => text,
+    }
+}
+
+/// Drop commas directly before a closing `}` or `]`, outside strings.
+pub(crate) fn strip_trailing_commas(json: &str) -> String {
+    let chars: Vec<char> = json.chars().collect();
+    let mut out = String::with_capacity(json.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Key names the tasks schema defines, by their normalised form.
+struct SchemaKeys {
+    top: HashMap<String, String>,
+    story: HashMap<String, String>,
+    criterion: HashMap<String, String>,
+}
+
+impl SchemaKeys {
+    fn new() -> Self {
+        let schema = SchemaKind::Tasks.schema();
+        let names = |properties: Option<&Value>| -> HashMap<String, String> {
+            properties
+                .and_then(Value::as_object)
+                .map(|properties| {
+                    properties
+                        .keys()
+                        .map(|key| (normalise_key(key), key.clone()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let criterion = schema
+            .pointer("/$defs/criteria/items/oneOf")
+            .and_then(Value::as_array)
+            .and_then(|options| options.iter().find_map(|o| o.get("properties")));
+        Self {
+            top: names(schema.get("properties")),
+            story: names(schema.pointer("/$defs/story/properties")),
+            criterion: names(criterion),
+        }
+    }
+}
+
+/// A key in lower case without separators, so `user_stories`,
+/// `UserStories` and `userStories` compare equal.
+fn normalise_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Rename keys that match a schema key in all but case and separators,
+/// unless the object already has the schema's spelling.
+fn rename_keys(
+    object: &mut Map<String, Value>,
+    known: &HashMap<String, String>,
+    renames: &mut Vec<(String, String)>,
+) {
+    let wrong: Vec<(String, String)> = object
+        .keys()
+        .filter_map(|key| {
+            let canonical = known.get(&normalise_key(key))?;
+            (canonical != key && !object.contains_key(canonical))
+                .then(|| (key.clone(), canonical.clone()))
+        })
+        .collect();
+    for (from, to) in wrong {
+        if object.contains_key(&to) {
+            continue;
+        }
+        if let Some(value) = object.remove(&from) {
+            object.insert(to.clone(), value);
+            renames.push((from, to));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_fixes_commas_casing_and_fences() {
+        let text = "Here are the tasks:\n\n```json\n{\n  \"project\": \"todo\",\n  \
+                    \"user_stories\": [\n    {\"ID\": \"US-001\", \"title\": \"Add list, then items\", \
+                    \"acceptance_criteria\": [{\"Text\": \"Lists save\"},], \"passes\": false,},\n  ],\n}\n```\n";
+        let repaired = repair_tasks_json(text).unwrap();
+        assert_eq!(
+            repaired.fixes,
+            [
+                "extracted the JSON from surrounding text",
+                "removed trailing commas",
+                "renamed user_stories to userStories",
+                "renamed ID to id",
+                "renamed acceptance_criteria to acceptanceCriteria",
+                "renamed Text to text",
+            ]
+        );
+        let story = &repaired.document["userStories"][0];
+        assert_eq!(story["id"], "US-001");
+        assert_eq!(story["title"], "Add list, then items");
+        assert_eq!(story["acceptanceCriteria"][0]["text"], "Lists save");
+    }
+
+    #[test]
+    fn test_repair_leaves_valid_json_alone() {
+        let text = r#"{"userStories": [{"id": "a", "dependsOn": []}]}"#;
+        let repaired = repair_tasks_json(text).unwrap();
+        assert!(repaired.fixes.is_empty());
+        assert_eq!(
+            repaired.document,
+            serde_json::from_str::<Value>(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_repair_reports_what_it_cannot_fix() {
+        let problems =
+            repair_tasks_json(r#"{"userStories": [{"title": "No id", "priority": "high"}]}"#)
+                .unwrap_err();
+        assert_eq!(
+            problems,
+            [
+                "$.userStories[0]: missing required property 'id'",
+                "$.userStories[0].priority: expected integer, found string",
+            ]
+        );
+
+        assert_eq!(
+            repair_tasks_json(r#"{"project": "empty"}"#).unwrap_err(),
+            ["$.userStories: no stories found"]
+        );
+        assert!(repair_tasks_json("not json at all").unwrap_err()[0].starts_with("not valid JSON"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Completion probability scoring.
+//!
+//! Estimates how likely the agent is to finish each pending story without
+//! help, from its size, its failure history this session, and whether the
+//! stories it depends on are done. With `runner.ordering = "probability"`
+//! the loop works through likely wins first, so an unattended run finishes
+//! as many stories as it can instead of stalling on the hardest one.
+
+use std::collections::HashMap;
+
+use crate::progress::SessionProgress;
+
+use super::{PrdDocument, UserStory};
+
+/// Multiplier per iteration of estimated effort beyond the first.
+const EFFORT_PENALTY: f64 = 0.25;
+/// Criteria a story can have before it counts as large.
+const CRITERIA_ALLOWANCE: usize = 3;
+/// Multiplier per acceptance criterion beyond the allowance.
+const CRITERIA_PENALTY: f64 = 0.1;
+/// Chance kept after each recorded failure.
+const FAILURE_FACTOR: f64 = 0.6;
+/// Chance kept after each attempt that neither failed nor finished.
+const ATTEMPT_FACTOR: f64 = 0.85;
+/// Chance kept while a dependency is still pending.
+const BLOCKED_FACTOR: f64 = 0.1;
+
+/// What the session has recorded about attempts on a story.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskHistory {
+    /// Times the story was marked failed.
+    pub failures: u32,
+    /// Iterations that targeted the story.
+    pub attempts: u32,
+}
+
+/// A story's estimated chance of autonomous completion, with its factors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompletionScore {
+    /// Overall probability, 0.0 to 1.0.
+    pub probability: f64,
+    /// Factor from the story's size (effort or criteria count).
+    pub size: f64,
+    /// Factor from past failures and attempts.
+    pub history: f64,
+    /// Dependencies that have not passed yet.
+    pub blocked_by: Vec<String>,
+}
+
+/// Attempt history per story ID, from a session's progress.
+pub fn history_from(progress: &SessionProgress) -> HashMap<String, TaskHistory> {
+    progress
+        .tasks
+        .iter()
+        .map(|(id, task)| {
+            let history = TaskHistory {
+                failures: task.failure_count,
+                attempts: task.attempts,
+            };
+            (id.clone(), history)
+        })
+        .collect()
+}
+
+/// Score a story's chance of being completed autonomously.
+///
+/// Dependencies missing from the document are ignored.
+pub fn completion_score(
+    story: &UserStory,
+    prd: &PrdDocument,
+    history: TaskHistory,
+) -> CompletionScore {
+    let size = match story.effort {
+        Some(effort) => 1.0 / (1.0 + EFFORT_PENALTY * f64::from(effort.saturating_sub(1))),
+        None => {
+            let extra = story
+                .acceptance_criteria
+                .len()
+                .saturating_sub(CRITERIA_ALLOWANCE);
+            1.0 / (1.0 + CRITERIA_PENALTY * extra as f64)
+        }
+    };
+
+    let stalled = history.attempts.saturating_sub(history.failures);
+    let history_factor = FAILURE_FACTOR.powi(history.failures as i32)
+        * ATTEMPT_FACTOR.powi(stalled.min(i32::MAX as u32) as i32);
+
+    let blocked_by: Vec<String> = story
+        .depends_on
+        .iter()
+        .filter(|id| {
+            prd.user_stories
+                .iter()
+                .any(|other| &other.id == *id && !other.passes)
+        })
+        .cloned()
+        .collect();
+    let dependencies = if blocked_by.is_empty() {
+        1.0
+    } else {
+        BLOCKED_FACTOR
+    };
+
+    CompletionScore {
+        probability: size * history_factor * dependencies,
+        size,
+        history: history_factor,
+        blocked_by,
+    }
+}
+
+/// Reorder stories so the most likely completions come first.
+///
+/// The sort is stable, so equally likely stories keep their existing
+/// (priority) order.
+pub fn order_by_probability<'a>(
+    stories: Vec<&'a UserStory>,
+    prd: &PrdDocument,
+    history: &HashMap<String, TaskHistory>,
+) -> Vec<&'a UserStory> {
+    let mut scored: Vec<(&UserStory, f64)> = stories
+        .into_iter()
+        .map(|story| {
+            let past = history.get(&story.id).copied().unwrap_or_default();
+            (story, completion_score(story, prd, past).probability)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.into_iter().map(|(story, _)| story).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: &str, priority: i32) -> UserStory {
+        UserStory {
+            priority,
+            ..UserStory::new(id, id)
+        }
+    }
+
+    #[test]
+    fn test_small_clean_story_scores_one() {
+        let prd = PrdDocument::default();
+        let score = completion_score(&story("a", 1), &prd, TaskHistory::default());
+        assert_eq!(score.probability, 1.0);
+        assert!(score.blocked_by.is_empty());
+    }
+
+    #[test]
+    fn test_size_lowers_score() {
+        let prd = PrdDocument::default();
+        let big = UserStory {
+            effort: Some(5),
+            ..story("big", 1)
+        };
+        assert_eq!(
+            completion_score(&big, &prd, TaskHistory::default()).size,
+            0.5
+        );
+
+        let mut many = story("many", 1);
+        many.acceptance_criteria = (0..8).map(|i| format!("c{i}").into()).collect();
+        assert!(
+            (completion_score(&many, &prd, TaskHistory::default()).size - 1.0 / 1.5).abs() < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_failures_lower_score() {
+        let prd = PrdDocument::default();
+        let history = TaskHistory {
+            failures: 2,
+            attempts: 3,
+        };
+        let score = completion_score(&story("a", 1), &prd, history);
+        assert!((score.history - 0.36 * 0.85).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pending_dependency_blocks() {
+        let dep = story("dep", 1);
+        let mut done = story("done", 1);
+        done.passes = true;
+        let mut task = story("task", 1);
+        task.depends_on = vec!["dep".into(), "done".into(), "missing".into()];
+        let prd = PrdDocument {
+            user_stories: vec![dep, done, task.clone()],
+            ..Default::default()
+        };
+
+        let score = completion_score(&task, &prd, TaskHistory::default());
+        assert_eq!(score.blocked_by, vec!["dep".to_string()]);
+        assert!((score.probability - BLOCKED_FACTOR).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_order_by_probability() {
+        let hard = UserStory {
+            effort: Some(4),
+            ..story("hard", 1)
+        };
+        let easy = story("easy", 3);
+        let failing = story("failing", 2);
+        let also_easy = story("also-easy", 4);
+        let prd = PrdDocument {
+            user_stories: vec![hard, failing, easy, also_easy],
+            ..Default::default()
+        };
+        let history = HashMap::from([(
+            "failing".to_string(),
+            TaskHistory {
+                failures: 1,
+                attempts: 1,
+            },
+        )]);
+
+        let ordered = order_by_probability(
+            prd.get_pending_stories_aged(None, Default::default()),
+            &prd,
+            &history,
+        );
+        let ids: Vec<&str> = ordered.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["easy", "also-easy", "failing", "hard"]);
+    }
+}
@@ -0,0 +1,405 @@
+//! Task selection strategies.
+//!
+//! `selection.strategy` decides which pending story the loop picks next.
+//! Each strategy reorders the stories [`PrdDocument::get_pending_stories`]
+//! has already sorted by (aged) priority, and every sort is stable, so
+//! stories the strategy can't tell apart keep their priority order.
+//! `selection.stick_to_epic` then keeps the loop on one epic until it is
+//! done. The settings, with the session state they need, travel in a
+//! [`TaskOrder`].
+
+use std::collections::{HashMap, HashSet};
+
+use crate::config::{AfkConfig, SelectionStrategy, TaskOrdering};
+use crate::progress::SessionProgress;
+
+use super::scoring::{history_from, TaskHistory};
+use super::{PrdDocument, PriorityAging, UserStory};
+
+/// How [`PrdDocument::get_pending_stories`] orders pending stories.
+///
+/// The default is plain priority order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskOrder {
+    /// Priority aging (`runner.priority_aging`), or None for none.
+    pub aging: Option<PriorityAging>,
+    /// Priority or probability order (`runner.ordering`).
+    pub ordering: TaskOrdering,
+    /// How priority order is refined (`selection.strategy`).
+    pub strategy: SelectionStrategy,
+    /// Finish an epic before starting another (`selection.stick_to_epic`).
+    pub stick_to_epic: bool,
+    /// Attempt history per story ID, for probability order.
+    pub history: HashMap<String, TaskHistory>,
+    /// The story the session started most recently, for round-robin and
+    /// `stick_to_epic`.
+    pub last_started: Option<String>,
+}
+
+impl TaskOrder {
+    /// The order `config` sets up, for a session with `progress`.
+    pub fn new(config: &AfkConfig, progress: &SessionProgress) -> Self {
+        Self {
+            aging: config.runner.priority_aging.then_some(PriorityAging {
+                days_per_level: config.runner.aging_days,
+                cap: config.runner.aging_cap,
+            }),
+            ordering: config.runner.ordering,
+            strategy: config.selection.strategy,
+            stick_to_epic: config.selection.stick_to_epic,
+            history: history_from(progress),
+            last_started: last_started(progress),
+        }
+    }
+
+    /// [`Self::new`] with the session's progress loaded from
+    /// `.afk/progress.json`.
+    pub fn load(config: &AfkConfig) -> Self {
+        Self::new(config, &SessionProgress::load(None).unwrap_or_default())
+    }
+
+    /// Epic of the story the session started most recently, if it has one.
+    fn last_epic<'a>(&self, prd: &'a PrdDocument) -> Option<&'a str> {
+        prd.get_story(self.last_started.as_deref()?)?
+            .epic
+            .as_deref()
+    }
+
+    /// Put priority-sorted pending stories in this order.
+    pub fn apply<'a>(&self, stories: Vec<&'a UserStory>, prd: &PrdDocument) -> Vec<&'a UserStory> {
+        let last_epic = self.last_epic(prd);
+        let ordered = match self.ordering {
+            TaskOrdering::Priority => order(stories, self.strategy, last_epic),
+            TaskOrdering::Probability => {
+                super::scoring::order_by_probability(stories, prd, &self.history)
+            }
+        };
+        match last_epic.filter(|_| self.stick_to_epic) {
+            Some(epic) => stick_to(ordered, epic),
+            None => ordered,
+        }
+    }
+}
+
+/// Reorder priority-sorted pending stories by `strategy`.
+///
+/// Round-robin starts with the epic after `last_epic`, the epic worked on
+/// last.
+pub fn order<'a>(
+    stories: Vec<&'a UserStory>,
+    strategy: SelectionStrategy,
+    last_epic: Option<&str>,
+) -> Vec<&'a UserStory> {
+    match strategy {
+        SelectionStrategy::Priority => stories,
+        SelectionStrategy::Fifo => fifo(stories),
+        SelectionStrategy::SmallestFirst => smallest_first(stories),
+        SelectionStrategy::DependencyAware => dependency_aware(stories),
+        SelectionStrategy::RoundRobinByEpic => round_robin_by_epic(stories, last_epic),
+    }
+}
+
+/// Oldest first, by when sync added or last changed each story.
+///
+/// Stories without a timestamp predate it, so they come first.
+pub fn fifo(mut stories: Vec<&UserStory>) -> Vec<&UserStory> {
+    stories.sort_by(|a, b| a.touched_at.cmp(&b.touched_at));
+    stories
+}
+
+/// Fewest estimated iterations first (`effort`, counting 1 when the source
+/// gives none), then fewest unmet acceptance criteria.
+pub fn smallest_first(mut stories: Vec<&UserStory>) -> Vec<&UserStory> {
+    stories.sort_by_key(|s| {
+        let (met, total) = s.criteria_counts();
+        (s.effort.unwrap_or(1), total - met)
+    });
+    stories
+}
+
+/// Stories after the pending stories they depend on, and among those ready
+/// to start, the ones that (directly or not) unblock the most others first.
+///
+/// Stories caught in a dependency cycle keep their priority order.
+pub fn dependency_aware(stories: Vec<&UserStory>) -> Vec<&UserStory> {
+    let pending: HashSet<&str> = stories.iter().map(|s| s.id.as_str()).collect();
+    let unblocks: Vec<usize> = stories
+        .iter()
+        .map(|s| dependents(&s.id, &stories).len())
+        .collect();
+
+    let mut remaining: Vec<usize> = (0..stories.len()).collect();
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut ordered = Vec::with_capacity(stories.len());
+    while !remaining.is_empty() {
+        let ready = |&&i: &&usize| {
+            stories[i]
+                .depends_on
+                .iter()
+                .all(|dep| !pending.contains(dep.as_str()) || placed.contains(dep.as_str()))
+        };
+        // Highest unblock count wins; max_by_key keeps the last of equals,
+        // so search from the back to keep priority order on ties
+        let pick = remaining
+            .iter()
+            .rev()
+            .filter(ready)
+            .max_by_key(|&&i| unblocks[i])
+            .or_else(|| remaining.first())
+            .copied()
+            .unwrap_or_default();
+        remaining.retain(|&i| i != pick);
+        placed.insert(stories[pick].id.as_str());
+        ordered.push(stories[pick]);
+    }
+    ordered
+}
+
+/// IDs of the stories that wait on `id`, directly or through others.
+fn dependents<'a>(id: &str, stories: &[&'a UserStory]) -> HashSet<&'a str> {
+    let mut found: HashSet<&str> = HashSet::new();
+    let mut frontier = vec![id.to_string()];
+    while let Some(current) = frontier.pop() {
+        for story in stories {
+            if story.depends_on.contains(&current) && found.insert(story.id.as_str()) {
+                frontier.push(story.id.clone());
+            }
+        }
+    }
+    found.remove(id);
+    found
+}
+
+/// One story from each epic in turn, starting with the epic after
+/// `last_epic`. Stories without an epic share a group of their own.
+///
+/// Epics take turns in the order their most urgent story appears.
+pub fn round_robin_by_epic<'a>(
+    stories: Vec<&'a UserStory>,
+    last_epic: Option<&str>,
+) -> Vec<&'a UserStory> {
+    let mut epics: Vec<Option<&str>> = Vec::new();
+    let mut groups: HashMap<Option<&str>, Vec<&UserStory>> = HashMap::new();
+    for story in stories {
+        let epic = story.epic.as_deref();
+        if !groups.contains_key(&epic) {
+            epics.push(epic);
+        }
+        groups.entry(epic).or_default().push(story);
+    }
+
+    // Start after the epic worked on last, if it is still in the running
+    if let Some(i) = epics.iter().position(|e| *e == last_epic) {
+        epics.rotate_left(i + 1);
+    }
+
+    let mut queues: Vec<std::vec::IntoIter<&UserStory>> = epics
+        .iter()
+        .filter_map(|e| groups.remove(e))
+        .map(Vec::into_iter)
+        .collect();
+    let mut ordered = Vec::new();
+    while !queues.is_empty() {
+        queues.retain_mut(|queue| match queue.next() {
+            Some(story) => {
+                ordered.push(story);
+                true
+            }
+            None => false,
+        });
+    }
+    ordered
+}
+
+/// Stories in `epic` first, then the rest, each in their current order.
+fn stick_to<'a>(stories: Vec<&'a UserStory>, epic: &str) -> Vec<&'a UserStory> {
+    let (mut ordered, rest): (Vec<_>, Vec<_>) = stories
+        .into_iter()
+        .partition(|s| s.epic.as_deref() == Some(epic));
+    ordered.extend(rest);
+    ordered
+}
+
+/// Stories grouped by epic, epics in the order they first appear and
+/// stories without one last.
+pub fn group_by_epic<'a, I>(stories: I) -> Vec<(Option<&'a str>, Vec<&'a UserStory>)>
+where
+    I: IntoIterator<Item = &'a UserStory>,
+{
+    let mut groups: Vec<(Option<&str>, Vec<&UserStory>)> = Vec::new();
+    let mut ungrouped = Vec::new();
+    for story in stories {
+        match story.epic.as_deref() {
+            Some(epic) => match groups.iter_mut().find(|(e, _)| *e == Some(epic)) {
+                Some((_, group)) => group.push(story),
+                None => groups.push((Some(epic), vec![story])),
+            },
+            None => ungrouped.push(story),
+        }
+    }
+    if !ungrouped.is_empty() {
+        groups.push((None, ungrouped));
+    }
+    groups
+}
+
+/// ID of the task the session started most recently.
+fn last_started(progress: &SessionProgress) -> Option<String> {
+    progress
+        .tasks
+        .values()
+        .filter(|t| t.started_at.is_some())
+        .max_by(|a, b| a.started_at.cmp(&b.started_at))
+        .map(|t| t.id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::AcceptanceCriterion;
+
+    fn story(id: &str) -> UserStory {
+        UserStory::new(id, id)
+    }
+
+    fn ids<'a>(stories: &[&'a UserStory]) -> Vec<&'a str> {
+        stories.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_priority_keeps_order() {
+        let stories = [story("a"), story("b")];
+        let ordered = order(stories.iter().collect(), SelectionStrategy::Priority, None);
+        assert_eq!(ids(&ordered), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_fifo_oldest_first() {
+        let mut new = story("new");
+        new.touched_at = Some("2026-03-02T09:00:00".to_string());
+        let mut old = story("old");
+        old.touched_at = Some("2026-03-01T09:00:00".to_string());
+        let untouched = story("untouched");
+        let mut same = story("same-as-old");
+        same.touched_at = old.touched_at.clone();
+
+        let ordered = fifo(vec![&new, &old, &untouched, &same]);
+        assert_eq!(ids(&ordered), ["untouched", "old", "same-as-old", "new"]);
+    }
+
+    #[test]
+    fn test_smallest_first() {
+        let mut big = story("big");
+        big.acceptance_criteria = (0..4)
+            .map(|i| AcceptanceCriterion::new(format!("c{i}")))
+            .collect();
+        let mut half_done = story("half-done");
+        half_done.acceptance_criteria = (0..4)
+            .map(|i| AcceptanceCriterion::new(format!("c{i}")))
+            .collect();
+        half_done.acceptance_criteria[0].passed = true;
+        half_done.acceptance_criteria[1].passed = true;
+        let mut long = story("long");
+        long.effort = Some(3);
+        let small = story("small");
+
+        let ordered = smallest_first(vec![&long, &big, &half_done, &small]);
+        assert_eq!(ids(&ordered), ["small", "half-done", "big", "long"]);
+    }
+
+    #[test]
+    fn test_dependency_aware() {
+        // "schema" unblocks two stories, "docs" none; "api" needs "schema"
+        let docs = story("docs");
+        let schema = story("schema");
+        let mut api = story("api");
+        api.depends_on = vec!["schema".to_string()];
+        let mut ui = story("ui");
+        ui.depends_on = vec!["api".to_string(), "done-elsewhere".to_string()];
+
+        let ordered = dependency_aware(vec![&ui, &docs, &api, &schema]);
+        assert_eq!(ids(&ordered), ["schema", "api", "ui", "docs"]);
+    }
+
+    #[test]
+    fn test_dependency_aware_cycle_and_ties() {
+        let mut a = story("a");
+        a.depends_on = vec!["b".to_string()];
+        let mut b = story("b");
+        b.depends_on = vec!["a".to_string()];
+        let c = story("c");
+        let d = story("d");
+
+        // Independent stories keep their order; the cycle follows in order
+        let ordered = dependency_aware(vec![&c, &a, &b, &d]);
+        assert_eq!(ids(&ordered), ["c", "d", "a", "b"]);
+    }
+
+    #[test]
+    fn test_round_robin_by_epic() {
+        let in_epic = |id: &str, epic: Option<&str>| UserStory {
+            epic: epic.map(String::from),
+            ..story(id)
+        };
+        let stories = [
+            in_epic("auth-1", Some("auth")),
+            in_epic("auth-2", Some("auth")),
+            in_epic("billing-1", Some("billing")),
+            in_epic("chore", None),
+            in_epic("auth-3", Some("auth")),
+            in_epic("billing-2", Some("billing")),
+        ];
+
+        let ordered = round_robin_by_epic(stories.iter().collect(), None);
+        assert_eq!(
+            ids(&ordered),
+            [
+                "auth-1",
+                "billing-1",
+                "chore",
+                "auth-2",
+                "billing-2",
+                "auth-3"
+            ]
+        );
+
+        // After working on auth, billing goes first
+        let ordered = round_robin_by_epic(stories.iter().collect(), Some("auth"));
+        assert_eq!(ids(&ordered)[..3], ["billing-1", "chore", "auth-1"]);
+
+        // An epic that is no longer pending changes nothing
+        let ordered = round_robin_by_epic(stories.iter().collect(), Some("gone"));
+        assert_eq!(ids(&ordered)[0], "auth-1");
+    }
+
+    #[test]
+    fn test_stick_to_epic_and_grouping() {
+        let in_epic = |id: &str, epic: Option<&str>| UserStory {
+            epic: epic.map(String::from),
+            ..story(id)
+        };
+        let stories = [
+            in_epic("chore", None),
+            in_epic("auth-1", Some("auth")),
+            in_epic("billing-1", Some("billing")),
+            in_epic("auth-2", Some("auth")),
+        ];
+
+        let ordered = stick_to(stories.iter().collect(), "billing");
+        assert_eq!(ids(&ordered), ["billing-1", "chore", "auth-1", "auth-2"]);
+
+        let groups = group_by_epic(&stories);
+        let summary: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|(epic, stories)| (*epic, ids(stories)))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Some("auth"), vec!["auth-1", "auth-2"]),
+                (Some("billing"), vec!["billing-1"]),
+                (None, vec!["chore"]),
+            ]
+        );
+    }
+}
@@ -3,21 +3,25 @@
 //! This module implements the Ralph pattern: aggregating tasks from all sources
 //! into a unified tasks.json file that the AI reads directly.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 use std::process::Command;
 
 use chrono::Local;
 
-use crate::config::AfkConfig;
-use crate::prd::{PrdDocument, PrdError};
-use crate::sources::aggregate_tasks;
+use crate::config::{AfkConfig, DedupConfig, SourceConfig, SyncConflictPolicy, TASKS_FILE};
+use crate::prd::dedup::{dedup, DuplicateConflict, SourceMap, TaskMerge};
+use crate::prd::enrich::keep_generated;
+use crate::prd::{resolve_conflicts, PrdDocument, PrdError, SyncConflict};
+use crate::sources::{aggregate_tasks, source_owns};
 
 /// Sync tasks from all configured sources.
 ///
 /// This aggregates tasks from all sources and writes them to tasks.json.
-/// Existing completion status (passes: true) is preserved for matching IDs.
+/// Existing completion status (passes: true) is preserved for matching IDs,
+/// except where the source disagrees and `runner.sync_conflicts` says to
+/// take its side.
 ///
 /// If no sources are configured but .afk/tasks.json exists with tasks,
 /// it's used directly as the source of truth (created by afk import
@@ -44,39 +48,174 @@ pub fn sync_prd_with_root(
     branch_name: Option<&str>,
     root: Option<&Path>,
 ) -> Result<PrdDocument, PrdError> {
-    // Determine tasks path
-    let prd_path = root.map(|r| r.join(".afk/tasks.json"));
+    let policy = config.runner.sync_conflicts;
+    sync_prd_resolving(config, branch_name, root, |_| policy)
+}
 
-    // Load existing tasks
-    let existing_prd = PrdDocument::load(prd_path.as_deref())?;
+/// Sync tasks from all configured sources, settling each sync conflict
+/// with `resolve`.
+pub fn sync_prd_resolving(
+    config: &AfkConfig,
+    branch_name: Option<&str>,
+    root: Option<&Path>,
+    resolve: impl FnMut(&SyncConflict) -> SyncConflictPolicy,
+) -> Result<PrdDocument, PrdError> {
+    let prd_path = root.map(|r| r.join(".afk/tasks.json"));
 
     // If no sources configured but tasks.json exists with tasks, use it directly.
     // This handles the case where user created .afk/tasks.json via afk import
     // or placed it there manually — we don't want to overwrite it.
-    if config.sources.is_empty() && !existing_prd.user_stories.is_empty() {
-        return Ok(existing_prd);
+    if config.sources.is_empty() {
+        let existing_prd = PrdDocument::load(prd_path.as_deref())?;
+        if !existing_prd.user_stories.is_empty() {
+            return Ok(existing_prd);
+        }
     }
 
+    let mut plan = plan_sync(&config.sources, &config.dedup, branch_name, root)?;
+    resolve_conflicts(&mut plan, root, config.github.sync_back, resolve)?;
+    plan.save(root)?;
+    Ok(plan.prd)
+}
+
+/// What a sync would change in tasks.json.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan {
+    /// The merged task list.
+    pub prd: PrdDocument,
+    /// IDs of stories the sources list that tasks.json doesn't have yet.
+    pub added: Vec<String>,
+    /// IDs of existing stories whose details changed in their source.
+    pub updated: Vec<String>,
+    /// IDs of pending stories their source no longer lists.
+    ///
+    /// These are kept in the merged list so work in progress is never lost.
+    pub removed: Vec<String>,
+    /// IDs of every story the synced sources list.
+    pub listed: HashSet<String>,
+    /// Stories listed by more than one source, folded into one.
+    pub merges: Vec<TaskMerge>,
+    /// IDs of stories in tasks.json now merged into another.
+    pub folded: Vec<String>,
+    /// Disagreements between sources listing the same story.
+    pub duplicates: Vec<DuplicateConflict>,
+    /// Remembered merges, including any new ones.
+    pub source_map: SourceMap,
+}
+
+impl SyncPlan {
+    /// Whether the sync would change nothing.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.updated.is_empty()
+            && self.removed.is_empty()
+            && self.folded.is_empty()
+    }
+
+    /// Write the merged tasks and the source map.
+    pub fn save(&self, root: Option<&Path>) -> Result<(), PrdError> {
+        self.prd.save(root.map(|r| r.join(TASKS_FILE)).as_deref())?;
+        self.source_map.save(root)
+    }
+}
+
+/// Merge stories from some sources into tasks.json, without saving.
+///
+/// Stories from other sources are left as they are, so this can refresh a
+/// single source. Completion status (passes: true) is preserved for
+/// matching IDs. The same story listed by several sources is merged into
+/// one, as `dedup` says.
+pub fn plan_sync(
+    sources: &[SourceConfig],
+    dedup_config: &DedupConfig,
+    branch_name: Option<&str>,
+    root: Option<&Path>,
+) -> Result<SyncPlan, PrdError> {
+    let prd_path = root.map(|r| r.join(".afk/tasks.json"));
+    let existing_prd = PrdDocument::load(prd_path.as_deref())?;
+
     // Build map of existing stories by ID for merging
     let mut existing_by_id: HashMap<String, crate::prd::UserStory> = existing_prd
         .user_stories
-        .into_iter()
-        .map(|s| (s.id.clone(), s))
+        .iter()
+        .map(|s| (s.id.clone(), s.clone()))
         .collect();
 
-    // Aggregate from all sources
-    let source_stories = aggregate_tasks(&config.sources);
+    // Aggregate from the chosen sources, folding duplicates together,
+    // including ones of stories other sources put in tasks.json
+    let anchors: Vec<crate::prd::UserStory> = existing_prd
+        .user_stories
+        .iter()
+        .filter(|s| !sources.iter().any(|src| source_owns(src, &s.source)))
+        .cloned()
+        .collect();
+    let mut source_map = SourceMap::load(root);
+    let deduped = dedup(
+        aggregate_tasks(sources),
+        &anchors,
+        dedup_config,
+        &mut source_map,
+    );
+    let source_stories = deduped.stories;
+    let mut folded = Vec::new();
+    for merge in &deduped.merges {
+        let stale = existing_by_id
+            .get(&merge.duplicate_id)
+            .is_some_and(|s| s.source == merge.duplicate_source && s.id != merge.id);
+        if stale {
+            existing_by_id.remove(&merge.duplicate_id);
+            folded.push(merge.duplicate_id.clone());
+        }
+    }
+    let now = Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string();
+    let listed: HashSet<String> = source_stories.iter().map(|s| s.id.clone()).collect();
+    let mut added = Vec::new();
+    let mut updated = Vec::new();
 
     // Merge: add new tasks from sources, update existing ones (preserving passes status)
     for mut story in source_stories {
         if let Some(existing) = existing_by_id.get(&story.id) {
-            // Task exists - preserve completion status
+            // Task exists - preserve completion status, including criteria
+            // checked off so far (matched by text)
             story.passes = existing.passes;
+            keep_generated(&mut story, existing);
+            for criterion in &mut story.acceptance_criteria {
+                if let Some(prev) = existing
+                    .acceptance_criteria
+                    .iter()
+                    .find(|c| c.text == criterion.text)
+                {
+                    criterion.passed = prev.passed;
+                    criterion.evidence.clone_from(&prev.evidence);
+                }
+            }
+            story.touched_at.clone_from(&existing.touched_at);
+            if story != *existing {
+                story.touched_at = Some(now.clone());
+                if !updated.contains(&story.id) {
+                    updated.push(story.id.clone());
+                }
+            } else if story.touched_at.is_none() {
+                // Stories synced before touched_at existed start aging now
+                story.touched_at = Some(now.clone());
+            }
+        } else {
+            story.touched_at = Some(now.clone());
+            added.push(story.id.clone());
         }
         // Insert or update (source is authoritative for non-passes fields)
         existing_by_id.insert(story.id.clone(), story);
     }
 
+    let removed = existing_prd
+        .user_stories
+        .iter()
+        .filter(|s| !s.passes && !listed.contains(&s.id) && !folded.contains(&s.id))
+        .filter(|s| sources.iter().any(|src| source_owns(src, &s.source)))
+        .map(|s| s.id.clone())
+        .collect();
+
     // Collect all stories and sort by priority (1 = highest)
     let mut stories: Vec<_> = existing_by_id.into_values().collect();
     stories.sort_by_key(|s| s.priority);
@@ -94,19 +233,23 @@ pub fn sync_prd_with_root(
         existing_prd.description
     };
 
-    // Build PRD document
-    let prd = PrdDocument {
-        project,
-        branch_name: branch,
-        description,
-        user_stories: stories,
-        last_synced: Local::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
-    };
-
-    // Save to disk
-    prd.save(prd_path.as_deref())?;
-
-    Ok(prd)
+    Ok(SyncPlan {
+        prd: PrdDocument {
+            project,
+            branch_name: branch,
+            description,
+            user_stories: stories,
+            last_synced: now,
+        },
+        added,
+        updated,
+        removed,
+        listed,
+        merges: deduped.merges,
+        folded,
+        duplicates: deduped.conflicts,
+        source_map,
+    })
 }
 
 /// Get the current git branch name.
@@ -273,11 +416,19 @@ pub fn mark_story_complete_with_path(
 
         // Sync completion back to source
         if source == "beads" {
-            use crate::sources::close_beads_issue;
-            close_beads_issue(story_id);
-        } else if let Some(issue_number) = crate::sources::parse_github_issue_number(&source) {
-            use crate::sources::close_github_issue;
-            close_github_issue(issue_number, None);
+            let config = crate::config::AfkConfig::load(None).unwrap_or_default();
+            crate::sources::complete_beads_task(story_id, &config.sources);
+        } else if source.starts_with("github:") {
+            let config = crate::config::AfkConfig::load(None).unwrap_or_default();
+            crate::sources::close_github_task(&source, story_id, &config.github);
+        } else if source.starts_with("markdown:") {
+            if let Ok(config) = crate::config::AfkConfig::load(None) {
+                crate::sources::complete_markdown_task(&source, story_id, &config.sources);
+            }
+        } else if source.starts_with("stories-dir:") {
+            if let Ok(config) = crate::config::AfkConfig::load(None) {
+                crate::sources::complete_story_file(&source, &config.sources);
+            }
         }
 
         Ok(true)
@@ -346,6 +497,91 @@ mod tests {
         assert_eq!(result.user_stories[0].id, "story-1");
     }
 
+    #[test]
+    fn test_sync_applies_conflict_policy() {
+        let temp = TempDir::new().unwrap();
+        let afk_dir = temp.path().join(".afk");
+        fs::create_dir_all(&afk_dir).unwrap();
+        let todo = temp.path().join("todo.json");
+        fs::write(&todo, r#"[{"id": "open", "title": "Open"}]"#).unwrap();
+        let existing = format!(
+            r#"{{"userStories": [
+                {{"id": "open", "title": "Open", "source": "json:{todo}"}},
+                {{"id": "closed", "title": "Closed", "source": "json:{todo}"}}
+            ]}}"#,
+            todo = todo.display()
+        );
+        fs::write(afk_dir.join("tasks.json"), &existing).unwrap();
+        let mut progress = crate::progress::SessionProgress::new();
+        progress.set_task_status("closed", crate::progress::TaskStatus::Failed, "json", None);
+        progress.save(Some(&afk_dir.join("progress.json"))).unwrap();
+
+        let mut config = AfkConfig {
+            sources: vec![crate::config::SourceConfig::json(todo.to_str().unwrap())],
+            ..Default::default()
+        };
+        // Keeping local leaves the failed task pending
+        let prd = sync_prd_with_root(&config, None, Some(temp.path())).unwrap();
+        assert!(!prd.get_story("closed").unwrap().passes);
+
+        config.runner.sync_conflicts = SyncConflictPolicy::TakeRemote;
+        let prd = sync_prd_with_root(&config, None, Some(temp.path())).unwrap();
+        assert!(prd.get_story("closed").unwrap().passes);
+        assert!(!prd.get_story("open").unwrap().passes);
+        let progress =
+            crate::progress::SessionProgress::load(Some(&afk_dir.join("progress.json"))).unwrap();
+        assert_eq!(
+            progress.get_task("closed").unwrap().status,
+            crate::progress::TaskStatus::Completed
+        );
+    }
+
+    #[test]
+    fn test_plan_sync_single_source() {
+        let temp = TempDir::new().unwrap();
+        let afk_dir = temp.path().join(".afk");
+        fs::create_dir_all(&afk_dir).unwrap();
+        let todo = temp.path().join("todo.json");
+        let other = temp.path().join("other.json");
+        fs::write(
+            &todo,
+            r#"[{"id": "a", "title": "A renamed"}, {"id": "c", "title": "C"}]"#,
+        )
+        .unwrap();
+        fs::write(&other, r#"[{"id": "b", "title": "B renamed"}]"#).unwrap();
+
+        let existing = format!(
+            r#"{{"userStories": [
+                {{"id": "a", "title": "A", "source": "json:{todo}"}},
+                {{"id": "gone", "title": "Gone", "source": "json:{todo}"}},
+                {{"id": "b", "title": "B", "source": "json:{other}"}}
+            ]}}"#,
+            todo = todo.display(),
+            other = other.display()
+        );
+        fs::write(afk_dir.join("tasks.json"), &existing).unwrap();
+
+        let sources = [crate::config::SourceConfig::json(todo.to_str().unwrap())];
+        let plan = plan_sync(&sources, &DedupConfig::default(), None, Some(temp.path())).unwrap();
+
+        assert_eq!(plan.added, vec!["c"]);
+        assert_eq!(plan.updated, vec!["a"]);
+        assert_eq!(plan.removed, vec!["gone"]);
+        assert!(!plan.is_empty());
+        // Other sources' stories are untouched and pending work is kept
+        assert_eq!(plan.prd.get_story("b").unwrap().title, "B");
+        assert!(plan.prd.get_story("gone").is_some());
+        // Added and changed stories are stamped for priority aging
+        assert!(plan.prd.get_story("a").unwrap().touched_at.is_some());
+        assert!(plan.prd.get_story("c").unwrap().touched_at.is_some());
+        assert!(plan.prd.get_story("b").unwrap().touched_at.is_none());
+        // Planning never writes
+        assert_eq!(
+            fs::read_to_string(afk_dir.join("tasks.json")).unwrap(),
+            existing
+        );
+    }
+
     #[test]
     fn test_sync_tasks_merges_new_tasks_with_existing_completed() {
         let temp = TempDir::new().unwrap();
@@ -462,6 +698,42 @@ mod tests {
         assert!(!story2.unwrap().passes);
     }
 
+    #[test]
+    fn test_sync_tasks_preserves_checked_criteria() {
+        let temp = TempDir::new().unwrap();
+        let afk_dir = temp.path().join(".afk");
+        fs::create_dir_all(&afk_dir).unwrap();
+
+        let existing_tasks = r#"{
+            "userStories": [{
+                "id": "story-1", "title": "Story 1", "description": "Test", "priority": 1,
+                "acceptanceCriteria": [{"text": "AC1", "passed": true, "evidence": "Done"}, "AC2"]
+            }]
+        }"#;
+        fs::write(afk_dir.join("tasks.json"), existing_tasks).unwrap();
+
+        let source_json = r#"[
+            {"id": "story-1", "title": "Story 1", "priority": 1, "acceptanceCriteria": ["AC1", "AC2", "AC3"]}
+        ]"#;
+        let source_path = temp.path().join("source.json");
+        fs::write(&source_path, source_json).unwrap();
+
+        let config = AfkConfig {
+            sources: vec![crate::config::SourceConfig::json(
+                source_path.to_str().unwrap(),
+            )],
+            ..Default::default()
+        };
+
+        let result = sync_prd_with_root(&config, None, Some(temp.path())).unwrap();
+        let criteria = &result.user_stories[0].acceptance_criteria;
+        assert_eq!(criteria.len(), 3);
+        assert!(criteria[0].passed);
+        assert_eq!(criteria[0].evidence.as_deref(), Some("Done"));
+        assert!(!criteria[1].passed);
+        assert!(!criteria[2].passed);
+    }
+
     #[test]
     fn test_sync_tasks_sorts_by_priority() {
         let temp = TempDir::new().unwrap();
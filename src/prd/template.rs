@@ -0,0 +1,315 @@
+//! Task templates for `afk task new`.
+//!
+//! A template is a JSON file in `.afk/templates/tasks/` describing a kind of
+//! task: its title, description, priority and standard acceptance criteria,
+//! with `{{ name }}` placeholders filled in when the task is made. Built-in
+//! `bugfix`, `feature` and `refactor` templates are used unless a file of
+//! the same name replaces them.
+//!
+//! ```json
+//! {
+//!   "description": "Fix a bug, with a regression test",
+//!   "placeholders": {"component": "Component affected"},
+//!   "task": {
+//!     "title": "Fix {{ component }}",
+//!     "acceptanceCriteria": ["Regression test added"]
+//!   }
+//! }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::config::TASK_TEMPLATES_DIR;
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}").expect("PLACEHOLDER regex is valid")
+});
+
+/// Templates that ship with afk, by name.
+const BUILT_IN: &[(&str, &str)] = &[
+    ("bugfix", include_str!("templates/bugfix.json")),
+    ("feature", include_str!("templates/feature.json")),
+    ("refactor", include_str!("templates/refactor.json")),
+];
+
+/// Error type for task templates.
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    /// No template file or built-in template has this name.
+    #[error("Unknown task template '{name}': expected one of {known} or {TASK_TEMPLATES_DIR}/{name}.json")]
+    Unknown {
+        /// The name asked for.
+        name: String,
+        /// The templates that do exist, comma-separated.
+        known: String,
+    },
+
+    /// A template file could not be read.
+    #[error("Failed to read task template {0}: {1}")]
+    Read(PathBuf, std::io::Error),
+
+    /// A template is not valid template JSON.
+    #[error("Invalid task template {0}: {1}")]
+    Invalid(String, serde_json::Error),
+
+    /// Placeholders were left without values.
+    #[error("Task template '{name}' needs values for: {} (use --set NAME=VALUE)", .missing.join(", "))]
+    MissingValues {
+        /// The template's name.
+        name: String,
+        /// Placeholders with no value.
+        missing: Vec<String>,
+    },
+}
+
+/// The task a template makes. Every string may hold placeholders.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TemplateTask {
+    /// Task title.
+    pub title: String,
+    /// Task description; the title if empty.
+    pub description: String,
+    /// Priority from 1 to 5.
+    pub priority: Option<i32>,
+    /// Standard acceptance criteria.
+    pub acceptance_criteria: Vec<String>,
+    /// IDs of tasks that must be complete first.
+    pub depends_on: Vec<String>,
+    /// Model to run the task with.
+    pub model: Option<String>,
+    /// AI CLI to run the task with.
+    pub cli: Option<String>,
+}
+
+/// A task template.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TaskTemplate {
+    /// Template name, from its file name.
+    #[serde(skip)]
+    pub name: String,
+    /// What the template is for, shown by `afk task new --list`.
+    pub description: String,
+    /// Prompts for placeholders, by placeholder name.
+    pub placeholders: BTreeMap<String, String>,
+    /// The task to make.
+    pub task: TemplateTask,
+}
+
+impl TaskTemplate {
+    /// Parse a template from JSON.
+    pub fn parse(name: &str, json: &str) -> Result<Self, TemplateError> {
+        let mut template: Self =
+            serde_json::from_str(json).map_err(|e| TemplateError::Invalid(name.to_string(), e))?;
+        template.name = name.to_string();
+        Ok(template)
+    }
+
+    /// Load the named template from `.afk/templates/tasks/` under `root`
+    /// (the current directory if None), falling back to the built-ins.
+    pub fn load(name: &str, root: Option<&Path>) -> Result<Self, TemplateError> {
+        let path = templates_dir(root).join(format!("{name}.json"));
+        if path.is_file() {
+            let json = fs::read_to_string(&path).map_err(|e| TemplateError::Read(path, e))?;
+            return Self::parse(name, &json);
+        }
+        match BUILT_IN.iter().find(|(built_in, _)| *built_in == name) {
+            Some((_, json)) => Self::parse(name, json),
+            None => Err(TemplateError::Unknown {
+                name: name.to_string(),
+                known: list_templates(root)
+                    .into_iter()
+                    .map(|t| t.name)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }),
+        }
+    }
+
+    /// Placeholder names in the order they first appear, then any the
+    /// template describes but doesn't use.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let used = self
+            .texts()
+            .flat_map(|text| PLACEHOLDER.captures_iter(text))
+            .map(|captures| captures[1].to_string());
+        for name in used.chain(self.placeholders.keys().cloned()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The prompt for a placeholder: its description, or its name.
+    pub fn prompt_for<'a>(&'a self, name: &'a str) -> &'a str {
+        self.placeholders.get(name).map_or(name, String::as_str)
+    }
+
+    /// The task with every placeholder replaced by its value.
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<TemplateTask, TemplateError> {
+        let missing: Vec<String> = self
+            .placeholders()
+            .into_iter()
+            .filter(|name| !values.get(name).is_some_and(|v| !v.trim().is_empty()))
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingValues {
+                name: self.name.clone(),
+                missing,
+            });
+        }
+        let fill = |text: &str| -> String {
+            PLACEHOLDER
+                .replace_all(text, |captures: &Captures| {
+                    values[&captures[1]].trim().to_string()
+                })
+                .into_owned()
+        };
+        let task = &self.task;
+        Ok(TemplateTask {
+            title: fill(&task.title),
+            description: fill(&task.description),
+            priority: task.priority,
+            acceptance_criteria: task.acceptance_criteria.iter().map(|c| fill(c)).collect(),
+            depends_on: task.depends_on.iter().map(|d| fill(d)).collect(),
+            model: task.model.as_deref().map(fill),
+            cli: task.cli.as_deref().map(fill),
+        })
+    }
+
+    /// Every string in the task, where placeholders can appear.
+    fn texts(&self) -> impl Iterator<Item = &String> {
+        let task = &self.task;
+        [&task.title, &task.description]
+            .into_iter()
+            .chain(&task.acceptance_criteria)
+            .chain(&task.depends_on)
+            .chain(task.model.iter())
+            .chain(task.cli.iter())
+    }
+}
+
+/// The directory of task templates under `root`.
+fn templates_dir(root: Option<&Path>) -> PathBuf {
+    match root {
+        Some(root) => root.join(TASK_TEMPLATES_DIR),
+        None => PathBuf::from(TASK_TEMPLATES_DIR),
+    }
+}
+
+/// Every template available under `root` (the current directory if None):
+/// the built-ins and the files in `.afk/templates/tasks/`, by name. Files
+/// that don't parse are left out.
+pub fn list_templates(root: Option<&Path>) -> Vec<TaskTemplate> {
+    let mut templates: BTreeMap<String, TaskTemplate> = BUILT_IN
+        .iter()
+        .filter_map(|(name, json)| Some((name.to_string(), TaskTemplate::parse(name, json).ok()?)))
+        .collect();
+    if let Ok(entries) = fs::read_dir(templates_dir(root)) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if let Ok(template) = TaskTemplate::load(name, root) {
+                templates.insert(name.to_string(), template);
+            }
+        }
+    }
+    templates.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bugfix_template_renders() {
+        let temp = TempDir::new().unwrap();
+        let template = TaskTemplate::load("bugfix", Some(temp.path())).unwrap();
+        assert_eq!(template.placeholders(), ["component", "error"]);
+        assert_eq!(template.prompt_for("error"), "Error message or symptom");
+
+        let task = template
+            .render(&values(&[
+                ("component", "login form"),
+                ("error", " 500 on submit "),
+            ]))
+            .unwrap();
+        assert_eq!(task.title, "Fix login form: 500 on submit");
+        assert_eq!(task.priority, Some(2));
+        assert!(task
+            .acceptance_criteria
+            .iter()
+            .any(|c| c == "Regression test added that fails without the fix"));
+
+        let err = template
+            .render(&values(&[("component", "login form"), ("error", " ")]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Task template 'bugfix' needs values for: error (use --set NAME=VALUE)"
+        );
+    }
+
+    #[test]
+    fn test_project_templates_replace_built_ins() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join(TASK_TEMPLATES_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("bugfix.json"),
+            r#"{"task": {"title": "Bug in {{ area }}", "acceptanceCriteria": ["{{area}} test added"]}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("migration.json"),
+            r#"{"description": "Schema change"}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+
+        let template = TaskTemplate::load("bugfix", Some(temp.path())).unwrap();
+        assert_eq!(template.placeholders(), ["area"]);
+        assert_eq!(template.prompt_for("area"), "area");
+        let task = template.render(&values(&[("area", "billing")])).unwrap();
+        assert_eq!(task.title, "Bug in billing");
+        assert_eq!(task.acceptance_criteria, ["billing test added"]);
+
+        let names: Vec<String> = list_templates(Some(temp.path()))
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["bugfix", "feature", "migration", "refactor"]);
+
+        assert!(matches!(
+            TaskTemplate::load("broken", Some(temp.path())),
+            Err(TemplateError::Invalid(..))
+        ));
+        let unknown = TaskTemplate::load("chore", Some(temp.path())).unwrap_err();
+        assert_eq!(
+            unknown.to_string(),
+            "Unknown task template 'chore': expected one of bugfix, feature, migration, refactor \
+             or .afk/templates/tasks/chore.json"
+        );
+    }
+}
@@ -3,9 +3,12 @@
 //! This module handles archiving and clearing afk sessions,
 //! including moving session files to timestamped archive directories.
 
-use crate::config::{ARCHIVE_DIR, PROGRESS_FILE, TASKS_FILE};
+use crate::config::{
+    ARCHIVE_DIR, ARTIFACTS_DIR, BURNDOWN_FILE, LOGS_DIR, PROGRESS_FILE, REPRO_DIR, TASKS_FILE,
+    WARMUP_FILE,
+};
 use crate::git::get_current_branch;
-use crate::progress::{ProgressError, SessionProgress};
+use crate::progress::{ProgressError, SessionNote, SessionProgress};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -26,6 +29,19 @@ pub struct ArchiveMetadata {
     pub tasks_completed: usize,
     /// Number of tasks pending.
     pub tasks_pending: usize,
+    /// Session tags.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Session notes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<SessionNote>,
+}
+
+impl ArchiveMetadata {
+    /// Whether the session carries a tag (case-insensitive).
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
 }
 
 /// Archive and clear the current session.
@@ -41,6 +57,10 @@ pub struct ArchiveMetadata {
 ///
 /// The path to the archive directory, or None if there's nothing to archive.
 pub fn archive_session(reason: &str) -> Result<Option<PathBuf>, ProgressError> {
+    if crate::config::is_read_only() {
+        return Err(ProgressError::ReadOnly);
+    }
+
     let progress_path = Path::new(PROGRESS_FILE);
     let tasks_path = Path::new(TASKS_FILE);
 
@@ -76,6 +96,36 @@ pub fn archive_session(reason: &str) -> Result<Option<PathBuf>, ProgressError> {
         fs::rename(tasks_path, &archive_tasks)?;
     }
 
+    // Move iteration repro records to archive (if any)
+    let repro_path = Path::new(REPRO_DIR);
+    if repro_path.exists() {
+        fs::rename(repro_path, archive_dir.join("repro"))?;
+    }
+
+    // Move gate output artefacts to archive (if any)
+    let artifacts_path = Path::new(ARTIFACTS_DIR);
+    if artifacts_path.exists() {
+        fs::rename(artifacts_path, archive_dir.join("artifacts"))?;
+    }
+
+    // Move iteration transcripts to archive (if any)
+    let logs_path = Path::new(LOGS_DIR);
+    if logs_path.exists() {
+        fs::rename(logs_path, archive_dir.join("logs"))?;
+    }
+
+    // Move the burn-down history with the tasks it tracks (if any)
+    let burndown_path = Path::new(BURNDOWN_FILE);
+    if burndown_path.exists() {
+        fs::rename(burndown_path, archive_dir.join("burndown.jsonl"))?;
+    }
+
+    // Move the warm-up summary to archive (if any)
+    let warmup_path = Path::new(WARMUP_FILE);
+    if warmup_path.exists() {
+        fs::rename(warmup_path, archive_dir.join("warmup.md"))?;
+    }
+
     // Write metadata
     let (pending, completed, iterations, branch) = if let Some(ref p) = progress {
         let (pend, _, comp, _, _) = p.get_task_counts();
@@ -93,6 +143,11 @@ pub fn archive_session(reason: &str) -> Result<Option<PathBuf>, ProgressError> {
         iterations,
         tasks_completed: completed,
         tasks_pending: pending,
+        tags: progress
+            .as_ref()
+            .map(|p| p.tags.clone())
+            .unwrap_or_default(),
+        notes: progress.map(|p| p.notes).unwrap_or_default(),
     };
     let metadata_path = archive_dir.join("metadata.json");
     let metadata_json = serde_json::to_string_pretty(&metadata)?;
@@ -103,10 +158,29 @@ pub fn archive_session(reason: &str) -> Result<Option<PathBuf>, ProgressError> {
 
 /// Clear the current session (delete progress.json).
 pub fn clear_session() -> Result<(), ProgressError> {
+    if crate::config::is_read_only() {
+        return Err(ProgressError::ReadOnly);
+    }
     let progress_path = Path::new(PROGRESS_FILE);
     if progress_path.exists() {
         fs::remove_file(progress_path)?;
     }
+    let repro_path = Path::new(REPRO_DIR);
+    if repro_path.exists() {
+        fs::remove_dir_all(repro_path)?;
+    }
+    let artifacts_path = Path::new(ARTIFACTS_DIR);
+    if artifacts_path.exists() {
+        fs::remove_dir_all(artifacts_path)?;
+    }
+    let logs_path = Path::new(LOGS_DIR);
+    if logs_path.exists() {
+        fs::remove_dir_all(logs_path)?;
+    }
+    let warmup_path = Path::new(WARMUP_FILE);
+    if warmup_path.exists() {
+        fs::remove_file(warmup_path)?;
+    }
     Ok(())
 }
 
@@ -192,6 +266,14 @@ pub fn update_stored_branch() -> Result<(), ProgressError> {
     Ok(())
 }
 
+/// Add tags to the current session.
+pub fn tag_session(tags: &[String]) -> Result<(), ProgressError> {
+    let mut progress = SessionProgress::load(None)?;
+    progress.add_tags(tags);
+    progress.save(None)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,6 +288,8 @@ mod tests {
             iterations: 10,
             tasks_completed: 5,
             tasks_pending: 3,
+            tags: Vec::new(),
+            notes: Vec::new(),
         };
 
         let json = serde_json::to_string_pretty(&metadata).unwrap();
@@ -234,6 +318,8 @@ mod tests {
             iterations: 5,
             tasks_completed: 5,
             tasks_pending: 0,
+            tags: Vec::new(),
+            notes: Vec::new(),
         };
 
         let json = serde_json::to_string_pretty(&metadata).unwrap();
@@ -309,6 +395,8 @@ mod tests {
             iterations: 3,
             tasks_completed: 2,
             tasks_pending: 1,
+            tags: Vec::new(),
+            notes: Vec::new(),
         };
 
         let json = serde_json::to_string_pretty(&metadata).unwrap();
@@ -319,4 +407,26 @@ mod tests {
         assert_eq!(parsed.branch, Some("feature/branch-archiving".to_string()));
         assert_eq!(parsed.reason, "branch_change");
     }
+
+    #[test]
+    fn test_archive_metadata_tags_and_notes() {
+        // Older metadata without tags still loads
+        let json = r#"{"archived_at": "2024-01-15T10:30:00", "branch": null,
+            "reason": "manual", "iterations": 1, "tasks_completed": 0, "tasks_pending": 1}"#;
+        let metadata: ArchiveMetadata = serde_json::from_str(json).unwrap();
+        assert!(metadata.tags.is_empty());
+        assert!(!metadata.has_tag("night2"));
+
+        let metadata = ArchiveMetadata {
+            tags: vec!["Night2".to_string()],
+            notes: vec![SessionNote {
+                at: "2024-01-15T10:00:00".to_string(),
+                text: "Paused for review".to_string(),
+            }],
+            ..metadata
+        };
+        assert!(metadata.has_tag("night2"));
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(json.contains("\"notes\":[{\"at\""));
+    }
 }
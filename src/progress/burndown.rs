@@ -0,0 +1,435 @@
+//! Backlog burn-down.
+//!
+//! After each iteration the loop appends how many tasks are complete and
+//! remaining to `.afk/burndown.jsonl`. The file sits next to the tasks file
+//! and is archived with it, so its history covers every session run against
+//! the same backlog. `afk status`, the TUI header and the tracking issue
+//! summary draw it as a sparkline, with a verdict on whether the run is
+//! converging; `afk report burndown` draws it as a chart, in tasks or in
+//! summed task estimates, with the pace and when the backlog should be done.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::config::BURNDOWN_FILE;
+use crate::prd::PrdDocument;
+use crate::progress::SessionProgress;
+
+/// Sparkline levels, lowest first.
+const LEVELS: &[char] = &['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Points the trend is judged over.
+pub const TREND_WINDOW: usize = 5;
+
+/// Completed and remaining tasks after an iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BurndownPoint {
+    /// ISO timestamp of the point.
+    pub timestamp: String,
+    /// Start of the session the point belongs to.
+    pub session: String,
+    /// Iterations the session had run.
+    pub iteration: u32,
+    /// Stories that pass.
+    pub completed: usize,
+    /// Stories that don't pass yet.
+    pub remaining: usize,
+    /// Summed `estimate` of the stories that pass, if any story has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_estimate: Option<u32>,
+    /// Summed `estimate` of the stories that don't pass yet, if any story
+    /// has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_estimate: Option<u32>,
+}
+
+impl BurndownPoint {
+    /// The backlog's counts now.
+    pub fn now(prd: &PrdDocument, progress: &SessionProgress) -> Self {
+        let (completed, total) = prd.get_story_counts();
+        let estimates = estimate_totals(prd);
+        Self {
+            timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            session: progress.started_at.clone(),
+            iteration: progress.iterations,
+            completed,
+            remaining: total - completed,
+            completed_estimate: estimates.map(|(done, _)| done),
+            remaining_estimate: estimates.map(|(_, left)| left),
+        }
+    }
+}
+
+/// Summed estimates of the stories that pass and the ones that don't, or
+/// None if no story has an estimate.
+pub fn estimate_totals(prd: &PrdDocument) -> Option<(u32, u32)> {
+    let mut estimated = prd
+        .user_stories
+        .iter()
+        .filter_map(|s| s.estimate.map(|e| (s.passes, e)))
+        .peekable();
+    estimated.peek()?;
+    Some(estimated.fold((0, 0), |(done, left), (passes, e)| {
+        if passes {
+            (done + e, left)
+        } else {
+            (done, left + e)
+        }
+    }))
+}
+
+/// What a burn-down counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    /// Tasks.
+    Tasks,
+    /// Summed task estimates.
+    Estimates,
+}
+
+impl Measure {
+    /// Estimates when the latest point has them, otherwise tasks.
+    pub fn for_history(points: &[BurndownPoint]) -> Self {
+        match points.last() {
+            Some(p) if p.remaining_estimate.is_some() => Self::Estimates,
+            _ => Self::Tasks,
+        }
+    }
+
+    /// The remaining amount at a point, if the point measured it.
+    pub fn remaining(self, point: &BurndownPoint) -> Option<usize> {
+        match self {
+            Self::Tasks => Some(point.remaining),
+            Self::Estimates => point.remaining_estimate.map(|e| e as usize),
+        }
+    }
+
+    /// The latest points that all measured it, oldest first.
+    pub fn history(self, points: &[BurndownPoint]) -> &[BurndownPoint] {
+        let start = points
+            .iter()
+            .rposition(|p| self.remaining(p).is_none())
+            .map_or(0, |i| i + 1);
+        &points[start..]
+    }
+}
+
+/// How fast the latest session is burning down the backlog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pace {
+    /// Amount finished per hour (negative when the backlog grew).
+    pub per_hour: f64,
+    /// Hours the pace is measured over.
+    pub hours: f64,
+    /// When the backlog should be done at this pace, if it is shrinking
+    /// and not done yet.
+    pub finish: Option<NaiveDateTime>,
+}
+
+impl Pace {
+    /// Pace over the points of the last session in `points`, measured in
+    /// `measure`. None with fewer than two points or no time between them.
+    pub fn of(points: &[BurndownPoint], measure: Measure) -> Option<Self> {
+        let session = &points.last()?.session;
+        let points: Vec<&BurndownPoint> = points.iter().filter(|p| &p.session == session).collect();
+        let (first, last) = (points.first()?, points.last()?);
+        let start = parse_timestamp(&first.timestamp)?;
+        let end = parse_timestamp(&last.timestamp)?;
+        let hours = (end - start).num_seconds() as f64 / 3600.0;
+        if hours <= 0.0 {
+            return None;
+        }
+        let remaining = measure.remaining(last)? as f64;
+        let per_hour = (measure.remaining(first)? as f64 - remaining) / hours;
+        let finish = (per_hour > 0.0 && remaining > 0.0).then(|| {
+            end + chrono::Duration::seconds((remaining / per_hour * 3600.0).round() as i64)
+        });
+        Some(Self {
+            per_hour,
+            hours,
+            finish,
+        })
+    }
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+/// Where the backlog is heading over the last [`TREND_WINDOW`] points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    /// Fewer tasks remain than at the start of the window.
+    Converging,
+    /// The same number remain; iterations aren't finishing tasks.
+    Stalled,
+    /// More remain; tasks are being added or reopened faster than done.
+    Growing,
+}
+
+impl Trend {
+    /// Judge the trend from remaining-task counts, oldest first. None with
+    /// fewer than two.
+    pub fn of(remaining: &[usize]) -> Option<Self> {
+        let window = &remaining[remaining.len().saturating_sub(TREND_WINDOW)..];
+        if window.len() < 2 {
+            return None;
+        }
+        let (first, last) = (window[0], window[window.len() - 1]);
+        Some(match last.cmp(&first) {
+            std::cmp::Ordering::Less => Self::Converging,
+            std::cmp::Ordering::Equal if last == 0 => Self::Converging,
+            std::cmp::Ordering::Equal => Self::Stalled,
+            std::cmp::Ordering::Greater => Self::Growing,
+        })
+    }
+
+    /// Short description.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Converging => "converging",
+            Self::Stalled => "stalled",
+            Self::Growing => "growing",
+        }
+    }
+}
+
+/// Append the backlog's counts now to `.afk/burndown.jsonl`.
+///
+/// Does nothing in read-only mode; write errors are ignored.
+pub fn record(prd: &PrdDocument, progress: &SessionProgress) {
+    if crate::config::is_read_only() {
+        return;
+    }
+    let point = BurndownPoint::now(prd, progress);
+    let _ = record_to(Path::new(BURNDOWN_FILE), &point);
+}
+
+/// Append a point to a burn-down file.
+pub fn record_to(path: &Path, point: &BurndownPoint) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut line = serde_json::to_string(point)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// Load the burn-down history. A missing file or unreadable lines give
+/// fewer points, never an error.
+pub fn load(path: Option<&Path>) -> Vec<BurndownPoint> {
+    fs::read_to_string(path.unwrap_or(Path::new(BURNDOWN_FILE)))
+        .map(|contents| {
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Sparkline of remaining-task counts, at most `width` characters of the
+/// most recent values. Values are scaled against the largest shown, so a
+/// burn-down slopes towards `▁`.
+pub fn sparkline(remaining: &[usize], width: usize) -> String {
+    let shown = &remaining[remaining.len().saturating_sub(width)..];
+    let max = shown.iter().copied().max().unwrap_or(0);
+    shown
+        .iter()
+        .map(|&value| LEVELS[(value * (LEVELS.len() - 1)).checked_div(max).unwrap_or(0)])
+        .collect()
+}
+
+/// Bar chart of remaining amounts, oldest first: `height` rows of the last
+/// `width` values, each a column scaled against the largest shown, over an
+/// axis labelled with that largest value and 0.
+pub fn chart(remaining: &[usize], width: usize, height: usize) -> Vec<String> {
+    let shown = &remaining[remaining.len().saturating_sub(width)..];
+    let max = shown.iter().copied().max().unwrap_or(0).max(1);
+    let label_width = max.to_string().len();
+    let mut lines: Vec<String> = (1..=height)
+        .rev()
+        .map(|row| {
+            // Half-row steps, so columns between rows still show
+            let bars: String = shown
+                .iter()
+                .map(|&value| {
+                    let halves = value * 2 * height;
+                    if halves >= 2 * row * max {
+                        '█'
+                    } else if halves >= (2 * row - 1) * max {
+                        '▄'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            let (label, edge) = if row == height {
+                (max.to_string(), '┤')
+            } else {
+                (String::new(), '│')
+            };
+            format!("{label:>label_width$} {edge}{bars}")
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    lines.push(format!("{:>label_width$} └{}", 0, "─".repeat(shown.len())));
+    lines
+}
+
+/// Remaining counts of a history, oldest first.
+pub fn remaining(points: &[BurndownPoint]) -> Vec<usize> {
+    points.iter().map(|p| p.remaining).collect()
+}
+
+/// One line describing a history: a sparkline of the last `width` points,
+/// how the remaining count moved, and the trend. None with fewer than two
+/// points.
+pub fn describe(points: &[BurndownPoint], width: usize) -> Option<String> {
+    let counts = remaining(points);
+    let trend = Trend::of(&counts)?;
+    let sessions = match session_count(points) {
+        1 => String::new(),
+        n => format!(" over {n} sessions"),
+    };
+    Some(format!(
+        "{} {} → {} remaining{sessions} ({})",
+        sparkline(&counts, width),
+        counts[0],
+        counts[counts.len() - 1],
+        trend.label()
+    ))
+}
+
+/// Number of distinct sessions in a history.
+pub fn session_count(points: &[BurndownPoint]) -> usize {
+    let mut sessions: Vec<&str> = points.iter().map(|p| p.session.as_str()).collect();
+    sessions.dedup();
+    sessions.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn point(session: &str, remaining: usize) -> BurndownPoint {
+        BurndownPoint {
+            timestamp: "2026-01-12T14:05:31".to_string(),
+            session: session.to_string(),
+            iteration: 0,
+            completed: 10 - remaining,
+            remaining,
+            completed_estimate: None,
+            remaining_estimate: None,
+        }
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".afk/burndown.jsonl");
+        assert!(load(Some(&path)).is_empty());
+        record_to(&path, &point("s1", 8)).unwrap();
+        record_to(&path, &point("s2", 5)).unwrap();
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "not json\n").unwrap();
+        let points = load(Some(&path));
+        assert_eq!(remaining(&points), [8, 5]);
+        assert_eq!(session_count(&points), 2);
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[8, 6, 4, 2, 0], 10), "█▆▄▂▁");
+        assert_eq!(sparkline(&[9, 8, 6, 4], 2), "█▅");
+        assert_eq!(sparkline(&[0, 0], 5), "▁▁");
+        assert_eq!(sparkline(&[], 5), "");
+    }
+
+    #[test]
+    fn test_chart() {
+        assert_eq!(chart(&[4, 2, 0], 10, 2), ["4 ┤█", "  │██", "0 └───"]);
+        assert_eq!(chart(&[12, 9, 3, 1], 3, 2), ["9 ┤█", "  │█▄", "0 └───"]);
+        assert_eq!(chart(&[], 10, 1), ["1 ┤", "0 └"]);
+    }
+
+    #[test]
+    fn test_measure_and_pace() {
+        let mut points = vec![
+            point("s1", 9),
+            point("s2", 8),
+            point("s2", 6),
+            point("s2", 2),
+        ];
+        points[1].timestamp = "2026-01-12T22:00:00".to_string();
+        points[2].timestamp = "2026-01-12T23:00:00".to_string();
+        points[3].timestamp = "2026-01-13T00:00:00".to_string();
+        for (p, estimate) in points[2..].iter_mut().zip([20, 10]) {
+            p.remaining_estimate = Some(estimate);
+        }
+        assert_eq!(Measure::for_history(&points), Measure::Estimates);
+        assert_eq!(Measure::Estimates.history(&points).len(), 2);
+        assert_eq!(Measure::Tasks.history(&points).len(), 4);
+
+        let tasks = Pace::of(&points, Measure::Tasks).unwrap();
+        assert!((tasks.per_hour - 3.0).abs() < 1e-9);
+        assert!((tasks.hours - 2.0).abs() < 1e-9);
+        assert_eq!(tasks.finish, parse_timestamp("2026-01-13T00:40:00"));
+        let estimates = Pace::of(&points[2..], Measure::Estimates).unwrap();
+        assert!((estimates.per_hour - 10.0).abs() < 1e-9);
+        assert_eq!(estimates.finish, parse_timestamp("2026-01-13T01:00:00"));
+        assert_eq!(Pace::of(&points[..1], Measure::Tasks), None);
+    }
+
+    #[test]
+    fn test_estimate_totals() {
+        let mut prd = PrdDocument::default();
+        prd.user_stories = vec![
+            crate::prd::UserStory::new("a", "A"),
+            crate::prd::UserStory::new("b", "B"),
+        ];
+        assert_eq!(estimate_totals(&prd), None);
+        prd.user_stories[0].estimate = Some(3);
+        prd.user_stories[0].passes = true;
+        prd.user_stories[1].estimate = Some(5);
+        assert_eq!(estimate_totals(&prd), Some((3, 5)));
+    }
+
+    #[test]
+    fn test_trend() {
+        assert_eq!(Trend::of(&[5]), None);
+        assert_eq!(Trend::of(&[9, 9, 5, 4, 4, 4]), Some(Trend::Converging));
+        assert_eq!(Trend::of(&[9, 5, 5, 5, 5, 5]), Some(Trend::Stalled));
+        assert_eq!(Trend::of(&[3, 4]), Some(Trend::Growing));
+        assert_eq!(Trend::of(&[0, 0]), Some(Trend::Converging));
+        assert_eq!(Trend::Stalled.label(), "stalled");
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe(&[point("s1", 8)], 10), None);
+        let points = vec![
+            point("s1", 8),
+            point("s1", 6),
+            point("s2", 6),
+            point("s2", 2),
+        ];
+        assert_eq!(
+            describe(&points, 10).as_deref(),
+            Some("█▆▆▂ 8 → 2 remaining over 2 sessions (converging)")
+        );
+        assert_eq!(
+            describe(&points[..2], 10).as_deref(),
+            Some("█▆ 8 → 6 remaining (converging)")
+        );
+    }
+}
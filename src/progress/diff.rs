@@ -0,0 +1,306 @@
+//! Session diffing between archives.
+//!
+//! This module compares two sessions (archived or current) and reports
+//! what changed: tasks newly completed, regressions, iteration and cost
+//! deltas, and learning changes.
+
+use crate::config::{ARCHIVE_DIR, PROGRESS_FILE, TASKS_FILE};
+use crate::prd::{PrdDocument, PrdError};
+use crate::progress::{ProgressError, SessionProgress, TaskStatus};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// Name used to refer to the current (unarchived) session.
+pub const CURRENT_SESSION: &str = "current";
+
+/// Error type for loading a session snapshot.
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    /// The name is not a plain archive directory name.
+    #[error("invalid archive name: {0}")]
+    InvalidName(String),
+    /// No archive has this name.
+    #[error("archive not found: {0}")]
+    NotFound(String),
+    /// The session's progress file could not be loaded.
+    #[error(transparent)]
+    Progress(#[from] ProgressError),
+    /// The session's tasks file could not be loaded.
+    #[error(transparent)]
+    Tasks(#[from] PrdError),
+}
+
+/// Whether a name is a single, plain path component.
+fn is_archive_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\'])
+        && !Path::new(name).is_absolute()
+}
+
+/// A loaded session, either archived or current.
+#[derive(Debug, Clone, Default)]
+pub struct SessionSnapshot {
+    /// Archive name, or "current" for the live session.
+    pub name: String,
+    /// Session progress (iterations, task status, learnings).
+    pub progress: SessionProgress,
+    /// Task list at the time of the snapshot.
+    pub prd: PrdDocument,
+}
+
+impl SessionSnapshot {
+    /// Load a snapshot by archive name, or the current session for "current".
+    ///
+    /// Archive names are single directory names; anything with a path
+    /// separator or `..` is refused so it can't reach outside the archive.
+    pub fn load(name: &str) -> Result<Self, SnapshotError> {
+        let (progress_path, tasks_path) = if name == CURRENT_SESSION {
+            (PathBuf::from(PROGRESS_FILE), PathBuf::from(TASKS_FILE))
+        } else {
+            if !is_archive_name(name) {
+                return Err(SnapshotError::InvalidName(name.to_string()));
+            }
+            let dir = Path::new(ARCHIVE_DIR).join(name);
+            if !dir.is_dir() {
+                return Err(SnapshotError::NotFound(name.to_string()));
+            }
+            (dir.join("progress.json"), dir.join("tasks.json"))
+        };
+
+        let progress = SessionProgress::load(Some(&progress_path))?;
+        let prd = PrdDocument::load(Some(&tasks_path))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            progress,
+            prd,
+        })
+    }
+
+    /// IDs of all tasks known to this snapshot.
+    fn task_ids(&self) -> BTreeSet<String> {
+        self.prd
+            .user_stories
+            .iter()
+            .map(|s| s.id.clone())
+            .chain(self.progress.tasks.keys().cloned())
+            .collect()
+    }
+
+    /// Whether a task is complete, using session status over the PRD flag.
+    fn is_complete(&self, task_id: &str) -> bool {
+        if let Some(task) = self.progress.tasks.get(task_id) {
+            if task.status == TaskStatus::Completed {
+                return true;
+            }
+        }
+        self.prd
+            .user_stories
+            .iter()
+            .any(|s| s.id == task_id && s.passes)
+    }
+
+    /// Number of completed tasks in this snapshot.
+    fn completed_count(&self) -> usize {
+        self.task_ids()
+            .iter()
+            .filter(|id| self.is_complete(id))
+            .count()
+    }
+
+    /// Total failure count across all tasks.
+    fn total_failures(&self) -> u32 {
+        self.progress.tasks.values().map(|t| t.failure_count).sum()
+    }
+
+    /// All (task_id, learning) pairs in this snapshot.
+    fn learnings(&self) -> BTreeSet<(String, String)> {
+        self.progress
+            .tasks
+            .values()
+            .flat_map(|t| t.learnings.iter().map(|l| (t.id.clone(), l.clone())))
+            .collect()
+    }
+}
+
+/// Differences between two sessions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionDiff {
+    /// Tasks complete in the second session but not the first.
+    pub newly_completed: Vec<String>,
+    /// Tasks complete in the first session but not the second.
+    pub regressions: Vec<String>,
+    /// Tasks only present in the second session.
+    pub added_tasks: Vec<String>,
+    /// Tasks only present in the first session.
+    pub removed_tasks: Vec<String>,
+    /// Iterations in the first and second session.
+    pub iterations: (u32, u32),
+    /// Completed task counts in the first and second session.
+    pub completed: (usize, usize),
+    /// Total failure counts in the first and second session.
+    pub failures: (u32, u32),
+    /// Cost in USD of the first and second session, where reported.
+    pub cost: (Option<f64>, Option<f64>),
+    /// Learnings recorded only in the second session, as (task_id, learning).
+    pub learnings_added: Vec<(String, String)>,
+    /// Learnings recorded only in the first session, as (task_id, learning).
+    pub learnings_removed: Vec<(String, String)>,
+}
+
+impl SessionDiff {
+    /// Whether the two sessions differ in any tracked way.
+    pub fn is_empty(&self) -> bool {
+        self.newly_completed.is_empty()
+            && self.regressions.is_empty()
+            && self.added_tasks.is_empty()
+            && self.removed_tasks.is_empty()
+            && self.iterations.0 == self.iterations.1
+            && self.failures.0 == self.failures.1
+            && self.cost.0 == self.cost.1
+            && self.learnings_added.is_empty()
+            && self.learnings_removed.is_empty()
+    }
+}
+
+/// Compare two session snapshots.
+///
+/// `a` is treated as the baseline and `b` as the comparison.
+pub fn diff_sessions(a: &SessionSnapshot, b: &SessionSnapshot) -> SessionDiff {
+    let ids_a = a.task_ids();
+    let ids_b = b.task_ids();
+
+    let mut diff = SessionDiff {
+        iterations: (a.progress.iterations, b.progress.iterations),
+        completed: (a.completed_count(), b.completed_count()),
+        failures: (a.total_failures(), b.total_failures()),
+        cost: (a.progress.cost_usd, b.progress.cost_usd),
+        ..Default::default()
+    };
+
+    for id in ids_a.intersection(&ids_b) {
+        match (a.is_complete(id), b.is_complete(id)) {
+            (false, true) => diff.newly_completed.push(id.clone()),
+            (true, false) => diff.regressions.push(id.clone()),
+            _ => {}
+        }
+    }
+    for id in ids_b.difference(&ids_a) {
+        diff.added_tasks.push(id.clone());
+        if b.is_complete(id) {
+            diff.newly_completed.push(id.clone());
+        }
+    }
+    diff.removed_tasks = ids_a.difference(&ids_b).cloned().collect();
+
+    let learnings_a = a.learnings();
+    let learnings_b = b.learnings();
+    diff.learnings_added = learnings_b.difference(&learnings_a).cloned().collect();
+    diff.learnings_removed = learnings_a.difference(&learnings_b).cloned().collect();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::UserStory;
+    use crate::progress::TaskProgress;
+
+    fn snapshot(name: &str, iterations: u32, stories: &[(&str, bool)]) -> SessionSnapshot {
+        let mut progress = SessionProgress::new();
+        progress.iterations = iterations;
+        SessionSnapshot {
+            name: name.to_string(),
+            progress,
+            prd: PrdDocument {
+                user_stories: stories
+                    .iter()
+                    .map(|(id, passes)| UserStory {
+                        id: id.to_string(),
+                        passes: *passes,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_diff_identical_sessions_is_empty() {
+        let a = snapshot("a", 3, &[("t1", true), ("t2", false)]);
+        let diff = diff_sessions(&a, &a.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.completed, (1, 1));
+    }
+
+    #[test]
+    fn test_diff_completed_and_regressions() {
+        let a = snapshot("a", 2, &[("t1", true), ("t2", false), ("old", false)]);
+        let b = snapshot("b", 5, &[("t1", false), ("t2", true), ("new", true)]);
+
+        let diff = diff_sessions(&a, &b);
+        assert_eq!(diff.newly_completed, vec!["t2", "new"]);
+        assert_eq!(diff.cost, (None, None));
+        assert_eq!(diff.regressions, vec!["t1"]);
+        assert_eq!(diff.added_tasks, vec!["new"]);
+        assert_eq!(diff.removed_tasks, vec!["old"]);
+        assert_eq!(diff.iterations, (2, 5));
+    }
+
+    #[test]
+    fn test_diff_uses_session_status_and_learnings() {
+        let a = snapshot("a", 1, &[("t1", false)]);
+        let mut b = a.clone();
+        let mut task = TaskProgress::new("t1", "prd");
+        task.status = TaskStatus::Completed;
+        task.failure_count = 2;
+        task.learnings.push("Use the builder".to_string());
+        b.progress.tasks.insert("t1".to_string(), task);
+        b.progress.cost_usd = Some(0.75);
+
+        let diff = diff_sessions(&a, &b);
+        assert_eq!(diff.cost, (None, Some(0.75)));
+        assert_eq!(diff.newly_completed, vec!["t1"]);
+        assert_eq!(diff.failures, (0, 2));
+        assert_eq!(
+            diff.learnings_added,
+            vec![("t1".to_string(), "Use the builder".to_string())]
+        );
+        assert!(diff.learnings_removed.is_empty());
+    }
+
+    #[test]
+    fn test_load_missing_archive_errors() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::env::set_current_dir(temp.path()).unwrap();
+
+        assert!(matches!(
+            SessionSnapshot::load("20240101_000000"),
+            Err(SnapshotError::NotFound(_))
+        ));
+        assert!(SessionSnapshot::load(CURRENT_SESSION).is_ok());
+
+        for name in ["..", "../..", "a/b", "/etc", "..\\x", ""] {
+            assert!(
+                matches!(
+                    SessionSnapshot::load(name),
+                    Err(SnapshotError::InvalidName(_))
+                ),
+                "{name}"
+            );
+        }
+
+        // A corrupt tasks file is an error, not an empty session
+        let dir = Path::new(ARCHIVE_DIR).join("20240101_000000");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tasks.json"), "{ not json").unwrap();
+        assert!(matches!(
+            SessionSnapshot::load("20240101_000000"),
+            Err(SnapshotError::Tasks(_))
+        ));
+    }
+}
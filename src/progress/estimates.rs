@@ -0,0 +1,307 @@
+//! Estimate-vs-actual tracking.
+//!
+//! Stories may carry an `effort` estimate in iterations. This module
+//! compares estimates with the iterations and time tasks actually took,
+//! flags large overruns, and calibrates future estimates from the history
+//! of the current and archived sessions. A story's `estimate` (story points
+//! or minutes) is set against the wall-clock time the loop spent on it.
+
+use chrono::NaiveDateTime;
+
+use crate::prd::PrdDocument;
+use crate::progress::{list_archives, SessionProgress, SessionSnapshot, TaskStatus};
+
+/// A task counts as a large overrun once it takes this many times its estimate.
+pub const OVERRUN_FACTOR: f64 = 2.0;
+
+/// Estimated and actual effort for one task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffortSample {
+    /// Task ID.
+    pub task_id: String,
+    /// Estimated iterations, if the story had one.
+    pub effort: Option<u32>,
+    /// Iterations that targeted the task.
+    pub actual: u32,
+    /// Seconds from first attempt to completion, if both are known.
+    pub seconds: Option<i64>,
+}
+
+impl EffortSample {
+    /// Whether the task took far longer than estimated.
+    pub fn is_overrun(&self) -> bool {
+        self.effort
+            .is_some_and(|effort| f64::from(self.actual) > f64::from(effort) * OVERRUN_FACTOR)
+    }
+}
+
+/// Samples for every attempted task in a session.
+///
+/// Tasks that were never attempted by the loop (e.g. completed by hand)
+/// are left out, since they say nothing about iterations.
+pub fn session_samples(prd: &PrdDocument, progress: &SessionProgress) -> Vec<EffortSample> {
+    let mut samples: Vec<EffortSample> = progress
+        .tasks
+        .values()
+        .filter(|task| task.attempts > 0)
+        .map(|task| EffortSample {
+            task_id: task.id.clone(),
+            effort: prd
+                .user_stories
+                .iter()
+                .find(|s| s.id == task.id)
+                .and_then(|s| s.effort),
+            actual: task.attempts,
+            seconds: match (&task.started_at, &task.completed_at) {
+                (Some(start), Some(end)) => elapsed_seconds(start, end),
+                _ => None,
+            },
+        })
+        .collect();
+    samples.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+    samples
+}
+
+/// Samples for completed tasks across the current and archived sessions.
+pub fn history_samples() -> Vec<EffortSample> {
+    let mut names = vec![crate::progress::CURRENT_SESSION.to_string()];
+    names.extend(
+        list_archives()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name, _)| name),
+    );
+
+    names
+        .iter()
+        .filter_map(|name| SessionSnapshot::load(name).ok())
+        .flat_map(|snapshot| completed_samples(&snapshot.prd, &snapshot.progress))
+        .collect()
+}
+
+/// Samples for a session's completed tasks only.
+fn completed_samples(prd: &PrdDocument, progress: &SessionProgress) -> Vec<EffortSample> {
+    session_samples(prd, progress)
+        .into_iter()
+        .filter(|sample| {
+            progress
+                .get_task(&sample.task_id)
+                .is_some_and(|t| t.status == TaskStatus::Completed)
+        })
+        .collect()
+}
+
+/// Project-specific calibration learned from completed tasks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    /// Actual iterations per estimated iteration (1.0 with no history).
+    pub ratio: f64,
+    /// Average iterations for a task without an estimate.
+    pub iterations_per_task: f64,
+    /// Average seconds per iteration, if any completion times are known.
+    pub seconds_per_iteration: Option<f64>,
+    /// Number of completed tasks the calibration is based on.
+    pub samples: usize,
+}
+
+impl Calibration {
+    /// Learn a calibration from completed task samples.
+    pub fn from_samples(samples: &[EffortSample]) -> Self {
+        let estimated: Vec<&EffortSample> = samples.iter().filter(|s| s.effort.is_some()).collect();
+        let total_effort: u32 = estimated.iter().filter_map(|s| s.effort).sum();
+        let ratio = if total_effort > 0 {
+            f64::from(estimated.iter().map(|s| s.actual).sum::<u32>()) / f64::from(total_effort)
+        } else {
+            1.0
+        };
+
+        let iterations_per_task = if samples.is_empty() {
+            1.0
+        } else {
+            f64::from(samples.iter().map(|s| s.actual).sum::<u32>()) / samples.len() as f64
+        };
+
+        let timed: Vec<&EffortSample> = samples.iter().filter(|s| s.seconds.is_some()).collect();
+        let timed_iterations: u32 = timed.iter().map(|s| s.actual).sum();
+        let seconds_per_iteration = (timed_iterations > 0).then(|| {
+            timed.iter().filter_map(|s| s.seconds).sum::<i64>() as f64 / f64::from(timed_iterations)
+        });
+
+        Self {
+            ratio,
+            iterations_per_task,
+            seconds_per_iteration,
+            samples: samples.len(),
+        }
+    }
+
+    /// Estimated iterations to finish the pending stories.
+    pub fn estimate_iterations(&self, prd: &PrdDocument) -> f64 {
+        prd.user_stories
+            .iter()
+            .filter(|story| !story.passes)
+            .map(|story| match story.effort {
+                Some(effort) => f64::from(effort) * self.ratio,
+                None => self.iterations_per_task,
+            })
+            .sum()
+    }
+}
+
+/// Summed `estimate`s of completed tasks against the time they took.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EstimateActuals {
+    /// Completed tasks with an estimate and recorded time.
+    pub tasks: usize,
+    /// Their summed estimates.
+    pub estimate: u32,
+    /// Wall-clock seconds the loop spent on them.
+    pub seconds: u64,
+}
+
+impl EstimateActuals {
+    /// Totals for the stories that pass, were estimated, and have time
+    /// recorded in the session. Tasks finished without the loop are left
+    /// out, since their time is unknown.
+    pub fn of(prd: &PrdDocument, progress: &SessionProgress) -> Self {
+        prd.user_stories
+            .iter()
+            .filter(|story| story.passes)
+            .filter_map(|story| {
+                let seconds = progress.get_task(&story.id)?.seconds_spent;
+                Some((story.estimate?, seconds)).filter(|_| seconds > 0)
+            })
+            .fold(Self::default(), |total, (estimate, seconds)| Self {
+                tasks: total.tasks + 1,
+                estimate: total.estimate + estimate,
+                seconds: total.seconds + seconds,
+            })
+    }
+
+    /// Actual minutes per estimated unit, if anything was estimated.
+    pub fn minutes_per_unit(&self) -> Option<f64> {
+        (self.estimate > 0).then(|| self.seconds as f64 / 60.0 / f64::from(self.estimate))
+    }
+}
+
+/// Seconds between two progress timestamps.
+fn elapsed_seconds(start: &str, end: &str) -> Option<i64> {
+    let parse = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok();
+    let seconds = (parse(end)? - parse(start)?).num_seconds();
+    (seconds >= 0).then_some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::UserStory;
+
+    fn sample(effort: Option<u32>, actual: u32, seconds: Option<i64>) -> EffortSample {
+        EffortSample {
+            task_id: "t".to_string(),
+            effort,
+            actual,
+            seconds,
+        }
+    }
+
+    #[test]
+    fn test_session_samples_and_overruns() {
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory {
+                    effort: Some(1),
+                    ..UserStory::new("auth", "Login")
+                },
+                UserStory::new("docs", "Docs"),
+            ],
+            ..Default::default()
+        };
+        let mut progress = SessionProgress::new();
+        for _ in 0..3 {
+            progress.record_attempt("auth", "json");
+        }
+        progress.record_attempt("docs", "json");
+        progress.set_task_status("never-run", TaskStatus::Completed, "manual", None);
+
+        let samples = session_samples(&prd, &progress);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].task_id, "auth");
+        assert_eq!(samples[0].effort, Some(1));
+        assert_eq!(samples[0].actual, 3);
+        assert!(samples[0].is_overrun());
+        assert!(!samples[1].is_overrun());
+    }
+
+    #[test]
+    fn test_calibration_from_samples() {
+        let calibration = Calibration::from_samples(&[
+            sample(Some(2), 3, Some(600)),
+            sample(Some(2), 5, None),
+            sample(None, 1, Some(120)),
+        ]);
+        assert_eq!(calibration.ratio, 2.0);
+        assert_eq!(calibration.iterations_per_task, 3.0);
+        assert_eq!(calibration.seconds_per_iteration, Some(180.0));
+        assert_eq!(calibration.samples, 3);
+
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory {
+                    effort: Some(2),
+                    ..UserStory::new("a", "A")
+                },
+                UserStory::new("b", "B"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(calibration.estimate_iterations(&prd), 7.0);
+    }
+
+    #[test]
+    fn test_calibration_without_history() {
+        let calibration = Calibration::from_samples(&[]);
+        assert_eq!(calibration.ratio, 1.0);
+        assert_eq!(calibration.iterations_per_task, 1.0);
+        assert_eq!(calibration.seconds_per_iteration, None);
+    }
+
+    #[test]
+    fn test_estimate_actuals() {
+        let mut prd = PrdDocument {
+            user_stories: vec![
+                UserStory::new("a", "A"),
+                UserStory::new("b", "B"),
+                UserStory::new("c", "C"),
+                UserStory::new("d", "D"),
+            ],
+            ..Default::default()
+        };
+        for (story, estimate) in prd.user_stories.iter_mut().zip([30, 20, 10, 40]) {
+            story.estimate = Some(estimate);
+            story.passes = story.id != "d";
+        }
+        let mut progress = SessionProgress::new();
+        for (id, seconds) in [("a", 2400), ("b", 1200), ("d", 600)] {
+            progress.set_task_status(id, TaskStatus::Completed, "json", None);
+            progress.get_task_mut(id).unwrap().seconds_spent = seconds;
+        }
+
+        // c was completed by hand, d isn't done
+        let actuals = EstimateActuals::of(&prd, &progress);
+        assert_eq!(actuals.tasks, 2);
+        assert_eq!(actuals.estimate, 50);
+        assert_eq!(actuals.seconds, 3600);
+        assert_eq!(actuals.minutes_per_unit(), Some(1.2));
+        assert_eq!(EstimateActuals::default().minutes_per_unit(), None);
+    }
+
+    #[test]
+    fn test_elapsed_seconds() {
+        assert_eq!(
+            elapsed_seconds("2026-01-01T10:00:00.000000", "2026-01-01T10:05:30.5"),
+            Some(330)
+        );
+        assert_eq!(elapsed_seconds("bad", "2026-01-01T10:00:00"), None);
+    }
+}
@@ -2,6 +2,8 @@
 //!
 //! This module provides functions to check various limits during the loop.
 
+use std::time::Duration;
+
 use super::{SessionProgress, TaskStatus};
 
 /// Signals for limit checking results.
@@ -76,9 +78,10 @@ pub fn check_limits(
         .tasks
         .iter()
         .filter(|(_, task)| {
-            task.status != TaskStatus::Completed
-                && task.status != TaskStatus::Skipped
-                && task.failure_count >= max_task_failures
+            !matches!(
+                task.status,
+                TaskStatus::Completed | TaskStatus::Skipped | TaskStatus::AwaitingReview
+            ) && task.failure_count >= max_task_failures
         })
         .map(|(id, _)| id.clone())
         .collect();
@@ -120,6 +123,53 @@ pub fn should_skip_task(progress: &SessionProgress, task_id: &str, max_failures:
     }
 }
 
+/// Add an iteration's wall-clock time to the session and to the task it
+/// worked on.
+///
+/// A task that has now used `max_task_minutes` (0 = no limit) without
+/// completing is skipped. Returns true if the task was skipped.
+pub fn charge_iteration_time(
+    progress: &mut SessionProgress,
+    task_id: Option<&str>,
+    elapsed: Duration,
+    max_task_minutes: u32,
+) -> bool {
+    let seconds = elapsed.as_secs();
+    progress.seconds_spent += seconds;
+
+    let Some(task) = task_id.and_then(|id| progress.get_task_mut(id)) else {
+        return false;
+    };
+    task.seconds_spent += seconds;
+    let over_budget =
+        max_task_minutes > 0 && task.seconds_spent >= u64::from(max_task_minutes) * 60;
+    if !over_budget
+        || matches!(
+            task.status,
+            TaskStatus::Completed | TaskStatus::Skipped | TaskStatus::AwaitingReview
+        )
+    {
+        return false;
+    }
+
+    let (id, source) = (task.id.clone(), task.source.clone());
+    progress.set_task_status(
+        &id,
+        TaskStatus::Skipped,
+        &source,
+        Some(format!(
+            "Skipped after using its {max_task_minutes}-minute budget"
+        )),
+    );
+    true
+}
+
+/// Whether the session has used its `max_session_minutes` (0 = no limit)
+/// of iteration time.
+pub fn session_budget_exceeded(progress: &SessionProgress, max_session_minutes: u32) -> bool {
+    max_session_minutes > 0 && progress.seconds_spent >= u64::from(max_session_minutes) * 60
+}
+
 /// Get the failure count for a task.
 pub fn get_failure_count(progress: &SessionProgress, task_id: &str) -> u32 {
     progress
@@ -229,6 +279,49 @@ mod tests {
         assert!(!should_skip_task(&progress, "nonexistent", 3));
     }
 
+    #[test]
+    fn test_charge_iteration_time_skips_over_budget_task() {
+        let mut progress = create_test_progress();
+        let ten_minutes = Duration::from_secs(600);
+
+        assert!(!charge_iteration_time(
+            &mut progress,
+            Some("task-001"),
+            ten_minutes,
+            15
+        ));
+        assert!(charge_iteration_time(
+            &mut progress,
+            Some("task-001"),
+            ten_minutes,
+            15
+        ));
+        let task = progress.get_task("task-001").unwrap();
+        assert_eq!(task.seconds_spent, 1200);
+        assert_eq!(task.status, TaskStatus::Skipped);
+        assert!(progress.is_skipped("task-001"));
+        assert_eq!(progress.seconds_spent, 1200);
+
+        // No limit, or no task, only adds to the session total
+        assert!(!charge_iteration_time(
+            &mut progress,
+            Some("task-002"),
+            ten_minutes * 10,
+            0
+        ));
+        assert!(!charge_iteration_time(&mut progress, None, ten_minutes, 15));
+        assert_eq!(progress.seconds_spent, 1200 + 6000 + 600);
+    }
+
+    #[test]
+    fn test_session_budget_exceeded() {
+        let mut progress = SessionProgress::new();
+        progress.seconds_spent = 3600;
+        assert!(session_budget_exceeded(&progress, 60));
+        assert!(!session_budget_exceeded(&progress, 61));
+        assert!(!session_budget_exceeded(&progress, 0));
+    }
+
     #[test]
     fn test_get_failure_count() {
         let progress = create_test_progress();
@@ -4,20 +4,33 @@
 //! mirroring the Python Pydantic models in src/afk/progress.py.
 
 pub mod archive;
+pub mod burndown;
+pub mod diff;
+pub mod estimates;
 pub mod limits;
+pub mod repro;
+pub mod selection;
+pub mod stats;
+pub mod transcript;
 
 pub use archive::{
-    archive_session, check_branch_change, clear_session, list_archives, update_stored_branch,
-    ArchiveMetadata, BranchChangeInfo,
+    archive_session, check_branch_change, clear_session, list_archives, tag_session,
+    update_stored_branch, ArchiveMetadata, BranchChangeInfo,
 };
+pub use burndown::{BurndownPoint, Measure, Pace, Trend};
+pub use diff::{diff_sessions, SessionDiff, SessionSnapshot, SnapshotError, CURRENT_SESSION};
+pub use estimates::{history_samples, session_samples, Calibration, EffortSample, EstimateActuals};
 pub use limits::{
-    check_limits, get_failure_count, should_skip_task, LimitCheckResult, LimitSignal,
+    charge_iteration_time, check_limits, get_failure_count, session_budget_exceeded,
+    should_skip_task, LimitCheckResult, LimitSignal,
 };
+pub use selection::{exclusions, Exclusion, Selection};
 
 use crate::config::PROGRESS_FILE;
+use crate::schema::{self, SchemaKind};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -36,6 +49,36 @@ pub enum TaskStatus {
     Failed,
     /// Task was skipped (e.g., due to too many failures).
     Skipped,
+    /// Task was marked passed and is waiting for `afk review`.
+    AwaitingReview,
+}
+
+/// How an iteration on a task failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The AI CLI exited with an error.
+    CliCrash,
+    /// The AI CLI stalled and was stopped.
+    Timeout,
+    /// Quality gates or criterion checks failed.
+    GatesFailed,
+    /// The iteration changed no files and completed nothing.
+    NoChanges,
+    /// The AI CLI reported a rate limit.
+    RateLimit,
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CliCrash => "AI CLI error",
+            Self::Timeout => "AI CLI stalled",
+            Self::GatesFailed => "gates failed",
+            Self::NoChanges => "no changes",
+            Self::RateLimit => "rate limited",
+        })
+    }
 }
 
 /// Progress record for a single task.
@@ -70,6 +113,46 @@ pub struct TaskProgress {
     /// Short-term learnings specific to this task, discovered during this session.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub learnings: Vec<String>,
+    /// Number of iterations that targeted this task.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub attempts: u32,
+    /// Parallel worker currently holding the task, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claimed_by: Option<String>,
+    /// Wall-clock seconds the loop has spent on the task.
+    #[serde(default, skip_serializing_if = "is_zero_u64")]
+    pub seconds_spent: u64,
+    /// URL of the pull request opened for the task (`git.open_pr`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pr_url: Option<String>,
+    /// Model each iteration on the task ran with, oldest first
+    /// ([`DEFAULT_MODEL`] when the AI CLI chose).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    /// Guardrail violations iterations on the task caused, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<String>,
+    /// Feedback from reviews that sent the task back, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub review_feedback: Vec<String>,
+    /// How the latest failed iteration on the task failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_failure: Option<FailureKind>,
+    /// Iterations in a row that failed as `last_failure` did.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub failure_streak: u32,
+}
+
+/// Recorded in [`TaskProgress::models`] for an iteration that passed no
+/// model to the AI CLI.
+pub const DEFAULT_MODEL: &str = "default";
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+fn is_zero_u64(n: &u64) -> bool {
+    *n == 0
 }
 
 impl TaskProgress {
@@ -85,12 +168,21 @@ impl TaskProgress {
             commits: Vec::new(),
             message: None,
             learnings: Vec::new(),
+            attempts: 0,
+            claimed_by: None,
+            seconds_spent: 0,
+            pr_url: None,
+            models: Vec::new(),
+            violations: Vec::new(),
+            review_feedback: Vec::new(),
+            last_failure: None,
+            failure_streak: 0,
         }
     }
 }
 
 /// Progress for the current afk session.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SessionProgress {
     /// ISO timestamp when the session was started.
     #[serde(default = "default_started_at")]
@@ -102,9 +194,40 @@ pub struct SessionProgress {
     /// Used to detect branch changes between runs.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_branch: Option<String>,
+    /// Task targeted by the previous prompt, used for continuation prompts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_task: Option<String>,
+    /// Commit at the start of the previous attempt on `last_task`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_task_commit: Option<String>,
     /// Map of task ID to task progress.
     #[serde(default)]
     pub tasks: HashMap<String, TaskProgress>,
+    /// Labels saying what the session was for (from `afk go --tag`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Free-form notes added with `afk note`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<SessionNote>,
+    /// Versions of the tools quality gates run, recorded on the session's
+    /// first `afk verify`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_versions: BTreeMap<String, String>,
+    /// Wall-clock seconds the loop has spent on iterations this session.
+    #[serde(default, skip_serializing_if = "is_zero_u64")]
+    pub seconds_spent: u64,
+    /// Cost in USD of this session's iterations, if the AI CLI reported any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// A timestamped note on a session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionNote {
+    /// ISO timestamp when the note was added.
+    pub at: String,
+    /// Note text.
+    pub text: String,
 }
 
 impl Default for SessionProgress {
@@ -113,7 +236,14 @@ impl Default for SessionProgress {
             started_at: default_started_at(),
             iterations: 0,
             last_branch: None,
+            last_task: None,
+            last_task_commit: None,
             tasks: HashMap::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            tool_versions: BTreeMap::new(),
+            seconds_spent: 0,
+            cost_usd: None,
         }
     }
 }
@@ -135,6 +265,12 @@ pub enum ProgressError {
     /// Error parsing the progress file JSON.
     #[error("Failed to parse progress JSON: {0}")]
     ParseError(#[from] serde_json::Error),
+    /// The progress file does not match the progress schema.
+    #[error("Progress file does not match the schema: {0}")]
+    Invalid(String),
+    /// Refused to write because read-only mode is active.
+    #[error("Refusing to write session state: read-only mode is active")]
+    ReadOnly,
 }
 
 impl SessionProgress {
@@ -144,7 +280,14 @@ impl SessionProgress {
             started_at: default_started_at(),
             iterations: 0,
             last_branch: None,
+            last_task: None,
+            last_task_commit: None,
             tasks: HashMap::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            tool_versions: BTreeMap::new(),
+            seconds_spent: 0,
+            cost_usd: None,
         }
     }
 
@@ -153,6 +296,23 @@ impl SessionProgress {
         self.last_branch = branch;
     }
 
+    /// Add tags to the session, skipping blanks and duplicates.
+    pub fn add_tags(&mut self, tags: &[String]) {
+        for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !self.tags.iter().any(|t| t == tag) {
+                self.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    /// Add a timestamped note to the session.
+    pub fn add_note(&mut self, text: impl Into<String>) {
+        self.notes.push(SessionNote {
+            at: Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            text: text.into(),
+        });
+    }
+
     /// Get the last branch for this session.
     pub fn get_branch(&self) -> Option<&str> {
         self.last_branch.as_deref()
@@ -173,7 +333,12 @@ impl SessionProgress {
         }
 
         let contents = fs::read_to_string(&path)?;
-        let progress: SessionProgress = serde_json::from_str(&contents)?;
+        let data: serde_json::Value = serde_json::from_str(&contents)?;
+        let violations = SchemaKind::Progress.validate(&data);
+        if !violations.is_empty() {
+            return Err(ProgressError::Invalid(schema::describe(&violations)));
+        }
+        let progress: SessionProgress = serde_json::from_value(data)?;
         Ok(progress)
     }
 
@@ -185,6 +350,10 @@ impl SessionProgress {
     ///
     /// Creates parent directories if they don't exist.
     pub fn save(&self, path: Option<&Path>) -> Result<(), ProgressError> {
+        if crate::config::is_read_only() {
+            return Err(ProgressError::ReadOnly);
+        }
+
         let path = path
             .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from(PROGRESS_FILE));
@@ -251,13 +420,88 @@ impl SessionProgress {
             TaskStatus::Failed => {
                 task.failure_count += 1;
             }
-            TaskStatus::Pending | TaskStatus::Skipped => {}
+            TaskStatus::Pending | TaskStatus::Skipped | TaskStatus::AwaitingReview => {}
         }
 
         task.status = status;
         task
     }
 
+    /// Record an iteration targeting a task, starting its clock if needed.
+    pub fn record_attempt(&mut self, task_id: &str, source: &str) {
+        let task = self
+            .tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| TaskProgress::new(task_id, source));
+        task.attempts += 1;
+        if task.started_at.is_none() {
+            task.started_at = Some(Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string());
+        }
+    }
+
+    /// Record the model an iteration on a task ran with. Tasks without
+    /// progress yet are left alone.
+    pub fn record_model(&mut self, task_id: &str, model: Option<&str>) {
+        if let Some(task) = self.tasks.get_mut(task_id) {
+            task.models.push(model.unwrap_or(DEFAULT_MODEL).to_string());
+        }
+    }
+
+    /// Record how an iteration on a task failed.
+    ///
+    /// Returns the number of iterations in a row that have now failed this
+    /// way.
+    pub fn record_failure(&mut self, task_id: &str, kind: FailureKind, source: &str) -> u32 {
+        let task = self
+            .tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| TaskProgress::new(task_id, source));
+        task.failure_streak = if task.last_failure == Some(kind) {
+            task.failure_streak + 1
+        } else {
+            1
+        };
+        task.last_failure = Some(kind);
+        task.failure_streak
+    }
+
+    /// Note an iteration on a task that didn't fail, ending any failure
+    /// streak. The last failure's kind is kept.
+    pub fn clear_failure_streak(&mut self, task_id: &str) {
+        if let Some(task) = self.tasks.get_mut(task_id) {
+            task.failure_streak = 0;
+        }
+    }
+
+    /// Claim a task for a parallel worker.
+    ///
+    /// Returns false if another worker already holds the task.
+    pub fn claim_task(&mut self, task_id: &str, source: &str, worker: &str) -> bool {
+        let task = self
+            .tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| TaskProgress::new(task_id, source));
+        if task.claimed_by.as_deref().is_some_and(|w| w != worker) {
+            return false;
+        }
+        task.claimed_by = Some(worker.to_string());
+        true
+    }
+
+    /// Release a worker's claim on a task.
+    pub fn release_claim(&mut self, task_id: &str) {
+        if let Some(task) = self.tasks.get_mut(task_id) {
+            task.claimed_by = None;
+        }
+    }
+
+    /// Release every claim, e.g. ones left behind by an interrupted run.
+    pub fn clear_claims(&mut self) {
+        for task in self.tasks.values_mut() {
+            task.claimed_by = None;
+        }
+    }
+
     /// Get all pending tasks.
     pub fn get_pending_tasks(&self) -> Vec<&TaskProgress> {
         self.tasks
@@ -298,6 +542,13 @@ impl SessionProgress {
             .collect()
     }
 
+    /// Whether a task has been skipped, so the loop should not pick it.
+    #[must_use]
+    pub fn is_skipped(&self, task_id: &str) -> bool {
+        self.get_task(task_id)
+            .is_some_and(|t| t.status == TaskStatus::Skipped)
+    }
+
     /// Check if all tasks are complete.
     ///
     /// Returns true if there are tasks and all are either completed or skipped.
@@ -324,6 +575,16 @@ impl SessionProgress {
         task.learnings.push(learning.into());
     }
 
+    /// Record a guardrail violation against a task.
+    pub fn record_violation(&mut self, task_id: &str, violation: impl Into<String>, source: &str) {
+        let task = self
+            .tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| TaskProgress::new(task_id, source));
+
+        task.violations.push(violation.into());
+    }
+
     /// Get all learnings grouped by task ID.
     ///
     /// Only returns tasks that have at least one learning.
@@ -367,6 +628,14 @@ impl SessionProgress {
             .collect()
     }
 
+    /// Record the pull request opened for a task.
+    pub fn set_pr_url(&mut self, task_id: &str, url: impl Into<String>, source: &str) {
+        self.tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| TaskProgress::new(task_id, source))
+            .pr_url = Some(url.into());
+    }
+
     /// Add a commit hash to a task.
     pub fn add_commit(&mut self, task_id: &str, commit_hash: impl Into<String>, source: &str) {
         let task = self
@@ -379,14 +648,17 @@ impl SessionProgress {
 
     /// Get task counts by status.
     ///
-    /// Returns (pending, in_progress, completed, failed, skipped).
+    /// Returns (pending, in_progress, completed, failed, skipped). Tasks
+    /// awaiting review count as in progress.
     #[must_use]
     pub fn get_task_counts(&self) -> (usize, usize, usize, usize, usize) {
         self.tasks.values().fold(
             (0, 0, 0, 0, 0),
             |(pending, in_progress, completed, failed, skipped), task| match task.status {
                 TaskStatus::Pending => (pending + 1, in_progress, completed, failed, skipped),
-                TaskStatus::InProgress => (pending, in_progress + 1, completed, failed, skipped),
+                TaskStatus::InProgress | TaskStatus::AwaitingReview => {
+                    (pending, in_progress + 1, completed, failed, skipped)
+                }
                 TaskStatus::Completed => (pending, in_progress, completed + 1, failed, skipped),
                 TaskStatus::Failed => (pending, in_progress, completed, failed + 1, skipped),
                 TaskStatus::Skipped => (pending, in_progress, completed, failed, skipped + 1),
@@ -400,6 +672,30 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_session_tags_and_notes() {
+        let mut progress = SessionProgress::new();
+        progress.add_tags(&[
+            "payment-refactor".to_string(),
+            " ".to_string(),
+            "night2".to_string(),
+            "payment-refactor".to_string(),
+        ]);
+        progress.add_note("Left the webhook retries for tomorrow");
+
+        assert_eq!(progress.tags, vec!["payment-refactor", "night2"]);
+        assert_eq!(progress.notes.len(), 1);
+        assert_eq!(
+            progress.notes[0].text,
+            "Left the webhook retries for tomorrow"
+        );
+
+        // Empty tags and notes are omitted from the file
+        let json = serde_json::to_string(&SessionProgress::new()).unwrap();
+        assert!(!json.contains("tags"));
+        assert!(!json.contains("notes"));
+    }
+
     #[test]
     fn test_task_status_default() {
         let status = TaskStatus::default();
@@ -425,6 +721,10 @@ mod tests {
             serde_json::to_string(&TaskStatus::Skipped).unwrap(),
             r#""skipped""#
         );
+        assert_eq!(
+            serde_json::to_string(&TaskStatus::AwaitingReview).unwrap(),
+            r#""awaiting_review""#
+        );
         assert_eq!(
             serde_json::to_string(&TaskStatus::Pending).unwrap(),
             r#""pending""#
@@ -466,6 +766,15 @@ mod tests {
             commits: vec!["abc123".to_string()],
             message: Some("Working on it".to_string()),
             learnings: vec!["Learned something".to_string()],
+            attempts: 0,
+            claimed_by: None,
+            seconds_spent: 0,
+            pr_url: None,
+            models: Vec::new(),
+            violations: Vec::new(),
+            review_feedback: Vec::new(),
+            last_failure: None,
+            failure_streak: 0,
         };
 
         let json = serde_json::to_string_pretty(&task).unwrap();
@@ -611,6 +920,21 @@ mod tests {
         assert!(session.tasks.contains_key("task-001"));
     }
 
+    #[test]
+    fn test_session_progress_load_rejects_schema_violations() {
+        let temp = TempDir::new().unwrap();
+        let progress_path = temp.path().join("progress.json");
+        fs::write(
+            &progress_path,
+            r#"{"tasks": {"task-001": {"status": "done"}}}"#,
+        )
+        .unwrap();
+
+        let err = SessionProgress::load(Some(&progress_path)).unwrap_err();
+        assert!(matches!(err, ProgressError::Invalid(_)));
+        assert!(err.to_string().contains("$.tasks.task-001.status"));
+    }
+
     #[test]
     fn test_session_progress_save_creates_directory() {
         let temp = TempDir::new().unwrap();
@@ -634,7 +958,14 @@ mod tests {
             started_at: "2024-01-01T09:00:00".to_string(),
             iterations: 15,
             last_branch: None,
+            last_task: None,
+            last_task_commit: None,
             tasks: HashMap::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            tool_versions: BTreeMap::new(),
+            seconds_spent: 0,
+            cost_usd: None,
         };
         original.tasks.insert(
             "task-001".to_string(),
@@ -648,6 +979,15 @@ mod tests {
                 commits: vec!["abc123".to_string()],
                 message: Some("Done".to_string()),
                 learnings: vec!["Learning 1".to_string()],
+                attempts: 0,
+                claimed_by: None,
+                seconds_spent: 0,
+                pr_url: None,
+                models: Vec::new(),
+                violations: Vec::new(),
+                review_feedback: Vec::new(),
+                last_failure: None,
+                failure_streak: 0,
             },
         );
 
@@ -1056,6 +1396,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_record_failure_streak() {
+        let mut session = SessionProgress::new();
+        assert_eq!(
+            session.record_failure("auth", FailureKind::NoChanges, "json"),
+            1
+        );
+        assert_eq!(
+            session.record_failure("auth", FailureKind::NoChanges, "json"),
+            2
+        );
+        // A different kind starts a new streak
+        assert_eq!(
+            session.record_failure("auth", FailureKind::RateLimit, "json"),
+            1
+        );
+        session.clear_failure_streak("auth");
+        let task = session.get_task("auth").unwrap();
+        assert_eq!(task.failure_streak, 0);
+        assert_eq!(task.last_failure, Some(FailureKind::RateLimit));
+        assert_eq!(
+            serde_json::to_string(&FailureKind::GatesFailed).unwrap(),
+            r#""gates_failed""#
+        );
+    }
+
     #[test]
     fn test_get_task_counts() {
         let mut session = SessionProgress::new();
@@ -1106,6 +1472,36 @@ mod tests {
         assert_eq!(skipped, 1);
     }
 
+    #[test]
+    fn test_claim_task() {
+        let mut session = SessionProgress::new();
+        assert!(session.claim_task("auth", "json", "worker-1"));
+        assert!(session.claim_task("auth", "json", "worker-1"));
+        assert!(!session.claim_task("auth", "json", "worker-2"));
+        assert!(session.claim_task("docs", "json", "worker-2"));
+
+        session.release_claim("auth");
+        assert!(session.claim_task("auth", "json", "worker-2"));
+
+        session.clear_claims();
+        assert!(session.tasks.values().all(|t| t.claimed_by.is_none()));
+    }
+
+    #[test]
+    fn test_record_model() {
+        let mut session = SessionProgress::new();
+        session.record_model("auth", Some("opus"));
+        assert!(session.get_task("auth").is_none());
+
+        session.record_attempt("auth", "json");
+        session.record_model("auth", None);
+        session.record_model("auth", Some("opus"));
+        assert_eq!(
+            session.get_task("auth").unwrap().models,
+            ["default", "opus"]
+        );
+    }
+
     #[test]
     fn test_get_task_counts_empty() {
         let session = SessionProgress::new();
@@ -1197,7 +1593,14 @@ mod tests {
             started_at: "2024-01-01T10:00:00.000000".to_string(),
             iterations: 5,
             last_branch: None,
+            last_task: None,
+            last_task_commit: None,
             tasks: HashMap::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            tool_versions: BTreeMap::new(),
+            seconds_spent: 0,
+            cost_usd: None,
         };
         session.tasks.insert(
             "task-001".to_string(),
@@ -1211,6 +1614,15 @@ mod tests {
                 commits: vec!["abc123".to_string()],
                 message: None,
                 learnings: vec!["A learning".to_string()],
+                attempts: 0,
+                claimed_by: None,
+                seconds_spent: 0,
+                pr_url: None,
+                models: Vec::new(),
+                violations: Vec::new(),
+                review_feedback: Vec::new(),
+                last_failure: None,
+                failure_streak: 0,
             },
         );
 
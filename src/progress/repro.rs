@@ -0,0 +1,271 @@
+//! Per-iteration reproducibility metadata.
+//!
+//! Each iteration records what it ran with (seed, model, prompt and config
+//! hashes, afk and AI CLI versions, and the exact command) under
+//! `.afk/repro/`, alongside the prompt it sent. `afk repro <iteration>`
+//! reads this back to re-create the invocation.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::ProgressError;
+use crate::config::{AfkConfig, REPRO_DIR};
+
+/// Reproducibility metadata for one iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IterationRecord {
+    /// Session iteration number.
+    pub iteration: u32,
+    /// When the iteration started.
+    pub timestamp: String,
+    /// Task the prompt pointed at, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Seed used for per-iteration random choices (model rotation).
+    pub seed: u64,
+    /// Model passed to the AI CLI, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// afk version that ran the iteration.
+    pub afk_version: String,
+    /// Output of `<ai cli> --version`, if available.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cli_version: Option<String>,
+    /// Hash of the prompt text.
+    pub prompt_hash: String,
+    /// Hash of the effective config.
+    pub config_hash: String,
+    /// AI CLI command and arguments, without the prompt.
+    pub command: Vec<String>,
+    /// Commit HEAD was at when the iteration started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_commit: Option<String>,
+}
+
+impl IterationRecord {
+    /// Shell command that re-runs the iteration with its recorded prompt.
+    pub fn shell_command(&self) -> String {
+        let mut parts: Vec<String> = self.command.iter().map(|a| shell_quote(a)).collect();
+        parts.push(format!(
+            "\"$(cat {})\"",
+            shell_quote(&prompt_path(self.iteration).to_string_lossy())
+        ));
+        parts.join(" ")
+    }
+}
+
+/// Path of the metadata file for an iteration.
+pub fn record_path(iteration: u32) -> PathBuf {
+    Path::new(REPRO_DIR).join(format!("iteration-{iteration}.json"))
+}
+
+/// Path of the saved prompt for an iteration.
+pub fn prompt_path(iteration: u32) -> PathBuf {
+    Path::new(REPRO_DIR).join(format!("iteration-{iteration}.md"))
+}
+
+/// A fresh seed for an iteration's random choices.
+pub fn new_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    // Fold in the process id so parallel runs don't share seeds
+    (nanos as u64) ^ (u64::from(std::process::id()) << 32)
+}
+
+/// Stable 64-bit FNV-1a hash, as hex.
+///
+/// Unlike `DefaultHasher`, this is stable across Rust versions, so hashes
+/// recorded by one afk build can be compared by another.
+pub fn stable_hash(data: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in data {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+/// Hash of a config's serialised form.
+pub fn config_hash(config: &AfkConfig) -> String {
+    // Go through `Value` so map keys are sorted and the hash doesn't depend
+    // on `HashMap` iteration order
+    let canonical = serde_json::to_value(config)
+        .map(|v| v.to_string())
+        .unwrap_or_default();
+    stable_hash(canonical.as_bytes())
+}
+
+/// Version reported by the AI CLI, cached for the life of the process.
+fn cli_version(command: &str) -> Option<String> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            let output = Command::new(command).arg("--version").output().ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            let version = String::from_utf8_lossy(&output.stdout);
+            let version = version.lines().next()?.trim();
+            (!version.is_empty()).then(|| version.to_string())
+        })
+        .clone()
+}
+
+/// Build the record for an iteration about to run.
+pub fn build_record(
+    config: &AfkConfig,
+    iteration: u32,
+    task_id: Option<String>,
+    seed: u64,
+    model: Option<&str>,
+    command: &[String],
+    prompt: &str,
+) -> IterationRecord {
+    IterationRecord {
+        iteration,
+        timestamp: Utc::now().format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+        task_id,
+        seed,
+        model: model.map(ToOwned::to_owned),
+        afk_version: env!("CARGO_PKG_VERSION").to_string(),
+        cli_version: command.first().and_then(|c| cli_version(c)),
+        prompt_hash: stable_hash(prompt.as_bytes()),
+        config_hash: config_hash(config),
+        command: command.to_vec(),
+        base_commit: crate::git::get_current_commit(),
+    }
+}
+
+/// Save an iteration's record and prompt under `.afk/repro/`.
+pub fn save_record(record: &IterationRecord, prompt: &str) -> Result<(), ProgressError> {
+    if crate::config::is_read_only() {
+        return Err(ProgressError::ReadOnly);
+    }
+    fs::create_dir_all(REPRO_DIR)?;
+    fs::write(prompt_path(record.iteration), prompt)?;
+    fs::write(
+        record_path(record.iteration),
+        serde_json::to_string_pretty(record)?,
+    )?;
+    Ok(())
+}
+
+/// Load the record for an iteration, if one was saved.
+pub fn load_record(iteration: u32) -> Result<Option<IterationRecord>, ProgressError> {
+    let path = record_path(iteration);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(Some(serde_json::from_str(&contents)?))
+}
+
+/// Quote a string for POSIX shells when it contains special characters.
+fn shell_quote(s: &str) -> String {
+    let safe = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+    if safe {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record() -> IterationRecord {
+        IterationRecord {
+            iteration: 3,
+            timestamp: "2026-01-01T00:00:00.000000".to_string(),
+            task_id: Some("auth-login".to_string()),
+            seed: 42,
+            model: Some("opus".to_string()),
+            afk_version: "0.4.20".to_string(),
+            cli_version: None,
+            prompt_hash: stable_hash(b"prompt"),
+            config_hash: "0".to_string(),
+            command: vec![
+                "claude".to_string(),
+                "-p".to_string(),
+                "--model".to_string(),
+                "opus".to_string(),
+            ],
+            base_commit: None,
+        }
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // Published FNV-1a test vectors
+        assert_eq!(stable_hash(b""), "cbf29ce484222325");
+        assert_eq!(stable_hash(b"a"), "af63dc4c8601ec8c");
+        assert_ne!(stable_hash(b"prompt one"), stable_hash(b"prompt two"));
+    }
+
+    #[test]
+    fn test_config_hash_changes_with_config() {
+        let config = AfkConfig::default();
+        let mut changed = AfkConfig::default();
+        changed.limits.max_iterations = 7;
+        let mut reordered = changed.clone();
+        for i in 0..8 {
+            changed
+                .feedback_loops
+                .custom
+                .insert(format!("gate{i}"), "true".to_string());
+            reordered
+                .feedback_loops
+                .custom
+                .insert(format!("gate{}", 7 - i), "true".to_string());
+        }
+        assert_eq!(config_hash(&changed), config_hash(&reordered));
+        assert_eq!(config_hash(&config), config_hash(&AfkConfig::default()));
+        assert_ne!(config_hash(&config), config_hash(&changed));
+    }
+
+    #[test]
+    fn test_shell_command() {
+        let mut record = sample_record();
+        record.command.push("--append-system-prompt".to_string());
+        record.command.push("it's fine".to_string());
+        assert_eq!(
+            record.shell_command(),
+            "claude -p --model opus --append-system-prompt 'it'\\''s fine' \
+             \"$(cat .afk/repro/iteration-3.md)\""
+        );
+    }
+
+    #[test]
+    fn test_record_roundtrip() {
+        let record = sample_record();
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(!json.contains("cli_version"));
+        let parsed: IterationRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, record);
+    }
+
+    #[test]
+    fn test_build_record() {
+        let config = AfkConfig::default();
+        let command = vec!["nonexistent-ai-cli-xyz".to_string(), "-p".to_string()];
+        let record = build_record(&config, 5, None, 9, None, &command, "Do the thing");
+
+        assert_eq!(record.iteration, 5);
+        assert_eq!(record.seed, 9);
+        assert_eq!(record.afk_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(record.prompt_hash, stable_hash(b"Do the thing"));
+        assert_eq!(record.config_hash, config_hash(&config));
+        assert_eq!(record.command, command);
+    }
+}
@@ -0,0 +1,303 @@
+//! Task selection and exclusion reasons.
+//!
+//! Each time the loop picks a task it evaluates every pending story and
+//! records why the ones it passed over were excluded (dependencies not
+//! done, failure budget used up, skipped this session, awaiting review,
+//! claimed by another worker) in `.afk/selection.json`. `afk explain --task <id>` reads that
+//! record, so nobody has to guess why a story keeps being ignored.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config::SELECTION_FILE;
+use crate::prd::{PrdDocument, TaskOrder, UserStory};
+
+use super::{SessionProgress, TaskStatus};
+
+/// Why a pending task can't be picked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Exclusion {
+    /// Stories the task depends on haven't passed.
+    DependencyUnmet {
+        /// IDs of the unfinished dependencies.
+        waiting_on: Vec<String>,
+    },
+    /// The task has failed `limits.max_task_failures` times.
+    FailureBudget {
+        /// Failures recorded this session.
+        failures: u32,
+        /// The configured limit.
+        max: u32,
+    },
+    /// The session skipped the task.
+    Skipped {
+        /// Why it was skipped, if recorded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// The task was marked passed and is waiting for `afk review`.
+    AwaitingReview,
+    /// A parallel worker holds the task.
+    Claimed {
+        /// Name of the worker.
+        worker: String,
+    },
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DependencyUnmet { waiting_on } => {
+                write!(f, "waiting on {}", waiting_on.join(", "))
+            }
+            Self::FailureBudget { failures, max } => write!(
+                f,
+                "failed {failures} time(s), limits.max_task_failures is {max}"
+            ),
+            Self::Skipped { message: Some(m) } => write!(f, "skipped this session: {m}"),
+            Self::Skipped { message: None } => write!(f, "skipped this session"),
+            Self::AwaitingReview => write!(f, "awaiting review (afk review)"),
+            Self::Claimed { worker } => write!(f, "claimed by {worker}"),
+        }
+    }
+}
+
+/// Reasons a pending story can't be picked; empty if it can be.
+///
+/// Dependencies that aren't in the backlog are ignored. Claims are not
+/// considered here (see [`Selection::exclude_claimed`]).
+pub fn exclusions(
+    story: &UserStory,
+    prd: &PrdDocument,
+    progress: &SessionProgress,
+    max_task_failures: u32,
+) -> Vec<Exclusion> {
+    let mut reasons = Vec::new();
+
+    let waiting_on: Vec<String> = story
+        .depends_on
+        .iter()
+        .filter(|dep| prd.get_story(dep).is_some_and(|d| !d.passes))
+        .cloned()
+        .collect();
+    if !waiting_on.is_empty() {
+        reasons.push(Exclusion::DependencyUnmet { waiting_on });
+    }
+
+    if let Some(task) = progress.get_task(&story.id) {
+        if task.status == TaskStatus::Skipped {
+            reasons.push(Exclusion::Skipped {
+                message: task.message.clone(),
+            });
+        } else if task.status == TaskStatus::AwaitingReview {
+            reasons.push(Exclusion::AwaitingReview);
+        } else if max_task_failures > 0 && task.failure_count >= max_task_failures {
+            reasons.push(Exclusion::FailureBudget {
+                failures: task.failure_count,
+                max: max_task_failures,
+            });
+        }
+    }
+    reasons
+}
+
+/// The outcome of one round of task selection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    /// When the selection ran.
+    pub evaluated_at: String,
+    /// Pickable task IDs, in the order they'd be picked.
+    pub eligible: Vec<String>,
+    /// Pending tasks that were passed over, with the reasons.
+    #[serde(default)]
+    pub excluded: BTreeMap<String, Vec<Exclusion>>,
+}
+
+impl Selection {
+    /// Sort pending stories, in `order`, into pickable and excluded.
+    pub fn evaluate(
+        prd: &PrdDocument,
+        progress: &SessionProgress,
+        order: &TaskOrder,
+        max_task_failures: u32,
+    ) -> Self {
+        let mut selection = Self {
+            evaluated_at: Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+            ..Default::default()
+        };
+        for story in prd.get_pending_stories(order) {
+            let reasons = exclusions(story, prd, progress, max_task_failures);
+            if reasons.is_empty() {
+                selection.eligible.push(story.id.clone());
+            } else {
+                selection.excluded.insert(story.id.clone(), reasons);
+            }
+        }
+        selection
+    }
+
+    /// Exclude eligible tasks a parallel worker already holds.
+    pub fn exclude_claimed(&mut self, progress: &SessionProgress) {
+        let excluded = &mut self.excluded;
+        self.eligible.retain(|id| {
+            let Some(worker) = progress.get_task(id).and_then(|t| t.claimed_by.clone()) else {
+                return true;
+            };
+            excluded
+                .entry(id.clone())
+                .or_default()
+                .push(Exclusion::Claimed { worker });
+            false
+        });
+    }
+
+    /// The task that would be picked.
+    pub fn picked(&self) -> Option<&str> {
+        self.eligible.first().map(String::as_str)
+    }
+
+    /// The eligible stories, in pick order.
+    pub fn stories<'a>(&self, prd: &'a PrdDocument) -> Vec<&'a UserStory> {
+        self.eligible
+            .iter()
+            .filter_map(|id| prd.get_story(id))
+            .collect()
+    }
+
+    /// Load the last recorded selection.
+    pub fn load(path: Option<&Path>) -> Option<Self> {
+        let path = path.unwrap_or(Path::new(SELECTION_FILE));
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+    }
+
+    /// Record the selection. Failures are ignored; recording must never
+    /// interrupt the loop.
+    pub fn save(&self, path: Option<&Path>) {
+        if crate::config::is_read_only() {
+            return;
+        }
+        let path = path.unwrap_or(Path::new(SELECTION_FILE));
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(path, json);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::TaskProgress;
+    use tempfile::TempDir;
+
+    fn story(id: &str, priority: i32, passes: bool, depends_on: &[&str]) -> UserStory {
+        UserStory {
+            priority,
+            passes,
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            ..UserStory::new(id, id)
+        }
+    }
+
+    fn backlog() -> PrdDocument {
+        PrdDocument {
+            user_stories: vec![
+                story("done", 1, true, &[]),
+                story("a", 1, false, &[]),
+                story("b", 2, false, &["a", "done", "elsewhere"]),
+                story("c", 3, false, &[]),
+                story("d", 4, false, &[]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_records_exclusions() {
+        let mut progress = SessionProgress::default();
+        let mut failing = TaskProgress::new("c", "json");
+        failing.failure_count = 3;
+        progress.tasks.insert("c".to_string(), failing);
+        progress.set_task_status("d", TaskStatus::Skipped, "json", Some("Too big".into()));
+
+        let selection = Selection::evaluate(&backlog(), &progress, &TaskOrder::default(), 3);
+        assert_eq!(selection.eligible, vec!["a"]);
+
+        // Awaiting review holds a task back like skipping it
+        let mut reviewing = progress.clone();
+        reviewing.set_task_status("a", TaskStatus::AwaitingReview, "json", None);
+        let held = Selection::evaluate(&backlog(), &reviewing, &TaskOrder::default(), 3);
+        assert!(held.eligible.is_empty());
+        assert_eq!(held.excluded["a"], vec![Exclusion::AwaitingReview]);
+        assert_eq!(selection.eligible, vec!["a"]);
+        assert_eq!(selection.picked(), Some("a"));
+        assert_eq!(
+            selection.excluded["b"],
+            vec![Exclusion::DependencyUnmet {
+                waiting_on: vec!["a".to_string()]
+            }]
+        );
+        assert_eq!(
+            selection.excluded["c"][0].to_string(),
+            "failed 3 time(s), limits.max_task_failures is 3"
+        );
+        assert_eq!(
+            selection.excluded["d"][0].to_string(),
+            "skipped this session: Too big"
+        );
+
+        // A limit of 0 disables the failure budget
+        let selection = Selection::evaluate(&backlog(), &progress, &TaskOrder::default(), 0);
+        assert_eq!(selection.eligible, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_exclude_claimed() {
+        let mut progress = SessionProgress::default();
+        progress.claim_task("a", "json", "worker-2");
+        let mut selection = Selection::evaluate(&backlog(), &progress, &TaskOrder::default(), 50);
+        selection.exclude_claimed(&progress);
+        assert_eq!(selection.eligible, vec!["c", "d"]);
+        assert_eq!(
+            selection.excluded["a"],
+            vec![Exclusion::Claimed {
+                worker: "worker-2".to_string()
+            }]
+        );
+        let prd = backlog();
+        let ids: Vec<&str> = selection
+            .stories(&prd)
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["c", "d"]);
+    }
+
+    #[test]
+    fn test_selection_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("selection.json");
+        assert!(Selection::load(Some(&path)).is_none());
+
+        let selection = Selection::evaluate(
+            &backlog(),
+            &SessionProgress::default(),
+            &TaskOrder::default(),
+            50,
+        );
+        selection.save(Some(&path));
+        assert_eq!(Selection::load(Some(&path)), Some(selection));
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"reason\": \"dependency_unmet\""));
+    }
+}
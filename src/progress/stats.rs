@@ -0,0 +1,385 @@
+//! Analytics across sessions.
+//!
+//! `afk stats` reads every archived session, the current one and the event
+//! log, and reports how the loop has done over time: how often tasks from
+//! each kind of source get finished, how many iterations a finished task
+//! takes, why tasks fail, and what the runs cost day by day.
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::events::{Event, EventRecord};
+use crate::prd::dedup::source_kind;
+use crate::progress::{SessionSnapshot, TaskStatus};
+
+/// Failure reasons longer than this are cut short, so near-identical
+/// messages group together.
+const REASON_WIDTH: usize = 80;
+
+/// Recorded for failed or skipped tasks without a message.
+const NO_REASON: &str = "(no reason recorded)";
+
+/// How tasks from one kind of source fared.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SourceStats {
+    /// Kind of source, e.g. "github" or "markdown".
+    pub source: String,
+    /// Tasks the loop worked on.
+    pub attempted: usize,
+    /// Of those, tasks completed.
+    pub completed: usize,
+    /// Of those, tasks that failed.
+    pub failed: usize,
+    /// Of those, tasks skipped.
+    pub skipped: usize,
+}
+
+impl SourceStats {
+    /// Share of attempted tasks completed, from 0 to 1.
+    pub fn success_rate(&self) -> f64 {
+        if self.attempted == 0 {
+            0.0
+        } else {
+            self.completed as f64 / self.attempted as f64
+        }
+    }
+}
+
+/// Why tasks failed or were skipped, and how often.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailureReason {
+    /// The task's message, first line only.
+    pub reason: String,
+    /// Tasks that ended with it.
+    pub count: usize,
+}
+
+/// What one day's iterations used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DailyUsage {
+    /// Local date, "YYYY-MM-DD".
+    pub date: String,
+    /// Iterations that finished.
+    pub iterations: u32,
+    /// Input tokens.
+    pub input_tokens: u64,
+    /// Output tokens.
+    pub output_tokens: u64,
+    /// Cost in USD, summed over iterations that reported one.
+    pub cost_usd: Option<f64>,
+}
+
+/// Metrics across sessions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// Sessions looked at.
+    pub sessions: usize,
+    /// Iterations they ran.
+    pub iterations: u32,
+    /// Per kind of source, most attempted first.
+    pub sources: Vec<SourceStats>,
+    /// Average iterations a completed task took, if any were completed.
+    pub iterations_per_task: Option<f64>,
+    /// Failure reasons, most common first.
+    pub failures: Vec<FailureReason>,
+    /// Usage per day from the event log, oldest first.
+    pub usage: Vec<DailyUsage>,
+}
+
+impl Stats {
+    /// Collect metrics from sessions and event records, leaving out
+    /// sessions started and events recorded before `since`.
+    pub fn collect(
+        sessions: &[SessionSnapshot],
+        events: &[EventRecord],
+        since: Option<NaiveDate>,
+    ) -> Self {
+        let included = |timestamp: &str| since.map_or(true, |date| on_or_after(timestamp, date));
+        let mut stats = Self::default();
+        let mut sources: BTreeMap<&str, SourceStats> = BTreeMap::new();
+        let mut reasons: BTreeMap<String, usize> = BTreeMap::new();
+        let (mut completed_tasks, mut completed_attempts) = (0u32, 0u32);
+
+        for session in sessions.iter().filter(|s| included(&s.progress.started_at)) {
+            stats.sessions += 1;
+            stats.iterations += session.progress.iterations;
+            for task in session.progress.tasks.values().filter(|t| t.attempts > 0) {
+                let kind = source_kind(&task.source);
+                let entry = sources.entry(kind).or_insert_with(|| SourceStats {
+                    source: kind.to_string(),
+                    ..Default::default()
+                });
+                entry.attempted += 1;
+                match task.status {
+                    TaskStatus::Completed => {
+                        entry.completed += 1;
+                        completed_tasks += 1;
+                        completed_attempts += task.attempts;
+                    }
+                    TaskStatus::Failed => entry.failed += 1,
+                    TaskStatus::Skipped => entry.skipped += 1,
+                    _ => continue,
+                }
+                if matches!(task.status, TaskStatus::Failed | TaskStatus::Skipped) {
+                    *reasons.entry(reason(task.message.as_deref())).or_default() += 1;
+                }
+            }
+        }
+
+        stats.sources = sources.into_values().collect();
+        stats
+            .sources
+            .sort_by(|a, b| b.attempted.cmp(&a.attempted).then(a.source.cmp(&b.source)));
+        stats.iterations_per_task = (completed_tasks > 0)
+            .then(|| f64::from(completed_attempts) / f64::from(completed_tasks));
+        stats.failures = reasons
+            .into_iter()
+            .map(|(reason, count)| FailureReason { reason, count })
+            .collect();
+        stats.failures.sort_by_key(|f| std::cmp::Reverse(f.count));
+        stats.usage = daily_usage(events.iter().filter(|r| included(&r.timestamp)));
+        stats
+    }
+
+    /// Tasks attempted and completed across every source.
+    pub fn totals(&self) -> (usize, usize) {
+        self.sources
+            .iter()
+            .fold((0, 0), |(a, c), s| (a + s.attempted, c + s.completed))
+    }
+
+    /// Cost in USD across every day that reported one.
+    pub fn total_cost(&self) -> Option<f64> {
+        self.usage
+            .iter()
+            .filter_map(|day| day.cost_usd)
+            .reduce(|a, b| a + b)
+    }
+}
+
+/// Whether an ISO timestamp falls on or after a date.
+fn on_or_after(timestamp: &str, date: NaiveDate) -> bool {
+    // ISO dates compare as strings
+    timestamp.get(..10).unwrap_or(timestamp) >= date.to_string().as_str()
+}
+
+/// A failure message, grouped on its first line.
+fn reason(message: Option<&str>) -> String {
+    let line = message.and_then(|m| m.lines().next()).map(str::trim);
+    match line {
+        Some(line) if !line.is_empty() => {
+            if line.chars().count() > REASON_WIDTH {
+                let cut: String = line.chars().take(REASON_WIDTH - 1).collect();
+                format!("{cut}…")
+            } else {
+                line.to_string()
+            }
+        }
+        _ => NO_REASON.to_string(),
+    }
+}
+
+/// Iterations and token usage per day.
+fn daily_usage<'a>(records: impl Iterator<Item = &'a EventRecord>) -> Vec<DailyUsage> {
+    let mut days: BTreeMap<&str, DailyUsage> = BTreeMap::new();
+    for record in records {
+        let date = record.timestamp.get(..10).unwrap_or(&record.timestamp);
+        let day = || DailyUsage {
+            date: date.to_string(),
+            ..Default::default()
+        };
+        match &record.event {
+            Event::IterationEnd { .. } => days.entry(date).or_insert_with(day).iterations += 1,
+            Event::TokenUsage {
+                input_tokens,
+                output_tokens,
+                cost_usd,
+                ..
+            } => {
+                let entry = days.entry(date).or_insert_with(day);
+                entry.input_tokens += input_tokens;
+                entry.output_tokens += output_tokens;
+                if let Some(cost) = cost_usd {
+                    entry.cost_usd = Some(entry.cost_usd.unwrap_or(0.0) + cost);
+                }
+            }
+            _ => {}
+        }
+    }
+    days.into_values().collect()
+}
+
+/// Parse `--since`: a date ("2026-01-31") or a span back from `today`
+/// ("30d", "4w").
+pub fn parse_since(s: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date);
+    }
+    let span = |digits: &str, days: i64| {
+        digits
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| today.checked_sub_signed(Duration::days(n * days)))
+    };
+    let date = if let Some(days) = s.strip_suffix('d') {
+        span(days, 1)
+    } else if let Some(weeks) = s.strip_suffix('w') {
+        span(weeks, 7)
+    } else {
+        None
+    };
+    date.ok_or_else(|| format!("invalid --since '{s}' (use e.g. 2026-01-31, 30d or 4w)"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::PrdDocument;
+    use crate::progress::SessionProgress;
+
+    fn session(
+        started_at: &str,
+        tasks: &[(&str, &str, TaskStatus, u32, Option<&str>)],
+    ) -> SessionSnapshot {
+        let mut progress = SessionProgress::new();
+        progress.started_at = started_at.to_string();
+        for (id, source, status, attempts, message) in tasks {
+            progress.set_task_status(id, *status, source, message.map(String::from));
+            let task = progress.get_task_mut(id).unwrap();
+            task.attempts = *attempts;
+            progress.iterations += attempts;
+        }
+        SessionSnapshot {
+            name: started_at.to_string(),
+            progress,
+            prd: PrdDocument::default(),
+        }
+    }
+
+    fn record(timestamp: &str, event: Event) -> EventRecord {
+        EventRecord {
+            timestamp: timestamp.to_string(),
+            event,
+        }
+    }
+
+    fn usage(cost_usd: Option<f64>) -> Event {
+        Event::TokenUsage {
+            iteration: 1,
+            input_tokens: 1000,
+            output_tokens: 100,
+            cost_usd,
+        }
+    }
+
+    #[test]
+    fn test_collect_stats() {
+        use TaskStatus::*;
+        const BUDGET: &str = "Skipped after using its 30-minute budget";
+        let budget = Some(BUDGET);
+        let sessions = vec![
+            session(
+                "2026-01-05T10:00:00.000000",
+                &[("old", "beads", Failed, 4, Some("Tests fail"))],
+            ),
+            session(
+                "2026-02-01T10:00:00.000000",
+                &[
+                    ("a", "github:acme/api#1", Completed, 1, None),
+                    ("b", "github:acme/api#2", Completed, 3, None),
+                    ("c", "github:acme/api#3", Skipped, 2, budget),
+                    ("d", "markdown:TODO.md", Failed, 1, None),
+                    ("e", "markdown:TODO.md", InProgress, 1, None),
+                    ("f", "markdown:TODO.md", Completed, 0, None),
+                ],
+            ),
+            session(
+                "2026-02-03T10:00:00.000000",
+                &[("g", "markdown:TODO.md", Skipped, 2, budget)],
+            ),
+        ];
+        let events = vec![
+            record("2026-01-05T10:05:00.000000", usage(Some(1.0))),
+            record(
+                "2026-02-01T10:05:00.000000",
+                Event::IterationEnd {
+                    iteration: 1,
+                    task_id: None,
+                    success: true,
+                    duration_seconds: 60.0,
+                    error: None,
+                },
+            ),
+            record("2026-02-01T10:05:01.000000", usage(Some(0.5))),
+            record("2026-02-01T11:05:01.000000", usage(None)),
+            record("2026-02-03T10:05:01.000000", usage(Some(0.25))),
+        ];
+
+        let stats = Stats::collect(&sessions, &events, NaiveDate::from_ymd_opt(2026, 2, 1));
+        assert_eq!(stats.sessions, 2);
+        assert_eq!(stats.iterations, 10);
+        let sources: Vec<(&str, usize, usize)> = stats
+            .sources
+            .iter()
+            .map(|s| (s.source.as_str(), s.attempted, s.completed))
+            .collect();
+        // Tasks the loop never ran (f) don't count
+        assert_eq!(sources, [("github", 3, 2), ("markdown", 3, 0)]);
+        assert!((stats.sources[0].success_rate() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.totals(), (6, 2));
+        assert_eq!(stats.iterations_per_task, Some(2.0));
+        assert_eq!(
+            stats.failures,
+            [
+                FailureReason {
+                    reason: BUDGET.to_string(),
+                    count: 2
+                },
+                FailureReason {
+                    reason: NO_REASON.to_string(),
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(stats.usage.len(), 2);
+        assert_eq!(stats.usage[0].date, "2026-02-01");
+        assert_eq!(stats.usage[0].iterations, 1);
+        assert_eq!(stats.usage[0].input_tokens, 2000);
+        assert_eq!(stats.usage[0].cost_usd, Some(0.5));
+        assert_eq!(stats.total_cost(), Some(0.75));
+
+        let all = Stats::collect(&sessions, &events, None);
+        assert_eq!(all.sessions, 3);
+        assert_eq!(all.total_cost(), Some(1.75));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 10).unwrap();
+        assert_eq!(
+            parse_since("2026-01-31", today),
+            Ok(NaiveDate::from_ymd_opt(2026, 1, 31).unwrap())
+        );
+        assert_eq!(
+            parse_since("30d", today),
+            Ok(NaiveDate::from_ymd_opt(2026, 2, 8).unwrap())
+        );
+        assert_eq!(
+            parse_since("2w", today),
+            Ok(NaiveDate::from_ymd_opt(2026, 2, 24).unwrap())
+        );
+        assert!(parse_since("yesterday", today).is_err());
+        assert!(parse_since("d", today).is_err());
+    }
+
+    #[test]
+    fn test_reason() {
+        assert_eq!(reason(Some("Tests fail\nmore detail")), "Tests fail");
+        assert_eq!(reason(Some("  ")), NO_REASON);
+        assert_eq!(reason(None), NO_REASON);
+        assert_eq!(reason(Some(&"x".repeat(100))).chars().count(), REASON_WIDTH);
+    }
+}
@@ -0,0 +1,472 @@
+//! Per-iteration transcripts of the AI CLI's output.
+//!
+//! While an iteration runs, everything the AI CLI prints is appended to
+//! `<feedback.log_dir>/iteration-<n>.log`: raw stdout lines, its stderr and a
+//! one-line summary of each parsed stream event, each tagged and
+//! timestamped. The transcript outlives the TUI's scrollback, and
+//! `afk logs <n>` reads it back. A transcript that grows past
+//! `feedback.log_max_kb` is rotated to `iteration-<n>.log.1`, and only the
+//! newest `feedback.log_keep` iterations are kept.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+use crate::config::{FeedbackConfig, LOGS_DIR};
+use crate::parser::{StreamEvent, TokenUsage};
+
+/// Tag on raw stdout lines.
+pub const TAG_OUT: &str = "out";
+/// Tag on stderr lines.
+pub const TAG_ERR: &str = "err";
+/// Tag on parsed stream events.
+pub const TAG_EVENT: &str = "event";
+/// Tag on afk's own notes, such as stall retries.
+pub const TAG_AFK: &str = "afk";
+
+/// Directory transcripts are written to: `feedback.log_dir`, or the
+/// default when transcripts are off (earlier ones may still be there).
+pub fn transcript_dir(config: &FeedbackConfig) -> PathBuf {
+    if config.log_dir.trim().is_empty() {
+        PathBuf::from(LOGS_DIR)
+    } else {
+        PathBuf::from(&config.log_dir)
+    }
+}
+
+/// An iteration's whole transcript, rotated part first, if it was kept.
+pub fn read_transcript(dir: &Path, iteration: u32) -> io::Result<Option<String>> {
+    let path = log_path(dir, iteration);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut contents = fs::read_to_string(rotated_path(dir, iteration)).unwrap_or_default();
+    contents.push_str(&fs::read_to_string(path)?);
+    Ok(Some(contents))
+}
+
+/// The tag of a transcript line, e.g. "event", or None for header lines.
+pub fn line_tag(line: &str) -> Option<&str> {
+    let (_, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    Some(rest.split_once(" | ")?.0.trim_end())
+}
+
+/// Path of an iteration's transcript in `dir`.
+pub fn log_path(dir: &Path, iteration: u32) -> PathBuf {
+    dir.join(format!("iteration-{iteration}.log"))
+}
+
+/// Path an iteration's transcript is rotated to.
+pub fn rotated_path(dir: &Path, iteration: u32) -> PathBuf {
+    dir.join(format!("iteration-{iteration}.log.1"))
+}
+
+/// Iterations with a transcript in `dir`, oldest first.
+pub fn list_logs(dir: &Path) -> io::Result<Vec<u32>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut iterations: Vec<u32> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            e.file_name()
+                .to_str()?
+                .strip_prefix("iteration-")?
+                .strip_suffix(".log")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    iterations.sort_unstable();
+    Ok(iterations)
+}
+
+/// The transcript of the iteration being run.
+///
+/// Writes are best effort: if the file can't be written, logging stops and
+/// the iteration carries on.
+#[derive(Debug)]
+pub struct IterationLog {
+    file: Option<File>,
+    dir: PathBuf,
+    iteration: u32,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl IterationLog {
+    /// Start the transcript for an iteration, replacing any earlier one with
+    /// the same number, and prune old transcripts.
+    ///
+    /// Logs nothing when `feedback.log_dir` is empty or afk is read-only.
+    pub fn start(config: &FeedbackConfig, iteration: u32, command: &[String]) -> Self {
+        if config.log_dir.trim().is_empty() || crate::config::is_read_only() {
+            return Self::disabled();
+        }
+        Self::start_in(
+            Path::new(&config.log_dir),
+            iteration,
+            command,
+            config.log_keep,
+            u64::from(config.log_max_kb) * 1024,
+        )
+    }
+
+    /// A transcript that discards everything.
+    pub fn disabled() -> Self {
+        Self {
+            file: None,
+            dir: PathBuf::new(),
+            iteration: 0,
+            written: 0,
+            max_bytes: 0,
+        }
+    }
+
+    fn start_in(dir: &Path, iteration: u32, command: &[String], keep: u32, max_bytes: u64) -> Self {
+        let mut log = Self {
+            file: None,
+            dir: dir.to_path_buf(),
+            iteration,
+            written: 0,
+            max_bytes,
+        };
+        if fs::create_dir_all(dir).is_err() {
+            return log;
+        }
+        let _ = fs::remove_file(rotated_path(dir, iteration));
+        log.file = File::create(log_path(dir, iteration)).ok();
+        let _ = prune(dir, keep);
+        log.write_raw(&format!(
+            "# afk iteration {iteration}\n# started: {}\n# command: {}\n\n",
+            Local::now().format("%Y-%m-%d %H:%M:%S"),
+            command.join(" ")
+        ));
+        log
+    }
+
+    /// Whether anything is being written.
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Log a raw line of the AI CLI's stdout.
+    pub fn raw(&mut self, line: &str) {
+        self.line(TAG_OUT, line);
+    }
+
+    /// Log the AI CLI's stderr.
+    pub fn stderr(&mut self, text: &str) {
+        for line in text.trim_end().lines() {
+            self.line(TAG_ERR, line);
+        }
+    }
+
+    /// Log a summary of a parsed stream event.
+    pub fn event(&mut self, event: &StreamEvent) {
+        if let Some(summary) = describe(event) {
+            self.line(TAG_EVENT, &summary);
+        }
+    }
+
+    /// Log a note from afk itself.
+    pub fn note(&mut self, text: &str) {
+        self.line(TAG_AFK, text);
+    }
+
+    fn line(&mut self, tag: &str, text: &str) {
+        if self.file.is_none() {
+            return;
+        }
+        let time = Local::now().format("%H:%M:%S");
+        self.write_raw(&format!("[{time}] {tag:<5} | {text}\n"));
+    }
+
+    fn write_raw(&mut self, text: &str) {
+        if self.max_bytes > 0
+            && self.written > 0
+            && self.written + text.len() as u64 > self.max_bytes
+        {
+            self.rotate();
+        }
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        if file.write_all(text.as_bytes()).is_err() {
+            self.file = None;
+            return;
+        }
+        self.written += text.len() as u64;
+    }
+
+    /// Move the full transcript aside and start a fresh one.
+    fn rotate(&mut self) {
+        self.file = None;
+        let path = log_path(&self.dir, self.iteration);
+        if fs::rename(&path, rotated_path(&self.dir, self.iteration)).is_ok() {
+            self.file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&path)
+                .ok();
+        }
+        self.written = 0;
+    }
+}
+
+/// One-line summary of a stream event, or None for events not worth
+/// logging (the echoed prompt and unrecognised events).
+pub fn describe(event: &StreamEvent) -> Option<String> {
+    Some(match event {
+        StreamEvent::SystemInit { model, session_id } => format!(
+            "session started (model: {}, id: {})",
+            model.as_deref().unwrap_or("default"),
+            session_id.as_deref().unwrap_or("-")
+        ),
+        StreamEvent::AssistantMessage { text } => format!("assistant: {}", text.trim()),
+        StreamEvent::ToolStarted {
+            tool_name,
+            path,
+            command,
+            ..
+        } => match (path, command) {
+            (Some(path), _) => format!("tool {tool_name} started: {path}"),
+            (None, Some(command)) => format!("tool {tool_name} started: $ {command}"),
+            (None, None) => format!("tool {tool_name} started"),
+        },
+        StreamEvent::ToolCompleted {
+            tool_name,
+            path,
+            command,
+            success,
+            exit_code,
+            ..
+        } => {
+            let status = if *success { "completed" } else { "failed" };
+            let mut summary = match (path, command) {
+                (Some(path), _) => format!("tool {tool_name} {status}: {path}"),
+                (None, Some(command)) => format!("tool {tool_name} {status}: $ {command}"),
+                (None, None) => format!("tool {tool_name} {status}"),
+            };
+            if let Some(code) = exit_code {
+                summary.push_str(&format!(" (exit {code})"));
+            }
+            summary
+        }
+        StreamEvent::Result {
+            success,
+            duration_ms,
+            result_text,
+            usage,
+        } => {
+            let mut summary = format!("result: {}", if *success { "success" } else { "failure" });
+            if let Some(ms) = duration_ms {
+                summary.push_str(&format!(" in {:.1}s", *ms as f64 / 1000.0));
+            }
+            if let Some(usage) = usage {
+                summary.push_str(&format!(", {}", describe_usage(usage)));
+            }
+            if let Some(text) = result_text {
+                summary.push_str(&format!(" - {}", text.trim()));
+            }
+            summary
+        }
+        StreamEvent::Usage(usage) => format!("usage: {}", describe_usage(usage)),
+        StreamEvent::Error { message } => format!("error: {message}"),
+        StreamEvent::UserMessage { .. } | StreamEvent::Unknown { .. } => return None,
+    })
+}
+
+fn describe_usage(usage: &TokenUsage) -> String {
+    let mut text = format!(
+        "{} in / {} out tokens",
+        usage.input_tokens, usage.output_tokens
+    );
+    if let Some(cost) = usage.cost_usd {
+        text.push_str(&format!(" (${cost:.4})"));
+    }
+    text
+}
+
+/// Remove all but the newest `keep` transcripts. 0 keeps all.
+fn prune(dir: &Path, keep: u32) -> io::Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let iterations = list_logs(dir)?;
+    let excess = iterations.len().saturating_sub(keep as usize);
+    for iteration in &iterations[..excess] {
+        fs::remove_file(log_path(dir, *iteration))?;
+        let _ = fs::remove_file(rotated_path(dir, *iteration));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ToolType;
+    use tempfile::TempDir;
+
+    fn command() -> Vec<String> {
+        vec!["claude".to_string(), "-p".to_string()]
+    }
+
+    #[test]
+    fn test_log_writes_tagged_lines() {
+        let temp = TempDir::new().unwrap();
+        let mut log = IterationLog::start_in(temp.path(), 3, &command(), 0, 0);
+        assert!(log.is_enabled());
+        log.raw("{\"type\":\"assistant\"}");
+        log.event(&StreamEvent::ToolCompleted {
+            tool_name: "Write".to_string(),
+            tool_type: ToolType::Write,
+            path: Some("src/lib.rs".to_string()),
+            command: None,
+            success: true,
+            exit_code: None,
+            lines: None,
+            file_size: None,
+        });
+        log.event(&StreamEvent::ToolCompleted {
+            tool_name: "Bash".to_string(),
+            tool_type: ToolType::Command,
+            path: None,
+            command: Some("cargo test".to_string()),
+            success: false,
+            exit_code: Some(101),
+            lines: None,
+            file_size: None,
+        });
+        log.event(&StreamEvent::UserMessage {
+            text: "prompt".to_string(),
+        });
+        log.stderr("warning: slow\nretrying\n");
+        log.note("attempt 2");
+
+        let contents = fs::read_to_string(log_path(temp.path(), 3)).unwrap();
+        assert!(contents.starts_with("# afk iteration 3\n"));
+        assert!(contents.contains("# command: claude -p\n"));
+        let lines: Vec<&str> = contents
+            .lines()
+            .filter(|l| l.starts_with('['))
+            .map(|l| &l[11..])
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "out   | {\"type\":\"assistant\"}",
+                "event | tool Write completed: src/lib.rs",
+                "event | tool Bash failed: $ cargo test (exit 101)",
+                "err   | warning: slow",
+                "err   | retrying",
+                "afk   | attempt 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_rotates_when_full() {
+        let temp = TempDir::new().unwrap();
+        let mut log = IterationLog::start_in(temp.path(), 1, &command(), 0, 200);
+        for i in 0..10 {
+            log.raw(&format!("line {i} {}", "x".repeat(20)));
+        }
+        let current = fs::read_to_string(log_path(temp.path(), 1)).unwrap();
+        let rotated = fs::read_to_string(rotated_path(temp.path(), 1)).unwrap();
+        assert!(current.len() <= 200);
+        assert!(current.contains("line 9"));
+        // Only the last full file is kept; the header went with the first
+        assert!(!rotated.starts_with("# afk"));
+        assert!(rotated.contains("line 5"));
+        assert!(!current.contains("line 5"));
+
+        // A new run of the iteration starts from scratch
+        IterationLog::start_in(temp.path(), 1, &command(), 0, 200);
+        assert!(!rotated_path(temp.path(), 1).exists());
+    }
+
+    #[test]
+    fn test_start_prunes_old_logs() {
+        let temp = TempDir::new().unwrap();
+        for iteration in [1, 2, 10] {
+            IterationLog::start_in(temp.path(), iteration, &command(), 2, 0);
+        }
+        fs::write(temp.path().join("notes.txt"), "").unwrap();
+        assert_eq!(list_logs(temp.path()).unwrap(), [2, 10]);
+        assert!(list_logs(&temp.path().join("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_log_writes_nothing() {
+        let mut log = IterationLog::disabled();
+        log.raw("ignored");
+        assert!(!log.is_enabled());
+
+        let config = FeedbackConfig {
+            log_dir: String::new(),
+            ..Default::default()
+        };
+        assert!(!IterationLog::start(&config, 1, &command()).is_enabled());
+    }
+
+    #[test]
+    fn test_read_transcript_and_line_tag() {
+        let temp = TempDir::new().unwrap();
+        assert!(read_transcript(temp.path(), 2).unwrap().is_none());
+        fs::write(rotated_path(temp.path(), 2), "first\n").unwrap();
+        fs::write(log_path(temp.path(), 2), "second\n").unwrap();
+        assert_eq!(
+            read_transcript(temp.path(), 2).unwrap().as_deref(),
+            Some("first\nsecond\n")
+        );
+
+        assert_eq!(
+            line_tag("[12:00:00] event | tool Read started"),
+            Some("event")
+        );
+        assert_eq!(line_tag("[12:00:00] out   | [1] x | y"), Some("out"));
+        assert_eq!(line_tag("# afk iteration 1"), None);
+    }
+
+    #[test]
+    fn test_describe() {
+        let result = StreamEvent::Result {
+            success: true,
+            duration_ms: Some(1500),
+            result_text: Some("Done".to_string()),
+            usage: None,
+        };
+        assert_eq!(
+            describe(&result).as_deref(),
+            Some("result: success in 1.5s - Done")
+        );
+        let usage = TokenUsage {
+            input_tokens: 1200,
+            output_tokens: 300,
+            cost_usd: Some(0.0125),
+        };
+        assert_eq!(
+            describe(&StreamEvent::Usage(usage)).as_deref(),
+            Some("usage: 1200 in / 300 out tokens ($0.0125)")
+        );
+        let init = StreamEvent::SystemInit {
+            model: Some("sonnet".to_string()),
+            session_id: None,
+        };
+        assert_eq!(
+            describe(&init).as_deref(),
+            Some("session started (model: sonnet, id: -)")
+        );
+        assert_eq!(
+            describe(&StreamEvent::Error {
+                message: "boom".to_string()
+            })
+            .as_deref(),
+            Some("error: boom")
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Prompt token budget.
+//!
+//! With `prompt.max_tokens` set, or on the Ollama backend, whose budget is
+//! three quarters of `ollama.context_size` (see
+//! [`AfkConfig::prompt_budget`](crate::config::AfkConfig::prompt_budget)),
+//! a prompt estimated to exceed it is re-rendered with the lowest-priority context dropped one item at a time:
+//! the recent commit history first (oldest commit first), then the task's
+//! learnings (oldest first), then context files (last listed first). What
+//! was cut is reported by `afk prompt --explain`.
+
+/// Characters per token assumed by [`estimate_tokens`].
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Rough token count of a prompt.
+///
+/// Uses about four characters per token, which is close for English prose
+/// and code with most tokenisers. Meant for budgeting, not billing.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Prompt context that can be dropped to fit the budget.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trimmable {
+    /// Recent commits, newest first.
+    pub recent_commits: Vec<String>,
+    /// The task's learnings, oldest first.
+    pub learnings: Vec<String>,
+    /// Configured context files.
+    pub context_files: Vec<String>,
+}
+
+impl Trimmable {
+    /// Drop the lowest-priority item left. None once nothing is left.
+    fn cut_one(&mut self) -> Option<Cut> {
+        let (section, item) = if let Some(commit) = self.recent_commits.pop() {
+            ("recent commit", commit)
+        } else if !self.learnings.is_empty() {
+            ("learning", self.learnings.remove(0))
+        } else {
+            ("context file", self.context_files.pop()?)
+        };
+        Some(Cut { section, item })
+    }
+}
+
+/// An item left out of the prompt to fit the budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut {
+    /// What kind of item it was, e.g. "learning".
+    pub section: &'static str,
+    /// The item itself.
+    pub item: String,
+}
+
+/// A prompt rendered within the budget, if it could be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fitted {
+    /// The rendered prompt.
+    pub prompt: String,
+    /// Its estimated token count.
+    pub estimated_tokens: usize,
+    /// Items dropped to fit, in the order they were dropped.
+    pub cuts: Vec<Cut>,
+}
+
+/// Render a prompt, dropping trimmable context until it fits `max_tokens`.
+///
+/// A budget of 0 means no budget: the prompt is rendered once. If it is
+/// still over budget with everything trimmable dropped, that last render is
+/// returned. Only items whose removal shortened the prompt are reported, so
+/// context the template doesn't use never shows up as cut.
+pub fn fit<E>(
+    max_tokens: u32,
+    mut parts: Trimmable,
+    render: impl Fn(&Trimmable) -> Result<String, E>,
+) -> Result<Fitted, E> {
+    let mut cuts = Vec::new();
+    let mut pending: Option<(Cut, usize)> = None;
+    loop {
+        let prompt = render(&parts)?;
+        if let Some((cut, previous_len)) = pending.take() {
+            if prompt.len() < previous_len {
+                cuts.push(cut);
+            }
+        }
+        let estimated_tokens = estimate_tokens(&prompt);
+        let over = max_tokens > 0 && estimated_tokens > max_tokens as usize;
+        match over.then(|| parts.cut_one()).flatten() {
+            Some(cut) => pending = Some((cut, prompt.len())),
+            None => {
+                return Ok(Fitted {
+                    prompt,
+                    estimated_tokens,
+                    cuts,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(parts: &Trimmable) -> Result<String, ()> {
+        Ok([
+            "x".repeat(40),
+            parts.recent_commits.concat(),
+            parts.learnings.concat(),
+            parts.context_files.concat(),
+        ]
+        .concat())
+    }
+
+    fn parts() -> Trimmable {
+        Trimmable {
+            recent_commits: vec!["c".repeat(8), "d".repeat(8)],
+            learnings: vec!["l".repeat(8), "m".repeat(8)],
+            context_files: vec!["f".repeat(8), "g".repeat(8)],
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcd"), 1);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens("éééé"), 1);
+    }
+
+    #[test]
+    fn test_fit_without_budget_keeps_everything() {
+        let fitted = fit(0, parts(), render).unwrap();
+        assert_eq!(fitted.estimated_tokens, 22);
+        assert!(fitted.cuts.is_empty());
+    }
+
+    #[test]
+    fn test_fit_cuts_lowest_priority_first() {
+        let fitted = fit(14, parts(), render).unwrap();
+        assert_eq!(fitted.estimated_tokens, 14);
+        let cut: Vec<(&str, &str)> = fitted
+            .cuts
+            .iter()
+            .map(|c| (c.section, c.item.as_str()))
+            .collect();
+        assert_eq!(
+            cut,
+            [
+                ("recent commit", "dddddddd"),
+                ("recent commit", "cccccccc"),
+                ("learning", "llllllll"),
+                ("learning", "mmmmmmmm"),
+            ]
+        );
+        assert!(fitted.prompt.ends_with("ffffffffgggggggg"));
+    }
+
+    #[test]
+    fn test_fit_returns_smallest_prompt_when_budget_is_too_small() {
+        let fitted = fit(1, parts(), render).unwrap();
+        assert_eq!(fitted.cuts.len(), 6);
+        assert_eq!(fitted.cuts[5].section, "context file");
+        assert_eq!(fitted.cuts[5].item, "ffffffff");
+        assert_eq!(fitted.estimated_tokens, 10);
+    }
+
+    #[test]
+    fn test_fit_reports_only_rendered_cuts() {
+        let without_commits = |parts: &Trimmable| -> Result<String, ()> {
+            Ok(["x".repeat(40), parts.learnings.concat()].concat())
+        };
+        let fitted = fit(12, parts(), without_commits).unwrap();
+        assert_eq!(fitted.estimated_tokens, 12);
+        assert_eq!(
+            fitted.cuts,
+            [Cut {
+                section: "learning",
+                item: "llllllll".to_string()
+            }]
+        );
+    }
+}
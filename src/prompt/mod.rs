@@ -2,18 +2,27 @@
 //!
 //! This module generates prompts for AI CLI tools using Tera templates.
 
+pub mod budget;
 pub mod template;
 
 use std::collections::HashMap;
 use std::path::Path;
 use tera::{Context, Tera};
 
-use crate::config::AfkConfig;
-use crate::prd::PrdDocument;
-use crate::progress::SessionProgress;
+use crate::config::{AfkConfig, ContinuationMode, EVENTS_FILE, WARMUP_FILE};
+use crate::events::last_gate_failures;
+use crate::git::{diff_stats_since, get_current_commit, recent_commits, RepoLayout};
+use crate::prd::{PrdDocument, TaskOrder, UserStory};
+use crate::progress::{Selection, SessionProgress};
+
+use budget::Trimmable;
 
 // Re-export key types and functions for convenience.
-pub use template::{get_template, get_template_with_root, DEFAULT_TEMPLATE};
+pub use budget::{estimate_tokens, Cut};
+pub use template::{
+    get_template, get_template_with_root, named_template_path, template_exists, validate_template,
+    CONTINUATION_TEMPLATE, DEFAULT_TEMPLATE, TASK_FIELDS, TEMPLATE_VARIABLES, WARMUP_TEMPLATE,
+};
 
 /// Error type for prompt generation operations.
 #[derive(Debug, thiserror::Error)]
@@ -27,6 +36,16 @@ pub enum PromptError {
     /// Error rendering the Tera template.
     #[error("Template rendering failed: {0}")]
     TemplateError(#[from] tera::Error),
+    /// The template uses a variable afk does not provide.
+    #[error("Unknown template variable `{name}` on line {line} (available: {available})")]
+    UnknownVariable {
+        /// The variable (or `variable.field`) that was not recognised.
+        name: String,
+        /// Line of the template it appears on.
+        line: usize,
+        /// Names that would be accepted in its place.
+        available: String,
+    },
 }
 
 /// A simplified story struct for template rendering.
@@ -36,6 +55,64 @@ pub struct NextStoryContext {
     pub id: String,
     /// The story priority (1 = highest).
     pub priority: i32,
+    /// Number of acceptance criteria already checked off.
+    pub criteria_met: usize,
+    /// Acceptance criteria not yet met.
+    pub unmet_criteria: Vec<String>,
+    /// Latest comments from the story's discussion thread, oldest first.
+    pub discussion: Vec<String>,
+    /// Feedback from `afk review` rejections, oldest first.
+    pub review_feedback: Vec<String>,
+    /// Verify commands of the unmet criteria, which must pass for the story
+    /// to be accepted as complete.
+    pub checks: Vec<String>,
+}
+
+/// The next task's details, exposed to templates as `task`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TaskContext {
+    /// The task ID.
+    pub id: String,
+    /// Short title.
+    pub title: String,
+    /// Full description.
+    pub description: String,
+    /// The task priority (1 = highest).
+    pub priority: i32,
+    /// Where the task came from (e.g. "json:.afk/tasks.json").
+    pub source: String,
+    /// All acceptance criteria.
+    pub acceptance_criteria: Vec<String>,
+    /// Acceptance criteria not yet met.
+    pub unmet_criteria: Vec<String>,
+    /// Number of acceptance criteria already checked off.
+    pub criteria_met: usize,
+    /// Latest comments from the task's discussion thread, oldest first.
+    pub discussion: Vec<String>,
+    /// Feedback from `afk review` rejections, oldest first.
+    pub review_feedback: Vec<String>,
+    /// Times the task has failed this session.
+    pub failure_count: u32,
+}
+
+/// Number of commits listed in the `recent_commits` template variable.
+const RECENT_COMMITS: usize = 5;
+
+/// Story details for the condensed continuation prompt.
+#[derive(Debug, Clone, serde::Serialize)]
+struct ContinuationStory {
+    id: String,
+    title: String,
+    description: String,
+    discussion: Vec<String>,
+    review_feedback: Vec<String>,
+}
+
+/// Change since the previous attempt, for the continuation prompt.
+#[derive(Debug, Clone, serde::Serialize)]
+struct ContinuationDiff {
+    files: u32,
+    lines: u32,
 }
 
 /// Result of prompt generation.
@@ -46,6 +123,12 @@ pub struct PromptResult {
     pub iteration: u32,
     /// Whether all stories are complete.
     pub all_complete: bool,
+    /// Estimated token count of the prompt.
+    pub estimated_tokens: usize,
+    /// Context left out to fit `prompt.max_tokens`.
+    pub cuts: Vec<Cut>,
+    /// The task the prompt points at, if any.
+    pub task_id: Option<String>,
 }
 
 /// Generate the prompt for the next iteration.
@@ -99,10 +182,13 @@ pub fn generate_prompt_with_root(
     let tasks_path = root.map(|r| r.join(".afk/tasks.json"));
     let prd = PrdDocument::load(tasks_path.as_deref())?;
 
-    // Calculate counts
-    let pending_stories = prd.get_pending_stories();
+    // Calculate counts; excluded tasks (skipped, waiting on dependencies,
+    // out of failures) are never picked
+    let order = TaskOrder::new(config, &progress);
+    let pending_stories: Vec<&UserStory> =
+        Selection::evaluate(&prd, &progress, &order, config.limits.max_task_failures).stories(&prd);
     let total_stories = prd.user_stories.len();
-    let completed_count = total_stories - pending_stories.len();
+    let completed_count = prd.user_stories.iter().filter(|s| s.passes).count();
 
     // Max iterations for display (limit enforcement is in loop controller)
     let max_iterations = limit_override.unwrap_or(config.limits.max_iterations);
@@ -118,70 +204,316 @@ pub fn generate_prompt_with_root(
     // Increment iteration for tracking
     let iteration = progress.increment_iteration();
 
-    // Save the updated progress
-    let progress_save_path = root.map(|r| r.join(".afk/progress.json"));
-    progress.save(progress_save_path.as_deref())?;
-
-    // Build feedback loops dict (filter out None values)
-    let mut feedback_loops: HashMap<String, String> = HashMap::new();
-    if let Some(ref types_cmd) = config.feedback_loops.types {
-        feedback_loops.insert("types".to_string(), types_cmd.clone());
-    }
-    if let Some(ref lint_cmd) = config.feedback_loops.lint {
-        feedback_loops.insert("lint".to_string(), lint_cmd.clone());
+    // Detect whether the next story continues the previous attempt, and
+    // remember this attempt for the next prompt
+    let next = pending_stories.first().copied();
+    let condensed = config.prompt.continuation_mode == ContinuationMode::Condensed;
+    let continuing =
+        condensed && next.is_some_and(|s| progress.last_task.as_deref() == Some(s.id.as_str()));
+    let previous_commit = progress.last_task_commit.take();
+    if let Some(story) = next {
+        progress.record_attempt(&story.id, &story.source);
     }
-    if let Some(ref test_cmd) = config.feedback_loops.test {
-        feedback_loops.insert("test".to_string(), test_cmd.clone());
+    let task_id = next.map(|s| s.id.clone());
+    if condensed {
+        progress.last_task = next.map(|s| s.id.clone());
+        progress.last_task_commit = get_current_commit();
     }
-    if let Some(ref build_cmd) = config.feedback_loops.build {
-        feedback_loops.insert("build".to_string(), build_cmd.clone());
+
+    // Save the updated progress (previews in read-only mode leave it untouched)
+    if !crate::config::is_read_only() {
+        let progress_save_path = root.map(|r| r.join(".afk/progress.json"));
+        progress.save(progress_save_path.as_deref())?;
     }
-    // Add custom commands
-    for (name, cmd) in &config.feedback_loops.custom {
-        feedback_loops.insert(name.clone(), cmd.clone());
+
+    let feedback_loops = feedback_loop_commands(config);
+    let warmup = warmup_summary(config, root);
+
+    if let (true, Some(story)) = (continuing, next) {
+        let counts = ContinuationCounts {
+            iteration,
+            max_iterations,
+            completed_count,
+            total_count: total_stories,
+        };
+        let parts = Trimmable {
+            learnings: progress
+                .get_task(&story.id)
+                .map(|t| t.learnings.clone())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        let fitted = budget::fit(config.prompt_budget(), parts, |parts| {
+            render_continuation(
+                config,
+                &progress,
+                story,
+                previous_commit.as_deref(),
+                &counts,
+                &parts.learnings,
+                &feedback_loops,
+                &stop_signal,
+            )
+            .map(|prompt| with_warmup(warmup.as_deref(), prompt))
+        })?;
+        return Ok(PromptResult {
+            prompt: fitted.prompt,
+            iteration,
+            all_complete,
+            estimated_tokens: fitted.estimated_tokens,
+            cuts: fitted.cuts,
+            task_id,
+        });
     }
 
     // Get template
     let template_str = get_template_with_root(config, root);
+    validate_template(&template_str)?;
 
     // Set up Tera and render
     let mut tera = Tera::default();
     tera.add_raw_template("prompt", &template_str)?;
 
     // Get next story for context
+    let review_feedback = |id: &str| {
+        progress
+            .get_task(id)
+            .map(|t| t.review_feedback.clone())
+            .unwrap_or_default()
+    };
     let next_story: Option<NextStoryContext> = pending_stories.first().map(|s| NextStoryContext {
         id: s.id.clone(),
         priority: s.priority,
+        criteria_met: s.criteria_counts().0,
+        unmet_criteria: s.unmet_criteria().iter().map(|c| c.text.clone()).collect(),
+        discussion: s.discussion.clone(),
+        review_feedback: review_feedback(&s.id),
+        checks: s
+            .unmet_criteria()
+            .iter()
+            .filter_map(|c| c.verify.clone())
+            .collect(),
     });
-
-    // Build context
+    let task_progress = next.and_then(|s| progress.get_task(&s.id));
+    let task: Option<TaskContext> = next.map(|s| TaskContext {
+        id: s.id.clone(),
+        title: s.title.clone(),
+        description: s.description.clone(),
+        priority: s.priority,
+        source: s.source.clone(),
+        acceptance_criteria: s
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.clone())
+            .collect(),
+        unmet_criteria: s.unmet_criteria().iter().map(|c| c.text.clone()).collect(),
+        criteria_met: s.criteria_counts().0,
+        discussion: s.discussion.clone(),
+        review_feedback: review_feedback(&s.id),
+        failure_count: task_progress.map_or(0, |t| t.failure_count),
+    });
+    let learnings = task_progress
+        .map(|t| t.learnings.clone())
+        .unwrap_or_default();
+    let events_path = root.map_or_else(
+        || Path::new(EVENTS_FILE).to_path_buf(),
+        |r| r.join(EVENTS_FILE),
+    );
+
+    // Build context; the trimmable parts are added per render
     let mut context = Context::new();
     context.insert("iteration", &iteration);
     context.insert("max_iterations", &max_iterations);
     context.insert("completed_count", &completed_count);
     context.insert("total_count", &total_stories);
     context.insert("next_story", &next_story);
-    context.insert("context_files", &config.prompt.context_files);
+    context.insert("task", &task);
+    context.insert("gate_failures", &last_gate_failures(&events_path));
     context.insert("feedback_loops", &feedback_loops);
     context.insert("custom_instructions", &config.prompt.instructions);
     context.insert("bootstrap", &bootstrap);
     context.insert("stop_signal", &stop_signal);
     context.insert("has_frontend", &config.prompt.has_frontend);
-
-    let prompt = tera.render("prompt", &context)?;
+    context.insert(
+        "submodules",
+        RepoLayout::load_at(root.unwrap_or(Path::new("."))).submodules(),
+    );
+
+    let parts = Trimmable {
+        recent_commits: recent_commits(RECENT_COMMITS),
+        learnings,
+        context_files: config.prompt.context_files.clone(),
+    };
+    let fitted = budget::fit(config.prompt_budget(), parts, |parts| {
+        let mut context = context.clone();
+        context.insert("learnings", &parts.learnings);
+        context.insert("recent_commits", &parts.recent_commits);
+        context.insert("context_files", &parts.context_files);
+        tera.render("prompt", &context)
+            .map(|prompt| with_warmup(warmup.as_deref(), prompt))
+    })?;
 
     Ok(PromptResult {
-        prompt,
+        prompt: fitted.prompt,
         iteration,
         all_complete,
+        estimated_tokens: fitted.estimated_tokens,
+        cuts: fitted.cuts,
+        task_id,
     })
 }
 
+/// Configured quality gate commands by name (unset gates are left out).
+fn feedback_loop_commands(config: &AfkConfig) -> HashMap<String, String> {
+    let mut feedback_loops: HashMap<String, String> = HashMap::new();
+    if let Some(ref types_cmd) = config.feedback_loops.types {
+        feedback_loops.insert("types".to_string(), types_cmd.clone());
+    }
+    if let Some(ref lint_cmd) = config.feedback_loops.lint {
+        feedback_loops.insert("lint".to_string(), lint_cmd.clone());
+    }
+    if let Some(ref test_cmd) = config.feedback_loops.test {
+        feedback_loops.insert("test".to_string(), test_cmd.clone());
+    }
+    if let Some(ref build_cmd) = config.feedback_loops.build {
+        feedback_loops.insert("build".to_string(), build_cmd.clone());
+    }
+    // Add custom commands
+    for (name, cmd) in &config.feedback_loops.custom {
+        feedback_loops.insert(name.clone(), cmd.clone());
+    }
+    feedback_loops
+}
+
+/// Saved warm-up summary, if warm-up is enabled and has run.
+fn warmup_summary(config: &AfkConfig, root: Option<&Path>) -> Option<String> {
+    if !config.runner.warmup {
+        return None;
+    }
+    let path = root.map_or_else(
+        || Path::new(WARMUP_FILE).to_path_buf(),
+        |r| r.join(WARMUP_FILE),
+    );
+    let summary = std::fs::read_to_string(path).ok()?;
+    let summary = summary.trim();
+    (!summary.is_empty()).then(|| summary.to_string())
+}
+
+/// Prepend the warm-up summary to a prompt.
+fn with_warmup(summary: Option<&str>, prompt: String) -> String {
+    match summary {
+        Some(summary) => {
+            format!("## Repository Notes (from warm-up)\n\n{summary}\n\n---\n\n{prompt}")
+        }
+        None => prompt,
+    }
+}
+
+/// A pending story listed in the warm-up prompt.
+#[derive(Debug, Clone, serde::Serialize)]
+struct WarmupStory {
+    id: String,
+    title: String,
+}
+
+/// Maximum stories listed in the warm-up prompt.
+const WARMUP_MAX_STORIES: usize = 20;
+
+/// Generate the prompt for the read-only warm-up iteration.
+///
+/// Lists the upcoming stories so the AI can sketch a plan, but does not
+/// touch session progress.
+pub fn generate_warmup_prompt(
+    config: &AfkConfig,
+    prd: &PrdDocument,
+) -> Result<String, PromptError> {
+    let stories: Vec<WarmupStory> = prd
+        .get_pending_stories(&TaskOrder::load(config))
+        .iter()
+        .take(WARMUP_MAX_STORIES)
+        .map(|s| WarmupStory {
+            id: s.id.clone(),
+            title: s.title.clone(),
+        })
+        .collect();
+
+    let mut tera = Tera::default();
+    tera.add_raw_template("warmup", WARMUP_TEMPLATE)?;
+
+    let mut context = Context::new();
+    context.insert("stories", &stories);
+    context.insert("context_files", &config.prompt.context_files);
+    context.insert("feedback_loops", &feedback_loop_commands(config));
+
+    Ok(tera.render("warmup", &context)?)
+}
+
+/// Iteration and story counts shown in the continuation prompt.
+#[derive(Debug, Clone, Copy)]
+struct ContinuationCounts {
+    iteration: u32,
+    max_iterations: u32,
+    completed_count: usize,
+    total_count: usize,
+}
+
+/// Render the condensed prompt for a story continuing from the last iteration.
+#[allow(clippy::too_many_arguments)]
+fn render_continuation(
+    config: &AfkConfig,
+    progress: &SessionProgress,
+    story: &UserStory,
+    previous_commit: Option<&str>,
+    counts: &ContinuationCounts,
+    learnings: &[String],
+    feedback_loops: &HashMap<String, String>,
+    stop_signal: &Option<String>,
+) -> Result<String, PromptError> {
+    let task = progress.get_task(&story.id);
+    let diff = previous_commit
+        .and_then(diff_stats_since)
+        .map(|stats| ContinuationDiff {
+            files: stats.files,
+            lines: stats.lines,
+        });
+
+    let mut tera = Tera::default();
+    tera.add_raw_template("continuation", CONTINUATION_TEMPLATE)?;
+
+    let mut context = Context::new();
+    context.insert("iteration", &counts.iteration);
+    context.insert("max_iterations", &counts.max_iterations);
+    context.insert("completed_count", &counts.completed_count);
+    context.insert("total_count", &counts.total_count);
+    context.insert(
+        "story",
+        &ContinuationStory {
+            id: story.id.clone(),
+            title: story.title.clone(),
+            description: story.description.clone(),
+            discussion: story.discussion.clone(),
+            review_feedback: task.map(|t| t.review_feedback.clone()).unwrap_or_default(),
+        },
+    );
+    let unmet: Vec<&str> = story.unmet_criteria().iter().map(|c| c.as_str()).collect();
+    context.insert("criteria", &unmet);
+    context.insert("failure_count", &task.map(|t| t.failure_count).unwrap_or(0));
+    context.insert("last_message", &task.and_then(|t| t.message.clone()));
+    context.insert("learnings", learnings);
+    context.insert("diff", &diff);
+    context.insert("feedback_loops", feedback_loops);
+    context.insert("custom_instructions", &config.prompt.instructions);
+    context.insert("stop_signal", stop_signal);
+
+    Ok(tera.render("continuation", &context)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{FeedbackLoopsConfig, LimitsConfig, PromptConfig};
     use crate::prd::UserStory;
+    use crate::progress::TaskStatus;
     use std::fs;
     use tempfile::TempDir;
 
@@ -386,6 +718,45 @@ mod tests {
         assert!(result.prompt.contains("Completed: 0/0 stories"));
     }
 
+    #[test]
+    fn test_generate_prompt_prepends_warmup_notes() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![UserStory::new("story-1", "Test Story")],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+        std::fs::write(temp.path().join(WARMUP_FILE), "Uses thiserror.\n").unwrap();
+
+        // Notes are ignored while warm-up is disabled
+        let mut config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(!result.prompt.contains("Uses thiserror."));
+
+        config.runner.warmup = true;
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result
+            .prompt
+            .starts_with("## Repository Notes (from warm-up)\n\nUses thiserror.\n"));
+    }
+
+    #[test]
+    fn test_generate_warmup_prompt() {
+        let prd = PrdDocument {
+            user_stories: vec![UserStory::new("story-1", "Add login")],
+            ..Default::default()
+        };
+        let mut config = AfkConfig::default();
+        config.prompt.context_files = vec!["AGENTS.md".to_string()];
+
+        let prompt = generate_warmup_prompt(&config, &prd).unwrap();
+        assert!(prompt.contains("read-only iteration"));
+        assert!(prompt.contains("- AGENTS.md"));
+        assert!(prompt.contains("- story-1: Add login"));
+    }
+
     #[test]
     fn test_generate_prompt_with_feedback_loops() {
         let temp = TempDir::new().unwrap();
@@ -590,6 +961,119 @@ mod tests {
         assert!(result.prompt.contains("Iteration: 1/200"));
     }
 
+    #[test]
+    fn test_generate_prompt_custom_template_variables() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        let mut progress = SessionProgress::new();
+        progress.add_learning("story-1", "Use the builder", "json");
+        progress.save(Some(&progress_path)).unwrap();
+
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                description: "Add the thing".to_string(),
+                acceptance_criteria: vec!["Works".into()],
+                ..UserStory::new("story-1", "Test Story")
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        fs::create_dir_all(temp.path().join(".afk/templates")).unwrap();
+        fs::write(
+            temp.path().join(".afk/templates/short.md"),
+            "{{ task.id }}: {{ task.title }}\n\
+             {% for c in task.acceptance_criteria %}- {{ c }}\n{% endfor %}\
+             {% for l in learnings %}* {{ l }}\n{% endfor %}\
+             gates: {{ gate_failures | length }}",
+        )
+        .unwrap();
+
+        let config = AfkConfig {
+            prompt: PromptConfig {
+                template: "short".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert_eq!(
+            result.prompt,
+            "story-1: Test Story\n- Works\n* Use the builder\ngates: 0"
+        );
+    }
+
+    #[test]
+    fn test_generate_prompt_trims_to_max_tokens() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        let mut progress = SessionProgress::new();
+        progress.add_learning("story-1", "old ".repeat(50), "json");
+        progress.add_learning("story-1", "Use the builder", "json");
+        progress.save(Some(&progress_path)).unwrap();
+        PrdDocument {
+            user_stories: vec![UserStory::new("story-1", "Test Story")],
+            ..Default::default()
+        }
+        .save(Some(&tasks_path))
+        .unwrap();
+        fs::create_dir_all(temp.path().join(".afk/templates")).unwrap();
+        fs::write(
+            temp.path().join(".afk/templates/short.md"),
+            "{{ task.id }}\n{% for l in learnings %}* {{ l }}\n{% endfor %}\
+             {% for f in context_files %}- {{ f }}\n{% endfor %}",
+        )
+        .unwrap();
+
+        let mut config = AfkConfig {
+            prompt: PromptConfig {
+                template: "short".to_string(),
+                context_files: vec!["AGENTS.md".to_string()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result.estimated_tokens > 50);
+        assert!(result.cuts.is_empty());
+
+        config.prompt.max_tokens = 20;
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert_eq!(result.prompt, "story-1\n* Use the builder\n- AGENTS.md\n");
+        assert_eq!(result.cuts.len(), 1);
+        assert_eq!(result.cuts[0].section, "learning");
+        assert!(result.estimated_tokens <= 20);
+    }
+
+    #[test]
+    fn test_generate_prompt_rejects_unknown_variable() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        PrdDocument::default().save(Some(&tasks_path)).unwrap();
+        fs::write(
+            temp.path().join(".afk/custom.md"),
+            "{{ task.id }} {{ tikket }}",
+        )
+        .unwrap();
+
+        let config = AfkConfig {
+            prompt: PromptConfig {
+                custom_path: Some(".afk/custom.md".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = generate_prompt_with_root(&config, false, None, Some(temp.path()))
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, PromptError::UnknownVariable { ref name, line: 1, .. } if name == "tikket")
+        );
+    }
+
     #[test]
     fn test_generate_prompt_missing_prd() {
         let temp = TempDir::new().unwrap();
@@ -708,6 +1192,112 @@ mod tests {
             .prompt
             .contains("Next story: pending-medium (priority 2)"));
         assert!(result.prompt.contains("Completed: 1/2 stories"));
+        assert!(!result.prompt.contains("Latest discussion"));
+    }
+
+    #[test]
+    fn test_generate_prompt_passes_over_skipped_stories() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        let mut progress = SessionProgress::new();
+        progress.set_task_status("over-budget", TaskStatus::Skipped, "json", None);
+        progress.save(Some(&progress_path)).unwrap();
+
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory {
+                    id: "over-budget".to_string(),
+                    priority: 1,
+                    ..Default::default()
+                },
+                UserStory {
+                    id: "next-up".to_string(),
+                    priority: 2,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+
+        assert!(result.prompt.contains("Next story: next-up (priority 2)"));
+        assert!(result.prompt.contains("Completed: 0/2 stories"));
+    }
+
+    #[test]
+    fn test_generate_prompt_includes_discussion() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                id: "gh-9".to_string(),
+                discussion: vec!["@alice (2024-05-03): Make it 10 attempts.".to_string()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result
+            .prompt
+            .contains("- Latest discussion (newest last; it overrides the description):"));
+        assert!(result
+            .prompt
+            .contains("  - @alice (2024-05-03): Make it 10 attempts."));
+    }
+
+    #[test]
+    fn test_generate_prompt_includes_review_feedback() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+        let mut progress = SessionProgress::new();
+        progress.set_task_status("gh-9", TaskStatus::Pending, "json", None);
+        progress.tasks.get_mut("gh-9").unwrap().review_feedback =
+            vec!["Handle an empty username".to_string()];
+        progress.save(Some(&progress_path)).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                id: "gh-9".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result
+            .prompt
+            .contains("- Review feedback (address it before setting `passes: true` again):"));
+        assert!(result.prompt.contains("  - Handle an empty username"));
+    }
+
+    #[test]
+    fn test_generate_prompt_asks_for_afk_result() {
+        let temp = TempDir::new().unwrap();
+        let (_, tasks_path) = setup_test_env(&temp);
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                id: "gh-9".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result.prompt.contains("```afk-result"));
+        assert!(result.prompt.contains(r#""task_id": "gh-9""#));
+        // The example never reads as the agent's own result
+        assert!(crate::parser::extract_agent_result(&result.prompt).is_none());
     }
 
     #[test]
@@ -715,6 +1305,11 @@ mod tests {
         let next_story = NextStoryContext {
             id: "test-123".to_string(),
             priority: 2,
+            criteria_met: 0,
+            unmet_criteria: vec!["Button renders".to_string()],
+            discussion: Vec::new(),
+            review_feedback: Vec::new(),
+            checks: vec!["cargo test ui::".to_string()],
         };
 
         // Verify it can be serialised (needed for template)
@@ -722,4 +1317,123 @@ mod tests {
         assert!(json.contains("test-123"));
         assert!(json.contains("2"));
     }
+
+    #[test]
+    fn test_generate_prompt_condensed_continuation() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                id: "story-1".to_string(),
+                title: "First Story".to_string(),
+                acceptance_criteria: vec!["Button renders".into()],
+                priority: 1,
+                passes: false,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig {
+            prompt: PromptConfig {
+                continuation_mode: ContinuationMode::Condensed,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        // First attempt gets the full template
+        let first = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(first.prompt.contains("## Your Task"));
+
+        // Same story again gets the condensed prompt
+        let second = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert_eq!(second.iteration, 2);
+        assert!(second.prompt.contains("(continued)"));
+        assert!(second.prompt.contains("story-1: First Story"));
+        assert!(second.prompt.contains("- Button renders"));
+        assert!(!second.prompt.contains("## Your Task"));
+    }
+
+    #[test]
+    fn test_generate_prompt_full_mode_never_condenses() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![UserStory {
+                id: "story-1".to_string(),
+                priority: 1,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        let second = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(second.prompt.contains("## Your Task"));
+
+        let progress = SessionProgress::load(Some(&progress_path)).unwrap();
+        assert!(progress.last_task.is_none());
+    }
+
+    #[test]
+    fn test_generate_prompt_highlights_unmet_criteria() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let mut story = UserStory {
+            id: "story-1".to_string(),
+            acceptance_criteria: vec!["Met one".into(), "Still open".into()],
+            priority: 1,
+            ..Default::default()
+        };
+        story.check_criterion(1, None);
+        let prd = PrdDocument {
+            user_stories: vec![story],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result.prompt.contains("Criteria already met: 1"));
+        assert!(result.prompt.contains("  - Still open"));
+        assert!(!result.prompt.contains("Met one"));
+    }
+
+    #[test]
+    fn test_generate_prompt_lists_criterion_checks() {
+        let temp = TempDir::new().unwrap();
+        let (progress_path, tasks_path) = setup_test_env(&temp);
+
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+        let mut checked = crate::prd::AcceptanceCriterion::new("Auth tests pass");
+        checked.verify = Some("cargo test auth::".to_string());
+        let story = UserStory {
+            id: "story-1".to_string(),
+            acceptance_criteria: vec![checked, "Docs updated".into()],
+            priority: 1,
+            ..Default::default()
+        };
+        let prd = PrdDocument {
+            user_stories: vec![story],
+            ..Default::default()
+        };
+        prd.save(Some(&tasks_path)).unwrap();
+
+        let config = AfkConfig::default();
+        let result = generate_prompt_with_root(&config, false, None, Some(temp.path())).unwrap();
+        assert!(result
+            .prompt
+            .contains("only complete when these commands pass"));
+        assert!(result.prompt.contains("  - `cargo test auth::`"));
+    }
 }
@@ -1,21 +1,137 @@
 //! Prompt templates for afk.
 //!
 //! This module defines the default prompt template and provides
-//! functionality to load custom templates.
+//! functionality to load and validate custom templates.
 
-use crate::config::AfkConfig;
+use crate::config::{AfkConfig, TEMPLATES_DIR};
+use regex::Regex;
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::PromptError;
+
+static COMMENT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)\{#.*?#\}").expect("COMMENT regex is valid"));
+static RAW_BLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{%-?\s*raw\s*-?%\}.*?\{%-?\s*endraw\s*-?%\}")
+        .expect("RAW_BLOCK regex is valid")
+});
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{\{-?(.*?)-?\}\}|\{%-?(.*?)-?%\}").expect("TAG regex is valid")
+});
+static STRING_LITERAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#""[^"]*"|'[^']*'|`[^`]*`"#).expect("STRING_LITERAL regex is valid")
+});
+static FILTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\|\s*\w+").expect("FILTER regex is valid"));
+static TEST: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bis\s+(?:not\s+)?\w+").expect("TEST regex is valid"));
+static KEYWORD_ARG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b\w+\s*=([^=]|$)").expect("KEYWORD_ARG regex is valid"));
+static VARIABLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"([A-Za-z_][A-Za-z0-9_]*(?:\.[A-Za-z_][A-Za-z0-9_]*)*)(\s*[(:])?")
+        .expect("VARIABLE regex is valid")
+});
 
 /// Default prompt template loaded from `default.md`.
 ///
 /// Uses Tera template syntax (similar to Jinja2).
 pub const DEFAULT_TEMPLATE: &str = include_str!("default.md");
 
+/// Condensed template used when a task continues across iterations.
+///
+/// Only rendered when `prompt.continuation_mode` is "condensed".
+pub const CONTINUATION_TEMPLATE: &str = include_str!("continuation.md");
+
+/// Template for the read-only warm-up iteration.
+///
+/// Only rendered when `runner.warmup` is enabled.
+pub const WARMUP_TEMPLATE: &str = include_str!("warmup.md");
+
+/// Variables available to the main prompt template.
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "iteration",
+    "max_iterations",
+    "completed_count",
+    "total_count",
+    "next_story",
+    "task",
+    "learnings",
+    "recent_commits",
+    "gate_failures",
+    "context_files",
+    "feedback_loops",
+    "custom_instructions",
+    "bootstrap",
+    "stop_signal",
+    "has_frontend",
+    "submodules",
+];
+
+/// Fields of the `task` variable.
+pub const TASK_FIELDS: &[&str] = &[
+    "id",
+    "title",
+    "description",
+    "priority",
+    "source",
+    "acceptance_criteria",
+    "unmet_criteria",
+    "criteria_met",
+    "discussion",
+    "review_feedback",
+    "failure_count",
+];
+
+/// Fields of the `next_story` variable.
+const NEXT_STORY_FIELDS: &[&str] = &[
+    "id",
+    "priority",
+    "criteria_met",
+    "unmet_criteria",
+    "discussion",
+    "review_feedback",
+    "checks",
+];
+
+/// Words in template expressions that are not variables.
+const KEYWORDS: &[&str] = &[
+    "and",
+    "or",
+    "not",
+    "in",
+    "is",
+    "true",
+    "false",
+    "True",
+    "False",
+    "loop",
+    "self",
+    "super",
+    "__tera_context",
+];
+
+/// Path of a named template in `.afk/templates/`.
+pub fn named_template_path(name: &str, root: Option<&Path>) -> PathBuf {
+    let path = Path::new(TEMPLATES_DIR).join(format!("{name}.md"));
+    match root {
+        Some(root) => root.join(path),
+        None => path,
+    }
+}
+
+/// Whether a template name refers to the built-in or an existing file.
+pub fn template_exists(name: &str, root: Option<&Path>) -> bool {
+    name == "default" || named_template_path(name, root).is_file()
+}
+
 /// Get the template string based on config.
 ///
 /// If a custom_path is specified in the config and the file exists,
-/// its contents are returned. Otherwise, the default template is used.
+/// its contents are returned. Otherwise `prompt.template` names a file in
+/// `.afk/templates/`, falling back to the default template.
 ///
 /// # Arguments
 ///
@@ -56,9 +172,129 @@ pub fn get_template_with_root(config: &AfkConfig, root: Option<&Path>) -> String
         }
     }
 
+    let name = config.prompt.template.as_str();
+    if name != "default" {
+        if let Ok(contents) = fs::read_to_string(named_template_path(name, root)) {
+            return contents;
+        }
+    }
+
     DEFAULT_TEMPLATE.to_string()
 }
 
+/// Check that a prompt template only uses known variables.
+///
+/// Loop variables and `set` targets count as known once declared, and
+/// `task`/`next_story` fields are checked too. Filters, tests, function
+/// calls and string literals are ignored.
+pub fn validate_template(template: &str) -> Result<(), PromptError> {
+    // Blank out comments and raw blocks, keeping offsets for line numbers
+    let blank = |caps: &regex::Captures| {
+        caps[0]
+            .chars()
+            .map(|c| if c == '\n' { '\n' } else { ' ' })
+            .collect::<String>()
+    };
+    let template = COMMENT.replace_all(template, blank);
+    let template = RAW_BLOCK.replace_all(&template, blank);
+
+    let mut locals: HashSet<String> = HashSet::new();
+    for caps in TAG.captures_iter(&template) {
+        let start = caps.get(0).map_or(0, |m| m.start());
+        let line = template[..start].matches('\n').count() + 1;
+        let expression = match (caps.get(1), caps.get(2)) {
+            (Some(expr), _) => expr.as_str().to_string(),
+            (None, Some(tag)) => match tag_expression(tag.as_str(), &mut locals) {
+                Some(expr) => expr,
+                None => continue,
+            },
+            (None, None) => continue,
+        };
+        for path in expression_variables(&expression) {
+            check_variable(&path, &locals, line)?;
+        }
+    }
+    Ok(())
+}
+
+/// The expression part of a `{% ... %}` tag, recording any names it declares.
+fn tag_expression(tag: &str, locals: &mut HashSet<String>) -> Option<String> {
+    let tag = tag.trim();
+    let (keyword, rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    match keyword {
+        "if" | "elif" => Some(rest.to_string()),
+        "for" => {
+            let (names, iterable) = rest.split_once(" in ")?;
+            locals.extend(names.split(',').map(|n| n.trim().to_string()));
+            Some(iterable.to_string())
+        }
+        "set" | "set_global" => {
+            let (name, value) = rest.split_once('=')?;
+            locals.insert(name.trim().to_string());
+            Some(value.to_string())
+        }
+        "macro" => {
+            let params = rest.split_once('(')?.1.trim_end_matches(')');
+            locals.extend(
+                params
+                    .split(',')
+                    .filter_map(|p| p.split('=').next())
+                    .map(|p| p.trim().to_string()),
+            );
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Dotted variable paths referenced by a template expression.
+fn expression_variables(expression: &str) -> Vec<String> {
+    let expression = STRING_LITERAL.replace_all(expression, " ");
+    let expression = FILTER.replace_all(&expression, " ,");
+    let expression = TEST.replace_all(&expression, " ");
+    let expression = KEYWORD_ARG.replace_all(&expression, " $1");
+    VARIABLE
+        .captures_iter(&expression)
+        // Function calls and macro namespaces are not variables
+        .filter(|caps| caps.get(2).is_none())
+        .map(|caps| caps[1].to_string())
+        .filter(|path| !KEYWORDS.contains(&path.split('.').next().unwrap_or_default()))
+        .collect()
+}
+
+/// Check one dotted variable path against the known variables.
+fn check_variable(path: &str, locals: &HashSet<String>, line: usize) -> Result<(), PromptError> {
+    let mut parts = path.split('.');
+    let root = parts.next().unwrap_or_default();
+    if locals.contains(root) {
+        return Ok(());
+    }
+    if !TEMPLATE_VARIABLES.contains(&root) {
+        return Err(PromptError::UnknownVariable {
+            name: root.to_string(),
+            line,
+            available: TEMPLATE_VARIABLES.join(", "),
+        });
+    }
+    let fields = match root {
+        "task" => TASK_FIELDS,
+        "next_story" => NEXT_STORY_FIELDS,
+        _ => return Ok(()),
+    };
+    match parts.next() {
+        Some(field) if !fields.contains(&field) => Err(PromptError::UnknownVariable {
+            name: format!("{root}.{field}"),
+            line,
+            available: fields
+                .iter()
+                .map(|f| format!("{root}.{f}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }),
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -380,4 +616,77 @@ mod tests {
         let rendered = result.unwrap();
         assert!(!rendered.contains("## Browser Testing"));
     }
+
+    #[test]
+    fn test_builtin_templates_validate() {
+        validate_template(DEFAULT_TEMPLATE).unwrap();
+    }
+
+    #[test]
+    fn test_validate_template_accepts_locals_filters_and_literals() {
+        let template = "{# {{ ignored }} #}\n\
+            {% set heading = \"Task \" ~ task.id %}{{ heading }}\n\
+            {% for commit in recent_commits %}{{ loop.index }}. {{ commit | upper }}{% endfor %}\n\
+            {% if gate_failures is defined and gate_failures | length > 0 %}\
+            {{ gate_failures | join(sep=\", \") }}{% endif %}\n\
+            {% for name, cmd in feedback_loops -%}{{ name }}{% endfor %}\n\
+            {{ task.title | default(value=\"untitled\") }} {{ now() }}\n\
+            {% raw %}{{ literal }}{% endraw %}";
+        validate_template(template).unwrap();
+    }
+
+    #[test]
+    fn test_validate_template_reports_unknown_variable() {
+        let err = validate_template("Task\n\n{{ task.id }} {{ tsak.title }}").unwrap_err();
+        match err {
+            PromptError::UnknownVariable { name, line, .. } => {
+                assert_eq!(name, "tsak");
+                assert_eq!(line, 3);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+
+        let err = validate_template("{% if task.owner %}x{% endif %}").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Unknown template variable `task.owner` on line 1 (available: task.id,"));
+    }
+
+    #[test]
+    fn test_named_template_resolution() {
+        let temp = TempDir::new().unwrap();
+        let path = named_template_path("review", Some(temp.path()));
+        assert_eq!(path, temp.path().join(".afk/templates/review.md"));
+        assert!(template_exists("default", Some(temp.path())));
+        assert!(!template_exists("review", Some(temp.path())));
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "Review {{ task.id }}").unwrap();
+        assert!(template_exists("review", Some(temp.path())));
+
+        let config = AfkConfig {
+            prompt: PromptConfig {
+                template: "review".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            get_template_with_root(&config, Some(temp.path())),
+            "Review {{ task.id }}"
+        );
+
+        // A missing named template falls back to the default
+        let config = AfkConfig {
+            prompt: PromptConfig {
+                template: "missing".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            get_template_with_root(&config, Some(temp.path())),
+            DEFAULT_TEMPLATE
+        );
+    }
 }
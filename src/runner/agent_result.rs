@@ -0,0 +1,183 @@
+//! Agent results.
+//!
+//! An agent that ends with an `afk-result` block has the outcome recorded
+//! whether or not it updated the task files itself: `done` marks the story
+//! passed, so the usual criterion checks, review and source sync follow;
+//! `blocked` and `failed` count a failure against the task; and the summary
+//! and any new learnings go into progress.
+
+use std::path::Path;
+
+use crate::config::{PROGRESS_FILE, TASKS_FILE};
+use crate::parser::{AgentResult, AgentStatus};
+use crate::prd::PrdDocument;
+use crate::progress::{SessionProgress, TaskProgress, TaskStatus};
+
+/// Apply an agent's result to the task it names, or to `task_id` when it
+/// names none, in the project at `root` (the current directory if None).
+/// Returns a message for each change.
+pub fn apply(result: &AgentResult, task_id: Option<&str>, root: Option<&Path>) -> Vec<String> {
+    let Some(id) = result.task_id.as_deref().or(task_id) else {
+        return Vec::new();
+    };
+    let tasks_path = root.map(|root| root.join(TASKS_FILE));
+    let progress_path = root.map(|root| root.join(PROGRESS_FILE));
+    let Ok(mut prd) = PrdDocument::load(tasks_path.as_deref()) else {
+        return Vec::new();
+    };
+    let Some(story) = prd.get_story_mut(id) else {
+        return vec![format!("afk-result names unknown task {id}; ignored")];
+    };
+    let source = story.source.clone();
+    let mut progress = SessionProgress::load(progress_path.as_deref()).unwrap_or_default();
+    let mut messages = Vec::new();
+
+    let mut passed = false;
+    match result.status {
+        AgentStatus::Done => {
+            if !story.passes {
+                story.passes = true;
+                passed = true;
+                messages.push(format!("Marked {id} passed from its afk-result"));
+            }
+            record_summary(&mut progress, id, &source, result.summary.as_deref());
+        }
+        AgentStatus::Partial => {
+            record_summary(&mut progress, id, &source, result.summary.as_deref());
+        }
+        AgentStatus::Blocked | AgentStatus::Failed => {
+            let status = result.status;
+            let message = match &result.summary {
+                Some(summary) => format!("Agent reported {status}: {summary}"),
+                None => format!("Agent reported {status}"),
+            };
+            progress.set_task_status(id, TaskStatus::Failed, &source, Some(message.clone()));
+            messages.push(format!("{id}: {message}"));
+        }
+    }
+
+    let known = progress
+        .get_task(id)
+        .map(|t| t.learnings.clone())
+        .unwrap_or_default();
+    let mut learned = 0;
+    for learning in &result.learnings {
+        let learning = learning.trim();
+        if !learning.is_empty() && !known.iter().any(|k| k == learning) {
+            progress.add_learning(id, learning, &source);
+            learned += 1;
+        }
+    }
+    if learned > 0 {
+        let plural = if learned == 1 { "" } else { "s" };
+        messages.push(format!("Recorded {learned} learning{plural} for {id}"));
+    }
+
+    if !crate::config::is_read_only() {
+        if passed {
+            let _ = prd.save(tasks_path.as_deref());
+        }
+        let _ = progress.save(progress_path.as_deref());
+    }
+    messages
+}
+
+/// Keep the agent's summary as the task's latest message.
+fn record_summary(progress: &mut SessionProgress, id: &str, source: &str, summary: Option<&str>) {
+    let Some(summary) = summary.filter(|s| !s.trim().is_empty()) else {
+        return;
+    };
+    progress
+        .tasks
+        .entry(id.to_string())
+        .or_insert_with(|| TaskProgress::new(id, source))
+        .message = Some(summary.trim().to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prd::UserStory;
+    use tempfile::TempDir;
+
+    fn project() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join(".afk")).unwrap();
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory::new("auth", "Login"),
+                UserStory::new("docs", "Docs"),
+            ],
+            ..Default::default()
+        };
+        prd.save(Some(&temp.path().join(TASKS_FILE))).unwrap();
+        temp
+    }
+
+    fn result(status: AgentStatus, task_id: Option<&str>) -> AgentResult {
+        AgentResult {
+            status,
+            task_id: task_id.map(String::from),
+            summary: Some("Added the login form".to_string()),
+            learnings: vec!["Forms use the shared validator".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_apply_done_marks_story_passed() {
+        let temp = project();
+        let root = Some(temp.path());
+
+        let messages = apply(&result(AgentStatus::Done, None), Some("auth"), root);
+        assert_eq!(
+            messages,
+            [
+                "Marked auth passed from its afk-result",
+                "Recorded 1 learning for auth"
+            ]
+        );
+
+        let prd = PrdDocument::load(Some(&temp.path().join(TASKS_FILE))).unwrap();
+        assert!(prd.get_story("auth").unwrap().passes);
+        assert!(!prd.get_story("docs").unwrap().passes);
+        let progress = SessionProgress::load(Some(&temp.path().join(PROGRESS_FILE))).unwrap();
+        let task = progress.get_task("auth").unwrap();
+        assert_eq!(task.message.as_deref(), Some("Added the login form"));
+        assert_eq!(task.learnings, ["Forms use the shared validator"]);
+
+        // Reported again, nothing new is recorded
+        assert!(apply(&result(AgentStatus::Done, None), Some("auth"), root).is_empty());
+    }
+
+    #[test]
+    fn test_apply_blocked_counts_a_failure_on_named_task() {
+        let temp = project();
+        let root = Some(temp.path());
+
+        let mut blocked = result(AgentStatus::Blocked, Some("docs"));
+        blocked.learnings.clear();
+        let messages = apply(&blocked, Some("auth"), root);
+        assert_eq!(
+            messages,
+            ["docs: Agent reported blocked: Added the login form"]
+        );
+
+        let progress = SessionProgress::load(Some(&temp.path().join(PROGRESS_FILE))).unwrap();
+        let task = progress.get_task("docs").unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(task.failure_count, 1);
+        assert!(progress.get_task("auth").is_none());
+    }
+
+    #[test]
+    fn test_apply_ignores_unknown_task() {
+        let temp = project();
+        let messages = apply(
+            &result(AgentStatus::Done, Some("nope")),
+            None,
+            Some(temp.path()),
+        );
+        assert_eq!(messages, ["afk-result names unknown task nope; ignored"]);
+        assert!(apply(&result(AgentStatus::Done, None), None, Some(temp.path())).is_empty());
+    }
+}
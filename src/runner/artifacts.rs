@@ -0,0 +1,238 @@
+//! Gate output artefacts.
+//!
+//! Each verify run writes every gate's combined stdout/stderr to
+//! `.afk/artifacts/<iteration>/<gate>.log`, so the output is still there
+//! after the pass/fail decision. The AI CLI's stderr for the iteration goes
+//! to `ai-cli-stderr.log` alongside. Logs are capped in size and only the
+//! most recent iterations are kept.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::{ArtifactsConfig, ARTIFACTS_DIR};
+
+use super::quality_gates::GateResult;
+
+/// Directory holding one iteration's artefacts.
+pub fn artifact_dir(root: &Path, iteration: u32) -> PathBuf {
+    root.join(iteration.to_string())
+}
+
+/// Save each gate's output for an iteration and prune old iterations.
+///
+/// Returns the paths written, in gate order. Does nothing when artefacts
+/// are disabled or read-only mode is active.
+pub fn save_gate_artifacts(
+    config: &ArtifactsConfig,
+    iteration: u32,
+    gates: &[GateResult],
+) -> io::Result<Vec<PathBuf>> {
+    save_gate_artifacts_in(Path::new(ARTIFACTS_DIR), config, iteration, gates)
+}
+
+/// Save artefacts under a specific root directory.
+fn save_gate_artifacts_in(
+    root: &Path,
+    config: &ArtifactsConfig,
+    iteration: u32,
+    gates: &[GateResult],
+) -> io::Result<Vec<PathBuf>> {
+    if !config.enabled || gates.is_empty() || crate::config::is_read_only() {
+        return Ok(Vec::new());
+    }
+
+    let dir = artifact_dir(root, iteration);
+    fs::create_dir_all(&dir)?;
+
+    let max_bytes = config.max_kb as usize * 1024;
+    let mut paths = Vec::with_capacity(gates.len());
+    for gate in gates {
+        let path = dir.join(format!("{}.log", file_name(&gate.name)));
+        let status = if gate.passed { "passed" } else { "failed" };
+        let header = format!(
+            "# gate: {}\n# status: {status}\n# duration: {:.1}s\n\n",
+            gate.name, gate.duration_seconds
+        );
+        fs::write(&path, header + &cap_output(&gate.output, max_bytes))?;
+        paths.push(path);
+    }
+
+    prune_artifacts(root, config.keep_iterations)?;
+    Ok(paths)
+}
+
+/// File the AI CLI's stderr is saved to within an iteration's directory.
+pub const STDERR_LOG: &str = "ai-cli-stderr.log";
+
+/// Save the AI CLI's stderr for an iteration.
+///
+/// Returns the path written, or None when there was no stderr, artefacts
+/// are disabled, or read-only mode is active.
+pub fn save_stderr_artifact(
+    config: &ArtifactsConfig,
+    iteration: u32,
+    stderr: &str,
+) -> io::Result<Option<PathBuf>> {
+    save_stderr_artifact_in(Path::new(ARTIFACTS_DIR), config, iteration, stderr)
+}
+
+/// Save the stderr log under a specific root directory.
+fn save_stderr_artifact_in(
+    root: &Path,
+    config: &ArtifactsConfig,
+    iteration: u32,
+    stderr: &str,
+) -> io::Result<Option<PathBuf>> {
+    if !config.enabled || stderr.trim().is_empty() || crate::config::is_read_only() {
+        return Ok(None);
+    }
+
+    let dir = artifact_dir(root, iteration);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(STDERR_LOG);
+    let max_bytes = config.max_kb as usize * 1024;
+    fs::write(&path, cap_output(stderr.trim_end(), max_bytes) + "\n")?;
+
+    prune_artifacts(root, config.keep_iterations)?;
+    Ok(Some(path))
+}
+
+/// Keep only the tail of output longer than `max_bytes`.
+fn cap_output(output: &str, max_bytes: usize) -> String {
+    if output.len() <= max_bytes {
+        return output.to_string();
+    }
+    let mut start = output.len() - max_bytes;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("[... {start} bytes truncated ...]\n{}", &output[start..])
+}
+
+/// Gate name made safe for use as a file name.
+fn file_name(gate: &str) -> String {
+    let name: String = gate
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.trim_matches('.').is_empty() {
+        "gate".to_string()
+    } else {
+        name
+    }
+}
+
+/// Remove all but the newest `keep` iteration directories. 0 keeps all.
+fn prune_artifacts(root: &Path, keep: u32) -> io::Result<()> {
+    if keep == 0 {
+        return Ok(());
+    }
+    let mut iterations: Vec<u32> = fs::read_dir(root)?
+        .filter_map(Result::ok)
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().to_str()?.parse().ok())
+        .collect();
+    iterations.sort_unstable();
+
+    let excess = iterations.len().saturating_sub(keep as usize);
+    for iteration in &iterations[..excess] {
+        fs::remove_dir_all(artifact_dir(root, *iteration))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn gate(name: &str, passed: bool, output: &str) -> GateResult {
+        GateResult {
+            name: name.to_string(),
+            passed,
+            output: output.to_string(),
+            duration_seconds: 1.5,
+        }
+    }
+
+    #[test]
+    fn test_save_gate_artifacts() {
+        let temp = TempDir::new().unwrap();
+        let config = ArtifactsConfig::default();
+        let gates = [
+            gate("lint", true, "ok\n"),
+            gate("e2e/smoke", false, "boom\n"),
+        ];
+
+        let paths = save_gate_artifacts_in(temp.path(), &config, 3, &gates).unwrap();
+
+        assert_eq!(paths[0], temp.path().join("3").join("lint.log"));
+        assert_eq!(paths[1], temp.path().join("3").join("e2e_smoke.log"));
+        let log = fs::read_to_string(&paths[1]).unwrap();
+        assert!(log.starts_with("# gate: e2e/smoke\n# status: failed\n"));
+        assert!(log.ends_with("boom\n"));
+    }
+
+    #[test]
+    fn test_save_stderr_artifact() {
+        let temp = TempDir::new().unwrap();
+        let config = ArtifactsConfig::default();
+
+        let path = save_stderr_artifact_in(temp.path(), &config, 4, "warning: slow\n")
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, temp.path().join("4").join(STDERR_LOG));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "warning: slow\n");
+
+        assert!(save_stderr_artifact_in(temp.path(), &config, 5, "  \n")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_save_gate_artifacts_disabled() {
+        let temp = TempDir::new().unwrap();
+        let config = ArtifactsConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        let paths = save_gate_artifacts_in(temp.path(), &config, 1, &[gate("test", true, "")]);
+        assert!(paths.unwrap().is_empty());
+        assert!(!temp.path().join("1").exists());
+    }
+
+    #[test]
+    fn test_cap_output_keeps_tail() {
+        assert_eq!(cap_output("short", 10), "short");
+        let capped = cap_output("aaaaaaaaaaerror: here", 11);
+        assert_eq!(capped, "[... 10 bytes truncated ...]\nerror: here");
+        // Never splits a multi-byte character
+        let capped = cap_output("ééé", 3);
+        assert!(capped.ends_with("é"));
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_iterations() {
+        let temp = TempDir::new().unwrap();
+        let config = ArtifactsConfig {
+            keep_iterations: 2,
+            ..Default::default()
+        };
+        for iteration in [1, 2, 10, 3] {
+            save_gate_artifacts_in(temp.path(), &config, iteration, &[gate("test", true, "")])
+                .unwrap();
+        }
+
+        assert!(!temp.path().join("1").exists());
+        assert!(!temp.path().join("2").exists());
+        assert!(temp.path().join("3").exists());
+        assert!(temp.path().join("10").exists());
+    }
+}
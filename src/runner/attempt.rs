@@ -0,0 +1,294 @@
+//! AI CLI attempts on tokio.
+//!
+//! An attempt spawns the AI CLI once and drives it from a single async
+//! task: stdout lines are handed to the caller as they arrive, stderr is
+//! drained alongside, and the CLI, with everything it started, is killed
+//! when it goes quiet for longer than the stall limit, when the caller
+//! sees a completion signal, or when the run's [`CancellationToken`] is
+//! cancelled by Ctrl-C or by quitting the TUI. Each attempt runs on its
+//! own current-thread runtime, so the loop around it stays synchronous.
+
+use std::future;
+use std::io;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+use tokio::io::BufReader;
+use tokio::time::{sleep_until, Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::capture::{collect_tail, read_line, OutputLine, MAX_LINE_BYTES};
+use super::process::ProcessTree;
+
+/// What the caller wants after seeing a line of output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    /// Keep reading.
+    Continue,
+    /// Stop the AI CLI, e.g. on a completion signal.
+    Stop,
+}
+
+/// How an attempt ended.
+#[derive(Debug)]
+pub enum AttemptEnd {
+    /// The AI CLI exited by itself.
+    Exited(ExitStatus),
+    /// The HTTP backend's reply ended.
+    Finished,
+    /// The caller asked for it to be stopped.
+    Stopped,
+    /// It printed nothing for the stall limit and was killed.
+    Stalled,
+    /// The cancellation token was cancelled and it was killed.
+    Cancelled,
+}
+
+/// The outcome of an attempt.
+#[derive(Debug)]
+pub struct Attempt {
+    /// How it ended.
+    pub end: AttemptEnd,
+    /// The tail of its stderr, if stderr was piped.
+    pub stderr: String,
+    /// Error that stopped stdout being read, if any.
+    pub read_error: Option<io::Error>,
+}
+
+/// Error type for running an attempt.
+#[derive(Debug, thiserror::Error)]
+pub enum AttemptError {
+    /// The async runtime could not be started.
+    #[error("Failed to start async runtime: {0}")]
+    Runtime(io::Error),
+    /// The AI CLI could not be started.
+    #[error("Failed to spawn AI CLI: {0}")]
+    Spawn(io::Error),
+    /// Waiting for the AI CLI to exit failed.
+    #[error("Failed to wait for AI CLI: {0}")]
+    Wait(io::Error),
+    /// The HTTP backend has no model to ask for.
+    #[error("The AI backend needs a model: set ai_cli.models, or ollama.model for Ollama")]
+    NoModel,
+    /// The HTTP backend could not be reached.
+    #[error("AI backend request failed: {0}")]
+    Request(String),
+    /// The HTTP backend refused the request.
+    #[error("AI backend returned {status}: {body}")]
+    Status {
+        /// HTTP status code.
+        status: u16,
+        /// Start of the response body.
+        body: String,
+    },
+}
+
+/// Run `command` to completion, calling `on_line` with each line of its
+/// stdout.
+///
+/// With a `stall` limit the command is killed once it has printed nothing
+/// for that long. Its stdout should be piped; stderr is captured if piped.
+pub fn run_attempt(
+    command: Command,
+    stall: Option<Duration>,
+    cancel: &CancellationToken,
+    on_line: impl FnMut(OutputLine) -> Flow,
+) -> Result<Attempt, AttemptError> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(AttemptError::Runtime)?
+        .block_on(drive(command, stall, cancel, on_line))
+}
+
+async fn drive(
+    command: Command,
+    stall: Option<Duration>,
+    cancel: &CancellationToken,
+    mut on_line: impl FnMut(OutputLine) -> Flow,
+) -> Result<Attempt, AttemptError> {
+    let mut child = tokio::process::Command::from(command)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(AttemptError::Spawn)?;
+    let tree = ProcessTree::attach_async(&child);
+    let stderr = child.stderr.take().map(|s| tokio::spawn(collect_tail(s)));
+
+    let mut read_error = None;
+    let mut end = None;
+    if let Some(stdout) = child.stdout.take() {
+        let mut reader = BufReader::new(stdout);
+        let mut last_output = Instant::now();
+        end = loop {
+            tokio::select! {
+                biased;
+                () = cancel.cancelled() => break Some(AttemptEnd::Cancelled),
+                () = idle(stall.map(|limit| last_output + limit)) => {
+                    break Some(AttemptEnd::Stalled)
+                }
+                line = read_line(&mut reader, MAX_LINE_BYTES) => match line {
+                    Ok(Some(line)) => {
+                        last_output = Instant::now();
+                        if on_line(line) == Flow::Stop {
+                            break Some(AttemptEnd::Stopped);
+                        }
+                    }
+                    Ok(None) => break None,
+                    Err(e) => {
+                        read_error = Some(e);
+                        break None;
+                    }
+                }
+            }
+        };
+    }
+
+    // A CLI that closed stdout can still be cancelled while it exits
+    let end = match end {
+        Some(end) => {
+            kill(tree.as_ref());
+            let _ = child.wait().await;
+            end
+        }
+        None => tokio::select! {
+            status = child.wait() => AttemptEnd::Exited(status.map_err(AttemptError::Wait)?),
+            () = cancel.cancelled() => {
+                kill(tree.as_ref());
+                let _ = child.wait().await;
+                AttemptEnd::Cancelled
+            }
+        },
+    };
+
+    let stderr = match stderr {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
+    Ok(Attempt {
+        end,
+        stderr,
+        read_error,
+    })
+}
+
+/// Resolves at `deadline`, or never without one.
+pub(super) async fn idle(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline).await,
+        None => future::pending().await,
+    }
+}
+
+fn kill(tree: Option<&ProcessTree>) {
+    if let Some(tree) = tree {
+        tree.kill();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::runner::process::shell_command;
+    use std::process::Stdio;
+    use std::time::Instant;
+
+    fn piped(script: &str) -> Command {
+        let mut cmd = shell_command(script);
+        cmd.current_dir(std::env::temp_dir())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        cmd
+    }
+
+    #[test]
+    fn test_attempt_streams_lines_and_stderr() {
+        let mut lines = Vec::new();
+        let attempt = run_attempt(
+            piped("echo one; echo oops >&2; echo two; exit 3"),
+            None,
+            &CancellationToken::new(),
+            |line| {
+                lines.push(line.text);
+                Flow::Continue
+            },
+        )
+        .unwrap();
+        assert_eq!(lines, ["one", "two"]);
+        assert_eq!(attempt.stderr, "oops");
+        assert!(matches!(attempt.end, AttemptEnd::Exited(s) if s.code() == Some(3)));
+    }
+
+    #[test]
+    fn test_attempt_stops_when_asked() {
+        let start = Instant::now();
+        let attempt = run_attempt(
+            piped("echo done; exec sleep 30"),
+            None,
+            &CancellationToken::new(),
+            |_| Flow::Stop,
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Stopped));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_attempt_kills_silent_process() {
+        let attempt = run_attempt(
+            piped("sleep 30"),
+            Some(Duration::from_millis(100)),
+            &CancellationToken::new(),
+            |_| Flow::Continue,
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Stalled));
+    }
+
+    #[test]
+    fn test_attempt_spares_active_process() {
+        let attempt = run_attempt(
+            piped("for i in 1 2 3 4 5; do echo $i; sleep 0.1; done"),
+            Some(Duration::from_millis(400)),
+            &CancellationToken::new(),
+            |_| Flow::Continue,
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Exited(s) if s.success()));
+    }
+
+    #[test]
+    fn test_attempt_kills_on_cancel() {
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            trigger.cancel();
+        });
+        let start = Instant::now();
+        let attempt = run_attempt(
+            piped("sleep 30"),
+            Some(Duration::from_secs(60)),
+            &cancel,
+            |_| Flow::Continue,
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Cancelled));
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // Still cancelled after stdout closes
+        let attempt = run_attempt(piped("exec >&-; sleep 30"), None, &cancel, |_| {
+            Flow::Continue
+        })
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Cancelled));
+    }
+
+    #[test]
+    fn test_attempt_spawn_failure() {
+        let mut cmd = Command::new("/nonexistent/ai-cli");
+        cmd.stdout(Stdio::piped());
+        let err =
+            run_attempt(cmd, None, &CancellationToken::new(), |_| Flow::Continue).unwrap_err();
+        assert!(matches!(err, AttemptError::Spawn(e) if e.kind() == io::ErrorKind::NotFound));
+    }
+}
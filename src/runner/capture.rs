@@ -0,0 +1,173 @@
+//! AI CLI output capture.
+//!
+//! The AI CLI's output is not trusted to be well-formed: it may contain
+//! invalid UTF-8, single lines of many megabytes (a tool result dumping a
+//! whole file), or stderr noise. Lines are decoded lossily and capped in
+//! length, and stderr is drained by its own task so a chatty CLI can't
+//! fill the pipe and stall while afk is still reading stdout.
+
+use std::collections::VecDeque;
+use std::io;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, BufReader};
+
+/// Longest line kept from the AI CLI; the rest is dropped.
+pub const MAX_LINE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Stderr kept per iteration; older output is dropped.
+pub const MAX_STDERR_BYTES: usize = 256 * 1024;
+
+/// One line of AI CLI output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLine {
+    /// The line without its terminator, with invalid UTF-8 replaced and a
+    /// marker appended if it was cut short.
+    pub text: String,
+    /// Bytes dropped from the end of the line.
+    pub truncated: usize,
+}
+
+/// Read one line, decoding lossily and dropping bytes past `max_bytes`.
+///
+/// Returns None at end of input. Never fails on invalid UTF-8, and never
+/// holds more than `max_bytes` of a line in memory. Not cancel-safe: a line
+/// partly read when the future is dropped is lost.
+pub async fn read_line<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> io::Result<Option<OutputLine>> {
+    let mut bytes = Vec::new();
+    let mut truncated = 0;
+    let mut read_any = false;
+    loop {
+        let buf = match reader.fill_buf().await {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            break;
+        }
+        read_any = true;
+        let (chunk, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (&buf[..i], Some(i + 1)),
+            None => (buf, None),
+        };
+        let room = max_bytes.saturating_sub(bytes.len());
+        bytes.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        truncated += chunk.len().saturating_sub(room);
+        let used = done.unwrap_or(chunk.len());
+        reader.consume(used);
+        if done.is_some() {
+            break;
+        }
+    }
+    if !read_any {
+        return Ok(None);
+    }
+
+    if bytes.last() == Some(&b'\r') {
+        bytes.pop();
+    }
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if truncated > 0 {
+        text.push_str(&format!(" [... {truncated} bytes truncated]"));
+    }
+    Ok(Some(OutputLine { text, truncated }))
+}
+
+/// Read a stream to its end, keeping the last `MAX_STDERR_BYTES`.
+pub async fn collect_tail<R: AsyncRead + Unpin>(stream: R) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut lines: VecDeque<String> = VecDeque::new();
+    let mut kept = 0;
+    let mut dropped = 0;
+    while let Ok(Some(line)) = read_line(&mut reader, MAX_STDERR_BYTES).await {
+        kept += line.text.len() + 1;
+        lines.push_back(line.text);
+        while kept > MAX_STDERR_BYTES && lines.len() > 1 {
+            if let Some(old) = lines.pop_front() {
+                kept -= old.len() + 1;
+                dropped += old.len() + 1;
+            }
+        }
+    }
+    let text = Vec::from(lines).join("\n");
+    if dropped > 0 {
+        format!("[... {dropped} bytes truncated ...]\n{text}")
+    } else {
+        text
+    }
+}
+
+/// Shorten text for display to at most `max_chars` characters.
+pub fn truncate_display(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{kept}...")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn lines(input: &[u8], max_bytes: usize) -> Vec<OutputLine> {
+        let mut reader = BufReader::with_capacity(8, input);
+        let mut lines = Vec::new();
+        while let Some(line) = read_line(&mut reader, max_bytes).await.unwrap() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn test_read_line_splits_and_strips_terminators() {
+        let read = lines(b"one\r\ntwo\n\nlast", 100).await;
+        let texts: Vec<&str> = read.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["one", "two", "", "last"]);
+        assert!(read.iter().all(|l| l.truncated == 0));
+    }
+
+    #[tokio::test]
+    async fn test_read_line_replaces_invalid_utf8() {
+        let read = lines(b"ok \xff\xfe bytes\nnext\n", 100).await;
+        assert_eq!(read[0].text, "ok \u{FFFD}\u{FFFD} bytes");
+        assert_eq!(read[1].text, "next");
+    }
+
+    #[tokio::test]
+    async fn test_read_line_caps_long_lines() {
+        let mut input = vec![b'x'; 50];
+        input.extend_from_slice(b"\nshort\n");
+        let read = lines(&input, 20).await;
+        assert_eq!(read[0].truncated, 30);
+        assert_eq!(
+            read[0].text,
+            format!("{} [... 30 bytes truncated]", "x".repeat(20))
+        );
+        assert_eq!(read[1].text, "short");
+    }
+
+    #[tokio::test]
+    async fn test_collect_tail_keeps_tail() {
+        assert_eq!(
+            collect_tail(&b"warn: a\nwarn: b\n"[..]).await,
+            "warn: a\nwarn: b"
+        );
+        assert_eq!(collect_tail(&b""[..]).await, "");
+
+        let noisy = ("e".repeat(1000) + "\n").repeat(400);
+        let text = collect_tail(noisy.as_bytes()).await;
+        assert!(text.starts_with("[... "));
+        assert!(text.len() <= MAX_STDERR_BYTES + 64);
+    }
+
+    #[test]
+    fn test_truncate_display_respects_char_boundaries() {
+        assert_eq!(truncate_display("short", 10), "short");
+        assert_eq!(truncate_display("ééééééé", 5), "éé...");
+        assert_eq!(truncate_display("abcdefgh", 6), "abc...");
+    }
+}
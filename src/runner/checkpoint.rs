@@ -0,0 +1,225 @@
+//! Iteration checkpoints.
+//!
+//! While an iteration runs, `.afk/iteration-state.json` records which phase
+//! it has reached (prompt built, AI CLI launched, gates running, committing)
+//! and the commit it started from. The loop removes the file once the
+//! iteration ends, so finding it on startup means afk or the machine died
+//! mid-iteration, and `afk go` offers to roll the iteration back or resume.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ITERATION_STATE_FILE;
+use crate::git::{commits_since, get_current_commit, reset_hard, stash_changes};
+
+/// How far an iteration got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IterationPhase {
+    /// The iteration has started; the prompt is not built yet.
+    Starting,
+    /// The prompt has been built.
+    PromptBuilt,
+    /// The AI CLI is running.
+    CliLaunched,
+    /// Quality gates are running (`afk verify`).
+    GatesRunning,
+    /// Gates passed; the work is being committed.
+    Committing,
+}
+
+impl fmt::Display for IterationPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Starting => "starting",
+            Self::PromptBuilt => "building the prompt",
+            Self::CliLaunched => "running the AI CLI",
+            Self::GatesRunning => "running quality gates",
+            Self::Committing => "committing",
+        };
+        f.write_str(text)
+    }
+}
+
+/// State of the iteration in progress.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IterationCheckpoint {
+    /// Iteration number within the run (1-based).
+    pub iteration: u32,
+    /// Task the iteration is working on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    /// Phase reached.
+    pub phase: IterationPhase,
+    /// Commit the iteration started from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<String>,
+    /// When the iteration started.
+    pub started_at: String,
+    /// When the phase was last updated.
+    pub updated_at: String,
+}
+
+/// What rolling back an interrupted iteration did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rollback {
+    /// Whether uncommitted changes were stashed.
+    pub stashed: bool,
+    /// Commits the branch was moved back past.
+    pub commits_undone: usize,
+}
+
+impl IterationCheckpoint {
+    /// Record the start of an iteration. Does nothing in read-only mode.
+    pub fn begin(iteration: u32, task_id: Option<String>, baseline: Option<String>) {
+        let now = timestamp();
+        let checkpoint = Self {
+            iteration,
+            task_id,
+            phase: IterationPhase::Starting,
+            baseline,
+            started_at: now.clone(),
+            updated_at: now,
+        };
+        checkpoint.save(None);
+    }
+
+    /// Load the checkpoint, if an iteration is (or was) in progress.
+    pub fn load(path: Option<&Path>) -> Option<Self> {
+        let path = path.unwrap_or(Path::new(ITERATION_STATE_FILE));
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+    }
+
+    /// Write the checkpoint. Failures are ignored; a checkpoint must never
+    /// interrupt the loop.
+    pub fn save(&self, path: Option<&Path>) {
+        if crate::config::is_read_only() {
+            return;
+        }
+        let path = path.unwrap_or(Path::new(ITERATION_STATE_FILE));
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    /// Remove the checkpoint once the iteration has ended.
+    pub fn clear(path: Option<&Path>) {
+        let path = path.unwrap_or(Path::new(ITERATION_STATE_FILE));
+        if path.exists() && !crate::config::is_read_only() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Undo the iteration: stash its uncommitted changes (outside `.afk/`)
+    /// and move the branch back to the commit it started from. Commits it
+    /// made stay reachable through the reflog.
+    pub fn rollback(&self) -> Rollback {
+        let stashed = stash_changes(&format!(
+            "afk: interrupted iteration {}{}",
+            self.iteration,
+            self.task_id
+                .as_deref()
+                .map(|id| format!(" ({id})"))
+                .unwrap_or_default()
+        ));
+        let mut commits_undone = 0;
+        if let Some(baseline) = &self.baseline {
+            if get_current_commit().as_ref() != Some(baseline) {
+                let undone = commits_since(baseline).len();
+                if reset_hard(baseline) {
+                    commits_undone = undone;
+                }
+            }
+        }
+        Rollback {
+            stashed,
+            commits_undone,
+        }
+    }
+}
+
+/// Record that the iteration in progress reached a phase. Does nothing when
+/// no iteration is in progress (e.g. `afk verify` run by hand).
+pub fn record_phase(phase: IterationPhase) {
+    record_phase_at(Path::new(ITERATION_STATE_FILE), phase);
+}
+
+/// Record a phase in the checkpoint stored at a path.
+fn record_phase_at(path: &Path, phase: IterationPhase) {
+    if let Some(mut checkpoint) = IterationCheckpoint::load(Some(path)) {
+        checkpoint.phase = phase;
+        checkpoint.updated_at = timestamp();
+        checkpoint.save(Some(path));
+    }
+}
+
+/// Current local time, to the second.
+fn timestamp() -> String {
+    Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn checkpoint() -> IterationCheckpoint {
+        IterationCheckpoint {
+            iteration: 3,
+            task_id: Some("auth-1".to_string()),
+            phase: IterationPhase::Starting,
+            baseline: Some("abc123".to_string()),
+            started_at: "2024-05-01T12:00:00".to_string(),
+            updated_at: "2024-05-01T12:00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip_and_clear() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(ITERATION_STATE_FILE);
+        assert!(IterationCheckpoint::load(Some(&path)).is_none());
+
+        checkpoint().save(Some(&path));
+        assert_eq!(IterationCheckpoint::load(Some(&path)), Some(checkpoint()));
+        let json = fs::read_to_string(&path).unwrap();
+        assert!(json.contains("\"phase\": \"starting\""));
+
+        IterationCheckpoint::clear(Some(&path));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_record_phase_updates_checkpoint() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(ITERATION_STATE_FILE);
+
+        // Without a checkpoint there is nothing to update
+        record_phase_at(&path, IterationPhase::GatesRunning);
+        assert!(!path.exists());
+
+        checkpoint().save(Some(&path));
+        record_phase_at(&path, IterationPhase::CliLaunched);
+        let loaded = IterationCheckpoint::load(Some(&path)).unwrap();
+        assert_eq!(loaded.phase, IterationPhase::CliLaunched);
+        assert_eq!(loaded.started_at, "2024-05-01T12:00:00");
+    }
+
+    #[test]
+    fn test_phase_display() {
+        assert_eq!(
+            IterationPhase::GatesRunning.to_string(),
+            "running quality gates"
+        );
+        assert_eq!(IterationPhase::Committing.to_string(), "committing");
+    }
+}
@@ -0,0 +1,243 @@
+//! CI-backed acceptance criteria.
+//!
+//! Acceptance criteria of the form `ci:<workflow>` are resolved by asking
+//! the repository's CI (via `gh run list`) for the latest run of that
+//! workflow on the current branch, so a task only completes once the real
+//! pipeline is green rather than just the local gates. A run for an older
+//! commit than HEAD doesn't count: CI hasn't seen the work yet.
+
+use std::process::Command;
+
+use crate::git::{get_current_branch, get_current_commit};
+use crate::prd::AcceptanceCriterion;
+
+/// Prefix marking an acceptance criterion as a CI check.
+pub const CI_CRITERION_PREFIX: &str = "ci:";
+
+/// Status of the latest run of a CI workflow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiStatus {
+    /// The latest run completed successfully.
+    Passed,
+    /// The latest run completed with a non-success conclusion.
+    Failed(String),
+    /// The latest run has not finished yet, or was for an older commit.
+    Pending,
+    /// No run of the workflow exists for the branch.
+    NotFound,
+    /// CI could not be queried.
+    Unavailable(String),
+}
+
+impl CiStatus {
+    /// Whether the workflow is green.
+    pub fn is_passed(&self) -> bool {
+        matches!(self, CiStatus::Passed)
+    }
+
+    /// Short human-readable description.
+    pub fn describe(&self) -> String {
+        match self {
+            CiStatus::Passed => "passed".to_string(),
+            CiStatus::Failed(conclusion) => format!("failed ({conclusion})"),
+            CiStatus::Pending => "still running".to_string(),
+            CiStatus::NotFound => "no runs for this branch".to_string(),
+            CiStatus::Unavailable(reason) => format!("unavailable: {reason}"),
+        }
+    }
+}
+
+/// Result of checking one CI criterion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CiCheck {
+    /// Workflow name from the criterion.
+    pub workflow: String,
+    /// Status of its latest run.
+    pub status: CiStatus,
+}
+
+/// Extract the workflow name from a `ci:<workflow>` criterion.
+pub fn ci_workflow(criterion: &str) -> Option<&str> {
+    let workflow = criterion.trim().strip_prefix(CI_CRITERION_PREFIX)?.trim();
+    if workflow.is_empty() {
+        None
+    } else {
+        Some(workflow)
+    }
+}
+
+/// Workflow names from all CI criteria in a list of acceptance criteria.
+pub fn ci_workflows(criteria: &[AcceptanceCriterion]) -> Vec<String> {
+    criteria
+        .iter()
+        .filter_map(|c| ci_workflow(c.as_str()))
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Check every CI criterion against the current branch.
+///
+/// Returns an empty list if there are no CI criteria.
+pub fn check_ci_criteria(criteria: &[AcceptanceCriterion]) -> Vec<CiCheck> {
+    let workflows = ci_workflows(criteria);
+    if workflows.is_empty() {
+        return Vec::new();
+    }
+
+    let branch = get_current_branch();
+    let head = get_current_commit();
+    workflows
+        .into_iter()
+        .map(|workflow| {
+            let status = match (&branch, &head) {
+                (Some(b), Some(h)) => check_ci_workflow(&workflow, b, h),
+                _ => CiStatus::Unavailable("not on a git branch".to_string()),
+            };
+            CiCheck { workflow, status }
+        })
+        .collect()
+}
+
+/// Why a list of acceptance criteria's CI checks don't pass, e.g.
+/// "test still running", or None if every one is green.
+pub fn ci_failure_details(criteria: &[AcceptanceCriterion]) -> Option<String> {
+    describe_unpassed(&check_ci_criteria(criteria))
+}
+
+/// Describe the checks that aren't green, or None if all are.
+pub fn describe_unpassed(checks: &[CiCheck]) -> Option<String> {
+    let failing: Vec<String> = checks
+        .iter()
+        .filter(|check| !check.status.is_passed())
+        .map(|check| format!("{} {}", check.workflow, check.status.describe()))
+        .collect();
+    (!failing.is_empty()).then(|| failing.join(", "))
+}
+
+/// Query the latest run of a workflow on a branch, which only counts if it
+/// ran on commit `head`.
+pub fn check_ci_workflow(workflow: &str, branch: &str, head: &str) -> CiStatus {
+    let output = match Command::new("gh")
+        .args([
+            "run",
+            "list",
+            "--workflow",
+            workflow,
+            "--branch",
+            branch,
+            "--limit",
+            "1",
+            "--json",
+            "headSha,status,conclusion",
+        ])
+        .output()
+    {
+        Ok(o) => o,
+        Err(e) => return CiStatus::Unavailable(format!("failed to run gh: {e}")),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return CiStatus::Unavailable(stderr.trim().to_string());
+    }
+
+    parse_run_list(&String::from_utf8_lossy(&output.stdout), head)
+}
+
+/// Parse `gh run list --json headSha,status,conclusion` output. A run for
+/// a commit other than `head` is pending: CI hasn't run on HEAD yet.
+fn parse_run_list(json: &str, head: &str) -> CiStatus {
+    let runs: Vec<serde_json::Value> = match serde_json::from_str(json) {
+        Ok(r) => r,
+        Err(e) => return CiStatus::Unavailable(format!("invalid gh output: {e}")),
+    };
+
+    let Some(run) = runs.first() else {
+        return CiStatus::NotFound;
+    };
+
+    let sha = run.get("headSha").and_then(|v| v.as_str()).unwrap_or("");
+    let status = run.get("status").and_then(|v| v.as_str()).unwrap_or("");
+    if sha != head || status != "completed" {
+        return CiStatus::Pending;
+    }
+
+    match run.get("conclusion").and_then(|v| v.as_str()) {
+        Some("success") => CiStatus::Passed,
+        Some(conclusion) if !conclusion.is_empty() => CiStatus::Failed(conclusion.to_string()),
+        _ => CiStatus::Failed("unknown".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_workflow_parsing() {
+        assert_eq!(ci_workflow("ci:build"), Some("build"));
+        assert_eq!(
+            ci_workflow("  ci: Release Pipeline "),
+            Some("Release Pipeline")
+        );
+        assert_eq!(ci_workflow("ci:"), None);
+        assert_eq!(ci_workflow("Tests pass in CI"), None);
+    }
+
+    #[test]
+    fn test_ci_workflows_filters_criteria() {
+        let criteria: Vec<AcceptanceCriterion> =
+            vec!["Button renders".into(), "ci:test".into(), "ci:lint".into()];
+        assert_eq!(ci_workflows(&criteria), vec!["test", "lint"]);
+        assert!(check_ci_criteria(&criteria[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_run_list() {
+        assert_eq!(
+            parse_run_list(
+                r#"[{"headSha":"abc","status":"completed","conclusion":"success"}]"#,
+                "abc"
+            ),
+            CiStatus::Passed
+        );
+        assert_eq!(
+            parse_run_list(
+                r#"[{"headSha":"abc","status":"completed","conclusion":"failure"}]"#,
+                "abc"
+            ),
+            CiStatus::Failed("failure".to_string())
+        );
+        assert_eq!(
+            parse_run_list(
+                r#"[{"headSha":"abc","status":"in_progress","conclusion":""}]"#,
+                "abc"
+            ),
+            CiStatus::Pending
+        );
+        assert_eq!(parse_run_list("[]", "abc"), CiStatus::NotFound);
+        assert!(matches!(
+            parse_run_list("not json", "abc"),
+            CiStatus::Unavailable(_)
+        ));
+    }
+
+    #[test]
+    fn test_parse_run_list_ignores_runs_for_other_commits() {
+        let older = r#"[{"headSha":"old","status":"completed","conclusion":"success"}]"#;
+        assert_eq!(parse_run_list(older, "new"), CiStatus::Pending);
+        let untagged = r#"[{"status":"completed","conclusion":"success"}]"#;
+        assert_eq!(parse_run_list(untagged, "new"), CiStatus::Pending);
+    }
+
+    #[test]
+    fn test_ci_status_describe() {
+        assert!(CiStatus::Passed.is_passed());
+        assert!(!CiStatus::Pending.is_passed());
+        assert_eq!(
+            CiStatus::Failed("cancelled".to_string()).describe(),
+            "failed (cancelled)"
+        );
+        assert_eq!(CiStatus::NotFound.describe(), "no runs for this branch");
+    }
+}
@@ -0,0 +1,261 @@
+//! Control files.
+//!
+//! External tools (cron jobs, CI, chat bots) can steer a running loop
+//! without an API by creating files under `.afk/control/`. The loop checks
+//! them between iterations: while `pause` exists it waits, and `stop` ends
+//! the run gracefully. `skip` marks the task being worked on as skipped once
+//! its iteration ends, so the loop moves on to another. The stop and skip
+//! files are removed once honoured so the next run starts normally. `afk
+//! pause`, `afk resume` and the TUI's `p`, `s` and `q` keys write them.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{CONTROL_DIR, PAUSE_FILE, SKIP_FILE, STOP_FILE};
+use crate::prd::PrdDocument;
+use crate::progress::{SessionProgress, TaskStatus};
+
+use super::StopReason;
+
+/// How often the pause file is re-checked while paused.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What the control files currently ask of the loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlState {
+    /// No control file is present.
+    Running,
+    /// `.afk/control/pause` exists.
+    Paused,
+    /// `.afk/control/stop` exists.
+    StopRequested,
+}
+
+impl fmt::Display for ControlState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Running => "running",
+            Self::Paused => "paused",
+            Self::StopRequested => "stop requested",
+        };
+        f.write_str(text)
+    }
+}
+
+/// The state requested through `.afk/control/`. A stop outranks a pause.
+pub fn control_state() -> ControlState {
+    state_in(Path::new(CONTROL_DIR))
+}
+
+fn state_in(dir: &Path) -> ControlState {
+    if dir.join(file_name(STOP_FILE)).exists() {
+        ControlState::StopRequested
+    } else if dir.join(file_name(PAUSE_FILE)).exists() {
+        ControlState::Paused
+    } else {
+        ControlState::Running
+    }
+}
+
+/// Honour the control files before an iteration.
+///
+/// Blocks while the loop is paused, calling `on_pause(true)` when it
+/// pauses and `on_pause(false)` when it resumes. Returns why the loop
+/// should stop, if it should.
+pub fn check_control(
+    interrupted: &CancellationToken,
+    on_pause: impl FnMut(bool),
+) -> Option<StopReason> {
+    check_in(Path::new(CONTROL_DIR), interrupted, POLL_INTERVAL, on_pause)
+}
+
+fn check_in(
+    dir: &Path,
+    interrupted: &CancellationToken,
+    poll: Duration,
+    mut on_pause: impl FnMut(bool),
+) -> Option<StopReason> {
+    let mut paused = false;
+    loop {
+        match state_in(dir) {
+            ControlState::StopRequested => {
+                if !crate::config::is_read_only() {
+                    let _ = fs::remove_file(dir.join(file_name(STOP_FILE)));
+                }
+                return Some(StopReason::ExternalStop);
+            }
+            ControlState::Paused => {
+                if interrupted.is_cancelled() {
+                    return Some(StopReason::UserInterrupt);
+                }
+                if !paused {
+                    paused = true;
+                    on_pause(true);
+                }
+                std::thread::sleep(poll);
+            }
+            ControlState::Running => {
+                if paused {
+                    on_pause(false);
+                }
+                return None;
+            }
+        }
+    }
+}
+
+/// Ask the loop to wait before its next iteration.
+pub fn request_pause() -> io::Result<()> {
+    create(Path::new(CONTROL_DIR), PAUSE_FILE)
+}
+
+/// Let a paused loop carry on. Returns false if it wasn't paused.
+pub fn resume() -> io::Result<bool> {
+    remove(Path::new(CONTROL_DIR), PAUSE_FILE)
+}
+
+/// Ask the loop to stop once its current iteration ends.
+pub fn request_stop() -> io::Result<()> {
+    create(Path::new(CONTROL_DIR), STOP_FILE)
+}
+
+/// Ask the loop to skip the task it is working on once its iteration ends.
+pub fn request_skip() -> io::Result<()> {
+    create(Path::new(CONTROL_DIR), SKIP_FILE)
+}
+
+/// Whether a skip was requested, clearing the request.
+pub fn take_skip_request() -> bool {
+    !crate::config::is_read_only() && remove(Path::new(CONTROL_DIR), SKIP_FILE).unwrap_or(false)
+}
+
+/// Honour a skip requested during an iteration on `task` (id and source).
+///
+/// Unless the task passed in `prd`, it is marked skipped so the loop picks
+/// another. Returns a message saying so, if it was.
+pub fn skip_if_requested(task: Option<(&str, &str)>, prd: &PrdDocument) -> Option<String> {
+    if !take_skip_request() {
+        return None;
+    }
+    let (id, source) = task?;
+    if prd.get_story(id).is_some_and(|s| s.passes) {
+        return None;
+    }
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    progress.set_task_status(
+        id,
+        TaskStatus::Skipped,
+        source,
+        Some("Skipped by request".to_string()),
+    );
+    let _ = progress.save(None);
+    Some(format!("Skipped {id}; `afk reset {id}` brings it back"))
+}
+
+fn create(dir: &Path, file: &str) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(file_name(file)), "")
+}
+
+fn remove(dir: &Path, file: &str) -> io::Result<bool> {
+    match fs::remove_file(dir.join(file_name(file))) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Final component of a control file path.
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const POLL: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn test_state_in() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(state_in(temp.path()), ControlState::Running);
+        fs::write(temp.path().join("pause"), "").unwrap();
+        assert_eq!(state_in(temp.path()), ControlState::Paused);
+        fs::write(temp.path().join("stop"), "").unwrap();
+        assert_eq!(state_in(temp.path()), ControlState::StopRequested);
+        assert_eq!(ControlState::StopRequested.to_string(), "stop requested");
+    }
+
+    #[test]
+    fn test_stop_file_is_consumed() {
+        let temp = TempDir::new().unwrap();
+        let interrupted = CancellationToken::new();
+        assert_eq!(check_in(temp.path(), &interrupted, POLL, |_| {}), None);
+
+        fs::write(temp.path().join("stop"), "").unwrap();
+        assert_eq!(
+            check_in(temp.path(), &interrupted, POLL, |_| {}),
+            Some(StopReason::ExternalStop)
+        );
+        assert!(!temp.path().join("stop").exists());
+    }
+
+    #[test]
+    fn test_pause_waits_until_removed() {
+        let temp = TempDir::new().unwrap();
+        let pause = temp.path().join("pause");
+        fs::write(&pause, "").unwrap();
+        let remover = {
+            let pause = pause.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                fs::remove_file(pause).unwrap();
+            })
+        };
+
+        let mut changes = Vec::new();
+        let interrupted = CancellationToken::new();
+        let result = check_in(temp.path(), &interrupted, POLL, |p| changes.push(p));
+        remover.join().unwrap();
+        assert_eq!(result, None);
+        assert_eq!(changes, vec![true, false]);
+    }
+
+    #[test]
+    fn test_create_and_remove_requests() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("control");
+        create(&dir, PAUSE_FILE).unwrap();
+        create(&dir, SKIP_FILE).unwrap();
+        assert_eq!(state_in(&dir), ControlState::Paused);
+        assert!(remove(&dir, PAUSE_FILE).unwrap());
+        assert!(!remove(&dir, PAUSE_FILE).unwrap());
+        assert_eq!(state_in(&dir), ControlState::Running);
+        assert!(dir.join("skip").exists());
+    }
+
+    #[test]
+    fn test_interrupt_or_stop_ends_pause() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("pause"), "").unwrap();
+        let interrupted = CancellationToken::new();
+        interrupted.cancel();
+        assert_eq!(
+            check_in(temp.path(), &interrupted, POLL, |_| {}),
+            Some(StopReason::UserInterrupt)
+        );
+
+        fs::write(temp.path().join("stop"), "").unwrap();
+        assert_eq!(
+            check_in(temp.path(), &CancellationToken::new(), POLL, |_| {}),
+            Some(StopReason::ExternalStop)
+        );
+    }
+}
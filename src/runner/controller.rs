@@ -3,24 +3,55 @@
 //! This module implements the main loop lifecycle, including limits,
 //! stop conditions, and session management.
 
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
-
-use crate::config::AfkConfig;
-use crate::prd::{mark_story_in_progress, sync_prd_with_root, PrdDocument};
-
-use super::iteration::IterationRunner;
-use super::make_path_relative;
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, AiBackend, RetryPolicy, STOP_FILE, WARMUP_FILE};
+use crate::events::{self, Event};
+use crate::feedback::Notifier;
+use crate::git::changes::{self, FileChange, FileStatus};
+use crate::git::{commits_since, get_current_commit};
+use crate::plugins::{PluginEvent, PluginHost};
+use crate::prd::{mark_story_in_progress, sync_prd_with_root, PrdDocument, TaskOrder, UserStory};
+use crate::progress::transcript::IterationLog;
+use crate::progress::{charge_iteration_time, session_budget_exceeded, Selection, SessionProgress};
+
+use super::agent_result;
+use super::artifacts::save_stderr_artifact;
+use super::attempt::{run_attempt, AttemptEnd, AttemptError, Flow};
+use super::capture::{truncate_display, OutputLine};
+use super::checkpoint::{record_phase, IterationCheckpoint, IterationPhase};
+use super::control::{check_control, take_skip_request};
+use super::escalation::{self, ModelChoice};
+use super::gate_revert::gate_baseline;
+use super::guardrails::guardrail_baseline;
+use super::hooks::{self, run_hook, HookContext, HookEvent};
+use super::http_backend::{request_line, run_http_attempt, run_ollama_attempt};
+use super::iteration::{IterationResult, IterationRunner};
 use super::output_handler::{FeedbackMode, OutputHandler};
-use super::{RunOptions, RunResult, StopReason};
+use super::overrides;
+use super::process::program_command;
+use super::retry::{self, RetryAction, RetryState};
+use super::settle::{record_burndown, settle, Finished, Notice};
+use super::stall::{retry_message, retry_prompt, stall_limit, stalled_error, STALLED};
+use super::task_branch::TaskBranches;
+use super::task_pr::open_completed_prs;
+use super::tracking::TrackingReporter;
+use super::warmup::{run_warmup, warmup_needed};
+use super::{format_time_budget, RunOptions, RunResult, StopReason};
+use super::{make_path_relative, tool_target};
 
 /// Controls the main loop lifecycle.
 pub struct LoopController {
     config: AfkConfig,
     output: OutputHandler,
     iteration_runner: IterationRunner,
-    interrupted: Arc<AtomicBool>,
+    interrupted: CancellationToken,
+    plugins: PluginHost,
+    time_budget: Option<Duration>,
+    tracking: Option<TrackingReporter>,
+    notifier: Option<Notifier>,
 }
 
 impl LoopController {
@@ -41,37 +72,66 @@ impl LoopController {
             config.feedback.thinking_threshold_secs,
         );
 
+        output.set_show_files(config.feedback.show_files);
+
         let mut iter_output = OutputHandler::with_feedback(feedback_mode, show_mascot);
+        iter_output.set_show_files(config.feedback.show_files);
         iter_output.set_activity_thresholds(
             config.feedback.active_threshold_secs,
             config.feedback.thinking_threshold_secs,
         );
 
-        let iteration_runner = IterationRunner::with_output_handler(config.clone(), iter_output);
+        let interrupted = CancellationToken::new();
+        let mut iteration_runner =
+            IterationRunner::with_output_handler(config.clone(), iter_output);
+        iteration_runner.set_cancel_token(interrupted.clone());
+        let tracking = TrackingReporter::new(&config.reporting);
+        let notifier = Notifier::new(&config.notifications);
 
         Self {
             config,
             output,
             iteration_runner,
-            interrupted: Arc::new(AtomicBool::new(false)),
+            interrupted,
+            plugins: PluginHost::default(),
+            time_budget: None,
+            tracking,
+            notifier,
         }
     }
 
     /// Create with custom output handler (legacy).
     pub fn with_output(config: AfkConfig, output: OutputHandler) -> Self {
-        let iteration_runner =
+        let interrupted = CancellationToken::new();
+        let mut iteration_runner =
             IterationRunner::with_output_handler(config.clone(), OutputHandler::new());
+        iteration_runner.set_cancel_token(interrupted.clone());
+        let tracking = TrackingReporter::new(&config.reporting);
+        let notifier = Notifier::new(&config.notifications);
 
         Self {
             config,
             output,
             iteration_runner,
-            interrupted: Arc::new(AtomicBool::new(false)),
+            interrupted,
+            plugins: PluginHost::default(),
+            time_budget: None,
+            tracking,
+            notifier,
         }
     }
 
-    /// Get the interrupt flag for external signaling.
-    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+    /// Run against a wall-clock budget instead of the session timeout.
+    ///
+    /// The in-flight iteration always finishes; no new one starts once the
+    /// budget is used up.
+    pub fn set_time_budget(&mut self, budget: Option<Duration>) {
+        self.time_budget = budget;
+    }
+
+    /// Get the token that interrupts the loop, and the running AI CLI,
+    /// when cancelled.
+    pub fn cancel_token(&self) -> CancellationToken {
         self.interrupted.clone()
     }
 
@@ -119,8 +179,26 @@ impl LoopController {
             }
         };
 
+        // Load plugins before doing any work, so policy is never skipped
+        self.plugins = match PluginHost::load(&self.config.plugins) {
+            Ok(plugins) => plugins,
+            Err(e) => {
+                let error_msg = e.to_string();
+                self.output.error(&error_msg);
+                return RunResult {
+                    iterations_completed: 0,
+                    tasks_completed: 0,
+                    stop_reason: StopReason::AiError(Some(error_msg)),
+                    duration_seconds: start_time.elapsed().as_secs_f64(),
+                    archived_to: None,
+                };
+            }
+        };
+        // Their gates run wherever the loop runs gates
+        self.plugins.add_gates(&mut self.config.feedback_loops);
+
         // Check if there are any tasks
-        let pending_stories = prd.get_pending_stories();
+        let pending_stories = prd.get_pending_stories(&TaskOrder::load(&self.config));
         if pending_stories.is_empty() {
             if prd.user_stories.is_empty() {
                 self.output.info("No tasks found. Add tasks to continue.");
@@ -164,8 +242,30 @@ impl LoopController {
             .set_iteration_context(1, display_limit, task_id, task_description);
 
         // Main loop
+        self.plugins.emit(&PluginEvent::LoopStart {
+            max_iterations: max_iter,
+        });
+        events::record(Event::LoopStart {
+            max_iterations: max_iter,
+        });
         let result =
             self.run_main_loop(max_iter, until_complete, timeout_override, start_time, &prd);
+        self.plugins.emit(&PluginEvent::LoopEnd {
+            reason: result.stop_reason.to_string(),
+        });
+        record_loop_end(&result);
+        if let Some(tracking) = self.tracking.as_mut() {
+            tracking.finish();
+        }
+        if let Some(notifier) = &self.notifier {
+            for warning in notifier.run_end(
+                &result.stop_reason.to_string(),
+                result.iterations_completed,
+                result.tasks_completed,
+            ) {
+                self.output.warning(&warning);
+            }
+        }
 
         // Display session complete panel
         self.output.session_complete_panel(
@@ -192,20 +292,57 @@ impl LoopController {
         let stop_reason;
 
         let timeout_minutes = timeout_override.unwrap_or(self.config.limits.timeout_minutes);
-        let timeout_duration = std::time::Duration::from_secs(timeout_minutes as u64 * 60);
+        let (time_limit, limit_reason) = session_limit(timeout_minutes, self.time_budget);
+        if let Some(budget) = self.time_budget {
+            self.output
+                .info(&format!("Focus run: {} budget", format_time_budget(budget)));
+        }
+
+        if warmup_needed(&self.config) {
+            self.output
+                .info("Warm-up: reading the repo before the first task...");
+            match run_warmup(&self.config) {
+                Ok(_) => self
+                    .output
+                    .success(&format!("Warm-up notes saved to {WARMUP_FILE}")),
+                Err(e) => self.output.warning(&format!("Warm-up failed: {e}")),
+            }
+        }
+
+        let mut branches = TaskBranches::new(&self.config);
+        let mut retries = RetryState::default();
+        record_burndown(prd);
+        // A skip requested while nothing was running has no task to apply to
+        take_skip_request();
 
         loop {
             // Check for user interrupt
-            if self.interrupted.load(Ordering::SeqCst) {
+            if self.interrupted.is_cancelled() {
                 stop_reason = StopReason::UserInterrupt;
                 self.output.info("User interrupted");
                 break;
             }
 
-            // Check timeout
-            if start_time.elapsed() >= timeout_duration {
-                stop_reason = StopReason::Timeout;
-                self.output.warning("Session timeout reached");
+            // Honour .afk/control/pause and .afk/control/stop
+            let output = &self.output;
+            if let Some(reason) = check_control(&self.interrupted, |paused| {
+                if paused {
+                    output.warning("Paused: run `afk resume` to carry on");
+                } else {
+                    output.info("Resumed");
+                }
+            }) {
+                if reason == StopReason::ExternalStop {
+                    self.output.info(&format!("Stop requested via {STOP_FILE}"));
+                }
+                stop_reason = reason;
+                break;
+            }
+
+            // Check timeout or time budget
+            if start_time.elapsed() >= time_limit {
+                self.output.warning(&limit_reason.to_string());
+                stop_reason = limit_reason;
                 break;
             }
 
@@ -216,6 +353,13 @@ impl LoopController {
                 break;
             }
 
+            // Check the session's time budget
+            if session_over_budget(&self.config) {
+                stop_reason = StopReason::SessionBudgetExceeded;
+                self.output.warning(&stop_reason.to_string());
+                break;
+            }
+
             // Reload PRD to check completion
             let mut current_prd = match PrdDocument::load(None) {
                 Ok(p) => p,
@@ -230,10 +374,10 @@ impl LoopController {
                         .info("Local tasks complete, checking sources for more work...");
                     match sync_prd_with_root(&self.config, None, None) {
                         Ok(new_prd) => {
-                            if !new_prd.get_pending_stories().is_empty() {
+                            if new_prd.pending_count() > 0 {
                                 self.output.info(&format!(
                                     "Found {} more tasks from sources",
-                                    new_prd.get_pending_stories().len()
+                                    new_prd.pending_count()
                                 ));
                                 current_prd = new_prd;
                             } else {
@@ -256,10 +400,11 @@ impl LoopController {
             }
 
             // Get next task
-            let pending = current_prd.get_pending_stories();
+            let selection = select_tasks(&self.config, &current_prd);
+            let pending = selection.stories(&current_prd);
             if pending.is_empty() && !until_complete {
                 stop_reason = StopReason::NoTasks;
-                self.output.info("No more pending tasks");
+                self.output.info(&no_tasks_message(&selection));
                 break;
             }
 
@@ -268,13 +413,86 @@ impl LoopController {
                 let _ = mark_story_in_progress(&task.id);
             }
 
+            // Work on the task's own branch (git.auto_branch)
+            if let (Some(branches), Some(task)) = (branches.as_mut(), pending.first()) {
+                match branches.enter(&task.id) {
+                    Ok(Some(message)) => self.output.info(&message),
+                    Ok(None) => {}
+                    Err(e) => self.output.warning(&format!("Task branch: {e}")),
+                }
+            }
+
             // Run iteration
             let iteration = iterations_completed + 1;
+            let task_id = pending.first().map(|t| t.id.clone());
+            let baseline = guardrail_baseline(&self.config.guardrails);
+            let plugin_start = plugin_baseline(&self.plugins);
+            self.plugins.emit(&PluginEvent::IterationStart {
+                iteration,
+                task_id: task_id.clone(),
+            });
+            let iter_start = Instant::now();
+            let event_start = record_iteration_start(iteration, pending.first().copied());
+            let snapshot = changes::Snapshot::take();
+            let gate_start = gate_baseline(&self.config);
+            IterationCheckpoint::begin(iteration, task_id.clone(), event_start.clone());
+            let hook_context = HookContext::for_task(iteration, pending.first().copied());
+            if let Some(warning) =
+                run_hook(&self.config.hooks, HookEvent::PreIteration, &hook_context)
+            {
+                self.output.warning(&warning);
+            }
+            let failures_before = hooks::task_failures(task_id.as_deref());
             let result = self.iteration_runner.run(iteration, None);
 
             iterations_completed += 1;
+            let files = record_iteration_end(
+                &self.config,
+                iteration,
+                task_id.clone(),
+                &result,
+                iter_start.elapsed(),
+                event_start.as_deref(),
+                snapshot.as_ref(),
+            );
+            self.output.changed_files(&files);
+            IterationCheckpoint::clear(None);
+            // Record the outcome the agent reported, in case it didn't
+            if let Some(reported) = &result.agent_result {
+                for message in agent_result::apply(reported, task_id.as_deref(), None) {
+                    self.output.info(&message);
+                }
+            }
+            if let Some(skipped) =
+                charge_iteration(&self.config, task_id.as_deref(), iter_start.elapsed())
+            {
+                self.output.warning(&skipped);
+            }
+            let hook_warnings = [
+                hooks::after_iteration(&self.config.hooks, &hook_context, &result, failures_before),
+                hooks::on_tasks_completed(&self.config.hooks, iteration, &current_prd),
+            ];
+            for warning in hook_warnings.iter().flatten() {
+                self.output.warning(warning);
+            }
+            if let Some(notifier) = self.notifier.as_mut() {
+                let failures = (failures_before, hooks::task_failures(task_id.as_deref()));
+                for warning in notifier.after_iteration(
+                    pending.first().map(|t| (t.id.as_str(), t.title.as_str())),
+                    failures,
+                    self.config.limits.max_task_failures,
+                    made_commits(event_start.as_deref()),
+                ) {
+                    self.output.warning(&warning);
+                }
+            }
+            sync_failed_task(pending.first().copied(), failures_before, &self.config);
+            if let Some(tracking) = self.tracking.as_mut() {
+                tracking.after_iteration(iteration);
+            }
 
             // Handle result
+            let mut failure = None;
             if !result.success {
                 if let Some(ref error) = result.error {
                     if error == "AFK_COMPLETE" {
@@ -283,23 +501,100 @@ impl LoopController {
                     } else if error == "AFK_LIMIT_REACHED" {
                         stop_reason = StopReason::MaxIterations;
                         break;
-                    } else {
-                        self.output.error(error);
-                        stop_reason = StopReason::AiError(Some(error.clone()));
+                    } else if error == "User interrupted" {
+                        stop_reason = StopReason::UserInterrupt;
+                        self.output.info("User interrupted");
                         break;
+                    } else {
+                        // Carry on past failures `retry` says to retry
+                        failure = retry::classify_error(error, &result.output);
+                        if let Some(kind) = failure.filter(|kind| {
+                            retry::policy(&self.config.retry, *kind) == RetryPolicy::Stop
+                        }) {
+                            let task = pending.first().map(|t| (t.id.as_str(), t.source.as_str()));
+                            retries.after_failure(&self.config, task, kind);
+                            failure = None;
+                        }
+                        if failure.is_none() {
+                            self.output.error(error);
+                            stop_reason = StopReason::AiError(Some(error.clone()));
+                            break;
+                        }
+                        self.output.warning(error);
                     }
                 }
             }
 
-            // Check if task was completed (PRD updated)
-            let updated_prd = PrdDocument::load(None).unwrap_or(current_prd.clone());
-            let old_completed = current_prd.user_stories.iter().filter(|s| s.passes).count();
-            let new_completed = updated_prd.user_stories.iter().filter(|s| s.passes).count();
-            if new_completed > old_completed {
-                tasks_completed += (new_completed - old_completed) as u32;
+            // Settle the iteration: reverts, vetoes, holds and guardrails
+            let finished = Finished {
+                iteration,
+                task: pending.first().copied(),
+                prd: &current_prd,
+                guardrail_start: baseline.as_deref(),
+                gate_start: gate_start.as_ref(),
+                plugin_start: plugin_start.as_deref(),
+                changed: !files.is_empty() || made_commits(event_start.as_deref()),
+                failure,
+            };
+            let output = &self.output;
+            let settled = settle(
+                &self.config,
+                &self.plugins,
+                branches.as_mut(),
+                &finished,
+                &mut |notice| match notice {
+                    Notice::Warning(message) => output.warning(&message),
+                    Notice::Info(message) | Notice::Review(message) | Notice::Skipped(message) => {
+                        output.info(&message)
+                    }
+                },
+            );
+            tasks_completed += settled.tasks_completed;
+            self.iteration_runner.set_prompt_notes(settled.notes);
+            if let Some(reason) = settled.stop {
+                stop_reason = reason;
+                break;
+            }
+
+            // Then respond to a failed iteration as `retry` says
+            let Some(kind) = settled.failure else {
+                retries.after_success(task_id.as_deref());
+                continue;
+            };
+            let task = pending.first().map(|t| (t.id.as_str(), t.source.as_str()));
+            match retries.after_failure(&self.config, task, kind) {
+                RetryAction::Continue(message) | RetryAction::Skipped(message) => {
+                    self.output.warning(&message);
+                }
+                RetryAction::Wait(delay, message) => {
+                    self.output.warning(&message);
+                    if !retry::wait(delay, &self.interrupted) {
+                        stop_reason = StopReason::UserInterrupt;
+                        self.output.info("User interrupted");
+                        break;
+                    }
+                }
+                RetryAction::Stop(message) => {
+                    self.output.error(&message);
+                    stop_reason = StopReason::Failure(message);
+                    break;
+                }
+            }
+        }
 
-                // Sync completed beads tasks back to beads
-                sync_completed_tasks(&current_prd, &updated_prd);
+        // Leave the last task's branch, merging it if the task passed, or
+        // open pull requests for tasks completed on the run's branch
+        let final_prd = PrdDocument::load(None).unwrap_or_else(|_| prd.clone());
+        if stop_reason == StopReason::Complete {
+            record_burndown(&final_prd);
+        }
+        if let Some(branches) = branches.as_mut() {
+            if let Some(outcome) = branches.close(&final_prd) {
+                self.report_branch(&outcome.message(branches.base()));
+            }
+        } else {
+            for message in open_completed_prs(&self.config, prd, &final_prd) {
+                self.report_branch(&message);
             }
         }
 
@@ -336,6 +631,15 @@ impl LoopController {
         }
     }
 
+    /// Print a task branch or pull request outcome.
+    fn report_branch(&self, (message, is_warning): &(String, bool)) {
+        if *is_warning {
+            self.output.warning(message);
+        } else {
+            self.output.info(message);
+        }
+    }
+
     /// Get reference to output handler.
     pub fn output_handler(&self) -> &OutputHandler {
         &self.output
@@ -348,14 +652,19 @@ impl LoopController {
 pub fn run_loop_with_options(config: &AfkConfig, options: RunOptions) -> RunResult {
     use super::sleep_guard::SleepGuard;
 
+    if options.workers > 1 {
+        return super::pool::run_pool_with_options(config, options);
+    }
+
     let mut controller =
         LoopController::with_feedback(config.clone(), options.feedback_mode, options.show_mascot);
+    controller.set_time_budget(options.time_budget);
 
     // Set up Ctrl+C handler
-    let interrupt_flag = controller.interrupt_flag();
+    let cancel = controller.cancel_token();
     let handler_result = ctrlc::set_handler(move || {
-        // Set the interrupt flag
-        interrupt_flag.store(true, Ordering::SeqCst);
+        // Stop the loop and the running AI CLI
+        cancel.cancel();
         eprintln!("\n\x1b[33mInterrupting... press Ctrl+C again to force quit\x1b[0m");
     });
 
@@ -401,6 +710,8 @@ pub fn run_loop(
         resume,
         feedback_mode: FeedbackMode::Minimal,
         show_mascot: true,
+        time_budget: None,
+        workers: 1,
     };
     run_loop_with_options(config, options)
 }
@@ -414,6 +725,7 @@ pub fn run_loop_with_tui(config: &AfkConfig, options: RunOptions) -> RunResult {
     use crate::tui::{TuiApp, TuiEvent};
     use crate::watcher::{ChangeType, FileWatcher};
     use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+    use std::sync::Arc;
     use std::thread;
 
     // Try to create TUI app
@@ -440,8 +752,8 @@ pub fn run_loop_with_tui(config: &AfkConfig, options: RunOptions) -> RunResult {
     let config_clone = config.clone();
     let options_clone = options.clone();
 
-    // Shared interrupt flag for graceful shutdown
-    let interrupted = Arc::new(AtomicBool::new(false));
+    // Shared cancellation token for graceful shutdown
+    let interrupted = CancellationToken::new();
     let interrupted_runner = interrupted.clone();
 
     // Start file watcher in a separate thread
@@ -473,7 +785,11 @@ pub fn run_loop_with_tui(config: &AfkConfig, options: RunOptions) -> RunResult {
 
     // Spawn the runner in a background thread
     let runner_handle = thread::spawn(move || {
-        run_loop_with_tui_sender(&config_clone, options_clone, tx, interrupted_runner)
+        if options_clone.workers > 1 {
+            super::pool::run_pool(&config_clone, &options_clone, Some(tx), interrupted_runner)
+        } else {
+            run_loop_with_tui_sender(&config_clone, options_clone, tx, interrupted_runner)
+        }
     });
 
     // Run TUI in main thread (handles input and rendering)
@@ -482,7 +798,7 @@ pub fn run_loop_with_tui(config: &AfkConfig, options: RunOptions) -> RunResult {
     }
 
     // Signal runner to stop (user pressed Q or Esc)
-    interrupted.store(true, AtomicOrdering::SeqCst);
+    interrupted.cancel();
 
     // Stop file watcher
     watcher_running.store(false, std::sync::atomic::Ordering::SeqCst);
@@ -509,7 +825,7 @@ fn run_loop_with_tui_sender(
     config: &AfkConfig,
     options: RunOptions,
     tx: std::sync::mpsc::Sender<crate::tui::TuiEvent>,
-    interrupted: Arc<AtomicBool>,
+    interrupted: CancellationToken,
 ) -> RunResult {
     use crate::tui::TuiEvent;
 
@@ -546,8 +862,34 @@ fn run_loop_with_tui_sender(
         }
     };
 
+    // Load plugins before doing any work, so policy is never skipped
+    let plugins = match PluginHost::load(&config.plugins) {
+        Ok(plugins) => plugins,
+        Err(e) => {
+            let error_msg = e.to_string();
+            let _ = tx.send(TuiEvent::Error(error_msg.clone()));
+            let _ = tx.send(TuiEvent::SessionComplete {
+                iterations: 0,
+                tasks: 0,
+                duration: start_time.elapsed().as_secs_f64(),
+                reason: "Plugin load failed".to_string(),
+            });
+            return RunResult {
+                iterations_completed: 0,
+                tasks_completed: 0,
+                stop_reason: super::StopReason::AiError(Some(error_msg)),
+                duration_seconds: start_time.elapsed().as_secs_f64(),
+                archived_to: None,
+            };
+        }
+    };
+    // Their gates run wherever the loop runs gates
+    let mut config = config.clone();
+    plugins.add_gates(&mut config.feedback_loops);
+    let config = &config;
+
     // Check if there are any tasks
-    let pending_stories = prd.get_pending_stories();
+    let pending_stories = prd.get_pending_stories(&TaskOrder::load(config));
     if pending_stories.is_empty() {
         let reason = if prd.user_stories.is_empty() {
             "No tasks found"
@@ -586,6 +928,7 @@ fn run_loop_with_tui_sender(
         let _ = tx.send(TuiEvent::TaskInfo {
             id: task.id.clone(),
             title: task.title.clone(),
+            epic: task.epic.clone(),
         });
     }
 
@@ -595,27 +938,73 @@ fn run_loop_with_tui_sender(
         pending: task_count,
         complete: initial_complete,
     });
+    let _ = tx.send(TuiEvent::Burndown(record_burndown(&prd)));
+    take_skip_request();
 
     // Main loop
     let mut iterations_completed: u32 = 0;
     let mut tasks_completed: u32 = 0;
+    let mut prompt_notes: Vec<String> = Vec::new();
+    let mut tracking = TrackingReporter::new(&config.reporting);
+    let mut notifier = Notifier::new(&config.notifications);
+    let mut branches = TaskBranches::new(config);
+    let mut retries = RetryState::default();
     let stop_reason;
 
     let timeout_minutes = options
         .timeout_minutes
         .unwrap_or(config.limits.timeout_minutes);
-    let timeout_duration = std::time::Duration::from_secs(timeout_minutes as u64 * 60);
+    let (time_limit, limit_reason) = session_limit(timeout_minutes, options.time_budget);
+    if let Some(budget) = options.time_budget {
+        let _ = tx.send(TuiEvent::TimeBudget(budget));
+    }
+
+    if warmup_needed(config) {
+        let _ = tx.send(TuiEvent::OutputLine(
+            "Warm-up: reading the repo before the first task...".to_string(),
+        ));
+        match run_warmup(config) {
+            Ok(_) => {
+                let _ = tx.send(TuiEvent::OutputLine(format!(
+                    "Warm-up notes saved to {WARMUP_FILE}"
+                )));
+            }
+            Err(e) => {
+                let _ = tx.send(TuiEvent::Warning(format!("Warm-up failed: {e}")));
+            }
+        }
+    }
+
+    plugins.emit(&PluginEvent::LoopStart {
+        max_iterations: max_iter,
+    });
+    events::record(Event::LoopStart {
+        max_iterations: max_iter,
+    });
 
     loop {
         // Check for user interrupt (Q pressed in TUI)
-        if interrupted.load(Ordering::SeqCst) {
+        if interrupted.is_cancelled() {
             stop_reason = super::StopReason::UserInterrupt;
             break;
         }
 
-        // Check timeout
-        if start_time.elapsed() >= timeout_duration {
-            stop_reason = super::StopReason::Timeout;
+        // Honour .afk/control/pause and .afk/control/stop
+        if let Some(reason) = check_control(&interrupted, |paused| {
+            let _ = tx.send(TuiEvent::Paused(paused));
+            let _ = tx.send(if paused {
+                TuiEvent::Warning("Paused: run `afk resume` to carry on".to_string())
+            } else {
+                TuiEvent::OutputLine("Resumed".to_string())
+            });
+        }) {
+            stop_reason = reason;
+            break;
+        }
+
+        // Check timeout or time budget
+        if start_time.elapsed() >= time_limit {
+            stop_reason = limit_reason;
             break;
         }
 
@@ -625,6 +1014,12 @@ fn run_loop_with_tui_sender(
             break;
         }
 
+        // Check the session's time budget
+        if session_over_budget(config) {
+            stop_reason = super::StopReason::SessionBudgetExceeded;
+            break;
+        }
+
         // Reload PRD to check completion
         let mut current_prd = match PrdDocument::load(None) {
             Ok(p) => p,
@@ -640,10 +1035,10 @@ fn run_loop_with_tui_sender(
                 ));
                 match sync_prd_with_root(config, None, None) {
                     Ok(new_prd) => {
-                        if !new_prd.get_pending_stories().is_empty() {
+                        if new_prd.pending_count() > 0 {
                             let _ = tx.send(TuiEvent::OutputLine(format!(
                                 "Found {} more tasks from sources",
-                                new_prd.get_pending_stories().len()
+                                new_prd.pending_count()
                             )));
                             current_prd = new_prd;
                         } else {
@@ -663,8 +1058,12 @@ fn run_loop_with_tui_sender(
         }
 
         // Get next task
-        let pending = current_prd.get_pending_stories();
+        let selection = select_tasks(config, &current_prd);
+        let pending = selection.stories(&current_prd);
         if pending.is_empty() && !options.until_complete {
+            if !selection.excluded.is_empty() {
+                let _ = tx.send(TuiEvent::Warning(no_tasks_message(&selection)));
+            }
             stop_reason = super::StopReason::NoTasks;
             break;
         }
@@ -674,6 +1073,19 @@ fn run_loop_with_tui_sender(
             let _ = mark_story_in_progress(&task.id);
         }
 
+        // Work on the task's own branch (git.auto_branch)
+        if let (Some(branches), Some(task)) = (branches.as_mut(), pending.first()) {
+            match branches.enter(&task.id) {
+                Ok(Some(message)) => {
+                    let _ = tx.send(TuiEvent::OutputLine(message));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let _ = tx.send(TuiEvent::Warning(format!("Task branch: {e}")));
+                }
+            }
+        }
+
         // Send iteration start event
         let iteration = iterations_completed + 1;
         let _ = tx.send(TuiEvent::IterationStart {
@@ -686,20 +1098,99 @@ fn run_loop_with_tui_sender(
             let _ = tx.send(TuiEvent::TaskInfo {
                 id: task.id.clone(),
                 title: task.title.clone(),
+                epic: task.epic.clone(),
             });
         }
 
         // Run iteration with TUI output
         let iter_start = Instant::now();
-        let result = run_iteration_with_tui(config, iteration, tx.clone(), interrupted.clone());
+        let task_id = pending.first().map(|t| t.id.clone());
+        let baseline = guardrail_baseline(&config.guardrails);
+        let plugin_start = plugin_baseline(&plugins);
+        plugins.emit(&PluginEvent::IterationStart {
+            iteration,
+            task_id: task_id.clone(),
+        });
+        let event_start = record_iteration_start(iteration, pending.first().copied());
+        let snapshot = changes::Snapshot::take();
+        let gate_start = gate_baseline(config);
+        IterationCheckpoint::begin(iteration, task_id.clone(), event_start.clone());
+        let hook_context = HookContext::for_task(iteration, pending.first().copied());
+        if let Some(warning) = run_hook(&config.hooks, HookEvent::PreIteration, &hook_context) {
+            let _ = tx.send(TuiEvent::Warning(warning));
+        }
+        let failures_before = hooks::task_failures(task_id.as_deref());
+        let mut iter_config = config.clone();
+        iter_config.prompt.instructions.append(&mut prompt_notes);
+        let result = run_iteration_with_tui(
+            &iter_config,
+            iteration,
+            task_id.as_deref(),
+            tx.clone(),
+            &interrupted,
+        );
 
         iterations_completed += 1;
+        let files = record_iteration_end(
+            config,
+            iteration,
+            task_id.clone(),
+            &result,
+            iter_start.elapsed(),
+            event_start.as_deref(),
+            snapshot.as_ref(),
+        );
+        let changed = !files.is_empty() || made_commits(event_start.as_deref());
+        for file in files {
+            let change_type = match file.status {
+                FileStatus::Added => "created",
+                FileStatus::Modified => "modified",
+                FileStatus::Deleted => "deleted",
+            };
+            let _ = tx.send(TuiEvent::FileChange {
+                path: file.path,
+                change_type: change_type.to_string(),
+            });
+        }
+        IterationCheckpoint::clear(None);
+        // Record the outcome the agent reported, in case it didn't
+        if let Some(reported) = &result.agent_result {
+            for message in agent_result::apply(reported, task_id.as_deref(), None) {
+                let _ = tx.send(TuiEvent::OutputLine(format!("📋 {message}")));
+            }
+        }
+        if let Some(skipped) = charge_iteration(config, task_id.as_deref(), iter_start.elapsed()) {
+            let _ = tx.send(TuiEvent::Warning(skipped));
+        }
+        let hook_warnings = [
+            hooks::after_iteration(&config.hooks, &hook_context, &result, failures_before),
+            hooks::on_tasks_completed(&config.hooks, iteration, &current_prd),
+        ];
+        for warning in hook_warnings.into_iter().flatten() {
+            let _ = tx.send(TuiEvent::Warning(warning));
+        }
+        if let Some(notifier) = notifier.as_mut() {
+            let failures = (failures_before, hooks::task_failures(task_id.as_deref()));
+            for warning in notifier.after_iteration(
+                pending.first().map(|t| (t.id.as_str(), t.title.as_str())),
+                failures,
+                config.limits.max_task_failures,
+                made_commits(event_start.as_deref()),
+            ) {
+                let _ = tx.send(TuiEvent::Warning(warning));
+            }
+        }
+        sync_failed_task(pending.first().copied(), failures_before, config);
+        if let Some(tracking) = tracking.as_mut() {
+            tracking.after_iteration(iteration);
+        }
 
         let _ = tx.send(TuiEvent::IterationComplete {
             duration_secs: iter_start.elapsed().as_secs_f64(),
         });
 
         // Handle result
+        let mut failure = None;
         if !result.success {
             if let Some(ref error) = result.error {
                 if error == "AFK_COMPLETE" {
@@ -712,31 +1203,127 @@ fn run_loop_with_tui_sender(
                     stop_reason = super::StopReason::UserInterrupt;
                     break;
                 } else {
-                    let _ = tx.send(TuiEvent::Error(error.clone()));
-                    stop_reason = super::StopReason::AiError(Some(error.clone()));
-                    break;
+                    // Carry on past failures `retry` says to retry
+                    failure = retry::classify_error(error, &result.output);
+                    if let Some(kind) = failure
+                        .filter(|kind| retry::policy(&config.retry, *kind) == RetryPolicy::Stop)
+                    {
+                        let task = pending.first().map(|t| (t.id.as_str(), t.source.as_str()));
+                        retries.after_failure(config, task, kind);
+                        failure = None;
+                    }
+                    if failure.is_none() {
+                        let _ = tx.send(TuiEvent::Error(error.clone()));
+                        stop_reason = super::StopReason::AiError(Some(error.clone()));
+                        break;
+                    }
+                    let _ = tx.send(TuiEvent::Warning(error.clone()));
                 }
             }
         }
 
-        // Check if task was completed
-        let updated_prd = PrdDocument::load(None).unwrap_or(current_prd.clone());
-        let old_completed = current_prd.user_stories.iter().filter(|s| s.passes).count();
-        let new_completed = updated_prd.user_stories.iter().filter(|s| s.passes).count();
-        if new_completed > old_completed {
-            tasks_completed += (new_completed - old_completed) as u32;
+        // Settle the iteration: reverts, vetoes, holds and guardrails
+        let finished = Finished {
+            iteration,
+            task: pending.first().copied(),
+            prd: &current_prd,
+            guardrail_start: baseline.as_deref(),
+            gate_start: gate_start.as_ref(),
+            plugin_start: plugin_start.as_deref(),
+            changed,
+            failure,
+        };
+        let settled = settle(
+            config,
+            &plugins,
+            branches.as_mut(),
+            &finished,
+            &mut |notice| {
+                let _ = tx.send(match notice {
+                    Notice::Info(message) => TuiEvent::OutputLine(message),
+                    Notice::Warning(message) => TuiEvent::Warning(message),
+                    Notice::Review(message) => TuiEvent::OutputLine(format!("👀 {message}")),
+                    Notice::Skipped(message) => TuiEvent::OutputLine(format!("⏭️  {message}")),
+                });
+            },
+        );
+        tasks_completed += settled.tasks_completed;
+        prompt_notes = settled.notes;
 
-            // Sync completed beads tasks back to beads
-            sync_completed_tasks(&current_prd, &updated_prd);
+        // Update task counts
+        if let Some(burndown) = settled.burndown {
+            let complete = settled.prd.user_stories.iter().filter(|s| s.passes).count();
+            let _ = tx.send(TuiEvent::TaskCounts {
+                pending: settled.prd.pending_count() as u32,
+                complete: complete as u32,
+            });
+            let _ = tx.send(TuiEvent::Burndown(burndown));
+        }
+        if let Some(reason) = settled.stop {
+            stop_reason = reason;
+            break;
         }
 
-        // Update task counts
-        let current_pending = updated_prd.get_pending_stories().len() as u32;
-        let current_complete = updated_prd.user_stories.iter().filter(|s| s.passes).count() as u32;
-        let _ = tx.send(TuiEvent::TaskCounts {
-            pending: current_pending,
-            complete: current_complete,
-        });
+        // Then respond to a failed iteration as `retry` says
+        let Some(kind) = settled.failure else {
+            retries.after_success(task_id.as_deref());
+            continue;
+        };
+        let task = pending.first().map(|t| (t.id.as_str(), t.source.as_str()));
+        match retries.after_failure(config, task, kind) {
+            RetryAction::Continue(message) | RetryAction::Skipped(message) => {
+                let _ = tx.send(TuiEvent::Warning(message));
+            }
+            RetryAction::Wait(delay, message) => {
+                let _ = tx.send(TuiEvent::Warning(message));
+                let _ = tx.send(TuiEvent::Backoff(Some(delay)));
+                let waited = retry::wait(delay, &interrupted);
+                let _ = tx.send(TuiEvent::Backoff(None));
+                if !waited {
+                    stop_reason = super::StopReason::UserInterrupt;
+                    break;
+                }
+            }
+            RetryAction::Stop(message) => {
+                let _ = tx.send(TuiEvent::Error(message.clone()));
+                stop_reason = super::StopReason::Failure(message);
+                break;
+            }
+        }
+    }
+
+    // Leave the last task's branch, merging it if the task passed, or open
+    // pull requests for tasks completed on the run's branch
+    let final_prd = PrdDocument::load(None).unwrap_or_else(|_| prd.clone());
+    if stop_reason == super::StopReason::Complete {
+        record_burndown(&final_prd);
+    }
+    if let Some(branches) = branches.as_mut() {
+        if let Some(outcome) = branches.close(&final_prd) {
+            let _ = tx.send(branch_event(outcome.message(branches.base())));
+        }
+    } else {
+        for message in open_completed_prs(config, &prd, &final_prd) {
+            let _ = tx.send(branch_event(message));
+        }
+    }
+
+    plugins.emit(&PluginEvent::LoopEnd {
+        reason: stop_reason.to_string(),
+    });
+    events::record(Event::LoopEnd {
+        reason: stop_reason.to_string(),
+        iterations: iterations_completed,
+        tasks_completed,
+    });
+    if let Some(tracking) = tracking.as_mut() {
+        tracking.finish();
+    }
+    if let Some(notifier) = &notifier {
+        let reason = stop_reason.to_string();
+        for warning in notifier.run_end(&reason, iterations_completed, tasks_completed) {
+            let _ = tx.send(TuiEvent::Warning(warning));
+        }
     }
 
     // Send session complete
@@ -760,44 +1347,247 @@ fn run_loop_with_tui_sender(
     }
 }
 
-/// Build and spawn the AI CLI command.
+/// TUI event for a task branch or pull request outcome.
+fn branch_event((message, is_warning): (String, bool)) -> crate::tui::TuiEvent {
+    if is_warning {
+        crate::tui::TuiEvent::Warning(message)
+    } else {
+        crate::tui::TuiEvent::OutputLine(message)
+    }
+}
+
+/// Wall-clock limit for a session and the reason reported when it is hit.
+///
+/// A focus-run time budget replaces the session timeout.
+pub(super) fn session_limit(
+    timeout_minutes: u32,
+    time_budget: Option<Duration>,
+) -> (Duration, StopReason) {
+    match time_budget {
+        Some(budget) => (budget, StopReason::TimeBudget),
+        None => (
+            Duration::from_secs(timeout_minutes as u64 * 60),
+            StopReason::Timeout,
+        ),
+    }
+}
+
+/// Sort pending stories into pickable and excluded, recording why each
+/// excluded one was passed over for `afk explain`.
+fn select_tasks(config: &AfkConfig, prd: &PrdDocument) -> Selection {
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let order = TaskOrder::new(config, &progress);
+    let selection = Selection::evaluate(prd, &progress, &order, config.limits.max_task_failures);
+    selection.save(None);
+    selection
+}
+
+/// What to say when no task can be picked.
+fn no_tasks_message(selection: &Selection) -> String {
+    match selection.excluded.len() {
+        0 => "No more pending tasks".to_string(),
+        n => format!("No more pending tasks ({n} excluded; see afk explain --task <id>)"),
+    }
+}
+
+/// Whether the session has used its `limits.max_session_minutes`.
+fn session_over_budget(config: &AfkConfig) -> bool {
+    SessionProgress::load(None)
+        .is_ok_and(|p| session_budget_exceeded(&p, config.limits.max_session_minutes))
+}
+
+/// Charge an iteration's time to the session and its task.
+///
+/// A task that used up `limits.max_task_minutes` without completing is
+/// skipped; returns a message saying so.
+fn charge_iteration(
+    config: &AfkConfig,
+    task_id: Option<&str>,
+    elapsed: Duration,
+) -> Option<String> {
+    let mut progress = SessionProgress::load(None).ok()?;
+    let completed = task_id.is_some_and(|id| {
+        PrdDocument::load(None)
+            .ok()
+            .and_then(|prd| prd.get_story(id).map(|s| s.passes))
+            .unwrap_or(false)
+    });
+    let limit = if completed {
+        0
+    } else {
+        config.limits.max_task_minutes
+    };
+    let skipped = charge_iteration_time(&mut progress, task_id, elapsed, limit);
+    if !crate::config::is_read_only() {
+        let _ = progress.save(None);
+    }
+    skipped.then(|| {
+        format!(
+            "Skipping {}: it used its {limit}-minute budget",
+            task_id.unwrap_or_default()
+        )
+    })
+}
+
+/// Whether an iteration made commits since the commit it started from.
+/// Without a starting commit (outside git) progress can't be told, so it
+/// counts as made.
+fn made_commits(baseline: Option<&str>) -> bool {
+    baseline.map_or(true, |rev| !commits_since(rev).is_empty())
+}
+
+/// Record the commit an iteration starts from, if any plugins are loaded.
+fn plugin_baseline(plugins: &PluginHost) -> Option<String> {
+    if plugins.is_empty() {
+        None
+    } else {
+        get_current_commit()
+    }
+}
+
+/// Log the task picked for an iteration and the iteration starting.
+///
+/// Returns the commit the iteration starts from, so its commits can be
+/// logged when it ends.
+fn record_iteration_start(iteration: u32, task: Option<&UserStory>) -> Option<String> {
+    if let Some(task) = task {
+        events::record(Event::TaskSelected {
+            iteration,
+            task_id: task.id.clone(),
+            title: task.title.clone(),
+            priority: task.priority,
+        });
+    }
+    events::record(Event::IterationStart {
+        iteration,
+        task_id: task.map(|t| t.id.clone()),
+    });
+    get_current_commit()
+}
+
+/// Log an iteration's outcome, its commits, the files it changed since
+/// `snapshot`, its tool calls, and its token usage. Returns the files.
+fn record_iteration_end(
+    config: &AfkConfig,
+    iteration: u32,
+    task_id: Option<String>,
+    result: &IterationResult,
+    duration: Duration,
+    baseline: Option<&str>,
+    snapshot: Option<&changes::Snapshot>,
+) -> Vec<FileChange> {
+    events::record(Event::IterationEnd {
+        iteration,
+        task_id: task_id.clone(),
+        success: result.success,
+        duration_seconds: duration.as_secs_f64(),
+        error: result.error.clone(),
+    });
+    let commits = baseline.map(commits_since).unwrap_or_default();
+    for sha in &commits {
+        events::record(Event::Commit {
+            iteration,
+            task_id: task_id.clone(),
+            sha: sha.clone(),
+        });
+    }
+    if let Some(id) = task_id.as_deref() {
+        record_task_commits(id, &commits);
+    }
+    let files = snapshot.map(changes::Snapshot::changes).unwrap_or_default();
+    if !files.is_empty() {
+        events::record(Event::FilesChanged {
+            iteration,
+            files: files.clone(),
+        });
+    }
+    for call in events::tool_calls(&result.output, config.ai_cli.detect_cli_format()) {
+        events::record(Event::ToolCall { iteration, call });
+    }
+    if let Some((input_tokens, output_tokens, cost_usd)) = events::token_usage(&result.output) {
+        if let Some(cost) = cost_usd {
+            record_session_cost(cost);
+        }
+        events::record(Event::TokenUsage {
+            iteration,
+            input_tokens,
+            output_tokens,
+            cost_usd,
+        });
+    }
+    files
+}
+
+/// Add an iteration's commits to its task in progress.json, for
+/// `afk git squash`.
+fn record_task_commits(task_id: &str, commits: &[String]) {
+    if commits.is_empty() || crate::config::is_read_only() {
+        return;
+    }
+    let Ok(mut progress) = SessionProgress::load(None) else {
+        return;
+    };
+    for sha in commits {
+        progress.add_commit(task_id, sha.clone(), "unknown");
+    }
+    let _ = progress.save(None);
+}
+
+/// Add an iteration's cost to the session in progress.json, for
+/// `afk archive diff`.
+fn record_session_cost(cost: f64) {
+    if crate::config::is_read_only() {
+        return;
+    }
+    let Ok(mut progress) = SessionProgress::load(None) else {
+        return;
+    };
+    progress.cost_usd = Some(progress.cost_usd.unwrap_or(0.0) + cost);
+    let _ = progress.save(None);
+}
+
+/// Log why the loop stopped.
+fn record_loop_end(result: &RunResult) {
+    events::record(Event::LoopEnd {
+        reason: result.stop_reason.to_string(),
+        iterations: result.iterations_completed,
+        tasks_completed: result.tasks_completed,
+    });
+}
+
+/// Build the AI CLI command.
 ///
 /// Constructs the command with the prompt and output format arguments,
-/// then spawns it as a child process with piped stdout/stderr.
+/// with piped stdout/stderr.
 ///
-/// If multiple models are configured, one is selected pseudo-randomly
-/// and displayed in the output.
+/// The CLI runs on the model in `choice`; a model picked by rotation is
+/// displayed in the output. Reproducibility metadata is saved before
+/// returning.
 ///
-/// Returns the spawned child process or an error result if spawn fails.
+/// Returns the command or an error if there is nothing to run.
 fn build_ai_command(
     config: &AfkConfig,
     prompt: &str,
+    session_iteration: u32,
+    choice: &ModelChoice,
     tx: &std::sync::mpsc::Sender<crate::tui::TuiEvent>,
-) -> Result<std::process::Child, super::iteration::IterationResult> {
+) -> Result<std::process::Command, &'static str> {
     use crate::tui::TuiEvent;
-    use std::process::{Command, Stdio};
+    use std::process::Stdio;
 
-    // Select model upfront so we can display it
-    let selected_model = config.ai_cli.select_model().map(|s| s.to_string());
+    let (seed, selected_model) = (choice.seed, choice.model.clone());
 
-    let mut cmd_parts = vec![config.ai_cli.command.clone()];
-    cmd_parts.extend(
-        config
-            .ai_cli
-            .full_args_with_model(selected_model.as_deref()),
-    );
+    let cmd_parts = ai_command_line(config, selected_model.as_deref());
 
     if cmd_parts.is_empty() {
-        return Err(super::iteration::IterationResult::failure(
-            "No command specified",
-        ));
+        return Err("No command specified");
     }
 
     let command = &cmd_parts[0];
     let args: Vec<&str> = cmd_parts[1..].iter().map(|s| s.as_str()).collect();
 
     // Display model selection if multiple models configured
-    if config.ai_cli.models.len() > 1 {
+    if config.ai_cli.models.len() > 1 && choice.escalated_after.is_none() {
         if let Some(ref model) = selected_model {
             let _ = tx.send(TuiEvent::OutputLine(format!(
                 "🎲 Model: {} (1 of {})",
@@ -813,27 +1603,38 @@ fn build_ai_command(
         args.join(" ")
     )));
 
-    let mut cmd = Command::new(command);
+    if let Err(e) = super::iteration::record_iteration(
+        config,
+        session_iteration,
+        seed,
+        selected_model.as_deref(),
+        &cmd_parts,
+        prompt,
+    ) {
+        let _ = tx.send(TuiEvent::Warning(format!(
+            "Could not save repro metadata: {e}"
+        )));
+    }
+
+    let mut cmd = program_command(command);
     cmd.args(&args)
         .arg(prompt)
+        .envs(config.git.identity_env())
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
+    Ok(cmd)
+}
 
-    match cmd.spawn() {
-        Ok(child) => Ok(child),
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                Err(super::iteration::IterationResult::failure(format!(
-                    "AI CLI not found: {}",
-                    command
-                )))
-            } else {
-                Err(super::iteration::IterationResult::failure(format!(
-                    "Failed to spawn AI CLI: {e}"
-                )))
-            }
+/// The AI CLI command line for `model`, or the HTTP backend's request.
+fn ai_command_line(config: &AfkConfig, model: Option<&str>) -> Vec<String> {
+    match config.ai_cli.backend {
+        AiBackend::Cli => {
+            let mut parts = vec![config.ai_cli.command.clone()];
+            parts.extend(config.ai_cli.full_args_with_model(model));
+            parts
         }
+        AiBackend::Http | AiBackend::Ollama => request_line(config, model),
     }
 }
 
@@ -865,42 +1666,44 @@ fn handle_stream_event(
     match event {
         StreamEvent::AssistantMessage { text } => {
             // Truncate long messages for display
-            let display_text = if text.len() > 200 {
-                format!("{}...", &text[..197])
-            } else {
-                text.clone()
-            };
-            let _ = tx.send(TuiEvent::OutputLine(display_text));
+            let _ = tx.send(TuiEvent::OutputLine(truncate_display(text, 200)));
         }
         StreamEvent::ToolStarted {
             tool_name,
             tool_type,
             path,
+            command,
         } => {
-            let path_str = path
-                .as_ref()
-                .map(|p| format!(" {}", make_path_relative(p)))
-                .unwrap_or_default();
-            let _ = tx.send(TuiEvent::OutputLine(format!("→ {}{}", tool_type, path_str)));
+            let target = tool_target(path.as_deref(), command.as_deref());
+            let _ = tx.send(TuiEvent::OutputLine(format!("→ {}{}", tool_type, target)));
             let _ = tx.send(TuiEvent::ToolCall(tool_name.clone()));
         }
         StreamEvent::ToolCompleted {
             tool_type,
             path,
+            command,
             success,
+            exit_code,
             lines,
             ..
         } => {
             let status = if *success { "✓" } else { "✗" };
             let lines_str = lines.map(|l| format!(" ({} lines)", l)).unwrap_or_default();
-            let path_str = path
-                .as_ref()
-                .map(|p| format!(" {}", make_path_relative(p)))
+            let exit_str = exit_code
+                .filter(|&code| code != 0)
+                .map(|code| format!(" (exit {code})"))
                 .unwrap_or_default();
+            let target = tool_target(path.as_deref(), command.as_deref());
             let _ = tx.send(TuiEvent::OutputLine(format!(
-                "{} {}{}{}",
-                status, tool_type, path_str, lines_str
+                "{} {}{}{}{}",
+                status, tool_type, target, lines_str, exit_str
             )));
+            if let Some(command) = command {
+                let _ = tx.send(TuiEvent::CommandRun {
+                    command: command.clone(),
+                    exit_code: *exit_code,
+                });
+            }
 
             // Emit file change event for file operations
             if let Some(p) = path {
@@ -940,7 +1743,7 @@ fn handle_stream_event(
                 let _ = tx.send(TuiEvent::OutputLine(format!("◉ Model: {}", m)));
             }
         }
-        StreamEvent::UserMessage { .. } | StreamEvent::Unknown { .. } => {
+        StreamEvent::UserMessage { .. } | StreamEvent::Usage(_) | StreamEvent::Unknown { .. } => {
             // Skip these
         }
     }
@@ -948,48 +1751,29 @@ fn handle_stream_event(
     false
 }
 
-/// Wait for the AI CLI child process to complete.
+/// Result of an AI CLI that exited by itself.
 ///
-/// Returns a success result if the process exits cleanly, or a failure
-/// result with the exit code and stderr content if it fails.
-fn wait_for_completion(
-    mut child: std::process::Child,
+/// Returns a success result if the process exited cleanly, or a failure
+/// result with the exit code and stderr content if it failed.
+fn exit_result(
+    status: std::process::ExitStatus,
     output: String,
+    stderr_output: &str,
 ) -> super::iteration::IterationResult {
-    use std::io::{BufRead, BufReader};
-
-    // Capture stderr before waiting
-    let stderr_output = if let Some(stderr) = child.stderr.take() {
-        let reader = BufReader::new(stderr);
-        let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
-        lines.join("\n")
+    if status.success() {
+        return super::iteration::IterationResult::success(output);
+    }
+    let exit_code = status.code().unwrap_or(-1);
+    // Include full stderr in error message
+    let error_msg = if stderr_output.is_empty() {
+        format!("AI CLI exited with code {exit_code}")
     } else {
-        String::new()
+        format!(
+            "AI CLI exited with code {exit_code}: {}",
+            stderr_output.trim()
+        )
     };
-
-    match child.wait() {
-        Ok(status) => {
-            if !status.success() {
-                let exit_code = status.code().unwrap_or(-1);
-                // Include full stderr in error message
-                let error_msg = if stderr_output.is_empty() {
-                    format!("AI CLI exited with code {exit_code}")
-                } else {
-                    format!(
-                        "AI CLI exited with code {exit_code}: {}",
-                        stderr_output.trim()
-                    )
-                };
-                super::iteration::IterationResult::failure_with_output(error_msg, output)
-            } else {
-                super::iteration::IterationResult::success(output)
-            }
-        }
-        Err(e) => super::iteration::IterationResult::failure_with_output(
-            format!("Failed to wait for AI CLI: {e}"),
-            output,
-        ),
-    }
+    super::iteration::IterationResult::failure_with_output(error_msg, output)
 }
 
 /// Check if a line contains a completion signal.
@@ -1000,26 +1784,28 @@ fn contains_completion_signal(line: &str) -> bool {
 }
 
 /// Run a single iteration with TUI output.
+///
+/// An attempt that stalls is retried up to `runner.stall_retries` times.
 fn run_iteration_with_tui(
     config: &AfkConfig,
-    _iteration: u32,
+    iteration: u32,
+    task_id: Option<&str>,
     tx: std::sync::mpsc::Sender<crate::tui::TuiEvent>,
-    interrupted: Arc<AtomicBool>,
+    interrupted: &CancellationToken,
 ) -> super::iteration::IterationResult {
-    use crate::parser::StreamJsonParser;
     use crate::prompt::generate_prompt_with_root;
     use crate::tui::TuiEvent;
-    use std::io::{BufRead, BufReader};
 
     // Generate prompt
-    let prompt = match generate_prompt_with_root(config, true, None, None) {
-        Ok(result) => result.prompt,
-        Err(e) => {
-            return super::iteration::IterationResult::failure(format!(
-                "Failed to generate prompt: {e}"
-            ));
-        }
-    };
+    let (prompt, session_iteration, prompt_task) =
+        match generate_prompt_with_root(config, true, None, None) {
+            Ok(result) => (result.prompt, result.iteration, result.task_id),
+            Err(e) => {
+                return super::iteration::IterationResult::failure(format!(
+                    "Failed to generate prompt: {e}"
+                ));
+            }
+        };
 
     // Check for stop signals in prompt
     if prompt.contains("AFK_COMPLETE") {
@@ -1028,6 +1814,7 @@ fn run_iteration_with_tui(
             task_id: None,
             error: Some("AFK_COMPLETE".to_string()),
             output: String::new(),
+            agent_result: None,
         };
     }
     if prompt.contains("AFK_LIMIT_REACHED") {
@@ -1036,13 +1823,87 @@ fn run_iteration_with_tui(
             task_id: None,
             error: Some("AFK_LIMIT_REACHED".to_string()),
             output: String::new(),
+            agent_result: None,
         };
     }
 
-    // Build and spawn the AI CLI command
-    let mut child = match build_ai_command(config, &prompt, &tx) {
-        Ok(child) => child,
-        Err(result) => return result,
+    record_phase(IterationPhase::PromptBuilt);
+
+    // Apply the task's own model and CLI, if it sets them
+    let task_config = overrides::for_task(config, prompt_task.as_deref());
+    let config = task_config.as_ref();
+
+    // Select the model once, escalating a failing task; stall retries reuse it
+    let choice = escalation::choose_and_record(
+        config,
+        prompt_task.as_deref(),
+        crate::progress::repro::new_seed(),
+    );
+    let _ = tx.send(TuiEvent::Escalation(
+        choice.escalated_after.and(choice.model.clone()),
+    ));
+    if let Some(message) = choice.escalation_message() {
+        let _ = tx.send(TuiEvent::OutputLine(format!("⬆️  {message}")));
+    }
+
+    let command = ai_command_line(config, choice.model.as_deref());
+    let mut log = IterationLog::start(&config.feedback, session_iteration, &command);
+
+    let mut attempt_prompt = prompt.clone();
+    let mut attempt = 0;
+    loop {
+        let result = run_tui_attempt(
+            config,
+            &attempt_prompt,
+            session_iteration,
+            &choice,
+            &mut log,
+            &tx,
+            interrupted,
+        );
+        let limit = match stall_limit(config) {
+            Some(limit) if result.error.as_deref() == Some(STALLED) => limit,
+            _ => return result,
+        };
+        attempt += 1;
+        events::record(Event::Stalled {
+            iteration,
+            task_id: task_id.map(String::from),
+            idle_seconds: limit.as_secs(),
+            attempt,
+        });
+        let retries = config.runner.stall_retries;
+        if attempt > retries {
+            return super::iteration::IterationResult::failure_with_output(
+                stalled_error(limit),
+                result.output,
+            );
+        }
+        let message = retry_message(limit, attempt, retries);
+        log.note(&message);
+        let _ = tx.send(TuiEvent::Warning(message));
+        attempt_prompt = retry_prompt(config, &prompt, limit);
+    }
+}
+
+/// Spawn the AI CLI once and stream its output to the TUI and the
+/// iteration's transcript.
+fn run_tui_attempt(
+    config: &AfkConfig,
+    prompt: &str,
+    session_iteration: u32,
+    choice: &ModelChoice,
+    log: &mut IterationLog,
+    tx: &std::sync::mpsc::Sender<crate::tui::TuiEvent>,
+    interrupted: &CancellationToken,
+) -> super::iteration::IterationResult {
+    use crate::parser::{ResultCollector, StreamJsonParser};
+    use crate::tui::TuiEvent;
+
+    // Build the AI CLI command
+    let cmd = match build_ai_command(config, prompt, session_iteration, choice, tx) {
+        Ok(cmd) => cmd,
+        Err(error) => return super::iteration::IterationResult::failure(error),
     };
 
     // Create NDJSON parser if using stream-json format
@@ -1054,118 +1915,163 @@ fn run_iteration_with_tui(
 
     // Stream stdout to TUI
     let mut output_buffer = Vec::new();
-    let mut completion_detected = false;
-    let mut user_interrupted = false;
-
-    if let Some(stdout) = child.stdout.take() {
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            // Check for user interrupt (Q pressed in TUI)
-            if interrupted.load(Ordering::SeqCst) {
-                user_interrupted = true;
-                let _ = child.kill();
-                break;
-            }
-
-            match line {
-                Ok(line) => {
-                    // Parse and process based on output format
-                    if let Some(ref mut parser) = stream_parser {
-                        // NDJSON mode: parse and emit events
-                        if let Some(event) = parser.parse_line(&line) {
-                            if handle_stream_event(&event, &tx) {
-                                completion_detected = true;
-                                let _ = child.kill();
-                                break;
-                            }
-                        } else {
-                            // Parsing failed - fall back to raw line display
-                            let _ = tx.send(TuiEvent::OutputLine(line.clone()));
-                            if contains_completion_signal(&line) {
-                                completion_detected = true;
-                                let _ = tx.send(TuiEvent::OutputLine(
-                                    "✓ Completion signal detected".to_string(),
-                                ));
-                                let _ = child.kill();
-                                break;
-                            }
-                        }
-                    } else {
-                        // Plain text mode: send line as-is
-                        let _ = tx.send(TuiEvent::OutputLine(line.clone()));
-
-                        // Track tool calls from output patterns
-                        if line.contains("antml:invoke") || line.contains("<tool_call>") {
-                            let _ = tx.send(TuiEvent::ToolCall("tool".to_string()));
-                        }
-
-                        // Check for completion signal in plain text mode
-                        if contains_completion_signal(&line) {
-                            completion_detected = true;
-                            let _ = tx.send(TuiEvent::OutputLine(
-                                "✓ Completion signal detected".to_string(),
-                            ));
-                            let _ = child.kill();
-                            break;
-                        }
-                    }
+    let mut rate_limit = None;
+    let mut collector = ResultCollector::new(
+        config
+            .ai_cli
+            .uses_stream_json()
+            .then(|| StreamJsonParser::new(config.ai_cli.detect_cli_format())),
+    );
+    let completion = || {
+        let _ = tx.send(TuiEvent::OutputLine(
+            "✓ Completion signal detected".to_string(),
+        ));
+        Flow::Stop
+    };
 
-                    output_buffer.push(format!("{line}\n"));
+    record_phase(IterationPhase::CliLaunched);
+    let on_line = |captured: OutputLine| {
+        if captured.truncated > 0 {
+            let _ = tx.send(TuiEvent::Warning(format!(
+                "Output line too long; dropped {} bytes",
+                captured.truncated
+            )));
+        }
+        let line = captured.text;
+        log.raw(&line);
+        collector.push_line(&line);
+        // Parse and process based on output format
+        if let Some(ref mut parser) = stream_parser {
+            // NDJSON mode: parse and emit events
+            let events = parser.parse_events(&line);
+            for event in &events {
+                log.event(event);
+                if let Some(message) = crate::parser::rate_limit_message(event) {
+                    rate_limit = Some(message.to_string());
                 }
-                Err(e) => {
-                    let _ = tx.send(TuiEvent::Warning(format!("Error reading output: {e}")));
-                    break;
+                if handle_stream_event(event, tx) {
+                    return Flow::Stop;
+                }
+            }
+            if events.is_empty() {
+                // Parsing failed - fall back to raw line display
+                let _ = tx.send(TuiEvent::OutputLine(line.clone()));
+                if contains_completion_signal(&line) {
+                    return completion();
                 }
             }
+        } else {
+            // Plain text mode: send line as-is
+            let _ = tx.send(TuiEvent::OutputLine(line.clone()));
+
+            // Track tool calls from output patterns
+            if line.contains("antml:invoke") || line.contains("<tool_call>") {
+                let _ = tx.send(TuiEvent::ToolCall("tool".to_string()));
+            }
+
+            // Check for completion signal in plain text mode
+            if contains_completion_signal(&line) {
+                return completion();
+            }
         }
-    }
 
+        output_buffer.push(format!("{line}\n"));
+        Flow::Continue
+    };
+    let stall = stall_limit(config);
+    let attempt = match config.ai_cli.backend {
+        AiBackend::Cli => run_attempt(cmd, stall, interrupted, on_line),
+        AiBackend::Http => run_http_attempt(
+            &config.ai_cli,
+            choice.model.as_deref(),
+            prompt,
+            stall,
+            interrupted,
+            on_line,
+        ),
+        AiBackend::Ollama => run_ollama_attempt(
+            &config.ollama,
+            choice.model.as_deref(),
+            prompt,
+            stall,
+            interrupted,
+            on_line,
+        ),
+    };
+    let attempt = match attempt {
+        Ok(attempt) => attempt,
+        Err(AttemptError::Spawn(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return super::iteration::IterationResult::failure(format!(
+                "AI CLI not found: {}",
+                config.ai_cli.command
+            ));
+        }
+        Err(e) => return super::iteration::IterationResult::failure(e.to_string()),
+    };
+    if let Some(e) = attempt.read_error {
+        let _ = tx.send(TuiEvent::Warning(format!("Error reading output: {e}")));
+    }
     let output = output_buffer.concat();
 
-    if user_interrupted {
-        // User pressed Q in TUI - return gracefully
-        return super::iteration::IterationResult {
-            success: false,
-            task_id: None,
-            error: Some("User interrupted".to_string()),
-            output,
-        };
+    // Stderr goes to the iteration log, never into the parsed output
+    let stderr_output = attempt.stderr;
+    log.stderr(&stderr_output);
+    if let Err(e) = save_stderr_artifact(&config.artifacts, session_iteration, &stderr_output) {
+        let _ = tx.send(TuiEvent::Warning(format!(
+            "Could not save AI CLI stderr: {e}"
+        )));
     }
 
-    if completion_detected {
-        return super::iteration::IterationResult::success(output);
+    // A CLI that hit a rate limit hasn't done the work, whatever its exit
+    // code says
+    if let Some(message) = rate_limit {
+        if !matches!(attempt.end, AttemptEnd::Stopped | AttemptEnd::Cancelled) {
+            return super::iteration::IterationResult::failure_with_output(
+                format!("AI CLI rate limited: {message}"),
+                output,
+            );
+        }
     }
 
-    wait_for_completion(child, output)
+    let result = match attempt.end {
+        // User pressed Q in TUI - return gracefully
+        AttemptEnd::Cancelled => {
+            super::iteration::IterationResult::failure_with_output("User interrupted", output)
+        }
+        AttemptEnd::Stopped | AttemptEnd::Finished => {
+            super::iteration::IterationResult::success(output)
+        }
+        AttemptEnd::Stalled => {
+            super::iteration::IterationResult::failure_with_output(STALLED, output)
+        }
+        AttemptEnd::Exited(status) => exit_result(status, output, &stderr_output),
+    };
+    super::iteration::IterationResult {
+        agent_result: collector.result(),
+        ..result
+    }
 }
 
-/// Sync completed tasks back to their sources.
-///
-/// Compares old and new PRD states to find tasks that changed from
-/// `passes: false` to `passes: true` and closes them in beads or GitHub.
-fn sync_completed_tasks(old_prd: &PrdDocument, new_prd: &PrdDocument) {
-    use std::collections::HashSet;
-
-    // Collect IDs of previously completed tasks
-    let previously_complete: HashSet<&str> = old_prd
-        .user_stories
-        .iter()
-        .filter(|s| s.passes)
-        .map(|s| s.id.as_str())
-        .collect();
-
-    // Find and sync newly completed tasks
-    for story in new_prd
-        .user_stories
-        .iter()
-        .filter(|s| s.passes && !previously_complete.contains(s.id.as_str()))
-    {
-        if story.source == "beads" {
-            crate::sources::close_beads_issue(&story.id);
-        } else if let Some(issue_number) = crate::sources::parse_github_issue_number(&story.source)
-        {
-            crate::sources::close_github_issue(issue_number, None);
-        }
+/// Record a failed attempt in the task's source (beads, with writeback), if
+/// the iteration failed the task.
+fn sync_failed_task(task: Option<&UserStory>, failures_before: u32, config: &AfkConfig) {
+    let Some(task) = task.filter(|t| t.source == "beads") else {
+        return;
+    };
+    let Some(progress) = SessionProgress::load(None).ok() else {
+        return;
+    };
+    let Some(tracked) = progress.get_task(&task.id) else {
+        return;
+    };
+    if tracked.failure_count > failures_before {
+        crate::sources::fail_beads_task(
+            &task.id,
+            tracked.failure_count,
+            config.limits.max_task_failures,
+            tracked.message.as_deref(),
+            &config.sources,
+        );
     }
 }
 
@@ -1177,18 +2083,29 @@ mod tests {
     fn test_loop_controller_new() {
         let config = AfkConfig::default();
         let controller = LoopController::new(config);
-        assert!(!controller.interrupted.load(Ordering::SeqCst));
+        assert!(!controller.interrupted.is_cancelled());
+    }
+
+    #[test]
+    fn test_session_limit_prefers_time_budget() {
+        let (limit, reason) = session_limit(120, None);
+        assert_eq!(limit, Duration::from_secs(7200));
+        assert_eq!(reason, StopReason::Timeout);
+
+        let (limit, reason) = session_limit(120, Some(Duration::from_secs(300)));
+        assert_eq!(limit, Duration::from_secs(300));
+        assert_eq!(reason, StopReason::TimeBudget);
     }
 
     #[test]
-    fn test_loop_controller_interrupt_flag() {
+    fn test_loop_controller_cancel_token() {
         let config = AfkConfig::default();
         let controller = LoopController::new(config);
-        let flag = controller.interrupt_flag();
+        let token = controller.cancel_token();
 
-        assert!(!flag.load(Ordering::SeqCst));
-        flag.store(true, Ordering::SeqCst);
-        assert!(flag.load(Ordering::SeqCst));
+        assert!(!token.is_cancelled());
+        token.cancel();
+        assert!(controller.interrupted.is_cancelled());
     }
 
     #[test]
@@ -0,0 +1,254 @@
+//! Command-checked acceptance criteria.
+//!
+//! An acceptance criterion can carry a `verify` command, such as
+//! `cargo test auth::`, that proves it holds. `afk verify --task` and
+//! `afk done` run them, and when an iteration marks a story passed the
+//! runner does too: a story whose checks fail is put back to pending, with
+//! the failure recorded so the next iteration knows what is still wrong.
+//! Criteria whose checks pass are checked off with the command as evidence.
+//! `ci:<workflow>` criteria hold a story back the same way until the
+//! workflow is green for the current commit, though only a failed run
+//! counts as a failure of the task.
+
+use std::path::Path;
+use std::time::Instant;
+
+use crate::prd::{AcceptanceCriterion, PrdDocument, UserStory};
+use crate::progress::{SessionProgress, TaskStatus};
+
+use super::ci_checks::{check_ci_criteria, ci_workflow, describe_unpassed, CiStatus};
+use super::hooks;
+use super::process::shell_command;
+
+/// Output lines kept from a failed check for the failure message.
+const FAILURE_LINES: usize = 5;
+
+/// Result of running one criterion's verify command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CriterionCheck {
+    /// 1-based position of the criterion.
+    pub number: usize,
+    /// The criterion text.
+    pub text: String,
+    /// The command that was run.
+    pub command: String,
+    /// Whether it exited successfully.
+    pub passed: bool,
+    /// Its combined stdout and stderr.
+    pub output: String,
+    /// How long it took.
+    pub duration_seconds: f64,
+}
+
+impl CriterionCheck {
+    /// Short description, e.g. "#2 `cargo test auth::`".
+    pub fn label(&self) -> String {
+        format!("#{} `{}`", self.number, self.command)
+    }
+}
+
+/// Run the verify command of every criterion that has one, in `dir` (the
+/// current directory if None).
+///
+/// Returns an empty list if no criterion has a command.
+pub fn check_criterion_commands(
+    criteria: &[AcceptanceCriterion],
+    dir: Option<&Path>,
+) -> Vec<CriterionCheck> {
+    criteria
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| {
+            let command = c.verify.as_deref()?.trim();
+            (!command.is_empty()).then(|| run_check(i + 1, &c.text, command, dir))
+        })
+        .collect()
+}
+
+fn run_check(number: usize, text: &str, command: &str, dir: Option<&Path>) -> CriterionCheck {
+    let start = Instant::now();
+    let mut cmd = shell_command(command);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let (passed, output) = match cmd.output() {
+        Ok(o) => (
+            o.status.success(),
+            format!(
+                "{}{}",
+                String::from_utf8_lossy(&o.stdout),
+                String::from_utf8_lossy(&o.stderr)
+            ),
+        ),
+        Err(e) => (false, format!("Failed to run command: {e}")),
+    };
+    CriterionCheck {
+        number,
+        text: text.to_string(),
+        command: command.to_string(),
+        passed,
+        output,
+        duration_seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// Why a set of checks doesn't pass, or None if every one did.
+pub fn failure_details(checks: &[CriterionCheck]) -> Option<String> {
+    let failed: Vec<String> = checks
+        .iter()
+        .filter(|c| !c.passed)
+        .map(|c| {
+            let lines: Vec<&str> = c.output.trim_end().lines().collect();
+            let tail = lines[lines.len().saturating_sub(FAILURE_LINES)..].join("\n");
+            if tail.is_empty() {
+                format!("criterion {} failed", c.label())
+            } else {
+                format!("criterion {} failed:\n{tail}", c.label())
+            }
+        })
+        .collect();
+    (!failed.is_empty()).then(|| failed.join("\n"))
+}
+
+/// Run a story's checks and check off the criteria that pass.
+///
+/// Returns why the story can't be complete, if any check failed.
+pub fn verify_story(story: &mut UserStory, dir: Option<&Path>) -> Option<String> {
+    let checks = check_criterion_commands(&story.acceptance_criteria, dir);
+    for check in checks.iter().filter(|c| c.passed) {
+        story.check_criterion(check.number, Some(format!("verified: {}", check.command)));
+    }
+    failure_details(&checks)
+}
+
+/// A story [`hold_unverified`] put back to pending.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeldStory {
+    /// Why the story was held back.
+    pub warning: String,
+    /// Whether a check failed, rather than CI having no result for the
+    /// commit yet.
+    pub failed: bool,
+}
+
+/// Hold back stories the AI CLI marked passed whose checks fail.
+///
+/// Every story that went from pending in `old` to passed in `new` has its
+/// verify commands run and its CI criteria checked. Those with a failing
+/// check or a workflow that isn't green are put back to pending in `new`,
+/// which is saved. A failing verify command or CI run is recorded against
+/// the task in progress.json as a failure; a workflow that is still running,
+/// hasn't run or can't be queried only keeps the task pending.
+pub fn hold_unverified(old: &PrdDocument, new: &mut PrdDocument) -> Vec<HeldStory> {
+    let completed: Vec<String> = hooks::newly_completed(old, new)
+        .into_iter()
+        .filter(|s| {
+            s.acceptance_criteria
+                .iter()
+                .any(|c| c.verify.is_some() || ci_workflow(c.as_str()).is_some())
+        })
+        .map(|s| s.id.clone())
+        .collect();
+    if completed.is_empty() {
+        return Vec::new();
+    }
+
+    let mut held = Vec::new();
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    for id in completed {
+        let Some(story) = new.user_stories.iter_mut().find(|s| s.id == id) else {
+            continue;
+        };
+        let ci_checks = check_ci_criteria(&story.acceptance_criteria);
+        let ci = describe_unpassed(&ci_checks).map(|ci| format!("CI: {ci}"));
+        let checks = verify_story(story, None);
+        let failed = checks.is_some()
+            || ci_checks
+                .iter()
+                .any(|check| matches!(check.status, CiStatus::Failed(_)));
+        let details = match (checks, ci) {
+            (None, None) => continue,
+            (Some(checks), Some(ci)) => format!("{checks}\n{ci}"),
+            (Some(details), None) | (None, Some(details)) => details,
+        };
+        story.passes = false;
+        let status = if failed {
+            TaskStatus::Failed
+        } else {
+            TaskStatus::Pending
+        };
+        progress.set_task_status(
+            &id,
+            status,
+            &story.source,
+            Some(format!("Marked passed but {details}")),
+        );
+        held.push(HeldStory {
+            warning: format!(
+                "{id} not complete: {}",
+                details.lines().next().unwrap_or_default()
+            ),
+            failed,
+        });
+    }
+    if !crate::config::is_read_only() {
+        let _ = new.save(None);
+        let _ = progress.save(None);
+    }
+    held
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn criterion(text: &str, verify: Option<&str>) -> AcceptanceCriterion {
+        AcceptanceCriterion {
+            verify: verify.map(String::from),
+            ..AcceptanceCriterion::new(text)
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_criterion_commands() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let criteria = [
+            criterion("Compiles", Some("true")),
+            criterion("Reviewed", None),
+            criterion("Tests pass", Some("echo 2 failed; exit 1")),
+        ];
+        let checks = check_criterion_commands(&criteria, Some(temp.path()));
+        assert_eq!(checks.len(), 2);
+        assert!(checks[0].passed);
+        assert_eq!(checks[1].number, 3);
+        assert!(!checks[1].passed);
+        assert_eq!(checks[1].label(), "#3 `echo 2 failed; exit 1`");
+        assert_eq!(
+            failure_details(&checks).as_deref(),
+            Some("criterion #3 `echo 2 failed; exit 1` failed:\n2 failed")
+        );
+        assert_eq!(failure_details(&checks[..1]), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_story_checks_off_passing_criteria() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("marker"), "").unwrap();
+        let mut story = UserStory::new("auth", "Auth");
+        story.acceptance_criteria = vec![
+            criterion("Marker exists", Some("test -f marker")),
+            criterion("Docs exist", Some("test -f docs.md")),
+        ];
+
+        let details = verify_story(&mut story, Some(temp.path())).unwrap();
+        assert!(details.starts_with("criterion #2 `test -f docs.md` failed"));
+        assert!(story.acceptance_criteria[0].passed);
+        assert_eq!(
+            story.acceptance_criteria[0].evidence.as_deref(),
+            Some("verified: test -f marker")
+        );
+        assert!(!story.acceptance_criteria[1].passed);
+    }
+}
@@ -0,0 +1,122 @@
+//! Model escalation.
+//!
+//! A task that keeps failing may be beyond the model it is running on. With
+//! `ai_cli.escalation_models` set, a task that has failed
+//! `ai_cli.escalate_after` times runs on the first of them instead of a model
+//! from `ai_cli.models`, and every further `escalate_after` failures move it
+//! one model up the list. The model each iteration used is recorded against
+//! the task in progress.json, where `afk status` shows it.
+
+use crate::config::AfkConfig;
+use crate::progress::SessionProgress;
+
+/// The model an iteration runs with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelChoice {
+    /// Seed the rotation choice was made from, recorded for `afk repro`.
+    pub seed: u64,
+    /// Model passed to the AI CLI, or None for the CLI's default.
+    pub model: Option<String>,
+    /// Failures of the task when its model was escalated, if it was.
+    pub escalated_after: Option<u32>,
+}
+
+impl ModelChoice {
+    /// Notice shown when the iteration runs on an escalated model.
+    pub fn escalation_message(&self) -> Option<String> {
+        let failures = self.escalated_after?;
+        let model = self.model.as_deref()?;
+        Some(format!(
+            "Model escalated to {model} after {failures} failure{}",
+            if failures == 1 { "" } else { "s" }
+        ))
+    }
+}
+
+/// Choose the model for an iteration on `task_id`.
+///
+/// A task that has failed often enough runs on its escalation model;
+/// otherwise the rotation in `ai_cli.models` picks one from `seed`.
+pub fn choose(
+    config: &AfkConfig,
+    progress: &SessionProgress,
+    task_id: Option<&str>,
+    seed: u64,
+) -> ModelChoice {
+    let failures = task_id
+        .and_then(|id| progress.get_task(id))
+        .map_or(0, |t| t.failure_count);
+    match config.ai_cli.escalation_model(failures) {
+        Some(model) => ModelChoice {
+            seed,
+            model: Some(model.to_string()),
+            escalated_after: Some(failures),
+        },
+        None => ModelChoice {
+            seed,
+            model: config.ai_cli.select_model_seeded(seed).map(String::from),
+            escalated_after: None,
+        },
+    }
+}
+
+/// Choose the model for an iteration on `task_id` and record it against the
+/// task in progress.json (unless afk is read-only).
+pub fn choose_and_record(config: &AfkConfig, task_id: Option<&str>, seed: u64) -> ModelChoice {
+    let Some(id) = task_id else {
+        return choose(config, &SessionProgress::new(), None, seed);
+    };
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    let choice = choose(config, &progress, Some(id), seed);
+    if !crate::config::is_read_only() {
+        progress.record_model(id, choice.model.as_deref());
+        let _ = progress.save(None);
+    }
+    choice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::TaskStatus;
+
+    fn config() -> AfkConfig {
+        let mut config = AfkConfig::default();
+        config.ai_cli.models = vec!["haiku".to_string()];
+        config.ai_cli.escalation_models = vec!["sonnet".to_string(), "opus".to_string()];
+        config
+    }
+
+    fn failed(times: u32) -> SessionProgress {
+        let mut progress = SessionProgress::new();
+        progress.record_attempt("auth", "json");
+        for _ in 0..times {
+            progress.set_task_status("auth", TaskStatus::Failed, "json", None);
+        }
+        progress
+    }
+
+    #[test]
+    fn test_choose_rotates_until_escalation() {
+        let choice = choose(&config(), &failed(1), Some("auth"), 3);
+        assert_eq!(choice.model.as_deref(), Some("haiku"));
+        assert_eq!(choice.escalated_after, None);
+        assert_eq!(choice.escalation_message(), None);
+
+        let choice = choose(&config(), &failed(5), None, 3);
+        assert_eq!(choice.model.as_deref(), Some("haiku"));
+    }
+
+    #[test]
+    fn test_choose_escalates_failing_task() {
+        let choice = choose(&config(), &failed(2), Some("auth"), 3);
+        assert_eq!(choice.model.as_deref(), Some("sonnet"));
+        assert_eq!(
+            choice.escalation_message().as_deref(),
+            Some("Model escalated to sonnet after 2 failures")
+        );
+        let choice = choose(&config(), &failed(4), Some("auth"), 3);
+        assert_eq!(choice.model.as_deref(), Some("opus"));
+        assert_eq!(choice.seed, 3);
+    }
+}
@@ -0,0 +1,194 @@
+//! Quality gate result cache.
+//!
+//! A passing gate is recorded in `.afk/gate-cache.json` against the git
+//! tree hash of the working directory (tracked and untracked files, minus
+//! ignored files and `.afk/`) and the gate's command. While neither changes,
+//! later verify runs reuse the result instead of running the gate again.
+//! Failing gates are never cached, so they always re-run.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::GATE_CACHE_FILE;
+
+use super::quality_gates::GateResult;
+
+/// A cached passing gate run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedGate {
+    /// Working tree hash the gate passed on.
+    tree: String,
+    /// Command (or WASM gate config) that was run.
+    command: String,
+    /// Output of the run.
+    output: String,
+    /// Duration of the original run in seconds.
+    duration_seconds: f64,
+}
+
+/// Gate results keyed by gate name, valid for one working tree.
+#[derive(Debug)]
+pub struct GateCache {
+    path: PathBuf,
+    tree: String,
+    entries: HashMap<String, CachedGate>,
+}
+
+impl GateCache {
+    /// Open the project's gate cache for the current working tree.
+    ///
+    /// Returns None outside a git repository, where there is nothing to key on.
+    pub fn open() -> Option<Self> {
+        working_tree_hash().map(|tree| Self::open_at(Path::new(GATE_CACHE_FILE), tree))
+    }
+
+    /// Open a cache file for a given tree hash. A missing or unreadable file
+    /// starts an empty cache.
+    pub fn open_at(path: &Path, tree: String) -> Self {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            tree,
+            entries,
+        }
+    }
+
+    /// A cached result for the gate, if it passed on this tree with this command.
+    pub fn lookup(&self, name: &str, command: &str) -> Option<GateResult> {
+        self.entries
+            .get(name)
+            .filter(|entry| entry.tree == self.tree && entry.command == command)
+            .map(|entry| GateResult {
+                name: name.to_string(),
+                passed: true,
+                output: entry.output.clone(),
+                duration_seconds: entry.duration_seconds,
+            })
+    }
+
+    /// Record a gate run. Passing runs are cached; failing runs drop any
+    /// earlier entry for the gate.
+    pub fn store(&mut self, command: &str, result: &GateResult) {
+        if result.passed {
+            self.entries.insert(
+                result.name.clone(),
+                CachedGate {
+                    tree: self.tree.clone(),
+                    command: command.to_string(),
+                    output: result.output.clone(),
+                    duration_seconds: result.duration_seconds,
+                },
+            );
+        } else {
+            self.entries.remove(&result.name);
+        }
+    }
+
+    /// Write the cache back to disk. Does nothing in read-only mode.
+    pub fn save(&self) -> io::Result<()> {
+        if crate::config::is_read_only() {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)
+    }
+}
+
+/// Git tree hash of the working directory, including unstaged and
+/// untracked files but not ignored files or `.afk/`.
+///
+/// Stages into a scratch copy of the index, so the real index is untouched.
+pub fn working_tree_hash() -> Option<String> {
+    let index = git(&["rev-parse", "--git-path", "index"], None)?;
+    let scratch = std::env::temp_dir().join(format!("afk-gate-index-{}", std::process::id()));
+    // Copying keeps git's stat cache, so unchanged files aren't re-hashed.
+    // With no index yet (fresh repo) git starts from an empty one.
+    let _ = fs::copy(&index, &scratch);
+
+    let hash = git(&["add", "-A", "--", ".", ":(exclude).afk"], Some(&scratch))
+        .and_then(|_| git(&["write-tree"], Some(&scratch)));
+    let _ = fs::remove_file(&scratch);
+    hash
+}
+
+/// Run git, optionally against another index file; returns trimmed stdout.
+fn git(args: &[&str], index: Option<&Path>) -> Option<String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args);
+    if let Some(index) = index {
+        cmd.env("GIT_INDEX_FILE", index);
+    }
+    let output = cmd.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn gate(name: &str, passed: bool) -> GateResult {
+        GateResult {
+            name: name.to_string(),
+            passed,
+            output: "ok\n".to_string(),
+            duration_seconds: 2.5,
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_requires_same_tree_and_command() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(".afk/gate-cache.json");
+
+        let mut cache = GateCache::open_at(&path, "tree-a".to_string());
+        assert!(cache.lookup("test", "cargo test").is_none());
+        cache.store("cargo test", &gate("test", true));
+        cache.save().unwrap();
+
+        let cache = GateCache::open_at(&path, "tree-a".to_string());
+        let hit = cache.lookup("test", "cargo test").unwrap();
+        assert!(hit.passed);
+        assert_eq!(hit.output, "ok\n");
+        assert_eq!(hit.duration_seconds, 2.5);
+        assert!(cache.lookup("test", "cargo test --all").is_none());
+
+        let cache = GateCache::open_at(&path, "tree-b".to_string());
+        assert!(cache.lookup("test", "cargo test").is_none());
+    }
+
+    #[test]
+    fn test_failure_evicts_entry() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("gate-cache.json");
+
+        let mut cache = GateCache::open_at(&path, "tree".to_string());
+        cache.store("make lint", &gate("lint", true));
+        assert!(cache.lookup("lint", "make lint").is_some());
+        cache.store("make lint", &gate("lint", false));
+        assert!(cache.lookup("lint", "make lint").is_none());
+    }
+
+    #[test]
+    fn test_corrupt_cache_starts_empty() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("gate-cache.json");
+        fs::write(&path, "not json").unwrap();
+
+        let cache = GateCache::open_at(&path, "tree".to_string());
+        assert!(cache.lookup("test", "cargo test").is_none());
+    }
+}
@@ -0,0 +1,311 @@
+//! Tool versions behind the quality gates.
+//!
+//! "Gates pass locally but not in the loop" is usually a different toolchain.
+//! Each `afk verify` asks the tools the gates run for their versions
+//! (`cargo --version`, `node --version`, `python -m pytest --version`). The
+//! first set seen in a session is recorded in `.afk/progress.json`, and the
+//! set from the last run where every gate passed in `.afk/gate-env.json`.
+//! A version that differs from either is reported as a warning.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::{FeedbackLoopsConfig, GATE_ENV_FILE, PROGRESS_FILE};
+use crate::progress::SessionProgress;
+
+/// Package managers that run on Node, whose gates also depend on its version.
+const NODE_TOOLS: &[&str] = &["npm", "npx", "yarn", "pnpm"];
+
+/// Tool name to version string.
+pub type ToolVersions = BTreeMap<String, String>;
+
+/// A tool whose version differs from an earlier record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    /// Tool, as invoked by the gate (e.g. "cargo", "python -m pytest").
+    pub tool: String,
+    /// Version recorded earlier.
+    pub before: String,
+    /// Version found now.
+    pub after: String,
+}
+
+/// Tool versions captured before a verify run.
+#[derive(Debug)]
+pub struct GateEnvironment {
+    root: PathBuf,
+    versions: ToolVersions,
+}
+
+impl GateEnvironment {
+    /// Capture the versions of the tools the configured gates run, warning
+    /// (on stderr) about any that changed during the session or differ from
+    /// the last green run.
+    pub fn check(feedback_loops: &FeedbackLoopsConfig) -> Self {
+        Self::check_in(
+            Path::new("."),
+            capture_versions(&gate_tools(feedback_loops)),
+        )
+    }
+
+    /// Compare captured versions with the records under a project root.
+    fn check_in(root: &Path, versions: ToolVersions) -> Self {
+        let env = Self {
+            root: root.to_path_buf(),
+            versions,
+        };
+        if env.versions.is_empty() {
+            return env;
+        }
+
+        for change in env.record_session() {
+            eprintln!(
+                "\x1b[33mWarning:\x1b[0m {} changed mid-session: {} -> {}",
+                change.tool, change.before, change.after
+            );
+        }
+        for change in version_changes(&env.last_green(), &env.versions) {
+            eprintln!(
+                "\x1b[33mWarning:\x1b[0m {} differs from the last green run: {} -> {}",
+                change.tool, change.before, change.after
+            );
+        }
+        env
+    }
+
+    /// Versions captured for this run.
+    pub fn versions(&self) -> &ToolVersions {
+        &self.versions
+    }
+
+    /// Remember these versions as the last ones every gate passed with.
+    pub fn record_green(&self) {
+        if self.versions.is_empty() || crate::config::is_read_only() {
+            return;
+        }
+        let path = self.root.join(GATE_ENV_FILE);
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string_pretty(&self.versions) {
+            let _ = fs::write(path, json);
+        }
+    }
+
+    /// Versions from the last green run, empty if there isn't one.
+    fn last_green(&self) -> ToolVersions {
+        fs::read_to_string(self.root.join(GATE_ENV_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Add the versions to the session record, if a session is running,
+    /// returning the ones that changed since it was made. Changed versions
+    /// replace the recorded ones, so each change is reported once.
+    fn record_session(&self) -> Vec<VersionChange> {
+        let path = self.root.join(PROGRESS_FILE);
+        if !path.exists() {
+            return Vec::new();
+        }
+        let Ok(mut progress) = SessionProgress::load(Some(&path)) else {
+            return Vec::new();
+        };
+        let changes = version_changes(&progress.tool_versions, &self.versions);
+        let before = progress.tool_versions.clone();
+        progress.tool_versions.extend(self.versions.clone());
+        if progress.tool_versions != before && !crate::config::is_read_only() {
+            let _ = progress.save(Some(&path));
+        }
+        changes
+    }
+}
+
+/// Tools whose versions matter for the configured shell gates, each as the
+/// words that invoke it.
+///
+/// Every command in a chain (`&&`, `||`, `;`, `|`) counts; leading
+/// `VAR=value` assignments are skipped, and `python -m <module>` counts as
+/// the module. Node package managers also bring in `node`.
+pub fn gate_tools(feedback_loops: &FeedbackLoopsConfig) -> Vec<String> {
+    let commands = [
+        &feedback_loops.types,
+        &feedback_loops.lint,
+        &feedback_loops.test,
+        &feedback_loops.build,
+    ]
+    .into_iter()
+    .flatten()
+    .chain(feedback_loops.custom.values());
+
+    let mut tools: Vec<String> = Vec::new();
+    for command in commands {
+        for tool in command_tools(command) {
+            if !tools.contains(&tool) {
+                tools.push(tool);
+            }
+        }
+    }
+    tools
+}
+
+/// Tools invoked by one shell command.
+fn command_tools(command: &str) -> Vec<String> {
+    let mut tools = Vec::new();
+    let command = command.replace("&&", ";").replace("||", ";");
+    let segments = command
+        .split([';', '|'])
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    for segment in segments {
+        let mut words = segment
+            .split_whitespace()
+            .skip_while(|w| w.contains('=') && !w.starts_with('-'));
+        let Some(program) = words.next() else {
+            continue;
+        };
+        let tool = match (words.next(), words.next()) {
+            (Some("-m"), Some(module)) if program.starts_with("python") => {
+                format!("{program} -m {module}")
+            }
+            _ => program.to_string(),
+        };
+        if NODE_TOOLS.contains(&program) {
+            tools.push("node".to_string());
+        }
+        tools.push(tool);
+    }
+    tools
+}
+
+/// Ask each tool for its version. Tools that can't be run are left out.
+pub fn capture_versions(tools: &[String]) -> ToolVersions {
+    tools
+        .iter()
+        .filter_map(|tool| tool_version(tool).map(|v| (tool.clone(), v)))
+        .collect()
+}
+
+/// First line of `<tool> --version`, from stdout or else stderr.
+fn tool_version(tool: &str) -> Option<String> {
+    let mut words = tool.split_whitespace();
+    let output = Command::new(words.next()?)
+        .args(words)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Tools present in both sets whose versions differ.
+pub fn version_changes(before: &ToolVersions, after: &ToolVersions) -> Vec<VersionChange> {
+    after
+        .iter()
+        .filter_map(|(tool, version)| {
+            let old = before.get(tool)?;
+            (old != version).then(|| VersionChange {
+                tool: tool.clone(),
+                before: old.clone(),
+                after: version.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn versions(pairs: &[(&str, &str)]) -> ToolVersions {
+        pairs
+            .iter()
+            .map(|(t, v)| (t.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_gate_tools() {
+        let mut feedback_loops = FeedbackLoopsConfig {
+            lint: Some("cargo clippy -- -D warnings".to_string()),
+            test: Some("RUST_LOG=off cargo test 2>&1 && python3 -m pytest -q".to_string()),
+            build: Some("npm run build | tee build.log".to_string()),
+            ..Default::default()
+        };
+        feedback_loops
+            .custom
+            .insert("e2e".to_string(), "npx playwright test".to_string());
+
+        assert_eq!(
+            gate_tools(&feedback_loops),
+            vec!["cargo", "python3 -m pytest", "node", "npm", "tee", "npx"]
+        );
+        assert!(gate_tools(&FeedbackLoopsConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_version_changes() {
+        let before = versions(&[("cargo", "cargo 1.80.0"), ("node", "v20.1.0")]);
+        let after = versions(&[
+            ("cargo", "cargo 1.81.0"),
+            ("node", "v20.1.0"),
+            ("go", "1.22"),
+        ]);
+        assert_eq!(
+            version_changes(&before, &after),
+            vec![VersionChange {
+                tool: "cargo".to_string(),
+                before: "cargo 1.80.0".to_string(),
+                after: "cargo 1.81.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_session_records_first_versions_and_changes() {
+        let temp = TempDir::new().unwrap();
+        let progress_path = temp.path().join(PROGRESS_FILE);
+        SessionProgress::new().save(Some(&progress_path)).unwrap();
+
+        GateEnvironment::check_in(temp.path(), versions(&[("cargo", "cargo 1.80.0")]));
+        let saved = SessionProgress::load(Some(&progress_path)).unwrap();
+        assert_eq!(saved.tool_versions, versions(&[("cargo", "cargo 1.80.0")]));
+
+        let env = GateEnvironment::check_in(temp.path(), versions(&[("cargo", "cargo 1.81.0")]));
+        assert!(env.record_session().is_empty());
+        let saved = SessionProgress::load(Some(&progress_path)).unwrap();
+        assert_eq!(saved.tool_versions["cargo"], "cargo 1.81.0");
+    }
+
+    #[test]
+    fn test_record_green() {
+        let temp = TempDir::new().unwrap();
+        let env = GateEnvironment::check_in(temp.path(), versions(&[("node", "v20.1.0")]));
+        assert!(env.last_green().is_empty());
+
+        env.record_green();
+        let later = GateEnvironment::check_in(temp.path(), versions(&[("node", "v22.0.0")]));
+        assert_eq!(later.last_green(), versions(&[("node", "v20.1.0")]));
+        assert_eq!(later.versions()["node"], "v22.0.0");
+    }
+
+    #[test]
+    fn test_missing_tool_is_skipped() {
+        let tools = vec!["afk-no-such-tool-xyz".to_string()];
+        assert!(capture_versions(&tools).is_empty());
+    }
+}
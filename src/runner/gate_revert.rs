@@ -0,0 +1,245 @@
+//! Reverting iterations that break the quality gates.
+//!
+//! With `git.revert_on_gate_failure`, the gates are run again after an
+//! iteration that changed files, as long as they were green when it started.
+//! If any now fail, the branch is moved back to the commit the iteration
+//! started at and the files it changed are put back, so broken changes don't
+//! pile up; the failure is recorded against the task with the gate output,
+//! and the next prompt is given that output to work from. Files that already
+//! had uncommitted edits are put back to those edits, not to the commit.
+
+use std::path::Path;
+
+use crate::config::{AfkConfig, EVENTS_FILE};
+use crate::events::{self, last_gate_failures, Event};
+use crate::git::changes::Snapshot;
+use crate::git::{
+    commits_since, get_current_commit, reset_mixed, restore_paths, restore_saved, save_paths,
+};
+use crate::prd::PrdDocument;
+use crate::progress::{SessionProgress, TaskStatus};
+
+use super::quality_gates::{has_configured_gates, run_quality_gates_quiet, GateResult};
+
+/// Lines of each failed gate's output kept for the event log and prompt.
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Where an iteration started, for putting it back.
+#[derive(Debug, Clone)]
+pub struct GateBaseline {
+    commit: String,
+    snapshot: Snapshot,
+    /// Contents of the files that were dirty, by path.
+    dirty: Vec<(String, Option<String>)>,
+}
+
+/// Note where an iteration starts, if it should be reverted when it breaks
+/// the gates: the option is on, gates are configured, and the last gate run
+/// passed (an iteration can't be blamed for gates that were already red).
+pub fn gate_baseline(config: &AfkConfig) -> Option<GateBaseline> {
+    if !config.git.revert_on_gate_failure || !has_configured_gates(&config.feedback_loops) {
+        return None;
+    }
+    if !last_gate_failures(Path::new(EVENTS_FILE)).is_empty() {
+        return None;
+    }
+    let snapshot = Snapshot::take()?;
+    let dirty: Vec<String> = snapshot
+        .dirty_paths()
+        .into_iter()
+        .filter(|path| !path.starts_with(".afk/"))
+        .collect();
+    Some(GateBaseline {
+        commit: get_current_commit()?,
+        dirty: save_paths(&dirty)?,
+        snapshot,
+    })
+}
+
+/// An iteration reverted for failing the gates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GateRevert {
+    /// Names of the gates that failed.
+    pub failed_gates: Vec<String>,
+    /// The end of each failed gate's output.
+    pub output: String,
+    /// Files the iteration had changed.
+    pub files: Vec<String>,
+    /// Whether every file was put back.
+    pub reverted: bool,
+}
+
+impl GateRevert {
+    /// One-line description for the console.
+    pub fn message(&self) -> String {
+        format!(
+            "Quality gates failed after the iteration ({}); {} {} file{}",
+            self.failed_gates.join(", "),
+            if self.reverted {
+                "reverted"
+            } else {
+                "could not fully revert"
+            },
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" }
+        )
+    }
+
+    /// Prompt instruction telling the AI its last attempt was undone, and
+    /// why.
+    pub fn instruction(&self) -> String {
+        format!(
+            "The previous iteration was reverted because it left the quality gates failing \
+             ({}). Its changes to {} were undone. Start again from the current code, and make \
+             sure the gates pass before you finish. Gate output:\n```\n{}\n```",
+            self.failed_gates.join(", "),
+            self.files.join(", "),
+            self.output.trim_end()
+        )
+    }
+}
+
+/// Run the gates after an iteration and revert it if they fail.
+///
+/// Does nothing if the iteration changed no files. Otherwise a failure is
+/// logged as a `gate_revert` event, recorded against `task` (ID and source),
+/// whose story is put back to pending if it was marked passed.
+pub fn revert_if_gates_fail(
+    config: &AfkConfig,
+    baseline: &GateBaseline,
+    iteration: u32,
+    task: Option<(&str, &str)>,
+) -> Option<GateRevert> {
+    let files: Vec<String> = baseline
+        .snapshot
+        .changes()
+        .into_iter()
+        .map(|change| change.path)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    let result = run_quality_gates_quiet(&config.feedback_loops, None);
+    if result.all_passed {
+        return None;
+    }
+
+    let failed: Vec<&GateResult> = result.gates.iter().filter(|g| !g.passed).collect();
+    let mut reverted = true;
+    if !commits_since(&baseline.commit).is_empty() {
+        reverted &= reset_mixed(&baseline.commit);
+    }
+    reverted &= restore_paths(
+        &baseline.commit,
+        &files,
+        "afk: revert changes that broke quality gates",
+    );
+    // Edits made before the iteration are the user's, so keep them
+    let dirty: Vec<(String, Option<String>)> = baseline
+        .dirty
+        .iter()
+        .filter(|(path, _)| files.contains(path))
+        .cloned()
+        .collect();
+    reverted &= restore_saved(&dirty);
+
+    let revert = GateRevert {
+        failed_gates: failed.iter().map(|g| g.name.clone()).collect(),
+        output: output_tail(&failed),
+        files,
+        reverted,
+    };
+    events::record(Event::GateRevert {
+        iteration,
+        task_id: task.map(|(id, _)| id.to_string()),
+        commit: baseline.commit.clone(),
+        failed_gates: revert.failed_gates.clone(),
+        output: revert.output.clone(),
+    });
+    if let Some((id, source)) = task {
+        record_failure(id, source, &revert);
+    }
+    Some(revert)
+}
+
+/// Mark the task failed, and pending again if the AI marked it passed.
+fn record_failure(task_id: &str, source: &str, revert: &GateRevert) {
+    if crate::config::is_read_only() {
+        return;
+    }
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    progress.set_task_status(task_id, TaskStatus::Failed, source, Some(revert.message()));
+    let _ = progress.save(None);
+
+    if let Ok(mut prd) = PrdDocument::load(None) {
+        if let Some(story) = prd
+            .user_stories
+            .iter_mut()
+            .find(|s| s.id == task_id && s.passes)
+        {
+            story.passes = false;
+            let _ = prd.save(None);
+        }
+    }
+}
+
+/// The last lines of each failed gate's output, headed by its name.
+fn output_tail(failed: &[&GateResult]) -> String {
+    failed
+        .iter()
+        .map(|gate| {
+            let lines: Vec<&str> = gate.output.lines().collect();
+            let start = lines.len().saturating_sub(OUTPUT_TAIL_LINES);
+            format!("== {} ==\n{}", gate.name, lines[start..].join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(name: &str, output: &str) -> GateResult {
+        GateResult {
+            name: name.to_string(),
+            passed: false,
+            output: output.to_string(),
+            duration_seconds: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_output_tail() {
+        let long: String = (1..=50).map(|n| format!("line {n}\n")).collect();
+        let test = gate("test", &long);
+        let lint = gate("lint", "warning: unused\n");
+        let tail = output_tail(&[&test, &lint]);
+        assert!(tail.starts_with("== test ==\nline 11\n"));
+        assert!(!tail.contains("line 10\n"));
+        assert!(tail.ends_with("line 50\n== lint ==\nwarning: unused"));
+    }
+
+    #[test]
+    fn test_message_and_instruction() {
+        let revert = GateRevert {
+            failed_gates: vec!["test".to_string()],
+            output: "== test ==\n1 failed".to_string(),
+            files: vec!["src/lib.rs".to_string()],
+            reverted: true,
+        };
+        assert_eq!(
+            revert.message(),
+            "Quality gates failed after the iteration (test); reverted 1 file"
+        );
+        let instruction = revert.instruction();
+        assert!(instruction.contains("src/lib.rs"));
+        assert!(instruction.ends_with("```\n== test ==\n1 failed\n```"));
+    }
+
+    #[test]
+    fn test_no_baseline_when_disabled() {
+        let config = AfkConfig::default();
+        assert!(gate_baseline(&config).is_none());
+    }
+}
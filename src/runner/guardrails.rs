@@ -0,0 +1,270 @@
+//! Per-iteration guardrails.
+//!
+//! This module checks the size of the change an iteration produced against
+//! the configured limits, so oversized rewrites are not landed unreviewed,
+//! and reverts changes to protected paths such as CI workflows or secrets.
+
+use std::path::Path;
+
+use crate::config::GuardrailsConfig;
+use crate::git::{
+    changed_files_since_in, diff_stats_since, get_current_commit, get_repo_root, restore_paths_in,
+    DiffStats,
+};
+use crate::path_matcher::glob_matches;
+use crate::prd::PrdDocument;
+use crate::progress::SessionProgress;
+
+use super::hooks::newly_completed;
+
+/// Whether any guardrail limit, protected path or secret scan is configured.
+pub fn guardrails_enabled(config: &GuardrailsConfig) -> bool {
+    config.max_changed_lines > 0
+        || config.max_changed_files > 0
+        || !config.protected_paths.is_empty()
+        || config.secret_scan
+}
+
+/// Record the commit an iteration starts from, if guardrails are enabled.
+pub fn guardrail_baseline(config: &GuardrailsConfig) -> Option<String> {
+    if guardrails_enabled(config) {
+        get_current_commit()
+    } else {
+        None
+    }
+}
+
+/// Check the change since `baseline` against the limits.
+///
+/// Returns a description of the violation, or None if within limits.
+pub fn check_iteration_diff(config: &GuardrailsConfig, baseline: &str) -> Option<String> {
+    diff_stats_since(baseline).and_then(|stats| check_guardrails(config, &stats))
+}
+
+/// Check diff stats against the limits.
+///
+/// Returns a description of the violation, or None if within limits.
+pub fn check_guardrails(config: &GuardrailsConfig, stats: &DiffStats) -> Option<String> {
+    let mut violations = Vec::new();
+
+    if config.max_changed_lines > 0 && stats.lines > config.max_changed_lines {
+        violations.push(format!(
+            "{} lines changed (limit {})",
+            stats.lines, config.max_changed_lines
+        ));
+    }
+    if config.max_changed_files > 0 && stats.files > config.max_changed_files {
+        violations.push(format!(
+            "{} files changed (limit {})",
+            stats.files, config.max_changed_files
+        ));
+    }
+
+    if violations.is_empty() {
+        None
+    } else {
+        Some(format!(
+            "Diff guardrail exceeded: {}",
+            violations.join(", ")
+        ))
+    }
+}
+
+/// Build the prompt instruction asking the AI to split oversized work.
+pub fn split_instruction(violation: &str) -> String {
+    format!(
+        "The previous iteration was too large ({violation}). Do not land large changes in \
+         one go: split the remaining work into smaller, independently reviewable steps and \
+         complete only one step this iteration."
+    )
+}
+
+/// Put each story newly marked passed in `new` (compared with `old`) back
+/// to pending, as the iteration that completed it was too large to accept.
+/// Returns a message for each one.
+pub fn reopen_oversized(old: &PrdDocument, new: &mut PrdDocument) -> Vec<String> {
+    let reopened: Vec<String> = newly_completed(old, new)
+        .into_iter()
+        .map(|s| s.id.clone())
+        .collect();
+    for story in new
+        .user_stories
+        .iter_mut()
+        .filter(|s| reopened.contains(&s.id))
+    {
+        story.passes = false;
+    }
+    if !reopened.is_empty() && !crate::config::is_read_only() {
+        let _ = new.save(None);
+    }
+    reopened
+        .iter()
+        .map(|id| format!("{id} back to pending until the work is split"))
+        .collect()
+}
+
+/// The changed files that match a protected path glob.
+pub fn protected_changes(config: &GuardrailsConfig, changed: &[String]) -> Vec<String> {
+    changed
+        .iter()
+        .filter(|path| {
+            config
+                .protected_paths
+                .iter()
+                .any(|glob| glob_matches(glob, path))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Revert changes to protected paths since `baseline`, recording the
+/// violation against the task the iteration worked on.
+///
+/// Returns a description of the violation, or None if no protected path
+/// was touched.
+pub fn revert_protected_paths(
+    config: &GuardrailsConfig,
+    baseline: &str,
+    task: Option<(&str, &str)>,
+) -> Option<String> {
+    if config.protected_paths.is_empty() {
+        return None;
+    }
+    let root = get_repo_root()?;
+    let violation = revert_protected_paths_in(config, Path::new(&root), baseline)?;
+    if let Some((id, source)) = task {
+        if !crate::config::is_read_only() {
+            let mut progress = SessionProgress::load(None).unwrap_or_default();
+            progress.record_violation(id, &violation, source);
+            let _ = progress.save(None);
+        }
+    }
+    Some(violation)
+}
+
+/// Revert changes to protected paths since `baseline` in the worktree at
+/// `dir`, without recording anything.
+///
+/// Returns a description of the violation, or None if no protected path
+/// was touched.
+pub fn revert_protected_paths_in(
+    config: &GuardrailsConfig,
+    dir: &Path,
+    baseline: &str,
+) -> Option<String> {
+    if config.protected_paths.is_empty() {
+        return None;
+    }
+    let touched = protected_changes(config, &changed_files_since_in(dir, baseline));
+    if touched.is_empty() {
+        return None;
+    }
+    let message = "afk: revert changes to protected paths";
+    let reverted = if restore_paths_in(dir, baseline, &touched, message) {
+        "reverted"
+    } else {
+        "could not all be reverted"
+    };
+    Some(format!(
+        "Protected paths changed ({reverted}): {}",
+        touched.join(", ")
+    ))
+}
+
+/// Build the prompt instruction telling the AI its protected-path changes
+/// were undone.
+pub fn protected_instruction(violation: &str, config: &GuardrailsConfig) -> String {
+    format!(
+        "The previous iteration broke a guardrail. {violation}. These paths are protected \
+         and any change to them is undone: {}. Complete the task without modifying them.",
+        config.protected_paths.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(lines: u32, files: u32) -> GuardrailsConfig {
+        GuardrailsConfig {
+            max_changed_lines: lines,
+            max_changed_files: files,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_guardrails_disabled_by_default() {
+        let config = GuardrailsConfig::default();
+        assert!(!guardrails_enabled(&config));
+        assert!(guardrail_baseline(&config).is_none());
+
+        let stats = DiffStats {
+            files: 1000,
+            lines: 100_000,
+        };
+        assert!(check_guardrails(&config, &stats).is_none());
+    }
+
+    #[test]
+    fn test_check_guardrails_within_limits() {
+        let stats = DiffStats {
+            files: 5,
+            lines: 200,
+        };
+        assert!(check_guardrails(&limits(500, 10), &stats).is_none());
+    }
+
+    #[test]
+    fn test_check_guardrails_exceeded() {
+        let stats = DiffStats {
+            files: 12,
+            lines: 8000,
+        };
+
+        let violation = check_guardrails(&limits(500, 10), &stats).unwrap();
+        assert!(violation.contains("8000 lines changed (limit 500)"));
+        assert!(violation.contains("12 files changed (limit 10)"));
+
+        let violation = check_guardrails(&limits(0, 10), &stats).unwrap();
+        assert!(!violation.contains("lines changed"));
+    }
+
+    #[test]
+    fn test_split_instruction_includes_violation() {
+        let instruction = split_instruction("900 lines changed (limit 500)");
+        assert!(instruction.contains("900 lines changed"));
+        assert!(instruction.contains("smaller"));
+    }
+
+    #[test]
+    fn test_protected_changes() {
+        let config = GuardrailsConfig {
+            protected_paths: vec![".github/workflows/**".to_string(), "*.pem".to_string()],
+            ..Default::default()
+        };
+        assert!(guardrails_enabled(&config));
+        let changed = [
+            ".github/workflows/ci.yml".to_string(),
+            "src/main.rs".to_string(),
+            "certs/server.pem".to_string(),
+        ];
+        assert_eq!(
+            protected_changes(&config, &changed),
+            [".github/workflows/ci.yml", "certs/server.pem"]
+        );
+        assert!(protected_changes(&GuardrailsConfig::default(), &changed).is_empty());
+    }
+
+    #[test]
+    fn test_protected_instruction_lists_globs() {
+        let config = GuardrailsConfig {
+            protected_paths: vec!["secrets/**".to_string()],
+            ..Default::default()
+        };
+        let instruction =
+            protected_instruction("Protected paths changed (reverted): secrets/key", &config);
+        assert!(instruction.contains("secrets/key"));
+        assert!(instruction.contains("protected and any change to them is undone: secrets/**"));
+    }
+}
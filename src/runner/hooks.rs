@@ -0,0 +1,323 @@
+//! Lifecycle hooks.
+//!
+//! The `hooks` config section names shell commands to run at points in the
+//! loop (before and after each iteration, when a task completes or fails,
+//! when quality gates fail), so users can send notifications or clean up
+//! without patching afk. Hooks get the details through `AFK_*` environment
+//! variables. Their output is captured; a hook that fails is reported as a
+//! warning and never stops the loop.
+
+use std::process::Stdio;
+
+use crate::config::HooksConfig;
+use crate::prd::{PrdDocument, UserStory};
+use crate::progress::SessionProgress;
+
+use super::iteration::IterationResult;
+use super::process::shell_command;
+
+/// Lines of a failing hook's output shown in its warning.
+const OUTPUT_LINES: usize = 5;
+
+/// A point in the loop's lifecycle that can run a hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// Before an iteration, once its task is picked.
+    PreIteration,
+    /// After an iteration.
+    PostIteration,
+    /// A task was completed.
+    TaskComplete,
+    /// A task failure was recorded.
+    TaskFail,
+    /// Quality gates failed.
+    GateFail,
+}
+
+impl HookEvent {
+    /// Config key of the hook, also passed to it as `AFK_HOOK`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PreIteration => "pre_iteration",
+            Self::PostIteration => "post_iteration",
+            Self::TaskComplete => "on_task_complete",
+            Self::TaskFail => "on_task_fail",
+            Self::GateFail => "on_gate_fail",
+        }
+    }
+
+    /// The command configured for this event, if any.
+    fn command(self, hooks: &HooksConfig) -> Option<&str> {
+        let command = match self {
+            Self::PreIteration => &hooks.pre_iteration,
+            Self::PostIteration => &hooks.post_iteration,
+            Self::TaskComplete => &hooks.on_task_complete,
+            Self::TaskFail => &hooks.on_task_fail,
+            Self::GateFail => &hooks.on_gate_fail,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// What a hook is told about the event.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookContext {
+    /// Iteration number (`AFK_ITERATION`).
+    pub iteration: Option<u32>,
+    /// Task ID (`AFK_TASK_ID`).
+    pub task_id: Option<String>,
+    /// Task title (`AFK_TASK_TITLE`).
+    pub task_title: Option<String>,
+    /// Whether the iteration succeeded (`AFK_SUCCESS`).
+    pub success: Option<bool>,
+    /// Error or failure message (`AFK_ERROR`).
+    pub error: Option<String>,
+    /// Names of failing gates (`AFK_FAILED_GATES`, comma-separated).
+    pub failed_gates: Vec<String>,
+}
+
+impl HookContext {
+    /// Context for an iteration working on a task.
+    pub fn for_task(iteration: u32, task: Option<&UserStory>) -> Self {
+        Self {
+            iteration: Some(iteration),
+            task_id: task.map(|t| t.id.clone()),
+            task_title: task.map(|t| t.title.clone()),
+            ..Default::default()
+        }
+    }
+
+    /// Environment variables for a hook.
+    pub fn env(&self, event: HookEvent) -> Vec<(&'static str, String)> {
+        let mut env = vec![("AFK_HOOK", event.name().to_string())];
+        if let Some(iteration) = self.iteration {
+            env.push(("AFK_ITERATION", iteration.to_string()));
+        }
+        if let Some(id) = &self.task_id {
+            env.push(("AFK_TASK_ID", id.clone()));
+        }
+        if let Some(title) = &self.task_title {
+            env.push(("AFK_TASK_TITLE", title.clone()));
+        }
+        if let Some(success) = self.success {
+            env.push(("AFK_SUCCESS", success.to_string()));
+        }
+        if let Some(error) = &self.error {
+            env.push(("AFK_ERROR", error.clone()));
+        }
+        if !self.failed_gates.is_empty() {
+            env.push(("AFK_FAILED_GATES", self.failed_gates.join(",")));
+        }
+        env
+    }
+}
+
+/// Run the hook configured for an event, if there is one.
+///
+/// Returns a warning if the hook couldn't be run or exited non-zero.
+pub fn run_hook(hooks: &HooksConfig, event: HookEvent, context: &HookContext) -> Option<String> {
+    let command = event.command(hooks)?;
+    let output = shell_command(command)
+        .envs(context.env(event))
+        .stdin(Stdio::null())
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => {
+            let text = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+            let tail = lines[lines.len().saturating_sub(OUTPUT_LINES)..].join("\n");
+            let status = output
+                .status
+                .code()
+                .map_or_else(|| "a signal".to_string(), |c| format!("status {c}"));
+            Some(if tail.is_empty() {
+                format!("{} hook exited with {status}", event.name())
+            } else {
+                format!("{} hook exited with {status}:\n{tail}", event.name())
+            })
+        }
+        Err(e) => Some(format!("Failed to run {} hook: {e}", event.name())),
+    }
+}
+
+/// Failures recorded so far for a task, to spot one an iteration adds.
+pub fn task_failures(task_id: Option<&str>) -> u32 {
+    let Some(id) = task_id else {
+        return 0;
+    };
+    SessionProgress::load(None)
+        .ok()
+        .and_then(|p| p.get_task(id).map(|t| t.failure_count))
+        .unwrap_or(0)
+}
+
+/// Run the hooks due after an iteration: `post_iteration`, then
+/// `on_task_fail` if the iteration recorded a failure for its task.
+///
+/// Completion signals count as success. Returns any warnings.
+pub fn after_iteration(
+    hooks: &HooksConfig,
+    context: &HookContext,
+    result: &IterationResult,
+    failures_before: u32,
+) -> Vec<String> {
+    let signalled = matches!(
+        result.error.as_deref(),
+        Some("AFK_COMPLETE" | "AFK_LIMIT_REACHED")
+    );
+    let success = result.success || signalled;
+    let post = HookContext {
+        success: Some(success),
+        error: if success { None } else { result.error.clone() },
+        ..context.clone()
+    };
+    let mut warnings: Vec<String> = run_hook(hooks, HookEvent::PostIteration, &post)
+        .into_iter()
+        .collect();
+
+    let task_id = context.task_id.as_deref();
+    if task_failures(task_id) > failures_before {
+        let message = task_id.and_then(|id| {
+            SessionProgress::load(None)
+                .ok()
+                .and_then(|p| p.get_task(id).and_then(|t| t.message.clone()))
+        });
+        let failed = HookContext {
+            error: message,
+            ..context.clone()
+        };
+        warnings.extend(run_hook(hooks, HookEvent::TaskFail, &failed));
+    }
+    warnings
+}
+
+/// Tasks passing in `new` that weren't passing in `old`.
+pub fn newly_completed<'a>(old: &PrdDocument, new: &'a PrdDocument) -> Vec<&'a UserStory> {
+    new.user_stories
+        .iter()
+        .filter(|s| s.passes && !old.get_story(&s.id).is_some_and(|o| o.passes))
+        .collect()
+}
+
+/// Run `on_task_complete` for each task completed since `old` was loaded
+/// (compared with `.afk/tasks.json` now). Returns any warnings.
+pub fn on_tasks_completed(hooks: &HooksConfig, iteration: u32, old: &PrdDocument) -> Vec<String> {
+    if HookEvent::TaskComplete.command(hooks).is_none() {
+        return Vec::new();
+    }
+    let Ok(new) = PrdDocument::load(None) else {
+        return Vec::new();
+    };
+    newly_completed(old, &new)
+        .into_iter()
+        .filter_map(|story| {
+            run_hook(
+                hooks,
+                HookEvent::TaskComplete,
+                &HookContext::for_task(iteration, Some(story)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: &str, passes: bool) -> UserStory {
+        UserStory {
+            passes,
+            ..UserStory::new(id, format!("Title of {id}"))
+        }
+    }
+
+    #[test]
+    fn test_context_env() {
+        let context = HookContext {
+            success: Some(false),
+            error: Some("boom".to_string()),
+            failed_gates: vec!["lint".to_string(), "test".to_string()],
+            ..HookContext::for_task(3, Some(&story("auth-1", false)))
+        };
+        let env = context.env(HookEvent::PostIteration);
+        assert_eq!(
+            env,
+            vec![
+                ("AFK_HOOK", "post_iteration".to_string()),
+                ("AFK_ITERATION", "3".to_string()),
+                ("AFK_TASK_ID", "auth-1".to_string()),
+                ("AFK_TASK_TITLE", "Title of auth-1".to_string()),
+                ("AFK_SUCCESS", "false".to_string()),
+                ("AFK_ERROR", "boom".to_string()),
+                ("AFK_FAILED_GATES", "lint,test".to_string()),
+            ]
+        );
+        assert_eq!(
+            HookContext::default().env(HookEvent::GateFail),
+            vec![("AFK_HOOK", "on_gate_fail".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_unconfigured_hook_does_nothing() {
+        let hooks = HooksConfig {
+            post_iteration: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert!(run_hook(&hooks, HookEvent::PreIteration, &HookContext::default()).is_none());
+        assert!(run_hook(&hooks, HookEvent::PostIteration, &HookContext::default()).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hook_gets_env_and_reports_failure() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let out = temp.path().join("hook.txt");
+        let hooks = HooksConfig {
+            pre_iteration: Some(format!(
+                "echo \"$AFK_HOOK $AFK_ITERATION $AFK_TASK_ID\" > {}",
+                out.display()
+            )),
+            on_task_fail: Some("echo nope; exit 3".to_string()),
+            ..Default::default()
+        };
+        let context = HookContext::for_task(2, Some(&story("db-1", false)));
+
+        assert!(run_hook(&hooks, HookEvent::PreIteration, &context).is_none());
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap().trim(),
+            "pre_iteration 2 db-1"
+        );
+
+        let warning = run_hook(&hooks, HookEvent::TaskFail, &context).unwrap();
+        assert!(warning.starts_with("on_task_fail hook exited with status 3:\nnope"));
+    }
+
+    #[test]
+    fn test_newly_completed() {
+        let old = PrdDocument {
+            user_stories: vec![story("a", true), story("b", false), story("c", false)],
+            ..Default::default()
+        };
+        let new = PrdDocument {
+            user_stories: vec![
+                story("a", true),
+                story("b", true),
+                story("c", false),
+                story("d", true),
+            ],
+            ..Default::default()
+        };
+        let ids: Vec<&str> = newly_completed(&old, &new)
+            .iter()
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["b", "d"]);
+    }
+}
@@ -0,0 +1,409 @@
+//! HTTP backends: OpenAI-compatible endpoints and local Ollama servers.
+//!
+//! With `ai_cli.backend` set to `http`, afk posts the prompt to a chat
+//! completions endpoint instead of spawning a CLI, and reads the reply as
+//! server-sent events. With `ollama`, it posts to the Ollama server's
+//! `/api/chat`, asking for `ollama.context_size` tokens of context, and
+//! reads the reply as one JSON object per line. Each event's JSON is
+//! handed on as a line of output, so the reply goes through the same
+//! parser, transcript and completion checks as a CLI's stream-json output,
+//! under the same stall limit and cancellation as
+//! [`run_attempt`](super::attempt::run_attempt).
+
+use std::io;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, AiBackend, AiCliConfig, OllamaConfig};
+
+use super::attempt::{idle, Attempt, AttemptEnd, AttemptError, Flow};
+use super::capture::OutputLine;
+
+/// Longest error body kept from a refused request.
+const MAX_ERROR_BODY: usize = 500;
+
+/// The request to the HTTP backend, shown and recorded in place of a CLI
+/// command line.
+pub fn request_line(config: &AfkConfig, model: Option<&str>) -> Vec<String> {
+    let (url, model) = match config.ai_cli.backend {
+        AiBackend::Ollama => (
+            config.ollama.chat_url(),
+            model.or(config.ollama.model.as_deref()),
+        ),
+        AiBackend::Cli | AiBackend::Http => (
+            config.ai_cli.chat_completions_url(),
+            model.or_else(|| config.ai_cli.models.first().map(String::as_str)),
+        ),
+    };
+    let mut line = vec!["POST".to_string(), url];
+    if let Some(model) = model {
+        line.push(format!("model={model}"));
+    }
+    line
+}
+
+/// Ask the HTTP backend for a reply to `prompt` from `model`, calling
+/// `on_line` with each streamed chunk.
+pub fn run_http_attempt(
+    ai_cli: &AiCliConfig,
+    model: Option<&str>,
+    prompt: &str,
+    stall: Option<Duration>,
+    cancel: &CancellationToken,
+    on_line: impl FnMut(OutputLine) -> Flow,
+) -> Result<Attempt, AttemptError> {
+    let model = model
+        .or_else(|| ai_cli.models.first().map(String::as_str))
+        .ok_or(AttemptError::NoModel)?;
+    let mut request = client()?.post(ai_cli.chat_completions_url()).json(&json!({
+        "model": model,
+        "messages": [{"role": "user", "content": prompt}],
+        "stream": true,
+        "stream_options": {"include_usage": true},
+    }));
+    if let Some(key) = ai_cli.api_key() {
+        request = request.bearer_auth(key);
+    }
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(AttemptError::Runtime)?
+        .block_on(drive(request, Framing::Sse, stall, cancel, on_line))
+}
+
+/// Ask the Ollama server for a reply to `prompt` from `model`, or else
+/// `ollama.model`, calling `on_line` with each streamed chunk.
+pub fn run_ollama_attempt(
+    ollama: &OllamaConfig,
+    model: Option<&str>,
+    prompt: &str,
+    stall: Option<Duration>,
+    cancel: &CancellationToken,
+    on_line: impl FnMut(OutputLine) -> Flow,
+) -> Result<Attempt, AttemptError> {
+    let model = model
+        .or(ollama.model.as_deref())
+        .ok_or(AttemptError::NoModel)?;
+    let request = client()?.post(ollama.chat_url()).json(&json!({
+        "model": model,
+        "messages": [{"role": "user", "content": prompt}],
+        "stream": true,
+        "options": {"num_ctx": ollama.context_size},
+    }));
+
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(AttemptError::Runtime)?
+        .block_on(drive(request, Framing::Ndjson, stall, cancel, on_line))
+}
+
+fn client() -> Result<reqwest::Client, AttemptError> {
+    reqwest::Client::builder()
+        .user_agent(format!("afk/{}", env!("CARGO_PKG_VERSION")))
+        .connect_timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| AttemptError::Request(e.to_string()))
+}
+
+async fn drive(
+    request: reqwest::RequestBuilder,
+    framing: Framing,
+    stall: Option<Duration>,
+    cancel: &CancellationToken,
+    mut on_line: impl FnMut(OutputLine) -> Flow,
+) -> Result<Attempt, AttemptError> {
+    let ended = |end| Attempt {
+        end,
+        stderr: String::new(),
+        read_error: None,
+    };
+    let started = Instant::now();
+    let mut response = tokio::select! {
+        biased;
+        () = cancel.cancelled() => return Ok(ended(AttemptEnd::Cancelled)),
+        () = idle(stall.map(|limit| started + limit)) => return Ok(ended(AttemptEnd::Stalled)),
+        response = request.send() => response.map_err(|e| AttemptError::Request(e.to_string()))?,
+    };
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AttemptError::Status {
+            status,
+            body: body.trim().chars().take(MAX_ERROR_BODY).collect(),
+        });
+    }
+
+    let mut events = EventStream::new(framing);
+    let mut read_error = None;
+    let mut last_output = Instant::now();
+    let end = 'read: loop {
+        tokio::select! {
+            biased;
+            () = cancel.cancelled() => break AttemptEnd::Cancelled,
+            () = idle(stall.map(|limit| last_output + limit)) => break AttemptEnd::Stalled,
+            chunk = response.chunk() => match chunk {
+                Ok(Some(bytes)) => {
+                    last_output = Instant::now();
+                    for data in events.push(&bytes) {
+                        if data == "[DONE]" {
+                            break 'read AttemptEnd::Finished;
+                        }
+                        let line = OutputLine { text: data, truncated: 0 };
+                        if on_line(line) == Flow::Stop {
+                            break 'read AttemptEnd::Stopped;
+                        }
+                    }
+                }
+                Ok(None) => break AttemptEnd::Finished,
+                Err(e) => {
+                    read_error = Some(io::Error::other(e));
+                    break AttemptEnd::Finished;
+                }
+            }
+        }
+    };
+    Ok(Attempt {
+        read_error,
+        ..ended(end)
+    })
+}
+
+/// How a streamed reply is split into events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// Server-sent events, with each event's JSON in a `data:` line.
+    Sse,
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// Splits a streamed reply into the data of each event.
+#[derive(Debug)]
+struct EventStream {
+    framing: Framing,
+    buffer: Vec<u8>,
+}
+
+impl EventStream {
+    fn new(framing: Framing) -> Self {
+        Self {
+            framing,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Add bytes from the stream, returning the data lines now complete.
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let mut data = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let payload = match self.framing {
+                Framing::Sse => line.trim_end().strip_prefix("data:").map(str::trim_start),
+                Framing::Ndjson => Some(line.trim()).filter(|line| !line.is_empty()),
+            };
+            if let Some(payload) = payload {
+                data.push(payload.to_string());
+            }
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AiBackend;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve one request with `response`, returning the server's address
+    /// and a handle yielding the request body.
+    fn serve(response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                if header.trim().is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (format!("http://127.0.0.1:{port}"), handle)
+    }
+
+    fn http_config(address: &str) -> AiCliConfig {
+        AiCliConfig {
+            backend: AiBackend::Http,
+            base_url: Some(format!("{address}/v1/")),
+            api_key_env: Some("AFK_TEST_UNSET_API_KEY".to_string()),
+            models: vec!["test-model".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_http_attempt_streams_events() {
+        let (address, server) = serve(concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+            ": keep-alive\n\n",
+            "data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3}}\r\n\r\n",
+            "data: [DONE]\n\n",
+        ));
+        let mut lines = Vec::new();
+        let attempt = run_http_attempt(
+            &http_config(&address),
+            None,
+            "Say hi",
+            None,
+            &CancellationToken::new(),
+            |line| {
+                lines.push(line.text);
+                Flow::Continue
+            },
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Finished));
+        assert_eq!(
+            lines,
+            [
+                r#"{"choices":[{"delta":{"content":"Hi"}}]}"#,
+                r#"{"choices":[],"usage":{"prompt_tokens":3}}"#,
+            ]
+        );
+        let request: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(request["model"], "test-model");
+        assert_eq!(request["stream"], true);
+        assert_eq!(request["messages"][0]["content"], "Say hi");
+    }
+
+    #[test]
+    fn test_http_attempt_reports_refusal() {
+        let (address, _server) = serve(concat!(
+            "HTTP/1.1 401 Unauthorized\r\nContent-Length: 15\r\nConnection: close\r\n\r\n",
+            "invalid api key",
+        ));
+        let err = run_http_attempt(
+            &http_config(&address),
+            Some("other-model"),
+            "Say hi",
+            None,
+            &CancellationToken::new(),
+            |_| Flow::Continue,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            AttemptError::Status { status: 401, ref body } if body == "invalid api key"
+        ));
+    }
+
+    #[test]
+    fn test_ollama_attempt_streams_lines() {
+        let (address, server) = serve(concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n",
+            "{\"message\":{\"content\":\"Hi\"},\"done\":false}\n",
+            "{\"message\":{\"content\":\"\"},\"done\":true,\"eval_count\":1}\n",
+        ));
+        let ollama = OllamaConfig {
+            host: address,
+            model: Some("qwen2.5-coder:7b".to_string()),
+            context_size: 4096,
+        };
+        let mut lines = Vec::new();
+        let attempt = run_ollama_attempt(
+            &ollama,
+            None,
+            "Say hi",
+            None,
+            &CancellationToken::new(),
+            |line| {
+                lines.push(line.text);
+                Flow::Continue
+            },
+        )
+        .unwrap();
+        assert!(matches!(attempt.end, AttemptEnd::Finished));
+        assert_eq!(lines.len(), 2);
+        let request: serde_json::Value = serde_json::from_str(&server.join().unwrap()).unwrap();
+        assert_eq!(request["model"], "qwen2.5-coder:7b");
+        assert_eq!(request["options"]["num_ctx"], 4096);
+    }
+
+    #[test]
+    fn test_request_line() {
+        let mut config = AfkConfig::default();
+        config.ai_cli.backend = AiBackend::Ollama;
+        config.ollama.model = Some("llama3.1:8b".to_string());
+        assert_eq!(
+            request_line(&config, None),
+            [
+                "POST",
+                "http://localhost:11434/api/chat",
+                "model=llama3.1:8b"
+            ]
+        );
+        assert_eq!(
+            request_line(&config, Some("qwen2.5-coder:7b"))[2],
+            "model=qwen2.5-coder:7b"
+        );
+    }
+
+    #[test]
+    fn test_http_attempt_needs_a_model() {
+        let ai_cli = AiCliConfig {
+            backend: AiBackend::Http,
+            models: Vec::new(),
+            ..Default::default()
+        };
+        let err = run_http_attempt(
+            &ai_cli,
+            None,
+            "Say hi",
+            None,
+            &CancellationToken::new(),
+            |_| Flow::Continue,
+        )
+        .unwrap_err();
+        assert!(matches!(err, AttemptError::NoModel));
+    }
+
+    #[test]
+    fn test_event_stream_splits_across_chunks() {
+        let mut events = EventStream::new(Framing::Sse);
+        assert!(events.push(b"data: {\"a\":").is_empty());
+        assert_eq!(
+            events.push(b"1}\n\nevent: ping\ndata:[DONE]\n"),
+            ["{\"a\":1}", "[DONE]"]
+        );
+    }
+
+    #[test]
+    fn test_event_stream_splits_ndjson() {
+        let mut events = EventStream::new(Framing::Ndjson);
+        assert!(events.push(b"{\"a\":").is_empty());
+        assert_eq!(
+            events.push(b"1}\n\n{\"b\":2}\r\n"),
+            ["{\"a\":1}", "{\"b\":2}"]
+        );
+    }
+}
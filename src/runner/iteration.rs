@@ -3,17 +3,33 @@
 //! This module handles spawning AI CLI, streaming output, and detecting completion signals.
 //! Supports both plain text and NDJSON stream-json output formats.
 
-use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 use std::sync::mpsc::Sender;
 
-use crate::config::AfkConfig;
-use crate::parser::{StreamEvent, StreamJsonParser};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, AiBackend};
+use crate::events::{self, Event};
+use crate::parser::{
+    rate_limit_message, AgentResult, ResultCollector, StreamEvent, StreamJsonParser,
+};
+use crate::prd::{PrdDocument, TaskOrder};
+use crate::progress::repro::{build_record, new_seed, save_record};
+use crate::progress::transcript::IterationLog;
 use crate::prompt::generate_prompt_with_root;
 use crate::tui::TuiEvent;
 
-use super::make_path_relative;
+use super::artifacts::save_stderr_artifact;
+use super::attempt::{run_attempt, AttemptEnd, AttemptError, Flow};
+use super::capture::{truncate_display, OutputLine};
+use super::checkpoint::{record_phase, IterationPhase};
+use super::escalation;
+use super::http_backend::{request_line, run_http_attempt, run_ollama_attempt};
 use super::output_handler::OutputHandler;
+use super::overrides;
+use super::process::program_command;
+use super::stall::{retry_message, retry_prompt, stall_limit, stalled_error, STALLED};
+use super::{make_path_relative, tool_target};
 
 /// Result of a single iteration.
 #[derive(Debug)]
@@ -26,6 +42,8 @@ pub struct IterationResult {
     pub error: Option<String>,
     /// Output from the AI CLI.
     pub output: String,
+    /// The agent's `afk-result` block, if it ended with one.
+    pub agent_result: Option<AgentResult>,
 }
 
 impl IterationResult {
@@ -36,6 +54,7 @@ impl IterationResult {
             task_id: None,
             error: None,
             output,
+            agent_result: None,
         }
     }
 
@@ -46,6 +65,7 @@ impl IterationResult {
             task_id: None,
             error: Some(error.into()),
             output: String::new(),
+            agent_result: None,
         }
     }
 
@@ -56,6 +76,7 @@ impl IterationResult {
             task_id: None,
             error: Some(error.into()),
             output,
+            agent_result: None,
         }
     }
 }
@@ -72,16 +93,20 @@ pub struct IterationRunner {
     tui_sender: Option<Sender<TuiEvent>>,
     /// NDJSON parser for stream-json format.
     stream_parser: Option<StreamJsonParser>,
+    /// Extra instructions appended to the next generated prompt.
+    prompt_notes: Vec<String>,
+    /// Cancelled on Ctrl-C; stops the running AI CLI.
+    cancel: CancellationToken,
+    /// Rate-limit message the AI CLI reported during the current attempt.
+    rate_limit: Option<String>,
+    /// The `afk-result` block the agent ended the current attempt with.
+    agent_result: Option<AgentResult>,
 }
 
 impl IterationRunner {
     /// Create a new IterationRunner.
     pub fn new(config: AfkConfig) -> Self {
-        let stream_parser = if config.ai_cli.uses_stream_json() {
-            Some(StreamJsonParser::new(config.ai_cli.detect_cli_format()))
-        } else {
-            None
-        };
+        let stream_parser = stream_parser_for(&config);
 
         let mut output = OutputHandler::new();
         output.set_activity_thresholds(
@@ -98,16 +123,16 @@ impl IterationRunner {
             current_task_description: None,
             tui_sender: None,
             stream_parser,
+            prompt_notes: Vec::new(),
+            cancel: CancellationToken::new(),
+            rate_limit: None,
+            agent_result: None,
         }
     }
 
     /// Create with custom OutputHandler.
     pub fn with_output_handler(config: AfkConfig, output: OutputHandler) -> Self {
-        let stream_parser = if config.ai_cli.uses_stream_json() {
-            Some(StreamJsonParser::new(config.ai_cli.detect_cli_format()))
-        } else {
-            None
-        };
+        let stream_parser = stream_parser_for(&config);
 
         Self {
             config,
@@ -118,14 +143,31 @@ impl IterationRunner {
             current_task_description: None,
             tui_sender: None,
             stream_parser,
+            prompt_notes: Vec::new(),
+            cancel: CancellationToken::new(),
+            rate_limit: None,
+            agent_result: None,
         }
     }
 
+    /// Stop the running AI CLI, and everything it started, once `token`
+    /// is cancelled.
+    pub fn set_cancel_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
     /// Set a TUI event sender for real-time updates.
     pub fn set_tui_sender(&mut self, sender: Sender<TuiEvent>) {
         self.tui_sender = Some(sender);
     }
 
+    /// Set extra instructions for the next generated prompt.
+    ///
+    /// Replaces any previously set notes; pass an empty vec to clear them.
+    pub fn set_prompt_notes(&mut self, notes: Vec<String>) {
+        self.prompt_notes = notes;
+    }
+
     /// Set context for the current iteration.
     pub fn set_iteration_context(
         &mut self,
@@ -156,11 +198,11 @@ impl IterationRunner {
             self.current_iteration = iteration;
         }
 
-        // Generate prompt if not provided
-        let prompt = match prompt {
-            Some(p) => p,
-            None => match generate_prompt_with_root(&self.config, true, None, None) {
-                Ok(result) => result.prompt,
+        // Generate prompt if not provided, noting its session iteration and task
+        let (prompt, session_iteration, task_id) = match prompt {
+            Some(p) => (p, None, None),
+            None => match generate_prompt_with_root(&self.prompt_config(), true, None, None) {
+                Ok(result) => (result.prompt, Some(result.iteration), result.task_id),
                 Err(e) => {
                     return IterationResult::failure(format!("Failed to generate prompt: {e}"));
                 }
@@ -174,6 +216,7 @@ impl IterationRunner {
                 task_id: None,
                 error: Some("AFK_COMPLETE".to_string()),
                 output: String::new(),
+                agent_result: None,
             };
         }
         if prompt.contains("AFK_LIMIT_REACHED") {
@@ -182,29 +225,59 @@ impl IterationRunner {
                 task_id: None,
                 error: Some("AFK_LIMIT_REACHED".to_string()),
                 output: String::new(),
+                agent_result: None,
             };
         }
 
-        // Select model upfront so we can display it
-        let selected_model = self.config.ai_cli.select_model().map(|s| s.to_string());
+        record_phase(IterationPhase::PromptBuilt);
+
+        // Apply the task's own model and CLI, if it sets them
+        let config = overrides::for_task(&self.config, task_id.as_deref()).into_owned();
+        self.stream_parser = stream_parser_for(&config);
+
+        // Select model upfront so we can display it, escalating a failing task
+        let choice = escalation::choose_and_record(&config, task_id.as_deref(), new_seed());
+        let (seed, selected_model) = (choice.seed, choice.model.clone());
 
         // Build command with output format args and selected model
-        let mut cmd_parts = vec![self.config.ai_cli.command.clone()];
-        cmd_parts.extend(
-            self.config
-                .ai_cli
-                .full_args_with_model(selected_model.as_deref()),
-        );
+        let cmd_parts = match config.ai_cli.backend {
+            AiBackend::Cli => {
+                let mut parts = vec![config.ai_cli.command.clone()];
+                parts.extend(
+                    config
+                        .ai_cli
+                        .full_args_with_model(selected_model.as_deref()),
+                );
+                parts
+            }
+            AiBackend::Http | AiBackend::Ollama => request_line(&config, selected_model.as_deref()),
+        };
+
+        if let Some(session_iteration) = session_iteration {
+            if let Err(e) = record_iteration(
+                &config,
+                session_iteration,
+                seed,
+                selected_model.as_deref(),
+                &cmd_parts,
+                &prompt,
+            ) {
+                self.output
+                    .warning(&format!("Could not save repro metadata: {e}"));
+            }
+        }
 
         self.output.iteration_header(iteration, self.max_iterations);
 
-        // Display model selection if multiple models configured
-        if self.config.ai_cli.models.len() > 1 {
+        // Display model selection if escalated or multiple models configured
+        if let Some(message) = choice.escalation_message() {
+            self.output.warning(&message);
+        } else if config.ai_cli.models.len() > 1 {
             if let Some(ref model) = selected_model {
                 self.output.info(&format!(
                     "Model: {} (1 of {})",
                     model,
-                    self.config.ai_cli.models.len()
+                    config.ai_cli.models.len()
                 ));
             }
         }
@@ -221,18 +294,83 @@ impl IterationRunner {
                 self.current_task_id.as_ref(),
                 self.current_task_description.as_ref(),
             ) {
+                let epic = PrdDocument::load(None)
+                    .ok()
+                    .and_then(|prd| prd.get_story(id).and_then(|s| s.epic.clone()));
                 let _ = sender.send(TuiEvent::TaskInfo {
                     id: id.clone(),
                     title: title.clone(),
+                    epic,
                 });
             }
         }
 
-        self.execute_command(&cmd_parts, &prompt)
+        let log_iteration = session_iteration.unwrap_or(self.current_iteration);
+        let mut log = IterationLog::start(&self.config.feedback, log_iteration, &cmd_parts);
+        let mut attempt_prompt = prompt.clone();
+        let mut attempt = 0;
+        loop {
+            let result = self.execute_command(
+                &cmd_parts,
+                &config,
+                selected_model.as_deref(),
+                &attempt_prompt,
+                log_iteration,
+                &mut log,
+            );
+            let limit = match stall_limit(&self.config) {
+                Some(limit) if result.error.as_deref() == Some(STALLED) => limit,
+                _ => {
+                    return IterationResult {
+                        agent_result: self.agent_result.take(),
+                        ..result
+                    }
+                }
+            };
+            attempt += 1;
+            events::record(Event::Stalled {
+                iteration,
+                task_id: self.current_task_id.clone(),
+                idle_seconds: limit.as_secs(),
+                attempt,
+            });
+            let retries = self.config.runner.stall_retries;
+            if attempt > retries {
+                return IterationResult::failure_with_output(stalled_error(limit), result.output);
+            }
+            let message = retry_message(limit, attempt, retries);
+            log.note(&message);
+            self.output.warning(&message);
+            attempt_prompt = retry_prompt(&self.config, &prompt, limit);
+        }
+    }
+
+    /// Config used for prompt generation, including any prompt notes.
+    fn prompt_config(&self) -> AfkConfig {
+        let mut config = self.config.clone();
+        config
+            .prompt
+            .instructions
+            .extend(self.prompt_notes.iter().cloned());
+        config
     }
 
     /// Execute AI CLI command and return result.
-    fn execute_command(&mut self, cmd_parts: &[String], prompt: &str) -> IterationResult {
+    ///
+    /// With the HTTP backend, the request goes to `ai_cli` for `model`
+    /// instead. The AI CLI's stderr is saved under `log_iteration` in the
+    /// artefacts, and its output is written to the iteration's transcript as
+    /// it arrives. If it stalls, it is killed and the result's error is
+    /// [`STALLED`].
+    fn execute_command(
+        &mut self,
+        cmd_parts: &[String],
+        config: &AfkConfig,
+        model: Option<&str>,
+        prompt: &str,
+        log_iteration: u32,
+        log: &mut IterationLog,
+    ) -> IterationResult {
         if cmd_parts.is_empty() {
             return IterationResult::failure("No command specified");
         }
@@ -241,9 +379,10 @@ impl IterationRunner {
         let args: Vec<&str> = cmd_parts[1..].iter().map(|s| s.as_str()).collect();
 
         // Build full command with prompt as final argument
-        let mut cmd = Command::new(command);
+        let mut cmd = program_command(command);
         cmd.args(&args)
             .arg(prompt)
+            .envs(self.config.git.identity_env())
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
@@ -257,121 +396,64 @@ impl IterationRunner {
         );
         self.output.start_feedback(None);
 
-        // Spawn process
-        let mut child = match cmd.spawn() {
-            Ok(child) => child,
+        record_phase(IterationPhase::CliLaunched);
+        self.rate_limit = None;
+        let cancel = self.cancel.clone();
+        let mut output_buffer = Vec::new();
+        let mut collector = ResultCollector::new(stream_parser_for(config));
+        let stall = stall_limit(&self.config);
+        let on_line = |captured: OutputLine| {
+            if captured.truncated > 0 {
+                self.output.warning(&format!(
+                    "Output line too long; dropped {} bytes",
+                    captured.truncated
+                ));
+            }
+            let line = captured.text;
+            log.raw(&line);
+            collector.push_line(&line);
+            let flow = self.handle_line(&line, log);
+            if flow == Flow::Continue {
+                output_buffer.push(format!("{line}\n"));
+            }
+            flow
+        };
+        let attempt = match config.ai_cli.backend {
+            AiBackend::Cli => run_attempt(cmd, stall, &cancel, on_line),
+            AiBackend::Http => {
+                run_http_attempt(&config.ai_cli, model, prompt, stall, &cancel, on_line)
+            }
+            AiBackend::Ollama => {
+                run_ollama_attempt(&config.ollama, model, prompt, stall, &cancel, on_line)
+            }
+        };
+        self.agent_result = collector.result();
+        let attempt = match attempt {
+            Ok(attempt) => attempt,
+            Err(AttemptError::Spawn(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.output.stop_feedback();
+                return IterationResult::failure(format!(
+                    "AI CLI not found: {}. Is it installed and in your PATH?",
+                    command
+                ));
+            }
             Err(e) => {
                 self.output.stop_feedback();
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    return IterationResult::failure(format!(
-                        "AI CLI not found: {}. Is it installed and in your PATH?",
-                        command
-                    ));
-                }
-                return IterationResult::failure(format!("Failed to spawn AI CLI: {e}"));
+                return IterationResult::failure(e.to_string());
             }
         };
-
-        // Stream stdout
-        let mut output_buffer = Vec::new();
-        let mut completion_detected = false;
-
-        if let Some(stdout) = child.stdout.take() {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines() {
-                match line {
-                    Ok(line) => {
-                        // Parse and display based on output format
-                        if self.stream_parser.is_some() {
-                            // NDJSON mode: parse and convert to display text
-                            // Falls back to raw line if parsing fails (CLI doesn't support stream-json)
-                            if let Some(ref mut parser) = self.stream_parser {
-                                if let Some(event) = parser.parse_line(&line) {
-                                    // Check for completion signal only in assistant messages
-                                    // (not in user messages which may contain the prompt with examples)
-                                    if let crate::parser::StreamEvent::AssistantMessage {
-                                        ref text,
-                                    } = event
-                                    {
-                                        if self.output.contains_completion_signal(text) {
-                                            completion_detected = true;
-                                            self.output.completion_detected();
-                                            // Terminate the process
-                                            let _ = child.kill();
-                                            break;
-                                        }
-                                    }
-
-                                    // Convert event to display text and emit TUI event
-                                    let (display, tui_event) = self.stream_event_to_display(&event);
-
-                                    // Send TUI event if we have a sender
-                                    if let (Some(ref sender), Some(tui_event)) =
-                                        (&self.tui_sender, tui_event)
-                                    {
-                                        let _ = sender.send(tui_event);
-                                    }
-
-                                    if let Some(display) = display {
-                                        self.output.stream_line(&format!("{display}\n"));
-                                    }
-                                } else {
-                                    // Parsing returned None - check if it's valid JSON we should suppress
-                                    // vs plain text we should display
-                                    let is_json = line.trim_start().starts_with('{')
-                                        && line.trim_end().ends_with('}');
-
-                                    if is_json {
-                                        // Valid JSON but no displayable content - silently skip
-                                        // Still check for completion signals in case they're embedded
-                                        if self.output.contains_completion_signal(&line) {
-                                            completion_detected = true;
-                                            self.output.completion_detected();
-                                            let _ = child.kill();
-                                            break;
-                                        }
-                                    } else {
-                                        // Plain text output - display as-is (fallback for CLIs
-                                        // that don't support stream-json)
-                                        self.output.stream_line(&format!("{line}\n"));
-                                        if self.output.contains_completion_signal(&line) {
-                                            completion_detected = true;
-                                            self.output.completion_detected();
-                                            let _ = child.kill();
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                        } else {
-                            // Plain text mode: display as-is and check raw line
-                            self.output.stream_line(&format!("{line}\n"));
-                            if self.output.contains_completion_signal(&line) {
-                                completion_detected = true;
-                                self.output.completion_detected();
-                                // Terminate the process
-                                let _ = child.kill();
-                                break;
-                            }
-                        }
-                        output_buffer.push(format!("{line}\n"));
-                    }
-                    Err(e) => {
-                        self.output.warning(&format!("Error reading output: {e}"));
-                        break;
-                    }
-                }
-            }
+        if let Some(e) = attempt.read_error {
+            self.output.warning(&format!("Error reading output: {e}"));
         }
 
-        // Capture stderr before waiting for process
-        let stderr_output = if let Some(stderr) = child.stderr.take() {
-            let reader = BufReader::new(stderr);
-            let lines: Vec<String> = reader.lines().map_while(Result::ok).collect();
-            lines.join("\n")
-        } else {
-            String::new()
-        };
+        // Stderr goes to the iteration log, never into the parsed output
+        let stderr_output = attempt.stderr;
+        log.stderr(&stderr_output);
+        if let Err(e) = save_stderr_artifact(&self.config.artifacts, log_iteration, &stderr_output)
+        {
+            self.output
+                .warning(&format!("Could not save AI CLI stderr: {e}"));
+        }
 
         // Show iteration summary with stats
         self.output.iteration_summary();
@@ -381,36 +463,103 @@ impl IterationRunner {
 
         let output = output_buffer.concat();
 
-        if completion_detected {
-            return IterationResult::success(output);
+        // A CLI that hit a rate limit hasn't done the work, whatever its exit
+        // code says
+        if let Some(message) = self.rate_limit.take() {
+            if !matches!(attempt.end, AttemptEnd::Stopped | AttemptEnd::Cancelled) {
+                return IterationResult::failure_with_output(
+                    format!("AI CLI rate limited: {message}"),
+                    output,
+                );
+            }
         }
 
-        // Wait for process to finish
-        match child.wait() {
-            Ok(status) => {
-                if !status.success() {
-                    let exit_code = status.code().unwrap_or(-1);
-                    // Include full stderr in error message
-                    let error_msg = if stderr_output.is_empty() {
-                        format!("AI CLI exited with code {exit_code}")
-                    } else {
-                        format!(
-                            "AI CLI exited with code {exit_code}\n\x1b[31m{}\x1b[0m",
-                            stderr_output.trim()
-                        )
-                    };
-                    // Display stderr prominently
-                    if !stderr_output.is_empty() {
-                        self.output.error(&error_msg);
-                    }
-                    return IterationResult::failure_with_output(error_msg, output);
+        let status = match attempt.end {
+            AttemptEnd::Stopped | AttemptEnd::Finished => return IterationResult::success(output),
+            AttemptEnd::Stalled => return IterationResult::failure_with_output(STALLED, output),
+            AttemptEnd::Cancelled => {
+                return IterationResult::failure_with_output("User interrupted", output)
+            }
+            AttemptEnd::Exited(status) => status,
+        };
+        if !status.success() {
+            let exit_code = status.code().unwrap_or(-1);
+            // Include full stderr in error message
+            let error_msg = if stderr_output.is_empty() {
+                format!("AI CLI exited with code {exit_code}")
+            } else {
+                format!(
+                    "AI CLI exited with code {exit_code}\n\x1b[31m{}\x1b[0m",
+                    stderr_output.trim()
+                )
+            };
+            // Display stderr prominently
+            if !stderr_output.is_empty() {
+                self.output.error(&error_msg);
+            }
+            return IterationResult::failure_with_output(error_msg, output);
+        }
+        IterationResult::success(output)
+    }
+
+    /// Parse and display one line of AI CLI output.
+    ///
+    /// Returns [`Flow::Stop`] once the line carries a completion signal.
+    fn handle_line(&mut self, line: &str, log: &mut IterationLog) -> Flow {
+        if self.stream_parser.is_none() {
+            // Plain text mode: display as-is and check raw line
+            self.output.stream_line(&format!("{line}\n"));
+            return self.check_completion(line);
+        }
+        // NDJSON mode: parse and convert to display text
+        // Falls back to raw line if parsing fails (CLI doesn't support stream-json)
+        let events = self
+            .stream_parser
+            .as_mut()
+            .map(|parser| parser.parse_events(line))
+            .unwrap_or_default();
+        if events.is_empty() {
+            // Parsing found nothing - check if it's valid JSON we should suppress
+            // vs plain text we should display
+            let is_json = line.trim_start().starts_with('{') && line.trim_end().ends_with('}');
+            if !is_json {
+                self.output.stream_line(&format!("{line}\n"));
+            }
+            // Still check for completion signals in case they're embedded
+            return self.check_completion(line);
+        }
+        for event in events {
+            log.event(&event);
+            if let Some(message) = rate_limit_message(&event) {
+                self.rate_limit = Some(message.to_string());
+            }
+            // Check for completion signal only in assistant messages
+            // (not in user messages which may contain the prompt with examples)
+            if let StreamEvent::AssistantMessage { ref text } = event {
+                if self.check_completion(text) == Flow::Stop {
+                    return Flow::Stop;
                 }
-                IterationResult::success(output)
             }
-            Err(e) => IterationResult::failure_with_output(
-                format!("Failed to wait for AI CLI: {e}"),
-                output,
-            ),
+
+            // Convert event to display text and emit TUI event
+            let (display, tui_event) = self.stream_event_to_display(&event);
+            if let (Some(ref sender), Some(tui_event)) = (&self.tui_sender, tui_event) {
+                let _ = sender.send(tui_event);
+            }
+            if let Some(display) = display {
+                self.output.stream_line(&format!("{display}\n"));
+            }
+        }
+        Flow::Continue
+    }
+
+    /// Note a completion signal in `text`, asking for the AI CLI to stop.
+    fn check_completion(&mut self, text: &str) -> Flow {
+        if self.output.contains_completion_signal(text) {
+            self.output.completion_detected();
+            Flow::Stop
+        } else {
+            Flow::Continue
         }
     }
 
@@ -443,11 +592,7 @@ impl IterationRunner {
                     return (None, None);
                 }
                 // Truncate very long messages for display
-                let display_text = if text.len() > 200 {
-                    format!("{}...", &text[..197])
-                } else {
-                    text.clone()
-                };
+                let display_text = truncate_display(text, 200);
                 let display = format!("\x1b[37m{}\x1b[0m", display_text);
                 let tui_event = TuiEvent::OutputLine(text.clone());
                 (Some(display), Some(tui_event))
@@ -456,35 +601,41 @@ impl IterationRunner {
                 tool_name,
                 tool_type,
                 path,
+                command,
             } => {
-                let path_str = path
-                    .as_ref()
-                    .map(|p| format!(" {}", make_path_relative(p)))
-                    .unwrap_or_default();
-                let display = format!("\x1b[33m→ {}{}\x1b[0m", tool_type, path_str);
+                let target = tool_target(path.as_deref(), command.as_deref());
+                let display = format!("\x1b[33m→ {}{}\x1b[0m", tool_type, target);
                 let tui_event = TuiEvent::ToolCall(tool_name.clone());
                 (Some(display), Some(tui_event))
             }
             StreamEvent::ToolCompleted {
                 tool_type,
                 path,
+                command,
                 success,
+                exit_code,
                 lines,
                 ..
             } => {
                 let status = if *success { "✓" } else { "✗" };
                 let lines_str = lines.map(|l| format!(" ({} lines)", l)).unwrap_or_default();
-                let path_str = path
-                    .as_ref()
-                    .map(|p| format!(" {}", make_path_relative(p)))
+                let exit_str = exit_code
+                    .filter(|&code| code != 0)
+                    .map(|code| format!(" (exit {code})"))
                     .unwrap_or_default();
+                let target = tool_target(path.as_deref(), command.as_deref());
                 let colour = if *success { "\x1b[32m" } else { "\x1b[31m" };
                 let display = format!(
-                    "{}{} {}{}{}\x1b[0m",
-                    colour, status, tool_type, path_str, lines_str
+                    "{}{} {}{}{}{}\x1b[0m",
+                    colour, status, tool_type, target, lines_str, exit_str
                 );
 
-                // Emit file change event for file operations
+                // Emit file change event for file operations, or note the
+                // command run
+                let command_run = command.as_ref().map(|command| TuiEvent::CommandRun {
+                    command: command.clone(),
+                    exit_code: *exit_code,
+                });
                 let tui_event = path.as_ref().map(|p| {
                     let change_type = match tool_type {
                         crate::parser::ToolType::Read => "read",
@@ -499,7 +650,7 @@ impl IterationRunner {
                     }
                 });
 
-                (Some(display), tui_event)
+                (Some(display), tui_event.or(command_run))
             }
             StreamEvent::Result {
                 success,
@@ -528,14 +679,40 @@ impl IterationRunner {
                 let tui_event = TuiEvent::Error(message.clone());
                 (Some(display), Some(tui_event))
             }
-            StreamEvent::Unknown { .. } => {
-                // Don't display unknown events
+            StreamEvent::Usage(_) | StreamEvent::Unknown { .. } => {
+                // Don't display usage or unknown events
                 (None, None)
             }
         }
     }
 }
 
+/// NDJSON parser for a config's AI CLI, if it streams JSON.
+fn stream_parser_for(config: &AfkConfig) -> Option<StreamJsonParser> {
+    config
+        .ai_cli
+        .uses_stream_json()
+        .then(|| StreamJsonParser::new(config.ai_cli.detect_cli_format()))
+}
+
+/// Save reproducibility metadata for an iteration about to run.
+pub(super) fn record_iteration(
+    config: &AfkConfig,
+    iteration: u32,
+    seed: u64,
+    model: Option<&str>,
+    command: &[String],
+    prompt: &str,
+) -> Result<(), crate::progress::ProgressError> {
+    let task_id = PrdDocument::load(None).ok().and_then(|prd| {
+        prd.get_pending_stories(&TaskOrder::load(config))
+            .first()
+            .map(|story| story.id.clone())
+    });
+    let record = build_record(config, iteration, task_id, seed, model, command, prompt);
+    save_record(&record, prompt)
+}
+
 /// Run a single iteration with fresh AI context.
 ///
 /// Convenience function that creates an IterationRunner and runs it.
@@ -558,6 +735,22 @@ mod tests {
         assert_eq!(result.output, "Some output");
     }
 
+    #[test]
+    fn test_prompt_notes_extend_instructions() {
+        let mut config = AfkConfig::default();
+        config.prompt.instructions = vec!["Existing".to_string()];
+        let mut runner = IterationRunner::new(config);
+
+        runner.set_prompt_notes(vec!["Split the work".to_string()]);
+        assert_eq!(
+            runner.prompt_config().prompt.instructions,
+            vec!["Existing", "Split the work"]
+        );
+
+        runner.set_prompt_notes(Vec::new());
+        assert_eq!(runner.prompt_config().prompt.instructions, vec!["Existing"]);
+    }
+
     #[test]
     fn test_iteration_result_failure() {
         let result = IterationResult::failure("Something went wrong");
@@ -653,7 +846,14 @@ mod tests {
         let config = AfkConfig::default();
         let mut runner = IterationRunner::new(config);
 
-        let result = runner.execute_command(&[], "prompt");
+        let result = runner.execute_command(
+            &[],
+            &AfkConfig::default(),
+            None,
+            "prompt",
+            1,
+            &mut IterationLog::disabled(),
+        );
 
         assert!(!result.success);
         assert!(result.error.unwrap().contains("No command specified"));
@@ -4,14 +4,49 @@
 //! Each iteration spawns a fresh AI CLI instance with clean context.
 
 use std::sync::OnceLock;
-
+use std::time::Duration;
+
+mod agent_result;
+mod artifacts;
+mod attempt;
+mod capture;
+mod checkpoint;
+mod ci_checks;
+mod control;
 mod controller;
+mod criterion_checks;
+mod escalation;
+mod gate_cache;
+mod gate_env;
+mod gate_revert;
+mod guardrails;
+mod hooks;
+mod http_backend;
 mod iteration;
+mod oneshot;
 mod output_handler;
+mod overrides;
+mod pool;
+mod process;
 mod quality_gates;
+mod refresh;
+mod retry;
+mod review;
+mod sandbox;
+mod schedule;
+mod secrets;
+mod settle;
 mod sleep_guard;
+mod stall;
+mod task_branch;
+mod task_pr;
+mod test_map;
+mod tracking;
+mod warmup;
+mod wasm_gates;
 
 pub use sleep_guard::SleepGuard;
+pub use tokio_util::sync::CancellationToken;
 
 /// Cached current working directory for path relativisation.
 static CWD: OnceLock<String> = OnceLock::new();
@@ -35,7 +70,8 @@ pub fn make_path_relative(path: &str) -> &str {
     }
 
     // Use cached cwd with slash to avoid allocation on every call
-    let cwd_with_slash = CWD_WITH_SLASH.get_or_init(|| format!("{cwd}/"));
+    let cwd_with_slash =
+        CWD_WITH_SLASH.get_or_init(|| format!("{cwd}{}", std::path::MAIN_SEPARATOR));
 
     if let Some(relative) = path.strip_prefix(cwd_with_slash.as_str()) {
         return relative;
@@ -43,15 +79,36 @@ pub fn make_path_relative(path: &str) -> &str {
 
     // Also try without trailing slash (for exact matches)
     if let Some(relative) = path.strip_prefix(cwd.as_str()) {
-        return relative.strip_prefix('/').unwrap_or(relative);
+        return relative.strip_prefix(['/', '\\']).unwrap_or(relative);
     }
 
     path
 }
 
+/// What a tool call works on, for display: its path relative to the
+/// working directory, or else its shell command, with a leading space.
+fn tool_target(path: Option<&str>, command: Option<&str>) -> String {
+    match (path, command) {
+        (Some(path), _) => format!(" {}", make_path_relative(path)),
+        (None, Some(command)) => format!(" $ {}", capture::truncate_display(command, 80)),
+        (None, None) => String::new(),
+    }
+}
+
+pub use ci_checks::{
+    check_ci_criteria, check_ci_workflow, ci_failure_details, ci_workflow, ci_workflows, CiCheck,
+    CiStatus, CI_CRITERION_PREFIX,
+};
+pub use control::{control_state, request_pause, request_skip, request_stop, resume, ControlState};
 pub use controller::{run_loop, run_loop_with_options, run_loop_with_tui, LoopController};
+pub use criterion_checks::{
+    check_criterion_commands, failure_details, verify_story, CriterionCheck,
+};
 pub use iteration::{run_iteration, IterationResult, IterationRunner};
+pub use oneshot::ask_ai;
 pub use output_handler::{FeedbackMode, OutputHandler, COMPLETION_SIGNALS};
+pub use pool::{run_pool, run_pool_with_options, worker_branch, worker_name, WorkerStats};
+pub use warmup::{run_warmup, warmup_needed};
 
 /// Options for running the loop with feedback display.
 #[derive(Debug, Clone, Default)]
@@ -68,6 +125,10 @@ pub struct RunOptions {
     pub feedback_mode: FeedbackMode,
     /// Show ASCII mascot in feedback.
     pub show_mascot: bool,
+    /// Wall-clock budget for a focus run; replaces the session timeout.
+    pub time_budget: Option<Duration>,
+    /// Parallel workers; more than one runs the worker pool.
+    pub workers: u32,
 }
 
 impl RunOptions {
@@ -98,6 +159,18 @@ impl RunOptions {
         self
     }
 
+    /// Set a wall-clock time budget.
+    pub fn with_time_budget(mut self, budget: Option<Duration>) -> Self {
+        self.time_budget = budget;
+        self
+    }
+
+    /// Set the number of parallel workers.
+    pub fn with_workers(mut self, workers: u32) -> Self {
+        self.workers = workers;
+        self
+    }
+
     /// Set resume flag.
     pub fn with_resume(mut self, resume: bool) -> Self {
         self.resume = resume;
@@ -132,10 +205,26 @@ impl RunOptions {
         matches!(s, Some("tui"))
     }
 }
+pub use artifacts::save_gate_artifacts;
+pub use checkpoint::{record_phase, IterationCheckpoint, IterationPhase, Rollback};
+pub use gate_cache::{working_tree_hash, GateCache};
+pub use gate_env::GateEnvironment;
+pub use hooks::{run_hook, HookContext, HookEvent};
+pub use process::{program_command, resolve_program, shell_command, ProcessTree};
 pub use quality_gates::{
-    get_configured_gate_names, has_configured_gates, run_quality_gates, GateResult,
-    QualityGateResult,
+    get_configured_gate_names, has_configured_gates, run_quality_gates, run_quality_gates_cached,
+    run_quality_gates_quiet, GateResult, QualityGateResult,
+};
+pub use refresh::refresh_derived;
+pub use sandbox::{
+    apply_sandbox, commit_sandbox, discard_sandbox, prepare_sandbox, sandbox_diff, sandbox_exists,
+    sandbox_patch, sandbox_path, SANDBOX_BRANCH,
 };
+pub use schedule::{parse_start_time, wait_until, CronSchedule, StartTime};
+pub use secrets::{run_secret_gate, SecretFinding, SecretScanner};
+pub use task_branch::{branch_name, BranchOutcome, TaskBranches};
+pub use task_pr::{open_completed_prs, open_task_pr};
+pub use test_map::{changed_files, in_iteration, TestPlan};
 
 /// Reasons for stopping the runner.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -152,6 +241,18 @@ pub enum StopReason {
     UserInterrupt,
     /// AI CLI error with optional details.
     AiError(Option<String>),
+    /// An iteration exceeded a guardrail and needs human review.
+    Guardrail(String),
+    /// A plugin vetoed an iteration's commits.
+    PluginVeto(String),
+    /// The focus run's time budget was used up.
+    TimeBudget,
+    /// The session used its `limits.max_session_minutes`.
+    SessionBudgetExceeded,
+    /// Stopped through `.afk/control/stop`.
+    ExternalStop,
+    /// An iteration failed in a way `retry` says to stop for.
+    Failure(String),
 }
 
 impl std::fmt::Display for StopReason {
@@ -165,15 +266,68 @@ impl std::fmt::Display for StopReason {
             StopReason::AiError(None) => write!(f, "AI CLI error"),
             StopReason::AiError(Some(msg)) => {
                 // Truncate long messages for display
-                let truncated = if msg.len() > 60 {
-                    format!("{}...", &msg[..57])
-                } else {
-                    msg.clone()
-                };
-                write!(f, "AI CLI error: {}", truncated)
+                write!(f, "AI CLI error: {}", capture::truncate_display(msg, 60))
             }
+            StopReason::Guardrail(msg) => write!(f, "{msg}"),
+            StopReason::PluginVeto(msg) => write!(f, "Vetoed by plugin {msg}"),
+            StopReason::TimeBudget => write!(f, "Time budget used up"),
+            StopReason::SessionBudgetExceeded => write!(f, "Session time budget used up"),
+            StopReason::ExternalStop => write!(f, "Stop requested via control file"),
+            StopReason::Failure(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+/// Parse a time budget such as `90m`, `1h30m`, `2h`, or `45s`.
+///
+/// A bare number is taken as minutes.
+pub fn parse_time_budget(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Ok(minutes) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(minutes * 60));
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
         }
+        let unit = match c.to_ascii_lowercase() {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("invalid duration '{s}' (use e.g. 90m, 1h30m, 45s)")),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration '{s}' (use e.g. 90m, 1h30m, 45s)"))?;
+        total += value * unit;
+        digits.clear();
+    }
+
+    if !digits.is_empty() || total == 0 {
+        return Err(format!("invalid duration '{s}' (use e.g. 90m, 1h30m, 45s)"));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Format a time budget compactly, e.g. `1h30m`.
+pub fn format_time_budget(budget: Duration) -> String {
+    let secs = budget.as_secs();
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    let mut out = String::new();
+    if h > 0 {
+        out.push_str(&format!("{h}h"));
+    }
+    if m > 0 {
+        out.push_str(&format!("{m}m"));
+    }
+    if s > 0 || out.is_empty() {
+        out.push_str(&format!("{s}s"));
     }
+    out
 }
 
 /// Result of running the full loop.
@@ -210,6 +364,43 @@ mod tests {
             StopReason::AiError(Some("out of credits".to_string())).to_string(),
             "AI CLI error: out of credits"
         );
+        assert_eq!(
+            StopReason::Guardrail("Diff guardrail exceeded: 900 lines changed".to_string())
+                .to_string(),
+            "Diff guardrail exceeded: 900 lines changed"
+        );
+        assert_eq!(
+            StopReason::PluginVeto("policy: touches billing".to_string()).to_string(),
+            "Vetoed by plugin policy: touches billing"
+        );
+        assert_eq!(StopReason::TimeBudget.to_string(), "Time budget used up");
+        assert_eq!(
+            StopReason::Failure("Iteration on auth failed: no changes".to_string()).to_string(),
+            "Iteration on auth failed: no changes"
+        );
+    }
+
+    #[test]
+    fn test_parse_time_budget() {
+        assert_eq!(parse_time_budget("90m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_time_budget("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_time_budget("2H"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_time_budget("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_time_budget("30"), Ok(Duration::from_secs(1800)));
+        assert!(parse_time_budget("").is_err());
+        assert!(parse_time_budget("0m").is_err());
+        assert!(parse_time_budget("90").is_ok());
+        assert!(parse_time_budget("1h30").is_err());
+        assert!(parse_time_budget("soon").is_err());
+        assert!(parse_time_budget("h").is_err());
+    }
+
+    #[test]
+    fn test_format_time_budget() {
+        assert_eq!(format_time_budget(Duration::from_secs(5400)), "1h30m");
+        assert_eq!(format_time_budget(Duration::from_secs(7200)), "2h");
+        assert_eq!(format_time_budget(Duration::from_secs(45)), "45s");
+        assert_eq!(format_time_budget(Duration::ZERO), "0s");
     }
 
     #[test]
@@ -0,0 +1,113 @@
+//! Single prompts outside the iteration loop.
+//!
+//! The warm-up iteration and `afk tasks enrich` send the AI one prompt and
+//! want its reply as text, whichever backend is configured.
+
+use std::process::Stdio;
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, AiBackend};
+use crate::parser::{StreamEvent, StreamJsonParser};
+
+use super::attempt::Flow;
+use super::capture::OutputLine;
+use super::http_backend::{run_http_attempt, run_ollama_attempt};
+use super::process::program_command;
+
+/// Send `prompt` to the configured AI and return its reply.
+///
+/// Errors are returned as messages for the caller to report.
+pub fn ask_ai(config: &AfkConfig, prompt: &str) -> Result<String, String> {
+    match config.ai_cli.backend {
+        AiBackend::Cli => run_cli(config, prompt).map(|output| extract_reply(&output, config)),
+        AiBackend::Http | AiBackend::Ollama => {
+            let mut lines = Vec::new();
+            let on_line = |line: OutputLine| {
+                lines.push(line.text);
+                Flow::Continue
+            };
+            let cancel = CancellationToken::new();
+            match config.ai_cli.backend {
+                AiBackend::Ollama => {
+                    let model = config.ai_cli.models.first().map(String::as_str);
+                    run_ollama_attempt(&config.ollama, model, prompt, None, &cancel, on_line)
+                }
+                _ => run_http_attempt(&config.ai_cli, None, prompt, None, &cancel, on_line),
+            }
+            .map_err(|e| e.to_string())?;
+            Ok(lines.join("\n").trim().to_string())
+        }
+    }
+}
+
+/// Run the AI CLI on `prompt`, returning its stdout.
+fn run_cli(config: &AfkConfig, prompt: &str) -> Result<String, String> {
+    let output = program_command(&config.ai_cli.command)
+        .args(config.ai_cli.full_args_with_model(None))
+        .arg(prompt)
+        .envs(config.git.identity_env())
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {e}", config.ai_cli.command))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}",
+            config.ai_cli.command, output.status
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Extract the AI's reply from raw CLI output.
+///
+/// Stream-JSON output keeps only assistant message text; plain output, or
+/// stream-JSON output with no assistant text, is used as-is.
+fn extract_reply(output: &str, config: &AfkConfig) -> String {
+    if !config.ai_cli.uses_stream_json() {
+        return output.trim().to_string();
+    }
+
+    let mut parser = StreamJsonParser::new(config.ai_cli.detect_cli_format());
+    let texts: Vec<String> = output
+        .lines()
+        .flat_map(|line| parser.parse_events(line))
+        .filter_map(|event| match event {
+            StreamEvent::AssistantMessage { text } => Some(text),
+            _ => None,
+        })
+        .collect();
+    if texts.is_empty() {
+        return output.trim().to_string();
+    }
+    texts.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AiOutputFormat;
+
+    #[test]
+    fn test_extract_reply_plain_text() {
+        let mut config = AfkConfig::default();
+        config.ai_cli.output_format = AiOutputFormat::Text;
+        assert_eq!(extract_reply("\n  Notes here\n", &config), "Notes here");
+    }
+
+    #[test]
+    fn test_extract_reply_stream_json() {
+        let mut config = AfkConfig::default();
+        config.ai_cli.command = "claude".to_string();
+        let output = concat!(
+            r#"{"type":"system","subtype":"init"}"#,
+            "\n",
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Uses thiserror."}]}}"#,
+            "\n",
+        );
+        assert_eq!(extract_reply(output, &config), "Uses thiserror.");
+
+        // A CLI printing plain text despite the stream-json setting
+        assert_eq!(extract_reply("Uses anyhow.\n", &config), "Uses anyhow.");
+    }
+}
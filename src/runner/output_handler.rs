@@ -8,6 +8,7 @@ use std::path::Path;
 use crate::feedback::{
     ActivityState, DisplayMode, FeedbackDisplay, IterationMetrics, MetricsCollector,
 };
+use crate::git::changes::{FileChange, FileStatus};
 use crate::parser::{FileChangeType, OutputParser, ParsedEvent};
 use crate::watcher::{ChangeType, FileWatcher};
 
@@ -54,6 +55,8 @@ pub struct OutputHandler {
     feedback_mode: FeedbackMode,
     /// Whether to show mascot.
     show_mascot: bool,
+    /// Whether to show modified files.
+    show_files: bool,
     /// Current iteration context.
     iteration_current: u32,
     /// Maximum iterations.
@@ -77,6 +80,7 @@ impl OutputHandler {
             metrics_collector: MetricsCollector::new(),
             feedback_mode: FeedbackMode::None,
             show_mascot: true,
+            show_files: true,
             iteration_current: 0,
             iteration_max: 0,
             task_id: None,
@@ -112,6 +116,11 @@ impl OutputHandler {
         self.show_mascot = show;
     }
 
+    /// Set whether to show modified files.
+    pub fn set_show_files(&mut self, show: bool) {
+        self.show_files = show;
+    }
+
     /// Set activity thresholds for the metrics collector.
     ///
     /// # Arguments
@@ -150,6 +159,7 @@ impl OutputHandler {
         // Create and start feedback display if mode is not None
         if let Some(display_mode) = self.feedback_mode.to_display_mode() {
             let mut display = FeedbackDisplay::with_options(display_mode, self.show_mascot);
+            display.set_show_files(self.show_files);
             display.start();
             self.feedback_display = Some(display);
         }
@@ -464,6 +474,34 @@ impl OutputHandler {
         );
     }
 
+    /// Display the files an iteration changed, as git saw them.
+    pub fn changed_files(&self, files: &[FileChange]) {
+        if !self.show_files || files.is_empty() {
+            return;
+        }
+        let (added, removed) = files
+            .iter()
+            .fold((0, 0), |(a, r), f| (a + f.insertions, r + f.deletions));
+        println!(
+            "\x1b[1mFiles changed\x1b[0m \x1b[2m({})\x1b[0m  \x1b[32m+{added}\x1b[0m\x1b[2m/\x1b[0m\x1b[31m-{removed}\x1b[0m",
+            files.len()
+        );
+        for file in files {
+            let colour = match file.status {
+                FileStatus::Added => "\x1b[32m",
+                FileStatus::Modified => "\x1b[33m",
+                FileStatus::Deleted => "\x1b[31m",
+            };
+            println!(
+                "  {colour}{}\x1b[0m {}  \x1b[2m+{} -{}\x1b[0m",
+                file.status.marker(),
+                file.path,
+                file.insertions,
+                file.deletions
+            );
+        }
+    }
+
     /// Get elapsed time since feedback started.
     fn get_elapsed_time(&self) -> Option<std::time::Duration> {
         self.start_time.map(|t| t.elapsed())
@@ -0,0 +1,95 @@
+//! Per-task AI CLI overrides.
+//!
+//! A story in tasks.json can set `model` or `cli` to route its iterations
+//! somewhere other than `ai_cli` says: heavy refactors to a bigger model,
+//! trivial fixes to a cheap one. A task's `model` is used for every
+//! iteration on it, in place of `ai_cli.models` rotation and escalation. Its
+//! `cli` replaces `ai_cli.command`, with that CLI's default arguments when
+//! afk knows it and `ai_cli.args` otherwise.
+
+use std::borrow::Cow;
+
+use crate::bootstrap::AI_CLIS;
+use crate::config::AfkConfig;
+use crate::prd::{PrdDocument, UserStory};
+
+/// The config for an iteration on `story`, with its overrides applied.
+pub fn apply<'a>(config: &'a AfkConfig, story: &UserStory) -> Cow<'a, AfkConfig> {
+    if story.model.is_none() && story.cli.is_none() {
+        return Cow::Borrowed(config);
+    }
+    let mut config = config.clone();
+    if let Some(cli) = &story.cli {
+        if let Some(known) = AI_CLIS.iter().find(|c| c.command == cli.as_str()) {
+            config.ai_cli.args = known.args.iter().map(|a| a.to_string()).collect();
+        }
+        config.ai_cli.command = cli.clone();
+    }
+    if let Some(model) = &story.model {
+        config.ai_cli.models = vec![model.clone()];
+        config.ai_cli.escalation_models.clear();
+    }
+    Cow::Owned(config)
+}
+
+/// The config for an iteration on the task `task_id` in `.afk/tasks.json`.
+pub fn for_task<'a>(config: &'a AfkConfig, task_id: Option<&str>) -> Cow<'a, AfkConfig> {
+    let story = task_id.and_then(|id| {
+        PrdDocument::load(None)
+            .ok()?
+            .user_stories
+            .into_iter()
+            .find(|s| s.id == id)
+    });
+    match story {
+        Some(story) => apply(config, &story),
+        None => Cow::Borrowed(config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AfkConfig {
+        let mut config = AfkConfig::default();
+        config.ai_cli.models = vec!["haiku".to_string(), "sonnet".to_string()];
+        config.ai_cli.escalation_models = vec!["opus".to_string()];
+        config
+    }
+
+    #[test]
+    fn test_apply_without_overrides_borrows() {
+        let config = config();
+        let story = UserStory::new("fix-typo", "Fix typo");
+        assert!(matches!(apply(&config, &story), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_apply_model_pins_model() {
+        let mut story = UserStory::new("refactor", "Big refactor");
+        story.model = Some("opus".to_string());
+        let config = config();
+        let applied = apply(&config, &story);
+        assert_eq!(applied.ai_cli.models, ["opus"]);
+        assert!(applied.ai_cli.escalation_models.is_empty());
+        assert_eq!(applied.ai_cli.select_model_seeded(1), Some("opus"));
+        assert_eq!(applied.ai_cli.command, "claude");
+    }
+
+    #[test]
+    fn test_apply_cli_uses_known_args() {
+        let mut story = UserStory::new("ui", "Tweak UI");
+        story.cli = Some("agent".to_string());
+        let config = config();
+        let applied = apply(&config, &story);
+        assert_eq!(applied.ai_cli.command, "agent");
+        assert_eq!(applied.ai_cli.args, ["-p", "--force"]);
+        assert_eq!(applied.ai_cli.models, config.ai_cli.models);
+
+        story.cli = Some("./my-agent".to_string());
+        let applied = apply(&config, &story);
+        assert_eq!(applied.ai_cli.command, "./my-agent");
+        assert_eq!(applied.ai_cli.args, config.ai_cli.args);
+    }
+}
@@ -0,0 +1,1040 @@
+//! Parallel worker pool.
+//!
+//! `afk go --workers N` runs N AI CLI instances at once. Each worker gets its
+//! own git worktree under `.afk/worktrees/` on an `afk/worker-N` branch and
+//! claims a different pending task through a claim recorded in
+//! progress.json. When a worker's task passes, its branch is merged back into
+//! the current branch one worker at a time, so commits never race. A merge
+//! that conflicts is aborted and the worker's branch is kept for review.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, AiBackend, PROGRESS_FILE, TASKS_FILE, WARMUP_FILE, WORKTREES_DIR};
+use crate::git;
+use crate::parser::{ResultCollector, StreamEvent, StreamJsonParser};
+use crate::prd::{
+    mark_story_complete, mark_story_in_progress, sync_prd_with_root, PrdDocument, TaskOrder,
+    UserStory,
+};
+use crate::progress::repro::new_seed;
+use crate::progress::{
+    charge_iteration_time, get_failure_count, should_skip_task, Selection, SessionProgress,
+    TaskStatus,
+};
+use crate::prompt::generate_prompt_with_root;
+use crate::tui::TuiEvent;
+
+use super::agent_result;
+use super::attempt::{run_attempt, AttemptEnd, Flow};
+use super::capture::OutputLine;
+use super::ci_checks::ci_workflows;
+use super::control::check_control;
+use super::controller::session_limit;
+use super::criterion_checks::verify_story;
+use super::escalation;
+use super::guardrails::{guardrails_enabled, revert_protected_paths_in};
+use super::http_backend::{run_http_attempt, run_ollama_attempt};
+use super::output_handler::COMPLETION_SIGNALS;
+use super::overrides;
+use super::process::program_command;
+use super::quality_gates::has_configured_gates;
+use super::secrets::block_secrets_in;
+use super::tool_target;
+use super::warmup::{run_warmup, warmup_needed};
+use super::{RunOptions, RunResult, StopReason};
+
+/// Name of a worker, e.g. `worker-2`.
+pub fn worker_name(n: u32) -> String {
+    format!("worker-{n}")
+}
+
+/// Branch a worker commits to, e.g. `afk/worker-2`.
+pub fn worker_branch(n: u32) -> String {
+    format!("afk/{}", worker_name(n))
+}
+
+/// What one worker did during a parallel run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorkerStats {
+    /// Worker name.
+    pub worker: String,
+    /// Iterations the worker ran.
+    pub iterations: u32,
+    /// Tasks the worker completed.
+    pub tasks_completed: u32,
+    /// Task whose merge conflicted; the worker's branch is kept for review.
+    pub conflict: Option<String>,
+    /// Error that stopped the worker early.
+    pub error: Option<String>,
+}
+
+/// Run the loop with `options.workers` parallel workers.
+///
+/// Sets up Ctrl+C handling and prints per-worker status lines and a summary.
+pub fn run_pool_with_options(config: &AfkConfig, options: RunOptions) -> RunResult {
+    let interrupted = CancellationToken::new();
+    let cancel = interrupted.clone();
+    if let Err(e) = ctrlc::set_handler(move || {
+        cancel.cancel();
+        eprintln!("\n\x1b[33mInterrupting... stopping workers\x1b[0m");
+    }) {
+        eprintln!("\x1b[2mWarning: Could not set up Ctrl+C handler: {e}\x1b[0m");
+    }
+
+    let _sleep_guard = if config.limits.prevent_sleep {
+        super::SleepGuard::new()
+    } else {
+        super::SleepGuard::disabled()
+    };
+
+    run_pool(config, &options, None, interrupted)
+}
+
+/// Run the parallel worker pool.
+///
+/// With a TUI sender, worker output and status go to the TUI; otherwise
+/// status lines are printed. Each worker's full output is kept in
+/// `.afk/worktrees/<worker>.log`.
+pub fn run_pool(
+    config: &AfkConfig,
+    options: &RunOptions,
+    tui: Option<Sender<TuiEvent>>,
+    interrupted: CancellationToken,
+) -> RunResult {
+    let start = Instant::now();
+    let result = |stop_reason, iterations, tasks| RunResult {
+        iterations_completed: iterations,
+        tasks_completed: tasks,
+        stop_reason,
+        duration_seconds: start.elapsed().as_secs_f64(),
+        archived_to: None,
+    };
+
+    if !git::is_git_repo() {
+        let error = "Parallel workers need a git repository".to_string();
+        eprintln!("\x1b[31mError:\x1b[0m {error}");
+        return result(StopReason::AiError(Some(error)), 0, 0);
+    }
+
+    let unsupported = unsupported_settings(config);
+    if !unsupported.is_empty() {
+        let error = format!(
+            "Parallel workers don't run these per-iteration checks yet: {}. Run without --workers",
+            unsupported.join(", ")
+        );
+        eprintln!("\x1b[31mError:\x1b[0m {error}");
+        return result(StopReason::AiError(Some(error)), 0, 0);
+    }
+
+    let prd = match sync_prd_with_root(config, None, None) {
+        Ok(prd) => prd,
+        Err(e) => {
+            let error = format!("Failed to sync PRD: {e}");
+            eprintln!("\x1b[31mError:\x1b[0m {error}");
+            return result(StopReason::AiError(Some(error)), 0, 0);
+        }
+    };
+    let ci_tasks = pending_with_ci_criteria(&prd);
+    if !ci_tasks.is_empty() {
+        let error = format!(
+            "Parallel workers can't check ci: acceptance criteria, as CI never sees their \
+             unmerged branches: {}. Run without --workers",
+            ci_tasks.join(", ")
+        );
+        eprintln!("\x1b[31mError:\x1b[0m {error}");
+        return result(StopReason::AiError(Some(error)), 0, 0);
+    }
+    if prd.pending_count() == 0 {
+        let reason = if prd.user_stories.is_empty() {
+            StopReason::NoTasks
+        } else {
+            StopReason::Complete
+        };
+        return result(reason, 0, 0);
+    }
+
+    // Claims left by an interrupted run would block those tasks forever
+    if let Ok(mut progress) = SessionProgress::load(None) {
+        progress.clear_claims();
+        let _ = progress.save(None);
+    }
+
+    if let Err(e) = prepare_worktrees_dir() {
+        let error = format!("Failed to create {WORKTREES_DIR}: {e}");
+        eprintln!("\x1b[31mError:\x1b[0m {error}");
+        return result(StopReason::AiError(Some(error)), 0, 0);
+    }
+
+    if warmup_needed(config) {
+        if let Err(e) = run_warmup(config) {
+            eprintln!("\x1b[33mWarning:\x1b[0m Warm-up failed: {e}");
+        }
+    }
+
+    let workers = options.workers.max(1);
+    let max_iterations = if options.until_complete {
+        u32::MAX
+    } else {
+        options
+            .max_iterations
+            .unwrap_or(config.limits.max_iterations)
+    };
+    let timeout_minutes = options
+        .timeout_minutes
+        .unwrap_or(config.limits.timeout_minutes);
+    let (time_limit, limit_reason) = session_limit(timeout_minutes, options.time_budget);
+
+    if tui.is_none() {
+        println!(
+            "\x1b[1mRunning {workers} workers\x1b[0m \x1b[2m(logs in {WORKTREES_DIR}/)\x1b[0m"
+        );
+    }
+
+    let pool = Pool {
+        config,
+        max_iterations,
+        started: AtomicU32::new(0),
+        start,
+        time_limit,
+        limit_reason,
+        interrupted,
+        stop_reason: Mutex::new(None),
+        state: Mutex::new(()),
+        merges: Mutex::new(()),
+        tui,
+    };
+
+    let stats: Vec<WorkerStats> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (1..=workers)
+            .map(|n| {
+                let pool = &pool;
+                scope.spawn(move || pool.run_worker(n))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .zip(1..)
+            .map(|(handle, n)| {
+                handle.join().unwrap_or_else(|_| WorkerStats {
+                    worker: worker_name(n),
+                    error: Some("worker thread panicked".to_string()),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    });
+
+    // Release anything still held, e.g. after an interrupt
+    if let Ok(mut progress) = SessionProgress::load(None) {
+        progress.clear_claims();
+        let _ = progress.save(None);
+    }
+
+    let iterations = stats.iter().map(|s| s.iterations).sum();
+    let tasks = stats.iter().map(|s| s.tasks_completed).sum();
+    let all_complete = PrdDocument::load(None).is_ok_and(|p| p.all_stories_complete());
+    let stop_reason = if all_complete {
+        StopReason::Complete
+    } else if let Some(reason) = pool.take_stop_reason() {
+        reason
+    } else if let Some(error) = stats.iter().find_map(|s| s.error.clone()) {
+        StopReason::AiError(Some(error))
+    } else {
+        StopReason::NoTasks
+    };
+
+    if pool.tui.is_none() {
+        print_summary(&stats);
+    }
+
+    let mut run = result(stop_reason, iterations, tasks);
+    if run.stop_reason == StopReason::Complete && config.archive.enabled {
+        run.archived_to = crate::progress::archive_session("completed").ok().flatten();
+    }
+    run
+}
+
+/// Settings whose per-iteration checks only the sequential loop runs.
+///
+/// Workers would skip them silently, so a pool refuses to start while any
+/// are configured.
+fn unsupported_settings(config: &AfkConfig) -> Vec<&'static str> {
+    let mut unsupported = Vec::new();
+    if !config.plugins.paths.is_empty() {
+        unsupported.push("plugins");
+    }
+    if config.guardrails.max_changed_lines > 0 {
+        unsupported.push("guardrails.max_changed_lines");
+    }
+    if config.guardrails.max_changed_files > 0 {
+        unsupported.push("guardrails.max_changed_files");
+    }
+    if config.git.revert_on_gate_failure && has_configured_gates(&config.feedback_loops) {
+        unsupported.push("git.revert_on_gate_failure");
+    }
+    let hooks = [
+        ("hooks.pre_iteration", &config.hooks.pre_iteration),
+        ("hooks.post_iteration", &config.hooks.post_iteration),
+        ("hooks.on_task_complete", &config.hooks.on_task_complete),
+        ("hooks.on_task_fail", &config.hooks.on_task_fail),
+    ];
+    unsupported.extend(
+        hooks
+            .into_iter()
+            .filter(|(_, command)| command.is_some())
+            .map(|(name, _)| name),
+    );
+    unsupported
+}
+
+/// Pending tasks with a `ci:<workflow>` acceptance criterion.
+///
+/// Workers mark a task passed in their worktree before its branch is
+/// merged or pushed, so CI has never run on the commit they would check.
+/// A pool refuses to start with such tasks rather than complete them with
+/// CI unchecked.
+fn pending_with_ci_criteria(prd: &PrdDocument) -> Vec<String> {
+    prd.user_stories
+        .iter()
+        .filter(|s| !s.passes && !ci_workflows(&s.acceptance_criteria).is_empty())
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// Create the worktrees directory, ignored by the main repository.
+pub(super) fn prepare_worktrees_dir() -> std::io::Result<()> {
+    let dir = Path::new(WORKTREES_DIR);
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(".gitignore"), "*\n")
+}
+
+/// Print what each worker did.
+fn print_summary(stats: &[WorkerStats]) {
+    println!();
+    println!("\x1b[1mWorkers\x1b[0m");
+    for s in stats {
+        println!(
+            "  {}: {} iterations, {} tasks completed",
+            s.worker, s.iterations, s.tasks_completed
+        );
+        if let Some(task) = &s.conflict {
+            println!(
+                "    \x1b[33m⚠ {task} conflicted on merge; its work is on branch afk/{}\x1b[0m",
+                s.worker
+            );
+        }
+        if let Some(error) = &s.error {
+            println!("    \x1b[31m✗ {error}\x1b[0m");
+        }
+    }
+}
+
+/// Where an iteration left a worker's task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The task passes and its branch can be merged.
+    Passed,
+    /// The task still needs work; the worker keeps its claim.
+    Retry,
+    /// The task hit its failure or time limit and its claim was released.
+    Skipped,
+}
+
+/// One finished worker iteration, for [`Pool::record_iteration`].
+#[derive(Clone, Copy)]
+struct Iteration<'a> {
+    worker: &'a str,
+    path: &'a Path,
+    story: &'a UserStory,
+    /// Worktree commit the iteration started from, when guardrails are on.
+    baseline: Option<&'a str>,
+    elapsed: Duration,
+}
+
+/// State shared by all workers.
+struct Pool<'a> {
+    config: &'a AfkConfig,
+    max_iterations: u32,
+    /// Iterations started so far, across all workers.
+    started: AtomicU32,
+    start: Instant,
+    time_limit: Duration,
+    limit_reason: StopReason,
+    interrupted: CancellationToken,
+    /// Why the run stopped early, if it did.
+    stop_reason: Mutex<Option<StopReason>>,
+    /// Serialises reads and writes of tasks.json and progress.json.
+    state: Mutex<()>,
+    /// Serialises merges into the current branch.
+    merges: Mutex<()>,
+    tui: Option<Sender<TuiEvent>>,
+}
+
+impl Pool<'_> {
+    /// Claim tasks and run iterations until there is nothing left to do.
+    fn run_worker(&self, n: u32) -> WorkerStats {
+        let name = worker_name(n);
+        let branch = worker_branch(n);
+        let path = Path::new(WORKTREES_DIR).join(&name);
+        let mut stats = WorkerStats {
+            worker: name.clone(),
+            ..Default::default()
+        };
+
+        if !git::add_worktree(&path, &branch) {
+            stats.error = Some(format!("could not create worktree for {branch}"));
+            self.status(&name, None, "failed");
+            return stats;
+        }
+
+        let mut current: Option<UserStory> = None;
+        loop {
+            if self.should_stop(&name) {
+                break;
+            }
+
+            let story = match current.take() {
+                Some(story) => story,
+                None => {
+                    // Start each task from the latest merged work
+                    if let Some(head) = git::get_current_commit() {
+                        git::fast_forward_worktree(&path, &head);
+                    }
+                    let Some(story) = self.claim(&name, &path) else {
+                        break;
+                    };
+                    story
+                }
+            };
+
+            if !self.take_iteration() {
+                self.release(&story.id);
+                break;
+            }
+            self.status(&name, Some(&story.id), "running");
+            stats.iterations += 1;
+
+            let iter_start = Instant::now();
+            let baseline = guardrails_enabled(&self.config.guardrails)
+                .then(|| git::get_current_commit_in(&path))
+                .flatten();
+            let outcome = match self.run_agent(&name, &path, &story).and_then(|()| {
+                let iteration = Iteration {
+                    worker: &name,
+                    path: &path,
+                    story: &story,
+                    baseline: baseline.as_deref(),
+                    elapsed: iter_start.elapsed(),
+                };
+                self.record_iteration(&iteration)
+            }) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    self.release(&story.id);
+                    self.status(&name, Some(&story.id), "failed");
+                    stats.error = Some(e);
+                    break;
+                }
+            };
+            match outcome {
+                Outcome::Passed => {}
+                Outcome::Retry => {
+                    // Keep the claim and try again with the next iteration
+                    current = Some(story);
+                    continue;
+                }
+                Outcome::Skipped => {
+                    self.status(&name, Some(&story.id), "skipped");
+                    continue;
+                }
+            }
+
+            stats.tasks_completed += 1;
+            if self.merge(&branch, &story.id) {
+                self.status(&name, Some(&story.id), "merged");
+            } else {
+                self.status(&name, Some(&story.id), "conflict");
+                stats.conflict = Some(story.id);
+                break;
+            }
+        }
+
+        if let Some(story) = current {
+            self.release(&story.id);
+        }
+        git::remove_worktree(&path);
+        if stats.conflict.is_none() {
+            git::delete_merged_branch(&branch);
+        }
+        if stats.error.is_none() && stats.conflict.is_none() {
+            self.status(&name, None, "done");
+        }
+        stats
+    }
+
+    /// Check for an interrupt, a control file, or the session time limit.
+    ///
+    /// Waits here while `.afk/control/pause` exists.
+    fn should_stop(&self, worker: &str) -> bool {
+        if self.interrupted.is_cancelled() {
+            self.set_stop_reason(StopReason::UserInterrupt);
+            return true;
+        }
+        let control = check_control(&self.interrupted, |paused| {
+            self.status(worker, None, if paused { "paused" } else { "resumed" });
+        });
+        if let Some(reason) = control {
+            self.set_stop_reason(reason);
+            return true;
+        }
+        // The stop file is consumed by whichever worker saw it first
+        let stopped = self.stop_reason.lock().unwrap_or_else(|e| e.into_inner());
+        if *stopped == Some(StopReason::ExternalStop) {
+            return true;
+        }
+        drop(stopped);
+        if self.start.elapsed() >= self.time_limit {
+            self.set_stop_reason(self.limit_reason.clone());
+            return true;
+        }
+        false
+    }
+
+    /// Reserve one iteration from the shared budget.
+    fn take_iteration(&self) -> bool {
+        let taken = self.started.fetch_add(1, Ordering::SeqCst) < self.max_iterations;
+        if !taken {
+            self.set_stop_reason(StopReason::MaxIterations);
+        }
+        taken
+    }
+
+    /// Record why the run stopped, keeping the first reason.
+    fn set_stop_reason(&self, reason: StopReason) {
+        let mut stop = self.stop_reason.lock().unwrap_or_else(|e| e.into_inner());
+        stop.get_or_insert(reason);
+    }
+
+    /// Take the recorded stop reason.
+    fn take_stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    /// Lock the shared task and progress files.
+    fn lock_state(&self) -> MutexGuard<'_, ()> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Claim the next unclaimed pending task and copy it into the worktree.
+    fn claim(&self, worker: &str, path: &Path) -> Option<UserStory> {
+        let _state = self.lock_state();
+        let prd = PrdDocument::load(None).ok()?;
+        let mut progress = SessionProgress::load(None).ok()?;
+        let order = TaskOrder::new(self.config, &progress);
+        let selection = unclaimed_selection(
+            &prd,
+            &progress,
+            &order,
+            self.config.limits.max_task_failures,
+        );
+        selection.save(None);
+        let story = prd.get_story(selection.picked()?)?.clone();
+
+        progress.claim_task(&story.id, &story.source, worker);
+        progress.set_task_status(&story.id, TaskStatus::InProgress, &story.source, None);
+        progress.save(None).ok()?;
+        let _ = mark_story_in_progress(&story.id);
+
+        if let Err(e) = scope_worktree(path, &prd, &progress, &story) {
+            progress.release_claim(&story.id);
+            let _ = progress.save(None);
+            eprintln!("\x1b[33mWarning:\x1b[0m {worker}: could not prepare worktree: {e}");
+            return None;
+        }
+        Some(story)
+    }
+
+    /// Release a task's claim without completing it.
+    fn release(&self, task_id: &str) {
+        let _state = self.lock_state();
+        if let Ok(mut progress) = SessionProgress::load(None) {
+            progress.release_claim(task_id);
+            let _ = progress.save(None);
+        }
+    }
+
+    /// Run one AI CLI iteration inside a worker's worktree.
+    fn run_agent(&self, worker: &str, path: &Path, story: &UserStory) -> Result<(), String> {
+        let prompt = generate_prompt_with_root(self.config, true, None, Some(path))
+            .map_err(|e| format!("Failed to generate prompt: {e}"))?
+            .prompt;
+
+        let config = overrides::apply(self.config, story);
+        let ai_cli = &config.ai_cli;
+        let choice = {
+            let _state = self.lock_state();
+            escalation::choose_and_record(&config, Some(&story.id), new_seed())
+        };
+        let model = choice.model.clone();
+        let log_path = log_path(worker);
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)
+            .map_err(|e| format!("Failed to open {}: {e}", log_path.display()))?;
+        let _ = writeln!(log, "=== {} ===", story.id);
+        if let Some(message) = choice.escalation_message() {
+            let _ = writeln!(log, "{message}");
+        }
+        let stderr = log.try_clone().map(Stdio::from).unwrap_or(Stdio::null());
+
+        let mut cmd = program_command(&ai_cli.command);
+        cmd.args(ai_cli.full_args_with_model(model.as_deref()))
+            .arg(&prompt)
+            .envs(self.config.git.identity_env())
+            .current_dir(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(stderr);
+
+        let stream_parser = || {
+            ai_cli
+                .uses_stream_json()
+                .then(|| StreamJsonParser::new(ai_cli.detect_cli_format()))
+        };
+        let mut parser = stream_parser();
+        let mut collector = ResultCollector::new(stream_parser());
+        let on_line = |captured: OutputLine| {
+            let line = captured.text;
+            let _ = writeln!(log, "{line}");
+            collector.push_line(&line);
+            let (display, complete) = display_line(parser.as_mut(), &line);
+            if let (Some(tx), Some(text)) = (&self.tui, display) {
+                let _ = tx.send(TuiEvent::OutputLine(format!("[{worker}] {text}")));
+            }
+            if complete {
+                Flow::Stop
+            } else {
+                Flow::Continue
+            }
+        };
+        let attempt = match ai_cli.backend {
+            AiBackend::Cli => run_attempt(cmd, None, &self.interrupted, on_line),
+            AiBackend::Http => run_http_attempt(
+                ai_cli,
+                model.as_deref(),
+                &prompt,
+                None,
+                &self.interrupted,
+                on_line,
+            ),
+            AiBackend::Ollama => run_ollama_attempt(
+                &config.ollama,
+                model.as_deref(),
+                &prompt,
+                None,
+                &self.interrupted,
+                on_line,
+            ),
+        }
+        .map_err(|e| e.to_string())?;
+
+        // Record the outcome the agent reported, in case it didn't
+        if let Some(reported) = collector.result() {
+            for message in agent_result::apply(&reported, Some(&story.id), Some(path)) {
+                let _ = writeln!(log, "{message}");
+            }
+        }
+
+        match attempt.end {
+            AttemptEnd::Exited(status) if !status.success() => Err(format!(
+                "AI CLI exited with code {} (see {})",
+                status.code().unwrap_or(-1),
+                log_path.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fold a worktree iteration back into the shared progress.
+    ///
+    /// Changes to protected paths are reverted in the worktree first, and an
+    /// iteration whose changes contain secrets fails with its commits undone.
+    /// A task that now passes is marked complete (syncing to its source), or
+    /// held for `afk review` when `review.required` is set, and its claim
+    /// released. Otherwise the iteration counts as a failure, and a task that
+    /// has reached `limits.max_task_failures` or used its
+    /// `limits.max_task_minutes` is released and skipped.
+    fn record_iteration(&self, iteration: &Iteration) -> Result<Outcome, String> {
+        let Iteration {
+            worker,
+            path,
+            story,
+            baseline,
+            elapsed,
+        } = *iteration;
+        let guardrails = &self.config.guardrails;
+        let mut violations = Vec::new();
+        if let Some(violation) =
+            baseline.and_then(|rev| revert_protected_paths_in(guardrails, path, rev))
+        {
+            self.warn(worker, &violation);
+            violations.push(violation);
+        }
+
+        // A story marked passed still has to pass its criterion checks
+        let blocked = baseline.and_then(|rev| block_secrets_in(guardrails, path, rev));
+        if let Some(violation) = &blocked {
+            self.warn(worker, violation);
+            violations.push(violation.clone());
+            unmark_passed(path, &story.id);
+        }
+
+        let mut held = None;
+        let passed = blocked.is_none()
+            && match PrdDocument::load(Some(&path.join(TASKS_FILE)))
+                .ok()
+                .and_then(|prd| prd.get_story(&story.id).cloned())
+            {
+                Some(mut done) if done.passes => {
+                    held = verify_story(&mut done, Some(path));
+                    held.is_none()
+                }
+                _ => false,
+            };
+
+        let _state = self.lock_state();
+
+        let mut progress = SessionProgress::load(None).map_err(|e| e.to_string())?;
+        progress.increment_iteration();
+        let failures_before = get_failure_count(&progress, &story.id);
+        let worktree_task = SessionProgress::load(Some(&path.join(PROGRESS_FILE)))
+            .ok()
+            .and_then(|p| p.tasks.get(&story.id).cloned());
+        if let Some(mut task) = worktree_task {
+            task.claimed_by = progress
+                .get_task(&story.id)
+                .and_then(|t| t.claimed_by.clone());
+            progress.tasks.insert(story.id.clone(), task);
+        }
+        let review = passed && self.config.review.required;
+        if review {
+            progress.set_task_status(&story.id, TaskStatus::AwaitingReview, &story.source, None);
+            progress.release_claim(&story.id);
+        } else if passed {
+            progress.set_task_status(&story.id, TaskStatus::Completed, &story.source, None);
+            progress.release_claim(&story.id);
+        } else if let Some(violation) = blocked {
+            progress.set_task_status(
+                &story.id,
+                TaskStatus::Failed,
+                &story.source,
+                Some(violation),
+            );
+        } else if let Some(details) = held {
+            progress.set_task_status(
+                &story.id,
+                TaskStatus::Failed,
+                &story.source,
+                Some(format!("Marked passed but {details}")),
+            );
+        } else if get_failure_count(&progress, &story.id) == failures_before {
+            // The agent didn't record the failure itself
+            progress.set_task_status(
+                &story.id,
+                TaskStatus::Failed,
+                &story.source,
+                Some("Iteration ended without completing the task".to_string()),
+            );
+        }
+
+        for violation in &violations {
+            progress.record_violation(&story.id, violation, &story.source);
+        }
+
+        let limits = &self.config.limits;
+        let minutes = if passed { 0 } else { limits.max_task_minutes };
+        let over_budget = charge_iteration_time(&mut progress, Some(&story.id), elapsed, minutes);
+        let failed_out = limits.max_task_failures > 0
+            && should_skip_task(&progress, &story.id, limits.max_task_failures);
+        let skipped = !passed && (over_budget || failed_out);
+        if skipped {
+            progress.release_claim(&story.id);
+        }
+        progress.save(None).map_err(|e| e.to_string())?;
+        // The next iteration starts from the counts just recorded
+        let worktree_progress = path.join(PROGRESS_FILE);
+        if let (Ok(mut scoped), Some(task)) = (
+            SessionProgress::load(Some(&worktree_progress)),
+            progress.get_task(&story.id),
+        ) {
+            scoped.tasks.insert(story.id.clone(), task.clone());
+            let _ = scoped.save(Some(&worktree_progress));
+        }
+
+        if passed && !review {
+            mark_story_complete(&story.id).map_err(|e| e.to_string())?;
+        }
+        Ok(if passed {
+            Outcome::Passed
+        } else if skipped {
+            Outcome::Skipped
+        } else {
+            Outcome::Retry
+        })
+    }
+
+    /// Merge a worker's branch into the current branch, one at a time.
+    fn merge(&self, branch: &str, task_id: &str) -> bool {
+        let _merges = self.merges.lock().unwrap_or_else(|e| e.into_inner());
+        git::merge_branch(branch, &format!("Merge {branch}: {task_id}"))
+    }
+
+    /// Show a warning about a worker's iteration in the TUI or on stderr.
+    fn warn(&self, worker: &str, message: &str) {
+        match &self.tui {
+            Some(tx) => {
+                let _ = tx.send(TuiEvent::OutputLine(format!("[{worker}] ⚠ {message}")));
+            }
+            None => eprintln!("\x1b[33mWarning:\x1b[0m {worker}: {message}"),
+        }
+    }
+
+    /// Report a worker's state to the TUI or the console.
+    fn status(&self, worker: &str, task: Option<&str>, status: &str) {
+        if let Some(tx) = &self.tui {
+            let _ = tx.send(TuiEvent::WorkerStatus {
+                worker: worker.to_string(),
+                task: task.map(ToOwned::to_owned),
+                status: status.to_string(),
+            });
+            return;
+        }
+        let colour = match status {
+            "merged" | "done" => "32",
+            "conflict" | "failed" => "31",
+            "skipped" => "33",
+            _ => "36",
+        };
+        let task = task.map(|t| format!(" {t}")).unwrap_or_default();
+        println!("  \x1b[1m{worker}\x1b[0m \x1b[{colour}m{status}\x1b[0m{task}");
+    }
+}
+
+/// Pickable stories no worker holds, highest priority first, with the
+/// reasons the others were passed over.
+fn unclaimed_selection(
+    prd: &PrdDocument,
+    progress: &SessionProgress,
+    order: &TaskOrder,
+    max_task_failures: u32,
+) -> Selection {
+    let mut selection = Selection::evaluate(prd, progress, order, max_task_failures);
+    selection.exclude_claimed(progress);
+    selection
+}
+
+/// Write a worktree's `.afk/` files so its prompt targets only `story`.
+fn scope_worktree(
+    path: &Path,
+    prd: &PrdDocument,
+    progress: &SessionProgress,
+    story: &UserStory,
+) -> Result<(), String> {
+    let mut scoped = prd.clone();
+    scoped.user_stories.retain(|s| s.passes || s.id == story.id);
+    scoped
+        .save(Some(&path.join(TASKS_FILE)))
+        .map_err(|e| e.to_string())?;
+    progress
+        .save(Some(&path.join(PROGRESS_FILE)))
+        .map_err(|e| e.to_string())?;
+    if Path::new(WARMUP_FILE).exists() {
+        fs::copy(WARMUP_FILE, path.join(WARMUP_FILE)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Set a story in a worktree's tasks back to not passing.
+fn unmark_passed(path: &Path, story_id: &str) {
+    let tasks = path.join(TASKS_FILE);
+    if let Ok(mut prd) = PrdDocument::load(Some(&tasks)) {
+        if let Some(story) = prd.user_stories.iter_mut().find(|s| s.id == story_id) {
+            story.passes = false;
+            let _ = prd.save(Some(&tasks));
+        }
+    }
+}
+
+/// Log file holding a worker's full output.
+fn log_path(worker: &str) -> PathBuf {
+    Path::new(WORKTREES_DIR).join(format!("{worker}.log"))
+}
+
+/// Summarise one line of agent output, and whether it signals completion.
+fn display_line(parser: Option<&mut StreamJsonParser>, line: &str) -> (Option<String>, bool) {
+    let signals = |text: &str| COMPLETION_SIGNALS.iter().any(|s| text.contains(s));
+    let Some(parser) = parser else {
+        return (Some(line.to_string()), signals(line));
+    };
+
+    let events = parser.parse_events(line);
+    if events.is_empty() {
+        let display = (!line.trim_start().starts_with('{')).then(|| line.to_string());
+        return (display, signals(line));
+    }
+    let display_event = |event| match event {
+        StreamEvent::AssistantMessage { text } => (
+            text.lines()
+                .find(|l| !l.trim().is_empty())
+                .map(ToOwned::to_owned),
+            signals(&text),
+        ),
+        StreamEvent::ToolStarted {
+            tool_type,
+            path,
+            command,
+            ..
+        } => {
+            let target = tool_target(path.as_deref(), command.as_deref());
+            (Some(format!("→ {tool_type}{target}")), false)
+        }
+        StreamEvent::Error { message } => (Some(format!("✗ {message}")), false),
+        _ => (None, false),
+    };
+    events
+        .into_iter()
+        .map(display_event)
+        .fold((None, false), |(display, complete), (next, signal)| {
+            (display.or(next), complete || signal)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::CliFormat;
+
+    #[test]
+    fn test_worker_names() {
+        assert_eq!(worker_name(2), "worker-2");
+        assert_eq!(worker_branch(2), "afk/worker-2");
+    }
+
+    #[test]
+    fn test_unsupported_settings() {
+        let mut config = AfkConfig::default();
+        assert!(unsupported_settings(&config).is_empty());
+
+        // Protected paths and secrets are checked in each worktree
+        config.guardrails.protected_paths = vec![".github/**".to_string()];
+        config.guardrails.secret_scan = true;
+        // Gates are only re-run when there are some
+        config.git.revert_on_gate_failure = true;
+        assert!(unsupported_settings(&config).is_empty());
+
+        config.plugins.paths = vec!["policy.so".to_string()];
+        config.guardrails.max_changed_lines = 500;
+        config.feedback_loops.test = Some("cargo test".to_string());
+        config.hooks.post_iteration = Some("./notify.sh".to_string());
+        assert_eq!(
+            unsupported_settings(&config),
+            [
+                "plugins",
+                "guardrails.max_changed_lines",
+                "git.revert_on_gate_failure",
+                "hooks.post_iteration"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pending_with_ci_criteria() {
+        let story = |id: &str, passes: bool, criteria: &[&str]| UserStory {
+            id: id.to_string(),
+            passes,
+            acceptance_criteria: criteria.iter().map(|&c| c.into()).collect(),
+            ..Default::default()
+        };
+        let prd = PrdDocument {
+            user_stories: vec![
+                story("plain", false, &["Renders"]),
+                story("ci", false, &["Renders", "ci:test"]),
+                story("done", true, &["ci:test"]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(pending_with_ci_criteria(&prd), ["ci"]);
+    }
+
+    #[test]
+    fn test_unclaimed_selection_skips_claimed_and_done() {
+        let prd = PrdDocument {
+            user_stories: vec![
+                UserStory {
+                    priority: 1,
+                    ..UserStory::new("auth", "Login")
+                },
+                UserStory {
+                    priority: 2,
+                    passes: true,
+                    ..UserStory::new("docs", "Docs")
+                },
+                UserStory {
+                    priority: 3,
+                    ..UserStory::new("cli", "CLI")
+                },
+            ],
+            ..Default::default()
+        };
+        let mut progress = SessionProgress::new();
+        let next = |progress: &SessionProgress| {
+            unclaimed_selection(&prd, progress, &TaskOrder::default(), 50)
+                .picked()
+                .map(ToOwned::to_owned)
+        };
+        assert_eq!(next(&progress).unwrap(), "auth");
+
+        progress.claim_task("auth", "json", "worker-1");
+        assert_eq!(next(&progress).unwrap(), "cli");
+
+        progress.claim_task("cli", "json", "worker-2");
+        assert!(next(&progress).is_none());
+    }
+
+    #[test]
+    fn test_display_line_plain_text() {
+        assert_eq!(
+            display_line(None, "editing src/lib.rs"),
+            (Some("editing src/lib.rs".to_string()), false)
+        );
+        assert!(display_line(None, "done <promise>COMPLETE</promise>").1);
+    }
+
+    #[test]
+    fn test_display_line_stream_json() {
+        let mut parser = StreamJsonParser::new(CliFormat::Claude);
+        let line = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"\nAll done. AFK_COMPLETE"}]}}"#;
+        let (display, complete) = display_line(Some(&mut parser), line);
+        assert_eq!(display.as_deref(), Some("All done. AFK_COMPLETE"));
+        assert!(complete);
+
+        // Unrecognised JSON is hidden
+        assert_eq!(
+            display_line(Some(&mut parser), r#"{"type":"ping"}"#),
+            (None, false)
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Platform-neutral process handling.
+//!
+//! Configured commands (gates, hooks, criterion checks) run through the
+//! platform shell: `sh -c` on Unix and `cmd /C` on Windows. AI CLIs are
+//! started directly, never through a shell, so the prompt reaches them as a
+//! single argument. On Windows a bare name such as `claude` is looked up on
+//! `PATH` with each `PATHEXT` extension first, because CLIs installed with
+//! npm are `.cmd` shims that `Command::new` won't find by name alone. Batch
+//! files can't take an argument with line breaks in it, as every prompt has,
+//! so an npm shim is skipped and the script it wraps run with node instead.
+//!
+//! A running AI CLI is tracked as a [`ProcessTree`], so a stall, Ctrl-C or
+//! completion signal stops everything it launched and not just the CLI. On
+//! Windows the tree is a job object, which also stops anything the CLI left
+//! running when the iteration ends; on Unix its children are killed with it.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+
+/// A command run through the platform shell.
+pub fn shell_command(command: &str) -> Command {
+    let (shell, shell_arg) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = Command::new(shell);
+    cmd.args([shell_arg, command]);
+    cmd
+}
+
+/// A command that starts `program` directly, resolving it on `PATH` first
+/// on Windows and running the script behind an npm shim.
+pub fn program_command(program: &str) -> Command {
+    let path = resolve_program(program);
+    let is_batch = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("cmd") || ext.eq_ignore_ascii_case("bat"));
+    let script = is_batch
+        .then(|| std::fs::read_to_string(&path).ok())
+        .flatten()
+        .and_then(|contents| npm_shim_script(&contents));
+    match (script, path.parent()) {
+        (Some(script), Some(dir)) => {
+            // npm shims prefer a node.exe installed beside them
+            let node = dir.join("node.exe");
+            let mut cmd = if node.is_file() {
+                Command::new(node)
+            } else {
+                Command::new("node")
+            };
+            cmd.arg(dir.join(script));
+            cmd
+        }
+        _ => Command::new(path),
+    }
+}
+
+/// The script an npm `.cmd` shim runs, relative to the shim's directory.
+///
+/// npm writes shims that end by running `"%dp0%\node_modules\...\cli.js" %*`
+/// with node, `%dp0%` being the shim's own directory.
+fn npm_shim_script(contents: &str) -> Option<PathBuf> {
+    contents.split('"').find_map(|part| {
+        let script = part
+            .strip_prefix("%dp0%\\")
+            .or_else(|| part.strip_prefix("%~dp0\\"))?;
+        let is_js = [".js", ".cjs", ".mjs"]
+            .iter()
+            .any(|ext| script.to_ascii_lowercase().ends_with(ext));
+        is_js.then(|| script.split('\\').collect())
+    })
+}
+
+/// Where `program` will be started from.
+///
+/// On Windows a name without an extension is tried with each `PATHEXT`
+/// extension in the directories on `PATH`, or beside itself if it is a
+/// path. Elsewhere, and when nothing is found, it is returned unchanged.
+pub fn resolve_program(program: &str) -> PathBuf {
+    if !cfg!(windows) || Path::new(program).extension().is_some() {
+        return PathBuf::from(program);
+    }
+    let extensions = env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string());
+    let dirs: Vec<PathBuf> = if program.contains(['/', '\\']) {
+        vec![PathBuf::new()]
+    } else {
+        env::var_os("PATH")
+            .map(|path| env::split_paths(&path).collect())
+            .unwrap_or_default()
+    };
+    find_with_extensions(program, &dirs, &extensions).unwrap_or_else(|| PathBuf::from(program))
+}
+
+/// The first `dir/program.ext` that exists, trying extensions in order.
+fn find_with_extensions(program: &str, dirs: &[PathBuf], extensions: &str) -> Option<PathBuf> {
+    dirs.iter().find_map(|dir| {
+        extensions
+            .split(';')
+            .filter(|ext| !ext.is_empty())
+            .map(|ext| dir.join(format!("{program}{}", ext.to_ascii_lowercase())))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// A spawned process and everything it starts, to be stopped together.
+///
+/// Cheap to clone; clones refer to the same processes.
+#[derive(Clone)]
+pub struct ProcessTree {
+    inner: Arc<Tree>,
+}
+
+struct Tree {
+    pid: u32,
+    #[cfg(windows)]
+    job: Option<job::Job>,
+}
+
+impl ProcessTree {
+    /// Track a just-spawned child.
+    pub fn attach(child: &Child) -> Self {
+        Self {
+            inner: Arc::new(Tree {
+                pid: child.id(),
+                #[cfg(windows)]
+                job: job::Job::for_process(std::os::windows::io::AsRawHandle::as_raw_handle(child)),
+            }),
+        }
+    }
+
+    /// Track a just-spawned tokio child. None if it has already been
+    /// waited on.
+    pub fn attach_async(child: &tokio::process::Child) -> Option<Self> {
+        Some(Self {
+            inner: Arc::new(Tree {
+                pid: child.id()?,
+                #[cfg(windows)]
+                job: child.raw_handle().and_then(job::Job::for_process),
+            }),
+        })
+    }
+
+    /// Process id of the process the tree was started from.
+    pub fn pid(&self) -> u32 {
+        self.inner.pid
+    }
+
+    /// Kill every process in the tree.
+    pub fn kill(&self) {
+        #[cfg(windows)]
+        if let Some(ref job) = self.inner.job {
+            job.terminate();
+            return;
+        }
+        kill_tree(self.inner.pid);
+    }
+}
+
+impl std::fmt::Debug for ProcessTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessTree")
+            .field("pid", &self.inner.pid)
+            .finish()
+    }
+}
+
+/// Kill a process and its direct children, which may hold its output open.
+#[cfg(unix)]
+fn kill_tree(pid: u32) {
+    let pid = pid.to_string();
+    let _ = Command::new("pkill")
+        .args(["-KILL", "-P", &pid])
+        .stderr(Stdio::null())
+        .status();
+    let _ = Command::new("kill")
+        .args(["-KILL", &pid])
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Kill a process and its children.
+#[cfg(not(unix))]
+fn kill_tree(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Windows job objects.
+#[cfg(windows)]
+mod job {
+    use std::ffi::c_void;
+    use std::os::windows::io::RawHandle;
+
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, TerminateJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+        JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    };
+
+    /// A job object that kills its processes when closed.
+    pub struct Job(HANDLE);
+
+    // SAFETY: a job object handle may be used and closed from any thread.
+    unsafe impl Send for Job {}
+    // SAFETY: the only operation through a shared Job is TerminateJobObject,
+    // which is thread-safe.
+    unsafe impl Sync for Job {}
+
+    impl Job {
+        /// Put a process in a new job. None if the job couldn't be set up,
+        /// in which case the process is left as it was.
+        pub fn for_process(process: RawHandle) -> Option<Self> {
+            // SAFETY: null arguments ask for an unnamed job with default
+            // security; the returned handle is owned by the Job.
+            let handle = unsafe { CreateJobObjectW(std::ptr::null(), std::ptr::null()) };
+            if handle.is_null() {
+                return None;
+            }
+            let job = Job(handle);
+
+            // SAFETY: an all-zero JOBOBJECT_EXTENDED_LIMIT_INFORMATION is a
+            // valid "no limits" value.
+            let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { std::mem::zeroed() };
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            // SAFETY: info is a live value of the size passed, and both
+            // handles are open for the duration of the calls.
+            let assigned = unsafe {
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const c_void,
+                    std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+                ) != 0
+                    && AssignProcessToJobObject(job.0, process as HANDLE) != 0
+            };
+            assigned.then_some(job)
+        }
+
+        /// Kill every process in the job.
+        pub fn terminate(&self) {
+            // SAFETY: the handle is open until the Job is dropped.
+            unsafe {
+                TerminateJobObject(self.0, 1);
+            }
+        }
+    }
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // SAFETY: the handle was opened by CreateJobObjectW and is closed
+            // only here.
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_with_extensions() {
+        let temp = TempDir::new().unwrap();
+        let (first, second) = (temp.path().join("a"), temp.path().join("b"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("claude.ps1"), "").unwrap();
+        std::fs::write(second.join("claude.cmd"), "").unwrap();
+        std::fs::write(second.join("claude.exe"), "").unwrap();
+
+        let dirs = [first, second.clone()];
+        assert_eq!(
+            find_with_extensions("claude", &dirs, ".COM;.EXE;.BAT;.CMD"),
+            Some(second.join("claude.exe"))
+        );
+        assert_eq!(
+            find_with_extensions("claude", &dirs, ".CMD;;.EXE"),
+            Some(second.join("claude.cmd"))
+        );
+        assert_eq!(find_with_extensions("codex", &dirs, ".EXE;.CMD"), None);
+    }
+
+    #[test]
+    fn test_npm_shim_script() {
+        let shim = "@ECHO off\r\nGOTO start\r\n:find_dp0\r\nSET dp0=%~dp0\r\nEXIT /b\r\n\
+                    :start\r\nSETLOCAL\r\nCALL :find_dp0\r\n\r\n\
+                    IF EXIST \"%dp0%\\node.exe\" (\r\n  SET \"_prog=%dp0%\\node.exe\"\r\n) \
+                    ELSE (\r\n  SET \"_prog=node\"\r\n)\r\n\r\n\
+                    endLocal & goto #_undefined_# 2>NUL || title %COMSPEC% & \"%_prog%\"  \
+                    \"%dp0%\\node_modules\\@anthropic-ai\\claude-code\\cli.js\" %*\r\n";
+        assert_eq!(
+            npm_shim_script(shim),
+            Some(
+                ["node_modules", "@anthropic-ai", "claude-code", "cli.js"]
+                    .iter()
+                    .collect()
+            )
+        );
+        assert_eq!(npm_shim_script("@echo off\r\nagent.exe %*\r\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_program_is_unchanged_off_windows() {
+        assert_eq!(resolve_program("claude"), PathBuf::from("claude"));
+        assert_eq!(resolve_program("./agent.sh"), PathBuf::from("./agent.sh"));
+    }
+
+    #[test]
+    fn test_shell_command_runs_through_the_shell() {
+        let temp = TempDir::new().unwrap();
+        let output = shell_command("echo one&& echo two")
+            .current_dir(temp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(
+            stdout.lines().map(str::trim).collect::<Vec<_>>(),
+            ["one", "two"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_stops_children() {
+        let temp = TempDir::new().unwrap();
+        let mut child = shell_command("sleep 30 & echo $!; wait $!")
+            .current_dir(temp.path())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(child.stdout.take().unwrap()),
+            &mut line,
+        )
+        .unwrap();
+        let grandchild = line.trim().to_string();
+
+        let tree = ProcessTree::attach(&child);
+        assert_eq!(tree.pid(), child.id());
+        tree.kill();
+        assert!(!child.wait().unwrap().success());
+        // Give the kill a moment to land before checking the grandchild
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        // A killed process nobody has reaped yet shows as a zombie
+        let state = Command::new("ps")
+            .args(["-o", "stat=", "-p", &grandchild])
+            .output()
+            .unwrap();
+        let state = String::from_utf8_lossy(&state.stdout);
+        assert!(
+            state.trim().is_empty() || state.trim().starts_with('Z'),
+            "grandchild {grandchild} survived: {state}"
+        );
+    }
+}
@@ -4,9 +4,14 @@
 //! and report pass/fail status.
 
 use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
-use crate::config::FeedbackLoopsConfig;
+use crate::config::{FeedbackLoopsConfig, WasmGateConfig};
+
+use super::gate_cache::GateCache;
+use super::process::shell_command;
+use super::secrets::run_secret_gate;
+use super::wasm_gates::run_wasm_gate;
 
 /// Result of a single quality gate.
 #[derive(Debug, Clone)]
@@ -30,6 +35,8 @@ pub struct QualityGateResult {
     pub gates: Vec<GateResult>,
     /// Names of failed gates.
     pub failed_gates: Vec<String>,
+    /// Names of gates whose result was reused from the gate cache.
+    pub cached_gates: Vec<String>,
 }
 
 impl QualityGateResult {
@@ -39,6 +46,7 @@ impl QualityGateResult {
             all_passed: true,
             gates: Vec::new(),
             failed_gates: Vec::new(),
+            cached_gates: Vec::new(),
         }
     }
 
@@ -69,50 +77,38 @@ impl Default for QualityGateResult {
 ///
 /// QualityGateResult with pass/fail status for each gate.
 pub fn run_quality_gates(feedback_loops: &FeedbackLoopsConfig, verbose: bool) -> QualityGateResult {
-    let mut result = QualityGateResult::new();
-
-    // Collect gates to run
-    let mut gates: Vec<(String, String)> = Vec::new();
-
-    if let Some(ref cmd) = feedback_loops.types {
-        gates.push(("types".to_string(), cmd.clone()));
-    }
-    if let Some(ref cmd) = feedback_loops.lint {
-        gates.push(("lint".to_string(), cmd.clone()));
-    }
-    if let Some(ref cmd) = feedback_loops.test {
-        gates.push(("test".to_string(), cmd.clone()));
-    }
-    if let Some(ref cmd) = feedback_loops.build {
-        gates.push(("build".to_string(), cmd.clone()));
-    }
-
-    // Add custom gates
-    for (name, cmd) in &feedback_loops.custom {
-        gates.push((name.clone(), cmd.clone()));
-    }
+    run_quality_gates_cached(feedback_loops, verbose, None)
+}
 
-    if gates.is_empty() {
+/// Run all configured quality gates, reusing cached passes.
+///
+/// Gates found in `cache` for the current tree are not run again; fresh
+/// results are written back to it.
+pub fn run_quality_gates_cached(
+    feedback_loops: &FeedbackLoopsConfig,
+    verbose: bool,
+    cache: Option<&mut GateCache>,
+) -> QualityGateResult {
+    if !has_configured_gates(feedback_loops) {
         println!("\x1b[2mNo quality gates configured.\x1b[0m");
-        return result;
+        return QualityGateResult::new();
     }
 
     println!();
     println!("\x1b[1mRunning quality gates...\x1b[0m");
     println!();
 
-    for (name, cmd) in gates {
-        let gate_result = run_single_gate(&name, &cmd, verbose);
-
+    let result = run_gates(feedback_loops, cache, |gate_result, cached| {
         let status = if gate_result.passed {
             "\x1b[32m✓\x1b[0m"
         } else {
             "\x1b[31m✗\x1b[0m"
         };
+        let note = if cached { " \x1b[2mcached\x1b[0m" } else { "" };
 
         println!(
-            "  {} {} ({:.1}s)",
-            status, name, gate_result.duration_seconds
+            "  {} {} ({:.1}s){note}",
+            status, gate_result.name, gate_result.duration_seconds
         );
 
         if verbose && !gate_result.output.is_empty() {
@@ -120,9 +116,7 @@ pub fn run_quality_gates(feedback_loops: &FeedbackLoopsConfig, verbose: bool) ->
                 println!("      {line}");
             }
         }
-
-        result.add_gate(gate_result);
-    }
+    });
 
     println!();
 
@@ -138,16 +132,103 @@ pub fn run_quality_gates(feedback_loops: &FeedbackLoopsConfig, verbose: bool) ->
     result
 }
 
+/// Run all configured quality gates without printing anything.
+///
+/// Used for machine-readable reports, where stdout must hold only the
+/// report itself.
+pub fn run_quality_gates_quiet(
+    feedback_loops: &FeedbackLoopsConfig,
+    cache: Option<&mut GateCache>,
+) -> QualityGateResult {
+    run_gates(feedback_loops, cache, |_, _| {})
+}
+
+/// Run each gate in order, or take it from the cache, reporting each result
+/// (and whether it was cached) as it arrives.
+fn run_gates(
+    feedback_loops: &FeedbackLoopsConfig,
+    mut cache: Option<&mut GateCache>,
+    mut report: impl FnMut(&GateResult, bool),
+) -> QualityGateResult {
+    let mut result = QualityGateResult::new();
+    let (gates, wasm_gates) = collect_gates(feedback_loops);
+
+    // The secret scan runs first, so it blocks the commit however the rest
+    // go; it looks at uncommitted changes, which the tree cache can't key
+    if let Some(scan) = &feedback_loops.secret_scan {
+        let gate_result = run_secret_gate(scan);
+        report(&gate_result, false);
+        result.add_gate(gate_result);
+    }
+
+    // WASM gates are keyed on their serialised config
+    let shell = gates.into_iter().map(|(name, cmd)| (name, cmd, None));
+    let wasm = wasm_gates.into_iter().map(|(name, gate)| {
+        let key = serde_json::to_string(gate).unwrap_or_default();
+        (name.clone(), key, Some(gate))
+    });
+
+    for (name, key, wasm_gate) in shell.chain(wasm) {
+        let hit = cache.as_deref().and_then(|c| c.lookup(&name, &key));
+        let cached = hit.is_some();
+        let gate_result = hit.unwrap_or_else(|| match wasm_gate {
+            Some(gate) => run_wasm_gate(&name, gate),
+            None => run_single_gate(&name, &key, false),
+        });
+        if let Some(cache) = cache.as_deref_mut() {
+            cache.store(&key, &gate_result);
+        }
+
+        report(&gate_result, cached);
+        if cached {
+            result.cached_gates.push(name);
+        }
+        result.add_gate(gate_result);
+    }
+
+    if let Some(cache) = cache {
+        let _ = cache.save();
+    }
+    result
+}
+
+/// Shell gates as (name, command), and WASM gates as (name, config).
+type CollectedGates<'a> = (Vec<(String, String)>, Vec<(&'a String, &'a WasmGateConfig)>);
+
+/// Shell gates in run order, followed by WASM gates sorted by name.
+fn collect_gates(feedback_loops: &FeedbackLoopsConfig) -> CollectedGates<'_> {
+    let mut gates: Vec<(String, String)> = Vec::new();
+
+    if let Some(ref cmd) = feedback_loops.types {
+        gates.push(("types".to_string(), cmd.clone()));
+    }
+    if let Some(ref cmd) = feedback_loops.lint {
+        gates.push(("lint".to_string(), cmd.clone()));
+    }
+    if let Some(ref cmd) = feedback_loops.test {
+        gates.push(("test".to_string(), cmd.clone()));
+    }
+    if let Some(ref cmd) = feedback_loops.build {
+        gates.push(("build".to_string(), cmd.clone()));
+    }
+
+    // Add custom gates
+    for (name, cmd) in &feedback_loops.custom {
+        gates.push((name.clone(), cmd.clone()));
+    }
+
+    // Sandboxed WASM gates run after the shell gates
+    let mut wasm_gates: Vec<(&String, &WasmGateConfig)> = feedback_loops.wasm.iter().collect();
+    wasm_gates.sort_by_key(|(name, _)| name.as_str());
+
+    (gates, wasm_gates)
+}
+
 /// Run a single quality gate.
 fn run_single_gate(name: &str, cmd: &str, _verbose: bool) -> GateResult {
     let start = std::time::Instant::now();
 
-    // Parse command - use shell for complex commands
-    let shell = if cfg!(windows) { "cmd" } else { "sh" };
-    let shell_arg = if cfg!(windows) { "/C" } else { "-c" };
-
-    let process = Command::new(shell)
-        .args([shell_arg, cmd])
+    let process = shell_command(cmd)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn();
@@ -201,17 +282,22 @@ fn run_single_gate(name: &str, cmd: &str, _verbose: bool) -> GateResult {
 
 /// Check if any gates are configured.
 pub fn has_configured_gates(feedback_loops: &FeedbackLoopsConfig) -> bool {
-    feedback_loops.types.is_some()
+    feedback_loops.secret_scan.is_some()
+        || feedback_loops.types.is_some()
         || feedback_loops.lint.is_some()
         || feedback_loops.test.is_some()
         || feedback_loops.build.is_some()
         || !feedback_loops.custom.is_empty()
+        || !feedback_loops.wasm.is_empty()
 }
 
 /// Get list of configured gate names.
 pub fn get_configured_gate_names(feedback_loops: &FeedbackLoopsConfig) -> Vec<String> {
     let mut names = Vec::new();
 
+    if feedback_loops.secret_scan.is_some() {
+        names.push("secrets".to_string());
+    }
     if feedback_loops.types.is_some() {
         names.push("types".to_string());
     }
@@ -229,6 +315,10 @@ pub fn get_configured_gate_names(feedback_loops: &FeedbackLoopsConfig) -> Vec<St
         names.push(name.clone());
     }
 
+    let mut wasm_names: Vec<String> = feedback_loops.wasm.keys().cloned().collect();
+    wasm_names.sort();
+    names.extend(wasm_names);
+
     names
 }
 
@@ -0,0 +1,175 @@
+//! Refresh of derived context before a session.
+//!
+//! Some files afk generates are summaries of other files, such as the
+//! warm-up notes in `.afk/warmup.md`. Before each `afk go`, the inputs of
+//! each one are fingerprinted and compared with the fingerprint recorded
+//! last time (in `.afk/refresh.json`). A stale artefact is removed so the
+//! session regenerates it, and stale context never needs clearing by hand.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::{AfkConfig, REFRESH_FILE, WARMUP_FILE};
+use crate::progress::repro::stable_hash;
+use crate::prompt::WARMUP_TEMPLATE;
+
+/// Project files that shape every summary of the repo.
+const PROJECT_FILES: &[&str] = &[
+    "AGENTS.md",
+    "CLAUDE.md",
+    "README.md",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+];
+
+/// A generated file and what it was generated from.
+struct DerivedArtefact {
+    /// Name shown when the artefact is refreshed.
+    name: &'static str,
+    /// Path of the generated file.
+    path: &'static str,
+    /// Whether the artefact is in use with this config.
+    enabled: fn(&AfkConfig) -> bool,
+    /// Files read to produce it.
+    input_files: fn(&AfkConfig) -> Vec<PathBuf>,
+    /// Other inputs (template text, config values).
+    input_text: fn(&AfkConfig) -> String,
+}
+
+/// Every artefact afk derives from project files.
+const ARTEFACTS: &[DerivedArtefact] = &[DerivedArtefact {
+    name: "warm-up notes",
+    path: WARMUP_FILE,
+    enabled: |config| config.runner.warmup,
+    input_files: |config| {
+        PROJECT_FILES
+            .iter()
+            .map(|f| f.to_string())
+            .chain(config.prompt.context_files.iter().cloned())
+            .map(PathBuf::from)
+            .collect()
+    },
+    input_text: |config| {
+        format!(
+            "{WARMUP_TEMPLATE}\n{}",
+            config.prompt.instructions.join("\n")
+        )
+    },
+}];
+
+/// Remove derived artefacts whose inputs changed since they were made.
+///
+/// Returns the names of the artefacts removed; the session regenerates them.
+/// An artefact seen for the first time only has its inputs recorded. Does
+/// nothing in read-only mode.
+pub fn refresh_derived(config: &AfkConfig) -> io::Result<Vec<String>> {
+    refresh_derived_in(Path::new("."), config)
+}
+
+/// Refresh artefacts under a project root.
+fn refresh_derived_in(root: &Path, config: &AfkConfig) -> io::Result<Vec<String>> {
+    if crate::config::is_read_only() {
+        return Ok(Vec::new());
+    }
+
+    let record_path = root.join(REFRESH_FILE);
+    let mut recorded: HashMap<String, String> = fs::read_to_string(&record_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let mut refreshed = Vec::new();
+    for artefact in ARTEFACTS.iter().filter(|a| (a.enabled)(config)) {
+        let fingerprint = fingerprint(root, artefact, config);
+        let path = root.join(artefact.path);
+        let stale = recorded
+            .get(artefact.name)
+            .is_some_and(|previous| *previous != fingerprint);
+        if stale && path.exists() {
+            fs::remove_file(&path)?;
+            refreshed.push(artefact.name.to_string());
+        }
+        recorded.insert(artefact.name.to_string(), fingerprint);
+    }
+
+    if let Some(parent) = record_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&record_path, serde_json::to_string_pretty(&recorded)?)?;
+    Ok(refreshed)
+}
+
+/// Hash of an artefact's inputs. Missing files count as empty.
+fn fingerprint(root: &Path, artefact: &DerivedArtefact, config: &AfkConfig) -> String {
+    let mut data = (artefact.input_text)(config).into_bytes();
+    for file in (artefact.input_files)(config) {
+        data.extend_from_slice(file.to_string_lossy().as_bytes());
+        data.push(0);
+        data.extend(fs::read(root.join(&file)).unwrap_or_default());
+        data.push(0);
+    }
+    stable_hash(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn warmup_config() -> AfkConfig {
+        let mut config = AfkConfig::default();
+        config.runner.warmup = true;
+        config.prompt.context_files = vec!["docs/arch.md".to_string()];
+        config
+    }
+
+    #[test]
+    fn test_first_run_records_without_refreshing() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join(".afk")).unwrap();
+        fs::write(temp.path().join(WARMUP_FILE), "notes").unwrap();
+
+        let refreshed = refresh_derived_in(temp.path(), &warmup_config()).unwrap();
+        assert!(refreshed.is_empty());
+        assert!(temp.path().join(WARMUP_FILE).exists());
+        assert!(temp.path().join(REFRESH_FILE).exists());
+    }
+
+    #[test]
+    fn test_changed_input_removes_artefact() {
+        let temp = TempDir::new().unwrap();
+        let config = warmup_config();
+        fs::create_dir_all(temp.path().join(".afk")).unwrap();
+        fs::create_dir_all(temp.path().join("docs")).unwrap();
+        fs::write(temp.path().join("docs/arch.md"), "v1").unwrap();
+        fs::write(temp.path().join(WARMUP_FILE), "notes").unwrap();
+        refresh_derived_in(temp.path(), &config).unwrap();
+
+        // Unchanged inputs keep the artefact
+        assert!(refresh_derived_in(temp.path(), &config).unwrap().is_empty());
+        assert!(temp.path().join(WARMUP_FILE).exists());
+
+        fs::write(temp.path().join("docs/arch.md"), "v2").unwrap();
+        let refreshed = refresh_derived_in(temp.path(), &config).unwrap();
+        assert_eq!(refreshed, vec!["warm-up notes"]);
+        assert!(!temp.path().join(WARMUP_FILE).exists());
+    }
+
+    #[test]
+    fn test_disabled_artefact_is_ignored() {
+        let temp = TempDir::new().unwrap();
+        let mut config = warmup_config();
+        fs::create_dir_all(temp.path().join(".afk")).unwrap();
+        fs::write(temp.path().join(WARMUP_FILE), "notes").unwrap();
+        refresh_derived_in(temp.path(), &config).unwrap();
+
+        config.runner.warmup = false;
+        config.prompt.instructions = vec!["changed".to_string()];
+        assert!(refresh_derived_in(temp.path(), &config).unwrap().is_empty());
+        assert!(temp.path().join(WARMUP_FILE).exists());
+    }
+}
@@ -0,0 +1,348 @@
+//! Failure classification and retry policies.
+//!
+//! Each iteration that goes wrong is sorted into a [`FailureKind`]: the AI
+//! CLI exited with an error, stalled, or reported a rate limit; the
+//! criterion checks or the gate re-run failed; or the iteration changed
+//! nothing and completed nothing. The kind is recorded on the task in
+//! `progress.json`, along with how many iterations in a row have failed that
+//! way, and `retry.<kind>` picks what the loop does next: carry on, wait
+//! and carry on, skip the task, or stop.
+//!
+//! Rate limits aren't the task's fault, so they're counted by the loop's
+//! [`RetryState`] instead of on the task, and back off from
+//! `limits.rate_limit_backoff_secs`.
+
+use std::time::{Duration, Instant};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{AfkConfig, RetryConfig, RetryPolicy};
+use crate::parser::is_rate_limit_message;
+use crate::progress::{FailureKind, SessionProgress, TaskStatus};
+
+use super::stall::STALLED;
+
+/// Output lines searched for rate-limit messages.
+const RATE_LIMIT_TAIL: usize = 20;
+
+/// Errors that come from setting up the iteration rather than the AI CLI
+/// run, which no retry would fix.
+const SETUP_ERRORS: &[&str] = &[
+    "Failed to generate prompt",
+    "No command specified",
+    "AI CLI not found",
+];
+
+/// What the loop does after a failed iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RetryAction {
+    /// Carry on with the next iteration.
+    Continue(String),
+    /// Wait this long, then carry on.
+    Wait(Duration, String),
+    /// The task was skipped; carry on with the next one.
+    Skipped(String),
+    /// Stop the loop.
+    Stop(String),
+}
+
+/// The kind of failure an AI CLI error is, or None for errors no retry
+/// would fix (a missing CLI, a broken prompt template).
+pub fn classify_error(error: &str, output: &str) -> Option<FailureKind> {
+    if SETUP_ERRORS.iter().any(|e| error.starts_with(e)) {
+        return None;
+    }
+    let tail: Vec<&str> = output.lines().rev().take(RATE_LIMIT_TAIL).collect();
+    if is_rate_limit_message(error) || tail.iter().any(|line| is_rate_limit_message(line)) {
+        return Some(FailureKind::RateLimit);
+    }
+    if error == STALLED || error.starts_with("AI CLI stalled") {
+        return Some(FailureKind::Timeout);
+    }
+    Some(FailureKind::CliCrash)
+}
+
+/// The kind of failure an iteration whose AI CLI run succeeded is, if it
+/// failed: gates or criterion checks failed, or it changed nothing and
+/// completed nothing.
+pub fn classify_outcome(gates_failed: bool, changed: bool, completed: bool) -> Option<FailureKind> {
+    if gates_failed {
+        Some(FailureKind::GatesFailed)
+    } else if !changed && !completed {
+        Some(FailureKind::NoChanges)
+    } else {
+        None
+    }
+}
+
+/// The policy `retry` sets for a kind of failure.
+pub fn policy(config: &RetryConfig, kind: FailureKind) -> RetryPolicy {
+    match kind {
+        FailureKind::CliCrash => config.cli_crash,
+        FailureKind::Timeout => config.timeout,
+        FailureKind::GatesFailed => config.gates_failed,
+        FailureKind::NoChanges => config.no_changes,
+        FailureKind::RateLimit => config.rate_limit,
+    }
+}
+
+/// Failures the loop tracks itself rather than on tasks.
+#[derive(Debug, Default)]
+pub struct RetryState {
+    /// Rate-limited iterations in a row.
+    rate_limits: u32,
+}
+
+impl RetryState {
+    /// Respond to a failed iteration on `task` (ID and source), recording it
+    /// on the task unless it was rate limited.
+    pub fn after_failure(
+        &mut self,
+        config: &AfkConfig,
+        task: Option<(&str, &str)>,
+        kind: FailureKind,
+    ) -> RetryAction {
+        if kind == FailureKind::RateLimit {
+            self.rate_limits += 1;
+            return after_rate_limit(config, task, self.rate_limits);
+        }
+        self.rate_limits = 0;
+        after_failure(&config.retry, task, kind)
+    }
+
+    /// Note an iteration on a task that didn't fail.
+    pub fn after_success(&mut self, task_id: Option<&str>) {
+        self.rate_limits = 0;
+        after_success(task_id);
+    }
+}
+
+/// Decide what the loop does after the `streak`th rate-limited iteration in
+/// a row, leaving the task's failure record alone.
+fn after_rate_limit(config: &AfkConfig, task: Option<(&str, &str)>, streak: u32) -> RetryAction {
+    let on = task.map(|(id, _)| format!(" on {id}")).unwrap_or_default();
+    let times = if streak > 1 {
+        format!(" ({streak} in a row)")
+    } else {
+        String::new()
+    };
+    match config.retry.rate_limit {
+        RetryPolicy::Retry => RetryAction::Continue(format!("Rate limited{on}{times}; retrying")),
+        RetryPolicy::Backoff => {
+            let delay = backoff_delay(
+                config.limits.rate_limit_backoff_secs,
+                config.retry.max_backoff_secs,
+                streak,
+            );
+            RetryAction::Wait(
+                delay,
+                format!(
+                    "Rate limited{on}{times}; waiting {}s before retrying",
+                    delay.as_secs()
+                ),
+            )
+        }
+        RetryPolicy::Skip => match task {
+            Some((id, source)) if streak >= config.retry.skip_after => {
+                let progress = SessionProgress::load(None).ok();
+                if let Some(mut progress) = progress.filter(|_| !crate::config::is_read_only()) {
+                    let message = format!("Skipped after {streak} rate limits in a row");
+                    progress.set_task_status(id, TaskStatus::Skipped, source, Some(message));
+                    let _ = progress.save(None);
+                }
+                RetryAction::Skipped(format!(
+                    "Skipped {id}: rate limited {streak} times in a row"
+                ))
+            }
+            _ => RetryAction::Continue(format!("Rate limited{on}{times}; retrying")),
+        },
+        RetryPolicy::Stop => RetryAction::Stop(format!("Iteration{on} failed: rate limited")),
+    }
+}
+
+/// Record a failed iteration on `task` (ID and source) and decide what the
+/// loop does next.
+fn after_failure(
+    config: &RetryConfig,
+    task: Option<(&str, &str)>,
+    kind: FailureKind,
+) -> RetryAction {
+    let mut progress = SessionProgress::load(None).ok();
+    let streak = match (progress.as_mut(), task) {
+        (Some(progress), Some((id, source))) => progress.record_failure(id, kind, source),
+        _ => 1,
+    };
+    let on = task.map(|(id, _)| format!(" on {id}")).unwrap_or_default();
+    let times = if streak > 1 {
+        format!(" ({streak} in a row)")
+    } else {
+        String::new()
+    };
+
+    let action = match policy(config, kind) {
+        RetryPolicy::Retry => {
+            RetryAction::Continue(format!("Iteration{on} failed: {kind}{times}; retrying"))
+        }
+        RetryPolicy::Backoff => {
+            let delay = backoff_delay(config.backoff_secs, config.max_backoff_secs, streak);
+            RetryAction::Wait(
+                delay,
+                format!(
+                    "Iteration{on} failed: {kind}{times}; waiting {}s before retrying",
+                    delay.as_secs()
+                ),
+            )
+        }
+        RetryPolicy::Skip => match (progress.as_mut(), task) {
+            (Some(progress), Some((id, source))) if streak >= config.skip_after => {
+                let message = format!("Skipped after {streak} iterations in a row with {kind}");
+                progress.set_task_status(id, TaskStatus::Skipped, source, Some(message));
+                RetryAction::Skipped(format!("Skipped {id}: {kind} {streak} times in a row"))
+            }
+            _ => RetryAction::Continue(format!("Iteration{on} failed: {kind}{times}; retrying")),
+        },
+        RetryPolicy::Stop => RetryAction::Stop(format!("Iteration{on} failed: {kind}")),
+    };
+
+    if let Some(progress) = progress.filter(|_| !crate::config::is_read_only()) {
+        let _ = progress.save(None);
+    }
+    action
+}
+
+/// Clear the failure streak of a task whose iteration didn't fail.
+fn after_success(task_id: Option<&str>) {
+    let Some(id) = task_id else {
+        return;
+    };
+    if crate::config::is_read_only() {
+        return;
+    }
+    let Ok(mut progress) = SessionProgress::load(None) else {
+        return;
+    };
+    if progress.get_task(id).is_some_and(|t| t.failure_streak > 0) {
+        progress.clear_failure_streak(id);
+        let _ = progress.save(None);
+    }
+}
+
+/// How long `backoff` waits after the `streak`th failure in a row: `base`
+/// seconds doubled for each earlier one, up to `max` seconds.
+pub fn backoff_delay(base: u64, max: u64, streak: u32) -> Duration {
+    let factor = 2u64.saturating_pow(streak.saturating_sub(1));
+    Duration::from_secs(base.saturating_mul(factor).min(max))
+}
+
+/// Sleep for `delay`, waking early if the loop is interrupted.
+///
+/// Returns false if it was interrupted.
+pub fn wait(delay: Duration, interrupted: &CancellationToken) -> bool {
+    let deadline = Instant::now() + delay;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        if interrupted.is_cancelled() {
+            return false;
+        }
+        std::thread::sleep(left.min(Duration::from_millis(200)));
+    }
+    !interrupted.is_cancelled()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_error() {
+        assert_eq!(
+            classify_error("AI CLI exited with code 1", ""),
+            Some(FailureKind::CliCrash)
+        );
+        assert_eq!(
+            classify_error(STALLED, "working..."),
+            Some(FailureKind::Timeout)
+        );
+        assert_eq!(
+            classify_error("AI CLI stalled: no output for 300s", ""),
+            Some(FailureKind::Timeout)
+        );
+        assert_eq!(
+            classify_error(
+                "AI CLI exited with code 1",
+                "starting\nAPI Error: 429 Too Many Requests\n"
+            ),
+            Some(FailureKind::RateLimit)
+        );
+        assert_eq!(
+            classify_error("Claude usage limit reached", ""),
+            Some(FailureKind::RateLimit)
+        );
+        assert_eq!(classify_error("AI CLI not found: claude. Is it", ""), None);
+    }
+
+    #[test]
+    fn test_classify_outcome() {
+        assert_eq!(
+            classify_outcome(true, true, false),
+            Some(FailureKind::GatesFailed)
+        );
+        assert_eq!(
+            classify_outcome(false, false, false),
+            Some(FailureKind::NoChanges)
+        );
+        assert_eq!(classify_outcome(false, true, false), None);
+        assert_eq!(classify_outcome(false, false, true), None);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        assert_eq!(backoff_delay(30, 100, 1), Duration::from_secs(30));
+        assert_eq!(backoff_delay(30, 100, 2), Duration::from_secs(60));
+        assert_eq!(backoff_delay(30, 100, 3), Duration::from_secs(100));
+        assert_eq!(backoff_delay(30, 100, 80), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn test_rate_limits_back_off_without_a_task() {
+        let mut config = AfkConfig::default();
+        config.limits.rate_limit_backoff_secs = 10;
+        let mut state = RetryState::default();
+
+        let first = state.after_failure(&config, None, FailureKind::RateLimit);
+        assert_eq!(
+            first,
+            RetryAction::Wait(
+                Duration::from_secs(10),
+                "Rate limited; waiting 10s before retrying".to_string()
+            )
+        );
+        let second = state.after_failure(&config, None, FailureKind::RateLimit);
+        assert!(matches!(second, RetryAction::Wait(delay, _) if delay == Duration::from_secs(20)));
+
+        state.after_success(None);
+        let again = state.after_failure(&config, None, FailureKind::RateLimit);
+        assert!(matches!(again, RetryAction::Wait(delay, _) if delay == Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_policy_defaults() {
+        let config = RetryConfig::default();
+        assert_eq!(policy(&config, FailureKind::CliCrash), RetryPolicy::Stop);
+        assert_eq!(policy(&config, FailureKind::Timeout), RetryPolicy::Stop);
+        assert_eq!(policy(&config, FailureKind::NoChanges), RetryPolicy::Retry);
+        assert_eq!(
+            policy(&config, FailureKind::RateLimit),
+            RetryPolicy::Backoff
+        );
+    }
+
+    #[test]
+    fn test_wait_interrupted() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let start = Instant::now();
+        assert!(!wait(Duration::from_secs(30), &token));
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(wait(Duration::ZERO, &CancellationToken::new()));
+    }
+}
@@ -0,0 +1,39 @@
+//! Review queue.
+//!
+//! With `review.required` set, a story an iteration marks passed isn't
+//! completed: it's put back to not-passed and held as awaiting review until
+//! `afk review` approves it (completing it) or rejects it (returning it to
+//! pending with the reviewer's feedback for the next prompt).
+
+use crate::prd::PrdDocument;
+use crate::progress::{SessionProgress, TaskStatus};
+
+use super::hooks;
+
+/// Hold each story newly marked passed in `new` (compared with `old`) for
+/// review, unmarking it in `new`. Returns a message for each one.
+pub fn hold_for_review(old: &PrdDocument, new: &mut PrdDocument) -> Vec<String> {
+    let completed: Vec<String> = hooks::newly_completed(old, new)
+        .into_iter()
+        .map(|s| s.id.clone())
+        .collect();
+    if completed.is_empty() {
+        return Vec::new();
+    }
+
+    let mut messages = Vec::new();
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    for id in completed {
+        let Some(story) = new.user_stories.iter_mut().find(|s| s.id == id) else {
+            continue;
+        };
+        story.passes = false;
+        progress.set_task_status(&id, TaskStatus::AwaitingReview, &story.source, None);
+        messages.push(format!("{id} is awaiting review (afk review)"));
+    }
+    if !crate::config::is_read_only() {
+        let _ = new.save(None);
+        let _ = progress.save(None);
+    }
+    messages
+}
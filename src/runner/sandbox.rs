@@ -0,0 +1,203 @@
+//! Sandboxed runs.
+//!
+//! `afk go --sandbox` runs the loop in a git worktree at
+//! `.afk/worktrees/sandbox` on the `afk/sandbox` branch instead of the
+//! working tree, so a runaway agent can't touch the user's checkout. The
+//! `.afk/` state is copied in when the sandbox is created, and whatever the
+//! run leaves uncommitted is committed there when it ends. The changes reach
+//! the working tree only through `afk sandbox apply`, which merges the branch
+//! and brings the task state back with it, or as a patch. Later `--sandbox`
+//! runs carry on in the same sandbox until it is applied or discarded.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{AFK_DIR, PROGRESS_FILE, TASKS_FILE, WORKTREES_DIR};
+use crate::git;
+
+use super::pool::prepare_worktrees_dir;
+
+/// Branch the sandbox works on.
+pub const SANDBOX_BRANCH: &str = "afk/sandbox";
+
+/// Message for the commit of work a sandboxed run left uncommitted.
+const LEFTOVER_MESSAGE: &str = "afk: uncommitted sandbox changes";
+
+/// `.afk/` entries not copied into a new sandbox: other worktrees, and
+/// history that belongs to the main checkout.
+const SKIPPED_STATE: &[&str] = &[
+    "worktrees",
+    "archive",
+    "logs",
+    "artifacts",
+    "repro",
+    "control",
+];
+
+/// Where the sandbox worktree lives.
+pub fn sandbox_path() -> PathBuf {
+    Path::new(WORKTREES_DIR).join("sandbox")
+}
+
+/// Whether a sandbox has been created and not yet applied or discarded.
+pub fn sandbox_exists() -> bool {
+    // A worktree has a `.git` file pointing back at the main repository
+    sandbox_path().join(".git").exists()
+}
+
+/// Create the sandbox from HEAD, or reuse the existing one.
+///
+/// Returns its path.
+pub fn prepare_sandbox() -> Result<PathBuf, String> {
+    if !git::is_git_repo() {
+        return Err("--sandbox needs a git repository".to_string());
+    }
+    let path = sandbox_path();
+    if sandbox_exists() {
+        return Ok(path);
+    }
+    prepare_worktrees_dir().map_err(|e| e.to_string())?;
+    if !git::add_worktree(&path, SANDBOX_BRANCH) {
+        return Err(format!("could not create worktree on {SANDBOX_BRANCH}"));
+    }
+    let state = path.join(AFK_DIR);
+    copy_state(Path::new(AFK_DIR), &state).map_err(|e| e.to_string())?;
+    if !git::is_tracked(AFK_DIR) {
+        // Keep untracked state out of the sandbox's commits, as it is here
+        fs::write(state.join(".gitignore"), "*\n").map_err(|e| e.to_string())?;
+    }
+    Ok(path)
+}
+
+/// Copy `.afk/` into a new sandbox, leaving out [`SKIPPED_STATE`].
+fn copy_state(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.filter_map(Result::ok) {
+        let name = entry.file_name();
+        if SKIPPED_STATE.iter().any(|s| name == *s) {
+            continue;
+        }
+        let target = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_state(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Commit whatever a run left uncommitted in the sandbox.
+///
+/// Returns true if there was anything to commit.
+pub fn commit_sandbox(path: &Path) -> bool {
+    git::commit_all_in(path, LEFTOVER_MESSAGE)
+}
+
+/// The sandbox's changes since it branched off, excluding `.afk/`, as a
+/// unified diff or with `stat` a diffstat. Includes uncommitted work, so it
+/// can be reviewed while a run is still going.
+pub fn sandbox_diff(stat: bool) -> Result<String, String> {
+    let path = sandbox_path();
+    let base = git::merge_base("HEAD", SANDBOX_BRANCH)
+        .ok_or_else(|| format!("{SANDBOX_BRANCH} has no common history with HEAD"))?;
+    // Mark new files so the diff shows them
+    let _ = Command::new("git")
+        .arg("-C")
+        .arg(&path)
+        .args(["add", "-A", "--intent-to-add"])
+        .output();
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(&path).arg("diff");
+    if stat {
+        cmd.arg("--stat");
+    }
+    let output = cmd
+        .args([&base, "--", ".", ":(exclude).afk"])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The sandbox's commits as a patch series for `git am`, after committing
+/// any uncommitted work. Returns the patch and how many commits it holds.
+pub fn sandbox_patch() -> Result<(String, usize), String> {
+    commit_sandbox(&sandbox_path());
+    let base = git::merge_base("HEAD", SANDBOX_BRANCH)
+        .ok_or_else(|| format!("{SANDBOX_BRANCH} has no common history with HEAD"))?;
+    let range = format!("{base}..{SANDBOX_BRANCH}");
+    let commits = git::commit_log(&range).len();
+    let output = Command::new("git")
+        .args(["format-patch", "--stdout", &range])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok((
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        commits,
+    ))
+}
+
+/// Merge the sandbox into the current branch and remove it.
+///
+/// Any uncommitted work in it is committed first, and its tasks and
+/// progress replace the working tree's. A merge that conflicts is aborted
+/// and the sandbox kept. Returns the number of commits merged.
+pub fn apply_sandbox() -> Result<usize, String> {
+    let path = sandbox_path();
+    commit_sandbox(&path);
+    let commits = git::commit_log(&format!("HEAD..{SANDBOX_BRANCH}")).len();
+    if commits > 0 && !git::merge_branch(SANDBOX_BRANCH, &format!("Merge {SANDBOX_BRANCH}")) {
+        return Err(format!(
+            "merging {SANDBOX_BRANCH} failed (conflicts or local changes); the sandbox is kept"
+        ));
+    }
+    for file in [TASKS_FILE, PROGRESS_FILE] {
+        let sandboxed = path.join(file);
+        if sandboxed.exists() {
+            fs::copy(&sandboxed, file).map_err(|e| e.to_string())?;
+        }
+    }
+    // The sandbox's progress remembers its branch, not this one
+    let _ = crate::progress::update_stored_branch();
+    discard_sandbox();
+    Ok(commits)
+}
+
+/// Remove the sandbox worktree and its branch, losing its changes.
+pub fn discard_sandbox() -> bool {
+    let removed = git::remove_worktree(&sandbox_path());
+    git::delete_branch(SANDBOX_BRANCH) || removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_copy_state_skips_history() {
+        let temp = TempDir::new().unwrap();
+        let from = temp.path().join("from");
+        fs::create_dir_all(from.join("templates")).unwrap();
+        fs::create_dir_all(from.join("archive/old")).unwrap();
+        fs::create_dir_all(from.join("worktrees/worker-1")).unwrap();
+        fs::write(from.join("tasks.json"), "{}").unwrap();
+        fs::write(from.join("templates/prompt.md"), "hi").unwrap();
+        fs::write(from.join("archive/old/tasks.json"), "{}").unwrap();
+
+        let to = temp.path().join("to");
+        copy_state(&from, &to).unwrap();
+        assert!(to.join("tasks.json").exists());
+        assert!(to.join("templates/prompt.md").exists());
+        assert!(!to.join("archive").exists());
+        assert!(!to.join("worktrees").exists());
+    }
+}
@@ -0,0 +1,324 @@
+//! Scheduled runs.
+//!
+//! `afk go --at 02:00` waits until a time of day (or a date and time)
+//! before starting the loop. `schedule.cron` holds a standard five-field
+//! cron expression (minute, hour, day of month, month, day of week) that
+//! `afk scheduler start` follows, starting `afk go` each time it falls due
+//! and recording the outcome in the event log.
+
+use std::fmt;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+
+/// Longest single sleep while waiting, so a machine that slept through the
+/// start time notices soon after it wakes.
+const WAIT_STEP: Duration = Duration::from_secs(30);
+
+/// Days searched for the next time a cron schedule matches.
+const SEARCH_DAYS: u32 = 366 * 5;
+
+/// When `afk go --at` should start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartTime {
+    /// The next time the clock shows this time, today or tomorrow.
+    Time(NaiveTime),
+    /// This local date and time.
+    DateTime(NaiveDateTime),
+}
+
+impl StartTime {
+    /// The moment to start, or None if a given date and time has passed.
+    pub fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        match *self {
+            Self::Time(time) => {
+                let today = now.date_naive().and_time(time);
+                let start = if today > now.naive_local() {
+                    today
+                } else {
+                    today + chrono::Duration::days(1)
+                };
+                local(start)
+            }
+            Self::DateTime(at) => local(at).filter(|start| *start > now),
+        }
+    }
+}
+
+impl fmt::Display for StartTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Time(time) => write!(f, "{}", time.format("%H:%M")),
+            Self::DateTime(at) => write!(f, "{}", at.format("%Y-%m-%d %H:%M")),
+        }
+    }
+}
+
+/// Parse a start time: "HH:MM" (or "HH:MM:SS"), or a local date and time
+/// such as "2026-01-31 02:00" or "2026-01-31T02:00".
+pub fn parse_start_time(s: &str) -> Result<StartTime, String> {
+    let s = s.trim();
+    for format in ["%H:%M", "%H:%M:%S"] {
+        if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+            return Ok(StartTime::Time(time));
+        }
+    }
+    for format in [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(s, format) {
+            return Ok(StartTime::DateTime(at));
+        }
+    }
+    Err(format!(
+        "invalid start time '{s}' (use e.g. 02:00 or 2026-01-31 02:00)"
+    ))
+}
+
+/// A local time, taking the earlier one when clocks go back and None for
+/// a time skipped when they go forward.
+fn local(naive: NaiveDateTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// Sleep until `when`.
+pub fn wait_until(when: DateTime<Local>) {
+    loop {
+        let Ok(left) = (when - Local::now()).to_std() else {
+            return;
+        };
+        if left.is_zero() {
+            return;
+        }
+        thread::sleep(left.min(WAIT_STEP));
+    }
+}
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// Day of month and day of week were both restricted, so a day
+    /// matching either counts (as in cron).
+    either_day: bool,
+}
+
+impl CronSchedule {
+    /// Parse a five-field expression, or one of `@hourly`, `@daily`
+    /// (`@midnight`), `@weekly` and `@monthly`.
+    ///
+    /// Fields take `*`, numbers, ranges (`1-5`), steps (`*/15`, `0-30/10`)
+    /// and lists (`1,15`); months and days of week also take names
+    /// (`jan`, `mon-fri`), and Sunday is 0 or 7.
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "invalid cron expression '{expr}' (expected 5 fields: minute hour day month weekday)"
+            ));
+        };
+        let field_error = |name: &str, e: String| format!("invalid cron {name} field: {e}");
+        let weekdays =
+            parse_field(weekday, 0, 7, WEEKDAY_NAMES).map_err(|e| field_error("day-of-week", e))?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[]).map_err(|e| field_error("minute", e))?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(|e| field_error("hour", e))? as u32,
+            days: parse_field(day, 1, 31, &[]).map_err(|e| field_error("day", e))? as u32,
+            months: parse_field(month, 1, 12, MONTH_NAMES).map_err(|e| field_error("month", e))?
+                as u16,
+            // Fold 7 onto Sunday
+            weekdays: ((weekdays | (weekdays >> 7)) & 0x7f) as u8,
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+
+    /// The first time after `after` (to the minute) that the schedule
+    /// matches, looking up to five years ahead.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start =
+            after.naive_local().with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let mut date = start.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_day(date) {
+                let from = if date == start.date() {
+                    start.time()
+                } else {
+                    NaiveTime::MIN
+                };
+                for hour in from.hour()..24 {
+                    if self.hours & (1 << hour) == 0 {
+                        continue;
+                    }
+                    let first = if hour == from.hour() {
+                        from.minute()
+                    } else {
+                        0
+                    };
+                    for minute in first..60 {
+                        if self.minutes & (1 << minute) == 0 {
+                            continue;
+                        }
+                        let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+                        if let Some(at) = local(date.and_time(time)) {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Parse one cron field into a bit set of the values it allows.
+///
+/// `names` are the values from `min` on, in order.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_ascii_lowercase();
+        let n = match names.iter().position(|name| *name == lower) {
+            Some(i) => i as u32 + min,
+            None => s.parse().map_err(|_| format!("'{s}' is not a number"))?,
+        };
+        if n < min || n > max {
+            return Err(format!("{n} is outside {min}-{max}"));
+        }
+        Ok(n)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step '{step}'"))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (value(a)?, value(b)?)
+        } else {
+            let n = value(range)?;
+            // "5/10" means from 5 to the end in steps of 10
+            (n, if step > 1 { max } else { n })
+        };
+        if start > end {
+            return Err(format!("range {start}-{end} runs backwards"));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Local> {
+        local(NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()).unwrap()
+    }
+
+    fn next(expr: &str, after: &str) -> String {
+        CronSchedule::parse(expr)
+            .unwrap()
+            .next_after(at(after))
+            .unwrap()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        let time = parse_start_time("02:00").unwrap();
+        assert_eq!(
+            time.next_after(at("2026-03-10 01:00")),
+            Some(at("2026-03-10 02:00"))
+        );
+        assert_eq!(
+            time.next_after(at("2026-03-10 02:00")),
+            Some(at("2026-03-11 02:00"))
+        );
+
+        let date = parse_start_time("2026-03-12T06:30").unwrap();
+        assert_eq!(
+            date.next_after(at("2026-03-10 01:00")),
+            Some(at("2026-03-12 06:30"))
+        );
+        assert_eq!(date.next_after(at("2026-03-13 00:00")), None);
+        assert!(parse_start_time("2am").is_err());
+        assert!(parse_start_time("25:00").is_err());
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        assert_eq!(next("0 2 * * *", "2026-03-10 01:59"), "2026-03-10 02:00");
+        assert_eq!(next("0 2 * * *", "2026-03-10 02:00"), "2026-03-11 02:00");
+        assert_eq!(next("*/15 * * * *", "2026-03-10 10:07"), "2026-03-10 10:15");
+        assert_eq!(next("@hourly", "2026-03-10 23:30"), "2026-03-11 00:00");
+        // 2026-03-14 is a Saturday
+        assert_eq!(
+            next("30 9 * * mon-fri", "2026-03-13 10:00"),
+            "2026-03-16 09:30"
+        );
+        assert_eq!(next("0 0 * * 7", "2026-03-13 10:00"), "2026-03-15 00:00");
+        assert_eq!(
+            next("0 0 1 jan,jul *", "2026-03-13 10:00"),
+            "2026-07-01 00:00"
+        );
+        // Day of month or day of week, when both are given
+        assert_eq!(next("0 0 20 * fri", "2026-03-13 10:00"), "2026-03-20 00:00");
+        assert_eq!(next("0 0 29 2 *", "2026-03-01 00:00"), "2028-02-29 00:00");
+    }
+
+    #[test]
+    fn test_cron_parse_errors() {
+        assert!(CronSchedule::parse("0 2 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("0 5-2 * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("0 0 * foo *").is_err());
+        assert!(CronSchedule::parse("5/20 * * * *").is_ok());
+    }
+}
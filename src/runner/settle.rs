@@ -0,0 +1,568 @@
+//! Settling an iteration once the AI CLI has finished.
+//!
+//! Both loops, plain and TUI, hand a finished iteration to [`settle`]. It
+//! undoes changes to protected paths and secrets, checks the iteration's
+//! diff against the guardrails, reverts the iteration if it broke green
+//! gates, lets plugins veto it, holds back stories whose criterion checks,
+//! review or oversized diff stand in the way, and records what completed.
+//! The loops only differ in how they show what it reports.
+
+use crate::config::{AfkConfig, GuardrailAction};
+use crate::git::{commits_since, reset_mixed};
+use crate::plugins::{PluginEvent, PluginHost};
+use crate::prd::{PrdDocument, UserStory};
+use crate::progress::{burndown, FailureKind, SessionProgress};
+
+use super::control::skip_if_requested;
+use super::criterion_checks;
+use super::gate_revert::{revert_if_gates_fail, GateBaseline};
+use super::guardrails::{
+    check_iteration_diff, protected_instruction, reopen_oversized, revert_protected_paths,
+    split_instruction,
+};
+use super::hooks;
+use super::retry;
+use super::review;
+use super::secrets::{block_secrets, secrets_instruction};
+use super::task_branch::TaskBranches;
+use super::task_pr::open_completed_prs;
+use super::StopReason;
+
+/// Something to show the user while settling an iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Notice {
+    Info(String),
+    Warning(String),
+    /// A story was held for review.
+    Review(String),
+    /// The task was skipped by request.
+    Skipped(String),
+}
+
+impl Notice {
+    /// A task branch or pull request outcome, with whether it is a warning.
+    fn branch((message, is_warning): (String, bool)) -> Self {
+        if is_warning {
+            Self::Warning(message)
+        } else {
+            Self::Info(message)
+        }
+    }
+}
+
+/// An iteration the AI CLI has finished.
+pub struct Finished<'a> {
+    pub iteration: u32,
+    /// The task it worked on.
+    pub task: Option<&'a UserStory>,
+    /// The PRD as it was when the iteration started.
+    pub prd: &'a PrdDocument,
+    /// Commit the guardrails compare against.
+    pub guardrail_start: Option<&'a str>,
+    /// Gate results from before the iteration, if `gates.revert_on_fail` is on.
+    pub gate_start: Option<&'a GateBaseline>,
+    /// Commit plugins are shown the iteration's commits since.
+    pub plugin_start: Option<&'a str>,
+    /// Whether it changed files or made commits.
+    pub changed: bool,
+    /// How the AI CLI failed, if it did and the loop carries on.
+    pub failure: Option<FailureKind>,
+}
+
+/// What settling an iteration decided.
+#[derive(Debug)]
+pub struct Settled {
+    /// The PRD after the iteration, with held stories put back.
+    pub prd: PrdDocument,
+    /// Stories the iteration completed.
+    pub tasks_completed: u32,
+    /// Instructions for the next iteration's prompt.
+    pub notes: Vec<String>,
+    /// How the iteration failed, for `retry` to respond to.
+    pub failure: Option<FailureKind>,
+    /// Why the loop must stop, if it must.
+    pub stop: Option<StopReason>,
+    /// Remaining-task history, or None if the iteration was vetoed and
+    /// nothing was recorded.
+    pub burndown: Option<Vec<usize>>,
+}
+
+/// Settle a finished iteration, reporting what happens through `report`.
+pub fn settle(
+    config: &AfkConfig,
+    plugins: &PluginHost,
+    branches: Option<&mut TaskBranches>,
+    finished: &Finished,
+    report: &mut dyn FnMut(Notice),
+) -> Settled {
+    let task = finished.task.map(|t| (t.id.as_str(), t.source.as_str()));
+
+    // Undo changes to protected paths and secrets before anything accepts
+    // them
+    let mut notes = Vec::new();
+    if let Some(violation) = finished
+        .guardrail_start
+        .and_then(|rev| revert_protected_paths(&config.guardrails, rev, task))
+    {
+        notes.push(protected_instruction(&violation, &config.guardrails));
+        report(Notice::Warning(violation));
+    }
+    if let Some(violation) = finished
+        .guardrail_start
+        .and_then(|rev| block_secrets(&config.guardrails, rev, task))
+    {
+        notes.push(secrets_instruction(&violation));
+        report(Notice::Warning(violation));
+    }
+    // Check the diff's size before any completion is accepted
+    let on_exceed = config.guardrails.on_exceed;
+    let oversized = finished
+        .guardrail_start
+        .and_then(|rev| check_iteration_diff(&config.guardrails, rev));
+    if let Some(violation) = &oversized {
+        report(Notice::Warning(violation.clone()));
+        if on_exceed == GuardrailAction::Split {
+            notes.push(split_instruction(violation));
+        }
+    }
+    // Then undo the whole iteration if it broke green gates
+    let mut gates_failed = false;
+    if let Some(revert) = finished
+        .gate_start
+        .and_then(|start| revert_if_gates_fail(config, start, finished.iteration, task))
+    {
+        report(Notice::Warning(revert.message()));
+        notes.push(revert.instruction());
+        gates_failed = true;
+    }
+
+    // Let plugins review the iteration's commits before anything syncs,
+    // merges or opens a PR for them
+    if let Some(veto) = plugin_iteration_end(plugins, finished) {
+        report(Notice::Warning(format!("Vetoed by plugin {veto}")));
+        if let Some(message) = reject_iteration(finished.plugin_start, finished.prd) {
+            report(Notice::Warning(message));
+        }
+        return Settled {
+            prd: finished.prd.clone(),
+            tasks_completed: 0,
+            notes,
+            failure: finished.failure,
+            stop: Some(StopReason::PluginVeto(veto)),
+            burndown: None,
+        };
+    }
+
+    // Check if a task was completed, holding back any whose criterion
+    // checks fail, that came with an oversized diff or that await review
+    let before = finished.prd;
+    let mut prd = PrdDocument::load(None).unwrap_or_else(|_| before.clone());
+    for held in criterion_checks::hold_unverified(before, &mut prd) {
+        report(Notice::Warning(held.warning));
+        gates_failed |= held.failed;
+    }
+    let mut completed = false;
+    if oversized.is_some() {
+        match on_exceed {
+            GuardrailAction::Warn => {}
+            GuardrailAction::Split => {
+                for message in reopen_oversized(before, &mut prd) {
+                    report(Notice::Warning(message));
+                }
+            }
+            GuardrailAction::Review | GuardrailAction::Stop => {
+                for message in review::hold_for_review(before, &mut prd) {
+                    report(Notice::Review(message));
+                    completed = true;
+                }
+            }
+        }
+    }
+    if config.review.required {
+        for message in review::hold_for_review(before, &mut prd) {
+            report(Notice::Review(message));
+            completed = true;
+        }
+    }
+    let old_completed = before.user_stories.iter().filter(|s| s.passes).count();
+    let new_completed = prd.user_stories.iter().filter(|s| s.passes).count();
+    let tasks_completed = new_completed.saturating_sub(old_completed) as u32;
+    completed |= tasks_completed > 0;
+    let failure = finished
+        .failure
+        .or_else(|| retry::classify_outcome(gates_failed, finished.changed, completed));
+    if tasks_completed > 0 {
+        sync_completed_tasks(before, &prd, config);
+    }
+    if let Some(skipped) = skip_if_requested(task, &prd) {
+        report(Notice::Skipped(skipped));
+    }
+    let burndown = record_burndown(&prd);
+    match branches {
+        Some(branches) => {
+            if let Some(outcome) = branches.finish_if_passed(&prd) {
+                report(Notice::branch(outcome.message(branches.base())));
+            }
+        }
+        None => {
+            for message in open_completed_prs(config, before, &prd) {
+                report(Notice::branch(message));
+            }
+        }
+    }
+
+    Settled {
+        prd,
+        tasks_completed,
+        notes,
+        failure,
+        stop: oversized
+            .filter(|_| on_exceed == GuardrailAction::Stop)
+            .map(StopReason::Guardrail),
+        burndown: Some(burndown),
+    }
+}
+
+/// Append a burn-down point for the backlog and return the remaining-task
+/// history.
+pub fn record_burndown(prd: &PrdDocument) -> Vec<usize> {
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    burndown::record(prd, &progress);
+    burndown::remaining(&burndown::load(None))
+}
+
+/// Tell plugins an iteration finished. Returns a veto, if any.
+fn plugin_iteration_end(plugins: &PluginHost, finished: &Finished) -> Option<String> {
+    if plugins.is_empty() {
+        return None;
+    }
+    plugins.emit(&PluginEvent::IterationEnd {
+        iteration: finished.iteration,
+        task_id: finished.task.map(|t| t.id.clone()),
+        commits: finished.plugin_start.map(commits_since).unwrap_or_default(),
+    })
+}
+
+/// Reject a vetoed iteration: move back to the commit it started from,
+/// leaving its changes in the working tree, and put the stories it marked
+/// passed (compared with `before`) back to pending.
+///
+/// Returns a description of what was undone, or None if nothing was.
+fn reject_iteration(baseline: Option<&str>, before: &PrdDocument) -> Option<String> {
+    let mut undone = Vec::new();
+    if let Some(rev) = baseline {
+        let commits = commits_since(rev).len();
+        if commits > 0 && reset_mixed(rev) {
+            undone.push(format!("{commits} commit(s) undone"));
+        }
+    }
+    if let Ok(mut prd) = PrdDocument::load(None) {
+        let reopened: Vec<String> = hooks::newly_completed(before, &prd)
+            .into_iter()
+            .map(|s| s.id.clone())
+            .collect();
+        if !reopened.is_empty() {
+            for story in prd
+                .user_stories
+                .iter_mut()
+                .filter(|s| reopened.contains(&s.id))
+            {
+                story.passes = false;
+            }
+            if prd.save(None).is_ok() {
+                undone.push(format!("{} back to pending", reopened.join(", ")));
+            }
+        }
+    }
+    (!undone.is_empty()).then(|| format!("Rejected the iteration: {}", undone.join("; ")))
+}
+
+/// Sync completed tasks back to their sources.
+///
+/// Compares old and new PRD states to find tasks that changed from
+/// `passes: false` to `passes: true` and closes them in beads or GitHub
+/// (when `github.sync_back` is on), checks them off in markdown sources with
+/// writeback, or moves their story file to `done/` for stories-dir sources
+/// with writeback.
+fn sync_completed_tasks(old_prd: &PrdDocument, new_prd: &PrdDocument, config: &AfkConfig) {
+    for story in hooks::newly_completed(old_prd, new_prd) {
+        if story.source == "beads" {
+            crate::sources::complete_beads_task(&story.id, &config.sources);
+        } else if story.source.starts_with("github:") {
+            crate::sources::close_github_task(&story.source, &story.id, &config.github);
+        } else if story.source.starts_with("markdown:") {
+            crate::sources::complete_markdown_task(&story.source, &story.id, &config.sources);
+        } else {
+            crate::sources::complete_story_file(&story.source, &config.sources);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::config::{SourceConfig, SourceType};
+    use crate::git::{get_current_branch, get_current_commit};
+    use crate::prd::AcceptanceCriterion;
+    use crate::runner::gate_revert::gate_baseline;
+
+    fn story(id: &str, passes: bool) -> UserStory {
+        UserStory {
+            passes,
+            ..UserStory::new(id, format!("Title of {id}"))
+        }
+    }
+
+    fn prd(stories: Vec<UserStory>) -> PrdDocument {
+        PrdDocument {
+            user_stories: stories,
+            ..Default::default()
+        }
+    }
+
+    fn finished<'a>(task: &'a UserStory, before: &'a PrdDocument) -> Finished<'a> {
+        Finished {
+            iteration: 1,
+            task: Some(task),
+            prd: before,
+            guardrail_start: None,
+            gate_start: None,
+            plugin_start: None,
+            changed: true,
+            failure: None,
+        }
+    }
+
+    fn settle_collecting(config: &AfkConfig, finished: &Finished) -> (Settled, Vec<Notice>) {
+        let mut notices = Vec::new();
+        let settled = settle(
+            config,
+            &PluginHost::default(),
+            None,
+            finished,
+            &mut |notice| notices.push(notice),
+        );
+        (settled, notices)
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=afk", "-c", "user.email=afk@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_settle_counts_completed_tasks() {
+        let temp = TempDir::new().unwrap();
+        std::env::set_current_dir(temp.path()).unwrap();
+        let before = prd(vec![story("a", false), story("b", false)]);
+        prd(vec![story("a", true), story("b", false)])
+            .save(None)
+            .unwrap();
+
+        let (settled, notices) = settle_collecting(
+            &AfkConfig::default(),
+            &finished(&before.user_stories[0], &before),
+        );
+        assert_eq!(settled.tasks_completed, 1);
+        assert!(settled.prd.get_story("a").unwrap().passes);
+        assert_eq!(settled.failure, None);
+        assert_eq!(settled.stop, None);
+        assert!(settled.burndown.is_some());
+        assert!(notices.is_empty());
+
+        // Held for review, the story counts as progress but not as done
+        let mut config = AfkConfig::default();
+        config.review.required = true;
+        prd(vec![story("a", false), story("b", true)])
+            .save(None)
+            .unwrap();
+        let (settled, notices) =
+            settle_collecting(&config, &finished(&before.user_stories[1], &before));
+        assert_eq!(settled.tasks_completed, 0);
+        assert!(!settled.prd.get_story("b").unwrap().passes);
+        assert_eq!(settled.failure, None);
+        assert!(matches!(&notices[..], [Notice::Review(_)]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_settle_holds_back_failed_checks() {
+        let temp = TempDir::new().unwrap();
+        std::env::set_current_dir(temp.path()).unwrap();
+        let mut task = story("a", false);
+        task.acceptance_criteria = vec![AcceptanceCriterion {
+            verify: Some("false".to_string()),
+            ..AcceptanceCriterion::new("Never holds")
+        }];
+        let before = prd(vec![task.clone()]);
+        task.passes = true;
+        prd(vec![task]).save(None).unwrap();
+
+        let (settled, notices) = settle_collecting(
+            &AfkConfig::default(),
+            &finished(&before.user_stories[0], &before),
+        );
+        assert_eq!(settled.tasks_completed, 0);
+        assert!(!settled.prd.get_story("a").unwrap().passes);
+        assert_eq!(settled.failure, Some(FailureKind::GatesFailed));
+        assert!(matches!(&notices[..], [Notice::Warning(_)]));
+
+        // An iteration that did nothing at all made no progress
+        let finished = Finished {
+            changed: false,
+            ..finished(&before.user_stories[0], &before)
+        };
+        prd(vec![story("a", false)]).save(None).unwrap();
+        let (settled, _) = settle_collecting(&AfkConfig::default(), &finished);
+        assert_eq!(settled.failure, Some(FailureKind::NoChanges));
+    }
+
+    #[test]
+    fn test_settle_applies_the_diff_guardrail() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        git(dir, &["init", "-q"]);
+        fs::write(dir.join(".gitignore"), ".afk/\n").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "init"]);
+        std::env::set_current_dir(dir).unwrap();
+        let start = get_current_commit().unwrap();
+        fs::write(dir.join("one.rs"), "1\n").unwrap();
+        fs::write(dir.join("two.rs"), "2\n").unwrap();
+
+        let task = story("a", false);
+        let before = prd(vec![task.clone()]);
+        let finished = Finished {
+            guardrail_start: Some(&start),
+            ..finished(&task, &before)
+        };
+        let mut config = AfkConfig::default();
+        config.guardrails.max_changed_files = 1;
+
+        config.guardrails.on_exceed = GuardrailAction::Split;
+        let (settled, notices) = settle_collecting(&config, &finished);
+        assert_eq!(settled.stop, None);
+        assert_eq!(settled.notes.len(), 1);
+        assert!(matches!(&notices[..], [Notice::Warning(w)] if w.contains("2 files changed")));
+
+        config.guardrails.on_exceed = GuardrailAction::Stop;
+        let (settled, _) = settle_collecting(&config, &finished);
+        assert!(matches!(settled.stop, Some(StopReason::Guardrail(_))));
+        assert!(settled.notes.is_empty());
+    }
+
+    #[test]
+    fn test_settle_reverts_when_a_plugin_gate_fails() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        git(dir, &["init", "-q"]);
+        fs::write(dir.join(".gitignore"), ".afk/\n").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "init"]);
+        std::env::set_current_dir(dir).unwrap();
+
+        let mut config = AfkConfig::default();
+        config.git.revert_on_gate_failure = true;
+        // Without the plugin's gate there is nothing to run
+        assert!(gate_baseline(&config).is_none());
+        let plugins = PluginHost::with_gates_response(r#"{"gates": {"policy": "false"}}"#);
+        plugins.add_gates(&mut config.feedback_loops);
+        let gate_start = gate_baseline(&config).unwrap();
+
+        let task = story("a", false);
+        let before = prd(vec![task.clone()]);
+        fs::write(dir.join("work.rs"), "fn main() {}\n").unwrap();
+        prd(vec![story("a", true)]).save(None).unwrap();
+
+        let finished = Finished {
+            gate_start: Some(&gate_start),
+            ..finished(&task, &before)
+        };
+        let (settled, notices) = settle_collecting(&config, &finished);
+        assert_eq!(settled.tasks_completed, 0);
+        assert!(!settled.prd.get_story("a").unwrap().passes);
+        assert_eq!(settled.failure, Some(FailureKind::GatesFailed));
+        assert!(notices
+            .iter()
+            .any(|n| matches!(n, Notice::Warning(w) if w.contains("(policy)"))));
+        assert!(!dir.join("work.rs").exists());
+    }
+
+    #[test]
+    fn test_settle_holds_oversized_completions() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        git(dir, &["init", "-q", "-b", "main"]);
+        fs::write(dir.join(".gitignore"), ".afk/\n").unwrap();
+        fs::write(dir.join("TODO.md"), "- [ ] a: Do a\n").unwrap();
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", "init"]);
+        std::env::set_current_dir(dir).unwrap();
+
+        let mut config = AfkConfig::default();
+        config.guardrails.max_changed_files = 1;
+        config.guardrails.on_exceed = GuardrailAction::Review;
+        config.git.auto_branch = true;
+        config.git.auto_merge = true;
+        let mut source = SourceConfig::new(SourceType::Markdown);
+        source.path = Some("TODO.md".to_string());
+        source.writeback = true;
+        config.sources = vec![source];
+
+        let task = UserStory {
+            source: "markdown:TODO.md".to_string(),
+            ..story("a", false)
+        };
+        let before = prd(vec![task.clone()]);
+        let mut branches = TaskBranches::new(&config).unwrap();
+        branches.enter("a").unwrap();
+        let task_branch = get_current_branch().unwrap();
+        let start = get_current_commit().unwrap();
+        fs::write(dir.join("one.rs"), "1\n").unwrap();
+        fs::write(dir.join("two.rs"), "2\n").unwrap();
+        prd(vec![UserStory {
+            passes: true,
+            ..task.clone()
+        }])
+        .save(None)
+        .unwrap();
+
+        let finished = Finished {
+            guardrail_start: Some(&start),
+            ..finished(&task, &before)
+        };
+        let mut notices = Vec::new();
+        let settled = settle(
+            &config,
+            &PluginHost::default(),
+            Some(&mut branches),
+            &finished,
+            &mut |notice| notices.push(notice),
+        );
+        assert_eq!(settled.tasks_completed, 0);
+        assert_eq!(settled.stop, None);
+        assert!(
+            !PrdDocument::load(None)
+                .unwrap()
+                .get_story("a")
+                .unwrap()
+                .passes
+        );
+        assert!(notices.iter().any(|n| matches!(n, Notice::Review(_))));
+        // Neither synced back to its source nor merged
+        assert_eq!(fs::read_to_string("TODO.md").unwrap(), "- [ ] a: Do a\n");
+        assert_eq!(get_current_branch().unwrap(), task_branch);
+    }
+}
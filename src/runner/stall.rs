@@ -0,0 +1,86 @@
+//! Stall detection.
+//!
+//! An AI CLI can hang without exiting: waiting on a prompt that will never
+//! be answered, or stuck on a network call. With `runner.stall_factor` set,
+//! the attempt kills the CLI once it has printed nothing for that many times
+//! `feedback.thinking_threshold_secs` (see [`super::attempt`]). The
+//! iteration records the stall and runs again, up to `runner.stall_retries`
+//! times, telling the CLI that its previous attempt appeared stuck when
+//! `runner.stall_nudge` is on.
+
+use std::time::Duration;
+
+use crate::config::AfkConfig;
+
+/// Error an attempt returns when the AI CLI was killed for stalling.
+pub const STALLED: &str = "AFK_STALLED";
+
+/// How long the AI CLI may stay silent, or None if stall detection is off.
+pub fn stall_limit(config: &AfkConfig) -> Option<Duration> {
+    let secs = config
+        .feedback
+        .thinking_threshold_secs
+        .saturating_mul(u64::from(config.runner.stall_factor));
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// The prompt for a retry after a stall: the original, with a note that the
+/// last attempt went quiet if `runner.stall_nudge` is on.
+pub fn retry_prompt(config: &AfkConfig, prompt: &str, limit: Duration) -> String {
+    if !config.runner.stall_nudge {
+        return prompt.to_string();
+    }
+    format!(
+        "{prompt}\n\n## Previous attempt stalled\n\n\
+         Your previous attempt at this iteration produced no output for {}s and was \
+         stopped; you appear to have been stuck. Don't wait for input or on commands \
+         that may hang. If your last approach isn't working, try a different one.\n",
+        limit.as_secs()
+    )
+}
+
+/// Warning shown when a stalled attempt is retried.
+pub fn retry_message(limit: Duration, attempt: u32, retries: u32) -> String {
+    format!(
+        "AI CLI produced no output for {}s; retrying ({attempt}/{retries})",
+        limit.as_secs()
+    )
+}
+
+/// Error an iteration fails with once its retries are used up.
+pub fn stalled_error(limit: Duration) -> String {
+    format!("AI CLI stalled: no output for {}s", limit.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_limit() {
+        let mut config = AfkConfig::default();
+        assert_eq!(stall_limit(&config), None);
+        config.feedback.thinking_threshold_secs = 10;
+        config.runner.stall_factor = 30;
+        assert_eq!(stall_limit(&config), Some(Duration::from_secs(300)));
+        config.feedback.thinking_threshold_secs = 0;
+        assert_eq!(stall_limit(&config), None);
+    }
+
+    #[test]
+    fn test_retry_prompt() {
+        let mut config = AfkConfig::default();
+        let prompt = retry_prompt(&config, "Do the task", Duration::from_secs(60));
+        assert!(prompt.starts_with("Do the task\n\n## Previous attempt stalled"));
+        assert!(prompt.contains("no output for 60s"));
+        config.runner.stall_nudge = false;
+        assert_eq!(
+            retry_prompt(&config, "Do the task", Duration::from_secs(60)),
+            "Do the task"
+        );
+        assert_eq!(
+            retry_message(Duration::from_secs(60), 1, 2),
+            "AI CLI produced no output for 60s; retrying (1/2)"
+        );
+    }
+}
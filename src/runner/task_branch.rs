@@ -0,0 +1,219 @@
+//! Branch per task.
+//!
+//! With `git.auto_branch`, the loop works on each task on its own
+//! `<branch_prefix><task-id>` branch, started from the branch the run began
+//! on (the base). When the task passes the loop returns to the base; with
+//! `git.auto_merge` it first re-runs the quality gates on the task branch
+//! and merges it back if they pass. Branches whose gates fail or whose
+//! merge conflicts are left for review. With `git.open_pr` the branch is
+//! pushed and a pull request opened instead of merging. Uncommitted
+//! changes are carried across checkouts, as plain `git checkout` does.
+
+use crate::config::{AfkConfig, FeedbackLoopsConfig, GitConfig};
+use crate::git;
+use crate::prd::{PrdDocument, UserStory};
+
+use super::task_pr::{open_task_pr, pr_gates, run_gates};
+
+/// What happened when a task branch was left.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchOutcome {
+    /// Back on the base branch; the task branch is kept.
+    Returned(String),
+    /// The task branch was merged into the base and deleted.
+    Merged(String),
+    /// A pull request was opened from the task branch, with its URL.
+    PullRequest(String, String),
+    /// The task branch was left unmerged, with the reason.
+    Unmerged(String, String),
+}
+
+impl BranchOutcome {
+    /// Message for the loop's output, and whether it is a warning.
+    pub fn message(&self, base: &str) -> (String, bool) {
+        match self {
+            Self::Returned(branch) => {
+                (format!("Task work kept on {branch}; back on {base}"), false)
+            }
+            Self::Merged(branch) => (format!("Merged {branch} into {base}"), false),
+            Self::PullRequest(branch, url) => {
+                (format!("Opened pull request from {branch}: {url}"), false)
+            }
+            Self::Unmerged(branch, reason) => {
+                (format!("{branch} left unmerged for review: {reason}"), true)
+            }
+        }
+    }
+}
+
+/// Tracks the task branch the loop is on.
+#[derive(Debug)]
+pub struct TaskBranches {
+    git: GitConfig,
+    feedback_loops: FeedbackLoopsConfig,
+    base: String,
+    active: Option<(String, String)>,
+}
+
+impl TaskBranches {
+    /// Start tracking from the current branch, if `git.auto_branch` is on.
+    ///
+    /// Returns None when it's off, or outside a git repository or on a
+    /// detached HEAD, where there is no base branch to return to.
+    pub fn new(config: &AfkConfig) -> Option<Self> {
+        if !config.git.auto_branch || !git::is_git_repo() {
+            return None;
+        }
+        Some(Self {
+            git: config.git.clone(),
+            feedback_loops: config.feedback_loops.clone(),
+            base: git::get_current_branch()?,
+            active: None,
+        })
+    }
+
+    /// The branch tasks start from and return to.
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Switch to the task's branch, creating it from the base if needed.
+    ///
+    /// Returns a message when the branch changed. A task that didn't pass
+    /// keeps its branch; the next task still starts from the base.
+    pub fn enter(&mut self, task_id: &str) -> Result<Option<String>, String> {
+        let branch = branch_name(&self.git.branch_prefix, task_id);
+        if self.active.as_ref().is_some_and(|(_, b)| *b == branch) {
+            return Ok(None);
+        }
+        if self.active.take().is_some() && !git::checkout(&self.base) {
+            return Err(format!("could not return to {}", self.base));
+        }
+        if !git::create_branch(&branch) {
+            return Err(format!("could not check out {branch}"));
+        }
+        self.active = Some((task_id.to_string(), branch.clone()));
+        Ok(Some(format!("Working on {branch}")))
+    }
+
+    /// Leave the task branch if its task now passes.
+    pub fn finish_if_passed(&mut self, prd: &PrdDocument) -> Option<BranchOutcome> {
+        let (task_id, _) = self.active.as_ref()?;
+        let story = prd.get_story(task_id).filter(|s| s.passes)?;
+        let (_, branch) = self.active.take()?;
+        Some(self.finish(story, branch))
+    }
+
+    /// Leave the task branch at the end of the run, merging it if its
+    /// task passed.
+    pub fn close(&mut self, prd: &PrdDocument) -> Option<BranchOutcome> {
+        self.finish_if_passed(prd).or_else(|| {
+            let (_, branch) = self.active.take()?;
+            Some(if git::checkout(&self.base) {
+                BranchOutcome::Returned(branch)
+            } else {
+                BranchOutcome::Unmerged(branch, format!("could not return to {}", self.base))
+            })
+        })
+    }
+
+    fn finish(&self, story: &UserStory, branch: String) -> BranchOutcome {
+        let gates =
+            (self.git.auto_merge || self.git.open_pr).then(|| run_gates(&self.feedback_loops));
+        if !git::checkout(&self.base) {
+            return BranchOutcome::Unmerged(branch, format!("could not return to {}", self.base));
+        }
+        let Some(gates) = gates else {
+            return BranchOutcome::Returned(branch);
+        };
+        if self.git.open_pr {
+            let pr_gates = pr_gates(&gates);
+            return match open_task_pr(&self.git, story, &pr_gates, &branch, Some(&self.base)) {
+                Ok(url) => BranchOutcome::PullRequest(branch, url),
+                Err(e) => {
+                    BranchOutcome::Unmerged(branch, format!("could not open a pull request: {e}"))
+                }
+            };
+        }
+        if !gates.all_passed {
+            let failed: Vec<&str> = gates
+                .gates
+                .iter()
+                .filter(|g| !g.passed)
+                .map(|g| g.name.as_str())
+                .collect();
+            return BranchOutcome::Unmerged(
+                branch,
+                format!("gates failed ({})", failed.join(", ")),
+            );
+        }
+        let message = format!("Merge {branch}: {}", story.id);
+        if !git::merge_branch(&branch, &message) {
+            return BranchOutcome::Unmerged(branch, format!("conflicts with {}", self.base));
+        }
+        git::delete_merged_branch(&branch);
+        BranchOutcome::Merged(branch)
+    }
+}
+
+/// Branch name for a task: the prefix plus the task ID, with characters
+/// git doesn't allow in branch names replaced by `-`.
+pub fn branch_name(prefix: &str, task_id: &str) -> String {
+    let id: String = task_id
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let id = id.replace("..", "-");
+    format!(
+        "{prefix}{}",
+        id.trim_matches(|c| c == '.' || c == '/' || c == '-')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(branch_name("afk/", "task-001"), "afk/task-001");
+        assert_eq!(branch_name("afk/", "GH #12: fix"), "afk/GH--12--fix");
+        assert_eq!(branch_name("feature/", "a..b~1^"), "feature/a-b-1");
+        assert_eq!(branch_name("", "/lead/"), "lead");
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(TaskBranches::new(&AfkConfig::default()).is_none());
+    }
+
+    #[test]
+    fn test_outcome_messages() {
+        let merged = BranchOutcome::Merged("afk/a".to_string());
+        assert_eq!(
+            merged.message("main"),
+            ("Merged afk/a into main".to_string(), false)
+        );
+        let unmerged = BranchOutcome::Unmerged("afk/a".to_string(), "gates failed (lint)".into());
+        assert_eq!(
+            unmerged.message("main").0,
+            "afk/a left unmerged for review: gates failed (lint)"
+        );
+        assert!(unmerged.message("main").1);
+        let pr = BranchOutcome::PullRequest("afk/a".to_string(), "https://x/pull/1".into());
+        assert_eq!(
+            pr.message("main"),
+            (
+                "Opened pull request from afk/a: https://x/pull/1".to_string(),
+                false
+            )
+        );
+    }
+}
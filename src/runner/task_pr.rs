@@ -0,0 +1,143 @@
+//! Pull requests for completed tasks.
+//!
+//! With `git.open_pr`, each task the loop completes gets a pull request:
+//! from its task branch into the base when `git.auto_branch` is on (see
+//! [`TaskBranches`](super::TaskBranches)), otherwise from the branch the
+//! run is on into the repository's default branch. The PR URL is recorded
+//! on the task in `.afk/progress.json`.
+
+use crate::config::{AfkConfig, FeedbackLoopsConfig, GitConfig};
+use crate::git;
+use crate::git::pr::{PrGate, PullRequest};
+use crate::prd::{PrdDocument, UserStory};
+use crate::progress::SessionProgress;
+
+use super::hooks::newly_completed;
+use super::{run_quality_gates_quiet, GateCache, QualityGateResult};
+
+/// Run the quality gates quietly, reusing and updating the gate cache.
+pub fn run_gates(feedback_loops: &FeedbackLoopsConfig) -> QualityGateResult {
+    let mut cache = GateCache::open();
+    let gates = run_quality_gates_quiet(feedback_loops, cache.as_mut());
+    if let Some(cache) = cache {
+        let _ = cache.save();
+    }
+    gates
+}
+
+/// Gate results as shown in a pull request body.
+pub fn pr_gates(result: &QualityGateResult) -> Vec<PrGate> {
+    result
+        .gates
+        .iter()
+        .map(|gate| PrGate {
+            name: gate.name.clone(),
+            passed: gate.passed,
+        })
+        .collect()
+}
+
+/// Open a pull request for a completed task from `head` and record its URL.
+pub fn open_task_pr(
+    config: &GitConfig,
+    story: &UserStory,
+    gates: &[PrGate],
+    head: &str,
+    base: Option<&str>,
+) -> Result<String, String> {
+    let pr = PullRequest::render(config, story, gates, head, base)
+        .map_err(|e| format!("invalid PR template: {e}"))?;
+    let url = git::pr::open(head, base, &pr)?;
+    let mut progress = SessionProgress::load(None).unwrap_or_default();
+    progress.set_pr_url(&story.id, &url, &story.source);
+    let _ = progress.save(None);
+    Ok(url)
+}
+
+/// Open pull requests from the current branch for tasks completed between
+/// two loads of the backlog, skipping tasks that already have one.
+///
+/// Used when `git.auto_branch` is off. Returns messages for the loop's
+/// output, each with whether it is a warning.
+pub fn open_completed_prs(
+    config: &AfkConfig,
+    old: &PrdDocument,
+    new: &PrdDocument,
+) -> Vec<(String, bool)> {
+    if !config.git.open_pr {
+        return Vec::new();
+    }
+    let progress = SessionProgress::load(None).unwrap_or_default();
+    let stories: Vec<&UserStory> = newly_completed(old, new)
+        .into_iter()
+        .filter(|s| {
+            progress
+                .get_task(&s.id)
+                .and_then(|t| t.pr_url.as_ref())
+                .is_none()
+        })
+        .collect();
+    if stories.is_empty() {
+        return Vec::new();
+    }
+    let Some(head) = git::get_current_branch() else {
+        return vec![(
+            "Can't open pull requests from a detached HEAD".to_string(),
+            true,
+        )];
+    };
+    let gates = pr_gates(&run_gates(&config.feedback_loops));
+    stories
+        .into_iter()
+        .map(
+            |story| match open_task_pr(&config.git, story, &gates, &head, None) {
+                Ok(url) => (
+                    format!("Opened pull request for {}: {url}", story.id),
+                    false,
+                ),
+                Err(e) => (
+                    format!("Could not open a pull request for {}: {e}", story.id),
+                    true,
+                ),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::GateResult;
+
+    #[test]
+    fn test_pr_gates() {
+        let result = QualityGateResult {
+            all_passed: false,
+            gates: vec![GateResult {
+                name: "lint".to_string(),
+                passed: false,
+                output: "warning".to_string(),
+                duration_seconds: 1.5,
+            }],
+            failed_gates: vec!["lint".to_string()],
+            cached_gates: Vec::new(),
+        };
+        assert_eq!(
+            pr_gates(&result),
+            vec![PrGate {
+                name: "lint".to_string(),
+                passed: false
+            }]
+        );
+    }
+
+    #[test]
+    fn test_open_completed_prs_disabled() {
+        let old = PrdDocument::default();
+        let mut new = PrdDocument::default();
+        let mut story = UserStory::new("a", "A");
+        story.passes = true;
+        new.user_stories.push(story);
+        assert!(open_completed_prs(&AfkConfig::default(), &old, &new).is_empty());
+    }
+}
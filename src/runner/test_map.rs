@@ -0,0 +1,369 @@
+//! Changed-file test mapping.
+//!
+//! Maps the files an iteration changed to the narrow test commands that
+//! cover them, so `afk verify --changed-only` (and, with
+//! `feedback_loops.changed_only`, the gate run inside the loop) runs those
+//! instead of the whole suite. `feedback_loops.test_map` rules are tried
+//! first, then built-in heuristics for the toolchain the `test` gate uses
+//! (cargo, pytest, go test, jest). If any changed source file can't be
+//! mapped, the full test command runs.
+
+use std::path::Path;
+
+use crate::config::{FeedbackLoopsConfig, TestMapping};
+use crate::git;
+use crate::path_matcher::glob_matches;
+
+use super::IterationCheckpoint;
+
+/// Extensions of files that never need tests run.
+const NO_TEST_EXTENSIONS: &[&str] = &[
+    "md", "markdown", "txt", "rst", "adoc", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp",
+];
+
+/// Names of files that never need tests run.
+const NO_TEST_FILES: &[&str] = &[".gitignore", ".gitattributes", ".editorconfig"];
+
+/// How the test gate should run for a set of changed files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestPlan {
+    /// Files the plan was built from.
+    pub changed: usize,
+    /// Narrow commands covering the changes, in order, without duplicates.
+    pub commands: Vec<String>,
+    /// Changed files no rule or heuristic could map.
+    pub unmapped: Vec<String>,
+}
+
+impl TestPlan {
+    /// Map changed files (relative to the repository root) to tests.
+    pub fn build(changed: &[String], feedback_loops: &FeedbackLoopsConfig) -> Self {
+        Self::build_with(changed, feedback_loops, |path| Path::new(path).exists())
+    }
+
+    fn build_with(
+        changed: &[String],
+        feedback_loops: &FeedbackLoopsConfig,
+        exists: impl Fn(&str) -> bool,
+    ) -> Self {
+        let mut plan = Self {
+            changed: changed.len(),
+            ..Default::default()
+        };
+        let Some(test) = feedback_loops.test.as_deref() else {
+            return plan;
+        };
+        for path in changed {
+            let mapped = match rule_for(&feedback_loops.test_map, path) {
+                Some(rule) => Some((!rule.command.is_empty()).then(|| fill(&rule.command, path))),
+                None if needs_no_tests(path) => Some(None),
+                None => heuristic(test, path, &exists).map(Some),
+            };
+            match mapped {
+                Some(Some(command)) if !plan.commands.contains(&command) => {
+                    plan.commands.push(command)
+                }
+                Some(_) => {}
+                None => plan.unmapped.push(path.clone()),
+            }
+        }
+        plan
+    }
+
+    /// The feedback loops with the test gate narrowed to this plan.
+    ///
+    /// Unmapped files keep the full test command; changes that need no
+    /// tests drop the test gate.
+    pub fn apply(&self, feedback_loops: &FeedbackLoopsConfig) -> FeedbackLoopsConfig {
+        let mut narrowed = feedback_loops.clone();
+        if narrowed.test.is_some() && self.unmapped.is_empty() {
+            narrowed.test = (!self.commands.is_empty()).then(|| self.commands.join(" && "));
+        }
+        narrowed
+    }
+
+    /// One line describing what the test gate will run.
+    pub fn summary(&self) -> String {
+        let files = match self.changed {
+            1 => "1 changed file".to_string(),
+            n => format!("{n} changed files"),
+        };
+        if !self.unmapped.is_empty() {
+            let shown: Vec<&str> = self.unmapped.iter().take(3).map(String::as_str).collect();
+            let more = self.unmapped.len().saturating_sub(shown.len());
+            let more = if more > 0 {
+                format!(" and {more} more")
+            } else {
+                String::new()
+            };
+            return format!(
+                "{files}; no test mapping for {}{more}, running the full test suite",
+                shown.join(", ")
+            );
+        }
+        match self.commands.len() {
+            0 => format!("{files}; none needs tests, skipping the test gate"),
+            1 => format!("{files}; test gate runs {}", self.commands[0]),
+            n => format!("{files}; test gate runs {n} narrow commands"),
+        }
+    }
+}
+
+/// Files changed in the current iteration, or uncommitted changes when no
+/// iteration is running.
+pub fn changed_files() -> Vec<String> {
+    let baseline = IterationCheckpoint::load(None).and_then(|c| c.baseline);
+    git::changed_files_since(baseline.as_deref().unwrap_or("HEAD"))
+        .into_iter()
+        .filter(|path| !path.starts_with(".afk/"))
+        .collect()
+}
+
+/// Whether an iteration is running, so `changed_only` applies.
+pub fn in_iteration() -> bool {
+    IterationCheckpoint::load(None).is_some()
+}
+
+/// The first rule whose pattern matches a path.
+fn rule_for<'a>(rules: &'a [TestMapping], path: &str) -> Option<&'a TestMapping> {
+    rules.iter().find(|rule| glob_matches(&rule.pattern, path))
+}
+
+/// Documentation, images and git metadata.
+fn needs_no_tests(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    let ext = name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+    ext.is_some_and(|e| NO_TEST_EXTENSIONS.contains(&e.as_str()))
+        || NO_TEST_FILES.contains(&name)
+        || name.starts_with("LICENSE")
+        || path.starts_with("docs/")
+}
+
+/// Fill a rule command's placeholders for a path.
+fn fill(command: &str, path: &str) -> String {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    command
+        .replace("{path}", path)
+        .replace("{dir}", if dir.is_empty() { "." } else { dir })
+        .replace("{stem}", stem)
+        .replace("{module}", &rust_module(path).unwrap_or_default())
+}
+
+/// Built-in mapping for the toolchain the test command uses.
+fn heuristic(test: &str, path: &str, exists: &impl Fn(&str) -> bool) -> Option<String> {
+    let test = test.trim();
+    if test.starts_with("cargo test") && !test.contains(" -- ") {
+        cargo_target(path).map(|target| format!("{test} {target}"))
+    } else if test.contains("pytest") {
+        pytest_target(path, exists).map(|target| format!("{test} {target}"))
+    } else if test.starts_with("go test") {
+        let dir = path
+            .strip_suffix(".go")?
+            .rsplit_once('/')
+            .map_or("", |(d, _)| d);
+        let base = test.trim_end_matches("./...").trim_end();
+        Some(if dir.is_empty() {
+            format!("{base} .")
+        } else {
+            format!("{base} ./{dir}")
+        })
+    } else if test.contains("jest") {
+        let ext = path.rsplit_once('.')?.1;
+        ["js", "jsx", "ts", "tsx", "mjs", "cjs"]
+            .contains(&ext)
+            .then(|| format!("{test} --findRelatedTests {path}"))
+    } else {
+        None
+    }
+}
+
+/// `cargo test` arguments selecting the tests for a Rust file.
+///
+/// `src/a/b.rs` filters on the `a::b` module, `tests/x.rs` selects the
+/// `x` test target, and files in a workspace member's directory add
+/// `-p <member>`. Crate roots and build scripts can't be narrowed.
+fn cargo_target(path: &str) -> Option<String> {
+    let (member, rest) = split_member(path);
+    let package = member
+        .and_then(|m| m.rsplit('/').next())
+        .map(|name| format!("-p {name} "))
+        .unwrap_or_default();
+    if let Some(test) = rest.strip_prefix("tests/") {
+        let name = test.strip_suffix(".rs")?;
+        return (!name.contains('/')).then(|| format!("{package}--test {name}"));
+    }
+    rust_module(rest).map(|module| format!("{package}{module}"))
+}
+
+/// Split a path into its workspace member directory (if any) and the path
+/// within the member, at the first `src/` or `tests/` component.
+fn split_member(path: &str) -> (Option<&str>, &str) {
+    for marker in ["/src/", "/tests/"] {
+        if let Some(i) = path.find(marker) {
+            return (Some(&path[..i]), &path[i + 1..]);
+        }
+    }
+    (None, path)
+}
+
+/// Rust module path for a file under `src/`.
+fn rust_module(path: &str) -> Option<String> {
+    let rest = split_member(path)
+        .1
+        .strip_prefix("src/")?
+        .strip_suffix(".rs")?;
+    let rest = rest.strip_suffix("/mod").unwrap_or(rest);
+    if matches!(rest, "lib" | "main") || rest.starts_with("bin/") {
+        return None;
+    }
+    Some(rest.replace('/', "::"))
+}
+
+/// The pytest file covering a Python file: itself if it's a test, or an
+/// existing `test_<name>.py` next to it or under `tests/`.
+fn pytest_target(path: &str, exists: &impl Fn(&str) -> bool) -> Option<String> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let stem = name.strip_suffix(".py")?;
+    if stem.starts_with("test_") || stem.ends_with("_test") || dir.split('/').any(|d| d == "tests")
+    {
+        return Some(path.to_string());
+    }
+    let join = |d: &str| {
+        if d.is_empty() {
+            format!("test_{stem}.py")
+        } else {
+            format!("{d}/test_{stem}.py")
+        }
+    };
+    // src/pkg/mod.py is tested by tests/pkg/test_mod.py or tests/test_mod.py
+    let inner = dir
+        .strip_prefix("src/")
+        .or_else(|| dir.split_once('/').map(|(_, d)| d))
+        .unwrap_or("");
+    let tests_dir = if inner.is_empty() {
+        "tests".to_string()
+    } else {
+        format!("tests/{inner}")
+    };
+    [join(dir), join(&tests_dir), join("tests")]
+        .into_iter()
+        .find(|candidate| exists(candidate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loops(test: &str, test_map: Vec<TestMapping>) -> FeedbackLoopsConfig {
+        FeedbackLoopsConfig {
+            lint: Some("cargo clippy".to_string()),
+            test: Some(test.to_string()),
+            test_map,
+            ..Default::default()
+        }
+    }
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cargo_heuristics() {
+        assert_eq!(
+            cargo_target("src/sources/github.rs").as_deref(),
+            Some("sources::github")
+        );
+        assert_eq!(cargo_target("src/runner/mod.rs").as_deref(), Some("runner"));
+        assert_eq!(
+            cargo_target("tests/cli_integration.rs").as_deref(),
+            Some("--test cli_integration")
+        );
+        assert_eq!(
+            cargo_target("crates/core/src/parse/lexer.rs").as_deref(),
+            Some("-p core parse::lexer")
+        );
+        assert_eq!(cargo_target("src/lib.rs"), None);
+        assert_eq!(cargo_target("build.rs"), None);
+    }
+
+    #[test]
+    fn test_plan_narrows_the_test_gate() {
+        let changed = files(&["src/sources/github.rs", "README.md", "src/git/mod.rs"]);
+        let plan = TestPlan::build(&changed, &loops("cargo test", Vec::new()));
+        assert_eq!(
+            plan.commands,
+            ["cargo test sources::github", "cargo test git"]
+        );
+        assert!(plan.unmapped.is_empty());
+
+        let narrowed = plan.apply(&loops("cargo test", Vec::new()));
+        assert_eq!(
+            narrowed.test.as_deref(),
+            Some("cargo test sources::github && cargo test git")
+        );
+        assert_eq!(narrowed.lint.as_deref(), Some("cargo clippy"));
+        assert!(plan.summary().contains("3 changed files"));
+    }
+
+    #[test]
+    fn test_plan_falls_back_or_skips() {
+        let config = loops("cargo test", Vec::new());
+        let plan = TestPlan::build(&files(&["src/lib.rs", "src/a.rs"]), &config);
+        assert_eq!(plan.unmapped, ["src/lib.rs"]);
+        assert_eq!(plan.apply(&config).test.as_deref(), Some("cargo test"));
+        assert!(plan.summary().contains("no test mapping for src/lib.rs"));
+
+        let plan = TestPlan::build(&files(&["docs/guide.md", "logo.png"]), &config);
+        assert_eq!(plan.apply(&config).test, None);
+        assert!(plan.summary().contains("skipping the test gate"));
+    }
+
+    #[test]
+    fn test_rules_take_precedence() {
+        let config = loops(
+            "cargo test",
+            vec![
+                TestMapping {
+                    pattern: "src/api/**".to_string(),
+                    command: "cargo test --test api -- {stem}".to_string(),
+                },
+                TestMapping {
+                    pattern: "*.sql".to_string(),
+                    command: String::new(),
+                },
+            ],
+        );
+        let changed = files(&["src/api/users.rs", "db/schema.sql"]);
+        let plan = TestPlan::build(&changed, &config);
+        assert_eq!(plan.commands, ["cargo test --test api -- users"]);
+        assert!(plan.unmapped.is_empty());
+        assert_eq!(
+            fill("x {module} {dir} {path}", "src/a/b.rs"),
+            "x a::b src/a src/a/b.rs"
+        );
+    }
+
+    #[test]
+    fn test_other_toolchains() {
+        let exists = |p: &str| p == "tests/pkg/test_util.py";
+        assert_eq!(
+            heuristic("pytest -q", "src/pkg/util.py", &exists).as_deref(),
+            Some("pytest -q tests/pkg/test_util.py")
+        );
+        assert_eq!(
+            heuristic("pytest", "tests/test_api.py", &exists).as_deref(),
+            Some("pytest tests/test_api.py")
+        );
+        assert_eq!(heuristic("pytest", "src/pkg/other.py", &exists), None);
+        assert_eq!(
+            heuristic("go test ./...", "internal/store/db.go", &exists).as_deref(),
+            Some("go test ./internal/store")
+        );
+        assert_eq!(
+            heuristic("npx jest", "src/app.tsx", &exists).as_deref(),
+            Some("npx jest --findRelatedTests src/app.tsx")
+        );
+        assert_eq!(heuristic("make test", "src/app.c", &exists), None);
+    }
+}
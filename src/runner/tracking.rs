@@ -0,0 +1,305 @@
+//! Rolling run summary on a tracking issue or PR.
+//!
+//! With `reporting.tracking_issue` set, the loop keeps one comment on that
+//! GitHub issue or PR up to date with how the run is going (tasks done,
+//! the current task, iterations, commits, and cost), so people can follow
+//! along without access to the terminal. The comment is found again by a
+//! hidden marker, so later runs update it rather than adding new ones.
+//! Posting goes through the gh CLI; failures never interrupt the loop.
+
+use std::path::Path;
+use std::process::Command;
+
+use chrono::Local;
+
+use crate::config::{ReportingConfig, EVENTS_FILE};
+use crate::events::{run_summary, RunSummary};
+use crate::prd::PrdDocument;
+use crate::progress::burndown;
+
+/// Burn-down points drawn in the summary.
+const BURNDOWN_WIDTH: usize = 24;
+
+/// Hidden marker identifying afk's summary comment.
+const MARKER: &str = "<!-- afk-run-summary -->";
+
+/// An issue or PR to report to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingTarget {
+    /// Repository as "owner/repo"; None means the current repository.
+    pub repo: Option<String>,
+    /// Issue or PR number.
+    pub number: u64,
+}
+
+impl TrackingTarget {
+    /// Parse a number ("42", "#42"), "owner/repo#42", or a GitHub issue or
+    /// pull request URL.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if let Some(rest) = value
+            .strip_prefix("https://github.com/")
+            .or_else(|| value.strip_prefix("http://github.com/"))
+        {
+            let parts: Vec<&str> = rest.split('/').collect();
+            return match parts.as_slice() {
+                [owner, repo, "issues" | "pull", number, ..] => Some(Self {
+                    repo: Some(format!("{owner}/{repo}")),
+                    number: number.parse().ok()?,
+                }),
+                _ => None,
+            };
+        }
+        let (repo, number) = match value.rsplit_once('#') {
+            Some((repo, number)) if !repo.is_empty() => {
+                let (owner, name) = repo.split_once('/')?;
+                if owner.is_empty() || name.is_empty() {
+                    return None;
+                }
+                (Some(repo.to_string()), number)
+            }
+            Some((_, number)) => (None, number),
+            None => (None, value),
+        };
+        Some(Self {
+            repo,
+            number: number.parse().ok().filter(|n| *n > 0)?,
+        })
+    }
+
+    /// Path of the issue's comments endpoint for `gh api`.
+    fn comments_path(&self) -> String {
+        format!("repos/{}/issues/{}/comments", self.repo_path(), self.number)
+    }
+
+    /// Repository part of an API path; gh fills in the placeholders from
+    /// the current repository.
+    fn repo_path(&self) -> &str {
+        self.repo.as_deref().unwrap_or("{owner}/{repo}")
+    }
+}
+
+/// Keeps the summary comment on a tracking issue up to date.
+#[derive(Debug)]
+pub struct TrackingReporter {
+    target: TrackingTarget,
+    every: u32,
+    comment_id: Option<u64>,
+}
+
+impl TrackingReporter {
+    /// Create a reporter from config. Returns None if no tracking issue is
+    /// set, or it can't be parsed (with a warning).
+    pub fn new(config: &ReportingConfig) -> Option<Self> {
+        let value = config.tracking_issue.as_deref()?;
+        let Some(target) = TrackingTarget::parse(value) else {
+            eprintln!(
+                "\x1b[33mWarning:\x1b[0m reporting.tracking_issue '{value}' is not an issue \
+                 number, owner/repo#number, or GitHub URL; not reporting."
+            );
+            return None;
+        };
+        Some(Self {
+            target,
+            every: config.every.max(1),
+            comment_id: None,
+        })
+    }
+
+    /// Update the comment if this iteration is due for a report.
+    pub fn after_iteration(&mut self, iteration: u32) {
+        if iteration % self.every == 0 {
+            self.update();
+        }
+    }
+
+    /// Post the final summary when the loop stops.
+    pub fn finish(&mut self) {
+        self.update();
+    }
+
+    /// Post or edit the summary comment. Does nothing in read-only mode.
+    fn update(&mut self) {
+        if crate::config::is_read_only() {
+            return;
+        }
+        let prd = PrdDocument::load(None).unwrap_or_default();
+        let (done, total) = prd.get_story_counts();
+        let now = Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let burndown = burndown::describe(&burndown::load(None), BURNDOWN_WIDTH);
+        let body = summary_body(
+            &run_summary(Path::new(EVENTS_FILE)),
+            done,
+            total,
+            burndown.as_deref(),
+            &now,
+        );
+
+        if self.comment_id.is_none() {
+            self.comment_id = self.find_comment();
+        }
+        let posted = match self.comment_id {
+            Some(id) => gh_api(&[
+                "-X",
+                "PATCH",
+                &format!("repos/{}/issues/comments/{id}", self.target.repo_path()),
+                "-f",
+                &format!("body={body}"),
+                "--jq",
+                ".id",
+            ]),
+            None => gh_api(&[
+                &self.target.comments_path(),
+                "-f",
+                &format!("body={body}"),
+                "--jq",
+                ".id",
+            ]),
+        };
+        if let Some(id) = posted.and_then(|out| out.trim().parse().ok()) {
+            self.comment_id = Some(id);
+        }
+    }
+
+    /// ID of an existing summary comment on the issue, if any.
+    fn find_comment(&self) -> Option<u64> {
+        let query = format!(".[] | select(.body | contains(\"{MARKER}\")) | .id");
+        gh_api(&[&self.target.comments_path(), "--paginate", "--jq", &query])?
+            .lines()
+            .find_map(|line| line.trim().parse().ok())
+    }
+}
+
+/// Run `gh api` and return its stdout on success.
+fn gh_api(args: &[&str]) -> Option<String> {
+    let output = Command::new("gh").arg("api").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Markdown body of the summary comment.
+fn summary_body(
+    summary: &RunSummary,
+    done: usize,
+    total: usize,
+    burndown: Option<&str>,
+    updated: &str,
+) -> String {
+    let status = match &summary.ended {
+        Some(reason) => format!("Stopped: {reason}"),
+        None => "Running".to_string(),
+    };
+    let current = match (&summary.current_task, &summary.ended) {
+        (Some((id, title)), None) => format!("`{id}` {title}"),
+        _ => "-".to_string(),
+    };
+    let cost = summary
+        .cost_usd
+        .map_or_else(|| "-".to_string(), |c| format!("${c:.2}"));
+
+    let mut body = format!("{MARKER}\n### afk run summary\n\n");
+    body.push_str("| | |\n|---|---|\n");
+    body.push_str(&format!("| Status | {status} |\n"));
+    body.push_str(&format!("| Tasks done | {done} / {total} |\n"));
+    if let Some(burndown) = burndown {
+        body.push_str(&format!("| Burn-down | {burndown} |\n"));
+    }
+    body.push_str(&format!("| Current task | {current} |\n"));
+    body.push_str(&format!("| Iterations | {} |\n", summary.iterations));
+    body.push_str(&format!("| Commits | {} |\n", summary.commits));
+    body.push_str(&format!(
+        "| Tokens | {} in / {} out |\n",
+        summary.input_tokens, summary.output_tokens
+    ));
+    body.push_str(&format!("| Cost | {cost} |\n"));
+    body.push_str(&format!("\n_Updated {updated}_\n"));
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        let local = |number| TrackingTarget { repo: None, number };
+        let remote = |number| TrackingTarget {
+            repo: Some("acme/shop".to_string()),
+            number,
+        };
+        assert_eq!(TrackingTarget::parse("42"), Some(local(42)));
+        assert_eq!(TrackingTarget::parse("#42"), Some(local(42)));
+        assert_eq!(TrackingTarget::parse("acme/shop#7"), Some(remote(7)));
+        assert_eq!(
+            TrackingTarget::parse("https://github.com/acme/shop/pull/7/files"),
+            Some(remote(7))
+        );
+        assert_eq!(
+            TrackingTarget::parse("https://github.com/acme/shop/issues/7"),
+            Some(remote(7))
+        );
+        assert_eq!(TrackingTarget::parse("shop#7"), None);
+        assert_eq!(TrackingTarget::parse("0"), None);
+        assert_eq!(TrackingTarget::parse("soon"), None);
+    }
+
+    #[test]
+    fn test_comments_path() {
+        let target = TrackingTarget::parse("9").unwrap();
+        assert_eq!(
+            target.comments_path(),
+            "repos/{owner}/{repo}/issues/9/comments"
+        );
+    }
+
+    #[test]
+    fn test_reporter_needs_valid_target() {
+        assert!(TrackingReporter::new(&ReportingConfig::default()).is_none());
+        let config = ReportingConfig {
+            tracking_issue: Some("not an issue".to_string()),
+            every: 5,
+            ..Default::default()
+        };
+        assert!(TrackingReporter::new(&config).is_none());
+    }
+
+    #[test]
+    fn test_summary_body() {
+        let summary = RunSummary {
+            iterations: 4,
+            commits: 3,
+            current_task: Some(("auth-1".to_string(), "Login form".to_string())),
+            input_tokens: 1200,
+            output_tokens: 300,
+            cost_usd: Some(0.456),
+            ended: None,
+        };
+        let body = summary_body(
+            &summary,
+            2,
+            5,
+            Some("█▄▁ 5 → 3 remaining (converging)"),
+            "2024-05-01 12:00",
+        );
+        assert!(body.starts_with(MARKER));
+        assert!(body.contains("| Status | Running |"));
+        assert!(body.contains("| Tasks done | 2 / 5 |"));
+        assert!(body.contains("| Burn-down | █▄▁ 5 → 3 remaining (converging) |"));
+        assert!(body.contains("| Current task | `auth-1` Login form |"));
+        assert!(body.contains("| Cost | $0.46 |"));
+        assert!(body.contains("_Updated 2024-05-01 12:00_"));
+
+        let ended = RunSummary {
+            ended: Some("All tasks complete".to_string()),
+            cost_usd: None,
+            ..summary
+        };
+        let body = summary_body(&ended, 5, 5, None, "now");
+        assert!(body.contains("| Status | Stopped: All tasks complete |"));
+        assert!(body.contains("| Current task | - |"));
+        assert!(body.contains("| Cost | - |"));
+        assert!(!body.contains("Burn-down"));
+    }
+}
@@ -0,0 +1,52 @@
+//! Warm-up iteration.
+//!
+//! With `runner.warmup` enabled, the first run of a session spends one
+//! read-only iteration having the AI read the repo and summarise its
+//! conventions and a plan. The summary is saved to `.afk/warmup.md` and
+//! prepended to every later prompt.
+
+use std::fs;
+use std::path::Path;
+
+use crate::config::{AfkConfig, WARMUP_FILE};
+use crate::prd::PrdDocument;
+use crate::prompt::generate_warmup_prompt;
+
+use super::oneshot::ask_ai;
+
+/// Whether a warm-up iteration should run before the first task.
+pub fn warmup_needed(config: &AfkConfig) -> bool {
+    config.runner.warmup && !Path::new(WARMUP_FILE).exists()
+}
+
+/// Run the warm-up iteration and save its summary.
+///
+/// Returns the summary on success. Errors are returned as messages so the
+/// caller can warn and carry on with the normal loop.
+pub fn run_warmup(config: &AfkConfig) -> Result<String, String> {
+    let prd = PrdDocument::load(None).map_err(|e| e.to_string())?;
+    let prompt = generate_warmup_prompt(config, &prd).map_err(|e| e.to_string())?;
+
+    let summary = ask_ai(config, &prompt)?;
+    if summary.is_empty() {
+        return Err("AI CLI produced no summary".to_string());
+    }
+
+    if !crate::config::is_read_only() {
+        if let Some(parent) = Path::new(WARMUP_FILE).parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(WARMUP_FILE, format!("{summary}\n")).map_err(|e| e.to_string())?;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_not_needed_when_disabled() {
+        assert!(!warmup_needed(&AfkConfig::default()));
+    }
+}
@@ -0,0 +1,216 @@
+//! Sandboxed quality gates run as WASI modules.
+//!
+//! A WASM gate is a `.wasm` module run in an embedded runtime instead of a
+//! shell. It only sees the directories listed in its config, which makes it
+//! a safer choice than arbitrary shell strings for shared configs. A module
+//! still running after `timeout_secs` is interrupted and the gate fails. The
+//! runtime is compiled in with the `wasm-gates` feature.
+
+use crate::config::WasmGateConfig;
+
+use super::quality_gates::GateResult;
+
+/// Maximum bytes of stdout/stderr captured from a module.
+#[cfg_attr(not(feature = "wasm-gates"), allow(dead_code))]
+const OUTPUT_CAPACITY: usize = 1024 * 1024;
+
+/// Run a WASM gate and report whether it exited successfully.
+pub fn run_wasm_gate(name: &str, gate: &WasmGateConfig) -> GateResult {
+    let start = std::time::Instant::now();
+    let (passed, output) = match execute(name, gate) {
+        Ok(result) => result,
+        Err(message) => (false, message),
+    };
+
+    GateResult {
+        name: name.to_string(),
+        passed,
+        output,
+        duration_seconds: start.elapsed().as_secs_f64(),
+    }
+}
+
+/// Run the module, returning (passed, output) or a setup error.
+#[cfg(feature = "wasm-gates")]
+fn execute(name: &str, gate: &WasmGateConfig) -> Result<(bool, String), String> {
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use wasmtime::{Config, Engine, Linker, Module, Store, Trap};
+    use wasmtime_wasi::pipe::MemoryOutputPipe;
+    use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+    use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+    let mut config = Config::new();
+    config.epoch_interruption(true);
+    let engine = Engine::new(&config).map_err(|e| e.to_string())?;
+    let module = Module::from_file(&engine, &gate.module)
+        .map_err(|e| format!("Failed to load module {}: {e}", gate.module))?;
+
+    let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+    preview1::add_to_linker_sync(&mut linker, |ctx| ctx).map_err(|e| e.to_string())?;
+
+    let stdout = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+    let stderr = MemoryOutputPipe::new(OUTPUT_CAPACITY);
+
+    let mut builder = WasiCtxBuilder::new();
+    let mut args = vec![name.to_string()];
+    args.extend(gate.args.iter().cloned());
+    builder
+        .args(&args)
+        .stdout(stdout.clone())
+        .stderr(stderr.clone());
+
+    for dir in &gate.dirs {
+        builder
+            .preopened_dir(dir, dir, DirPerms::READ, FilePerms::READ)
+            .map_err(|e| format!("Failed to grant {dir}: {e}"))?;
+    }
+    for dir in &gate.write_dirs {
+        builder
+            .preopened_dir(dir, dir, DirPerms::all(), FilePerms::all())
+            .map_err(|e| format!("Failed to grant {dir}: {e}"))?;
+    }
+
+    let mut store = Store::new(&engine, builder.build_p1());
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| e.to_string())?;
+    let entry = instance
+        .get_typed_func::<(), ()>(&mut store, "_start")
+        .map_err(|e| e.to_string())?;
+
+    // Bump the epoch once the time is up, which traps the running module;
+    // dropping `done` lets the watchdog exit early
+    store.set_epoch_deadline(1);
+    let (done, finished) = mpsc::channel::<()>();
+    if gate.timeout_secs > 0 {
+        let timeout = Duration::from_secs(gate.timeout_secs);
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            if finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                engine.increment_epoch();
+            }
+        });
+    }
+    let call = entry.call(&mut store, ());
+    drop(done);
+
+    let (passed, error) = match call {
+        Ok(()) => (true, None),
+        Err(e) => match e.downcast_ref::<I32Exit>() {
+            Some(exit) => (exit.0 == 0, None),
+            None if e.downcast_ref::<Trap>() == Some(&Trap::Interrupt) => (
+                false,
+                Some(format!("Timed out after {}s", gate.timeout_secs)),
+            ),
+            None => (false, Some(e.to_string())),
+        },
+    };
+
+    let mut output = String::from_utf8_lossy(&stdout.contents()).into_owned();
+    output.push_str(&String::from_utf8_lossy(&stderr.contents()));
+    if let Some(error) = error {
+        output.push_str(&error);
+        output.push('\n');
+    }
+
+    Ok((passed, output))
+}
+
+/// Without the runtime, WASM gates always fail with an explanation.
+#[cfg(not(feature = "wasm-gates"))]
+fn execute(_name: &str, _gate: &WasmGateConfig) -> Result<(bool, String), String> {
+    Err("afk was built without WASM gate support (rebuild with --features wasm-gates)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_module_fails() {
+        let gate = WasmGateConfig {
+            module: "/nonexistent/gate.wasm".to_string(),
+            ..Default::default()
+        };
+        let result = run_wasm_gate("licence", &gate);
+        assert_eq!(result.name, "licence");
+        assert!(!result.passed);
+        assert!(!result.output.is_empty());
+    }
+
+    /// A minimal WASI module whose `_start` calls `proc_exit(code)`.
+    #[cfg(feature = "wasm-gates")]
+    fn exit_module(code: u8) -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // Types: (i32) -> () and () -> ()
+        wasm.extend([0x01, 0x08, 0x02, 0x60, 0x01, 0x7f, 0x00, 0x60, 0x00, 0x00]);
+        // Import wasi_snapshot_preview1.proc_exit
+        wasm.extend([0x02, 0x24, 0x01, 0x16]);
+        wasm.extend(b"wasi_snapshot_preview1");
+        wasm.push(0x09);
+        wasm.extend(b"proc_exit");
+        wasm.extend([0x00, 0x00]);
+        // One function, one page of exported memory, and _start
+        wasm.extend([0x03, 0x02, 0x01, 0x01]);
+        wasm.extend([0x05, 0x03, 0x01, 0x00, 0x01]);
+        wasm.extend([0x07, 0x13, 0x02, 0x06]);
+        wasm.extend(b"memory");
+        wasm.extend([0x02, 0x00, 0x06]);
+        wasm.extend(b"_start");
+        wasm.extend([0x00, 0x01]);
+        // _start body: i32.const code; call proc_exit
+        wasm.extend([0x0a, 0x08, 0x01, 0x06, 0x00, 0x41, code, 0x10, 0x00, 0x0b]);
+        wasm
+    }
+
+    #[cfg(feature = "wasm-gates")]
+    #[test]
+    fn test_exit_code_decides_pass() {
+        let temp = tempfile::TempDir::new().unwrap();
+
+        for (code, expected) in [(0, true), (1, false)] {
+            let path = temp.path().join(format!("exit{code}.wasm"));
+            std::fs::write(&path, exit_module(code)).unwrap();
+            let gate = WasmGateConfig {
+                module: path.to_string_lossy().into_owned(),
+                ..Default::default()
+            };
+            assert_eq!(run_wasm_gate("exit", &gate).passed, expected);
+        }
+    }
+
+    /// A minimal WASI module whose `_start` loops forever.
+    #[cfg(feature = "wasm-gates")]
+    fn spin_module() -> Vec<u8> {
+        let mut wasm = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // Type () -> (), one function, and _start
+        wasm.extend([0x01, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        wasm.extend([0x03, 0x02, 0x01, 0x00]);
+        wasm.extend([0x07, 0x0a, 0x01, 0x06]);
+        wasm.extend(b"_start");
+        wasm.extend([0x00, 0x00]);
+        // _start body: loop br 0 end
+        wasm.extend([
+            0x0a, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b,
+        ]);
+        wasm
+    }
+
+    #[cfg(feature = "wasm-gates")]
+    #[test]
+    fn test_timeout_fails_gate() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("spin.wasm");
+        std::fs::write(&path, spin_module()).unwrap();
+        let gate = WasmGateConfig {
+            module: path.to_string_lossy().into_owned(),
+            timeout_secs: 1,
+            ..Default::default()
+        };
+
+        let result = run_wasm_gate("spin", &gate);
+        assert!(!result.passed);
+        assert!(result.output.contains("Timed out after 1s"));
+    }
+}
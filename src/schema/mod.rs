@@ -0,0 +1,318 @@
+//! JSON Schemas for afk's state files.
+//!
+//! The schemas for `.afk/tasks.json` and `.afk/progress.json` are embedded
+//! in the binary (`afk schema tasks` prints one) so tools that generate afk
+//! backlogs can check their output against the same definition afk loads
+//! with. Validation covers the subset of JSON Schema the embedded schemas
+//! use: `type`, `properties`, `required`, `additionalProperties`, `items`,
+//! `enum`, `oneOf`, `minimum`, `minLength`, and local `$ref`s.
+
+use std::fmt;
+
+use serde_json::Value;
+
+/// Schema for `.afk/tasks.json`.
+pub const TASKS_SCHEMA: &str = include_str!("tasks.schema.json");
+
+/// Schema for `.afk/progress.json`.
+pub const PROGRESS_SCHEMA: &str = include_str!("progress.schema.json");
+
+/// A file afk publishes a schema for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaKind {
+    /// The task backlog (`.afk/tasks.json`).
+    Tasks,
+    /// Session progress (`.afk/progress.json`).
+    Progress,
+}
+
+impl SchemaKind {
+    /// The schema document, as published.
+    pub fn text(self) -> &'static str {
+        match self {
+            Self::Tasks => TASKS_SCHEMA,
+            Self::Progress => PROGRESS_SCHEMA,
+        }
+    }
+
+    /// The schema document, parsed.
+    pub fn schema(self) -> Value {
+        serde_json::from_str(self.text()).expect("embedded schema is valid JSON")
+    }
+
+    /// Check a document against the schema.
+    pub fn validate(self, document: &Value) -> Vec<SchemaViolation> {
+        validate(&self.schema(), document)
+    }
+}
+
+/// One way a document breaks its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Where in the document, as a JSON path (`$.userStories[2].id`).
+    pub path: String,
+    /// What is wrong there.
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Check a document against a schema, returning every violation found.
+pub fn validate(schema: &Value, document: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    check(schema, schema, document, "$", &mut violations);
+    violations
+}
+
+/// Join violations into one line per problem, for error messages.
+pub fn describe(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn check(root: &Value, schema: &Value, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let schema = resolve(root, schema);
+    let mut fail = |message: String| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            fail(format!(
+                "expected {}, found {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let names: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            fail(format!(
+                "expected one of {}, found {value}",
+                names.join(", ")
+            ));
+        }
+    }
+
+    if let (Some(minimum), Some(n)) = (
+        schema.get("minimum").and_then(Value::as_f64),
+        value.as_f64(),
+    ) {
+        if n < minimum {
+            fail(format!("must be at least {minimum}, found {n}"));
+        }
+    }
+
+    if let (Some(min), Some(s)) = (
+        schema.get("minLength").and_then(Value::as_u64),
+        value.as_str(),
+    ) {
+        if (s.chars().count() as u64) < min {
+            fail(format!("must be at least {min} character(s) long"));
+        }
+    }
+
+    if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+        let matching = options
+            .iter()
+            .filter(|option| validate_with(root, option, value).is_empty())
+            .count();
+        if matching != 1 {
+            fail(format!(
+                "must match exactly one of {} alternatives, matches {matching}",
+                options.len()
+            ));
+        }
+    }
+
+    if let Value::Object(map) = value {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(key) {
+                    fail(format!("missing required property '{key}'"));
+                }
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, item) in map {
+            let item_path = format!("{path}.{key}");
+            match properties.and_then(|p| p.get(key)) {
+                Some(property) => check(root, property, item, &item_path, out),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => out.push(SchemaViolation {
+                        path: item_path,
+                        message: "unexpected property".to_string(),
+                    }),
+                    Some(extra @ Value::Object(_)) => check(root, extra, item, &item_path, out),
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check(root, item_schema, item, &format!("{path}[{i}]"), out);
+        }
+    }
+}
+
+/// Violations of a subschema, for trying `oneOf` alternatives.
+fn validate_with(root: &Value, schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    check(root, schema, value, "$", &mut violations);
+    violations
+}
+
+/// Follow a local `$ref` ("#/$defs/story") to the schema it names.
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    match schema.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .map_or(schema, |target| resolve(root, target)),
+        None => schema,
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_embedded_schemas_parse() {
+        for kind in [SchemaKind::Tasks, SchemaKind::Progress] {
+            let schema = kind.schema();
+            assert_eq!(schema["type"], "object");
+            assert!(schema["$schema"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_valid_tasks_document() {
+        let document = json!({
+            "project": "shop",
+            "userStories": [
+                {
+                    "id": "auth-1",
+                    "title": "Login",
+                    "acceptanceCriteria": ["Form renders", {"text": "Errors shown", "passed": true}],
+                    "priority": 1,
+                    "passes": false,
+                    "effort": 3,
+                    "dependsOn": ["setup"],
+                    "extra": "ignored"
+                }
+            ]
+        });
+        assert!(SchemaKind::Tasks.validate(&document).is_empty());
+        assert!(SchemaKind::Tasks
+            .validate(&json!({"tasks": [{"id": "x"}]}))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_invalid_tasks_document() {
+        let document = json!({
+            "userStories": [
+                {"title": "No id"},
+                {"id": "b", "priority": "high", "acceptanceCriteria": [{"passed": true}]},
+                {"id": "c", "effort": -1}
+            ]
+        });
+        let found: Vec<String> = SchemaKind::Tasks
+            .validate(&document)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "$.userStories[0]: missing required property 'id'",
+                "$.userStories[1].acceptanceCriteria[0]: must match exactly one of 2 alternatives, matches 0",
+                "$.userStories[1].priority: expected integer, found string",
+                "$.userStories[2].effort: must be at least 0, found -1",
+            ]
+        );
+
+        let not_array = SchemaKind::Tasks.validate(&json!({"userStories": {}}));
+        assert_eq!(not_array[0].message, "expected array, found object");
+    }
+
+    #[test]
+    fn test_progress_document() {
+        let document = json!({
+            "started_at": "2024-05-01T12:00:00",
+            "iterations": 2,
+            "last_branch": null,
+            "tasks": {
+                "auth-1": {"id": "auth-1", "status": "in_progress", "failure_count": 1},
+                "auth-2": {"status": "done"}
+            },
+            "tool_versions": {"cargo": 1}
+        });
+        let found: Vec<String> = SchemaKind::Progress
+            .validate(&document)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(found.len(), 2);
+        assert!(found[0].starts_with("$.tasks.auth-2.status: expected one of"));
+        assert_eq!(
+            found[1],
+            "$.tool_versions.cargo: expected string, found integer"
+        );
+    }
+
+    #[test]
+    fn test_additional_properties_false() {
+        let schema =
+            json!({"type": "object", "properties": {"a": {}}, "additionalProperties": false});
+        let violations = validate(&schema, &json!({"a": 1, "b": 2}));
+        assert_eq!(violations[0].to_string(), "$.b: unexpected property");
+    }
+}
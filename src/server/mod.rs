@@ -0,0 +1,600 @@
+//! HTTP API server.
+//!
+//! `afk serve` answers a small JSON API over plain HTTP so a long run can be
+//! watched from a phone or fed into a dashboard. Every request reads the
+//! state files afresh, so the server can run alongside `afk go` in another
+//! terminal, and the control endpoints steer the loop through the same
+//! control files as `afk pause`, `afk resume` and the TUI keys.
+//!
+//! | Method | Path         | Answer                                        |
+//! |--------|--------------|-----------------------------------------------|
+//! | GET    | `/status`    | `afk status --json`                           |
+//! | GET    | `/tasks`     | `afk tasks --json`                            |
+//! | GET    | `/iteration` | The iteration in progress, or null            |
+//! | GET    | `/events`    | Event log records (`?limit=`, `?after=`)      |
+//! | GET    | `/metrics`   | Iterations, commits, tokens and cost this run |
+//! | GET    | `/stream`    | Live updates as server-sent events            |
+//! | POST   | `/pause`, `/resume`, `/stop`, `/skip` | The new control state |
+//!
+//! `/` serves a dashboard page built on these. With a token configured,
+//! API requests must send it as `Authorization: Bearer <token>` or a
+//! `token` query parameter. In read-only mode the control endpoints
+//! answer 403. There are no CORS headers: the dashboard is served from the
+//! same origin, and requests from other sites' pages are refused.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::cli::commands::status::status_json;
+use crate::cli::output::json::{control_name, TasksJson};
+use crate::config::{is_read_only, ServerConfig, EVENTS_FILE};
+use crate::events::run_summary;
+use crate::prd::PrdDocument;
+use crate::progress::SessionProgress;
+use crate::runner::{
+    control_state, request_pause, request_skip, request_stop, resume, IterationCheckpoint,
+};
+
+mod stream;
+
+/// The dashboard page served at `/`.
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// Environment variable read when `server.token` is unset.
+pub const TOKEN_ENV: &str = "AFK_SERVER_TOKEN";
+
+/// Events returned by `/events` when no `limit` is given.
+const DEFAULT_EVENT_LIMIT: usize = 50;
+
+/// How long a client may take to send its request.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request head (request line and headers) read.
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+
+/// Error type for starting the server.
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    /// Listening on a non-loopback address without a token.
+    #[error(
+        "Refusing to listen on {0} without a token. Set server.token or {TOKEN_ENV}, \
+         or use a loopback host."
+    )]
+    TokenRequired(String),
+    /// Failed to bind or accept connections.
+    #[error("Server error: {0}")]
+    IoError(#[from] io::Error),
+}
+
+/// The HTTP API server.
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    token: Option<String>,
+}
+
+impl Server {
+    /// Bind to the configured address.
+    ///
+    /// The token comes from `server.token`, falling back to
+    /// [`TOKEN_ENV`]. Fails without one unless the host is a loopback
+    /// address.
+    pub fn bind(config: &ServerConfig) -> Result<Self, ServerError> {
+        let token = config
+            .token
+            .clone()
+            .or_else(|| std::env::var(TOKEN_ENV).ok())
+            .filter(|t| !t.trim().is_empty());
+        if token.is_none() && !is_loopback(&config.host) {
+            return Err(ServerError::TokenRequired(config.host.clone()));
+        }
+        let listener = TcpListener::bind((config.host.as_str(), config.port))?;
+        Ok(Self { listener, token })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Whether requests must carry a token.
+    pub fn requires_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Answer requests until the process ends, one thread per connection.
+    pub fn run(self) -> Result<(), ServerError> {
+        for stream in self.listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let token = self.token.clone();
+            thread::spawn(move || {
+                let _ = handle_connection(stream, token.as_deref());
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether a host name or address only accepts local connections.
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// A parsed request head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+}
+
+impl Request {
+    /// Parse the request line and headers, leaving the body unread.
+    fn read(reader: &mut impl BufRead) -> Option<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let mut parts = line.split_whitespace();
+        let method = parts.next()?.to_uppercase();
+        let target = parts.next()?;
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = path.trim_end_matches('/').to_string();
+        let query = parse_query(query);
+
+        let mut headers = HashMap::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).ok()? == 0 {
+                break;
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+
+        Some(Self {
+            method,
+            path,
+            query,
+            headers,
+        })
+    }
+
+    /// The token the client sent, from the header or the query string.
+    fn token(&self) -> Option<&str> {
+        self.headers
+            .get("authorization")
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .or_else(|| self.query.get("token").map(String::as_str))
+            .map(str::trim)
+    }
+}
+
+/// Split a query string into decoded key/value pairs.
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes and `+` in a query component.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Compare tokens in time that doesn't depend on where they differ.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// A response: status code, content type and body.
+#[derive(Debug, Clone, PartialEq)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: impl Serialize) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_string(&body).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: json!({ "error": message }).to_string(),
+        }
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {reason}\r\n\
+             Content-Type: {}\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, token: Option<&str>) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_HEAD_BYTES));
+    let response = match Request::read(&mut reader) {
+        Some(request)
+            if request.method == "GET"
+                && request.path == "/stream"
+                && !foreign(&request, token)
+                && authorised(&request, token) =>
+        {
+            return stream::stream_updates(stream);
+        }
+        Some(request) => respond(&request, token),
+        None => Response::error(400, "malformed request"),
+    };
+    response.write_to(&mut stream)
+}
+
+/// Whether the request carries the token, if one is needed.
+fn authorised(request: &Request, token: Option<&str>) -> bool {
+    token.map_or(true, |expected| {
+        request.token().is_some_and(|t| tokens_match(t, expected))
+    })
+}
+
+/// Whether the request comes from another site's page, by its `Origin`,
+/// or reached a server without a token under a host name that isn't
+/// loopback, as a DNS rebinding attack would.
+fn foreign(request: &Request, token: Option<&str>) -> bool {
+    let host = request.headers.get("host").map(String::as_str);
+    let cross_origin = request.headers.get("origin").is_some_and(|origin| {
+        let origin_host = origin.split_once("://").map(|(_, h)| h);
+        origin_host.is_none() || origin_host != host
+    });
+    cross_origin || (token.is_none() && host.is_some_and(|h| !is_loopback(host_name(h))))
+}
+
+/// The host name in a `Host` header, without its port.
+fn host_name(host: &str) -> &str {
+    match host.find(']') {
+        Some(end) if host.starts_with('[') => &host[..=end],
+        _ => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    }
+}
+
+/// Check where the request came from and its token, and route it.
+fn respond(request: &Request, token: Option<&str>) -> Response {
+    // The page holds no data; it asks for the token before calling the API
+    if request.method == "GET" && request.path.is_empty() {
+        return Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: DASHBOARD.to_string(),
+        };
+    }
+    if foreign(request, token) {
+        return Response::error(403, "cross-origin request refused");
+    }
+    if !authorised(request, token) {
+        return Response::error(401, "missing or wrong token");
+    }
+    route(request)
+}
+
+fn route(request: &Request) -> Response {
+    let method = request.method.as_str();
+    match (method, request.path.as_str()) {
+        ("GET", "/status") => Response::ok(status_json()),
+        ("GET", "/tasks") => tasks(),
+        ("GET", "/iteration") => Response::ok(json!({
+            "iteration": IterationCheckpoint::load(None),
+        })),
+        ("GET", "/events") => events(request),
+        ("GET", "/metrics") => metrics(),
+        ("POST", "/pause") => control(request_pause),
+        ("POST", "/resume") => control(|| resume().map(|_| ())),
+        ("POST", "/stop") => control(request_stop),
+        ("POST", "/skip") => control(request_skip),
+        (
+            _,
+            "/status" | "/tasks" | "/iteration" | "/events" | "/metrics" | "/stream" | "/pause"
+            | "/resume" | "/stop" | "/skip",
+        ) => Response::error(405, "method not allowed"),
+        _ => Response::error(404, "not found"),
+    }
+}
+
+fn tasks() -> Response {
+    match PrdDocument::load(None) {
+        Ok(prd) => {
+            let progress = SessionProgress::load(None).unwrap_or_default();
+            let stories: Vec<_> = prd.user_stories.iter().collect();
+            Response::ok(TasksJson::new(&prd, &stories, &progress))
+        }
+        Err(e) => Response::error(500, &e.to_string()),
+    }
+}
+
+fn events(request: &Request) -> Response {
+    let limit = match request.query.get("limit").map(|l| l.parse()) {
+        None => DEFAULT_EVENT_LIMIT,
+        Some(Ok(limit)) => limit,
+        Some(Err(_)) => return Response::error(400, "limit must be a number"),
+    };
+    let after = match request.query.get("after").map(|a| a.parse()) {
+        None => None,
+        Some(Ok(after)) => Some(after),
+        Some(Err(_)) => return Response::error(400, "after must be a number"),
+    };
+    let (events, next) = tail_events(Path::new(EVENTS_FILE), limit, after);
+    Response::ok(json!({ "events": events, "next": next }))
+}
+
+/// Records from the event log, and the cursor to pass as `after` next time.
+///
+/// Without `after`, the last `limit` records. With it, up to `limit`
+/// records from that byte offset on. A cursor past the end of the log (it
+/// was cleared) starts from the beginning. Half-written lines are left for
+/// the next call.
+fn tail_events(path: &Path, limit: usize, after: Option<usize>) -> (Vec<Value>, usize) {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let complete = &contents[..contents.rfind('\n').map_or(0, |i| i + 1)];
+
+    let Some(after) = after else {
+        let lines: Vec<&str> = complete.lines().filter(|l| !l.trim().is_empty()).collect();
+        let events = lines[lines.len().saturating_sub(limit)..]
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        return (events, complete.len());
+    };
+
+    let mut offset = if after <= complete.len() && complete.is_char_boundary(after) {
+        after
+    } else {
+        0
+    };
+    let mut events = Vec::new();
+    for line in complete[offset..].split_inclusive('\n') {
+        if events.len() == limit {
+            break;
+        }
+        offset += line.len();
+        if let Ok(event) = serde_json::from_str(line.trim_end()) {
+            events.push(event);
+        }
+    }
+    (events, offset)
+}
+
+/// Totals for the latest run, from the event log.
+fn metrics() -> Response {
+    let summary = run_summary(Path::new(EVENTS_FILE));
+    Response::ok(json!({
+        "iterations": summary.iterations,
+        "commits": summary.commits,
+        "current_task": summary.current_task.map(|(id, title)| json!({ "id": id, "title": title })),
+        "input_tokens": summary.input_tokens,
+        "output_tokens": summary.output_tokens,
+        "cost_usd": summary.cost_usd,
+        "ended": summary.ended,
+    }))
+}
+
+/// Make a control request and answer with the control state after it.
+fn control(request: fn() -> io::Result<()>) -> Response {
+    if is_read_only() {
+        return Response::error(403, "afk is read-only");
+    }
+    match request() {
+        Ok(()) => Response::ok(json!({ "control": control_name(control_state()) })),
+        Err(e) => Response::error(500, &e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(head: &str) -> Request {
+        Request::read(&mut head.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_request_read_parses_head() {
+        let req = request(
+            "GET /events/?limit=5&token=a%2Bb+c HTTP/1.1\r\n\
+             Host: localhost\r\n\
+             Authorization: Bearer s3cret\r\n\r\nbody",
+        );
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.path, "/events");
+        assert_eq!(req.query["limit"], "5");
+        assert_eq!(req.query["token"], "a+b c");
+        assert_eq!(req.headers["host"], "localhost");
+        assert_eq!(req.token(), Some("s3cret"));
+        assert!(Request::read(&mut "".as_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_respond_refuses_other_origins() {
+        let forged = request(
+            "POST /stop HTTP/1.1\r\nHost: 127.0.0.1:7878\r\nOrigin: http://evil.example\r\n\r\n",
+        );
+        assert_eq!(respond(&forged, None).status, 403);
+        assert_eq!(respond(&forged, Some("abc")).status, 403);
+        let sandboxed =
+            request("POST /stop HTTP/1.1\r\nHost: 127.0.0.1:7878\r\nOrigin: null\r\n\r\n");
+        assert_eq!(respond(&sandboxed, None).status, 403);
+
+        // A rebound host name reaches loopback, but only with a token
+        let rebound = request("GET /nowhere HTTP/1.1\r\nHost: evil.example:7878\r\n\r\n");
+        assert_eq!(respond(&rebound, None).status, 403);
+        assert_eq!(respond(&rebound, Some("abc")).status, 401);
+
+        let same = request(
+            "GET /nowhere HTTP/1.1\r\nHost: [::1]:7878\r\nOrigin: http://[::1]:7878\r\n\r\n",
+        );
+        assert_eq!(respond(&same, None).status, 404);
+        let page = respond(&request("GET / HTTP/1.1\r\n\r\n"), None);
+        let mut written = Vec::new();
+        page.write_to(&mut written).unwrap();
+        assert!(!String::from_utf8(written)
+            .unwrap()
+            .contains("Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_respond_checks_token() {
+        let with_header = request("GET /nowhere HTTP/1.1\r\nAuthorization: Bearer abc\r\n\r\n");
+        let with_query = request("GET /nowhere?token=abc HTTP/1.1\r\n\r\n");
+        let without = request("GET /nowhere HTTP/1.1\r\n\r\n");
+        let wrong = request("GET /nowhere?token=abd HTTP/1.1\r\n\r\n");
+
+        assert_eq!(respond(&with_header, Some("abc")).status, 404);
+        assert_eq!(respond(&with_query, Some("abc")).status, 404);
+        assert_eq!(respond(&without, Some("abc")).status, 401);
+        assert_eq!(respond(&wrong, Some("abc")).status, 401);
+        assert_eq!(respond(&without, None).status, 404);
+        let preflight = request("OPTIONS /status HTTP/1.1\r\n\r\n");
+        assert_eq!(respond(&preflight, None).status, 405);
+
+        // The dashboard page is served without the token
+        let page = respond(&request("GET / HTTP/1.1\r\n\r\n"), Some("abc"));
+        assert_eq!(page.status, 200);
+        assert!(page.content_type.starts_with("text/html"));
+        assert!(page.body.contains("EventSource"));
+        assert!(page.body.contains("id=\"timeline\""));
+    }
+
+    #[test]
+    fn test_route_rejects_wrong_method() {
+        assert_eq!(route(&request("POST /status HTTP/1.1\r\n\r\n")).status, 405);
+        assert_eq!(route(&request("GET /pause HTTP/1.1\r\n\r\n")).status, 405);
+        let bad = request("GET /events?limit=lots HTTP/1.1\r\n\r\n");
+        assert_eq!(route(&bad).status, 400);
+    }
+
+    #[test]
+    fn test_tail_events_pages_with_cursor() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("events.jsonl");
+        let lines = ["{\"n\":1}\n", "{\"n\":2}\n", "{\"n\":3}\n"];
+        fs::write(&path, format!("{}{{\"n\":4", lines.concat())).unwrap();
+        let end = lines.concat().len();
+
+        let (events, next) = tail_events(&path, 2, None);
+        assert_eq!(events, vec![json!({"n": 2}), json!({"n": 3})]);
+        assert_eq!(next, end);
+
+        let (events, next) = tail_events(&path, 2, Some(0));
+        assert_eq!(events, vec![json!({"n": 1}), json!({"n": 2})]);
+        let (events, next) = tail_events(&path, 2, Some(next));
+        assert_eq!(events, vec![json!({"n": 3})]);
+        assert_eq!(next, end);
+
+        // A cursor past the end means the log was cleared
+        let (events, _) = tail_events(&path, 10, Some(end + 100));
+        assert_eq!(events.len(), 3);
+        assert_eq!(tail_events(&temp.path().join("none"), 5, None), (vec![], 0));
+    }
+
+    #[test]
+    fn test_bind_needs_token_off_loopback() {
+        assert!(is_loopback("127.0.0.1"));
+        assert!(is_loopback("localhost"));
+        assert!(is_loopback("[::1]"));
+        assert!(!is_loopback("0.0.0.0"));
+        assert!(!is_loopback("192.168.1.10"));
+
+        if std::env::var(TOKEN_ENV).is_err() {
+            let config = ServerConfig {
+                host: "0.0.0.0".to_string(),
+                port: 0,
+                token: None,
+            };
+            assert!(matches!(
+                Server::bind(&config),
+                Err(ServerError::TokenRequired(_))
+            ));
+        }
+        let server = Server::bind(&ServerConfig {
+            port: 0,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(server.local_addr().unwrap().ip().is_loopback());
+    }
+}
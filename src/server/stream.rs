@@ -0,0 +1,175 @@
+//! Live updates for the dashboard.
+//!
+//! `GET /stream` is a server-sent events stream built by tailing the files
+//! the loop writes: each new event log record is sent as a `record` event,
+//! each new line of the newest iteration transcript as an `output` event,
+//! and the iteration checkpoint as an `iteration` event whenever it changes.
+//! Nothing is sent while the loop is idle but a keep-alive comment.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::config::{AfkConfig, EVENTS_FILE};
+use crate::progress::transcript::{list_logs, log_path, transcript_dir};
+use crate::runner::IterationCheckpoint;
+
+/// How often the files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a keep-alive comment is sent, which also notices clients
+/// that have gone away.
+const KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// A file read a line at a time as it grows.
+#[derive(Debug)]
+struct Tail {
+    path: PathBuf,
+    offset: usize,
+}
+
+impl Tail {
+    /// Follow a file from its current end.
+    fn from_end(path: &Path) -> Self {
+        let offset = fs::metadata(path).map_or(0, |m| m.len() as usize);
+        Self {
+            path: path.to_path_buf(),
+            offset,
+        }
+    }
+
+    /// Follow a file from its start.
+    fn from_start(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            offset: 0,
+        }
+    }
+
+    /// Complete lines appended since the last call. A file that shrank
+    /// (cleared or rotated) is read again from the start.
+    fn read_new(&mut self) -> Vec<String> {
+        let Ok(contents) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+        if contents.len() < self.offset || !contents.is_char_boundary(self.offset) {
+            self.offset = 0;
+        }
+        let end = contents[self.offset..]
+            .rfind('\n')
+            .map_or(self.offset, |i| self.offset + i + 1);
+        let lines = contents[self.offset..end]
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(String::from)
+            .collect();
+        self.offset = end;
+        lines
+    }
+}
+
+/// Format one server-sent event. `data` must be a single line.
+fn sse(event: &str, data: &str) -> String {
+    format!("event: {event}\ndata: {data}\n\n")
+}
+
+/// Send updates to `stream` until the client goes away.
+pub(super) fn stream_updates(mut stream: impl Write) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/event-stream\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: keep-alive\r\n\r\n"
+    )?;
+    stream.flush()?;
+
+    let dir = transcript_dir(&AfkConfig::load(None).unwrap_or_default().feedback);
+    let mut events = Tail::from_end(Path::new(EVENTS_FILE));
+    let mut output: Option<(u32, Tail)> = None;
+    let mut checkpoint: Option<Value> = None;
+    let mut first = true;
+    let mut last_sent = Instant::now();
+
+    loop {
+        let mut message = String::new();
+        for line in events.read_new() {
+            message.push_str(&sse("record", &line));
+        }
+
+        let current = IterationCheckpoint::load(None).and_then(|c| serde_json::to_value(c).ok());
+        if first || current != checkpoint {
+            let data = current
+                .as_ref()
+                .map_or("null".to_string(), Value::to_string);
+            message.push_str(&sse("iteration", &data));
+            checkpoint = current;
+        }
+
+        // Follow the newest transcript, switching when a new one starts
+        let newest = list_logs(&dir).ok().and_then(|logs| logs.last().copied());
+        if let Some(iteration) = newest.filter(|n| output.as_ref().map(|o| o.0) != Some(*n)) {
+            let path = log_path(&dir, iteration);
+            // A transcript already under way when the client connects
+            // is sent whole, so the page shows the iteration so far
+            output = Some((iteration, Tail::from_start(&path)));
+            message.push_str(&sse("transcript", &iteration.to_string()));
+        }
+        if let Some((_, tail)) = output.as_mut() {
+            for line in tail.read_new() {
+                message.push_str(&sse("output", &Value::String(line).to_string()));
+            }
+        }
+
+        if message.is_empty() && last_sent.elapsed() >= KEEP_ALIVE {
+            message.push_str(": keep-alive\n\n");
+        }
+        if !message.is_empty() {
+            stream.write_all(message.as_bytes())?;
+            stream.flush()?;
+            last_sent = Instant::now();
+        }
+        first = false;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_tail_reads_complete_new_lines() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("log");
+        fs::write(&path, "old\n").unwrap();
+        let mut tail = Tail::from_end(&path);
+        assert!(tail.read_new().is_empty());
+
+        fs::write(&path, "old\nnew\nhalf").unwrap();
+        assert_eq!(tail.read_new(), vec!["new"]);
+        fs::write(&path, "old\nnew\nhalf done\n").unwrap();
+        assert_eq!(tail.read_new(), vec!["half done"]);
+
+        // Cleared and rewritten: read again from the start
+        fs::write(&path, "fresh\n").unwrap();
+        assert_eq!(tail.read_new(), vec!["fresh"]);
+        assert_eq!(Tail::from_start(&path).read_new(), vec!["fresh"]);
+        assert!(Tail::from_start(&temp.path().join("none"))
+            .read_new()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_sse_format() {
+        assert_eq!(
+            sse("record", "{\"a\":1}"),
+            "event: record\ndata: {\"a\":1}\n\n"
+        );
+    }
+}
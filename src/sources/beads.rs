@@ -1,9 +1,16 @@
 //! Beads (bd) task source adapter.
 //!
-//! Loads tasks from the beads issue tracker via the `bd` CLI.
+//! Loads tasks from the beads issue tracker via the `bd` CLI. Tasks are
+//! marked in progress when the loop picks them and closed when completed;
+//! with writeback enabled on the source, afk also comments with how each
+//! task went and moves failed tasks back to `open` (or `blocked`, once they
+//! run out of attempts).
 
+use crate::config::{SourceConfig, SourceType, EVENTS_FILE};
 use crate::prd::UserStory;
+use crate::progress::SessionProgress;
 use regex::Regex;
+use std::path::Path;
 use std::process::Command;
 use std::sync::LazyLock;
 
@@ -102,6 +109,104 @@ pub fn close_beads_issue(issue_id: &str) -> bool {
     }
 }
 
+/// Close the beads issue behind a completed task.
+///
+/// With writeback enabled on the beads source, first comments with the
+/// completion message (from `afk done -m`) and the commits made for it.
+///
+/// # Returns
+///
+/// `true` if the issue was closed.
+pub fn complete_beads_task(task_id: &str, sources: &[SourceConfig]) -> bool {
+    if writeback_enabled(sources) {
+        let message = SessionProgress::load(None)
+            .ok()
+            .and_then(|p| p.get_task(task_id).and_then(|t| t.message.clone()));
+        let commits = crate::events::task_commits(Path::new(EVENTS_FILE), task_id);
+        comment_beads_issue(
+            task_id,
+            &completion_comment(task_id, message.as_deref(), &commits),
+        );
+    }
+    close_beads_issue(task_id)
+}
+
+/// Record a failed attempt on the beads issue behind a task.
+///
+/// Only with writeback enabled on the beads source: comments with the
+/// failure, and moves the issue back to `open`, or to `blocked` once it has
+/// failed `max_failures` times (0 for no limit).
+///
+/// # Returns
+///
+/// `true` if the issue was updated.
+pub fn fail_beads_task(
+    task_id: &str,
+    failures: u32,
+    max_failures: u32,
+    message: Option<&str>,
+    sources: &[SourceConfig],
+) -> bool {
+    if !writeback_enabled(sources) {
+        return false;
+    }
+    let gave_up = max_failures > 0 && failures >= max_failures;
+    comment_beads_issue(task_id, &failure_comment(failures, gave_up, message));
+    let status = if gave_up { "blocked" } else { "open" };
+    matches!(
+        Command::new("bd")
+            .args(["update", task_id, "--status", status])
+            .output(),
+        Ok(output) if output.status.success()
+    )
+}
+
+/// Whether a beads source has writeback enabled, outside read-only mode.
+fn writeback_enabled(sources: &[SourceConfig]) -> bool {
+    !crate::config::is_read_only()
+        && sources
+            .iter()
+            .any(|s| s.source_type == SourceType::Beads && s.writeback)
+}
+
+/// Add a comment to a beads issue.
+fn comment_beads_issue(issue_id: &str, text: &str) -> bool {
+    matches!(
+        Command::new("bd").args(["comments", "add", issue_id, text]).output(),
+        Ok(output) if output.status.success()
+    )
+}
+
+/// The comment left on an issue when its task is completed.
+fn completion_comment(task_id: &str, message: Option<&str>, commits: &[String]) -> String {
+    let mut comment = format!("Completed by afk (task {task_id}).");
+    if let Some(message) = message.map(str::trim).filter(|m| !m.is_empty()) {
+        comment.push_str(&format!("\n\n{message}"));
+    }
+    if !commits.is_empty() {
+        comment.push_str("\n\nCommits:");
+        for sha in commits {
+            comment.push_str(&format!("\n- {sha}"));
+        }
+    }
+    comment
+}
+
+/// The comment left on an issue when an attempt at its task fails.
+fn failure_comment(failures: u32, gave_up: bool, message: Option<&str>) -> String {
+    let mut comment = format!("afk attempt {failures} failed");
+    match message.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(message) => comment.push_str(&format!(": {message}")),
+        None => comment.push('.'),
+    }
+    if gave_up {
+        comment.push_str(&format!(
+            "\n\nGiving up after {failures} failures (limits.max_task_failures)."
+        ));
+    }
+    comment
+}
+
 /// Run `bd list --status <status> --json` and parse the output.
 fn run_bd_list_json(status: &str) -> Result<Vec<UserStory>, BeadsError> {
     let output = Command::new("bd")
@@ -174,9 +279,9 @@ fn parse_beads_item(item: &serde_json::Value) -> Option<UserStory> {
     // Extract acceptance criteria from description
     let acceptance_criteria = extract_acceptance_criteria(&description);
     let acceptance_criteria = if acceptance_criteria.is_empty() {
-        vec![format!("Complete: {}", title)]
+        vec![format!("Complete: {}", title).into()]
     } else {
-        acceptance_criteria
+        acceptance_criteria.into_iter().map(Into::into).collect()
     };
 
     Some(UserStory {
@@ -188,6 +293,15 @@ fn parse_beads_item(item: &serde_json::Value) -> Option<UserStory> {
         passes: false,
         source: "beads".to_string(),
         notes: String::new(),
+        effort: None,
+        estimate: None,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
     })
 }
 
@@ -250,11 +364,20 @@ fn parse_text_line(line: &str) -> UserStory {
         id: task_id,
         title: title.clone(),
         description: title.clone(),
-        acceptance_criteria: vec![format!("Complete: {}", title)],
+        acceptance_criteria: vec![format!("Complete: {}", title).into()],
         priority: 3,
         passes: false,
         source: "beads".to_string(),
         notes: String::new(),
+        effort: None,
+        estimate: None,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
     }
 }
 
@@ -360,6 +483,40 @@ enum BeadsError {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_completion_comment() {
+        assert_eq!(
+            completion_comment("bd-1", None, &[]),
+            "Completed by afk (task bd-1)."
+        );
+        assert_eq!(
+            completion_comment(
+                "bd-1",
+                Some("Added the login form "),
+                &["abc123".to_string(), "def456".to_string()]
+            ),
+            "Completed by afk (task bd-1).\n\nAdded the login form\n\nCommits:\n- abc123\n- def456"
+        );
+    }
+
+    #[test]
+    fn test_failure_comment() {
+        assert_eq!(
+            failure_comment(1, false, Some("tests fail")),
+            "afk attempt 1 failed: tests fail"
+        );
+        assert_eq!(
+            failure_comment(3, true, None),
+            "afk attempt 3 failed.\n\nGiving up after 3 failures (limits.max_task_failures)."
+        );
+    }
+
+    #[test]
+    fn test_fail_beads_task_needs_writeback() {
+        let sources = [SourceConfig::beads()];
+        assert!(!fail_beads_task("bd-1", 1, 3, None, &sources));
+    }
+
     #[test]
     fn test_map_beads_priority_none() {
         assert_eq!(map_beads_priority(None), 3);
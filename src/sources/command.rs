@@ -0,0 +1,112 @@
+//! External command task source adapter.
+//!
+//! Runs an executable named in config and reads tasks from the JSON it
+//! prints on stdout, in any format the JSON source accepts: an array of
+//! tasks, or an object with a `tasks`, `userStories` or `items` array.
+//! This lets a script bridge afk to a tracker it has no adapter for.
+
+use std::process::{Command, Stdio};
+
+use crate::config::SourceConfig;
+use crate::prd::UserStory;
+
+use super::json::stories_from_value;
+use super::{skip, SourceError};
+
+/// Load tasks by running a command source's executable.
+///
+/// The executable in `path` runs with `args` in the current directory.
+/// A missing path, a failed run, a non-zero exit or output that isn't JSON
+/// is reported as a warning and yields an empty list.
+pub fn load_command_tasks(source: &SourceConfig) -> Vec<UserStory> {
+    try_load_command_tasks(source).unwrap_or_else(skip)
+}
+
+/// Load tasks from a command source like [`load_command_tasks`], returning
+/// what went wrong instead of warning.
+pub fn try_load_command_tasks(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    let Some(path) = source.path.as_deref().filter(|p| !p.trim().is_empty()) else {
+        return Err(SourceError::Misconfigured(
+            "Command source has no path".into(),
+        ));
+    };
+    run(path, &source.args).map_err(|e| SourceError::Failed {
+        kind: "Command",
+        message: format!("{path}: {e}"),
+    })
+}
+
+/// Run the executable and parse its output.
+fn run(path: &str, args: &[String]) -> Result<Vec<UserStory>, String> {
+    let output = Command::new(path)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr
+            .lines()
+            .rev()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| format!(": {}", l.trim()))
+            .unwrap_or_default();
+        return Err(format!("exited with {}{detail}", output.status));
+    }
+    let data: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("output is not valid JSON: {e}"))?;
+    Ok(stories_from_value(&data, &format!("command:{path}")))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn script(temp: &TempDir, body: &str) -> String {
+        let path = temp.path().join("source.sh");
+        fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_load_command_tasks() {
+        let temp = TempDir::new().unwrap();
+        let body = r#"echo '{"tasks": [
+  {"id": "T-1", "title": "First", "priority": 1, "acceptanceCriteria": ["Works"]},
+  {"id": "T-2", "title": "Done already", "passes": true},
+  {"id": "T-3", "title": "From '"$1"'"}
+]}'"#;
+        let mut source = SourceConfig::command(script(&temp, body));
+        source.args = vec!["arg".to_string()];
+        let stories = load_command_tasks(&source);
+        let ids: Vec<&str> = stories.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["T-1", "T-3"]);
+        assert_eq!(stories[0].priority, 1);
+        assert_eq!(stories[0].acceptance_criteria.len(), 1);
+        assert_eq!(stories[1].title, "From arg");
+        assert!(stories[0].source.starts_with("command:"));
+    }
+
+    #[test]
+    fn test_load_command_tasks_failures_yield_nothing() {
+        let temp = TempDir::new().unwrap();
+        let failing = SourceConfig::command(script(&temp, "echo oops >&2; exit 3"));
+        assert!(load_command_tasks(&failing).is_empty());
+        assert!(run(failing.path.as_deref().unwrap(), &[])
+            .unwrap_err()
+            .ends_with(": oops"));
+
+        let garbage = SourceConfig::command(script(&temp, "echo not json"));
+        assert!(load_command_tasks(&garbage).is_empty());
+
+        let missing = SourceConfig::command(temp.path().join("nope").to_string_lossy());
+        assert!(load_command_tasks(&missing).is_empty());
+        assert!(
+            load_command_tasks(&SourceConfig::new(crate::config::SourceType::Command)).is_empty()
+        );
+    }
+}
@@ -0,0 +1,324 @@
+//! CSV/TSV spreadsheet task source adapter.
+//!
+//! Loads tasks from a spreadsheet exported as CSV (or TSV, for `.tsv` and
+//! `.tab` files), one task per row. The header row names the columns, and
+//! the source's `columns` setting says which hold the ID, title,
+//! description, priority, acceptance criteria and done flag. The same
+//! format is written by `afk tasks export --format csv`.
+
+use super::{skip, SourceError};
+use crate::config::{ColumnMapping, SourceConfig};
+use crate::prd::UserStory;
+use std::fs;
+use std::path::Path;
+
+/// Prefix of the `source` of tasks read from a spreadsheet.
+const SOURCE_PREFIX: &str = "csv:";
+
+/// Default file path if none specified.
+pub const DEFAULT_PATH: &str = "tasks.csv";
+
+/// Cell values that mark a row done.
+const DONE_VALUES: &[&str] = &[
+    "yes",
+    "y",
+    "true",
+    "x",
+    "1",
+    "done",
+    "complete",
+    "completed",
+    "closed",
+    "✓",
+    "✔",
+];
+
+/// The field separator for a file: tabs for `.tsv` and `.tab`, commas
+/// otherwise.
+pub fn delimiter_for(path: &Path) -> char {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("tsv") || ext.eq_ignore_ascii_case("tab") => '\t',
+        _ => ',',
+    }
+}
+
+/// Load tasks from a CSV source, warning and yielding none if its columns
+/// don't match.
+pub fn load_csv_tasks(source: &SourceConfig) -> Vec<UserStory> {
+    try_load_csv_tasks(source).unwrap_or_else(skip)
+}
+
+/// Load tasks from a CSV source like [`load_csv_tasks`], returning what
+/// went wrong instead of warning.
+///
+/// A missing or unreadable file yields no tasks, as for other file-based
+/// sources; a file without the title column is an error.
+pub fn try_load_csv_tasks(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    let path = Path::new(source.path.as_deref().unwrap_or(DEFAULT_PATH));
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Ok(Vec::new());
+    };
+    let mapping = source.columns.clone().unwrap_or_default();
+    parse_tasks(
+        &contents,
+        delimiter_for(path),
+        &mapping,
+        &path.display().to_string(),
+    )
+}
+
+/// Tasks from the rows of a spreadsheet.
+fn parse_tasks(
+    contents: &str,
+    delimiter: char,
+    mapping: &ColumnMapping,
+    path: &str,
+) -> Result<Vec<UserStory>, SourceError> {
+    let mut records = parse_records(contents, delimiter).into_iter();
+    let Some(header) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+    };
+    let Some(title_column) = column(&mapping.title) else {
+        return Err(SourceError::Misconfigured(format!(
+            "CSV source {path} has no \"{}\" column",
+            mapping.title
+        )));
+    };
+    let id_column = column(&mapping.id);
+    let description_column = column(&mapping.description);
+    let priority_column = column(&mapping.priority);
+    let criteria_column = column(&mapping.criteria);
+    let done_column = column(&mapping.done);
+
+    let source = format!("{SOURCE_PREFIX}{path}");
+    let mut tasks = Vec::new();
+    for record in records {
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| record.get(i))
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let Some(title) = cell(Some(title_column)) else {
+            continue;
+        };
+        if cell(done_column).is_some_and(is_done) {
+            continue;
+        }
+
+        let criteria: Vec<String> = cell(criteria_column)
+            .map(|text| {
+                text.lines()
+                    .map(|line| line.trim().trim_start_matches(['-', '*']).trim())
+                    .filter(|line| !line.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let acceptance_criteria = if criteria.is_empty() {
+            vec![format!("Complete: {title}").into()]
+        } else {
+            criteria.into_iter().map(Into::into).collect()
+        };
+
+        tasks.push(UserStory {
+            id: cell(id_column).map_or_else(|| generate_id(title), String::from),
+            title: title.to_string(),
+            description: cell(description_column).unwrap_or(title).to_string(),
+            acceptance_criteria,
+            priority: cell(priority_column)
+                .and_then(super::priority_from_name)
+                .unwrap_or(3),
+            passes: false,
+            source: source.clone(),
+            notes: String::new(),
+            effort: None,
+            estimate: None,
+            discussion: Vec::new(),
+            touched_at: None,
+            depends_on: Vec::new(),
+            model: None,
+            cli: None,
+            epic: None,
+            generated: Vec::new(),
+        });
+    }
+    Ok(tasks)
+}
+
+/// Whether a done cell says the task is done.
+fn is_done(value: &str) -> bool {
+    DONE_VALUES.iter().any(|d| d.eq_ignore_ascii_case(value))
+}
+
+/// Generate an ID from a title.
+fn generate_id(title: &str) -> String {
+    let id = title
+        .chars()
+        .take(30)
+        .flat_map(char::to_lowercase)
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if id.is_empty() {
+        "task".to_string()
+    } else {
+        id
+    }
+}
+
+/// Split delimited text into records of fields.
+///
+/// Fields may be quoted with `"`, in which case they can hold delimiters,
+/// newlines and doubled quotes. Blank lines are skipped.
+pub fn parse_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+/// Format fields as one delimited line (without the newline), quoting
+/// those that need it.
+pub fn format_record<S: AsRef<str>>(fields: &[S], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SourceType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_records_handles_quoting() {
+        let text =
+            "ID,Title,Notes\r\na,\"Login, logout\",\"Says \"\"hi\"\"\nthen bye\"\n\nb,Docs,\n";
+        assert_eq!(
+            parse_records(text, ','),
+            [
+                vec!["ID", "Title", "Notes"],
+                vec!["a", "Login, logout", "Says \"hi\"\nthen bye"],
+                vec!["b", "Docs", ""],
+            ]
+        );
+
+        let fields = ["a", "Login, logout", "Says \"hi\"\nthen bye"];
+        let line = format_record(&fields, ',');
+        assert_eq!(line, "a,\"Login, logout\",\"Says \"\"hi\"\"\nthen bye\"");
+        assert_eq!(parse_records(&line, ','), [fields.to_vec()]);
+        assert_eq!(format_record(&["a b", "c,d"], '\t'), "a b\tc,d");
+    }
+
+    #[test]
+    fn test_load_csv_tasks_maps_columns() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("backlog.tsv");
+        fs::write(
+            &path,
+            "Key\tSummary\tPriority\tAcceptance Criteria\tShipped\n\
+             AUTH-1\tLogin form\tHigh\t\"- Validates email\n- Sets cookie\"\t\n\
+             AUTH-2\tLogout\t4\t\tyes\n\
+             \tExport to PDF!\t\t\tno\n\
+             AUTH-3\t\t2\t\t\n",
+        )
+        .unwrap();
+        let source = SourceConfig {
+            columns: Some(ColumnMapping {
+                id: "key".to_string(),
+                title: "Summary".to_string(),
+                done: "Shipped".to_string(),
+                ..Default::default()
+            }),
+            ..SourceConfig::csv(path.to_str().unwrap())
+        };
+        assert_eq!(source.source_type, SourceType::Csv);
+
+        let tasks = load_csv_tasks(&source);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].id, "AUTH-1");
+        assert_eq!(tasks[0].title, "Login form");
+        assert_eq!(tasks[0].description, "Login form");
+        assert_eq!(tasks[0].priority, 1);
+        let criteria: Vec<&str> = tasks[0]
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        assert_eq!(criteria, ["Validates email", "Sets cookie"]);
+        assert_eq!(tasks[0].source, format!("csv:{}", path.display()));
+        assert_eq!(tasks[1].id, "export-to-pdf");
+        assert_eq!(tasks[1].priority, 3);
+        assert_eq!(
+            tasks[1].acceptance_criteria[0].text,
+            "Complete: Export to PDF!"
+        );
+    }
+
+    #[test]
+    fn test_load_csv_tasks_needs_title_column() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("tasks.csv");
+        fs::write(&path, "ID,Name\n1,Login\n").unwrap();
+        let source = SourceConfig::csv(path.to_str().unwrap());
+        assert!(matches!(
+            try_load_csv_tasks(&source),
+            Err(SourceError::Misconfigured(message)) if message.contains("no \"Title\" column")
+        ));
+
+        let missing = SourceConfig::csv(temp.path().join("missing.csv").to_str().unwrap());
+        assert!(try_load_csv_tasks(&missing).unwrap().is_empty());
+    }
+}
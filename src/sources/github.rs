@@ -1,7 +1,13 @@
 //! GitHub Issues task source adapter.
 //!
-//! Uses the `gh` CLI to fetch issues and convert them to UserStory.
-
+//! Uses the `gh` CLI to fetch issues and convert them to UserStory. Sync is
+//! bidirectional: labels and assignees are re-imported on every sync, and
+//! completing a task closes its issue when `github.sync_back` is enabled.
+//! With `comments` set on the source, the latest issue comments are kept
+//! with the task so the agent sees the newest clarifications.
+
+use super::{skip, SourceError};
+use crate::config::GithubConfig;
 use crate::prd::UserStory;
 use serde::Deserialize;
 use std::process::Command;
@@ -19,9 +25,40 @@ pub struct GhIssue {
     /// Labels attached to the issue.
     #[serde(default)]
     pub labels: Vec<GhLabel>,
+    /// Users assigned to the issue.
+    #[serde(default)]
+    pub assignees: Vec<GhUser>,
     /// Issue state (open/closed).
     #[serde(default)]
     pub state: String,
+    /// Comments on the issue, oldest first (only when requested).
+    #[serde(default)]
+    pub comments: Vec<GhComment>,
+}
+
+/// A comment on a GitHub issue.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhComment {
+    /// Comment author (missing for deleted accounts).
+    #[serde(default)]
+    pub author: Option<GhUser>,
+    /// Comment text.
+    #[serde(default)]
+    pub body: String,
+    /// Creation time, e.g. "2024-05-01T12:00:00Z".
+    #[serde(default)]
+    pub created_at: String,
+}
+
+/// Longest comment text kept in a task's discussion summary.
+const MAX_COMMENT_CHARS: usize = 280;
+
+/// A GitHub user.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GhUser {
+    /// User login name.
+    pub login: String,
 }
 
 /// A GitHub label.
@@ -37,15 +74,28 @@ pub struct GhLabel {
 ///
 /// * `repo` - Optional repository in "owner/repo" format. If empty, uses current repo.
 /// * `labels` - Optional list of labels to filter by.
+/// * `comments` - Number of latest comments to keep per issue (0 skips
+///   fetching comments).
 ///
 /// # Returns
 ///
-/// Vector of UserStory items converted from GitHub issues.
-pub fn load_github_tasks(repo: Option<&str>, labels: &[String]) -> Vec<UserStory> {
+/// Vector of UserStory items converted from GitHub issues. Errors are
+/// reported as warnings and yield an empty list; see
+/// [`try_load_github_tasks`] to handle them.
+pub fn load_github_tasks(repo: Option<&str>, labels: &[String], comments: u32) -> Vec<UserStory> {
+    try_load_github_tasks(repo, labels, comments).unwrap_or_else(skip)
+}
+
+/// Load tasks from GitHub Issues like [`load_github_tasks`], returning
+/// what went wrong instead of warning.
+pub fn try_load_github_tasks(
+    repo: Option<&str>,
+    labels: &[String],
+    comments: u32,
+) -> Result<Vec<UserStory>, SourceError> {
     // Check if gh is available
     if !gh_available() {
-        eprintln!("Warning: gh CLI not available. Skipping GitHub source.");
-        return Vec::new();
+        return Err(SourceError::Unavailable("gh CLI"));
     }
 
     // Build command
@@ -55,7 +105,11 @@ pub fn load_github_tasks(repo: Option<&str>, labels: &[String]) -> Vec<UserStory
         "--state",
         "open",
         "--json",
-        "number,title,body,labels,state",
+        if comments > 0 {
+            "number,title,body,labels,assignees,state,comments"
+        } else {
+            "number,title,body,labels,assignees,state"
+        },
     ];
 
     // Add repo if specified
@@ -74,39 +128,48 @@ pub fn load_github_tasks(repo: Option<&str>, labels: &[String]) -> Vec<UserStory
     }
 
     // Run gh command
-    let output = match Command::new("gh").args(&args).output() {
-        Ok(o) => o,
-        Err(e) => {
-            eprintln!("Warning: Failed to run gh: {e}");
-            return Vec::new();
-        }
+    let failed = |message: String| SourceError::Failed {
+        kind: "GitHub",
+        message,
     };
+    let output = Command::new("gh")
+        .args(&args)
+        .output()
+        .map_err(|e| failed(format!("could not run gh: {e}")))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!("Warning: gh command failed: {stderr}");
-        return Vec::new();
+        return Err(failed(format!("gh issue list: {}", stderr.trim())));
     }
 
     // Parse JSON output
     let json_str = String::from_utf8_lossy(&output.stdout);
-    let issues: Vec<GhIssue> = match serde_json::from_str(&json_str) {
-        Ok(i) => i,
-        Err(e) => {
-            eprintln!("Warning: Failed to parse gh output: {e}");
-            return Vec::new();
-        }
-    };
+    let issues: Vec<GhIssue> = serde_json::from_str(&json_str)
+        .map_err(|e| failed(format!("unexpected gh output: {e}")))?;
 
     // Convert to UserStory
-    issues.into_iter().map(issue_to_story).collect()
+    let repo = repo.filter(|r| !r.is_empty());
+    Ok(issues
+        .into_iter()
+        .map(|mut issue| {
+            let older = issue.comments.len().saturating_sub(comments as usize);
+            issue.comments.drain(..older);
+            issue_to_story(issue, repo)
+        })
+        .collect())
 }
 
 /// Convert a GitHub issue to a UserStory.
-fn issue_to_story(issue: GhIssue) -> UserStory {
+///
+/// Labels and assignees are recorded in the notes, so changes to them on
+/// GitHub show up after the next sync.
+fn issue_to_story(issue: GhIssue, repo: Option<&str>) -> UserStory {
     let id = format!("gh-{}", issue.number);
     let priority = infer_priority(&issue.labels);
-    let acceptance_criteria = extract_acceptance_criteria(issue.body.as_deref());
+    let acceptance_criteria = extract_acceptance_criteria(issue.body.as_deref())
+        .into_iter()
+        .map(Into::into)
+        .collect();
 
     UserStory {
         id,
@@ -115,11 +178,59 @@ fn issue_to_story(issue: GhIssue) -> UserStory {
         acceptance_criteria,
         priority,
         passes: false,
-        source: format!("github:#{}", issue.number),
-        notes: String::new(),
+        source: github_source(repo, issue.number),
+        notes: issue_notes(&issue.labels, &issue.assignees),
+        effort: None,
+        estimate: None,
+        discussion: summarise_comments(&issue.comments),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
     }
 }
 
+/// Source string for an issue: `github:#123`, or `github:owner/repo#123`
+/// for an explicitly configured repository.
+fn github_source(repo: Option<&str>, number: i64) -> String {
+    format!("github:{}#{number}", repo.unwrap_or_default())
+}
+
+/// Notes line listing an issue's labels and assignees.
+fn issue_notes(labels: &[GhLabel], assignees: &[GhUser]) -> String {
+    let mut parts = Vec::new();
+    if !labels.is_empty() {
+        let names: Vec<&str> = labels.iter().map(|l| l.name.as_str()).collect();
+        parts.push(format!("Labels: {}", names.join(", ")));
+    }
+    if !assignees.is_empty() {
+        let logins: Vec<String> = assignees.iter().map(|a| format!("@{}", a.login)).collect();
+        parts.push(format!("Assignees: {}", logins.join(", ")));
+    }
+    parts.join("\n")
+}
+
+/// One line per comment: "@author (date): text", with whitespace collapsed
+/// and long comments cut short.
+fn summarise_comments(comments: &[GhComment]) -> Vec<String> {
+    comments
+        .iter()
+        .filter(|c| !c.body.trim().is_empty())
+        .map(|c| {
+            let author = c.author.as_ref().map_or("ghost", |a| a.login.as_str());
+            let date = c.created_at.get(..10).unwrap_or(&c.created_at);
+            let text = c.body.split_whitespace().collect::<Vec<_>>().join(" ");
+            let text = match text.char_indices().nth(MAX_COMMENT_CHARS) {
+                Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+                None => text,
+            };
+            format!("@{author} ({date}): {text}")
+        })
+        .collect()
+}
+
 /// Infer priority from GitHub labels.
 fn infer_priority(labels: &[GhLabel]) -> i32 {
     for label in labels {
@@ -240,6 +351,30 @@ fn gh_available() -> bool {
 ///
 /// `true` if successfully closed, `false` otherwise.
 pub fn close_github_issue(issue_number: i64, repo: Option<&str>) -> bool {
+    close_issue(issue_number, repo, None)
+}
+
+/// Close the GitHub issue behind a completed task.
+///
+/// Does nothing unless the source is a GitHub issue and sync-back is
+/// enabled. Leaves a comment naming the task so the closure is traceable.
+///
+/// # Returns
+///
+/// `true` if the issue was closed.
+pub fn close_github_task(source: &str, task_id: &str, config: &GithubConfig) -> bool {
+    if !config.sync_back || crate::config::is_read_only() {
+        return false;
+    }
+    let Some((repo, issue_number)) = parse_github_source(source) else {
+        return false;
+    };
+    let comment = format!("Completed by afk (task {task_id}).");
+    close_issue(issue_number, repo, Some(&comment))
+}
+
+/// Close an issue with `gh issue close`, optionally leaving a comment.
+fn close_issue(issue_number: i64, repo: Option<&str>, comment: Option<&str>) -> bool {
     if !gh_available() {
         return false;
     }
@@ -254,6 +389,9 @@ pub fn close_github_issue(issue_number: i64, repo: Option<&str>) -> bool {
             cmd.arg(format!("--repo={r}"));
         }
     }
+    if let Some(c) = comment {
+        cmd.args(["--comment", c]);
+    }
 
     match cmd.output() {
         Ok(output) => output.status.success(),
@@ -263,7 +401,8 @@ pub fn close_github_issue(issue_number: i64, repo: Option<&str>) -> bool {
 
 /// Parse a GitHub issue number from a source string.
 ///
-/// The source format is "github:#123" where 123 is the issue number.
+/// The source format is "github:#123" or "github:owner/repo#123" where 123
+/// is the issue number.
 ///
 /// # Arguments
 ///
@@ -273,10 +412,14 @@ pub fn close_github_issue(issue_number: i64, repo: Option<&str>) -> bool {
 ///
 /// The issue number if parsing succeeded, None otherwise.
 pub fn parse_github_issue_number(source: &str) -> Option<i64> {
-    if !source.starts_with("github:#") {
-        return None;
-    }
-    source.strip_prefix("github:#")?.parse().ok()
+    parse_github_source(source).map(|(_, number)| number)
+}
+
+/// Parse the repository (if any) and issue number from a source string.
+pub fn parse_github_source(source: &str) -> Option<(Option<&str>, i64)> {
+    let (repo, number) = source.strip_prefix("github:")?.rsplit_once('#')?;
+    let number = number.parse().ok()?;
+    Some(((!repo.is_empty()).then_some(repo), number))
 }
 
 #[cfg(test)]
@@ -388,15 +531,80 @@ Something else
             labels: vec![GhLabel {
                 name: "P1".to_string(),
             }],
+            assignees: Vec::new(),
             state: "open".to_string(),
+            comments: Vec::new(),
         };
 
-        let story = issue_to_story(issue);
+        let story = issue_to_story(issue, None);
         assert_eq!(story.id, "gh-42");
         assert_eq!(story.title, "Fix the bug");
         assert_eq!(story.priority, 1);
         assert_eq!(story.source, "github:#42");
+        assert_eq!(story.notes, "Labels: P1");
         assert!(!story.passes);
+        assert!(story.discussion.is_empty());
+    }
+
+    #[test]
+    fn test_issue_comments_become_discussion() {
+        let json = r#"{
+            "number": 9,
+            "title": "Rate limit login",
+            "comments": [
+                {"author": {"login": "alice"}, "body": "Use 5 attempts\n  per minute.", "createdAt": "2024-05-01T12:00:00Z"},
+                {"author": null, "body": "  ", "createdAt": "2024-05-02T08:00:00Z"},
+                {"body": "Actually make it 10.", "createdAt": "2024-05-03T09:30:00Z"}
+            ]
+        }"#;
+        let issue: GhIssue = serde_json::from_str(json).unwrap();
+
+        let story = issue_to_story(issue, None);
+        assert_eq!(
+            story.discussion,
+            vec![
+                "@alice (2024-05-01): Use 5 attempts per minute.",
+                "@ghost (2024-05-03): Actually make it 10."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_summarise_comments_truncates_long_text() {
+        let comment = GhComment {
+            author: Some(GhUser {
+                login: "bob".to_string(),
+            }),
+            body: "é".repeat(MAX_COMMENT_CHARS + 20),
+            created_at: String::new(),
+        };
+
+        let summary = summarise_comments(&[comment]);
+        assert!(summary[0].starts_with("@bob (): "));
+        assert!(summary[0].ends_with('…'));
+        assert_eq!(
+            summary[0].chars().count(),
+            "@bob (): ".len() + MAX_COMMENT_CHARS + 1
+        );
+    }
+
+    #[test]
+    fn test_issue_to_story_with_repo_and_assignees() {
+        let json = r#"{
+            "number": 7,
+            "title": "Add export",
+            "labels": [{"name": "feature"}, {"name": "low"}],
+            "assignees": [{"login": "octocat"}, {"login": "hubot"}]
+        }"#;
+        let issue: GhIssue = serde_json::from_str(json).unwrap();
+
+        let story = issue_to_story(issue, Some("acme/app"));
+        assert_eq!(story.source, "github:acme/app#7");
+        assert_eq!(story.priority, 3);
+        assert_eq!(
+            story.notes,
+            "Labels: feature, low\nAssignees: @octocat, @hubot"
+        );
     }
 
     #[test]
@@ -444,6 +652,25 @@ Something else
         assert!(parse_github_issue_number("").is_none());
     }
 
+    #[test]
+    fn test_parse_github_source() {
+        assert_eq!(parse_github_source("github:#42"), Some((None, 42)));
+        assert_eq!(
+            parse_github_source("github:acme/app#7"),
+            Some((Some("acme/app"), 7))
+        );
+        assert_eq!(parse_github_issue_number("github:acme/app#7"), Some(7));
+        assert!(parse_github_source("gitlab:#7").is_none());
+    }
+
+    #[test]
+    fn test_close_github_task_respects_sync_back() {
+        let config = GithubConfig { sync_back: false };
+        assert!(!close_github_task("github:#42", "gh-42", &config));
+        // Non-GitHub sources are never closed
+        assert!(!close_github_task("beads", "b-1", &GithubConfig::default()));
+    }
+
     #[test]
     fn test_close_github_issue_returns_false_when_gh_not_available() {
         // This test verifies graceful failure when gh is not available
@@ -0,0 +1,383 @@
+//! Jira project task source adapter.
+//!
+//! Searches a Jira site through the REST API (v2) for a project's open
+//! issues and converts each to a UserStory. Summaries become titles, and
+//! the source's property mapping picks the fields holding the description,
+//! priority, acceptance criteria, and status, by field name or ID, so
+//! criteria kept in a custom field are found.
+
+use super::{skip, SourceError};
+use crate::config::{PropertyMapping, SourceConfig};
+use crate::prd::UserStory;
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+/// Environment variable holding the site URL when the source has none.
+pub const URL_ENV: &str = "JIRA_URL";
+
+/// Environment variable holding the API token by default.
+pub const DEFAULT_TOKEN_ENV: &str = "JIRA_API_TOKEN";
+
+/// Environment variable holding the account email. With it, the token is
+/// sent as Jira Cloud basic auth; without it, as a bearer personal access
+/// token (Jira Data Center).
+pub const EMAIL_ENV: &str = "JIRA_EMAIL";
+
+/// Issues fetched per request.
+const PAGE_SIZE: usize = 100;
+
+/// Maximum issues fetched, as a guard against runaway pagination.
+const MAX_ISSUES: usize = 1000;
+
+/// Load tasks from a Jira project.
+///
+/// Needs a site URL (`url`, or `JIRA_URL`) and a token in `token_env`
+/// (default `JIRA_API_TOKEN`). Issues in the Done status category, or
+/// whose status is one of the mapping's done values, are skipped; `jql`
+/// and `labels` narrow the search further. Errors are reported as
+/// warnings and yield an empty list.
+pub fn load_jira_tasks(source: &SourceConfig) -> Vec<UserStory> {
+    try_load_jira_tasks(source).unwrap_or_else(skip)
+}
+
+/// Load tasks from a Jira project like [`load_jira_tasks`], returning what
+/// went wrong instead of warning.
+pub fn try_load_jira_tasks(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    let Some(jql) = build_jql(source) else {
+        return Err(SourceError::Misconfigured(
+            "Jira source has no project or JQL filter".into(),
+        ));
+    };
+    let Some(url) = source
+        .url
+        .clone()
+        .or_else(|| std::env::var(URL_ENV).ok())
+        .filter(|u| !u.trim().is_empty())
+    else {
+        return Err(SourceError::Misconfigured(format!(
+            "Jira source has no url and {URL_ENV} is not set"
+        )));
+    };
+    let token_env = source.token_env.as_deref().unwrap_or(DEFAULT_TOKEN_ENV);
+    let token = std::env::var(token_env).map_err(|_| SourceError::MissingEnv {
+        kind: "Jira",
+        var: token_env.to_string(),
+    })?;
+    let email = std::env::var(EMAIL_ENV).ok();
+
+    let (issues, names) =
+        search_issues(&url, &jql, email.as_deref(), &token).map_err(|e| SourceError::Failed {
+            kind: "Jira",
+            message: format!("search failed: {e}"),
+        })?;
+
+    let mapping = source.properties.clone().unwrap_or_default();
+    Ok(issues
+        .iter()
+        .filter_map(|issue| issue_to_story(issue, &names, &mapping))
+        .collect())
+}
+
+/// The JQL query for a source, or None if it names no project and no filter.
+fn build_jql(source: &SourceConfig) -> Option<String> {
+    let project = source.project.as_deref().filter(|p| !p.trim().is_empty());
+    let extra = source.jql.as_deref().filter(|j| !j.trim().is_empty());
+    if project.is_none() && extra.is_none() {
+        return None;
+    }
+
+    let mut clauses = Vec::new();
+    if let Some(project) = project {
+        clauses.push(format!("project = {}", quote(project.trim())));
+    }
+    clauses.push("statusCategory != Done".to_string());
+    if !source.labels.is_empty() {
+        let labels: Vec<String> = source.labels.iter().map(|l| quote(l)).collect();
+        clauses.push(format!("labels in ({})", labels.join(", ")));
+    }
+    if let Some(extra) = extra {
+        clauses.push(format!("({})", extra.trim()));
+    }
+    Some(clauses.join(" AND "))
+}
+
+/// Quote a JQL string value.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Fetch every issue matching a query, following pagination. Also returns
+/// the field ID to display name map used to resolve the property mapping.
+fn search_issues(
+    url: &str,
+    jql: &str,
+    email: Option<&str>,
+    token: &str,
+) -> Result<(Vec<Value>, Value), String> {
+    let client = Client::builder()
+        .user_agent(format!("afk/{}", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let endpoint = format!("{}/rest/api/2/search", url.trim_end_matches('/'));
+
+    let mut issues = Vec::new();
+    let mut names = Value::Null;
+    loop {
+        let request = client.get(&endpoint).query(&[
+            ("jql", jql),
+            ("startAt", &issues.len().to_string()),
+            ("maxResults", &PAGE_SIZE.to_string()),
+            ("fields", "*all"),
+            ("expand", "names"),
+        ]);
+        let request = match email {
+            Some(email) => request.basic_auth(email, Some(token)),
+            None => request.bearer_auth(token),
+        };
+
+        let response = request.send().map_err(|e| e.to_string())?;
+        let status = response.status();
+        let value: Value = response.json().map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            let message = value["errorMessages"][0]
+                .as_str()
+                .unwrap_or("request failed");
+            return Err(format!("{status}: {message}"));
+        }
+
+        if names.is_null() {
+            names = value["names"].clone();
+        }
+        let page = value["issues"].as_array().cloned().unwrap_or_default();
+        let fetched = page.len();
+        issues.extend(page);
+        let total = value["total"].as_u64().unwrap_or(0) as usize;
+        if fetched == 0 || issues.len() >= total {
+            break;
+        }
+        if issues.len() >= MAX_ISSUES {
+            eprintln!("Warning: Jira search matched more than {MAX_ISSUES} issues; truncating.");
+            break;
+        }
+    }
+
+    Ok((issues, names))
+}
+
+/// Convert a Jira issue to a UserStory, or None if it is done or has no key.
+fn issue_to_story(issue: &Value, names: &Value, mapping: &PropertyMapping) -> Option<UserStory> {
+    let key = issue["key"].as_str()?.to_string();
+    let fields = &issue["fields"];
+
+    let status = field(fields, names, &mapping.status).map_or_else(String::new, field_text);
+    if mapping
+        .done
+        .iter()
+        .any(|d| d.eq_ignore_ascii_case(status.trim()))
+    {
+        return None;
+    }
+
+    let title = field(fields, names, &mapping.title)
+        .map(field_text)
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| fields["summary"].as_str().map(ToOwned::to_owned))
+        .unwrap_or_else(|| key.clone())
+        .trim()
+        .to_string();
+    let description = field(fields, names, &mapping.description)
+        .map(field_text)
+        .unwrap_or_default();
+    let priority = field(fields, names, &mapping.priority)
+        .and_then(|value| match value.as_i64() {
+            Some(n) => Some((n as i32).clamp(1, 5)),
+            None => super::priority_from_name(&field_text(value)),
+        })
+        .unwrap_or(3);
+    let criteria = field(fields, names, &mapping.criteria)
+        .map(field_list)
+        .unwrap_or_default();
+    let acceptance_criteria = if criteria.is_empty() {
+        vec![format!("Complete: {title}").into()]
+    } else {
+        criteria.into_iter().map(Into::into).collect()
+    };
+
+    Some(UserStory {
+        id: key.clone(),
+        description: if description.trim().is_empty() {
+            title.clone()
+        } else {
+            description.trim().to_string()
+        },
+        title,
+        acceptance_criteria,
+        priority,
+        passes: false,
+        source: format!("jira:{key}"),
+        notes: String::new(),
+        effort: None,
+        estimate: None,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
+    })
+}
+
+/// Look up a field by ID, or by display name (case-insensitive) using the
+/// search's `names` map. Null fields count as missing.
+fn field<'a>(fields: &'a Value, names: &Value, property: &str) -> Option<&'a Value> {
+    let by_id = &fields[property];
+    if !by_id.is_null() {
+        return Some(by_id);
+    }
+    names
+        .as_object()?
+        .iter()
+        .find(|(_, name)| {
+            name.as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(property))
+        })
+        .map(|(id, _)| &fields[id.as_str()])
+        .filter(|value| !value.is_null())
+}
+
+/// Text of a field value: strings as they are, options and objects by
+/// their name or value, arrays one item per line.
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Array(items) => items.iter().map(field_text).collect::<Vec<_>>().join("\n"),
+        Value::Object(object) => ["name", "value", "displayName"]
+            .iter()
+            .find_map(|k| object.get(*k).and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// List items in a field: one per array entry, or one per line of text,
+/// with list markers (including Jira wiki `*` and `#`) stripped.
+fn field_list(value: &Value) -> Vec<String> {
+    let text = field_text(value);
+    text.lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(['-', '*', '#', '•'])
+                .trim_start()
+                .trim_start_matches("[ ]")
+                .trim()
+                .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names() -> Value {
+        json!({
+            "summary": "Summary",
+            "description": "Description",
+            "priority": "Priority",
+            "status": "Status",
+            "customfield_10042": "Acceptance Criteria"
+        })
+    }
+
+    fn sample_issue(status: &str) -> Value {
+        json!({
+            "key": "SHOP-12",
+            "fields": {
+                "summary": "Checkout with saved card",
+                "description": "Returning users pay in one click.",
+                "priority": {"name": "High"},
+                "status": {"name": status},
+                "customfield_10042": "* Card is charged\n* Receipt is emailed\n"
+            }
+        })
+    }
+
+    #[test]
+    fn test_issue_to_story() {
+        let mapping = PropertyMapping::default();
+        let story = issue_to_story(&sample_issue("To Do"), &names(), &mapping).unwrap();
+
+        assert_eq!(story.id, "SHOP-12");
+        assert_eq!(story.title, "Checkout with saved card");
+        assert_eq!(story.description, "Returning users pay in one click.");
+        assert_eq!(story.priority, 1);
+        let criteria: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        assert_eq!(criteria, vec!["Card is charged", "Receipt is emailed"]);
+        assert_eq!(story.source, "jira:SHOP-12");
+    }
+
+    #[test]
+    fn test_issue_mapping_by_field_id() {
+        let mapping = PropertyMapping {
+            criteria: "customfield_10050".to_string(),
+            priority: "customfield_10051".to_string(),
+            ..Default::default()
+        };
+        let mut issue = sample_issue("To Do");
+        issue["fields"]["customfield_10050"] =
+            json!([{"value": "Works offline"}, {"value": "Syncs later"}]);
+        issue["fields"]["customfield_10051"] = json!(4);
+
+        let story = issue_to_story(&issue, &Value::Null, &mapping).unwrap();
+        assert_eq!(story.priority, 4);
+        assert_eq!(story.acceptance_criteria.len(), 2);
+        assert_eq!(story.acceptance_criteria[1].text, "Syncs later");
+    }
+
+    #[test]
+    fn test_issue_in_done_status_is_skipped() {
+        let mapping = PropertyMapping {
+            done: vec!["Done".to_string(), "In Review".to_string()],
+            ..Default::default()
+        };
+        assert!(issue_to_story(&sample_issue("in review"), &names(), &mapping).is_none());
+        assert!(issue_to_story(&sample_issue("In Progress"), &names(), &mapping).is_some());
+    }
+
+    #[test]
+    fn test_build_jql() {
+        let mut source = SourceConfig::jira("SHOP");
+        assert_eq!(
+            build_jql(&source).unwrap(),
+            r#"project = "SHOP" AND statusCategory != Done"#
+        );
+
+        source.labels = vec!["afk".to_string()];
+        source.jql = Some("sprint in openSprints()".to_string());
+        assert_eq!(
+            build_jql(&source).unwrap(),
+            r#"project = "SHOP" AND statusCategory != Done AND labels in ("afk") AND (sprint in openSprints())"#
+        );
+
+        assert!(build_jql(&SourceConfig::jira("")).is_none());
+    }
+
+    #[test]
+    fn test_load_jira_tasks_without_credentials() {
+        let mut source = SourceConfig::jira("SHOP");
+        source.url = Some("https://example.atlassian.net".to_string());
+        source.token_env = Some("AFK_TEST_UNSET_JIRA_TOKEN".to_string());
+        assert!(load_jira_tasks(&source).is_empty());
+        assert!(load_jira_tasks(&SourceConfig::jira("")).is_empty());
+    }
+}
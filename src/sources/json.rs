@@ -2,7 +2,8 @@
 //!
 //! Loads tasks from JSON PRD files in various formats.
 
-use crate::prd::UserStory;
+use crate::prd::{AcceptanceCriterion, UserStory};
+use serde::Deserialize;
 use std::fs;
 use std::path::Path;
 
@@ -73,14 +74,15 @@ pub fn load_json_tasks(path: Option<&str>) -> Vec<UserStory> {
         Err(_) => return Vec::new(),
     };
 
-    // Extract items array
-    let items = extract_items(&data);
+    stories_from_value(&data, &format!("json:{}", file_path.display()))
+}
 
-    // Convert to UserStory, filtering out completed tasks
-    let source_str = format!("json:{}", file_path.display());
-    items
+/// Convert parsed JSON in any of the formats [`load_json_tasks`] accepts to
+/// stories, skipping those with `passes: true`.
+pub(crate) fn stories_from_value(data: &serde_json::Value, source: &str) -> Vec<UserStory> {
+    extract_items(data)
         .into_iter()
-        .filter_map(|item| parse_task_item(item, &source_str))
+        .filter_map(|item| parse_task_item(item, source))
         .collect()
 }
 
@@ -158,6 +160,37 @@ fn parse_task_item(item: &serde_json::Value, source: &str) -> Option<UserStory>
         .unwrap_or("")
         .to_string();
 
+    // Get effort estimate in iterations
+    let effort = item
+        .get("effort")
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok());
+
+    // Get size estimate in story points or minutes
+    let estimate = item
+        .get("estimate")
+        .or_else(|| item.get("storyPoints"))
+        .or_else(|| item.get("points"))
+        .and_then(|v| v.as_u64())
+        .and_then(|v| u32::try_from(v).ok());
+
+    // Get IDs of stories this one depends on
+    let depends_on = item
+        .get("dependsOn")
+        .or_else(|| item.get("depends_on"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Get the model and AI CLI the story's iterations should run with
+    let model = item.get("model").and_then(|v| v.as_str()).map(String::from);
+    let cli = item.get("cli").and_then(|v| v.as_str()).map(String::from);
+    let epic = item.get("epic").and_then(|v| v.as_str()).map(String::from);
+
     Some(UserStory {
         id,
         title,
@@ -167,6 +200,15 @@ fn parse_task_item(item: &serde_json::Value, source: &str) -> Option<UserStory>
         passes: false,
         source: source.to_string(),
         notes,
+        effort,
+        estimate,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on,
+        model,
+        cli,
+        epic,
+        generated: Vec::new(),
     })
 }
 
@@ -211,7 +253,9 @@ fn map_priority(priority: Option<&serde_json::Value>) -> i32 {
 }
 
 /// Extract acceptance criteria from various key names.
-fn extract_acceptance_criteria(item: &serde_json::Value, title: &str) -> Vec<String> {
+///
+/// Criteria already tracked as objects keep their passed flag and evidence.
+fn extract_acceptance_criteria(item: &serde_json::Value, title: &str) -> Vec<AcceptanceCriterion> {
     let criteria = item
         .get("acceptanceCriteria")
         .or_else(|| item.get("acceptance_criteria"))
@@ -219,19 +263,18 @@ fn extract_acceptance_criteria(item: &serde_json::Value, title: &str) -> Vec<Str
 
     match criteria {
         Some(serde_json::Value::Array(arr)) => {
-            let result: Vec<String> = arr
+            let result: Vec<AcceptanceCriterion> = arr
                 .iter()
-                .filter_map(|v| v.as_str())
-                .map(|s| s.to_string())
+                .filter_map(|v| AcceptanceCriterion::deserialize(v).ok())
                 .collect();
             if result.is_empty() {
-                vec![format!("Complete: {}", title)]
+                vec![format!("Complete: {}", title).into()]
             } else {
                 result
             }
         }
-        Some(serde_json::Value::String(s)) => vec![s.clone()],
-        _ => vec![format!("Complete: {}", title)],
+        Some(serde_json::Value::String(s)) => vec![s.as_str().into()],
+        _ => vec![format!("Complete: {}", title).into()],
     }
 }
 
@@ -258,6 +301,9 @@ mod tests {
                     "description": "Detailed description",
                     "priority": 1,
                     "acceptanceCriteria": ["Step 1", "Step 2"],
+                    "effort": 3,
+                    "model": "opus",
+                    "cli": "agent",
                     "passes": false
                 }
             ]
@@ -271,6 +317,9 @@ mod tests {
         assert_eq!(tasks[0].description, "Detailed description");
         assert_eq!(tasks[0].priority, 1);
         assert_eq!(tasks[0].acceptance_criteria, vec!["Step 1", "Step 2"]);
+        assert_eq!(tasks[0].effort, Some(3));
+        assert_eq!(tasks[0].model.as_deref(), Some("opus"));
+        assert_eq!(tasks[0].cli.as_deref(), Some("agent"));
         assert!(!tasks[0].passes);
     }
 
@@ -1,15 +1,21 @@
 //! Markdown checklist task source adapter.
 //!
-//! Loads tasks from markdown files with checkbox syntax.
+//! Loads tasks from markdown files with checkbox syntax, and with
+//! writeback enabled, checks a task's box again when it is completed.
 
+use crate::config::{SourceConfig, SourceType};
 use crate::prd::UserStory;
+use chrono::Local;
 use regex::Regex;
 use std::fs;
 use std::path::Path;
 use std::sync::LazyLock;
 
+/// Prefix of the `source` of tasks read from a markdown file.
+const SOURCE_PREFIX: &str = "markdown:";
+
 /// Default file paths to check if none specified.
-const DEFAULT_PATHS: &[&str] = &["tasks.md", "TODO.md", "prd.md", ".afk/tasks.md"];
+pub const DEFAULT_PATHS: &[&str] = &["tasks.md", "TODO.md", "prd.md", ".afk/tasks.md"];
 
 /// Regex pattern for markdown checkboxes.
 /// Matches: `- [ ]` or `- [x]` or `* [ ]` or `* [x]` with optional leading whitespace.
@@ -17,6 +23,11 @@ static CHECKBOX_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^[\s]*[-*]\s*\[([ xX])\]\s*(.+)$").expect("CHECKBOX_PATTERN regex is valid")
 });
 
+/// Regex pattern for headings, capturing the level (`#`s) and text.
+static HEADING_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(#{1,6})\s+(.+?)\s*#*\s*$").expect("HEADING_PATTERN regex is valid")
+});
+
 /// Regex pattern for priority tags like `[HIGH]`, `[LOW]`, `[P0]`, etc.
 static PRIORITY_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^\[([A-Z0-9]+)\]\s*(.+)$").expect("PRIORITY_PATTERN regex is valid")
@@ -35,6 +46,9 @@ static ID_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
 /// - `- [ ] [HIGH] Task with priority` - task with priority tag
 /// - `- [ ] task-id: Task with explicit ID` - task with explicit ID
 ///
+/// Tasks under a `## Heading` belong to that epic, until the next `##` or
+/// `#` heading; deeper headings don't change it.
+///
 /// # Arguments
 ///
 /// * `path` - Path to the markdown file. If None, tries default locations.
@@ -68,11 +82,20 @@ pub fn load_markdown_tasks(path: Option<&str>) -> Vec<UserStory> {
         Err(_) => return Vec::new(),
     };
 
-    let source_str = format!("markdown:{}", file_path.display());
+    let source_str = format!("{SOURCE_PREFIX}{}", file_path.display());
     let mut tasks = Vec::new();
 
     // Process each line
+    let mut epic: Option<String> = None;
     for line in contents.lines() {
+        if let Some(caps) = HEADING_PATTERN.captures(line) {
+            match caps[1].len() {
+                1 => epic = None,
+                2 => epic = Some(caps[2].to_string()),
+                _ => {}
+            }
+            continue;
+        }
         if let Some(caps) = CHECKBOX_PATTERN.captures(line) {
             let checkbox_state = caps.get(1).map(|m| m.as_str()).unwrap_or("");
             let text = caps.get(2).map(|m| m.as_str()).unwrap_or("").trim();
@@ -93,11 +116,20 @@ pub fn load_markdown_tasks(path: Option<&str>) -> Vec<UserStory> {
                 id: task_id,
                 title: title.clone(),
                 description: title.clone(),
-                acceptance_criteria: vec![format!("Complete: {}", title)],
+                acceptance_criteria: vec![format!("Complete: {}", title).into()],
                 priority,
                 passes: false,
                 source: source_str.clone(),
                 notes: String::new(),
+                effort: None,
+                estimate: None,
+                discussion: Vec::new(),
+                touched_at: None,
+                depends_on: Vec::new(),
+                model: None,
+                cli: None,
+                epic: epic.clone(),
+                generated: Vec::new(),
             });
         }
     }
@@ -105,6 +137,71 @@ pub fn load_markdown_tasks(path: Option<&str>) -> Vec<UserStory> {
     tasks
 }
 
+/// Check off a completed task in the markdown file it came from.
+///
+/// Only applies to tasks from a markdown source with writeback enabled.
+/// The first unchecked item with the task's ID gets `[x]` and a note
+/// saying when afk completed it. Failures are silently ignored, like the
+/// other writebacks.
+pub fn complete_markdown_task(source: &str, task_id: &str, sources: &[SourceConfig]) {
+    let Some(file) = source.strip_prefix(SOURCE_PREFIX) else {
+        return;
+    };
+    let enabled = sources.iter().any(|s| {
+        s.source_type == SourceType::Markdown
+            && s.writeback
+            && match s.path.as_deref() {
+                Some(path) => Path::new(path) == Path::new(file),
+                None => DEFAULT_PATHS.contains(&file),
+            }
+    });
+    if !enabled || crate::config::is_read_only() {
+        return;
+    }
+
+    let Ok(contents) = fs::read_to_string(file) else {
+        return;
+    };
+    let note = format!("(completed by afk {})", Local::now().format("%Y-%m-%d"));
+    if let Some(updated) = check_off(&contents, task_id, &note) {
+        let _ = fs::write(file, updated);
+    }
+}
+
+/// `contents` with the first unchecked item for `task_id` checked and
+/// `note` appended to it, or None if there is no such item.
+fn check_off(contents: &str, task_id: &str, note: &str) -> Option<String> {
+    let mut found = false;
+    let mut updated = String::with_capacity(contents.len() + note.len() + 1);
+    for line in contents.split_inclusive('\n') {
+        let (text, ending) = match line.strip_suffix("\r\n") {
+            Some(text) => (text, "\r\n"),
+            None => match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            },
+        };
+        let item = (!found)
+            .then(|| CHECKBOX_PATTERN.captures(text))
+            .flatten()
+            .filter(|caps| &caps[1] == " " && parse_task_line(caps[2].trim()).0 == task_id);
+        match item {
+            Some(caps) => {
+                let box_at = caps.get(1).map(|m| m.start()).unwrap_or_default();
+                updated.push_str(&text[..box_at]);
+                updated.push('x');
+                updated.push_str(text[box_at + 1..].trim_end());
+                updated.push(' ');
+                updated.push_str(note);
+                updated.push_str(ending);
+                found = true;
+            }
+            None => updated.push_str(line),
+        }
+    }
+    found.then_some(updated)
+}
+
 /// Parse a task line to extract ID, title, and priority.
 ///
 /// Returns (id, title, priority).
@@ -290,6 +387,80 @@ mod tests {
         assert_eq!(tasks[1].title, "More indented task");
     }
 
+    #[test]
+    fn test_load_markdown_tasks_epics_from_headings() {
+        let temp = TempDir::new().unwrap();
+        let content = r#"
+# Roadmap
+
+- [ ] Loose task
+
+## Authentication
+
+- [ ] Login form
+### Details
+- [ ] Password reset
+
+## Billing ##
+- [ ] Invoices
+
+# Later
+- [ ] Someday
+"#;
+        let path = write_markdown_file(&temp, "tasks.md", content);
+
+        let tasks = load_markdown_tasks(Some(path.to_str().unwrap()));
+        let epics: Vec<Option<&str>> = tasks.iter().map(|t| t.epic.as_deref()).collect();
+        assert_eq!(
+            epics,
+            [
+                None,
+                Some("Authentication"),
+                Some("Authentication"),
+                Some("Billing"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_off() {
+        let contents =
+            "# TODO\r\n- [x] Done already\r\n  - [ ] [HIGH] auth: Add login\r\n- [ ] Add login\r\n";
+        let updated = check_off(contents, "auth", "(done)").unwrap();
+        assert_eq!(
+            updated,
+            "# TODO\r\n- [x] Done already\r\n  - [x] [HIGH] auth: Add login (done)\r\n- [ ] Add login\r\n"
+        );
+        assert!(check_off(contents, "missing", "(done)").is_none());
+        assert!(check_off(contents, "done-already", "(done)").is_none());
+
+        // Generated IDs work too, and only the first match is checked
+        let contents = "- [ ] Write docs\n- [ ] Write docs";
+        assert_eq!(
+            check_off(contents, "write-docs", "(done)").unwrap(),
+            "- [x] Write docs (done)\n- [ ] Write docs"
+        );
+    }
+
+    #[test]
+    fn test_complete_markdown_task_needs_writeback() {
+        let temp = TempDir::new().unwrap();
+        let path = write_markdown_file(&temp, "TODO.md", "- [ ] First task\n");
+        let path_str = path.to_str().unwrap();
+        let source = format!("markdown:{path_str}");
+        let mut config = SourceConfig::markdown(path_str);
+
+        complete_markdown_task(&source, "first-task", std::slice::from_ref(&config));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [ ] First task\n");
+
+        config.writeback = true;
+        complete_markdown_task(&source, "first-task", &[config]);
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("- [x] First task (completed by afk "));
+        assert!(load_markdown_tasks(Some(path_str)).is_empty());
+    }
+
     #[test]
     fn test_load_markdown_tasks_missing_file() {
         let tasks = load_markdown_tasks(Some("/nonexistent/file.md"));
@@ -1,22 +1,78 @@
 //! Task source adapters.
 //!
-//! This module aggregates tasks from various sources (beads, json, markdown, github, openspec).
+//! This module aggregates tasks from various sources (beads, json, markdown, github, openspec,
+//! stories-dir, notion, trello, jira, csv spreadsheets, and external commands).
 
 pub mod beads;
+pub mod command;
+pub mod csv;
 pub mod github;
+pub mod jira;
 pub mod json;
 pub mod markdown;
+pub mod notion;
 pub mod openspec;
-
-pub use beads::{close_beads_issue, load_beads_tasks, start_beads_issue};
-pub use github::{close_github_issue, load_github_tasks, parse_github_issue_number};
+pub mod schedule;
+pub mod stories_dir;
+pub mod trello;
+
+pub use beads::{
+    close_beads_issue, complete_beads_task, fail_beads_task, load_beads_tasks, start_beads_issue,
+};
+pub use command::{load_command_tasks, try_load_command_tasks};
+pub use csv::{load_csv_tasks, try_load_csv_tasks};
+pub use github::{
+    close_github_issue, close_github_task, load_github_tasks, parse_github_issue_number,
+    parse_github_source, try_load_github_tasks,
+};
+pub use jira::{load_jira_tasks, try_load_jira_tasks};
 pub use json::load_json_tasks;
-pub use markdown::load_markdown_tasks;
+pub use markdown::{complete_markdown_task, load_markdown_tasks};
+pub use notion::{load_notion_tasks, try_load_notion_tasks};
 pub use openspec::load_openspec_tasks;
+pub use schedule::{is_poll_due, should_sync_on_change, ActiveHours};
+pub use stories_dir::{complete_story_file, load_stories_dir_tasks};
+pub use trello::{load_trello_tasks, try_load_trello_tasks};
 
 use crate::config::{SourceConfig, SourceType};
 use crate::prd::UserStory;
 
+/// Why a source yielded no tasks.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SourceError {
+    /// The source config is missing a setting it needs.
+    #[error("{0}")]
+    Misconfigured(String),
+    /// A credential the source reads from the environment isn't set.
+    #[error("{var} not set for the {kind} source")]
+    MissingEnv {
+        /// Kind of source, e.g. "Jira".
+        kind: &'static str,
+        /// The environment variable.
+        var: String,
+    },
+    /// A tool the source runs isn't installed.
+    #[error("{0} not available")]
+    Unavailable(&'static str),
+    /// Fetching the tasks failed.
+    #[error("{kind} source failed: {message}")]
+    Failed {
+        /// Kind of source, e.g. "GitHub".
+        kind: &'static str,
+        /// What went wrong.
+        message: String,
+    },
+}
+
+/// Tasks from every source that loaded, and what went wrong with the rest.
+#[derive(Debug, Default)]
+pub struct CollectedTasks {
+    /// Tasks, in source order.
+    pub tasks: Vec<UserStory>,
+    /// Errors from sources that yielded nothing.
+    pub errors: Vec<SourceError>,
+}
+
 /// Aggregate tasks from all configured sources.
 ///
 /// Dispatches to the appropriate loader based on source type, concatenates
@@ -45,15 +101,75 @@ use crate::prd::UserStory;
 /// ```
 #[must_use]
 pub fn aggregate_tasks(sources: &[SourceConfig]) -> Vec<UserStory> {
-    sources.iter().flat_map(load_from_source).collect()
+    let collected = collect_tasks(sources);
+    for error in collected.errors {
+        skip(error);
+    }
+    collected.tasks
+}
+
+/// Load tasks from every source, stopping at the first that fails.
+///
+/// For callers that want to handle source errors themselves rather than
+/// have them printed as warnings, as [`aggregate_tasks`] does.
+pub fn load_tasks(sources: &[SourceConfig]) -> Result<Vec<UserStory>, SourceError> {
+    let mut tasks = Vec::new();
+    for source in sources {
+        tasks.extend(try_load_from_source(source)?);
+    }
+    Ok(tasks)
+}
+
+/// Load tasks from every source, carrying on past those that fail.
+#[must_use]
+pub fn collect_tasks(sources: &[SourceConfig]) -> CollectedTasks {
+    let mut collected = CollectedTasks::default();
+    for source in sources {
+        match try_load_from_source(source) {
+            Ok(tasks) => collected.tasks.extend(tasks),
+            Err(e) => collected.errors.push(e),
+        }
+    }
+    collected
+}
+
+/// Warn that a source was skipped, yielding no tasks.
+pub(crate) fn skip(error: SourceError) -> Vec<UserStory> {
+    eprintln!("Warning: {error}. Skipping.");
+    Vec::new()
+}
+
+/// Whether a story's `source` string came from a configured source.
+///
+/// Matches on the source type's prefix and, where the source names a file,
+/// directory, or repository, on that too.
+#[must_use]
+pub fn source_owns(source: &SourceConfig, story_source: &str) -> bool {
+    let (prefix, key) = match source.source_type {
+        SourceType::Beads => return story_source == "beads",
+        SourceType::Json => ("json:", source.path.as_deref()),
+        SourceType::Markdown => ("markdown:", source.path.as_deref()),
+        SourceType::Github => ("github:", source.repo.as_deref()),
+        SourceType::Openspec => ("openspec:", None),
+        SourceType::StoriesDir => ("stories-dir:", source.path.as_deref()),
+        SourceType::Notion => ("notion:", None),
+        SourceType::Trello => ("trello:", None),
+        SourceType::Jira => ("jira:", source.project.as_deref()),
+        SourceType::Command => ("command:", source.path.as_deref()),
+        SourceType::Csv => ("csv:", source.path.as_deref()),
+    };
+    story_source
+        .strip_prefix(prefix)
+        .is_some_and(|rest| key.map_or(true, |k| rest.contains(k)))
 }
 
 /// Load tasks from a single source.
 ///
-/// Dispatches to the appropriate loader based on source type. Each loader
-/// handles its own errors gracefully and returns an empty vector on failure.
-fn load_from_source(source: &SourceConfig) -> Vec<UserStory> {
-    match source.source_type {
+/// Dispatches to the appropriate loader based on source type. File-based
+/// sources that are missing or unreadable yield no tasks rather than an
+/// error.
+pub fn try_load_from_source(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    Ok(match source.source_type {
         SourceType::Beads => load_beads_tasks(),
         SourceType::Json => {
             let path = source.path.as_deref();
@@ -65,9 +181,31 @@ fn load_from_source(source: &SourceConfig) -> Vec<UserStory> {
         }
         SourceType::Github => {
             let repo = source.repo.as_deref();
-            load_github_tasks(repo, &source.labels)
+            try_load_github_tasks(repo, &source.labels, source.comments.unwrap_or(0))?
         }
         SourceType::Openspec => load_openspec_tasks(),
+        SourceType::StoriesDir => load_stories_dir_tasks(source.path.as_deref()),
+        SourceType::Notion => try_load_notion_tasks(source)?,
+        SourceType::Trello => try_load_trello_tasks(source)?,
+        SourceType::Jira => try_load_jira_tasks(source)?,
+        SourceType::Command => try_load_command_tasks(source)?,
+        SourceType::Csv => try_load_csv_tasks(source)?,
+    })
+}
+
+/// Map a priority name from an external tool ("High", "P1", "Low") to afk's
+/// 1-5 scale. Returns None for names that don't look like a priority.
+pub(crate) fn priority_from_name(name: &str) -> Option<i32> {
+    let name = name.trim().to_lowercase();
+    if let Ok(n) = name.parse::<i32>() {
+        return Some(n.clamp(1, 5));
+    }
+    match name.as_str() {
+        "p0" | "p1" | "critical" | "urgent" | "highest" | "high" => Some(1),
+        "p2" | "medium" | "normal" => Some(2),
+        "p3" | "low" | "minor" => Some(4),
+        "p4" | "lowest" | "trivial" => Some(5),
+        _ => None,
     }
 }
 
@@ -77,6 +215,47 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_collect_and_load_tasks_report_failed_sources() {
+        let temp = TempDir::new().unwrap();
+        let json_path = temp.path().join("tasks.json");
+        fs::write(&json_path, r#"[{"id": "a", "title": "A"}]"#).unwrap();
+        let mut broken = SourceConfig::command("");
+        broken.path = None;
+        let sources = vec![SourceConfig::json(json_path.to_str().unwrap()), broken];
+
+        let collected = collect_tasks(&sources);
+        assert_eq!(collected.tasks.len(), 1);
+        assert_eq!(
+            collected.errors,
+            [SourceError::Misconfigured(
+                "Command source has no path".to_string()
+            )]
+        );
+        assert_eq!(aggregate_tasks(&sources).len(), 1);
+        assert!(matches!(
+            load_tasks(&sources),
+            Err(SourceError::Misconfigured(_))
+        ));
+        assert_eq!(load_tasks(&sources[..1]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_source_error_display() {
+        let err = SourceError::MissingEnv {
+            kind: "Jira",
+            var: "JIRA_API_TOKEN".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "JIRA_API_TOKEN not set for the Jira source"
+        );
+        assert_eq!(
+            SourceError::Unavailable("gh CLI").to_string(),
+            "gh CLI not available"
+        );
+    }
+
     #[test]
     fn test_aggregate_tasks_empty_sources() {
         let sources: Vec<SourceConfig> = vec![];
@@ -201,7 +380,7 @@ mod tests {
     fn test_load_from_source_openspec() {
         let source = SourceConfig::openspec();
         // OpenSpec loader may return empty if openspec/ doesn't exist
-        let _tasks = load_from_source(&source);
+        let _tasks = try_load_from_source(&source);
     }
 
     #[test]
@@ -302,7 +481,7 @@ mod tests {
     fn test_load_from_source_beads() {
         let source = SourceConfig::beads();
         // Beads loader may return empty if `bd` is not installed
-        let _tasks = load_from_source(&source);
+        let _tasks = try_load_from_source(&source);
     }
 
     #[test]
@@ -313,7 +492,7 @@ mod tests {
         fs::write(&json_path, json_content).unwrap();
 
         let source = SourceConfig::json(json_path.to_str().unwrap());
-        let tasks = load_from_source(&source);
+        let tasks = try_load_from_source(&source).unwrap();
 
         assert_eq!(tasks.len(), 1);
         assert_eq!(tasks[0].id, "task-1");
@@ -322,14 +501,9 @@ mod tests {
     #[test]
     fn test_load_from_source_json_no_path() {
         // When no path is specified, tries default locations
-        let source = SourceConfig {
-            source_type: SourceType::Json,
-            path: None,
-            repo: None,
-            labels: Vec::new(),
-        };
+        let source = SourceConfig::new(SourceType::Json);
         // Should return empty if no default files exist
-        let _tasks = load_from_source(&source);
+        let _tasks = try_load_from_source(&source);
     }
 
     #[test]
@@ -340,7 +514,7 @@ mod tests {
         fs::write(&md_path, md_content).unwrap();
 
         let source = SourceConfig::markdown(md_path.to_str().unwrap());
-        let tasks = load_from_source(&source);
+        let tasks = try_load_from_source(&source).unwrap();
 
         assert_eq!(tasks.len(), 2);
     }
@@ -349,7 +523,33 @@ mod tests {
     fn test_load_from_source_github() {
         let source = SourceConfig::github("owner/repo", vec![]);
         // GitHub loader returns empty if gh not available
-        let _tasks = load_from_source(&source);
+        let _tasks = try_load_from_source(&source);
+    }
+
+    #[test]
+    fn test_priority_from_name() {
+        assert_eq!(priority_from_name("High"), Some(1));
+        assert_eq!(priority_from_name(" P2 "), Some(2));
+        assert_eq!(priority_from_name("low"), Some(4));
+        assert_eq!(priority_from_name("9"), Some(5));
+        assert_eq!(priority_from_name("frontend"), None);
+    }
+
+    #[test]
+    fn test_source_owns() {
+        let todo = SourceConfig::markdown("TODO.md");
+        assert!(source_owns(&todo, "markdown:TODO.md"));
+        assert!(!source_owns(&todo, "markdown:docs/BACKLOG.md"));
+        assert!(!source_owns(&todo, "json:TODO.md"));
+
+        assert!(source_owns(&SourceConfig::beads(), "beads"));
+        let github = SourceConfig::github("acme/api", Vec::new());
+        assert!(source_owns(&github, "github:acme/api#12"));
+        assert!(!source_owns(&github, "github:#12"));
+
+        let tracker = SourceConfig::command("./scripts/tracker.sh");
+        assert!(source_owns(&tracker, "command:./scripts/tracker.sh"));
+        assert!(!source_owns(&tracker, "command:./other.sh"));
     }
 
     #[test]
@@ -0,0 +1,318 @@
+//! Notion database task source adapter.
+//!
+//! Queries a Notion database through the Notion API and converts each page
+//! to a UserStory, using the source's property mapping to find the title,
+//! description, priority, criteria, and status.
+
+use super::{skip, SourceError};
+use crate::config::{PropertyMapping, SourceConfig};
+use crate::prd::UserStory;
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+
+/// Notion API base URL.
+const NOTION_API_URL: &str = "https://api.notion.com/v1";
+
+/// Notion API version sent with every request.
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// Environment variable holding the integration token by default.
+pub const DEFAULT_TOKEN_ENV: &str = "NOTION_TOKEN";
+
+/// Maximum pages fetched, as a guard against runaway pagination.
+const MAX_PAGES: usize = 1000;
+
+/// Load tasks from a Notion database.
+///
+/// Reads the integration token from `token_env` (default `NOTION_TOKEN`).
+/// Pages whose status is one of the mapping's done values are skipped.
+/// Errors are reported as warnings and yield an empty list.
+pub fn load_notion_tasks(source: &SourceConfig) -> Vec<UserStory> {
+    try_load_notion_tasks(source).unwrap_or_else(skip)
+}
+
+/// Load tasks from a Notion database like [`load_notion_tasks`], returning
+/// what went wrong instead of warning.
+pub fn try_load_notion_tasks(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    let Some(database) = source.database.as_deref().filter(|d| !d.is_empty()) else {
+        return Err(SourceError::Misconfigured(
+            "Notion source has no database ID".into(),
+        ));
+    };
+    let token_env = source.token_env.as_deref().unwrap_or(DEFAULT_TOKEN_ENV);
+    let token = std::env::var(token_env).map_err(|_| SourceError::MissingEnv {
+        kind: "Notion",
+        var: token_env.to_string(),
+    })?;
+
+    let pages = query_database(database, &token).map_err(|e| SourceError::Failed {
+        kind: "Notion",
+        message: format!("query failed: {e}"),
+    })?;
+
+    let mapping = source.properties.clone().unwrap_or_default();
+    Ok(pages
+        .iter()
+        .filter_map(|page| page_to_story(page, &mapping))
+        .collect())
+}
+
+/// Fetch every page in a database, following pagination cursors.
+fn query_database(database: &str, token: &str) -> Result<Vec<Value>, String> {
+    let client = Client::builder()
+        .user_agent(format!("afk/{}", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = format!("{NOTION_API_URL}/databases/{database}/query");
+
+    let mut pages = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut body = json!({ "page_size": 100 });
+        if let Some(c) = &cursor {
+            body["start_cursor"] = json!(c);
+        }
+
+        let response = client
+            .post(&url)
+            .bearer_auth(token)
+            .header("Notion-Version", NOTION_VERSION)
+            .json(&body)
+            .send()
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let value: Value = response.json().map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            let message = value["message"].as_str().unwrap_or("request failed");
+            return Err(format!("{status}: {message}"));
+        }
+
+        if let Some(results) = value["results"].as_array() {
+            pages.extend(results.iter().cloned());
+        }
+        cursor = value["next_cursor"].as_str().map(ToOwned::to_owned);
+        if !value["has_more"].as_bool().unwrap_or(false) || cursor.is_none() {
+            break;
+        }
+        if pages.len() >= MAX_PAGES {
+            eprintln!("Warning: Notion database has more than {MAX_PAGES} pages; truncating.");
+            break;
+        }
+    }
+
+    Ok(pages)
+}
+
+/// Convert a Notion page to a UserStory, or None if it is done or untitled.
+fn page_to_story(page: &Value, mapping: &PropertyMapping) -> Option<UserStory> {
+    if page["archived"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let properties = &page["properties"];
+
+    let status = property_text(&properties[&mapping.status]);
+    if mapping
+        .done
+        .iter()
+        .any(|d| d.eq_ignore_ascii_case(status.trim()))
+    {
+        return None;
+    }
+
+    let title = property_text(&properties[&mapping.title])
+        .trim()
+        .to_string();
+    if title.is_empty() {
+        return None;
+    }
+
+    let page_id = page["id"].as_str()?.to_string();
+    let short_id: String = page_id.chars().filter(|c| *c != '-').take(8).collect();
+
+    let description = property_text(&properties[&mapping.description]);
+    let priority = property_priority(&properties[&mapping.priority]);
+    let criteria = property_list(&properties[&mapping.criteria]);
+    let acceptance_criteria = if criteria.is_empty() {
+        vec![format!("Complete: {title}").into()]
+    } else {
+        criteria.into_iter().map(Into::into).collect()
+    };
+
+    Some(UserStory {
+        id: format!("notion-{short_id}"),
+        description: if description.trim().is_empty() {
+            title.clone()
+        } else {
+            description.trim().to_string()
+        },
+        title,
+        acceptance_criteria,
+        priority: priority.unwrap_or(3),
+        passes: false,
+        source: format!("notion:{page_id}"),
+        notes: String::new(),
+        effort: None,
+        estimate: None,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
+    })
+}
+
+/// Plain text of a property, whatever its type.
+fn property_text(property: &Value) -> String {
+    match property["type"].as_str().unwrap_or("") {
+        kind @ ("title" | "rich_text") => property[kind]
+            .as_array()
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p["plain_text"].as_str())
+                    .collect::<String>()
+            })
+            .unwrap_or_default(),
+        kind @ ("select" | "status") => property[kind]["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        "multi_select" => property_list(property).join(", "),
+        "number" => property["number"]
+            .as_f64()
+            .map(|n| n.to_string())
+            .unwrap_or_default(),
+        "checkbox" => {
+            if property["checkbox"].as_bool().unwrap_or(false) {
+                "Done".to_string()
+            } else {
+                String::new()
+            }
+        }
+        _ => String::new(),
+    }
+}
+
+/// Priority from a number or a named select/status option.
+fn property_priority(property: &Value) -> Option<i32> {
+    if let Some(n) = property["number"].as_f64() {
+        return Some((n.round() as i32).clamp(1, 5));
+    }
+    super::priority_from_name(&property_text(property))
+}
+
+/// List items from a multi-select, or lines of a text property.
+fn property_list(property: &Value) -> Vec<String> {
+    if let Some(options) = property["multi_select"].as_array() {
+        return options
+            .iter()
+            .filter_map(|o| o["name"].as_str())
+            .map(ToOwned::to_owned)
+            .collect();
+    }
+    property_text(property)
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(['-', '*', '•'])
+                .trim_start()
+                .trim_start_matches("[ ]")
+                .trim()
+                .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_page(status: &str) -> Value {
+        json!({
+            "id": "59833787-2cf9-4fdf-8782-e53db20768a5",
+            "archived": false,
+            "properties": {
+                "Name": {"type": "title", "title": [
+                    {"plain_text": "Add "}, {"plain_text": "password reset"}
+                ]},
+                "Description": {"type": "rich_text", "rich_text": [
+                    {"plain_text": "Users can reset by email."}
+                ]},
+                "Priority": {"type": "select", "select": {"name": "High"}},
+                "Acceptance Criteria": {"type": "rich_text", "rich_text": [
+                    {"plain_text": "- Email is sent\n- Link expires after 1h\n"}
+                ]},
+                "Status": {"type": "status", "status": {"name": status}}
+            }
+        })
+    }
+
+    #[test]
+    fn test_page_to_story() {
+        let story =
+            page_to_story(&sample_page("In progress"), &PropertyMapping::default()).unwrap();
+
+        assert_eq!(story.id, "notion-59833787");
+        assert_eq!(story.title, "Add password reset");
+        assert_eq!(story.description, "Users can reset by email.");
+        assert_eq!(story.priority, 1);
+        let criteria: Vec<&str> = story
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        assert_eq!(criteria, vec!["Email is sent", "Link expires after 1h"]);
+        assert_eq!(story.source, "notion:59833787-2cf9-4fdf-8782-e53db20768a5");
+    }
+
+    #[test]
+    fn test_page_to_story_skips_done() {
+        let mapping = PropertyMapping::default();
+        assert!(page_to_story(&sample_page("Done"), &mapping).is_none());
+
+        let mapping = PropertyMapping {
+            done: vec!["Shipped".to_string()],
+            ..Default::default()
+        };
+        assert!(page_to_story(&sample_page("Done"), &mapping).is_some());
+        assert!(page_to_story(&sample_page("shipped"), &mapping).is_none());
+    }
+
+    #[test]
+    fn test_custom_property_mapping() {
+        let page = json!({
+            "id": "abc",
+            "properties": {
+                "Story": {"type": "title", "title": [{"plain_text": "Export CSV"}]},
+                "Rank": {"type": "number", "number": 2},
+                "Checks": {"type": "multi_select", "multi_select": [
+                    {"name": "Header row"}, {"name": "UTF-8"}
+                ]}
+            }
+        });
+        let mapping = PropertyMapping {
+            title: "Story".to_string(),
+            priority: "Rank".to_string(),
+            criteria: "Checks".to_string(),
+            ..Default::default()
+        };
+
+        let story = page_to_story(&page, &mapping).unwrap();
+        assert_eq!(story.title, "Export CSV");
+        assert_eq!(story.description, "Export CSV");
+        assert_eq!(story.priority, 2);
+        assert_eq!(story.acceptance_criteria.len(), 2);
+    }
+
+    #[test]
+    fn test_load_notion_tasks_without_token() {
+        let mut source = SourceConfig::notion("db");
+        source.token_env = Some("AFK_TEST_UNSET_NOTION_TOKEN".to_string());
+        assert!(load_notion_tasks(&source).is_empty());
+        assert!(load_notion_tasks(&SourceConfig::notion("")).is_empty());
+    }
+}
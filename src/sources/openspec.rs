@@ -145,7 +145,10 @@ fn load_change_tasks(change_path: &Path, change_id: &str) -> Vec<UserStory> {
             );
 
             // Build acceptance criteria from specs if available
-            let acceptance_criteria = build_acceptance_criteria(text, &specs);
+            let acceptance_criteria = build_acceptance_criteria(text, &specs)
+                .into_iter()
+                .map(Into::into)
+                .collect();
 
             tasks.push(UserStory {
                 id: task_id,
@@ -156,6 +159,15 @@ fn load_change_tasks(change_path: &Path, change_id: &str) -> Vec<UserStory> {
                 passes: false,
                 source: source_str.clone(),
                 notes: String::new(),
+                effort: None,
+                estimate: None,
+                discussion: Vec::new(),
+                touched_at: None,
+                depends_on: Vec::new(),
+                model: None,
+                cli: None,
+                epic: None,
+                generated: Vec::new(),
             });
         }
     }
@@ -459,7 +471,7 @@ Users MUST provide a second factor during login.
         assert!(tasks[0]
             .acceptance_criteria
             .iter()
-            .any(|ac| ac.contains("OTP required")));
+            .any(|ac| ac.as_str().contains("OTP required")));
     }
 
     #[test]
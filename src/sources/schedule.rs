@@ -0,0 +1,168 @@
+//! Per-source sync scheduling for watch mode.
+//!
+//! API-backed sources (github, beads) are polled on an interval, while
+//! file-based sources (json, markdown) sync as soon as their files change.
+//! Any source can be restricted to an active-hours window so nothing syncs
+//! while humans are editing.
+
+use chrono::{DateTime, Local, NaiveTime};
+
+use crate::config::{SourceConfig, SourceType};
+
+/// Default minutes between polls for API-backed sources.
+pub const DEFAULT_POLL_MINUTES: u32 = 10;
+
+/// A daily local-time window, possibly wrapping midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActiveHours {
+    /// Start of the window (inclusive).
+    pub start: NaiveTime,
+    /// End of the window (exclusive).
+    pub end: NaiveTime,
+}
+
+impl ActiveHours {
+    /// Parse a window in "HH:MM-HH:MM" format.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (start, end) = s.split_once('-')?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+        Some(Self { start, end })
+    }
+
+    /// Whether a time falls inside the window.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            // Window wraps midnight, e.g. 18:00-08:00
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Whether a source syncs on file changes rather than by polling.
+pub fn is_event_driven(source: &SourceConfig) -> bool {
+    source.poll_minutes.is_none()
+        && matches!(
+            source.source_type,
+            SourceType::Json | SourceType::Markdown | SourceType::Csv
+        )
+}
+
+/// Whether a source is inside its active hours at `now`.
+///
+/// Sources without `active_hours` are always active. Config loading
+/// rejects values that don't parse, so those never get here.
+pub fn is_active(source: &SourceConfig, now: DateTime<Local>) -> bool {
+    match source.active_hours.as_deref().and_then(ActiveHours::parse) {
+        Some(hours) => hours.contains(now.time()),
+        None => true,
+    }
+}
+
+/// Whether a polled source is due for a sync.
+///
+/// Event-driven sources are never polled.
+pub fn is_poll_due(
+    source: &SourceConfig,
+    last_sync: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> bool {
+    if is_event_driven(source) || !is_active(source, now) {
+        return false;
+    }
+
+    let interval = source.poll_minutes.unwrap_or(DEFAULT_POLL_MINUTES);
+    match last_sync {
+        Some(last) => now - last >= chrono::Duration::minutes(i64::from(interval)),
+        None => true,
+    }
+}
+
+/// Whether a file change should trigger a sync for this source.
+pub fn should_sync_on_change(source: &SourceConfig, now: DateTime<Local>) -> bool {
+    is_event_driven(source) && is_active(source, now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2024, 6, 3, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_active_hours_parse() {
+        let hours = ActiveHours::parse("09:00-17:30").unwrap();
+        assert_eq!(hours.start, time(9, 0));
+        assert_eq!(hours.end, time(17, 30));
+
+        assert!(ActiveHours::parse("9am-5pm").is_none());
+        assert!(ActiveHours::parse("09:00").is_none());
+    }
+
+    #[test]
+    fn test_active_hours_contains() {
+        let day = ActiveHours::parse("09:00-17:00").unwrap();
+        assert!(day.contains(time(9, 0)));
+        assert!(day.contains(time(12, 0)));
+        assert!(!day.contains(time(17, 0)));
+        assert!(!day.contains(time(20, 0)));
+
+        let night = ActiveHours::parse("18:00-08:00").unwrap();
+        assert!(night.contains(time(23, 0)));
+        assert!(night.contains(time(3, 0)));
+        assert!(!night.contains(time(12, 0)));
+    }
+
+    #[test]
+    fn test_file_sources_are_event_driven() {
+        assert!(is_event_driven(&SourceConfig::markdown("TODO.md")));
+        assert!(is_event_driven(&SourceConfig::json("tasks.json")));
+        assert!(!is_event_driven(&SourceConfig::github(
+            "owner/repo",
+            vec![]
+        )));
+        assert!(!is_event_driven(&SourceConfig::beads()));
+
+        let mut polled = SourceConfig::markdown("TODO.md");
+        polled.poll_minutes = Some(5);
+        assert!(!is_event_driven(&polled));
+    }
+
+    #[test]
+    fn test_is_poll_due_interval() {
+        let mut source = SourceConfig::github("owner/repo", vec![]);
+        assert!(is_poll_due(&source, None, at(12, 0)));
+        assert!(!is_poll_due(&source, Some(at(12, 0)), at(12, 5)));
+        assert!(is_poll_due(&source, Some(at(12, 0)), at(12, 10)));
+
+        source.poll_minutes = Some(30);
+        assert!(!is_poll_due(&source, Some(at(12, 0)), at(12, 10)));
+        assert!(is_poll_due(&source, Some(at(12, 0)), at(12, 30)));
+    }
+
+    #[test]
+    fn test_quiet_hours_block_syncing() {
+        let mut github = SourceConfig::github("owner/repo", vec![]);
+        github.active_hours = Some("18:00-08:00".to_string());
+        assert!(!is_poll_due(&github, None, at(12, 0)));
+        assert!(is_poll_due(&github, None, at(19, 0)));
+
+        let mut todo = SourceConfig::markdown("TODO.md");
+        assert!(should_sync_on_change(&todo, at(12, 0)));
+        todo.active_hours = Some("18:00-08:00".to_string());
+        assert!(!should_sync_on_change(&todo, at(12, 0)));
+        assert!(!is_poll_due(&todo, None, at(19, 0)));
+    }
+}
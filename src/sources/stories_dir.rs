@@ -0,0 +1,348 @@
+//! Stories directory task source adapter.
+//!
+//! Loads tasks from a directory where each `.md` file is one story. Optional
+//! front-matter between `---` lines sets the id, title, priority, effort,
+//! estimate and acceptance criteria; the rest of the file is the
+//! description. With writeback enabled, completed stories are moved into a
+//! `done/` subfolder.
+
+use crate::config::{SourceConfig, SourceType};
+use crate::prd::UserStory;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default directory if none specified.
+const DEFAULT_DIR: &str = "stories";
+
+/// Subfolder completed stories are moved into.
+pub const DONE_DIR: &str = "done";
+
+/// Source prefix for stories loaded from a directory.
+const SOURCE_PREFIX: &str = "stories-dir:";
+
+/// Front-matter fields read from a story file.
+#[derive(Debug, Default, PartialEq)]
+struct FrontMatter {
+    id: Option<String>,
+    title: Option<String>,
+    priority: Option<i32>,
+    effort: Option<u32>,
+    estimate: Option<u32>,
+    criteria: Vec<String>,
+    done: bool,
+}
+
+/// Load tasks from a directory of markdown story files.
+///
+/// Only `.md` files directly inside the directory are read, in file name
+/// order; anything under `done/` is already complete. Front-matter supports:
+///
+/// ```text
+/// ---
+/// id: auth-login
+/// title: User can log in
+/// priority: 1
+/// effort: 2
+/// estimate: 3
+/// criteria:
+///   - Login form validates email
+///   - Session cookie is set
+/// ---
+/// ```
+///
+/// Stories with `status: done` (or `done: true`) are skipped. Without an
+/// `id` the file stem is used; without a `title` the first `# ` heading is.
+///
+/// # Arguments
+///
+/// * `path` - Directory of story files. If None, uses `stories/`.
+///
+/// # Returns
+///
+/// A vector of UserStory items for pending stories.
+pub fn load_stories_dir_tasks(path: Option<&str>) -> Vec<UserStory> {
+    let dir = Path::new(path.unwrap_or(DEFAULT_DIR));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    files.sort();
+
+    files
+        .iter()
+        .filter_map(|file| {
+            let contents = fs::read_to_string(file).ok()?;
+            parse_story(file, &contents)
+        })
+        .collect()
+}
+
+/// Parse one story file, returning None if it is already done.
+fn parse_story(file: &Path, contents: &str) -> Option<UserStory> {
+    let (front, body) = split_front_matter(contents);
+    if front.done {
+        return None;
+    }
+
+    let stem = file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // Pull the title from the first heading if front-matter doesn't set it
+    let mut heading = None;
+    let mut description_lines = Vec::new();
+    for line in body.lines() {
+        if heading.is_none() && description_lines.iter().all(|l: &&str| l.trim().is_empty()) {
+            if let Some(text) = line.strip_prefix("# ") {
+                heading = Some(text.trim().to_string());
+                continue;
+            }
+        }
+        description_lines.push(line);
+    }
+
+    let title = front.title.or(heading).unwrap_or_else(|| stem.clone());
+    let description = description_lines.join("\n").trim().to_string();
+    let acceptance_criteria = if front.criteria.is_empty() {
+        vec![format!("Complete: {title}").into()]
+    } else {
+        front.criteria.into_iter().map(Into::into).collect()
+    };
+
+    Some(UserStory {
+        id: front.id.unwrap_or(stem),
+        description: if description.is_empty() {
+            title.clone()
+        } else {
+            description
+        },
+        title,
+        acceptance_criteria,
+        priority: front.priority.unwrap_or(3),
+        passes: false,
+        source: format!("{SOURCE_PREFIX}{}", file.display()),
+        notes: String::new(),
+        effort: front.effort,
+        estimate: front.estimate,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
+    })
+}
+
+/// Split a file into its front-matter and body.
+///
+/// Files without a leading `---` line have no front-matter.
+fn split_front_matter(contents: &str) -> (FrontMatter, &str) {
+    let Some(rest) = contents
+        .strip_prefix("---\n")
+        .or_else(|| contents.strip_prefix("---\r\n"))
+    else {
+        return (FrontMatter::default(), contents);
+    };
+
+    // Find the closing delimiter line
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let body = &rest[offset + line.len()..];
+            return (parse_front_matter(&rest[..offset]), body);
+        }
+        offset += line.len();
+    }
+
+    // Unterminated front-matter: treat the whole file as body
+    (FrontMatter::default(), contents)
+}
+
+/// Parse simple `key: value` front-matter with an optional criteria list.
+fn parse_front_matter(text: &str) -> FrontMatter {
+    let mut front = FrontMatter::default();
+    let mut in_criteria = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if in_criteria {
+            if let Some(item) = trimmed.strip_prefix("- ") {
+                front.criteria.push(unquote(item).to_string());
+                continue;
+            }
+            in_criteria = false;
+        }
+
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let value = unquote(value.trim());
+        match key.trim().to_lowercase().as_str() {
+            "id" => front.id = Some(value.to_string()),
+            "title" => front.title = Some(value.to_string()),
+            "priority" => front.priority = value.parse().ok(),
+            "effort" => front.effort = value.parse().ok(),
+            "estimate" | "points" => front.estimate = value.parse().ok(),
+            "status" => front.done = matches!(value.to_lowercase().as_str(), "done" | "closed"),
+            "done" => front.done = value.eq_ignore_ascii_case("true"),
+            "criteria" | "acceptance_criteria" | "acceptancecriteria" => {
+                if let Some(inline) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+                    front.criteria.extend(
+                        inline
+                            .split(',')
+                            .map(|c| unquote(c.trim()).to_string())
+                            .filter(|c| !c.is_empty()),
+                    );
+                } else {
+                    in_criteria = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    front
+}
+
+/// Strip matching single or double quotes from a value.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Move a completed story's file into the `done/` subfolder.
+///
+/// Only applies to stories from a `stories-dir` source with writeback
+/// enabled. Failures are silently ignored, like the other writebacks.
+pub fn complete_story_file(source: &str, sources: &[SourceConfig]) {
+    let Some(file) = source.strip_prefix(SOURCE_PREFIX) else {
+        return;
+    };
+    let file = Path::new(file);
+    let Some(dir) = file.parent() else {
+        return;
+    };
+
+    let enabled = sources.iter().any(|s| {
+        s.source_type == SourceType::StoriesDir
+            && s.writeback
+            && Path::new(s.path.as_deref().unwrap_or(DEFAULT_DIR)) == dir
+    });
+    if !enabled || crate::config::is_read_only() {
+        return;
+    }
+
+    let (Some(name), Ok(())) = (file.file_name(), fs::create_dir_all(dir.join(DONE_DIR))) else {
+        return;
+    };
+    let _ = fs::rename(file, dir.join(DONE_DIR).join(name));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_stories_dir_tasks() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        fs::write(
+            dir.join("02-login.md"),
+            "---\nid: auth-login\npriority: 1\neffort: 2\nestimate: 5\ncriteria:\n  - Form validates email\n  - \"Cookie is set\"\n---\n# User can log in\n\nUse the existing session store.\n",
+        )
+        .unwrap();
+        fs::write(dir.join("01-readme-badge.md"), "Add a CI badge.\n").unwrap();
+        fs::write(
+            dir.join("03-shipped.md"),
+            "---\nstatus: done\n---\n# Shipped\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a story").unwrap();
+        fs::create_dir_all(dir.join(DONE_DIR)).unwrap();
+        fs::write(dir.join(DONE_DIR).join("old.md"), "# Old\n").unwrap();
+
+        let tasks = load_stories_dir_tasks(dir.to_str());
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].id, "01-readme-badge");
+        assert_eq!(tasks[0].title, "01-readme-badge");
+        assert_eq!(tasks[0].description, "Add a CI badge.");
+        assert_eq!(tasks[0].priority, 3);
+
+        let login = &tasks[1];
+        assert_eq!(login.id, "auth-login");
+        assert_eq!(login.title, "User can log in");
+        assert_eq!(login.description, "Use the existing session store.");
+        assert_eq!(login.priority, 1);
+        assert_eq!(login.effort, Some(2));
+        assert_eq!(login.estimate, Some(5));
+        let criteria: Vec<&str> = login
+            .acceptance_criteria
+            .iter()
+            .map(|c| c.text.as_str())
+            .collect();
+        assert_eq!(criteria, vec!["Form validates email", "Cookie is set"]);
+        assert!(login.source.starts_with("stories-dir:"));
+        assert!(login.source.ends_with("02-login.md"));
+    }
+
+    #[test]
+    fn test_load_stories_dir_missing() {
+        assert!(load_stories_dir_tasks(Some("/nonexistent/stories")).is_empty());
+    }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let front = parse_front_matter(
+            "title: 'Quoted title'\ncriteria: [One, \"Two\"]\ndone: true\nowner: sam\n",
+        );
+        assert_eq!(front.title.as_deref(), Some("Quoted title"));
+        assert_eq!(front.criteria, vec!["One", "Two"]);
+        assert!(front.done);
+
+        // Unterminated front-matter is treated as body
+        let (front, body) = split_front_matter("---\nid: x\n# Heading\n");
+        assert_eq!(front, FrontMatter::default());
+        assert!(body.starts_with("---"));
+    }
+
+    #[test]
+    fn test_complete_story_file_moves_to_done() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("stories");
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("login.md");
+        fs::write(&file, "# Login\n").unwrap();
+        let source = format!("stories-dir:{}", file.display());
+
+        // Writeback disabled: file stays put
+        let mut config = SourceConfig::stories_dir(dir.to_str().unwrap());
+        complete_story_file(&source, std::slice::from_ref(&config));
+        assert!(file.exists());
+
+        config.writeback = true;
+        complete_story_file(&source, &[config]);
+        assert!(!file.exists());
+        assert!(dir.join(DONE_DIR).join("login.md").exists());
+        assert!(load_stories_dir_tasks(dir.to_str()).is_empty());
+    }
+}
@@ -0,0 +1,474 @@
+//! Trello board task source adapter.
+//!
+//! Reads open cards from a Trello board through the Trello API. Card names
+//! become titles, descriptions become descriptions, unchecked checklist
+//! items become acceptance criteria, and labels set the priority.
+//!
+//! Boards exported as JSON (Menu → Print, export and share → Export as JSON)
+//! are read the same way by `afk import --format trello`, with each list
+//! mapped to a status.
+
+use super::{skip, SourceError};
+use crate::config::{PropertyMapping, SourceConfig};
+use crate::prd::UserStory;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Trello API base URL.
+const TRELLO_API_URL: &str = "https://api.trello.com/1";
+
+/// Environment variable holding the API token by default.
+pub const DEFAULT_TOKEN_ENV: &str = "TRELLO_TOKEN";
+
+/// Environment variable holding the API key.
+pub const API_KEY_ENV: &str = "TRELLO_API_KEY";
+
+/// A Trello card as returned by the boards/cards endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloCard {
+    /// Card ID.
+    pub id: String,
+    /// Short numeric ID, unique within the board.
+    #[serde(default)]
+    pub id_short: u64,
+    /// Card name.
+    pub name: String,
+    /// Card description.
+    #[serde(default)]
+    pub desc: String,
+    /// ID of the list the card is in.
+    #[serde(default)]
+    pub id_list: String,
+    /// Labels on the card.
+    #[serde(default)]
+    pub labels: Vec<TrelloNamed>,
+    /// Checklists on the card.
+    #[serde(default)]
+    pub checklists: Vec<TrelloChecklist>,
+    /// Whether the card is archived.
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// A named Trello object (label or list).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloNamed {
+    /// Object ID.
+    #[serde(default)]
+    pub id: String,
+    /// Object name.
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A checklist on a card.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloChecklist {
+    /// Items in the checklist.
+    #[serde(default)]
+    pub check_items: Vec<TrelloCheckItem>,
+}
+
+/// A checklist item.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloCheckItem {
+    /// Item text.
+    pub name: String,
+    /// "complete" or "incomplete".
+    #[serde(default)]
+    pub state: String,
+}
+
+/// Load tasks from a Trello board.
+///
+/// Needs `TRELLO_API_KEY` and a token in `token_env` (default
+/// `TRELLO_TOKEN`). Cards in lists named in the mapping's done values are
+/// skipped, and `labels` restricts cards to those carrying one of them.
+/// Errors are reported as warnings and yield an empty list.
+pub fn load_trello_tasks(source: &SourceConfig) -> Vec<UserStory> {
+    try_load_trello_tasks(source).unwrap_or_else(skip)
+}
+
+/// Load tasks from a Trello board like [`load_trello_tasks`], returning
+/// what went wrong instead of warning.
+pub fn try_load_trello_tasks(source: &SourceConfig) -> Result<Vec<UserStory>, SourceError> {
+    let Some(board) = source.board.as_deref().filter(|b| !b.is_empty()) else {
+        return Err(SourceError::Misconfigured(
+            "Trello source has no board ID".into(),
+        ));
+    };
+    let token_env = source.token_env.as_deref().unwrap_or(DEFAULT_TOKEN_ENV);
+    let env = |var: &str| {
+        std::env::var(var).map_err(|_| SourceError::MissingEnv {
+            kind: "Trello",
+            var: var.to_string(),
+        })
+    };
+    let (key, token) = (env(API_KEY_ENV)?, env(token_env)?);
+
+    let (cards, lists) = fetch_board(board, &key, &token).map_err(|e| SourceError::Failed {
+        kind: "Trello",
+        message: format!("could not fetch the board: {e}"),
+    })?;
+
+    let list_names: HashMap<String, String> = lists.into_iter().map(|l| (l.id, l.name)).collect();
+    let mapping = source.properties.clone().unwrap_or_default();
+    Ok(cards
+        .into_iter()
+        .filter(|card| {
+            source.labels.is_empty()
+                || card.labels.iter().any(|l| {
+                    source
+                        .labels
+                        .iter()
+                        .any(|f| f.eq_ignore_ascii_case(&l.name))
+                })
+        })
+        .filter_map(|card| {
+            let list = list_names.get(&card.id_list).map(String::as_str);
+            card_to_story(card, list, &mapping)
+        })
+        .collect())
+}
+
+/// Fetch a board's open cards (with checklists) and its lists.
+fn fetch_board(
+    board: &str,
+    key: &str,
+    token: &str,
+) -> Result<(Vec<TrelloCard>, Vec<TrelloNamed>), reqwest::Error> {
+    let client = Client::builder()
+        .user_agent(format!("afk/{}", env!("CARGO_PKG_VERSION")))
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    let auth = [("key", key), ("token", token)];
+
+    let cards = client
+        .get(format!("{TRELLO_API_URL}/boards/{board}/cards"))
+        .query(&auth)
+        .query(&[
+            ("fields", "name,desc,idShort,idList,labels"),
+            ("checklists", "all"),
+        ])
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let lists = client
+        .get(format!("{TRELLO_API_URL}/boards/{board}/lists"))
+        .query(&auth)
+        .query(&[("fields", "name")])
+        .send()?
+        .error_for_status()?
+        .json()?;
+
+    Ok((cards, lists))
+}
+
+/// Convert a card to a UserStory, or None if its list means done.
+fn card_to_story(
+    card: TrelloCard,
+    list: Option<&str>,
+    mapping: &PropertyMapping,
+) -> Option<UserStory> {
+    if list.is_some_and(|l| mapping.done.iter().any(|d| d.eq_ignore_ascii_case(l))) {
+        return None;
+    }
+    Some(card_story(card))
+}
+
+/// Convert a card to a UserStory.
+fn card_story(card: TrelloCard) -> UserStory {
+    let priority = card
+        .labels
+        .iter()
+        .find_map(|l| super::priority_from_name(&l.name))
+        .unwrap_or(3);
+    let criteria: Vec<String> = card
+        .checklists
+        .iter()
+        .flat_map(|c| &c.check_items)
+        .filter(|item| item.state != "complete")
+        .map(|item| item.name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+    let acceptance_criteria = if criteria.is_empty() {
+        vec![format!("Complete: {}", card.name).into()]
+    } else {
+        criteria.into_iter().map(Into::into).collect()
+    };
+
+    UserStory {
+        id: format!("trello-{}", card.id_short),
+        description: if card.desc.trim().is_empty() {
+            card.name.clone()
+        } else {
+            card.desc.trim().to_string()
+        },
+        title: card.name,
+        acceptance_criteria,
+        priority,
+        passes: false,
+        source: format!("trello:{}", card.id),
+        notes: String::new(),
+        effort: None,
+        estimate: None,
+        discussion: Vec::new(),
+        touched_at: None,
+        depends_on: Vec::new(),
+        model: None,
+        cli: None,
+        epic: None,
+        generated: Vec::new(),
+    }
+}
+
+/// What importing a board export does with the cards in a list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListStatus {
+    /// Cards become pending tasks.
+    Pending,
+    /// Cards become tasks that already pass.
+    Done,
+    /// Cards are left out.
+    Skip,
+}
+
+/// List names that mean done when no status is given for them.
+const DONE_LISTS: &[&str] = &[
+    "done",
+    "complete",
+    "completed",
+    "finished",
+    "shipped",
+    "released",
+];
+
+/// Parse a `--list-status` value, `LIST=STATUS`, where the status is
+/// pending (or todo), done or skip.
+pub fn parse_list_status(value: &str) -> Result<(String, ListStatus), String> {
+    let Some((list, status)) = value.rsplit_once('=') else {
+        return Err(format!("expected LIST=STATUS, got '{value}'"));
+    };
+    let status = match status.trim().to_lowercase().as_str() {
+        "pending" | "todo" => ListStatus::Pending,
+        "done" => ListStatus::Done,
+        "skip" => ListStatus::Skip,
+        other => {
+            return Err(format!(
+                "unknown status '{other}' (expected pending, done or skip)"
+            ))
+        }
+    };
+    Ok((list.trim().to_string(), status))
+}
+
+/// A board as exported to JSON from Trello.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloBoardExport {
+    /// Board name.
+    #[serde(default)]
+    pub name: String,
+    /// The board's lists.
+    #[serde(default)]
+    pub lists: Vec<TrelloExportList>,
+    /// Every card on the board, archived ones included.
+    pub cards: Vec<TrelloCard>,
+    /// Every checklist on the board, each naming its card.
+    #[serde(default)]
+    pub checklists: Vec<TrelloExportChecklist>,
+}
+
+/// A list in a board export.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloExportList {
+    /// List ID.
+    pub id: String,
+    /// List name.
+    #[serde(default)]
+    pub name: String,
+    /// Whether the list is archived.
+    #[serde(default)]
+    pub closed: bool,
+}
+
+/// A checklist in a board export.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloExportChecklist {
+    /// ID of the card the checklist is on.
+    pub id_card: String,
+    /// Items in the checklist.
+    #[serde(default)]
+    pub check_items: Vec<TrelloCheckItem>,
+}
+
+impl TrelloBoardExport {
+    /// Parse a board export.
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The board's cards as tasks, in list then card order.
+    ///
+    /// Each list's cards take its status from `statuses` (matched by list
+    /// name, ignoring case); lists not named there are done if called
+    /// "Done", "Complete" or similar, and pending otherwise. Archived lists
+    /// and cards are left out.
+    pub fn into_stories(self, statuses: &[(String, ListStatus)]) -> Vec<UserStory> {
+        let mut checklists: HashMap<String, Vec<TrelloChecklist>> = HashMap::new();
+        for checklist in self.checklists {
+            checklists
+                .entry(checklist.id_card)
+                .or_default()
+                .push(TrelloChecklist {
+                    check_items: checklist.check_items,
+                });
+        }
+
+        let status_of = |list: &TrelloExportList| {
+            if list.closed {
+                return ListStatus::Skip;
+            }
+            statuses
+                .iter()
+                .rev()
+                .find(|(name, _)| name.eq_ignore_ascii_case(list.name.trim()))
+                .map(|(_, status)| *status)
+                .unwrap_or_else(|| {
+                    if DONE_LISTS
+                        .iter()
+                        .any(|d| d.eq_ignore_ascii_case(list.name.trim()))
+                    {
+                        ListStatus::Done
+                    } else {
+                        ListStatus::Pending
+                    }
+                })
+        };
+
+        let mut stories = Vec::new();
+        for list in &self.lists {
+            let status = status_of(list);
+            if status == ListStatus::Skip {
+                continue;
+            }
+            for card in self
+                .cards
+                .iter()
+                .filter(|c| c.id_list == list.id && !c.closed)
+            {
+                let mut card = card.clone();
+                if card.checklists.is_empty() {
+                    card.checklists = checklists.remove(&card.id).unwrap_or_default();
+                }
+                let mut story = card_story(card);
+                story.passes = status == ListStatus::Done;
+                stories.push(story);
+            }
+        }
+        stories
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_card() -> TrelloCard {
+        serde_json::from_str(
+            r#"{
+                "id": "5f1a2b",
+                "idShort": 42,
+                "name": "Dark mode toggle",
+                "desc": "",
+                "idList": "list-1",
+                "labels": [{"id": "l1", "name": "frontend"}, {"id": "l2", "name": "P1"}],
+                "checklists": [{"checkItems": [
+                    {"name": "Toggle persists", "state": "incomplete"},
+                    {"name": "Design review", "state": "complete"}
+                ]}]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_card_to_story() {
+        let story =
+            card_to_story(sample_card(), Some("To Do"), &PropertyMapping::default()).unwrap();
+
+        assert_eq!(story.id, "trello-42");
+        assert_eq!(story.title, "Dark mode toggle");
+        assert_eq!(story.description, "Dark mode toggle");
+        assert_eq!(story.priority, 1);
+        assert_eq!(story.acceptance_criteria.len(), 1);
+        assert_eq!(story.acceptance_criteria[0].text, "Toggle persists");
+        assert_eq!(story.source, "trello:5f1a2b");
+    }
+
+    #[test]
+    fn test_card_in_done_list_is_skipped() {
+        let mapping = PropertyMapping::default();
+        assert!(card_to_story(sample_card(), Some("done"), &mapping).is_none());
+        assert!(card_to_story(sample_card(), None, &mapping).is_some());
+    }
+
+    #[test]
+    fn test_board_export_into_stories() {
+        let export = TrelloBoardExport::parse(
+            r#"{
+                "name": "Roadmap",
+                "lists": [
+                    {"id": "l-todo", "name": "To Do"},
+                    {"id": "l-done", "name": "Done"},
+                    {"id": "l-ice", "name": "Icebox"},
+                    {"id": "l-old", "name": "Old", "closed": true}
+                ],
+                "cards": [
+                    {"id": "c1", "idShort": 1, "name": "Login", "idList": "l-todo", "idChecklists": ["k1"]},
+                    {"id": "c2", "idShort": 2, "name": "Signup", "idList": "l-done"},
+                    {"id": "c3", "idShort": 3, "name": "Archived", "idList": "l-todo", "closed": true},
+                    {"id": "c4", "idShort": 4, "name": "Someday", "idList": "l-ice"},
+                    {"id": "c5", "idShort": 5, "name": "Legacy", "idList": "l-old"}
+                ],
+                "checklists": [
+                    {"id": "k1", "idCard": "c1", "checkItems": [{"name": "Validates email", "state": "incomplete"}]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(export.name, "Roadmap");
+
+        let stories = export.into_stories(&[parse_list_status("icebox=skip").unwrap()]);
+        let ids: Vec<(&str, bool)> = stories.iter().map(|s| (s.id.as_str(), s.passes)).collect();
+        assert_eq!(ids, [("trello-1", false), ("trello-2", true)]);
+        assert_eq!(stories[0].acceptance_criteria[0].text, "Validates email");
+    }
+
+    #[test]
+    fn test_parse_list_status() {
+        assert_eq!(
+            parse_list_status("In Review = done").unwrap(),
+            ("In Review".to_string(), ListStatus::Done)
+        );
+        assert_eq!(
+            parse_list_status("Backlog=todo").unwrap().1,
+            ListStatus::Pending
+        );
+        assert!(parse_list_status("Backlog").is_err());
+        assert!(parse_list_status("Backlog=later")
+            .unwrap_err()
+            .contains("unknown status 'later'"));
+    }
+
+    #[test]
+    fn test_load_trello_tasks_without_credentials() {
+        let mut source = SourceConfig::trello("board");
+        source.token_env = Some("AFK_TEST_UNSET_TRELLO_TOKEN".to_string());
+        assert!(load_trello_tasks(&source).is_empty());
+        assert!(load_trello_tasks(&SourceConfig::trello("")).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 //! TUI application state and event handling.
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::io::{self, Stdout};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::time::{Duration, Instant};
@@ -13,6 +13,9 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 
 use super::ui;
+use crate::runner::{
+    control_state, request_pause, request_skip, request_stop, resume, ControlState,
+};
 
 /// Events that can be sent to the TUI.
 #[derive(Debug, Clone)]
@@ -28,6 +31,13 @@ pub enum TuiEvent {
         /// Type of change (created, modified, deleted).
         change_type: String,
     },
+    /// Shell command run by a tool call.
+    CommandRun {
+        /// The command line.
+        command: String,
+        /// Exit code, when the CLI reports one.
+        exit_code: Option<i32>,
+    },
     /// Error detected.
     Error(String),
     /// Warning detected.
@@ -50,7 +60,12 @@ pub enum TuiEvent {
         id: String,
         /// Task title.
         title: String,
+        /// Epic the task belongs to, if any.
+        epic: Option<String>,
     },
+    /// Escalated model the current task runs on, or None if it isn't
+    /// escalated.
+    Escalation(Option<String>),
     /// Task counts updated.
     TaskCounts {
         /// Number of pending tasks.
@@ -58,6 +73,8 @@ pub enum TuiEvent {
         /// Number of complete tasks.
         complete: u32,
     },
+    /// Remaining-task counts from the burn-down history, oldest first.
+    Burndown(Vec<usize>),
     /// Session complete.
     SessionComplete {
         /// Total iterations completed.
@@ -69,6 +86,22 @@ pub enum TuiEvent {
         /// Reason for session ending.
         reason: String,
     },
+    /// Wall-clock budget for a focus run.
+    TimeBudget(Duration),
+    /// The loop was paused (or resumed) through `.afk/control/pause`.
+    Paused(bool),
+    /// The loop is waiting this long before retrying a failed iteration, or
+    /// None once the wait is over.
+    Backoff(Option<Duration>),
+    /// A parallel worker's state changed.
+    WorkerStatus {
+        /// Worker name, e.g. "worker-1".
+        worker: String,
+        /// Task the worker holds, if any.
+        task: Option<String>,
+        /// Short status, e.g. "running" or "merged".
+        status: String,
+    },
     /// Quit the TUI.
     Quit,
 }
@@ -82,6 +115,10 @@ pub struct TuiStats {
     pub files_modified_set: HashSet<String>,
     /// Unique files that were created (deduplicated).
     pub files_created_set: HashSet<String>,
+    /// Shell commands run by tool calls.
+    pub commands_run: u32,
+    /// Shell commands that exited non-zero.
+    pub commands_failed: u32,
     /// Total number of errors.
     pub errors: u32,
     /// Total number of warnings.
@@ -116,10 +153,16 @@ pub struct TuiState {
     pub task_id: Option<String>,
     /// Current task title.
     pub task_title: Option<String>,
+    /// Epic of the current task, if any.
+    pub task_epic: Option<String>,
+    /// Escalated model the current task runs on, if any.
+    pub escalated_model: Option<String>,
     /// Number of pending tasks.
     pub tasks_pending: u32,
     /// Number of complete tasks.
     pub tasks_complete: u32,
+    /// Remaining-task counts from the burn-down history.
+    pub burndown: Vec<usize>,
     /// Start time.
     pub start_time: Instant,
     /// Iteration start time.
@@ -136,6 +179,16 @@ pub struct TuiState {
     pub scroll_offset: u16,
     /// Auto-scroll enabled.
     pub auto_scroll: bool,
+    /// Focus-run time budget, if any.
+    pub time_budget: Option<Duration>,
+    /// Whether the loop is paused by a control file.
+    pub paused: bool,
+    /// When the loop's wait before retrying a failed iteration ends.
+    pub backoff_until: Option<Instant>,
+    /// Whether `q` asked the loop to stop after its current iteration.
+    pub stopping: bool,
+    /// Parallel workers by name: held task and status.
+    pub workers: BTreeMap<String, (Option<String>, String)>,
 }
 
 /// Default maximum output lines for TUI buffer.
@@ -159,8 +212,11 @@ impl TuiState {
             iteration_max: 0,
             task_id: None,
             task_title: None,
+            task_epic: None,
+            escalated_model: None,
             tasks_pending: 0,
             tasks_complete: 0,
+            burndown: Vec::new(),
             start_time: now,
             iteration_start: now,
             stats: TuiStats::default(),
@@ -169,6 +225,11 @@ impl TuiState {
             session_result: None,
             scroll_offset: 0,
             auto_scroll: true,
+            time_budget: None,
+            paused: false,
+            backoff_until: None,
+            stopping: false,
+            workers: BTreeMap::new(),
         }
     }
 
@@ -216,6 +277,19 @@ impl TuiState {
     pub fn elapsed_secs(&self) -> f64 {
         self.start_time.elapsed().as_secs_f64()
     }
+
+    /// Time left in the focus-run budget, if one is set.
+    pub fn remaining_budget(&self) -> Option<Duration> {
+        self.time_budget
+            .map(|budget| budget.saturating_sub(self.start_time.elapsed()))
+    }
+
+    /// Time left before the loop retries a failed iteration, if it's
+    /// waiting.
+    pub fn remaining_backoff(&self) -> Option<Duration> {
+        self.backoff_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+    }
 }
 
 /// TUI application.
@@ -272,7 +346,15 @@ impl TuiApp {
                 if let Event::Key(key) = event::read()? {
                     if key.kind == KeyEventKind::Press {
                         match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => break,
+                            KeyCode::Esc => break,
+                            KeyCode::Char('q') => {
+                                if self.state.stopping || self.state.session_complete {
+                                    break;
+                                }
+                                self.request_stop();
+                            }
+                            KeyCode::Char('p') => self.toggle_pause(),
+                            KeyCode::Char('s') => self.request_skip(),
                             KeyCode::Up | KeyCode::Char('k') => self.state.scroll_up(),
                             KeyCode::Down | KeyCode::Char('j') => self.state.scroll_down(),
                             KeyCode::Char('g') => self.state.scroll_to_top(),
@@ -313,6 +395,51 @@ impl TuiApp {
         Ok(())
     }
 
+    /// Stop the loop once the current iteration ends; a second `q` quits
+    /// straight away.
+    fn request_stop(&mut self) {
+        match request_stop() {
+            Ok(()) => {
+                self.state.stopping = true;
+                self.state.add_output_line(
+                    "⏹️  Stopping after this iteration (q again to quit now)".to_string(),
+                );
+            }
+            Err(e) => self.control_error(e),
+        }
+    }
+
+    /// Pause the loop after the current iteration, or resume it.
+    fn toggle_pause(&mut self) {
+        let result = if control_state() == ControlState::Paused {
+            resume().map(|_| "▶️  Resuming")
+        } else {
+            request_pause().map(|()| "⏸️  Pausing after this iteration (p to resume)")
+        };
+        match result {
+            Ok(message) => self.state.add_output_line(message.to_string()),
+            Err(e) => self.control_error(e),
+        }
+    }
+
+    /// Skip the current task once its iteration ends.
+    fn request_skip(&mut self) {
+        match request_skip() {
+            Ok(()) => {
+                let task = self.state.task_id.as_deref().unwrap_or("the current task");
+                self.state
+                    .add_output_line(format!("⏭️  Skipping {task} after this iteration"));
+            }
+            Err(e) => self.control_error(e),
+        }
+    }
+
+    fn control_error(&mut self, e: io::Error) {
+        self.state.stats.warnings += 1;
+        self.state
+            .add_output_line(format!("⚠️  WARN: Could not write control file: {e}"));
+    }
+
     /// Handle a TUI event, returns false if should quit.
     fn handle_event(&mut self, event: TuiEvent) -> bool {
         match event {
@@ -345,6 +472,12 @@ impl TuiApp {
                     self.state.recent_files.pop_back();
                 }
             }
+            TuiEvent::CommandRun { exit_code, .. } => {
+                self.state.stats.commands_run += 1;
+                if exit_code.is_some_and(|code| code != 0) {
+                    self.state.stats.commands_failed += 1;
+                }
+            }
             TuiEvent::Error(msg) => {
                 self.state.stats.errors += 1;
                 self.state.add_output_line(format!("❌ ERROR: {}", msg));
@@ -368,14 +501,21 @@ impl TuiApp {
                     duration_secs
                 ));
             }
-            TuiEvent::TaskInfo { id, title } => {
+            TuiEvent::TaskInfo { id, title, epic } => {
                 self.state.task_id = Some(id);
                 self.state.task_title = Some(title);
+                self.state.task_epic = epic;
+            }
+            TuiEvent::Escalation(model) => {
+                self.state.escalated_model = model;
             }
             TuiEvent::TaskCounts { pending, complete } => {
                 self.state.tasks_pending = pending;
                 self.state.tasks_complete = complete;
             }
+            TuiEvent::Burndown(remaining) => {
+                self.state.burndown = remaining;
+            }
             TuiEvent::SessionComplete {
                 iterations,
                 tasks,
@@ -385,6 +525,22 @@ impl TuiApp {
                 self.state.session_complete = true;
                 self.state.session_result = Some((iterations, tasks, duration, reason));
             }
+            TuiEvent::TimeBudget(budget) => {
+                self.state.time_budget = Some(budget);
+            }
+            TuiEvent::Paused(paused) => {
+                self.state.paused = paused;
+            }
+            TuiEvent::Backoff(delay) => {
+                self.state.backoff_until = delay.map(|delay| Instant::now() + delay);
+            }
+            TuiEvent::WorkerStatus {
+                worker,
+                task,
+                status,
+            } => {
+                self.state.workers.insert(worker, (task, status));
+            }
             TuiEvent::Quit => {
                 return false;
             }
@@ -0,0 +1,601 @@
+//! Keyboard-driven config editor.
+//!
+//! Backs `afk config tui`: a two-pane screen listing config sections on the
+//! left and their fields on the right, with the selected key's documentation
+//! from [`crate::config::metadata`] underneath. Edits go through
+//! [`AfkConfig::set_by_path`], so they get the same validation as
+//! `afk config set`.
+
+use std::io;
+use std::time::Duration;
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::config::{metadata, AfkConfig};
+
+/// Which pane has keyboard focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    /// The section list.
+    Sections,
+    /// The field list for the selected section.
+    Fields,
+}
+
+/// What the user asked for when leaving the editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorAction {
+    /// Keep running.
+    Continue,
+    /// Save the edited config and exit.
+    Save,
+    /// Exit without saving.
+    Quit,
+}
+
+/// State of the config editor, independent of the terminal.
+#[derive(Debug)]
+pub struct ConfigEditorState {
+    config: AfkConfig,
+    sections: Vec<&'static str>,
+    section_index: usize,
+    field_index: usize,
+    focus: Focus,
+    input: Option<String>,
+    message: Option<(String, bool)>,
+    dirty: bool,
+    confirm_quit: bool,
+}
+
+impl ConfigEditorState {
+    /// Create editor state for a loaded config.
+    pub fn new(config: AfkConfig) -> Self {
+        let sections = AfkConfig::section_names()
+            .iter()
+            .copied()
+            .filter(|s| AfkConfig::fields_for_section(s).is_some_and(|f| !f.is_empty()))
+            .collect();
+
+        Self {
+            config,
+            sections,
+            section_index: 0,
+            field_index: 0,
+            focus: Focus::Sections,
+            input: None,
+            message: None,
+            dirty: false,
+            confirm_quit: false,
+        }
+    }
+
+    /// The config being edited.
+    pub fn config(&self) -> &AfkConfig {
+        &self.config
+    }
+
+    /// Consume the editor, returning the edited config.
+    pub fn into_config(self) -> AfkConfig {
+        self.config
+    }
+
+    /// Whether there are unsaved changes.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Which pane has focus.
+    pub fn focus(&self) -> Focus {
+        self.focus
+    }
+
+    /// The in-progress edit buffer, if editing.
+    pub fn input(&self) -> Option<&str> {
+        self.input.as_deref()
+    }
+
+    /// The selected section name.
+    pub fn section(&self) -> &'static str {
+        self.sections[self.section_index]
+    }
+
+    /// Field names of the selected section.
+    pub fn fields(&self) -> &'static [&'static str] {
+        AfkConfig::fields_for_section(self.section()).unwrap_or(&[])
+    }
+
+    /// Dot-notation path of the selected field.
+    pub fn current_path(&self) -> String {
+        let field = self.fields().get(self.field_index).copied().unwrap_or("");
+        format!("{}.{field}", self.section())
+    }
+
+    /// Current value of a field in the selected section.
+    pub fn value_of(&self, field: &str) -> String {
+        self.config
+            .get_by_path(&format!("{}.{field}", self.section()))
+            .unwrap_or_else(|_| "?".into())
+    }
+
+    /// Apply a key press and report whether the editor should exit.
+    pub fn handle_key(&mut self, code: KeyCode) -> EditorAction {
+        if self.input.is_some() {
+            self.handle_input_key(code);
+            return EditorAction::Continue;
+        }
+
+        let confirm_quit = std::mem::take(&mut self.confirm_quit);
+        match code {
+            KeyCode::Char('q') => {
+                if self.dirty && !confirm_quit {
+                    self.confirm_quit = true;
+                    self.set_message("Unsaved changes: press q again to discard, s to save", true);
+                } else {
+                    return EditorAction::Quit;
+                }
+            }
+            KeyCode::Char('s') => return EditorAction::Save,
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => self.focus = Focus::Fields,
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab | KeyCode::Esc => {
+                self.focus = Focus::Sections;
+            }
+            KeyCode::Enter => match self.focus {
+                Focus::Sections => self.focus = Focus::Fields,
+                Focus::Fields => {
+                    let path = self.current_path();
+                    self.input = Some(self.config.get_by_path(&path).unwrap_or_default());
+                    self.message = None;
+                }
+            },
+            KeyCode::Char('r') if self.focus == Focus::Fields => self.reset_current(),
+            _ => {}
+        }
+        EditorAction::Continue
+    }
+
+    /// Handle a key while editing a value.
+    fn handle_input_key(&mut self, code: KeyCode) {
+        let Some(input) = self.input.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => {
+                self.input = None;
+                self.message = None;
+            }
+            KeyCode::Enter => self.commit_input(),
+            _ => {}
+        }
+    }
+
+    /// Validate and apply the edit buffer.
+    fn commit_input(&mut self) {
+        let Some(value) = self.input.take() else {
+            return;
+        };
+        let path = self.current_path();
+        let old = self.config.get_by_path(&path).ok();
+
+        match self.config.set_by_path(&path, &value) {
+            Ok(()) => {
+                let new = self.config.get_by_path(&path).unwrap_or_default();
+                if old.as_deref() != Some(new.as_str()) {
+                    self.dirty = true;
+                }
+                self.set_message(&format!("{path} = {new}"), false);
+            }
+            Err(e) => {
+                // Keep the buffer so the user can fix the value
+                self.input = Some(value);
+                self.set_message(&e.to_string(), true);
+            }
+        }
+    }
+
+    /// Reset the selected field to its default.
+    fn reset_current(&mut self) {
+        let path = self.current_path();
+        let old = self.config.get_by_path(&path).ok();
+        match self.config.reset_field(&path) {
+            Ok(()) => {
+                let new = self.config.get_by_path(&path).unwrap_or_default();
+                if old.as_deref() != Some(new.as_str()) {
+                    self.dirty = true;
+                }
+                self.set_message(&format!("{path} reset to {new}"), false);
+            }
+            Err(e) => self.set_message(&e.to_string(), true),
+        }
+    }
+
+    /// Move the selection in the focused pane, wrapping at the ends.
+    fn move_selection(&mut self, delta: isize) {
+        let (index, len) = match self.focus {
+            Focus::Sections => (&mut self.section_index, self.sections.len()),
+            Focus::Fields => (
+                &mut self.field_index,
+                AfkConfig::fields_for_section(self.sections[self.section_index])
+                    .map_or(0, <[_]>::len),
+            ),
+        };
+        if len == 0 {
+            return;
+        }
+        *index = (*index as isize + delta).rem_euclid(len as isize) as usize;
+        if self.focus == Focus::Sections {
+            self.field_index = 0;
+        }
+    }
+
+    fn set_message(&mut self, message: &str, is_error: bool) {
+        self.message = Some((message.to_string(), is_error));
+    }
+}
+
+/// Run the config editor until the user saves or quits.
+///
+/// Returns the edited config if the user chose to save.
+pub fn run_config_editor(config: AfkConfig) -> io::Result<Option<AfkConfig>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut state = ConfigEditorState::new(config);
+    let result = event_loop(&mut terminal, &mut state);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    match result? {
+        EditorAction::Save => Ok(Some(state.into_config())),
+        _ => Ok(None),
+    }
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    state: &mut ConfigEditorState,
+) -> io::Result<EditorAction> {
+    loop {
+        terminal.draw(|f| draw(f, state))?;
+
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    let action = state.handle_key(key.code);
+                    if action != EditorAction::Continue {
+                        return Ok(action);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Draw the editor.
+fn draw(f: &mut Frame, state: &ConfigEditorState) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(8),
+            Constraint::Length(2),
+        ])
+        .split(f.area());
+
+    draw_header(f, rows[0], state);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(20), Constraint::Min(30)])
+        .split(rows[1]);
+    draw_sections(f, columns[0], state);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(columns[1]);
+    draw_fields(f, right[0], state);
+    draw_details(f, right[1], state);
+
+    draw_footer(f, rows[2], state);
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let border = if focused {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+    Block::default()
+        .title(title)
+        .title_style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .border_set(symbols::border::ROUNDED)
+}
+
+fn draw_header(f: &mut Frame, area: Rect, state: &ConfigEditorState) {
+    let mut spans = vec![
+        Span::styled(
+            " afk",
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(" │ ", Style::default().fg(Color::DarkGray)),
+        Span::styled("config", Style::default().fg(Color::White)),
+    ];
+    if state.is_dirty() {
+        spans.push(Span::styled(
+            "  ● unsaved",
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    let header = Paragraph::new(Line::from(spans)).block(
+        Block::default()
+            .borders(Borders::BOTTOM)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    f.render_widget(header, area);
+}
+
+fn draw_sections(f: &mut Frame, area: Rect, state: &ConfigEditorState) {
+    let items: Vec<ListItem> = state
+        .sections
+        .iter()
+        .map(|s| ListItem::new(format!(" {s}")))
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(" Sections ", state.focus == Focus::Sections))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        );
+
+    let mut list_state = ListState::default().with_selected(Some(state.section_index));
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_fields(f: &mut Frame, area: Rect, state: &ConfigEditorState) {
+    let fields = state.fields();
+    let width = fields.iter().map(|f| f.len()).max().unwrap_or(0);
+
+    let items: Vec<ListItem> = fields
+        .iter()
+        .map(|field| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!(" {field:<width$}  ")),
+                Span::styled(state.value_of(field), Style::default().fg(Color::Green)),
+            ]))
+        })
+        .collect();
+
+    let title = format!(" {} ", state.section());
+    let list = List::new(items)
+        .block(pane_block(&title, state.focus == Focus::Fields))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let selected = (state.focus == Focus::Fields).then_some(state.field_index);
+    let mut list_state = ListState::default().with_selected(selected);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_details(f: &mut Frame, area: Rect, state: &ConfigEditorState) {
+    let path = state.current_path();
+    let label = Style::default().fg(Color::DarkGray);
+
+    let mut lines = vec![Line::from(Span::styled(
+        path.clone(),
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    match metadata::get_metadata(&path) {
+        Some(meta) => {
+            lines.push(Line::from(meta.description));
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("Type:     ", label),
+                Span::raw(meta.value_type),
+            ]));
+            lines.push(Line::from(vec![
+                Span::styled("Default:  ", label),
+                Span::raw(meta.default),
+            ]));
+            if !meta.examples.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("Examples: ", label),
+                    Span::raw(meta.examples.join(", ")),
+                ]));
+            }
+        }
+        None => lines.push(Line::from(Span::styled(
+            "No documentation available",
+            label,
+        ))),
+    }
+
+    let details = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(pane_block(" Details ", false));
+    f.render_widget(details, area);
+}
+
+fn draw_footer(f: &mut Frame, area: Rect, state: &ConfigEditorState) {
+    let key = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let hint = Style::default().fg(Color::DarkGray);
+
+    let line = if let Some(input) = state.input() {
+        let mut spans = vec![
+            Span::styled(format!(" {} = ", state.current_path()), key),
+            Span::raw(format!("{input}█")),
+            Span::styled("  enter apply  esc cancel", hint),
+        ];
+        if let Some((message, true)) = &state.message {
+            spans.push(Span::styled(
+                format!("  {message}"),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        Line::from(spans)
+    } else if let Some((message, is_error)) = &state.message {
+        let colour = if *is_error { Color::Red } else { Color::Green };
+        Line::from(Span::styled(
+            format!(" {message}"),
+            Style::default().fg(colour),
+        ))
+    } else {
+        Line::from(vec![
+            Span::styled(" ↑↓", key),
+            Span::styled(" move  ", hint),
+            Span::styled("←→", key),
+            Span::styled(" pane  ", hint),
+            Span::styled("enter", key),
+            Span::styled(" edit  ", hint),
+            Span::styled("r", key),
+            Span::styled(" reset  ", hint),
+            Span::styled("s", key),
+            Span::styled(" save  ", hint),
+            Span::styled("q", key),
+            Span::styled(" quit", hint),
+        ])
+    };
+
+    let footer = Paragraph::new(line).block(
+        Block::default()
+            .borders(Borders::TOP)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    f.render_widget(footer, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(state: &mut ConfigEditorState, text: &str) {
+        for c in text.chars() {
+            state.handle_key(KeyCode::Char(c));
+        }
+    }
+
+    fn clear_input(state: &mut ConfigEditorState) {
+        while state.input().is_some_and(|i| !i.is_empty()) {
+            state.handle_key(KeyCode::Backspace);
+        }
+    }
+
+    #[test]
+    fn test_navigation_wraps_and_switches_panes() {
+        let mut state = ConfigEditorState::new(AfkConfig::default());
+        assert_eq!(state.section(), "limits");
+        assert!(!state.sections.contains(&"sources"));
+
+        state.handle_key(KeyCode::Up);
+        assert_eq!(state.section(), *state.sections.last().unwrap());
+        state.handle_key(KeyCode::Down);
+        assert_eq!(state.section(), "limits");
+
+        state.handle_key(KeyCode::Enter);
+        assert_eq!(state.focus(), Focus::Fields);
+        assert_eq!(state.current_path(), "limits.max_iterations");
+        state.handle_key(KeyCode::Down);
+        assert_eq!(state.current_path(), "limits.max_task_failures");
+
+        state.handle_key(KeyCode::Esc);
+        assert_eq!(state.focus(), Focus::Sections);
+    }
+
+    #[test]
+    fn test_edit_applies_valid_value() {
+        let mut state = ConfigEditorState::new(AfkConfig::default());
+        state.handle_key(KeyCode::Enter);
+        state.handle_key(KeyCode::Enter);
+        assert_eq!(state.input(), Some("200"));
+
+        clear_input(&mut state);
+        type_text(&mut state, "50");
+        state.handle_key(KeyCode::Enter);
+
+        assert!(state.input().is_none());
+        assert!(state.is_dirty());
+        assert_eq!(state.value_of("max_iterations"), "50");
+    }
+
+    #[test]
+    fn test_edit_rejects_invalid_value() {
+        let mut state = ConfigEditorState::new(AfkConfig::default());
+        state.handle_key(KeyCode::Enter);
+        state.handle_key(KeyCode::Enter);
+        clear_input(&mut state);
+        type_text(&mut state, "lots");
+        state.handle_key(KeyCode::Enter);
+
+        // Still editing, with the rejected value kept for correction
+        assert_eq!(state.input(), Some("lots"));
+        assert!(state.message.as_ref().is_some_and(|(_, err)| *err));
+        assert!(!state.is_dirty());
+
+        state.handle_key(KeyCode::Esc);
+        assert!(state.input().is_none());
+        assert_eq!(state.value_of("max_iterations"), "200");
+    }
+
+    #[test]
+    fn test_reset_restores_default() {
+        let mut config = AfkConfig::default();
+        config.set_by_path("limits.max_iterations", "10").unwrap();
+        let mut state = ConfigEditorState::new(config);
+
+        state.handle_key(KeyCode::Right);
+        state.handle_key(KeyCode::Char('r'));
+        assert_eq!(state.value_of("max_iterations"), "200");
+        assert!(state.is_dirty());
+    }
+
+    #[test]
+    fn test_quit_confirms_unsaved_changes() {
+        let mut state = ConfigEditorState::new(AfkConfig::default());
+        assert_eq!(state.handle_key(KeyCode::Char('q')), EditorAction::Quit);
+
+        let mut config = AfkConfig::default();
+        config.set_by_path("limits.max_iterations", "10").unwrap();
+        let mut state = ConfigEditorState::new(config);
+        state.handle_key(KeyCode::Right);
+        state.handle_key(KeyCode::Char('r'));
+
+        assert_eq!(state.handle_key(KeyCode::Char('q')), EditorAction::Continue);
+        assert_eq!(state.handle_key(KeyCode::Char('q')), EditorAction::Quit);
+        assert_eq!(state.handle_key(KeyCode::Char('s')), EditorAction::Save);
+    }
+}
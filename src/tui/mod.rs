@@ -5,8 +5,12 @@
 //! - Real-time statistics
 //! - Animated spinners and progress
 //! - Task and iteration info
+//!
+//! Also hosts the `afk config tui` settings editor.
 
 mod app;
+mod config_editor;
 mod ui;
 
 pub use app::{TuiApp, TuiEvent};
+pub use config_editor::{run_config_editor, ConfigEditorState, EditorAction, Focus};
@@ -15,6 +15,7 @@ use ratatui::{
 
 use super::app::TuiState;
 use crate::feedback;
+use crate::progress::burndown::{self, Trend};
 
 /// Spinner frames for animation.
 const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
@@ -24,6 +25,9 @@ const ACTIVITY_FRAMES: &[&str] = &[
     "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█", "▇", "▆", "▅", "▄", "▃", "▂",
 ];
 
+/// Burn-down points shown in the header.
+const BURNDOWN_WIDTH: usize = 16;
+
 /// Draw the entire TUI.
 pub fn draw(f: &mut Frame, state: &TuiState) {
     // Check if session is complete - show celebration screen
@@ -104,6 +108,32 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
         ));
     }
 
+    if state.paused {
+        spans.push(Span::styled(
+            " ⏸ PAUSED",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ));
+    }
+    if let Some(left) = state.remaining_backoff() {
+        let secs = left.as_secs();
+        spans.push(Span::styled(
+            format!(" ⏳ RETRY IN {}:{:02}", secs / 60, secs % 60),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ));
+    }
+    if state.stopping && !state.session_complete {
+        spans.push(Span::styled(
+            " ⏹ STOPPING",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ));
+    }
+
     // Task counts
     if state.tasks_pending > 0 || state.tasks_complete > 0 {
         spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
@@ -131,10 +161,35 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
             " complete",
             Style::default().fg(Color::DarkGray),
         ));
+        if state.burndown.len() > 1 {
+            let color = match Trend::of(&state.burndown) {
+                Some(Trend::Converging) => Color::Green,
+                Some(Trend::Growing) => Color::Red,
+                _ => Color::Yellow,
+            };
+            spans.push(Span::styled(
+                format!(" {}", burndown::sparkline(&state.burndown, BURNDOWN_WIDTH)),
+                Style::default().fg(color),
+            ));
+        }
     }
 
     spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
     spans.push(Span::styled(time_str, Style::default().fg(Color::Blue)));
+    if let Some(remaining) = state.remaining_budget() {
+        let label = if remaining.is_zero() {
+            " (finishing up)".to_string()
+        } else {
+            let secs = remaining.as_secs();
+            format!(
+                " ({}:{:02}:{:02} left)",
+                secs / 3600,
+                (secs % 3600) / 60,
+                secs % 60
+            )
+        };
+        spans.push(Span::styled(label, Style::default().fg(Color::Yellow)));
+    }
     spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
 
     // Activity bar
@@ -169,6 +224,20 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
             .add_modifier(ratatui::style::Modifier::BOLD),
     ));
     spans.push(Span::styled(" files", Style::default().fg(Color::DarkGray)));
+    if stats.commands_run > 0 {
+        let colour = if stats.commands_failed > 0 {
+            Color::Red
+        } else {
+            Color::Blue
+        };
+        spans.push(Span::styled(
+            format!(" {}", stats.commands_run),
+            Style::default()
+                .fg(colour)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ));
+        spans.push(Span::styled(" cmds", Style::default().fg(Color::DarkGray)));
+    }
 
     if stats.errors > 0 {
         spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
@@ -183,6 +252,12 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
     // Add task info if available
     if let Some(ref task_id) = state.task_id {
         spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+        if let Some(ref epic) = state.task_epic {
+            spans.push(Span::styled(
+                format!("{epic} ▸ "),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
         spans.push(Span::styled(
             task_id.clone(),
             Style::default()
@@ -202,6 +277,12 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
                 Style::default().fg(Color::DarkGray),
             ));
         }
+        if let Some(ref model) = state.escalated_model {
+            spans.push(Span::styled(
+                format!(" ⬆ {model}"),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
     }
 
     let header = Paragraph::new(Line::from(spans)).block(
@@ -216,8 +297,63 @@ fn draw_header(f: &mut Frame, area: Rect, state: &TuiState) {
 
 /// Draw the main body area.
 fn draw_body(f: &mut Frame, area: Rect, state: &TuiState) {
-    // Full-width output panel (simplified layout)
-    draw_output_panel(f, area, state);
+    if state.workers.is_empty() {
+        // Full-width output panel (simplified layout)
+        draw_output_panel(f, area, state);
+        return;
+    }
+
+    // Parallel run: merged output on the left, one row per worker on the right
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(40), Constraint::Length(36)])
+        .split(area);
+    draw_output_panel(f, chunks[0], state);
+    draw_workers_panel(f, chunks[1], state);
+}
+
+/// Draw the parallel workers panel.
+fn draw_workers_panel(f: &mut Frame, area: Rect, state: &TuiState) {
+    let width = area.width.saturating_sub(4) as usize;
+    let items: Vec<ListItem> = state
+        .workers
+        .iter()
+        .flat_map(|(worker, (task, status))| {
+            let colour = match status.as_str() {
+                "merged" | "done" => Color::Green,
+                "conflict" | "failed" => Color::Red,
+                "idle" => Color::DarkGray,
+                _ => Color::Yellow,
+            };
+            [
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{worker} "),
+                        Style::default().add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                    Span::styled(status.clone(), Style::default().fg(colour)),
+                ])),
+                ListItem::new(Span::styled(
+                    truncate_line(&format!("  {}", task.as_deref().unwrap_or("-")), width),
+                    Style::default().fg(Color::White),
+                )),
+            ]
+        })
+        .collect();
+
+    let panel = List::new(items).block(
+        Block::default()
+            .title(" Workers ")
+            .title_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(ratatui::style::Modifier::BOLD),
+            )
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray))
+            .border_set(symbols::border::ROUNDED),
+    );
+    f.render_widget(panel, area);
 }
 
 /// Draw the AI output panel.
@@ -315,7 +451,21 @@ fn draw_footer(f: &mut Frame, area: Rect) {
                 .fg(Color::Yellow)
                 .add_modifier(ratatui::style::Modifier::BOLD),
         ),
-        Span::styled(" quit  ", Style::default().fg(Color::DarkGray)),
+        Span::styled(" stop  ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            "p",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ),
+        Span::styled(" pause  ", Style::default().fg(Color::DarkGray)),
+        Span::styled(
+            "s",
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(ratatui::style::Modifier::BOLD),
+        ),
+        Span::styled(" skip  ", Style::default().fg(Color::DarkGray)),
         Span::styled(
             "↑↓",
             Style::default()